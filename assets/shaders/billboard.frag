@@ -0,0 +1,18 @@
+#version 330 core
+precision highp float;
+
+uniform sampler2D color;
+
+//Untextured billboards are drawn in their tint alone
+uniform bool textured = false;
+
+layout (location = 0) out vec4 out_color;
+
+in vec2 UV;
+in vec4 Tint;
+
+void main() {
+    vec4 base = textured ? texture(color, UV) * Tint : Tint;
+
+    out_color = base;
+}
@@ -0,0 +1,22 @@
+#version 330 core
+precision highp float;
+
+#include "lib/camera.glsl"
+
+//Unit quad from -0.5 to 0.5, facing +Z
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 uv;
+
+//Per-instance placement and tint. See graphics/pipeline/billboard.rs
+layout(location = 2) in mat4 instance_model;
+layout(location = 6) in vec4 instance_tint;
+
+out vec2 UV;
+out vec4 Tint;
+
+void main() {
+    UV = uv;
+    Tint = instance_tint;
+
+    gl_Position = view_projection * instance_model * vec4(position, 0.0, 1.0);
+}
@@ -0,0 +1,27 @@
+#version 330 core
+precision highp float;
+
+//One direction of a separable gaussian blur, run horizontally then vertically on the first bloom level
+uniform sampler2D source;
+uniform vec2 direction;
+
+layout (location = 0) out vec3 blurred;
+
+in vec2 UV;
+
+//9-tap gaussian folded into 5 bilinear taps
+const float offsets[3] = float[](0.0, 1.3846153846, 3.2307692308);
+const float weights[3] = float[](0.2270270270, 0.3162162162, 0.0702702703);
+
+void main() {
+    vec2 texel_step = direction / vec2(textureSize(source, 0));
+
+    vec3 result = texture(source, UV).rgb * weights[0];
+
+    for(int i = 1; i < 3; i++) {
+        result += texture(source, UV + texel_step * offsets[i]).rgb * weights[i];
+        result += texture(source, UV - texel_step * offsets[i]).rgb * weights[i];
+    }
+
+    blurred = result;
+}
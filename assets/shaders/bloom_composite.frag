@@ -0,0 +1,14 @@
+#version 330 core
+precision highp float;
+
+//Drawn with additive blending on top of the final image, once per bloom level
+uniform sampler2D bloom;
+uniform float intensity = 0.2;
+
+out vec4 color;
+
+in vec2 UV;
+
+void main() {
+    color = vec4(texture(bloom, UV).rgb * intensity, 0.0);
+}
@@ -0,0 +1,28 @@
+#version 330 core
+precision highp float;
+
+//Downsamples the previous bloom level to half size while blurring it
+uniform sampler2D source;
+
+layout (location = 0) out vec3 blurred;
+
+in vec2 UV;
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(source, 0));
+
+    //Center tap plus two rings of bilinear taps, weighted to approximate a gaussian
+    vec3 result = texture(source, UV).rgb * 0.125;
+
+    result += texture(source, UV + texel * vec2(-1.0, -1.0)).rgb * 0.125;
+    result += texture(source, UV + texel * vec2( 1.0, -1.0)).rgb * 0.125;
+    result += texture(source, UV + texel * vec2(-1.0,  1.0)).rgb * 0.125;
+    result += texture(source, UV + texel * vec2( 1.0,  1.0)).rgb * 0.125;
+
+    result += texture(source, UV + texel * vec2(-2.0,  0.0)).rgb * 0.09375;
+    result += texture(source, UV + texel * vec2( 2.0,  0.0)).rgb * 0.09375;
+    result += texture(source, UV + texel * vec2( 0.0, -2.0)).rgb * 0.09375;
+    result += texture(source, UV + texel * vec2( 0.0,  2.0)).rgb * 0.09375;
+
+    blurred = result;
+}
@@ -0,0 +1,32 @@
+#version 330 core
+precision highp float;
+
+//Extracts the bright parts of the lighting output while downsampling it to half resolution
+uniform sampler2D source;
+uniform float threshold = 1.0;
+
+layout (location = 0) out vec3 bright;
+
+in vec2 UV;
+
+float luminance(vec3 color) {
+    return dot(color, vec3(0.2126, 0.7152, 0.0722));
+}
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(source, 0));
+
+    //4-tap box filter, relying on bilinear filtering to cover a 4x4 area
+    vec3 color = texture(source, UV + texel * vec2(-1.0, -1.0)).rgb
+               + texture(source, UV + texel * vec2( 1.0, -1.0)).rgb
+               + texture(source, UV + texel * vec2(-1.0,  1.0)).rgb
+               + texture(source, UV + texel * vec2( 1.0,  1.0)).rgb;
+
+    color *= 0.25;
+
+    //Soft knee, so surfaces just above the threshold fade in instead of popping
+    float l = luminance(color);
+    float contribution = max(l - threshold, 0.0) / max(l, 0.0001);
+
+    bright = color * contribution;
+}
@@ -0,0 +1,80 @@
+#version 330 core
+precision highp float;
+
+//Must match `DebugView` in graphics/pipeline/debug.rs
+#define DEBUG_ALBEDO        1
+#define DEBUG_NORMALS       2
+#define DEBUG_DEPTH         3
+#define DEBUG_POSITION      4
+#define DEBUG_SMOOTHNESS    5
+#define DEBUG_SSAO          6
+#define DEBUG_BLOOM         7
+
+uniform int debug_view = DEBUG_ALBEDO;
+
+uniform mat4 view;
+uniform mat4 projection;
+
+uniform sampler2D ColorSs;
+uniform sampler2D NormalMs;
+uniform sampler2D PositionDs;
+
+uniform bool ssao_enabled = false;
+uniform sampler2D ssao_map;
+
+uniform bool bloom_enabled = false;
+uniform sampler2D bloom;
+
+layout (location = 0) out vec4 color;
+
+in vec2 UV;
+
+//Converts the nonlinear depth of a world space position into [0, 1] between the near and far planes
+float linear_depth(vec3 position) {
+    vec4 clip = projection * view * vec4(position, 1.0);
+
+    float ndc_depth = clip.z / clip.w;
+
+    //Recover the clip planes from the perspective projection matrix
+    float near = projection[3][2] / (projection[2][2] - 1.0);
+    float far = projection[3][2] / (projection[2][2] + 1.0);
+
+    float depth = (2.0 * near * far) / (far + near - ndc_depth * (far - near));
+
+    return (depth - near) / (far - near);
+}
+
+void main() {
+    vec4 ColorS     = texture(ColorSs, UV);
+    vec4 NormalM    = texture(NormalMs, UV);
+    vec4 PositionD  = texture(PositionDs, UV);
+
+    bool empty = length(NormalM.xyz) < 0.0001;
+
+    vec3 result = vec3(0.0);
+
+    if(debug_view == DEBUG_ALBEDO) {
+        result = ColorS.rgb;
+
+    } else if(debug_view == DEBUG_NORMALS) {
+        //Remap from [-1, 1] to displayable colors
+        result = empty ? vec3(0.0) : normalize(NormalM.xyz) * 0.5 + 0.5;
+
+    } else if(debug_view == DEBUG_DEPTH) {
+        result = empty ? vec3(1.0) : vec3(linear_depth(PositionD.xyz));
+
+    } else if(debug_view == DEBUG_POSITION) {
+        result = fract(PositionD.xyz);
+
+    } else if(debug_view == DEBUG_SMOOTHNESS) {
+        result = vec3(ColorS.w);
+
+    } else if(debug_view == DEBUG_SSAO) {
+        result = ssao_enabled ? vec3(texture(ssao_map, UV).r) : vec3(1.0);
+
+    } else if(debug_view == DEBUG_BLOOM) {
+        result = bloom_enabled ? texture(bloom, UV).rgb : vec3(0.0);
+    }
+
+    color = vec4(clamp(result, 0.0, 1.0), 1.0);
+}
@@ -2,27 +2,74 @@
 precision highp float;
 
 #include "lib/utils.glsl"
+#include "lib/constants.glsl"
 
 layout (location = 0) out vec4 gColorS;
 layout (location = 1) out vec4 gNormalM;
 layout (location = 2) out vec4 gPositionD;
+layout (location = 3) out uint gObjectId;
 
 in vec3 Position;
 in vec3 Normal;
 in vec3 UV;
+in vec3 Tangent;
+in vec3 Bitangent;
+flat in uint ObjectId;
 
+//Each map is only sampled if the material has one, otherwise its factor is used on its own
+uniform bool diffuse_mapping = true;
 uniform sampler2D color;
+uniform vec3 diffuse_factor = vec3(1.0);
+
+//Tangent-space normal map
+uniform bool normal_mapping = false;
+uniform sampler2D normal_map;
+
+//Roughness in the red channel
+uniform bool roughness_mapping = false;
+uniform sampler2D roughness_map;
+uniform float roughness_factor = 0.2;
+
+uniform float metallic_factor = 0.0;
+
+//Perturbs the interpolated normal by the normal map, unless the mesh has no tangents
+vec3 surface_normal() {
+    vec3 N = normalize(Normal);
+
+    if(!normal_mapping || length(Tangent) < EPSILON || length(Bitangent) < EPSILON) {
+        return N;
+    }
+
+    //Re-orthogonalize the tangent, since interpolation skews it
+    vec3 T = normalize(Tangent - dot(Tangent, N) * N);
+    vec3 B = normalize(Bitangent);
+
+    vec3 tangent_normal = texture(normal_map, UV.xy).xyz * 2.0 - 1.0;
+
+    return normalize(mat3(T, B, N) * tangent_normal);
+}
 
 void main() {
-    float smoothness = 0.8;
-    float metallic = 0.0;
+    vec3 diffuse = diffuse_factor;
+
+    if(diffuse_mapping) {
+        diffuse *= texture(color, UV.xy).rgb;
+    }
 
-    gColorS.rgb = texture(color, UV.xy).rgb;
-    gColorS.w = smoothness;
+    float roughness = roughness_factor;
 
-    gNormalM.xyz = Normal;
-    gNormalM.w = metallic;
+    if(roughness_mapping) {
+        roughness *= texture(roughness_map, UV.xy).r;
+    }
+
+    gColorS.rgb = diffuse;
+    gColorS.w = 1.0 - roughness;
+
+    gNormalM.xyz = surface_normal();
+    gNormalM.w = metallic_factor;
 
     gPositionD.xyz = Position;
     gPositionD.w = gl_FragCoord.z / gl_FragCoord.w;
+
+    gObjectId = ObjectId;
 }
\ No newline at end of file
@@ -1,27 +1,60 @@
 #version 330 core
 precision highp float;
 
+#include "lib/camera.glsl"
+
 layout(location = 0) in vec3 position;
 layout(location = 1) in vec3 normal;
 layout(location = 2) in vec3 uvw;
+layout(location = 3) in vec3 tangent;
+layout(location = 4) in vec3 bitangent;
+
+//Per-instance transforms, only used if `instanced` is set. See graphics/pipeline/instancing.rs
+layout(location = 5) in mat4 instance_model;
+layout(location = 9) in mat4 instance_mit;
+layout(location = 13) in uint instance_object_id;
 
 uniform mat4 model;
 uniform mat4 mvp;
 uniform mat4 mit;
 
+uniform bool instanced = false;
+
+//Written to the object ID attachment for picking, defaults to NO_OBJECT
+uniform uint object_id = 0xFFFFFFFFu;
+
 out vec3 Position;
 out vec3 Normal;
 out vec3 UV;
+out vec3 Tangent;
+out vec3 Bitangent;
+flat out uint ObjectId;
+
+//Must match the depth pre-pass exactly
+invariant gl_Position;
 
 void main() {
     vec4 ModelPosition = vec4(position, 1.0);
     vec4 ModelNormal = vec4(normal, 0.0);
 
-    Position = (model * ModelPosition).xyz;
+    mat4 Model = instanced ? instance_model : model;
+    mat4 MIT = instanced ? instance_mit : mit;
+
+    Position = (Model * ModelPosition).xyz;
 
-    Normal = normalize(mit * ModelNormal).xyz;
+    Normal = normalize(MIT * ModelNormal).xyz;
+
+    //Left unnormalized, since meshes without tangents leave them at zero
+    Tangent = (MIT * vec4(tangent, 0.0)).xyz;
+    Bitangent = (MIT * vec4(bitangent, 0.0)).xyz;
 
     UV = uvw;
 
-    gl_Position = mvp * ModelPosition;
-}
\ No newline at end of file
+    ObjectId = instanced ? instance_object_id : object_id;
+
+    if(instanced) {
+        gl_Position = view_projection * (instance_model * ModelPosition);
+    } else {
+        gl_Position = mvp * ModelPosition;
+    }
+}
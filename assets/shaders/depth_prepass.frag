@@ -0,0 +1,4 @@
+#version 330 core
+
+//Depth is written implicitly and color writes are masked off during the pre-pass
+void main() {}
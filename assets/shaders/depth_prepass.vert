@@ -0,0 +1,26 @@
+#version 330 core
+precision highp float;
+
+#include "lib/camera.glsl"
+
+layout(location = 0) in vec3 position;
+
+//Per-instance model matrix, only used if `instanced` is set
+layout(location = 5) in mat4 instance_model;
+
+uniform mat4 mvp;
+
+uniform bool instanced = false;
+
+//Must produce bit-identical depth to deferred_geometry.vert for GL_EQUAL depth testing
+invariant gl_Position;
+
+void main() {
+    vec4 ModelPosition = vec4(position, 1.0);
+
+    if(instanced) {
+        gl_Position = view_projection * (instance_model * ModelPosition);
+    } else {
+        gl_Position = mvp * ModelPosition;
+    }
+}
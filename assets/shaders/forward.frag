@@ -0,0 +1,50 @@
+#version 330 core
+#pragma optionNV (unroll all)
+
+precision highp float;
+
+#include "lib/constants.glsl"
+#include "lib/gamma.glsl"
+#include "lib/lights.glsl"
+#include "lib/camera.glsl"
+
+//Forward shading for alpha-blended geometry, drawn on top of the lighting stage after deferred shading
+
+uniform vec3 ambient = vec3(0.03);
+
+uniform sampler2D color;
+uniform vec4 tint = vec4(1.0);
+uniform float smoothness = 0.8;
+
+layout (location = 0) out vec4 gColor;
+
+in vec3 Position;
+in vec3 Normal;
+in vec3 UV;
+
+void main() {
+    vec4 base = texture(color, UV.xy) * tint;
+
+    vec3 Color = gamma_decode(base.rgb, 2.2);
+    vec3 N = normalize(Normal);
+    vec3 V = normalize(view_position - Position);
+
+    //Transparent surfaces are often seen from behind
+    if(!gl_FrontFacing) {
+        N = -N;
+    }
+
+    vec3 result = Color * ambient;
+
+    for(int i = 0; i < MAX_LIGHTS; i++) {
+        if(i >= num_lights) {
+            break;
+        }
+
+        vec3 L;
+
+        result += shade_light(lights[i], Color, Position, N, V, smoothness, L);
+    }
+
+    gColor = vec4(result, base.a);
+}
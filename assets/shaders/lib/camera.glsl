@@ -0,0 +1,12 @@
+#ifndef SHADER_LIB_CAMERA_GLSL_INCLUDED
+#define SHADER_LIB_CAMERA_GLSL_INCLUDED
+
+//Must match `CameraBlock` in graphics/pipeline/blocks.rs
+layout(std140) uniform Camera {
+    mat4 view;
+    mat4 projection;
+    mat4 view_projection;
+    vec3 view_position;
+};
+
+#endif //SHADER_LIB_CAMERA_GLSL_INCLUDED
@@ -0,0 +1,69 @@
+#ifndef SHADER_LIB_LIGHTS_GLSL_INCLUDED
+#define SHADER_LIB_LIGHTS_GLSL_INCLUDED
+
+#include "constants.glsl"
+#include "attenuation.glsl"
+
+//Must match `LightKind` in graphics/pipeline/lights.rs
+#define DIRECTIONAL_LIGHT   1
+#define POINT_LIGHT         2
+#define SPOT_LIGHT          3
+
+//Must match `MAX_LIGHTS` in graphics/pipeline/lights.rs
+#define MAX_LIGHTS 32
+
+struct Light {
+    int kind;
+    vec3 position;
+    vec3 direction;
+    vec3 color;
+    float intensity;
+    float radius;
+    vec2 cone;          //Cosines of the inner and outer spotlight cone angles
+};
+
+//Must match `LightsBlock` in graphics/pipeline/blocks.rs
+layout(std140) uniform Lights {
+    Light lights[MAX_LIGHTS];
+    int num_lights;
+};
+
+//Blinn-Phong contribution of a single light, without shadows. L is set to the direction towards the light.
+vec3 shade_light(Light light, vec3 Color, vec3 Position, vec3 Normal, vec3 V, float smoothness, out vec3 L) {
+    float attenuation = 1.0;
+
+    if(light.kind == DIRECTIONAL_LIGHT) {
+        //The negation is intentional, because the algorithms expect the opposite vector for the light direction.
+        L = normalize(-light.direction);
+        attenuation = light.intensity;
+
+    } else {
+        vec3 to_light = light.position - Position;
+        float d = length(to_light);
+
+        L = to_light / max(d, EPSILON);
+        attenuation = attenuation_radius(d, light.radius, light.intensity);
+
+        if(light.kind == SPOT_LIGHT) {
+            float theta = dot(-L, normalize(light.direction));
+
+            attenuation *= smoothstep(light.cone.y, light.cone.x, theta);
+        }
+    }
+
+    float NdotL = max(dot(Normal, L), 0.0);
+
+    if(NdotL <= 0.0 || attenuation < EPSILON) {
+        return vec3(0.0);
+    }
+
+    float shininess = exp2(10.0 * smoothness + 1.0);
+
+    vec3 H = normalize(L + V);
+
+    float specular = pow(max(dot(Normal, H), 0.0), shininess) * smoothness;
+
+    return (Color * FRAC_1_PI + vec3(specular)) * light.color * NdotL * attenuation;
+}
+
+#endif //SHADER_LIB_LIGHTS_GLSL_INCLUDED
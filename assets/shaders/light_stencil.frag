@@ -0,0 +1,4 @@
+#version 330 core
+
+//Light volumes are only rasterized for their effect on the stencil buffer
+void main() {}
@@ -0,0 +1,108 @@
+#version 330
+#pragma optionNV (unroll all)
+
+precision highp float;
+
+#include "lib/constants.glsl"
+#include "lib/gamma.glsl"
+#include "lib/lights.glsl"
+#include "lib/camera.glsl"
+
+uniform vec2 resolution;
+
+//Set when point lights are rendered separately as light volumes
+uniform bool skip_point_lights = false;
+
+uniform vec3 ambient = vec3(0.03);
+
+//Blurred ambient occlusion from the SSAO stage, only sampled if enabled
+uniform bool ssao_enabled = false;
+uniform sampler2D ssao_map;
+
+//Index of the directional light that casts shadows, or -1 for none
+uniform int shadow_light = -1;
+uniform mat4 light_matrix;
+uniform sampler2DShadow shadow_map;
+
+uniform sampler2D ColorSs;
+uniform sampler2D NormalMs;
+uniform sampler2D PositionDs;
+
+layout (location = 0) out vec4 gColor;
+
+in vec2 UV;
+
+//3x3 PCF lookup into the shadow map. Returns 1.0 for fully lit, 0.0 for fully shadowed.
+float shadow_factor(vec3 position, vec3 normal, vec3 L) {
+    vec4 light_space = light_matrix * vec4(position, 1.0);
+    vec3 coords = light_space.xyz / light_space.w * 0.5 + 0.5;
+
+    //Outside of the light frustum
+    if(coords.z > 1.0) {
+        return 1.0;
+    }
+
+    //Slope-scaled bias on top of the polygon offset used when rendering the shadow map
+    float bias = max(0.0025 * (1.0 - dot(normal, L)), 0.0005);
+
+    vec2 texel = 1.0 / vec2(textureSize(shadow_map, 0));
+
+    float lit = 0.0;
+
+    for(int x = -1; x <= 1; x++) {
+        for(int y = -1; y <= 1; y++) {
+            lit += texture(shadow_map, vec3(coords.xy + vec2(x, y) * texel, coords.z - bias));
+        }
+    }
+
+    return lit / 9.0;
+}
+
+void main() {
+    vec4 ColorS     = texture(ColorSs, UV);
+    vec4 NormalM    = texture(NormalMs, UV);
+    vec4 PositionD  = texture(PositionDs, UV);
+
+    vec3 Normal = NormalM.xyz;
+
+    //Nothing was rendered here
+    if(length(Normal) < EPSILON) {
+        gColor = vec4(0.0);
+        return;
+    }
+
+    Normal = normalize(Normal);
+
+    vec3 Color      = gamma_decode(ColorS.rgb, 2.2);
+    vec3 Position   = PositionD.xyz;
+
+    float smoothness = ColorS.w;
+
+    vec3 V = normalize(view_position - Position);
+
+    float ambient_occlusion = ssao_enabled ? texture(ssao_map, UV).r : 1.0;
+
+    vec3 result = Color * ambient * ambient_occlusion;
+
+    for(int i = 0; i < MAX_LIGHTS; i++) {
+        if(i >= num_lights) {
+            break;
+        }
+
+        if(skip_point_lights && lights[i].kind == POINT_LIGHT) {
+            continue;
+        }
+
+        vec3 L;
+
+        vec3 contribution = shade_light(lights[i], Color, Position, Normal, V, smoothness, L);
+
+        if(i == shadow_light) {
+            contribution *= shadow_factor(Position, Normal, L);
+        }
+
+        result += contribution;
+    }
+
+    gColor = vec4(result, 1.0);
+}
@@ -0,0 +1,17 @@
+#version 330 core
+precision highp float;
+
+//Log-luminance of the lighting output, averaged by generating mipmaps afterwards
+uniform sampler2D hdr;
+
+layout (location = 0) out float log_luminance;
+
+in vec2 UV;
+
+void main() {
+    vec3 color = texture(hdr, UV).rgb;
+
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+
+    log_luminance = log(max(luminance, 0.0001));
+}
@@ -0,0 +1,11 @@
+#version 330 core
+precision highp float;
+
+//Added for every fragment drawn, so the result is proportional to the number of layers
+uniform float increment = 0.1;
+
+layout (location = 0) out float overdraw;
+
+void main() {
+    overdraw = increment;
+}
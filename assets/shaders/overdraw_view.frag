@@ -0,0 +1,25 @@
+#version 330 core
+precision highp float;
+
+uniform sampler2D overdraw;
+
+layout (location = 0) out vec4 color;
+
+in vec2 UV;
+
+//Black for no fragments, then blue, green, yellow and finally red at 1.0 and above
+vec3 heatmap(float value) {
+    value = clamp(value, 0.0, 1.0);
+
+    vec3 cold = mix(vec3(0.0), vec3(0.0, 0.0, 1.0), clamp(value * 4.0, 0.0, 1.0));
+    vec3 warm = mix(vec3(0.0, 1.0, 0.0), vec3(1.0, 1.0, 0.0), clamp(value * 4.0 - 2.0, 0.0, 1.0));
+
+    vec3 color = mix(cold, vec3(0.0, 1.0, 0.0), clamp(value * 4.0 - 1.0, 0.0, 1.0));
+    color = value > 0.5 ? warm : color;
+
+    return mix(color, vec3(1.0, 0.0, 0.0), clamp(value * 4.0 - 3.0, 0.0, 1.0));
+}
+
+void main() {
+    color = vec4(heatmap(texture(overdraw, UV).r), 1.0);
+}
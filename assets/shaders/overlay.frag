@@ -0,0 +1,15 @@
+#version 330 core
+precision highp float;
+
+uniform sampler2D overlay;
+
+layout (location = 0) out vec4 color;
+
+in vec2 UV;
+
+void main() {
+    //Dropped textures are shown as they are, with no tonemapping, against black where they're transparent
+    vec4 texel = texture(overlay, UV);
+
+    color = vec4(texel.rgb * texel.a, 1.0);
+}
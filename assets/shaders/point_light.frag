@@ -0,0 +1,45 @@
+#version 330
+precision highp float;
+
+#include "lib/constants.glsl"
+#include "lib/gamma.glsl"
+#include "lib/lights.glsl"
+#include "lib/camera.glsl"
+
+uniform vec2 resolution;
+
+//Index of the light in the lights block this volume belongs to
+uniform int light_index = 0;
+
+uniform sampler2D ColorSs;
+uniform sampler2D NormalMs;
+uniform sampler2D PositionDs;
+
+layout (location = 0) out vec4 gColor;
+
+//Shades only a single light, which is blended additively on top of the fullscreen lighting pass
+void main() {
+    vec2 UV = gl_FragCoord.xy / resolution;
+
+    vec4 ColorS     = texture(ColorSs, UV);
+    vec4 NormalM    = texture(NormalMs, UV);
+    vec4 PositionD  = texture(PositionDs, UV);
+
+    vec3 Normal = NormalM.xyz;
+
+    //Nothing was rendered here
+    if(length(Normal) < EPSILON || light_index >= num_lights) {
+        discard;
+    }
+
+    Normal = normalize(Normal);
+
+    vec3 Color      = gamma_decode(ColorS.rgb, 2.2);
+    vec3 Position   = PositionD.xyz;
+
+    vec3 V = normalize(view_position - Position);
+
+    vec3 L;
+
+    gColor = vec4(shade_light(lights[light_index], Color, Position, Normal, V, ColorS.w, L), 0.0);
+}
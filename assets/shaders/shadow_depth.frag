@@ -0,0 +1,4 @@
+#version 330 core
+
+//Depth is written implicitly, there is no color output
+void main() {}
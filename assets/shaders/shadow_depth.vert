@@ -0,0 +1,11 @@
+#version 330 core
+precision highp float;
+
+layout(location = 0) in vec3 position;
+
+uniform mat4 model;
+uniform mat4 light_view_proj;
+
+void main() {
+    gl_Position = light_view_proj * model * vec4(position, 1.0);
+}
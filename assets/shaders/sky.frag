@@ -0,0 +1,24 @@
+#version 330 core
+precision highp float;
+
+#include "lib/gamma.glsl"
+
+uniform samplerCube skybox;
+
+//Whether the cubemap holds sRGB data that hasn't been linearized by the texture format
+uniform bool srgb = false;
+uniform float intensity = 1.0;
+
+in vec3 Ray;
+
+layout (location = 0) out vec4 gColor;
+
+void main() {
+    vec3 color = texture(skybox, normalize(Ray)).rgb;
+
+    if(srgb) {
+        color = gamma_decode(color, 2.2);
+    }
+
+    gColor = vec4(color * intensity, 1.0);
+}
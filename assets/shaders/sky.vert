@@ -0,0 +1,18 @@
+#version 330 core
+
+layout (location = 0) in vec2 position;
+
+uniform mat4 inverse_view_projection;
+
+out vec3 Ray;
+
+void main() {
+    //Placed on the far plane, so with GL_LEQUAL only pixels nothing was rendered to are filled
+    gl_Position = vec4(position, 1.0, 1.0);
+
+    //Reconstruct the world space view ray through this corner of the screen
+    vec4 far = inverse_view_projection * vec4(position, 1.0, 1.0);
+    vec4 near = inverse_view_projection * vec4(position, -1.0, 1.0);
+
+    Ray = far.xyz / far.w - near.xyz / near.w;
+}
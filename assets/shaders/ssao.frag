@@ -0,0 +1,67 @@
+#version 330 core
+precision highp float;
+
+//Must match `MAX_KERNEL_SIZE` in graphics/pipeline/ssao.rs
+#define MAX_KERNEL_SIZE 64
+
+uniform vec3 samples[MAX_KERNEL_SIZE];
+uniform int kernel_size = 32;
+uniform float radius = 0.5;
+uniform float bias = 0.025;
+
+uniform sampler2D noise;
+uniform float noise_size = 4.0;
+
+uniform mat4 view;
+uniform mat4 projection;
+uniform vec2 resolution;
+
+uniform sampler2D NormalMs;
+uniform sampler2D PositionDs;
+
+layout (location = 0) out float occlusion;
+
+in vec2 UV;
+
+void main() {
+    vec3 normal = texture(NormalMs, UV).xyz;
+
+    //Nothing was rendered here
+    if(length(normal) < 0.0001) {
+        occlusion = 1.0;
+        return;
+    }
+
+    //The G-buffer is in world space, but occlusion is computed in view space
+    vec3 position = (view * vec4(texture(PositionDs, UV).xyz, 1.0)).xyz;
+    normal = normalize(mat3(view) * normal);
+
+    vec3 random = vec3(texture(noise, UV * resolution / noise_size).xy, 0.0);
+
+    //Gram-Schmidt process to create a randomly rotated TBN basis around the normal
+    vec3 tangent = normalize(random - normal * dot(random, normal));
+    vec3 bitangent = cross(normal, tangent);
+    mat3 TBN = mat3(tangent, bitangent, normal);
+
+    float occluded = 0.0;
+
+    for(int i = 0; i < MAX_KERNEL_SIZE; i++) {
+        if(i >= kernel_size) {
+            break;
+        }
+
+        vec3 sample_position = position + (TBN * samples[i]) * radius;
+
+        vec4 offset = projection * vec4(sample_position, 1.0);
+        offset.xy = (offset.xy / offset.w) * 0.5 + 0.5;
+
+        float sample_depth = (view * vec4(texture(PositionDs, offset.xy).xyz, 1.0)).z;
+
+        //Fade out occlusion from geometry far outside the sampling radius
+        float range_check = smoothstep(0.0, 1.0, radius / abs(position.z - sample_depth));
+
+        occluded += (sample_depth >= sample_position.z + bias ? 1.0 : 0.0) * range_check;
+    }
+
+    occlusion = 1.0 - (occluded / float(max(kernel_size, 1)));
+}
@@ -0,0 +1,23 @@
+#version 330 core
+precision highp float;
+
+//Simple box blur matching the 4x4 noise texture, removing the noise pattern
+uniform sampler2D ssao_input;
+
+layout (location = 0) out float occlusion;
+
+in vec2 UV;
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(ssao_input, 0));
+
+    float result = 0.0;
+
+    for(int x = -2; x < 2; x++) {
+        for(int y = -2; y < 2; y++) {
+            result += texture(ssao_input, UV + vec2(float(x), float(y)) * texel).r;
+        }
+    }
+
+    occlusion = result / 16.0;
+}
@@ -0,0 +1,56 @@
+#version 330 core
+precision highp float;
+
+#include "lib/hdr.glsl"
+#include "lib/gamma.glsl"
+
+//Must match `Tonemapper` in graphics/pipeline/tonemap.rs
+#define TONEMAP_REINHARD    0
+#define TONEMAP_ACES        1
+
+uniform sampler2D hdr;
+
+uniform int tonemapper = TONEMAP_ACES;
+uniform float exposure = 1.0;
+uniform float gamma = 2.2;
+
+//Bypasses tonemapping and gamma correction to inspect raw values
+uniform bool raw = false;
+
+//When enabled, the exposure is computed from the smallest mip level of the log-luminance map
+uniform bool auto_exposure = false;
+uniform float key = 0.18;
+uniform sampler2D luminance_map;
+
+layout (location = 0) out vec4 color;
+
+in vec2 UV;
+
+void main() {
+    vec3 hdr_color = texture(hdr, UV).rgb;
+
+    if(raw) {
+        color = vec4(hdr_color, 1.0);
+        return;
+    }
+
+    float scene_exposure = exposure;
+
+    if(auto_exposure) {
+        float max_level = floor(log2(float(textureSize(luminance_map, 0).x)));
+        float average_luminance = exp(textureLod(luminance_map, vec2(0.5), max_level).r);
+
+        scene_exposure = key / max(average_luminance, 0.0001);
+    }
+
+    vec3 ldr_color;
+
+    if(tonemapper == TONEMAP_REINHARD) {
+        ldr_color = reinhard_tonemap(hdr_color * scene_exposure);
+    } else {
+        ldr_color = ACESFilm_tonemap_exposure(hdr_color, scene_exposure);
+    }
+
+    //The only place linear color is converted to display gamma
+    color = vec4(clamp(gamma_encode(ldr_color, gamma), 0.0, 1.0), 1.0);
+}
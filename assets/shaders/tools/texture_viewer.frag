@@ -13,6 +13,8 @@ uniform vec2 texture_resolution;
 uniform float zoom;
 uniform vec2 pos;
 
+uniform float exposure = 1.0;
+
 void main() {
     vec2 MUV = UV;
 
@@ -38,7 +40,7 @@ void main() {
 
     MUV.y = 1.0 - MUV.y;
 
-    color.rgb = texture(screen, MUV, step(5.0, zoom) * (1.0 / zoom)).rgb;
+    color.rgb = texture(screen, MUV, step(5.0, zoom) * (1.0 / zoom)).rgb * exposure;
 
     color.a = 1.0;
 }
\ No newline at end of file
@@ -0,0 +1,19 @@
+#version 330 core
+precision highp float;
+
+//Written into the albedo of the G-buffer, which is displayed directly while the wireframe raster mode is active
+uniform vec3 wire_color = vec3(0.0, 1.0, 0.0);
+
+layout (location = 0) out vec4 gColorS;
+layout (location = 1) out vec4 gNormalM;
+layout (location = 2) out vec4 gPositionD;
+
+in vec3 Position;
+
+void main() {
+    gColorS = vec4(wire_color, 0.0);
+    gNormalM = vec4(0.0);
+
+    gPositionD.xyz = Position;
+    gPositionD.w = gl_FragCoord.z / gl_FragCoord.w;
+}
@@ -0,0 +1,82 @@
+//! Shared handles to assets which may still be loading
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Cheaply cloned handle to an asset, which is filled in once the asset has loaded.
+///
+/// Every clone refers to the same slot, so whatever loads the asset can keep one handle
+/// while the rest are handed out before loading has finished.
+pub struct AssetHandle<T> {
+    slot: Arc<RwLock<Option<Arc<T>>>>,
+    path: Option<Arc<PathBuf>>,
+}
+
+impl<T> Clone for AssetHandle<T> {
+    #[inline]
+    fn clone(&self) -> AssetHandle<T> {
+        AssetHandle { slot: self.slot.clone(), path: self.path.clone() }
+    }
+}
+
+impl<T> Default for AssetHandle<T> {
+    #[inline(always)]
+    fn default() -> AssetHandle<T> { AssetHandle::pending() }
+}
+
+impl<T> Debug for AssetHandle<T> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "AssetHandle {{ path: {:?}, loaded: {} }}", self.path(), self.is_loaded())
+    }
+}
+
+impl<T> AssetHandle<T> {
+    /// Creates a handle to an asset that hasn't loaded yet
+    pub fn pending() -> AssetHandle<T> {
+        AssetHandle { slot: Arc::new(RwLock::new(None)), path: None }
+    }
+
+    /// Creates a handle to the asset stored at `path`, which hasn't loaded yet
+    pub fn at<P: AsRef<Path>>(path: P) -> AssetHandle<T> {
+        AssetHandle { slot: Arc::new(RwLock::new(None)), path: Some(Arc::new(path.as_ref().to_path_buf())) }
+    }
+
+    /// Creates a handle to an asset that has already loaded
+    pub fn loaded(asset: T) -> AssetHandle<T> {
+        AssetHandle { slot: Arc::new(RwLock::new(Some(Arc::new(asset)))), path: None }
+    }
+
+    /// Fills in the asset for every clone of this handle, replacing any asset already there
+    pub fn set(&self, asset: T) {
+        *self.slot.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Arc::new(asset));
+    }
+
+    /// Empties the handle again, such as when the asset is being reloaded
+    pub fn unload(&self) {
+        *self.slot.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
+
+    /// The asset, or `None` if it hasn't loaded yet
+    pub fn get(&self) -> Option<Arc<T>> {
+        self.slot.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// Where the asset is loaded from, if it came from a file
+    #[inline]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_ref().map(|path| path.as_path())
+    }
+
+    /// Checks if the asset has loaded
+    #[inline]
+    pub fn is_loaded(&self) -> bool {
+        self.slot.read().unwrap_or_else(|poisoned| poisoned.into_inner()).is_some()
+    }
+
+    /// Checks if both handles refer to the same slot
+    #[inline]
+    pub fn same_slot(&self, other: &AssetHandle<T>) -> bool {
+        &*self.slot as *const _ == &*other.slot as *const _
+    }
+}
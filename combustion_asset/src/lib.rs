@@ -56,4 +56,5 @@ extern crate combustion_protocols as protocols;
 pub mod error;
 pub mod asset;
 pub mod cache;
+pub mod handle;
 pub mod assets;
\ No newline at end of file
@@ -0,0 +1,33 @@
+//! OpenGL specific mesh components
+use std::mem;
+
+use ::backends::gl::bindings as glb;
+use ::backends::gl::wrapper::vertex_array::{GLVertexAttribute, GLVertexLayout};
+
+use nalgebra::{Point3, Vector3};
+
+use protocols::mesh::data::{Vertex, TexCoord};
+
+/// Attribute location of the vertex position
+pub const POSITION_LOCATION: u32 = 0;
+/// Attribute location of the vertex normal
+pub const NORMAL_LOCATION: u32 = 1;
+/// Attribute location of the texture coordinate, which matches the `uvw` input of the geometry shader
+pub const UV_LOCATION: u32 = 2;
+
+impl GLVertexLayout for Vertex {
+    fn attributes() -> Vec<GLVertexAttribute> {
+        let stride = mem::size_of::<Vertex>();
+
+        let normal_offset = mem::size_of::<Point3<f32>>();
+        let uv_offset = normal_offset + mem::size_of::<Vector3<f32>>();
+
+        debug_assert_eq!(uv_offset + mem::size_of::<TexCoord>(), stride);
+
+        vec![
+            GLVertexAttribute::new(POSITION_LOCATION, 3, glb::FLOAT, false, stride, 0),
+            GLVertexAttribute::new(NORMAL_LOCATION, 3, glb::FLOAT, false, stride, normal_offset),
+            GLVertexAttribute::new(UV_LOCATION, 2, glb::FLOAT, false, stride, uv_offset),
+        ]
+    }
+}
@@ -1 +1,2 @@
-pub mod texture;
\ No newline at end of file
+pub mod texture;
+pub mod mesh;
@@ -31,7 +31,9 @@ impl<T> TextureResource for T where T: Resource + texture::GLGenericTexture {
 
     #[inline]
     fn set_anisotropy(&mut self, value: f32) -> BackendResult<()> {
-        Ok(try_rethrow!(<Self as texture::GLGenericTexture>::set_anisotropy(self, value)))
+        try_rethrow!(<Self as texture::GLGenericTexture>::set_anisotropy(self, value));
+
+        Ok(())
     }
 
     fn set_filtering(&mut self, filter: TextureFilter, mipmap: Option<TextureFilter>) -> BackendResult<()> {
@@ -4,9 +4,13 @@ use super::{GLObject, GLBindable};
 
 use std::mem;
 use std::ptr;
+use std::slice;
+use std::ops::Deref;
 use std::os::raw::c_void;
 
 use super::error::*;
+use super::debug::label_object;
+use super::capabilities::GLCapabilities;
 
 #[repr(u32)]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
@@ -19,6 +23,7 @@ pub enum GLBufferTarget {
     DrawIndirectBuffer = DRAW_INDIRECT_BUFFER,
     ElementArrayBuffer = ELEMENT_ARRAY_BUFFER,
     PixelPackBuffer = PIXEL_PACK_BUFFER,
+    PixelUnpackBuffer = PIXEL_UNPACK_BUFFER,
     QueryBuffer = QUERY_BUFFER,
     ShaderStorageBuffer = SHADER_STORAGE_BUFFER,
     TextureBuffer = TEXTURE_BUFFER,
@@ -58,7 +63,9 @@ impl GLBindable for GLBuffer {
 }
 
 impl GLBuffer {
-    /// Create a new empty OpenGL Buffer and bind it
+    /// Create a new empty OpenGL Buffer and bind it.
+    ///
+    /// With direct state access the other methods don't bind the buffer, so bind it before using it through its target.
     pub fn new(target: GLBufferTarget) -> GLResult<GLBuffer> {
         let mut buffer = 0;
 
@@ -73,6 +80,18 @@ impl GLBuffer {
         Ok(GLBuffer(buffer, target, 0))
     }
 
+    /// Create a new empty vertex buffer
+    #[inline]
+    pub fn array_buffer() -> GLResult<GLBuffer> { GLBuffer::new(GLBufferTarget::ArrayBuffer) }
+
+    /// Create a new empty index buffer. It's bound to the current vertex array, so bind the right one first.
+    #[inline]
+    pub fn element_buffer() -> GLResult<GLBuffer> { GLBuffer::new(GLBufferTarget::ElementArrayBuffer) }
+
+    /// Create a new empty uniform buffer. See `GLUniformBuffer` for one that handles the std140 layout.
+    #[inline]
+    pub fn uniform_buffer() -> GLResult<GLBuffer> { GLBuffer::new(GLBufferTarget::UniformBuffer) }
+
     /// Returns the buffer target.
     #[inline]
     pub fn target(&self) -> GLBufferTarget { self.1 }
@@ -93,21 +112,139 @@ impl GLBuffer {
         unsafe { self.buffer_raw(data.as_ptr() as *const c_void, data.len() * mem::size_of::<T>(), usage) }
     }
 
+    /// Reallocates the buffer storage to hold exactly `data`
+    #[inline]
+    pub fn upload<T: Copy>(&mut self, data: &[T], usage: GLBufferUsage) -> GLResult<()> {
+        self.buffer_slice(data, usage)
+    }
+
+    /// Reallocates the buffer storage to `size` bytes with undefined contents, for buffers the GPU writes into
+    pub fn allocate(&mut self, size: usize, usage: GLBufferUsage) -> GLResult<()> {
+        if size == 0 {
+            throw!(GLError::InvalidValue);
+        }
+
+        unsafe { try_rethrow!(self.buffer_data(ptr::null(), size, usage)); }
+
+        self.2 = size;
+
+        Ok(())
+    }
+
+    /// Overwrites part of the existing storage with `data`, starting `offset` elements `T` in, without reallocating it.
+    ///
+    /// Fails with `InvalidValue` if the data would go past the end of what was last uploaded.
+    pub fn upload_sub<T: Copy>(&mut self, offset: usize, data: &[T]) -> GLResult<()> {
+        let (start, size) = try_rethrow!(self.byte_range::<T>(offset, data.len()));
+
+        if GLCapabilities::use_direct_state_access() {
+            try_rethrow!(self.check());
+
+            unsafe {
+                NamedBufferSubData(self.0, start as GLintptr, size as GLsizeiptr, data.as_ptr() as *const c_void);
+            }
+        } else {
+            try_rethrow!(self.bind());
+
+            unsafe {
+                BufferSubData(self.1 as GLenum, start as GLintptr, size as GLsizeiptr, data.as_ptr() as *const c_void);
+            }
+        }
+
+        check_gl_errors!();
+
+        Ok(())
+    }
+
+    /// Maps `len` elements `T` of the buffer, starting `offset` elements in, into client memory for reading.
+    ///
+    /// The range is unmapped when the returned mapping is dropped, and the buffer can't be used until then.
+    /// Fails with `InvalidValue` if the range goes past the end of what was last uploaded.
+    pub fn map_range<T: Copy>(&mut self, offset: usize, len: usize) -> GLResult<GLBufferMapping<T>> {
+        let data = try_rethrow!(self.map_raw::<T>(offset, len, MAP_READ_BIT));
+
+        Ok(GLBufferMapping { range: GLMappedRange(self), data: data as *const T, len: len })
+    }
+
+    /// Maps `len` elements `T` of the buffer, starting `offset` elements in, into client memory for writing.
+    ///
+    /// The mapped memory can't be read, so the mapping can only be written to. Otherwise the same as `map_range`.
+    pub fn map_range_write<T: Copy>(&mut self, offset: usize, len: usize) -> GLResult<GLBufferWriteMapping<T>> {
+        let data = try_rethrow!(self.map_raw::<T>(offset, len, MAP_WRITE_BIT));
+
+        Ok(GLBufferWriteMapping { range: GLMappedRange(self), data: data as *mut T, len: len })
+    }
+
+    fn map_raw<T>(&mut self, offset: usize, len: usize, access: GLbitfield) -> GLResult<*mut c_void> {
+        let (start, size) = try_rethrow!(self.byte_range::<T>(offset, len));
+
+        let data = if GLCapabilities::use_direct_state_access() {
+            try_rethrow!(self.check());
+
+            unsafe { MapNamedBufferRange(self.0, start as GLintptr, size as GLsizeiptr, access) }
+        } else {
+            try_rethrow!(self.bind());
+
+            unsafe { MapBufferRange(self.1 as GLenum, start as GLintptr, size as GLsizeiptr, access) }
+        };
+
+        check_gl_errors!();
+
+        if data.is_null() {
+            throw!(GLError::InvalidOperation);
+        }
+
+        Ok(data)
+    }
+
+    /// Byte offset and size of a range of elements `T`, if it lies within the buffer
+    fn byte_range<T>(&self, offset: usize, len: usize) -> GLResult<(usize, usize)> {
+        let element_size = mem::size_of::<T>();
+
+        let start = offset.checked_mul(element_size);
+        let size = len.checked_mul(element_size);
+
+        match (start, size) {
+            (Some(start), Some(size)) if size > 0 && start.checked_add(size).map_or(false, |end| end <= self.2) => {
+                Ok((start, size))
+            }
+            _ => throw!(GLError::InvalidValue),
+        }
+    }
+
     /// Buffer raw data to the `GLBuffer`
     pub unsafe fn buffer_raw(&mut self, data: *const c_void, size: usize, usage: GLBufferUsage) -> GLResult<()> {
         if data.is_null() || size == 0 {
             throw!(GLError::InvalidValue)
+        } else {
+            try_rethrow!(self.buffer_data(data, size, usage));
+
+            self.2 = size;
+
+            Ok(())
+        }
+    }
+
+    /// Reallocates the storage, copying `data` into it unless it's null
+    unsafe fn buffer_data(&self, data: *const c_void, size: usize, usage: GLBufferUsage) -> GLResult<()> {
+        if GLCapabilities::use_direct_state_access() {
+            try_rethrow!(self.check());
+
+            NamedBufferData(self.0, size as GLsizeiptr, data, usage as GLenum);
         } else {
             try_rethrow!(self.bind());
 
             BufferData(self.1 as GLenum, size as GLsizeiptr, data, usage as GLenum);
+        }
 
-            check_gl_errors!();
+        check_gl_errors!();
 
-            self.2 = size;
+        Ok(())
+    }
 
-            Ok(())
-        }
+    /// Names the buffer in debuggers and debug messages
+    pub fn label(&self, label: &str) -> GLResult<()> {
+        label_object(BUFFER, self.0, label)
     }
 
     pub fn delete(&mut self) -> GLResult<()> {
@@ -121,6 +258,119 @@ impl GLBuffer {
     }
 }
 
+/// Unmaps a buffer when dropped, unless already unmapped
+struct GLMappedRange<'a>(&'a mut GLBuffer);
+
+impl<'a> GLMappedRange<'a> {
+    fn unmap(&mut self) -> GLResult<()> {
+        let intact = if GLCapabilities::use_direct_state_access() {
+            unsafe { UnmapNamedBuffer((self.0).0) }
+        } else {
+            try_rethrow!(self.0.bind());
+
+            unsafe { UnmapBuffer((self.0).1 as GLenum) }
+        };
+
+        check_gl_errors!();
+
+        if intact == FALSE {
+            throw!(GLError::InvalidOperation);
+        }
+
+        Ok(())
+    }
+}
+
+/// A range of a `GLBuffer` mapped for reading, which derefs to a slice of the mapped elements
+pub struct GLBufferMapping<'a, T: Copy> {
+    range: GLMappedRange<'a>,
+    data: *const T,
+    len: usize,
+}
+
+impl<'a, T: Copy> GLBufferMapping<'a, T> {
+    /// Unmaps the range, which fails with `InvalidOperation` if the contents were lost while mapped,
+    /// such as from a display mode change. Dropping the mapping does the same but ignores any errors.
+    pub fn unmap(mut self) -> GLResult<()> {
+        let result = self.range.unmap();
+
+        // Already unmapped
+        self.data = ptr::null();
+
+        result
+    }
+}
+
+impl<'a, T: Copy> Deref for GLBufferMapping<'a, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.data, self.len) }
+    }
+}
+
+impl<'a, T: Copy> Drop for GLBufferMapping<'a, T> {
+    fn drop(&mut self) {
+        if !self.data.is_null() {
+            let _ = self.range.unmap();
+        }
+    }
+}
+
+/// A range of a `GLBuffer` mapped for writing. The mapped memory has undefined contents, so it can't be read back.
+pub struct GLBufferWriteMapping<'a, T: Copy> {
+    range: GLMappedRange<'a>,
+    data: *mut T,
+    len: usize,
+}
+
+impl<'a, T: Copy> GLBufferWriteMapping<'a, T> {
+    /// Number of mapped elements
+    #[inline(always)]
+    pub fn len(&self) -> usize { self.len }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Copies `data` into the mapping, starting `offset` elements in.
+    ///
+    /// Fails with `InvalidValue` if the data would go past the end of the mapped range.
+    pub fn write(&mut self, offset: usize, data: &[T]) -> GLResult<()> {
+        if offset.checked_add(data.len()).map_or(true, |end| end > self.len) {
+            throw!(GLError::InvalidValue);
+        }
+
+        unsafe { ptr::copy_nonoverlapping(data.as_ptr(), self.data.offset(offset as isize), data.len()); }
+
+        Ok(())
+    }
+
+    /// Sets every mapped element to `value`
+    pub fn fill(&mut self, value: T) {
+        for i in 0..self.len {
+            unsafe { ptr::write(self.data.offset(i as isize), value); }
+        }
+    }
+
+    /// Unmaps the range. See `GLBufferMapping::unmap`.
+    pub fn unmap(mut self) -> GLResult<()> {
+        let result = self.range.unmap();
+
+        // Already unmapped
+        self.data = ptr::null_mut();
+
+        result
+    }
+}
+
+impl<'a, T: Copy> Drop for GLBufferWriteMapping<'a, T> {
+    fn drop(&mut self) {
+        if !self.data.is_null() {
+            let _ = self.range.unmap();
+        }
+    }
+}
+
 impl Drop for GLBuffer {
     fn drop(&mut self) {
         self.delete().expect("Could not drop GLBuffer")
@@ -0,0 +1,224 @@
+//! Driver limits and supported extensions, queried once per process
+
+use super::bindings::types::*;
+use super::bindings::*;
+
+use std::fmt;
+use std::ffi::CStr;
+use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering, ATOMIC_BOOL_INIT};
+
+use fnv::FnvHashSet;
+
+use super::error::*;
+
+/// Limits and features of the OpenGL implementation, which vary between drivers
+#[derive(Debug, Clone)]
+pub struct GLCapabilities {
+    pub vendor: String,
+    pub renderer: String,
+    pub version_string: String,
+    /// Major and minor version of the context
+    pub version: (u32, u32),
+    /// Largest width or height of a 2D texture
+    pub max_texture_size: usize,
+    pub max_color_attachments: usize,
+    pub max_draw_buffers: usize,
+    /// Upper limit for multisampled attachments
+    pub max_samples: usize,
+    /// Texture units available to all shader stages combined
+    pub max_texture_units: usize,
+    /// Highest anisotropic filtering level, or `1.0` without anisotropic filtering
+    pub max_anisotropy: f32,
+    /// Offsets of uniform buffer ranges have to be multiples of this
+    pub uniform_buffer_offset_alignment: usize,
+    /// Largest number of vertices per tessellation patch, or `0` without tessellation shaders
+    pub max_patch_vertices: usize,
+    pub tessellation_shaders: bool,
+    pub compute_shaders: bool,
+    /// `glMultiDrawElementsIndirect`, which draws many indirect commands with one call
+    pub multi_draw_indirect: bool,
+    pub anisotropic_filtering: bool,
+    pub direct_state_access: bool,
+    /// `KHR_debug`, for debug output, groups and object labels
+    pub debug: bool,
+    pub s3tc_compression: bool,
+    pub rgtc_compression: bool,
+    pub bptc_compression: bool,
+    pub astc_compression: bool,
+    pub extensions: FnvHashSet<String>,
+}
+
+/// Set to force the bind-based fallbacks even where direct state access is supported
+static DIRECT_STATE_ACCESS_DISABLED: AtomicBool = ATOMIC_BOOL_INIT;
+
+lazy_static! {
+    static ref CAPABILITIES: RwLock<Option<Arc<GLCapabilities>>> = RwLock::new(None);
+}
+
+fn get_integer(parameter: GLenum) -> GLint {
+    let mut value: GLint = 0;
+
+    unsafe { GetIntegerv(parameter, &mut value as *mut _); }
+
+    value
+}
+
+fn get_string(name: GLenum) -> String {
+    unsafe {
+        let string = GetString(name);
+
+        if string.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(string as *const _).to_string_lossy().into_owned()
+        }
+    }
+}
+
+impl GLCapabilities {
+    /// Queries the current context and remembers the result for `current`, replacing any previous one.
+    ///
+    /// Call this once after the context is created.
+    pub fn query() -> GLResult<Arc<GLCapabilities>> {
+        let num_extensions = get_integer(NUM_EXTENSIONS).max(0) as GLuint;
+
+        let mut extensions = FnvHashSet::default();
+
+        for index in 0..num_extensions {
+            let extension = unsafe { GetStringi(EXTENSIONS, index) };
+
+            if !extension.is_null() {
+                extensions.insert(unsafe { CStr::from_ptr(extension as *const _).to_string_lossy().into_owned() });
+            }
+        }
+
+        let version = (get_integer(MAJOR_VERSION) as u32, get_integer(MINOR_VERSION) as u32);
+
+        check_gl_errors!();
+
+        let has = |extension: &str| extensions.contains(extension);
+        let at_least = |major: u32, minor: u32| version >= (major, minor);
+
+        let anisotropic_filtering = at_least(4, 6) || has("GL_EXT_texture_filter_anisotropic") || has("GL_ARB_texture_filter_anisotropic");
+
+        let max_anisotropy = if anisotropic_filtering {
+            let mut value: GLfloat = 0.0;
+
+            unsafe { GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY_EXT, &mut value as *mut _); }
+
+            check_gl_errors!();
+
+            value.max(1.0)
+        } else { 1.0 };
+
+        let tessellation_shaders = at_least(4, 0) || has("GL_ARB_tessellation_shader");
+
+        let max_patch_vertices = if tessellation_shaders { get_integer(MAX_PATCH_VERTICES) as usize } else { 0 };
+
+        let capabilities = GLCapabilities {
+            vendor: get_string(VENDOR),
+            renderer: get_string(RENDERER),
+            version_string: get_string(VERSION),
+            version: version,
+            max_texture_size: get_integer(MAX_TEXTURE_SIZE) as usize,
+            max_color_attachments: get_integer(MAX_COLOR_ATTACHMENTS) as usize,
+            max_draw_buffers: get_integer(MAX_DRAW_BUFFERS) as usize,
+            max_samples: get_integer(MAX_SAMPLES).max(1) as usize,
+            max_texture_units: get_integer(MAX_COMBINED_TEXTURE_IMAGE_UNITS) as usize,
+            max_anisotropy: max_anisotropy,
+            uniform_buffer_offset_alignment: get_integer(UNIFORM_BUFFER_OFFSET_ALIGNMENT).max(1) as usize,
+            max_patch_vertices: max_patch_vertices,
+            tessellation_shaders: tessellation_shaders,
+            compute_shaders: at_least(4, 3) || has("GL_ARB_compute_shader"),
+            multi_draw_indirect: at_least(4, 3) || has("GL_ARB_multi_draw_indirect"),
+            anisotropic_filtering: anisotropic_filtering,
+            direct_state_access: at_least(4, 5) || has("GL_ARB_direct_state_access"),
+            debug: at_least(4, 3) || has("GL_KHR_debug"),
+            s3tc_compression: has("GL_EXT_texture_compression_s3tc"),
+            rgtc_compression: at_least(3, 0) || has("GL_ARB_texture_compression_rgtc"),
+            bptc_compression: at_least(4, 2) || has("GL_ARB_texture_compression_bptc"),
+            astc_compression: has("GL_KHR_texture_compression_astc_ldr"),
+            extensions: extensions.clone(),
+        };
+
+        check_gl_errors!();
+
+        let capabilities = Arc::new(capabilities);
+
+        match CAPABILITIES.write() {
+            Ok(mut stored) => *stored = Some(capabilities.clone()),
+            Err(_) => throw!(GLError::PoisonError),
+        }
+
+        Ok(capabilities)
+    }
+
+    /// The capabilities from the last `query`, querying them now if that never happened
+    pub fn current() -> GLResult<Arc<GLCapabilities>> {
+        match CAPABILITIES.read() {
+            Ok(stored) => if let Some(ref capabilities) = *stored {
+                return Ok(capabilities.clone());
+            },
+            Err(_) => throw!(GLError::PoisonError),
+        }
+
+        GLCapabilities::query()
+    }
+
+    /// Whether the wrappers should modify objects with direct state access instead of binding them first.
+    ///
+    /// True if the driver supports it and it wasn't disabled with `set_direct_state_access_enabled`.
+    pub fn use_direct_state_access() -> bool {
+        if DIRECT_STATE_ACCESS_DISABLED.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        match GLCapabilities::current() {
+            Ok(capabilities) => capabilities.direct_state_access,
+            Err(_) => false,
+        }
+    }
+
+    /// Disabling direct state access makes the wrappers use the bind-based paths, like on drivers without it.
+    ///
+    /// It's enabled by default, and enabling it does nothing if the driver doesn't support it.
+    pub fn set_direct_state_access_enabled(enabled: bool) {
+        DIRECT_STATE_ACCESS_DISABLED.store(!enabled, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn has_extension(&self, extension: &str) -> bool {
+        self.extensions.contains(extension)
+    }
+}
+
+impl fmt::Display for GLCapabilities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn yes_no(value: bool) -> &'static str {
+            if value { "yes" } else { "no" }
+        }
+
+        try!(writeln!(f, "OpenGL {}.{} ({})", self.version.0, self.version.1, self.version_string));
+        try!(writeln!(f, "    Vendor:                    {}", self.vendor));
+        try!(writeln!(f, "    Renderer:                  {}", self.renderer));
+        try!(writeln!(f, "    Max texture size:          {}", self.max_texture_size));
+        try!(writeln!(f, "    Max color attachments:     {}", self.max_color_attachments));
+        try!(writeln!(f, "    Max draw buffers:          {}", self.max_draw_buffers));
+        try!(writeln!(f, "    Max samples:               {}", self.max_samples));
+        try!(writeln!(f, "    Max texture units:         {}", self.max_texture_units));
+        try!(writeln!(f, "    Max anisotropy:            {}", self.max_anisotropy));
+        try!(writeln!(f, "    UBO offset alignment:      {}", self.uniform_buffer_offset_alignment));
+        try!(writeln!(f, "    Max patch vertices:        {}", self.max_patch_vertices));
+        try!(writeln!(f, "    Tessellation shaders:      {}", yes_no(self.tessellation_shaders)));
+        try!(writeln!(f, "    Compute shaders:           {}", yes_no(self.compute_shaders)));
+        try!(writeln!(f, "    Multi-draw indirect:       {}", yes_no(self.multi_draw_indirect)));
+        try!(writeln!(f, "    Direct state access:       {}", yes_no(self.direct_state_access)));
+        try!(writeln!(f, "    KHR_debug:                 {}", yes_no(self.debug)));
+        try!(writeln!(f, "    Compression:               S3TC {}, RGTC {}, BPTC {}, ASTC {}",
+                      yes_no(self.s3tc_compression), yes_no(self.rgtc_compression),
+                      yes_no(self.bptc_compression), yes_no(self.astc_compression)));
+
+        write!(f, "    Extensions:                {}", self.extensions.len())
+    }
+}
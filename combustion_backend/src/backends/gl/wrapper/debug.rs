@@ -6,6 +6,8 @@ use std::os::raw::c_void;
 use std::ffi::{CStr, CString};
 use std::ptr;
 use std::mem;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering, ATOMIC_BOOL_INIT};
 use enum_primitive::FromPrimitive;
 
 use super::error::*;
@@ -103,11 +105,123 @@ impl From<GLDebugSeverity> for String {
 impl GLDebugSeverity {
     #[inline(always)]
     pub fn into_string(self) -> String { self.into() }
+
+    /// Every severity, from most to least severe
+    pub fn all() -> [GLDebugSeverity; 4] {
+        [GLDebugSeverity::High, GLDebugSeverity::Medium, GLDebugSeverity::Low, GLDebugSeverity::Notification]
+    }
+
+    fn level(self) -> u8 {
+        match self {
+            GLDebugSeverity::High => 3,
+            GLDebugSeverity::Medium => 2,
+            GLDebugSeverity::Low => 1,
+            GLDebugSeverity::Notification => 0,
+        }
+    }
+
+    /// Whether this is as severe as `other` or more. The derived `Ord` follows the enum values instead, which aren't in order.
+    #[inline]
+    pub fn at_least(self, other: GLDebugSeverity) -> bool {
+        self.level() >= other.level()
+    }
 }
 
 pub type GLDebugProc = fn(GLuint, GLDebugSource, GLDebugType, GLDebugSeverity, String);
 
+/// Which debug messages are passed on to the callback installed by `enable_debug_filtered`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GLDebugFilter {
+    /// Messages less severe than this are dropped by the driver
+    pub min_severity: GLDebugSeverity,
+    /// Sources to allow, or `None` for all of them
+    pub allowed_sources: Option<Vec<GLDebugSource>>,
+    /// Types to allow, or `None` for all of them
+    pub allowed_types: Option<Vec<GLDebugType>>,
+    /// Deliver messages during the call that caused them, so a stack trace shows where they came from
+    pub synchronous: bool,
+    /// IDs of known noisy messages, added to `DEBUG_IGNORED`
+    pub muted_ids: Vec<GLuint>,
+    /// Abort after a high severity message in debug builds, which stops a debugger at the offending call
+    /// when `synchronous` is set. Release builds only log the message.
+    pub break_on_high: bool,
+}
+
+impl Default for GLDebugFilter {
+    fn default() -> GLDebugFilter {
+        GLDebugFilter {
+            min_severity: GLDebugSeverity::Low,
+            allowed_sources: None,
+            allowed_types: None,
+            synchronous: true,
+            muted_ids: Vec::new(),
+            break_on_high: false,
+        }
+    }
+}
+
+impl GLDebugFilter {
+    /// Whether a message would be passed on, not counting muted IDs
+    pub fn allows(&self, source: GLDebugSource, ty: GLDebugType, severity: GLDebugSeverity) -> bool {
+        severity.at_least(self.min_severity) &&
+            self.allowed_sources.as_ref().map_or(true, |sources| sources.contains(&source)) &&
+            self.allowed_types.as_ref().map_or(true, |types| types.contains(&ty))
+    }
+
+    /// Replaces the driver's message rules with this filter. Muted IDs are added to any already muted.
+    pub fn apply(&self) -> GLResult<()> {
+        try_rethrow!(set_synchronous(self.synchronous));
+
+        // Start from nothing, then enable each allowed combination
+        unsafe { DebugMessageControl(DONT_CARE, DONT_CARE, DONT_CARE, 0, ptr::null(), FALSE); }
+
+        check_gl_errors!();
+
+        let sources: Vec<Option<GLDebugSource>> = match self.allowed_sources {
+            Some(ref sources) => sources.iter().cloned().map(Some).collect(),
+            None => vec![None],
+        };
+
+        let types: Vec<Option<GLDebugType>> = match self.allowed_types {
+            Some(ref types) => types.iter().cloned().map(Some).collect(),
+            None => vec![None],
+        };
+
+        for &severity in GLDebugSeverity::all().iter().filter(|severity| severity.at_least(self.min_severity)) {
+            for &source in &sources {
+                for &ty in &types {
+                    try_rethrow!(set_filter(source, ty, Some(severity)));
+                }
+            }
+        }
+
+        for &id in &self.muted_ids {
+            mute_message(id);
+        }
+
+        BREAK_ON_HIGH.store(self.break_on_high, Ordering::SeqCst);
+
+        Ok(())
+    }
+}
+
+static BREAK_ON_HIGH: AtomicBool = ATOMIC_BOOL_INIT;
+
+/// Installs `cb` for every debug message. Fails with `Unsupported` if the context wasn't created with the debug flag.
 pub fn enable_debug(cb: GLDebugProc, synchronous: bool) -> GLResult<()> {
+    try_rethrow!(install_callback(cb, synchronous));
+
+    set_filter(None, None, None)
+}
+
+/// Installs `cb` for only the debug messages `filter` allows
+pub fn enable_debug_filtered(cb: GLDebugProc, filter: &GLDebugFilter) -> GLResult<()> {
+    try_rethrow!(install_callback(cb, filter.synchronous));
+
+    filter.apply()
+}
+
+fn install_callback(cb: GLDebugProc, synchronous: bool) -> GLResult<()> {
     let mut flags: GLint = 0;
 
     unsafe {
@@ -127,8 +241,6 @@ pub fn enable_debug(cb: GLDebugProc, synchronous: bool) -> GLResult<()> {
             DebugMessageCallback(debug_callback, cb as *mut _);
 
             check_gl_errors!();
-
-            try_rethrow!(set_filter(None, None, None));
         }
 
         Ok(())
@@ -187,6 +299,82 @@ pub fn send_debug_message(id: GLuint,
 
     Ok(())
 }
+
+/// Opens a named group of commands, which debuggers like RenderDoc show as a collapsible region.
+///
+/// Does nothing if `glPushDebugGroup` isn't loaded. Prefer `gl_debug_group!`, which also closes the group.
+pub fn push_group(name: &str) -> GLResult<()> {
+    if !PushDebugGroup::is_loaded() {
+        return Ok(());
+    }
+
+    unsafe {
+        PushDebugGroup(DEBUG_SOURCE_APPLICATION, 0, name.len() as GLsizei, name.as_ptr() as *const _);
+    }
+
+    check_gl_errors!();
+
+    Ok(())
+}
+
+/// Closes the last group opened by `push_group`. Does nothing if `glPopDebugGroup` isn't loaded.
+pub fn pop_group() -> GLResult<()> {
+    if !PopDebugGroup::is_loaded() {
+        return Ok(());
+    }
+
+    unsafe { PopDebugGroup(); }
+
+    check_gl_errors!();
+
+    Ok(())
+}
+
+/// Debug group that is closed when dropped
+pub struct GLDebugGroup(());
+
+impl GLDebugGroup {
+    pub fn new(name: &str) -> GLResult<GLDebugGroup> {
+        try_rethrow!(push_group(name));
+
+        Ok(GLDebugGroup(()))
+    }
+}
+
+impl Drop for GLDebugGroup {
+    fn drop(&mut self) {
+        // Any error is left for the next check instead of panicking, possibly while already returning an error
+        if PopDebugGroup::is_loaded() {
+            unsafe { PopDebugGroup(); }
+        }
+    }
+}
+
+/// Opens a debug group that lasts until the end of the enclosing scope, returning early with any error
+#[macro_export]
+macro_rules! gl_debug_group {
+    ($name:expr) => {
+        let _debug_group = try!($crate::backends::gl::wrapper::debug::GLDebugGroup::new($name));
+    };
+}
+
+/// Names an OpenGL object for debuggers and debug messages, where `identifier` is the kind of object, like `GL_TEXTURE`.
+///
+/// Does nothing if `glObjectLabel` isn't loaded.
+pub fn label_object(identifier: GLenum, object: GLuint, label: &str) -> GLResult<()> {
+    if !ObjectLabel::is_loaded() {
+        return Ok(());
+    }
+
+    unsafe {
+        ObjectLabel(identifier, object, label.len() as GLsizei, label.as_ptr() as *const _);
+    }
+
+    check_gl_errors!();
+
+    Ok(())
+}
+
 lazy_static! {
     pub static ref DEBUG_IGNORED: Arc<RwLock<Vec<GLuint>>> = {
         //Default ignores
@@ -194,6 +382,20 @@ lazy_static! {
     };
 }
 
+/// Stops passing messages with the given ID to the debug callback
+pub fn mute_message(id: GLuint) {
+    let mut ignored = DEBUG_IGNORED.write().unwrap();
+
+    if !ignored.contains(&id) {
+        ignored.push(id);
+    }
+}
+
+/// Undoes `mute_message`, including for the IDs muted by default
+pub fn unmute_message(id: GLuint) {
+    DEBUG_IGNORED.write().unwrap().retain(|&ignored| ignored != id);
+}
+
 extern "system" fn debug_callback(source: GLenum,
                                   ty: GLenum,
                                   id: GLuint,
@@ -208,15 +410,24 @@ extern "system" fn debug_callback(source: GLenum,
     if callback_ptr.is_null() {
         error!("Invalid callback supplied to OpenGL Debug Callback invocation function.");
     } else {
+        let severity = GLDebugSeverity::from_u32(severity as u32).expect("Cannot get GLDebugSeverity from u32 primitive");
+
         unsafe {
             let callback: GLDebugProc = mem::transmute(callback_ptr);
 
             callback(id,
                      GLDebugSource::from_u32(source as u32).expect("Cannot get GLDebugSource from u32 primitive"),
                      GLDebugType::from_u32(ty as u32).expect("Cannot get GLDebugType from u32 primitive"),
-                     GLDebugSeverity::from_u32(severity as u32).expect("Cannot get GLDebugSeverity from u32 primitive"),
+                     severity,
                      CStr::from_ptr(message).to_string_lossy().into());
         }
+
+        // Panicking can't unwind through the driver, so abort instead
+        if cfg!(debug_assertions) && severity == GLDebugSeverity::High && BREAK_ON_HIGH.load(Ordering::SeqCst) {
+            error!("Aborting after high severity OpenGL debug message {}", id);
+
+            process::abort();
+        }
     }
 }
 
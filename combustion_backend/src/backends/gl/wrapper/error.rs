@@ -10,6 +10,8 @@ use std::sync::atomic::{Ordering, AtomicBool, ATOMIC_BOOL_INIT};
 
 use image::ImageError;
 
+use protocols::error::ProtocolError;
+
 use trace_error::TraceResult;
 
 pub type GLResult<T> = TraceResult<T, GLError>;
@@ -34,6 +36,8 @@ pub enum GLError {
     FromUtf8Error(FromUtf8Error),
     //Errors from the `image` library
     Image(ImageError),
+    //Errors reading texture protocols
+    Protocol(ProtocolError),
     //Errors from this program
     Unsupported,
     IncompleteFramebuffer,
@@ -41,46 +45,116 @@ pub enum GLError {
     InvalidInstance,
     AlreadyInitialized,
     UnsupportedExtension(String),
+    /// The driver can't use the compressed texture format, so it has to be decoded some other way
+    UnsupportedFormat(GLenum),
+    /// A shader program was linked with shader stages that can't be used together
+    InvalidShaderStages(&'static str),
+    /// A shader failed to compile, with the compiler's info log
+    ShaderCompile(String),
+    /// A shader program failed to link, with the linker's info log
+    ProgramLink(String),
+    /// A shader program has no active uniform by that name
+    UniformNotFound(String),
+    /// Every texture unit a `GLTextureUnits` can hand out is in use
+    TextureUnitsExhausted,
+    /// Every error flag OpenGL had set when they were checked, along with where that happened
+    Driver(Vec<GLError>, GLErrorContext),
+}
+
+/// Where OpenGL errors were checked, and optionally what was being done at the time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GLErrorContext {
+    /// Source file and line of the check, if it went through `check_gl_errors!`
+    pub location: Option<(&'static str, u32)>,
+    pub operation: Option<String>,
 }
 
+impl GLErrorContext {
+    pub fn new(file: &'static str, line: u32, operation: Option<String>) -> GLErrorContext {
+        GLErrorContext { location: Some((file, line)), operation: operation }
+    }
+}
+
+impl Display for GLErrorContext {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self.location {
+            Some((file, line)) => try!(write!(f, "at {}:{}", file, line)),
+            None => try!(write!(f, "at unknown location")),
+        }
+
+        if let Some(ref operation) = self.operation {
+            try!(write!(f, " during {}", operation));
+        }
+
+        Ok(())
+    }
+}
+
+/// `glGetError` keeps returning `GL_CONTEXT_LOST` on some drivers, so draining the flags gives up after this many
+const MAX_ERROR_FLAGS: usize = 16;
+
 static mut CHECK_DISABLED: AtomicBool = ATOMIC_BOOL_INIT;
 
+/// Returns early with every pending OpenGL error, recording the file and line of the check.
+///
+/// Takes an optional description of the operation, either on its own like `check_gl_errors!("clearing the gbuffer")`
+/// or as a format string and arguments like `check_gl_errors!("TexImage2D for {:?}", path)`.
+/// It's only formatted if there were errors.
+///
+/// `check_gl_errors!(=> value)` checks and then evaluates to `value`.
 #[macro_export]
 macro_rules! check_gl_errors {
-    () => {try_rethrow!(GLError::check())};
+    () => {try_rethrow!(GLError::check_at(file!(), line!(), None))};
 
-    ($ret:expr) => {
-        try_rethrow!(GLError::check());
+    (=> $ret:expr) => {{
+        check_gl_errors!();
 
         $ret
+    }};
+
+    ($operation:expr) => {
+        try_rethrow!(GLError::check_with(file!(), line!(), || format!("{}", $operation)))
+    };
+
+    ($fmt:expr, $($arg:tt)+) => {
+        try_rethrow!(GLError::check_with(file!(), line!(), || format!($fmt, $($arg)+)))
     };
 }
 
+/// Same as `check_gl_errors!`
+#[macro_export]
+macro_rules! check_errors {
+    ($($arg:tt)*) => {check_gl_errors!($($arg)*)};
+}
+
 impl GLError {
-    /// Check if there are any errors in the OpenGL error queue
+    /// Check if there are any errors in the OpenGL error queue, without recording where.
+    /// `check_gl_errors!` should be used instead wherever possible.
     ///
     /// If check was disabled, this functions returns `Ok(())` immediately
     #[inline(never)]
     pub fn check() -> GLResult<()> {
+        GLError::check_context(|| GLErrorContext { location: None, operation: None })
+    }
+
+    /// Check for errors like `check`, blaming them on the given source location and operation
+    #[inline(never)]
+    pub fn check_at(file: &'static str, line: u32, operation: Option<String>) -> GLResult<()> {
+        GLError::check_context(|| GLErrorContext::new(file, line, operation))
+    }
+
+    /// Same as `check_at`, but only describes the operation if there were errors
+    #[inline(never)]
+    pub fn check_with<F>(file: &'static str, line: u32, operation: F) -> GLResult<()> where F: FnOnce() -> String {
+        GLError::check_context(|| GLErrorContext::new(file, line, Some(operation())))
+    }
+
+    fn check_context<F>(context: F) -> GLResult<()> where F: FnOnce() -> GLErrorContext {
         if unsafe { !CHECK_DISABLED.load(Ordering::SeqCst) } {
-            //Get last error from OpenGL
-            let err_code = unsafe { GetError() };
+            let errors = GLError::drain();
 
-            //If there was an error, match it to the known OpenGL error kinds
-            if err_code != NO_ERROR {
-                let err = match err_code {
-                    INVALID_ENUM => GLError::InvalidEnum,
-                    INVALID_VALUE => GLError::InvalidValue,
-                    INVALID_OPERATION => GLError::InvalidOperation,
-                    STACK_OVERFLOW => GLError::StackOverflow,
-                    STACK_UNDERFLOW => GLError::StackUnderflow,
-                    OUT_OF_MEMORY => GLError::OutOfMemory,
-                    INVALID_FRAMEBUFFER_OPERATION => GLError::InvalidFramebufferOperation,
-                    CONTEXT_LOST => GLError::ContextLost,
-                    _ => GLError::UnknownError(err_code)
-                };
-
-                throw!(err);
+            if !errors.is_empty() {
+                throw!(GLError::Driver(errors, context()));
             }
         }
 
@@ -101,6 +175,48 @@ impl GLError {
     pub unsafe fn enable_check() {
         CHECK_DISABLED.store(false, Ordering::SeqCst);
     }
+
+    /// Clears every error flag OpenGL has set, returning them in the order they were reported
+    pub fn drain() -> Vec<GLError> {
+        let mut errors = Vec::new();
+
+        while errors.len() < MAX_ERROR_FLAGS {
+            let err_code = unsafe { GetError() };
+
+            if err_code == NO_ERROR {
+                break;
+            }
+
+            errors.push(GLError::from_code(err_code));
+        }
+
+        errors
+    }
+
+    /// Matches an error code from `glGetError` to the known OpenGL error kinds
+    pub fn from_code(err_code: GLenum) -> GLError {
+        match err_code {
+            INVALID_ENUM => GLError::InvalidEnum,
+            INVALID_VALUE => GLError::InvalidValue,
+            INVALID_OPERATION => GLError::InvalidOperation,
+            STACK_OVERFLOW => GLError::StackOverflow,
+            STACK_UNDERFLOW => GLError::StackUnderflow,
+            OUT_OF_MEMORY => GLError::OutOfMemory,
+            INVALID_FRAMEBUFFER_OPERATION => GLError::InvalidFramebufferOperation,
+            CONTEXT_LOST => GLError::ContextLost,
+            _ => GLError::UnknownError(err_code)
+        }
+    }
+
+    /// Whether the context can't be trusted to keep rendering after this error,
+    /// because it was lost or ran out of memory
+    pub fn is_fatal(&self) -> bool {
+        match *self {
+            GLError::Driver(ref errors, _) => errors.iter().any(GLError::is_fatal),
+            GLError::OutOfMemory | GLError::ContextLost => true,
+            _ => false,
+        }
+    }
 }
 
 impl From<NulError> for GLError {
@@ -132,6 +248,12 @@ impl From<ImageError> for GLError {
     }
 }
 
+impl From<ProtocolError> for GLError {
+    fn from(err: ProtocolError) -> GLError {
+        GLError::Protocol(err)
+    }
+}
+
 impl From<GLError> for io::Error {
     fn from(err: GLError) -> io::Error {
         match err {
@@ -143,7 +265,20 @@ impl From<GLError> for io::Error {
 
 impl Display for GLError {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "{}", self.description())
+        match *self {
+            GLError::Driver(ref errors, ref context) => {
+                for (i, err) in errors.iter().enumerate() {
+                    try!(write!(f, "{}{}", if i > 0 { ", " } else { "" }, err));
+                }
+
+                write!(f, " {}", context)
+            }
+            GLError::UnknownError(code) => write!(f, "Unknown Error 0x{:04X}", code),
+            GLError::UnsupportedFormat(format) => write!(f, "Unsupported Format 0x{:04X}", format),
+            GLError::ShaderCompile(ref log) => write!(f, "Shader Compile Error: {}", log),
+            GLError::ProgramLink(ref log) => write!(f, "Program Link Error: {}", log),
+            _ => write!(f, "{}", self.description())
+        }
     }
 }
 
@@ -163,12 +298,21 @@ impl Error for GLError {
             GLError::ContextLost => "GPU Context Lost",
             GLError::UnknownError(_) => "Unknown Error",
             GLError::Image(ref err) => err.description(),
+            GLError::Protocol(ref err) => err.description(),
             GLError::Unsupported => "Unsupported",
             GLError::IncompleteFramebuffer => "Incomplete Framebuffer",
             GLError::PoisonError => "Poison Error",
             GLError::InvalidInstance => "Invalid Instance",
             GLError::AlreadyInitialized => "Already Initialized",
-            GLError::UnsupportedExtension(_) => "Unsupported Extension"
+            GLError::UnsupportedExtension(_) => "Unsupported Extension",
+            GLError::UnsupportedFormat(_) => "Unsupported Format",
+            GLError::InvalidShaderStages(reason) => reason,
+            GLError::ShaderCompile(_) => "Shader Compile Error",
+            GLError::ProgramLink(_) => "Program Link Error",
+            GLError::UniformNotFound(_) => "Uniform Not Found",
+            GLError::TextureUnitsExhausted => "Texture Units Exhausted",
+            GLError::Driver(ref errors, _) if errors.len() == 1 => errors[0].description(),
+            GLError::Driver(..) => "Multiple OpenGL Errors",
         }
     }
 }
\ No newline at end of file
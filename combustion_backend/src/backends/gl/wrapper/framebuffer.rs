@@ -4,10 +4,17 @@ use super::{GLObject, GLBindable};
 
 use std::mem;
 use std::ptr;
+use std::time::Duration;
 
 use super::error::*;
+use super::debug::label_object;
 use super::shader::*;
 use super::renderbuffer::*;
+use super::buffer::*;
+use super::sync::GLFence;
+use super::state::GLStateCache;
+use super::texture::GLGenericTexture;
+use super::capabilities::GLCapabilities;
 
 #[derive(Eq, PartialEq)]
 pub struct GLFramebuffer(GLuint);
@@ -27,7 +34,7 @@ impl GLBindable for GLFramebuffer {
     fn bind(&self) -> GLResult<()> {
         try_rethrow!(self.check());
 
-        unsafe { BindFramebuffer(FRAMEBUFFER, self.0); }
+        GLStateCache::bind_framebuffer(FRAMEBUFFER, self.0);
 
         check_gl_errors!();
 
@@ -47,7 +54,7 @@ impl GLFramebuffer {
 
         check_gl_errors!();
 
-        unsafe { BindFramebuffer(FRAMEBUFFER, framebuffer); }
+        GLStateCache::bind_framebuffer(FRAMEBUFFER, framebuffer);
 
         check_gl_errors!();
 
@@ -60,25 +67,129 @@ impl GLFramebuffer {
         Ok(FRAMEBUFFER_COMPLETE == unsafe { CheckFramebufferStatus(FRAMEBUFFER) })
     }
 
-    pub fn renderbuffer(&mut self, renderbuffer: &GLRenderbuffer) -> GLResult<()> {
-        try_rethrow!(self.bind());
+    /// Attaches the base level of a texture, like `GL_COLOR_ATTACHMENT0` or `GL_DEPTH_ATTACHMENT`.
+    ///
+    /// Leaves the framebuffer bound, unless direct state access is available.
+    pub fn texture<T: GLGenericTexture + ?Sized>(&mut self, attachment: GLenum, texture: &T) -> GLResult<()> {
+        try_rethrow!(texture.check());
 
-        unsafe {
-            FramebufferRenderbuffer(FRAMEBUFFER,
-                                    DEPTH_STENCIL_ATTACHMENT,
-                                    RENDERBUFFER,
-                                    renderbuffer.raw());
+        if GLCapabilities::use_direct_state_access() && !is_default_framebuffer(self) {
+            unsafe { NamedFramebufferTexture(self.0, attachment, texture.raw(), 0); }
+        } else {
+            try_rethrow!(self.bind());
+
+            unsafe { FramebufferTexture2D(FRAMEBUFFER, attachment, texture.kind() as GLenum, texture.raw(), 0); }
+        }
+
+        check_gl_errors!();
+
+        Ok(())
+    }
+
+    /// Attaches a renderbuffer, usually to `DEPTH_STENCIL_ATTACHMENT`, `DEPTH_ATTACHMENT` or `STENCIL_ATTACHMENT`
+    pub fn renderbuffer(&mut self, attachment: GLenum, renderbuffer: &GLRenderbuffer) -> GLResult<()> {
+        try_rethrow!(renderbuffer.check());
+
+        if GLCapabilities::use_direct_state_access() && !is_default_framebuffer(self) {
+            unsafe { NamedFramebufferRenderbuffer(self.0, attachment, RENDERBUFFER, renderbuffer.raw()); }
+        } else {
+            try_rethrow!(self.bind());
+
+            unsafe { FramebufferRenderbuffer(FRAMEBUFFER, attachment, RENDERBUFFER, renderbuffer.raw()); }
         }
 
+        check_gl_errors!();
+
         Ok(())
     }
 
+    /// Reads back a region of the given color attachment, or the back buffer for the default framebuffer,
+    /// as tightly packed rows with the bottom row first.
+    ///
+    /// `format` and `data_type` are passed straight to `glReadPixels`, so they decide how the bytes are laid out.
+    pub fn read_pixels(&self, attachment: usize, width: usize, height: usize, format: GLenum, data_type: GLenum) -> GLResult<Vec<u8>> {
+        let mut pixels = vec![0u8; width * height * try_rethrow!(pixel_size(format, data_type))];
+
+        self.prepare_read(attachment);
+
+        unsafe {
+            ReadPixels(0, 0, width as GLsizei, height as GLsizei, format, data_type, pixels.as_mut_ptr() as *mut _);
+        }
+
+        check_gl_errors!();
+
+        Ok(pixels)
+    }
+
+    /// Starts reading back a region like `read_pixels`, but into a pixel pack buffer instead of waiting for the GPU to get there.
+    ///
+    /// Poll the returned readback once per frame until it has the pixels.
+    pub fn read_pixels_async(&self, attachment: usize, width: usize, height: usize, format: GLenum, data_type: GLenum) -> GLResult<GLPendingReadback> {
+        let size = width * height * try_rethrow!(pixel_size(format, data_type));
+
+        let mut buffer = try_rethrow!(GLBuffer::new(GLBufferTarget::PixelPackBuffer));
+
+        try_rethrow!(buffer.allocate(size, GLBufferUsage::StreamRead));
+        try_rethrow!(buffer.bind());
+
+        self.prepare_read(attachment);
+
+        unsafe {
+            // With a pixel pack buffer bound, the pointer is an offset into it
+            ReadPixels(0, 0, width as GLsizei, height as GLsizei, format, data_type, ptr::null_mut());
+
+            // Otherwise any later synchronous reads would go into the buffer as well
+            BindBuffer(PIXEL_PACK_BUFFER, 0);
+        }
+
+        check_gl_errors!();
+
+        let fence = try_rethrow!(GLFence::insert());
+
+        Ok(GLPendingReadback { buffer: buffer, fence: Some(fence) })
+    }
+
+    /// Selects the attachment, or the back buffer, to read tightly packed rows from
+    fn prepare_read(&self, attachment: usize) {
+        GLStateCache::bind_framebuffer(READ_FRAMEBUFFER, self.0);
+
+        unsafe {
+            if is_default_framebuffer(self) {
+                ReadBuffer(BACK);
+            } else {
+                ReadBuffer(COLOR_ATTACHMENT0 + attachment as GLenum);
+            }
+
+            PixelStorei(PACK_ALIGNMENT, 1);
+        }
+    }
+
+    /// Same as `read_pixels`, but reads the attachment as floats, which is needed for 16F and 32F attachments
+    pub fn read_pixels_f32(&self, attachment: usize, width: usize, height: usize, format: GLenum) -> GLResult<Vec<f32>> {
+        let bytes = try_rethrow!(self.read_pixels(attachment, width, height, format, FLOAT));
+
+        Ok(bytes.chunks(4).map(|chunk| {
+            let mut value = [0u8; 4];
+
+            value.copy_from_slice(chunk);
+
+            unsafe { mem::transmute::<[u8; 4], f32>(value) }
+        }).collect())
+    }
+
+    /// Names the framebuffer in debuggers and debug messages
+    pub fn label(&self, label: &str) -> GLResult<()> {
+        label_object(FRAMEBUFFER, self.0, label)
+    }
+
     pub fn delete(&mut self) -> GLResult<()> {
         if self.is_valid() && self.0 != 0 {
             unsafe {
                 DeleteFramebuffers(1, &mut self.0 as *mut _);
             }
 
+            GLStateCache::forget_framebuffer(self.0);
+
             check_gl_errors!();
         }
 
@@ -90,4 +201,85 @@ impl Drop for GLFramebuffer {
     fn drop(&mut self) {
         self.delete().expect("Could not drop GLFramebuffer")
     }
-}
\ No newline at end of file
+}
+
+/// Size in bytes of a single pixel transferred with the given format and data type
+pub fn pixel_size(format: GLenum, data_type: GLenum) -> GLResult<usize> {
+    let channels = match format {
+        RED | GREEN | BLUE | DEPTH_COMPONENT | RED_INTEGER => 1,
+        RG | RG_INTEGER => 2,
+        RGB | BGR | RGB_INTEGER => 3,
+        RGBA | BGRA | RGBA_INTEGER => 4,
+        _ => throw!(GLError::InvalidValue),
+    };
+
+    let channel_size = match data_type {
+        UNSIGNED_BYTE | BYTE => 1,
+        UNSIGNED_SHORT | SHORT | HALF_FLOAT => 2,
+        UNSIGNED_INT | INT | FLOAT => 4,
+        _ => throw!(GLError::InvalidValue),
+    };
+
+    Ok(channels * channel_size)
+}
+
+/// Pixels being read back by `read_pixels_async`, which can be taken once the GPU has written them
+pub struct GLPendingReadback {
+    buffer: GLBuffer,
+    /// Taken along with the pixels
+    fence: Option<GLFence>,
+}
+
+impl GLPendingReadback {
+    /// Returns the pixels if they're ready, or `None` without blocking if they aren't.
+    ///
+    /// The pixels can only be taken once, after which this fails with `InvalidOperation`.
+    pub fn poll(&mut self) -> GLResult<Option<Vec<u8>>> {
+        let ready = match self.fence {
+            Some(ref fence) => try_rethrow!(fence.is_signaled()),
+            None => throw!(GLError::InvalidOperation),
+        };
+
+        if ready {
+            self.fence = None;
+
+            self.take_pixels().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Blocks until the pixels are ready and returns them
+    pub fn wait(mut self) -> GLResult<Vec<u8>> {
+        match self.fence.take() {
+            Some(fence) => {
+                while !try_rethrow!(fence.wait(Duration::from_millis(100))) {}
+            }
+            None => throw!(GLError::InvalidOperation),
+        }
+
+        self.take_pixels()
+    }
+
+    fn take_pixels(&mut self) -> GLResult<Vec<u8>> {
+        let len = self.buffer.num_bytes();
+
+        let pixels = {
+            let mapping = try_rethrow!(self.buffer.map_range::<u8>(0, len));
+
+            let pixels = mapping.to_vec();
+
+            // The contents can be lost while mapped, which only unmapping reports
+            try_rethrow!(mapping.unmap());
+
+            pixels
+        };
+
+        // Mapping bound the buffer, which would capture later synchronous reads
+        unsafe { BindBuffer(PIXEL_PACK_BUFFER, 0); }
+
+        check_gl_errors!();
+
+        Ok(pixels)
+    }
+}
@@ -0,0 +1,151 @@
+//! Indirect draw commands, submitted with a single `glMultiDrawElementsIndirect` where it's available
+
+use super::bindings::types::*;
+use super::bindings::*;
+use super::{GLObject, GLBindable};
+
+use std::mem;
+use std::ops::Range;
+
+use super::error::*;
+use super::buffer::*;
+use super::capabilities::GLCapabilities;
+
+/// Parameters of one indexed draw, laid out like `DrawElementsIndirectCommand`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct GLDrawCommand {
+    /// Number of indices to draw
+    pub count: u32,
+    pub instance_count: u32,
+    /// Offset into the index buffer, in indices rather than bytes
+    pub first_index: u32,
+    /// Added to every index before fetching vertices
+    pub base_vertex: i32,
+    /// Offset added to the instance index of attributes with a divisor
+    pub base_instance: u32,
+}
+
+impl GLDrawCommand {
+    /// Draws a single instance of `count` indices starting at `first_index`
+    pub fn new(count: u32, first_index: u32, base_vertex: i32) -> GLDrawCommand {
+        GLDrawCommand { count: count, instance_count: 1, first_index: first_index, base_vertex: base_vertex, base_instance: 0 }
+    }
+
+    pub fn with_instances(self, instance_count: u32, base_instance: u32) -> GLDrawCommand {
+        GLDrawCommand { instance_count: instance_count, base_instance: base_instance, ..self }
+    }
+}
+
+/// Draw commands stored on the GPU, drawn with one call where multi-draw indirect is supported.
+///
+/// Without it, `draw` loops over a copy of the commands kept on the CPU, so the result is the same either way.
+pub struct GLDrawIndirectBuffer {
+    buffer: GLBuffer,
+    commands: Vec<GLDrawCommand>,
+}
+
+fn index_size(index_type: GLenum) -> GLResult<usize> {
+    Ok(match index_type {
+        UNSIGNED_BYTE => 1,
+        UNSIGNED_SHORT => 2,
+        UNSIGNED_INT => 4,
+        _ => throw!(GLError::InvalidEnum),
+    })
+}
+
+impl GLDrawIndirectBuffer {
+    pub fn new() -> GLResult<GLDrawIndirectBuffer> {
+        Ok(GLDrawIndirectBuffer {
+            buffer: try_rethrow!(GLBuffer::new(GLBufferTarget::DrawIndirectBuffer)),
+            commands: Vec::new(),
+        })
+    }
+
+    /// Whether `draw` submits the commands with one `glMultiDrawElementsIndirect`, which needs OpenGL 4.3
+    pub fn is_supported() -> bool {
+        match GLCapabilities::current() {
+            Ok(capabilities) => capabilities.multi_draw_indirect && MultiDrawElementsIndirect::is_loaded(),
+            Err(_) => false,
+        }
+    }
+
+    #[inline(always)]
+    pub fn buffer(&self) -> &GLBuffer { &self.buffer }
+
+    #[inline(always)]
+    pub fn commands(&self) -> &[GLDrawCommand] { &self.commands }
+
+    #[inline]
+    pub fn len(&self) -> usize { self.commands.len() }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.commands.is_empty() }
+
+    /// Replaces every command, reallocating the buffer so the driver doesn't have to wait for draws still reading it
+    pub fn update(&mut self, commands: &[GLDrawCommand]) -> GLResult<()> {
+        self.commands.clear();
+        self.commands.extend_from_slice(commands);
+
+        if Self::is_supported() && !commands.is_empty() {
+            try_rethrow!(self.buffer.upload(commands, GLBufferUsage::StreamDraw));
+        }
+
+        Ok(())
+    }
+
+    /// Draws every command with the currently bound vertex array, program and index buffer
+    pub fn draw(&self, mode: GLenum, index_type: GLenum) -> GLResult<()> {
+        self.draw_range(mode, index_type, 0..self.commands.len())
+    }
+
+    /// Draws the commands in `range`, so a single buffer can hold the draws of several materials
+    pub fn draw_range(&self, mode: GLenum, index_type: GLenum, range: Range<usize>) -> GLResult<()> {
+        if range.start > range.end || range.end > self.commands.len() {
+            throw!(GLError::InvalidValue);
+        }
+
+        if range.start == range.end {
+            return Ok(());
+        }
+
+        let index_size = try_rethrow!(index_size(index_type));
+
+        if Self::is_supported() {
+            try_rethrow!(self.buffer.bind());
+
+            let offset = range.start * mem::size_of::<GLDrawCommand>();
+
+            unsafe {
+                MultiDrawElementsIndirect(mode, index_type, offset as *const _, range.len() as GLsizei,
+                                          mem::size_of::<GLDrawCommand>() as GLsizei);
+            }
+
+            check_gl_errors!("drawing {} indirect commands", range.len());
+        } else {
+            let base_instance = DrawElementsInstancedBaseVertexBaseInstance::is_loaded();
+
+            for command in &self.commands[range] {
+                let indices = (command.first_index as usize * index_size) as *const _;
+
+                unsafe {
+                    if base_instance {
+                        DrawElementsInstancedBaseVertexBaseInstance(mode, command.count as GLsizei, index_type, indices,
+                                                                    command.instance_count as GLsizei,
+                                                                    command.base_vertex, command.base_instance);
+                    } else if command.base_instance == 0 {
+                        DrawElementsInstancedBaseVertex(mode, command.count as GLsizei, index_type, indices,
+                                                        command.instance_count as GLsizei, command.base_vertex);
+                    } else {
+                        // Instanced attributes would start at the wrong instance
+                        throw!(GLError::Unsupported);
+                    }
+                }
+            }
+
+            check_gl_errors!("drawing indirect commands one at a time");
+        }
+
+        Ok(())
+    }
+}
@@ -28,25 +28,43 @@ macro_rules! impl_simple_globject {
 }
 
 pub mod debug;
+pub mod capabilities;
 //pub mod requires;
 
 pub mod vertex_array;
 pub mod shader;
 pub mod shader_program;
+pub mod program_cache;
 pub mod texture;
+pub mod texture_units;
+pub mod sampler;
 pub mod renderbuffer;
 pub mod framebuffer;
 pub mod buffer;
+pub mod indirect;
+pub mod query;
+pub mod sync;
+pub mod uniform_buffer;
+pub mod state;
 
 pub mod uniform;
 
 pub use self::debug::*;
+pub use self::capabilities::*;
 //pub use self::requires::*;
 pub use self::vertex_array::*;
 pub use self::shader::*;
 pub use self::shader_program::*;
+pub use self::program_cache::*;
 pub use self::texture::*;
+pub use self::texture_units::*;
+pub use self::sampler::*;
 pub use self::renderbuffer::*;
 pub use self::framebuffer::*;
 pub use self::buffer::*;
+pub use self::indirect::*;
+pub use self::query::*;
+pub use self::sync::*;
+pub use self::uniform_buffer::*;
+pub use self::state::*;
 pub use self::uniform::*;
\ No newline at end of file
@@ -0,0 +1,178 @@
+//! On-disk cache of linked shader program binaries, used by `GLShaderProgramBuilder`
+
+use super::bindings::types::*;
+use super::bindings::*;
+
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::prelude::*;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use fnv::FnvHasher;
+
+use super::error::*;
+use super::capabilities::GLCapabilities;
+use super::shader::GLShaderVariant;
+use super::shader_program::GLShaderProgram;
+
+/// Identifies cache files, and changes whenever their layout does
+const MAGIC: &'static [u8; 8] = b"CMBPRG01";
+
+/// Magic, binary format, binary length and binary hash
+const HEADER_SIZE: usize = 8 + 4 + 4 + 8;
+
+lazy_static! {
+    static ref PROGRAM_CACHE_DIRECTORY: RwLock<Option<PathBuf>> = RwLock::new(None);
+}
+
+/// Sets the directory program binaries are cached in, or disables the cache with `None`, which is the default.
+///
+/// The directory is created when the first program is cached.
+pub fn set_program_cache_directory<P: Into<PathBuf>>(directory: Option<P>) -> GLResult<()> {
+    match PROGRAM_CACHE_DIRECTORY.write() {
+        Ok(mut stored) => *stored = directory.map(Into::into),
+        Err(_) => throw!(GLError::PoisonError),
+    }
+
+    Ok(())
+}
+
+pub fn program_cache_directory() -> GLResult<Option<PathBuf>> {
+    match PROGRAM_CACHE_DIRECTORY.read() {
+        Ok(stored) => Ok(stored.clone()),
+        Err(_) => throw!(GLError::PoisonError),
+    }
+}
+
+/// Whether a cache directory is set and the driver can save and load program binaries
+pub fn is_program_cache_enabled() -> GLResult<bool> {
+    if try_rethrow!(program_cache_directory()).is_none() || !GetProgramBinary::is_loaded() || !ProgramBinary::is_loaded() {
+        return Ok(false);
+    }
+
+    let mut num_formats: GLint = 0;
+
+    unsafe { GetIntegerv(NUM_PROGRAM_BINARY_FORMATS, &mut num_formats as *mut _); }
+
+    check_gl_errors!();
+
+    Ok(num_formats > 0)
+}
+
+/// Hashes the sources of a program together with the driver, since binaries only work with the driver that created them
+pub fn program_cache_key(sources: &[(GLShaderVariant, String)]) -> GLResult<u64> {
+    let capabilities = try_rethrow!(GLCapabilities::current());
+
+    let mut hasher = FnvHasher::default();
+
+    capabilities.vendor.hash(&mut hasher);
+    capabilities.renderer.hash(&mut hasher);
+    capabilities.version_string.hash(&mut hasher);
+    sources.hash(&mut hasher);
+
+    Ok(hasher.finish())
+}
+
+fn cache_path(key: u64) -> GLResult<Option<PathBuf>> {
+    Ok(try_rethrow!(program_cache_directory()).map(|directory| directory.join(format!("{:016x}.bin", key))))
+}
+
+fn hash_binary(binary: &[u8]) -> u64 {
+    let mut hasher = FnvHasher::default();
+
+    hasher.write(binary);
+
+    hasher.finish()
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    bytes.iter().rev().fold(0, |value, &byte| (value << 8) | byte as u32)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    bytes.iter().rev().fold(0, |value, &byte| (value << 8) | byte as u64)
+}
+
+fn write_le(bytes: &mut Vec<u8>, value: u64, size: usize) {
+    for i in 0..size {
+        bytes.push((value >> (i * 8)) as u8);
+    }
+}
+
+/// Splits a cache file into the binary format and the binary, or `None` if it's truncated or corrupted
+fn parse_cache_file(contents: &[u8]) -> Option<(GLenum, &[u8])> {
+    if contents.len() < HEADER_SIZE || contents[..8] != MAGIC[..] {
+        return None;
+    }
+
+    let format = read_u32(&contents[8..12]);
+    let len = read_u32(&contents[12..16]) as usize;
+    let hash = read_u64(&contents[16..24]);
+
+    let binary = &contents[HEADER_SIZE..];
+
+    if binary.len() != len || hash_binary(binary) != hash {
+        return None;
+    }
+
+    Some((format as GLenum, binary))
+}
+
+/// Tries to load the program with the given key from the cache, returning whether it was.
+///
+/// Entries that are corrupted or that the driver rejects are deleted, so they're replaced the next time the program is linked.
+pub fn load_cached_program(program: &mut GLShaderProgram, key: u64) -> GLResult<bool> {
+    let path = match try_rethrow!(cache_path(key)) {
+        Some(path) => path,
+        None => return Ok(false),
+    };
+
+    let mut contents = Vec::new();
+
+    match File::open(&path).and_then(|mut file| file.read_to_end(&mut contents)) {
+        Ok(_) => {}
+        Err(_) => return Ok(false),
+    }
+
+    let loaded = match parse_cache_file(&contents) {
+        Some((format, binary)) => try_rethrow!(program.load_binary(format, binary)),
+        None => false,
+    };
+
+    if !loaded {
+        let _ = fs::remove_file(&path);
+    }
+
+    Ok(loaded)
+}
+
+/// Saves a linked program to the cache under the given key
+pub fn save_cached_program(program: &GLShaderProgram, key: u64) -> GLResult<()> {
+    let path = match try_rethrow!(cache_path(key)) {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    let (format, binary) = try_rethrow!(program.get_binary());
+
+    let mut contents = Vec::with_capacity(HEADER_SIZE + binary.len());
+
+    contents.extend_from_slice(&MAGIC[..]);
+    write_le(&mut contents, format as u64, 4);
+    write_le(&mut contents, binary.len() as u64, 4);
+    write_le(&mut contents, hash_binary(&binary), 8);
+    contents.extend_from_slice(&binary);
+
+    if let Some(directory) = path.parent() {
+        try_throw!(fs::create_dir_all(directory));
+    }
+
+    // Written in full before being renamed into place, so a crash can't leave a truncated entry behind
+    let temporary = path.with_extension("tmp");
+
+    try_throw!(try_throw!(File::create(&temporary)).write_all(&contents));
+    try_throw!(fs::rename(&temporary, &path));
+
+    Ok(())
+}
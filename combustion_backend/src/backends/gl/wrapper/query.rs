@@ -0,0 +1,91 @@
+use super::bindings::types::*;
+use super::bindings::*;
+use super::GLObject;
+
+use super::error::*;
+
+/// Asynchronous query object, currently only used for `GL_TIME_ELAPSED` timer queries
+#[derive(Eq, PartialEq)]
+pub struct GLQuery(GLuint);
+
+impl_simple_globject!(GLQuery, IsQuery);
+
+impl GLQuery {
+    pub fn new() -> GLResult<GLQuery> {
+        let mut query: GLuint = 0;
+
+        unsafe { GenQueries(1, &mut query as *mut _); }
+
+        check_gl_errors!();
+
+        Ok(GLQuery(query))
+    }
+
+    /// Checks if the driver loaded the timer query functions and actually counts elapsed time
+    pub fn timer_supported() -> bool {
+        if !GetQueryObjectui64v::is_loaded() {
+            return false;
+        }
+
+        let mut bits: GLint = 0;
+
+        unsafe { GetQueryiv(TIME_ELAPSED, QUERY_COUNTER_BITS, &mut bits as *mut _); }
+
+        unsafe { GetError() == NO_ERROR && bits > 0 }
+    }
+
+    /// Starts measuring the time taken by all following GL commands. Only one timer query can be active at a time.
+    pub fn begin_timer(&self) -> GLResult<()> {
+        unsafe { BeginQuery(TIME_ELAPSED, self.0); }
+
+        check_gl_errors!();
+
+        Ok(())
+    }
+
+    pub fn end_timer() -> GLResult<()> {
+        unsafe { EndQuery(TIME_ELAPSED); }
+
+        check_gl_errors!();
+
+        Ok(())
+    }
+
+    /// Checks if the result can be read without stalling
+    pub fn is_available(&self) -> GLResult<bool> {
+        let mut available: GLuint = 0;
+
+        unsafe { GetQueryObjectuiv(self.0, QUERY_RESULT_AVAILABLE, &mut available as *mut _); }
+
+        check_gl_errors!();
+
+        Ok(available as GLboolean == TRUE)
+    }
+
+    /// Reads the result of the query, blocking until it's available. For timer queries this is in nanoseconds.
+    pub fn result(&self) -> GLResult<u64> {
+        let mut result: GLuint64 = 0;
+
+        unsafe { GetQueryObjectui64v(self.0, QUERY_RESULT, &mut result as *mut _); }
+
+        check_gl_errors!();
+
+        Ok(result as u64)
+    }
+
+    pub fn delete(&mut self) -> GLResult<()> {
+        if self.is_valid() {
+            unsafe { DeleteQueries(1, &self.0 as *const _); }
+
+            check_gl_errors!();
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for GLQuery {
+    fn drop(&mut self) {
+        self.delete().expect("Could not drop GLQuery")
+    }
+}
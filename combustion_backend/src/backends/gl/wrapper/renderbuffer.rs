@@ -7,9 +7,11 @@ use std::ptr;
 
 use super::error::*;
 use super::shader::*;
+use super::capabilities::GLCapabilities;
 
 //pub enum GLRenderbufferTarget {}
 
+/// Storage for a framebuffer attachment that's rendered to but never sampled, such as a depth-stencil buffer
 #[derive(Eq, PartialEq)]
 pub struct GLRenderbuffer(GLuint);
 
@@ -40,14 +42,40 @@ impl GLRenderbuffer {
         Ok(GLRenderbuffer(buffer))
     }
 
-    pub fn set_storage(&mut self, width: usize, height: usize) -> GLResult<()> {
-        try_rethrow!(self.bind());
+    /// Allocates storage with the given internal format, such as `DEPTH24_STENCIL8` or `STENCIL_INDEX8`,
+    /// replacing any previous storage. Framebuffers it's attached to keep it attached.
+    pub fn set_storage(&mut self, internal_format: GLenum, width: usize, height: usize) -> GLResult<()> {
+        if GLCapabilities::use_direct_state_access() {
+            try_rethrow!(self.check());
 
-        unsafe {
-            RenderbufferStorage(RENDERBUFFER,
-                                DEPTH24_STENCIL8,
-                                width as GLsizei,
-                                height as GLsizei);
+            unsafe { NamedRenderbufferStorage(self.0, internal_format, width as GLsizei, height as GLsizei); }
+        } else {
+            try_rethrow!(self.bind());
+
+            unsafe { RenderbufferStorage(RENDERBUFFER, internal_format, width as GLsizei, height as GLsizei); }
+        }
+
+        check_gl_errors!();
+
+        Ok(())
+    }
+
+    /// Same as `set_storage`, but allocates `samples` samples per pixel for use with multisampled framebuffers
+    pub fn set_storage_multisample(&mut self, samples: usize, internal_format: GLenum, width: usize, height: usize) -> GLResult<()> {
+        if GLCapabilities::use_direct_state_access() {
+            try_rethrow!(self.check());
+
+            unsafe {
+                NamedRenderbufferStorageMultisample(self.0, samples as GLsizei, internal_format,
+                                                    width as GLsizei, height as GLsizei);
+            }
+        } else {
+            try_rethrow!(self.bind());
+
+            unsafe {
+                RenderbufferStorageMultisample(RENDERBUFFER, samples as GLsizei, internal_format,
+                                               width as GLsizei, height as GLsizei);
+            }
         }
 
         check_gl_errors!();
@@ -70,4 +98,4 @@ impl Drop for GLRenderbuffer {
     fn drop(&mut self) {
         self.delete().expect("Could not drop GLRenderbuffer")
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,153 @@
+//! Sampler objects, which hold filtering and wrapping state separately from textures
+
+use super::bindings::types::*;
+use super::bindings::*;
+use super::GLObject;
+
+use super::error::*;
+use super::capabilities::GLCapabilities;
+use super::texture::{GLTextureFilter, GLTextureWrap};
+
+/// Filtering, wrapping and comparison state that overrides the parameters of whichever texture is bound to the same unit.
+///
+/// One sampler can be used with any number of textures, and the same texture can be sampled differently in different passes.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct GLSampler(GLuint);
+
+impl_simple_globject!(GLSampler, IsSampler);
+
+impl GLSampler {
+    pub fn new() -> GLResult<GLSampler> {
+        let mut sampler = 0;
+
+        unsafe { GenSamplers(1, &mut sampler); }
+
+        check_gl_errors!();
+
+        Ok(GLSampler(sampler))
+    }
+
+    /// Binds the sampler to the given texture unit, where it stays until replaced or unbound with `GLSampler::unbind`
+    pub fn bind(&self, unit: usize) -> GLResult<()> {
+        try_rethrow!(self.check());
+
+        unsafe { BindSampler(unit as GLuint, self.0); }
+
+        check_gl_errors!();
+
+        Ok(())
+    }
+
+    /// Removes any sampler from the given texture unit, so textures bound to it use their own parameters again
+    pub fn unbind(unit: usize) -> GLResult<()> {
+        unsafe { BindSampler(unit as GLuint, 0); }
+
+        check_gl_errors!();
+
+        Ok(())
+    }
+
+    fn parameteri(&mut self, name: GLenum, value: GLint) -> GLResult<()> {
+        unsafe { SamplerParameteri(self.0, name, value); }
+
+        check_gl_errors!();
+
+        Ok(())
+    }
+
+    fn parameterf(&mut self, name: GLenum, value: GLfloat) -> GLResult<()> {
+        unsafe { SamplerParameterf(self.0, name, value); }
+
+        check_gl_errors!();
+
+        Ok(())
+    }
+
+    pub fn set_filtering(&mut self, filter: GLTextureFilter, mipmap: Option<GLTextureFilter>) -> GLResult<()> {
+        let (min_filter, mag_filter) = filter.parameters(mipmap);
+
+        try_rethrow!(self.parameteri(TEXTURE_MIN_FILTER, min_filter as GLint));
+        try_rethrow!(self.parameteri(TEXTURE_MAG_FILTER, mag_filter as GLint));
+
+        Ok(())
+    }
+
+    /// Sets the wrap mode for each texture coordinate. Unlike textures, samplers don't know their dimensions, so all three are always set.
+    pub fn set_wrap(&mut self, s: GLTextureWrap, t: GLTextureWrap, r: GLTextureWrap) -> GLResult<()> {
+        try_rethrow!(self.parameteri(TEXTURE_WRAP_S, s as GLint));
+        try_rethrow!(self.parameteri(TEXTURE_WRAP_T, t as GLint));
+        try_rethrow!(self.parameteri(TEXTURE_WRAP_R, r as GLint));
+
+        Ok(())
+    }
+
+    /// Sets the border color used by `GLTextureWrap::ClampToBorder`
+    pub fn set_border_color(&mut self, color: [f32; 4]) -> GLResult<()> {
+        unsafe { SamplerParameterfv(self.0, TEXTURE_BORDER_COLOR, color.as_ptr()); }
+
+        check_gl_errors!();
+
+        Ok(())
+    }
+
+    /// Clamps sampling to the given level-of-detail range, and biases the computed level of detail.
+    pub fn set_lod_range(&mut self, base: f32, max: f32, bias: f32) -> GLResult<()> {
+        try_rethrow!(self.parameterf(TEXTURE_MIN_LOD, base));
+        try_rethrow!(self.parameterf(TEXTURE_MAX_LOD, max));
+        try_rethrow!(self.parameterf(TEXTURE_LOD_BIAS, bias));
+
+        Ok(())
+    }
+
+    pub fn get_max_anisotropy(&self) -> GLResult<f32> {
+        Ok(try_rethrow!(GLCapabilities::current()).max_anisotropy)
+    }
+
+    /// Sets the anisotropic filtering level, clamped between `1.0` and the maximum supported level.
+    ///
+    /// Returns the level that was actually set, which is always `1.0` without anisotropic filtering.
+    pub fn set_anisotropy(&mut self, value: f32) -> GLResult<f32> {
+        let capabilities = try_rethrow!(GLCapabilities::current());
+
+        if !capabilities.anisotropic_filtering {
+            return Ok(1.0);
+        }
+
+        let value = value.max(1.0).min(capabilities.max_anisotropy);
+
+        try_rethrow!(self.parameterf(TEXTURE_MAX_ANISOTROPY_EXT, value));
+
+        Ok(value)
+    }
+
+    /// Enables depth comparison with the given function, as used by `sampler2DShadow`, or disables it with `None`
+    pub fn set_compare(&mut self, func: Option<GLenum>) -> GLResult<()> {
+        match func {
+            Some(func) => {
+                try_rethrow!(self.parameteri(TEXTURE_COMPARE_MODE, COMPARE_REF_TO_TEXTURE as GLint));
+                try_rethrow!(self.parameteri(TEXTURE_COMPARE_FUNC, func as GLint));
+            }
+            None => {
+                try_rethrow!(self.parameteri(TEXTURE_COMPARE_MODE, NONE as GLint));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn delete(&mut self) -> GLResult<()> {
+        if self.is_valid() {
+            unsafe { DeleteSamplers(1, &self.0 as *const GLuint); }
+
+            check_gl_errors!();
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for GLSampler {
+    fn drop(&mut self) {
+        self.delete().expect("Could not drop GLSampler")
+    }
+}
@@ -9,7 +9,10 @@ use std::io::prelude::*;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 
+use enum_primitive::FromPrimitive;
+
 use super::error::*;
+use super::capabilities::GLCapabilities;
 use super::shader_program::*;
 
 /// `GLShader` represents a single shader. It is not a shader program.
@@ -24,16 +27,18 @@ impl From<GLuint> for GLShader {
     }
 }
 
-/// `GLShaderVariant` represents the supported shader types
-#[repr(u32)]
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-pub enum GLShaderVariant {
-    VertexShader = VERTEX_SHADER,
-    FragmentShader = FRAGMENT_SHADER,
-    ComputeShader = COMPUTE_SHADER,
-    GeometryShader = GEOMETRY_SHADER,
-    TessEvaluationShader = TESS_EVALUATION_SHADER,
-    TessControlShader = TESS_CONTROL_SHADER,
+enum_from_primitive! {
+    /// `GLShaderVariant` represents the supported shader types
+    #[repr(u32)]
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub enum GLShaderVariant {
+        VertexShader = VERTEX_SHADER,
+        FragmentShader = FRAGMENT_SHADER,
+        ComputeShader = COMPUTE_SHADER,
+        GeometryShader = GEOMETRY_SHADER,
+        TessEvaluationShader = TESS_EVALUATION_SHADER,
+        TessControlShader = TESS_CONTROL_SHADER,
+    }
 }
 
 /// `GLShaderInfo` represents the types of info that the shader can be queried for
@@ -111,6 +116,7 @@ impl GLShader {
         Ok(())
     }
 
+    /// Compiles the shader, failing with `ShaderCompile` and the compiler's info log if the source has errors
     pub fn compile(&mut self) -> GLResult<()> {
         try_rethrow!(self.check());
 
@@ -121,12 +127,22 @@ impl GLShader {
         let status = try_rethrow!(self.get_info(GLShaderInfo::CompileStatus));
 
         if status != TRUE as GLint {
-            panic!("{}", self.get_string(GLShaderString::InfoLog).unwrap());
+            throw!(GLError::ShaderCompile(try_rethrow!(self.get_string(GLShaderString::InfoLog))));
         }
 
         Ok(())
     }
 
+    /// The stage this shader was created for
+    pub fn variant(&self) -> GLResult<GLShaderVariant> {
+        let ty = try_rethrow!(self.get_info(GLShaderInfo::ShaderType));
+
+        match GLShaderVariant::from_u32(ty as u32) {
+            Some(variant) => Ok(variant),
+            None => throw!(GLError::InvalidEnum),
+        }
+    }
+
     /// Gets a single field from the shader info
     pub fn get_info(&self, field: GLShaderInfo) -> GLResult<GLint> {
         try_rethrow!(self.check());
@@ -206,3 +222,40 @@ impl Drop for GLShader {
         self.delete().expect("Could not drop GLShader")
     }
 }
+
+/// Sets the number of vertices per patch for `PATCHES` draws, which tessellation control shaders get as input.
+///
+/// Fails with `Unsupported` without tessellation shaders, and with `InvalidValue` outside of `1..max_patch_vertices`.
+pub fn set_patch_vertices(vertices: usize) -> GLResult<()> {
+    let capabilities = try_rethrow!(GLCapabilities::current());
+
+    if !capabilities.tessellation_shaders {
+        throw!(GLError::Unsupported);
+    }
+
+    if vertices == 0 || vertices > capabilities.max_patch_vertices {
+        throw!(GLError::InvalidValue);
+    }
+
+    unsafe { PatchParameteri(PATCH_VERTICES, vertices as GLint); }
+
+    check_gl_errors!();
+
+    Ok(())
+}
+
+/// Sets the tessellation levels used by programs that have a tessellation evaluation shader but no control shader
+pub fn set_default_tess_levels(outer: [f32; 4], inner: [f32; 2]) -> GLResult<()> {
+    if !try_rethrow!(GLCapabilities::current()).tessellation_shaders {
+        throw!(GLError::Unsupported);
+    }
+
+    unsafe {
+        PatchParameterfv(PATCH_DEFAULT_OUTER_LEVEL, outer.as_ptr());
+        PatchParameterfv(PATCH_DEFAULT_INNER_LEVEL, inner.as_ptr());
+    }
+
+    check_gl_errors!();
+
+    Ok(())
+}
@@ -4,18 +4,35 @@ use super::GLObject;
 
 use std::mem;
 use std::ptr;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
 use std::ffi::CString;
 
+use fnv::FnvHashMap;
+
 use super::error::*;
+use super::debug::label_object;
+use super::state::GLStateCache;
 use super::shader::*;
 use super::uniform::GLUniform;
+use super::capabilities::GLCapabilities;
+use super::program_cache::*;
 
 /// `GLShaderProgram` represents a whole shader program, linked with many shaders
-#[derive(Eq, PartialEq)]
-pub struct GLShaderProgram(GLuint);
+pub struct GLShaderProgram(GLuint, RefCell<FnvHashMap<String, GLint>>);
 
 impl_simple_globject!(GLShaderProgram, IsProgram);
 
+impl PartialEq for GLShaderProgram {
+    fn eq(&self, other: &GLShaderProgram) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for GLShaderProgram {}
+
 #[repr(u32)]
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum GLProgramInfo {
@@ -27,44 +44,158 @@ pub enum GLProgramInfo {
     ActiveAttributes = ACTIVE_ATTRIBUTES,
     ActiveAttributeMaxLength = ACTIVE_ATTRIBUTE_MAX_LENGTH,
     ActiveUniforms = ACTIVE_UNIFORMS,
-    ActiveUniformMaxLength = ACTIVE_UNIFORM_MAX_LENGTH
+    ActiveUniformMaxLength = ACTIVE_UNIFORM_MAX_LENGTH,
+    ProgramBinaryLength = PROGRAM_BINARY_LENGTH
 }
 
 pub enum GLProgramString {
     InfoLog
 }
 
+/// Builds a shader program from compiled shaders or from sources.
+///
+/// Programs built only from sources are cached in the program cache directory, if one is set,
+/// so later runs can load the linked binary instead of compiling the sources again.
 #[derive(Eq, PartialEq)]
-pub struct GLShaderProgramBuilder(GLShaderProgram);
+pub struct GLShaderProgramBuilder {
+    program: GLShaderProgram,
+    /// Sources that are only compiled if the program isn't cached
+    sources: Vec<(GLShaderVariant, String)>,
+    /// Shaders compiled elsewhere can't be hashed, so they disable caching
+    cacheable: bool,
+    /// Every attached stage, checked by `validate_stages` before linking
+    stages: Vec<GLShaderVariant>,
+}
 
 impl GLShaderProgramBuilder {
     #[inline(always)]
     pub fn new() -> GLResult<GLShaderProgramBuilder> {
-        Ok(GLShaderProgramBuilder(try_rethrow!(GLShaderProgram::new())))
+        Ok(GLShaderProgramBuilder {
+            program: try_rethrow!(GLShaderProgram::new()),
+            sources: Vec::new(),
+            cacheable: true,
+            stages: Vec::new(),
+        })
     }
 
+    /// Attaches an already compiled shader. Programs with any of these are never cached.
     #[inline(always)]
     pub fn attach_shader(mut self, shader: GLShader) -> GLResult<GLShaderProgramBuilder> {
-        try_rethrow!(self.0.attach_shader(shader));
+        self.stages.push(try_rethrow!(shader.variant()));
+
+        try_rethrow!(self.program.attach_shader(shader));
+
+        self.cacheable = false;
 
         Ok(self)
     }
 
+    /// Adds a shader source, which is compiled when linking unless the program can be loaded from the cache
     #[inline(always)]
+    pub fn attach_source(mut self, source: String, variant: GLShaderVariant) -> GLResult<GLShaderProgramBuilder> {
+        self.stages.push(variant);
+        self.sources.push((variant, source));
+
+        Ok(self)
+    }
+
+    /// Same as `attach_source`, reading the source from a file
+    pub fn attach_file<P: AsRef<Path>>(self, path: P, variant: GLShaderVariant) -> GLResult<GLShaderProgramBuilder> {
+        let mut source = String::new();
+
+        try_throw!(try_throw!(File::open(path)).read_to_string(&mut source));
+
+        self.attach_source(source, variant)
+    }
+
+    /// Links the program, loading it from the program cache first if possible and adding it to the cache otherwise.
+    ///
+    /// Fails with `InvalidShaderStages` if the attached stages can't be linked together,
+    /// and with `ShaderCompile` or `ProgramLink` if the driver rejects the shaders.
     pub fn link(mut self) -> GLResult<GLShaderProgramBuilder> {
-        try_rethrow!(self.0.link());
+        try_rethrow!(validate_stages(&self.stages));
+
+        let key = if self.cacheable && !self.sources.is_empty() && try_rethrow!(is_program_cache_enabled()) {
+            Some(try_rethrow!(program_cache_key(&self.sources)))
+        } else { None };
+
+        if let Some(key) = key {
+            if try_rethrow!(load_cached_program(&mut self.program, key)) {
+                self.sources.clear();
+
+                return Ok(self);
+            }
+
+            try_rethrow!(self.program.set_binary_retrievable(true));
+        }
+
+        for (variant, source) in self.sources.drain(..) {
+            try_rethrow!(self.program.attach_shader(try_rethrow!(GLShader::from_source(source, variant))));
+        }
+
+        try_rethrow!(self.program.link());
+
+        if let Some(key) = key {
+            // The program works regardless, it just has to be compiled again next time
+            if let Err(err) = save_cached_program(&self.program, key) {
+                warn!("Could not cache shader program: {}", err);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Binds the named uniform block to a binding point. Must be called after linking.
+    #[inline(always)]
+    pub fn uniform_block(self, name: &str, binding: GLuint) -> GLResult<GLShaderProgramBuilder> {
+        try_rethrow!(self.program.bind_uniform_block(name, binding));
 
         Ok(self)
     }
 
     #[inline(always)]
-    pub fn finish(self) -> GLShaderProgram { self.0 }
+    pub fn finish(self) -> GLShaderProgram { self.program }
 }
 
+/// Checks that the shader stages can be linked into one program, before the driver fails with a less useful error.
+///
+/// Returns `InvalidShaderStages` describing the problem, or `Unsupported` for stages the driver doesn't have.
+pub fn validate_stages(stages: &[GLShaderVariant]) -> GLResult<()> {
+    let has = |variant: GLShaderVariant| stages.contains(&variant);
+
+    if stages.is_empty() {
+        throw!(GLError::InvalidShaderStages("Shader program has no shaders"));
+    }
+
+    if has(GLShaderVariant::ComputeShader) {
+        if stages.iter().any(|&variant| variant != GLShaderVariant::ComputeShader) {
+            throw!(GLError::InvalidShaderStages("Compute shaders can't be linked with other shader stages"));
+        }
+    } else if !has(GLShaderVariant::VertexShader) {
+        throw!(GLError::InvalidShaderStages("Shader program has no vertex shader"));
+    }
+
+    if has(GLShaderVariant::TessControlShader) && !has(GLShaderVariant::TessEvaluationShader) {
+        throw!(GLError::InvalidShaderStages("Tessellation control shader without a tessellation evaluation shader"));
+    }
+
+    let tessellation = has(GLShaderVariant::TessControlShader) || has(GLShaderVariant::TessEvaluationShader);
+
+    if tessellation || has(GLShaderVariant::ComputeShader) {
+        let capabilities = try_rethrow!(GLCapabilities::current());
+
+        if (tessellation && !capabilities.tessellation_shaders) ||
+            (has(GLShaderVariant::ComputeShader) && !capabilities.compute_shaders) {
+            throw!(GLError::Unsupported);
+        }
+    }
+
+    Ok(())
+}
 
 impl GLShaderProgram {
     pub fn new() -> GLResult<GLShaderProgram> {
-        let program: GLShaderProgram = GLShaderProgram(unsafe { CreateProgram() });
+        let program: GLShaderProgram = GLShaderProgram(unsafe { CreateProgram() }, RefCell::new(FnvHashMap::default()));
 
         check_gl_errors!();
 
@@ -74,7 +205,7 @@ impl GLShaderProgram {
     pub fn use_program(&self) -> GLResult<()> {
         try_rethrow!(self.check());
 
-        unsafe { UseProgram(self.0); }
+        GLStateCache::use_program(self.0);
 
         check_gl_errors!();
 
@@ -92,6 +223,7 @@ impl GLShaderProgram {
         Ok(())
     }
 
+    /// Links the attached shaders, failing with `ProgramLink` and the linker's info log if they can't be linked
     pub fn link(&mut self) -> GLResult<()> {
         try_rethrow!(self.check());
 
@@ -102,12 +234,125 @@ impl GLShaderProgram {
         let status = try_rethrow!(self.get_info(GLProgramInfo::LinkStatus));
 
         if status != TRUE as GLint {
-            panic!("{}", self.get_string(GLProgramString::InfoLog).unwrap());
+            throw!(GLError::ProgramLink(try_rethrow!(self.get_string(GLProgramString::InfoLog))));
+        }
+
+        self.cache_active_uniforms()
+    }
+
+    /// Hints that the binary will be retrieved with `get_binary`, which some drivers require. Must be set before linking.
+    pub fn set_binary_retrievable(&mut self, retrievable: bool) -> GLResult<()> {
+        try_rethrow!(self.check());
+
+        if ProgramParameteri::is_loaded() {
+            let value = if retrievable { TRUE } else { FALSE };
+
+            unsafe { ProgramParameteri(self.0, PROGRAM_BINARY_RETRIEVABLE_HINT, value as GLint); }
+
+            check_gl_errors!();
+        }
+
+        Ok(())
+    }
+
+    /// Retrieves the linked program as a driver-specific binary and its format
+    pub fn get_binary(&self) -> GLResult<(GLenum, Vec<u8>)> {
+        let len = try_rethrow!(self.get_info(GLProgramInfo::ProgramBinaryLength));
+
+        if len <= 0 {
+            throw!(GLError::Unsupported);
+        }
+
+        let mut buffer: Vec<u8> = vec![0; len as usize];
+        let mut written: GLsizei = 0;
+        let mut format: GLenum = 0;
+
+        unsafe {
+            GetProgramBinary(self.0, len, &mut written, &mut format, buffer.as_mut_ptr() as *mut _);
+        }
+
+        check_gl_errors!();
+
+        buffer.truncate(written as usize);
+
+        Ok((format, buffer))
+    }
+
+    /// Replaces the program with a binary from `get_binary`.
+    ///
+    /// Returns `false` if the driver rejected the binary, such as after a driver update,
+    /// in which case the program has to be linked from its shaders instead.
+    pub fn load_binary(&mut self, format: GLenum, binary: &[u8]) -> GLResult<bool> {
+        try_rethrow!(self.check());
+
+        unsafe { ProgramBinary(self.0, format, binary.as_ptr() as *const _, binary.len() as GLsizei); }
+
+        // Unsupported formats raise an error as well as failing the link, which is all that matters here
+        GLError::drain();
+
+        let linked = try_rethrow!(self.get_info(GLProgramInfo::LinkStatus)) == TRUE as GLint;
+
+        if linked {
+            try_rethrow!(self.cache_active_uniforms());
+        }
+
+        Ok(linked)
+    }
+
+    /// Replaces the cached uniform locations with those of every active uniform, so later lookups don't go through the driver
+    fn cache_active_uniforms(&mut self) -> GLResult<()> {
+        let count = try_rethrow!(self.get_info(GLProgramInfo::ActiveUniforms));
+        let max_len = try_rethrow!(self.get_info(GLProgramInfo::ActiveUniformMaxLength));
+
+        let mut locations = self.1.borrow_mut();
+
+        locations.clear();
+
+        let mut buffer: Vec<u8> = vec![0; max_len.max(1) as usize];
+
+        for index in 0..count.max(0) as GLuint {
+            let mut len: GLsizei = 0;
+            let mut size: GLint = 0;
+            let mut ty: GLenum = 0;
+
+            unsafe {
+                GetActiveUniform(self.0, index, buffer.len() as GLsizei, &mut len, &mut size, &mut ty, buffer.as_mut_ptr() as *mut GLchar);
+            }
+
+            check_gl_errors!();
+
+            let name = try_throw!(String::from_utf8(buffer[..len as usize].to_vec()));
+            let location = try_rethrow!(self.query_uniform_location(&name));
+
+            locations.insert(name, location);
         }
 
         Ok(())
     }
 
+    fn query_uniform_location(&self, name: &str) -> GLResult<GLint> {
+        let name = try_throw!(CString::new(name));
+
+        let location = unsafe { GetUniformLocation(self.0, name.as_ptr() as *const GLchar) };
+
+        check_gl_errors!();
+
+        Ok(location)
+    }
+
+    /// Location of the named uniform, or `-1` if it isn't active. Looked up through the driver only the first time.
+    pub fn get_uniform_location(&self, name: &str) -> GLResult<GLint> {
+        if let Some(location) = self.1.borrow().get(name) {
+            return Ok(*location);
+        }
+
+        let location = try_rethrow!(self.query_uniform_location(name));
+
+        self.1.borrow_mut().insert(name.to_string(), location);
+
+        Ok(location)
+    }
+
     pub fn get_info(&self, field: GLProgramInfo) -> GLResult<GLint> {
         try_rethrow!(self.check());
 
@@ -162,14 +407,51 @@ impl GLShaderProgram {
         Ok(buffer)
     }
 
+    /// Gets the named uniform, failing with `UniformNotFound` if the program has no such active uniform
     pub fn get_uniform(&self, name: &str) -> GLResult<GLUniform> {
+        let location = try_rethrow!(self.get_uniform_location(name));
+
+        if location == -1 {
+            throw!(GLError::UniformNotFound(name.to_string()));
+        }
+
+        Ok(GLUniform(location))
+    }
+
+    /// Same as `get_uniform`, but a missing uniform gives a handle that ignores everything set on it.
+    ///
+    /// Useful for uniforms the compiler may optimize out, such as those only used by debug code.
+    pub fn get_uniform_optional(&self, name: &str) -> GLResult<GLUniform> {
+        Ok(GLUniform(try_rethrow!(self.get_uniform_location(name))))
+    }
+
+    /// Binds the named uniform block to a binding point.
+    ///
+    /// Returns `false` if the program has no active block by that name, which is not an error,
+    /// since unused blocks are optimized out.
+    pub fn bind_uniform_block(&self, name: &str, binding: GLuint) -> GLResult<bool> {
+        try_rethrow!(self.check());
+
         let name = try_throw!(CString::new(name));
 
-        let id = unsafe { GetUniformLocation(self.0, name.as_ptr() as *const GLchar) };
+        let index = unsafe { GetUniformBlockIndex(self.0, name.as_ptr() as *const GLchar) };
+
+        check_gl_errors!();
+
+        if index == INVALID_INDEX {
+            return Ok(false);
+        }
+
+        unsafe { UniformBlockBinding(self.0, index, binding); }
 
         check_gl_errors!();
 
-        Ok(GLUniform(id))
+        Ok(true)
+    }
+
+    /// Names the program in debuggers and debug messages
+    pub fn label(&self, label: &str) -> GLResult<()> {
+        label_object(PROGRAM, self.0, label)
     }
 
     /// Deletes the shader program
@@ -179,6 +461,10 @@ impl GLShaderProgram {
         if self.is_valid() {
             unsafe { DeleteProgram(self.0); }
 
+            GLStateCache::forget_program(self.0);
+
+            self.1.borrow_mut().clear();
+
             check_gl_errors!();
 
             //If the current program still exists, at least check if it is queued for deletion...
@@ -0,0 +1,208 @@
+//! Shadow copy of frequently changed OpenGL state, so redundant state changes can be skipped
+//!
+//! The cache only knows about changes made through it, which includes the binding methods of the wrappers.
+//! Anything that changes the same state with raw GL calls must call `GLStateCache::invalidate` afterwards.
+
+use super::bindings::types::*;
+use super::bindings::*;
+
+use std::cell::RefCell;
+
+use fnv::FnvHashMap;
+
+/// Number of state changes issued to OpenGL, and skipped for already being in effect
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GLStateCounters {
+    pub issued: u64,
+    pub skipped: u64,
+}
+
+/// The last known OpenGL state of the current thread's context. `None` and missing entries mean the state is unknown.
+#[derive(Debug, Default)]
+pub struct GLStateCache {
+    program: Option<GLuint>,
+    draw_framebuffer: Option<GLuint>,
+    read_framebuffer: Option<GLuint>,
+    active_unit: Option<GLuint>,
+    /// Texture bound to each target of each unit
+    textures: FnvHashMap<(GLuint, GLenum), GLuint>,
+    capabilities: FnvHashMap<GLenum, bool>,
+    counters: GLStateCounters,
+}
+
+thread_local! {
+    static STATE_CACHE: RefCell<GLStateCache> = RefCell::new(GLStateCache::default());
+}
+
+impl GLStateCache {
+    #[inline]
+    fn with<F, R>(f: F) -> R where F: FnOnce(&mut GLStateCache) -> R {
+        STATE_CACHE.with(|cache| f(&mut *cache.borrow_mut()))
+    }
+
+    /// Counts the change, returning whether it has to be issued
+    #[inline]
+    fn record(&mut self, changed: bool) -> bool {
+        if changed {
+            self.counters.issued += 1;
+        } else {
+            self.counters.skipped += 1;
+        }
+
+        changed
+    }
+
+    /// Forgets all cached state, so every following change is issued. Counters are kept.
+    ///
+    /// Must be called after anything outside of the cache changes the tracked state, and when switching contexts on a thread.
+    pub fn invalidate() {
+        GLStateCache::with(|cache| {
+            let counters = cache.counters;
+
+            *cache = GLStateCache { counters: counters, ..GLStateCache::default() };
+        })
+    }
+
+    /// Counters since they were last taken
+    pub fn counters() -> GLStateCounters {
+        GLStateCache::with(|cache| cache.counters)
+    }
+
+    /// Returns the counters and resets them, such as once per frame
+    pub fn take_counters() -> GLStateCounters {
+        GLStateCache::with(|cache| ::std::mem::replace(&mut cache.counters, GLStateCounters::default()))
+    }
+
+    pub fn use_program(program: GLuint) {
+        let changed = GLStateCache::with(|cache| {
+            let changed = cache.program != Some(program);
+
+            cache.program = Some(program);
+            cache.record(changed)
+        });
+
+        if changed {
+            unsafe { UseProgram(program); }
+        }
+    }
+
+    /// Binds a framebuffer to `FRAMEBUFFER`, `DRAW_FRAMEBUFFER` or `READ_FRAMEBUFFER`
+    pub fn bind_framebuffer(target: GLenum, framebuffer: GLuint) {
+        let changed = GLStateCache::with(|cache| {
+            let (draw, read) = match target {
+                DRAW_FRAMEBUFFER => (true, false),
+                READ_FRAMEBUFFER => (false, true),
+                _ => (true, true),
+            };
+
+            let changed = (draw && cache.draw_framebuffer != Some(framebuffer)) ||
+                          (read && cache.read_framebuffer != Some(framebuffer));
+
+            if draw {
+                cache.draw_framebuffer = Some(framebuffer);
+            }
+
+            if read {
+                cache.read_framebuffer = Some(framebuffer);
+            }
+
+            cache.record(changed)
+        });
+
+        if changed {
+            unsafe { BindFramebuffer(target, framebuffer); }
+        }
+    }
+
+    /// Selects the texture unit that following texture bindings apply to
+    pub fn active_texture(unit: usize) {
+        let unit = unit as GLuint;
+
+        let changed = GLStateCache::with(|cache| {
+            let changed = cache.active_unit != Some(unit);
+
+            cache.active_unit = Some(unit);
+            cache.record(changed)
+        });
+
+        if changed {
+            unsafe { ActiveTexture(TEXTURE0 + unit); }
+        }
+    }
+
+    /// Binds a texture to the given target of the active texture unit
+    pub fn bind_texture(target: GLenum, texture: GLuint) {
+        let changed = GLStateCache::with(|cache| {
+            match cache.active_unit {
+                Some(unit) => {
+                    let changed = cache.textures.get(&(unit, target)) != Some(&texture);
+
+                    cache.textures.insert((unit, target), texture);
+                    cache.record(changed)
+                }
+                // Without knowing the unit, there's nothing to compare against or remember
+                None => cache.record(true),
+            }
+        });
+
+        if changed {
+            unsafe { BindTexture(target, texture); }
+        }
+    }
+
+    pub fn enable(capability: GLenum) {
+        GLStateCache::set_capability(capability, true)
+    }
+
+    pub fn disable(capability: GLenum) {
+        GLStateCache::set_capability(capability, false)
+    }
+
+    /// Enables or disables a capability such as `DEPTH_TEST`, `BLEND` or `CULL_FACE`
+    pub fn set_capability(capability: GLenum, enabled: bool) {
+        let changed = GLStateCache::with(|cache| {
+            let changed = cache.capabilities.insert(capability, enabled) != Some(enabled);
+
+            cache.record(changed)
+        });
+
+        if changed {
+            unsafe {
+                if enabled { Enable(capability); } else { Disable(capability); }
+            }
+        }
+    }
+
+    /// Forgets a deleted program, since OpenGL may reuse its name
+    pub fn forget_program(program: GLuint) {
+        GLStateCache::with(|cache| {
+            if cache.program == Some(program) {
+                cache.program = None;
+            }
+        })
+    }
+
+    /// Forgets a deleted framebuffer. Deleting a bound framebuffer binds the default one in its place.
+    pub fn forget_framebuffer(framebuffer: GLuint) {
+        GLStateCache::with(|cache| {
+            if cache.draw_framebuffer == Some(framebuffer) {
+                cache.draw_framebuffer = Some(0);
+            }
+
+            if cache.read_framebuffer == Some(framebuffer) {
+                cache.read_framebuffer = Some(0);
+            }
+        })
+    }
+
+    /// Forgets a deleted texture. Deleting a bound texture binds zero in its place on every unit.
+    pub fn forget_texture(texture: GLuint) {
+        GLStateCache::with(|cache| {
+            for bound in cache.textures.values_mut() {
+                if *bound == texture {
+                    *bound = 0;
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,166 @@
+use super::bindings::types::*;
+use super::bindings::*;
+
+use std::ptr;
+use std::cell::Cell;
+use std::time::Duration;
+
+use super::error::*;
+
+/// Sync object that becomes signaled once the GPU has finished every command issued before it
+pub struct GLFence {
+    sync: GLsync,
+    /// Whether the commands before the fence were flushed, without which it might never become signaled
+    flushed: Cell<bool>,
+}
+
+impl GLFence {
+    /// Inserts a new fence after all commands issued so far
+    pub fn insert() -> GLResult<GLFence> {
+        let sync = unsafe { FenceSync(SYNC_GPU_COMMANDS_COMPLETE, 0) };
+
+        check_gl_errors!();
+
+        if sync.is_null() {
+            throw!(GLError::InvalidOperation);
+        }
+
+        Ok(GLFence { sync: sync, flushed: Cell::new(false) })
+    }
+
+    /// Checks if the fence was reached without waiting, flushing the commands before it the first time
+    pub fn is_signaled(&self) -> GLResult<bool> {
+        if !self.flushed.get() {
+            unsafe { Flush(); }
+
+            self.flushed.set(true);
+        }
+
+        let mut status: GLint = 0;
+
+        unsafe { GetSynciv(self.sync, SYNC_STATUS, 1, ptr::null_mut(), &mut status as *mut _); }
+
+        check_gl_errors!();
+
+        Ok(status as GLenum == SIGNALED)
+    }
+
+    /// Blocks until the fence is reached or `timeout` passes, returning whether it was reached.
+    ///
+    /// A zero timeout only checks the fence, like `is_signaled`.
+    pub fn wait(&self, timeout: Duration) -> GLResult<bool> {
+        let flags = if self.flushed.get() { 0 } else { SYNC_FLUSH_COMMANDS_BIT };
+
+        let nanoseconds = timeout.as_secs()
+                                 .saturating_mul(1_000_000_000)
+                                 .saturating_add(timeout.subsec_nanos() as u64);
+
+        let result = unsafe { ClientWaitSync(self.sync, flags, nanoseconds as GLuint64) };
+
+        self.flushed.set(true);
+
+        match result {
+            ALREADY_SIGNALED | CONDITION_SATISFIED => Ok(true),
+            TIMEOUT_EXPIRED => Ok(false),
+            _ => {
+                check_gl_errors!();
+
+                throw!(GLError::InvalidOperation)
+            }
+        }
+    }
+
+    pub fn delete(&mut self) -> GLResult<()> {
+        if !self.sync.is_null() {
+            unsafe { DeleteSync(self.sync); }
+
+            self.sync = ptr::null();
+
+            check_gl_errors!();
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for GLFence {
+    fn drop(&mut self) {
+        self.delete().expect("Could not drop GLFence")
+    }
+}
+
+/// Index of a fence in a `GLFencePool`
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct GLFenceId(usize);
+
+/// Fences identified by index, for code that keeps many of them in flight, such as one per frame.
+///
+/// Sync objects can only be signaled once, so the pool recycles the slots of released fences rather than the fences themselves.
+#[derive(Default)]
+pub struct GLFencePool {
+    fences: Vec<Option<GLFence>>,
+    free: Vec<usize>,
+}
+
+impl GLFencePool {
+    pub fn new() -> GLFencePool {
+        GLFencePool::default()
+    }
+
+    /// Inserts a new fence after all commands issued so far
+    pub fn insert(&mut self) -> GLResult<GLFenceId> {
+        let fence = try_rethrow!(GLFence::insert());
+
+        Ok(GLFenceId(match self.free.pop() {
+            Some(index) => {
+                self.fences[index] = Some(fence);
+                index
+            }
+            None => {
+                self.fences.push(Some(fence));
+                self.fences.len() - 1
+            }
+        }))
+    }
+
+    /// The fence with the given ID, or `InvalidValue` if it was released
+    pub fn get(&self, id: GLFenceId) -> GLResult<&GLFence> {
+        match self.fences.get(id.0) {
+            Some(&Some(ref fence)) => Ok(fence),
+            _ => throw!(GLError::InvalidValue),
+        }
+    }
+
+    #[inline]
+    pub fn is_signaled(&self, id: GLFenceId) -> GLResult<bool> {
+        try_rethrow!(self.get(id)).is_signaled()
+    }
+
+    #[inline]
+    pub fn wait(&self, id: GLFenceId, timeout: Duration) -> GLResult<bool> {
+        try_rethrow!(self.get(id)).wait(timeout)
+    }
+
+    /// Deletes the fence and frees its slot for the next one. Its ID must not be used afterwards.
+    pub fn release(&mut self, id: GLFenceId) -> GLResult<()> {
+        match self.fences.get_mut(id.0).and_then(Option::take) {
+            Some(mut fence) => {
+                try_rethrow!(fence.delete());
+
+                self.free.push(id.0);
+
+                Ok(())
+            }
+            None => throw!(GLError::InvalidValue),
+        }
+    }
+
+    /// Number of fences that haven't been released
+    pub fn len(&self) -> usize {
+        self.fences.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,216 @@
+//! Uploading block-compressed texture data, validated against the format's block size
+
+use super::super::bindings::types::*;
+use super::super::bindings::*;
+
+use super::super::error::*;
+use super::super::capabilities::GLCapabilities;
+
+use super::super::GLBindable;
+use super::{GLGenericTexture, GLTextureVariant, GLTextureKind, TextureParameters};
+
+/// Extension families of compressed formats, which drivers support separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GLCompressionFamily {
+    /// DXT1, DXT3 and DXT5, also known as BC1 through BC3
+    S3tc,
+    /// BC4 and BC5
+    Rgtc,
+    /// BC6H and BC7
+    Bptc,
+    Astc,
+}
+
+impl GLCompressionFamily {
+    /// The family of a compressed internal format, or `None` if it isn't one of the block-compressed formats
+    pub fn of(internal_format: GLenum) -> Option<GLCompressionFamily> {
+        Some(match internal_format {
+            COMPRESSED_RGB_S3TC_DXT1_EXT | COMPRESSED_RGBA_S3TC_DXT1_EXT |
+            COMPRESSED_RGBA_S3TC_DXT3_EXT | COMPRESSED_RGBA_S3TC_DXT5_EXT |
+            COMPRESSED_SRGB_S3TC_DXT1_EXT | COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT |
+            COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT | COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT => GLCompressionFamily::S3tc,
+
+            COMPRESSED_RED_RGTC1 | COMPRESSED_SIGNED_RED_RGTC1 |
+            COMPRESSED_RG_RGTC2 | COMPRESSED_SIGNED_RG_RGTC2 => GLCompressionFamily::Rgtc,
+
+            COMPRESSED_RGBA_BPTC_UNORM | COMPRESSED_SRGB_ALPHA_BPTC_UNORM |
+            COMPRESSED_RGB_BPTC_SIGNED_FLOAT | COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT => GLCompressionFamily::Bptc,
+
+            _ if astc_block(internal_format).is_some() => GLCompressionFamily::Astc,
+
+            _ => return None,
+        })
+    }
+
+    pub fn is_supported(self, capabilities: &GLCapabilities) -> bool {
+        match self {
+            GLCompressionFamily::S3tc => capabilities.s3tc_compression,
+            GLCompressionFamily::Rgtc => capabilities.rgtc_compression,
+            GLCompressionFamily::Bptc => capabilities.bptc_compression,
+            GLCompressionFamily::Astc => capabilities.astc_compression,
+        }
+    }
+}
+
+/// Width and height of ASTC blocks, for both the linear and sRGB formats
+fn astc_block(internal_format: GLenum) -> Option<(usize, usize)> {
+    Some(match internal_format {
+        COMPRESSED_RGBA_ASTC_4x4_KHR | COMPRESSED_SRGB8_ALPHA8_ASTC_4x4_KHR => (4, 4),
+        COMPRESSED_RGBA_ASTC_5x4_KHR | COMPRESSED_SRGB8_ALPHA8_ASTC_5x4_KHR => (5, 4),
+        COMPRESSED_RGBA_ASTC_5x5_KHR | COMPRESSED_SRGB8_ALPHA8_ASTC_5x5_KHR => (5, 5),
+        COMPRESSED_RGBA_ASTC_6x5_KHR | COMPRESSED_SRGB8_ALPHA8_ASTC_6x5_KHR => (6, 5),
+        COMPRESSED_RGBA_ASTC_6x6_KHR | COMPRESSED_SRGB8_ALPHA8_ASTC_6x6_KHR => (6, 6),
+        COMPRESSED_RGBA_ASTC_8x5_KHR | COMPRESSED_SRGB8_ALPHA8_ASTC_8x5_KHR => (8, 5),
+        COMPRESSED_RGBA_ASTC_8x6_KHR | COMPRESSED_SRGB8_ALPHA8_ASTC_8x6_KHR => (8, 6),
+        COMPRESSED_RGBA_ASTC_8x8_KHR | COMPRESSED_SRGB8_ALPHA8_ASTC_8x8_KHR => (8, 8),
+        COMPRESSED_RGBA_ASTC_10x5_KHR | COMPRESSED_SRGB8_ALPHA8_ASTC_10x5_KHR => (10, 5),
+        COMPRESSED_RGBA_ASTC_10x6_KHR | COMPRESSED_SRGB8_ALPHA8_ASTC_10x6_KHR => (10, 6),
+        COMPRESSED_RGBA_ASTC_10x8_KHR | COMPRESSED_SRGB8_ALPHA8_ASTC_10x8_KHR => (10, 8),
+        COMPRESSED_RGBA_ASTC_10x10_KHR | COMPRESSED_SRGB8_ALPHA8_ASTC_10x10_KHR => (10, 10),
+        COMPRESSED_RGBA_ASTC_12x10_KHR | COMPRESSED_SRGB8_ALPHA8_ASTC_12x10_KHR => (12, 10),
+        COMPRESSED_RGBA_ASTC_12x12_KHR | COMPRESSED_SRGB8_ALPHA8_ASTC_12x12_KHR => (12, 12),
+        _ => return None,
+    })
+}
+
+/// Size of the blocks a compressed format is stored in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GLCompressedBlock {
+    pub bytes: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl GLCompressedBlock {
+    /// The block size of a compressed internal format, or `None` if it isn't one of the block-compressed formats
+    pub fn of(internal_format: GLenum) -> Option<GLCompressedBlock> {
+        let (bytes, width, height) = match internal_format {
+            COMPRESSED_RGB_S3TC_DXT1_EXT | COMPRESSED_RGBA_S3TC_DXT1_EXT |
+            COMPRESSED_SRGB_S3TC_DXT1_EXT | COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT |
+            COMPRESSED_RED_RGTC1 | COMPRESSED_SIGNED_RED_RGTC1 => (8, 4, 4),
+            _ => match GLCompressionFamily::of(internal_format) {
+                Some(GLCompressionFamily::Astc) => {
+                    let (width, height) = astc_block(internal_format).unwrap();
+
+                    (16, width, height)
+                }
+                Some(_) => (16, 4, 4),
+                None => return None,
+            }
+        };
+
+        Some(GLCompressedBlock { bytes: bytes, width: width, height: height })
+    }
+
+    /// Bytes an image of the given size takes up, rounded up to whole blocks
+    pub fn level_size(&self, width: usize, height: usize) -> usize {
+        let blocks_x = (width + self.width - 1) / self.width;
+        let blocks_y = (height + self.height - 1) / self.height;
+
+        blocks_x * blocks_y * self.bytes
+    }
+}
+
+/// One mipmap level of compressed data, for `upload_compressed_mips`
+#[derive(Debug, Clone, Copy)]
+pub struct GLCompressedLevel<'a> {
+    pub width: usize,
+    pub height: usize,
+    pub data: &'a [u8],
+}
+
+/// Checks the driver supports the format and `data` is exactly the size the format needs
+fn check_compressed(internal_format: GLenum, width: usize, height: usize, data: &[u8]) -> GLResult<()> {
+    let (family, block) = match (GLCompressionFamily::of(internal_format), GLCompressedBlock::of(internal_format)) {
+        (Some(family), Some(block)) => (family, block),
+        _ => throw!(GLError::InvalidEnum),
+    };
+
+    if !family.is_supported(&*try_rethrow!(GLCapabilities::current())) {
+        throw!(GLError::UnsupportedFormat(internal_format));
+    }
+
+    let expected = block.level_size(width, height);
+
+    if data.len() != expected {
+        error!("{}x{} compressed image is {} bytes, but format 0x{:04X} needs {}",
+               width, height, data.len(), internal_format, expected);
+
+        throw!(GLError::InvalidValue);
+    }
+
+    Ok(())
+}
+
+unsafe fn compressed_image(level: usize, internal_format: GLenum, width: usize, height: usize, data: &[u8]) {
+    CompressedTexImage2D(TEXTURE_2D, level as GLint, internal_format, width as GLsizei, height as GLsizei, 0,
+                         data.len() as GLsizei, data.as_ptr() as *const _);
+}
+
+/// Replaces mipmap level `level` of a 2D texture with compressed data
+pub fn upload_compressed<T: GLGenericTexture + ?Sized>(texture: &mut T, level: usize, internal_format: GLenum,
+                                                       width: usize, height: usize, data: &[u8]) -> GLResult<()> {
+    if texture.kind() != GLTextureKind::Texture2D {
+        throw!(GLError::Unsupported);
+    }
+
+    try_rethrow!(check_compressed(internal_format, width, height, data));
+
+    try_rethrow!(texture.bind());
+
+    unsafe { compressed_image(level, internal_format, width, height, data); }
+
+    check_gl_errors!("uploading level {} of a {}x{} texture with format 0x{:04X}", level, width, height, internal_format);
+
+    texture.format = None;
+    texture.internal_format = Some(internal_format);
+
+    Ok(())
+}
+
+/// Replaces the whole mipmap chain of a 2D texture, starting at the base level.
+///
+/// Each level has to be half the size of the one before it, rounded down to at least 1.
+/// Sampling stops at the last level given, so the chain doesn't have to go down to 1x1.
+pub fn upload_compressed_mips<T: GLGenericTexture + ?Sized>(texture: &mut T, internal_format: GLenum,
+                                                            levels: &[GLCompressedLevel]) -> GLResult<()> {
+    if texture.kind() != GLTextureKind::Texture2D {
+        throw!(GLError::Unsupported);
+    }
+
+    let (width, height) = match levels.first() {
+        Some(base) => (base.width, base.height),
+        None => throw!(GLError::InvalidValue),
+    };
+
+    // Everything is validated before anything is uploaded, so a bad level doesn't leave the texture half replaced
+    for (i, level) in levels.iter().enumerate() {
+        if level.width != (width >> i).max(1) || level.height != (height >> i).max(1) {
+            error!("Mipmap level {} is {}x{}, but a {}x{} texture needs {}x{}",
+                   i, level.width, level.height, width, height, (width >> i).max(1), (height >> i).max(1));
+
+            throw!(GLError::InvalidValue);
+        }
+
+        try_rethrow!(check_compressed(internal_format, level.width, level.height, level.data));
+    }
+
+    try_rethrow!(texture.bind());
+
+    for (i, level) in levels.iter().enumerate() {
+        unsafe { compressed_image(i, internal_format, level.width, level.height, level.data); }
+
+        check_gl_errors!("uploading level {} of a {}x{} texture with format 0x{:04X}", i, width, height, internal_format);
+    }
+
+    let parameters = try_rethrow!(TextureParameters::new(texture));
+
+    unsafe { parameters.int(TEXTURE_MAX_LEVEL, levels.len() as GLint - 1); }
+
+    check_gl_errors!();
+
+    texture.format = None;
+    texture.internal_format = Some(internal_format);
+
+    Ok(())
+}
@@ -0,0 +1,324 @@
+//! Creating textures from decoded images and texture protocols, with formats, mipmaps and sampling chosen in one place
+
+use super::super::bindings::types::*;
+use super::super::bindings::*;
+
+use image::{DynamicImage, GenericImage};
+
+use protocols::error::ProtocolError;
+use protocols::texture::protocol::{self, Channels, DataType, TextureKind};
+use protocols::texture::data::format::{Which, Uncompressed, SpecificFormat};
+
+use ::backends::gl::protocols::texture::GLCompressedSpecificFormats;
+
+use super::super::error::*;
+
+use super::super::GLBindable;
+use super::{GLTexture, GLTexture1D, GLTexture2D, GLGenericTexture, GLTextureVariant, GLTextureFilter, GLTextureWrap};
+use super::compressed::GLCompressedLevel;
+
+/// Where the mipmaps of a loaded texture come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GLMipmapPolicy {
+    /// Only the base level is uploaded, and sampled without mipmapping
+    None,
+    /// The driver generates a full chain from the base level
+    Generate,
+    /// Levels stored in a texture protocol are uploaded as they are, otherwise they're generated like `Generate`
+    Stored,
+}
+
+/// How `GLTexture::from_image` and `GLTexture::from_protocol_reader` set up the texture
+#[derive(Debug, Clone, Copy)]
+pub struct GLTextureOptions {
+    /// Stores 8-bit color images in an sRGB format. Texture protocols say whether they're sRGB themselves.
+    pub srgb: bool,
+    pub mipmaps: GLMipmapPolicy,
+    /// Used for magnification, minification and between mipmap levels
+    pub filter: GLTextureFilter,
+    pub wrap: GLTextureWrap,
+    /// Anisotropic filtering level, clamped to what the driver supports
+    pub anisotropy: f32,
+}
+
+impl Default for GLTextureOptions {
+    fn default() -> GLTextureOptions {
+        GLTextureOptions {
+            srgb: false,
+            mipmaps: GLMipmapPolicy::Stored,
+            filter: GLTextureFilter::Linear,
+            wrap: GLTextureWrap::Repeat,
+            anisotropy: 1.0,
+        }
+    }
+}
+
+/// Format, internal format and data type of uncompressed pixels, as given to `glTexImage*`
+fn uncompressed_format(uncompressed: &Uncompressed, srgb: bool) -> GLResult<(GLenum, GLenum, GLenum)> {
+    let channels = uncompressed.channels;
+
+    let format = match channels {
+        Channels::R => RED,
+        Channels::Rg => RG,
+        Channels::Rgb => RGB,
+        Channels::Rgba => RGBA,
+    };
+
+    let integer_format = match channels {
+        Channels::R => RED_INTEGER,
+        Channels::Rg => RG_INTEGER,
+        Channels::Rgb => RGB_INTEGER,
+        Channels::Rgba => RGBA_INTEGER,
+    };
+
+    // Indexed by the number of channels
+    let pick = |formats: [GLenum; 4]| formats[channels.num_channels() - 1];
+
+    Ok(match uncompressed.data_type {
+        DataType::UnsignedByte | DataType::Unspecified => {
+            let internal_format = match channels {
+                Channels::Rgb if srgb => SRGB8,
+                Channels::Rgba if srgb => SRGB8_ALPHA8,
+                _ => pick([R8, RG8, RGB8, RGBA8]),
+            };
+
+            (format, internal_format, UNSIGNED_BYTE)
+        }
+        DataType::Byte => (format, pick([R8_SNORM, RG8_SNORM, RGB8_SNORM, RGBA8_SNORM]), BYTE),
+        DataType::UnsignedShort => (format, pick([R16, RG16, RGB16, RGBA16]), UNSIGNED_SHORT),
+        DataType::Short => (format, pick([R16_SNORM, RG16_SNORM, RGB16_SNORM, RGBA16_SNORM]), SHORT),
+        DataType::UnsignedInt => (integer_format, pick([R32UI, RG32UI, RGB32UI, RGBA32UI]), UNSIGNED_INT),
+        DataType::Int => (integer_format, pick([R32I, RG32I, RGB32I, RGBA32I]), INT),
+        // HDR data is kept at full precision
+        DataType::Float => (format, pick([R32F, RG32F, RGB32F, RGBA32F]), FLOAT),
+        _ => throw!(GLError::Unsupported),
+    })
+}
+
+fn read_format(reader: &protocol::texture::Reader) -> GLResult<SpecificFormat> {
+    let which = match try_throw!(reader.get_compression().which().map_err(ProtocolError::from)) {
+        protocol::texture::compression::None(uncompressed_reader) => {
+            let uncompressed_reader = try_throw!(uncompressed_reader.map_err(ProtocolError::from));
+
+            Which::None(Uncompressed {
+                channels: try_throw!(uncompressed_reader.get_format().map_err(ProtocolError::from)),
+                data_type: try_throw!(uncompressed_reader.get_type().map_err(ProtocolError::from)),
+            })
+        },
+        protocol::texture::compression::Rgtc(rgtc) => Which::Rgtc(try_throw!(rgtc.map_err(ProtocolError::from))),
+        protocol::texture::compression::Bptc(bptc) => Which::Bptc(try_throw!(bptc.map_err(ProtocolError::from))),
+        protocol::texture::compression::S3tc(s3tc) => Which::S3tc(try_throw!(s3tc.map_err(ProtocolError::from))),
+        protocol::texture::compression::Astc(blocksize) => Which::Astc(try_throw!(blocksize.map_err(ProtocolError::from))),
+    };
+
+    Ok(SpecificFormat { which: which, srgb: reader.get_srgb() })
+}
+
+/// Bytes a tightly packed mipmap level of the given size takes up
+fn level_size(uncompressed: &Uncompressed, width: usize, height: usize) -> usize {
+    let bytes = match uncompressed.data_type {
+        DataType::Byte | DataType::UnsignedByte | DataType::Unspecified => 1,
+        DataType::Short | DataType::UnsignedShort => 2,
+        _ => 4,
+    };
+
+    width * height * uncompressed.channels.num_channels() * bytes
+}
+
+/// Uploads one uncompressed mipmap level of a 1D or 2D texture
+fn upload_level(kind: GLenum, level: usize, uncompressed: &Uncompressed, srgb: bool, width: usize, height: usize, data: &[u8]) -> GLResult<()> {
+    let size = level_size(uncompressed, width, height);
+
+    if data.len() < size {
+        error!("Mipmap level {} has {} bytes, but {}x{} {:?} needs {}", level, data.len(), width, height, uncompressed, size);
+
+        throw!(GLError::InvalidValue);
+    }
+
+    let (pixel_format, internal_format, data_type) = try_rethrow!(uncompressed_format(uncompressed, srgb));
+
+    unsafe {
+        PixelStorei(UNPACK_ALIGNMENT, 1);
+
+        if kind == TEXTURE_1D {
+            TexImage1D(kind, level as GLint, internal_format as GLint, width as GLsizei, 0,
+                       pixel_format, data_type, data.as_ptr() as *const _);
+        } else {
+            TexImage2D(kind, level as GLint, internal_format as GLint, width as GLsizei, height as GLsizei, 0,
+                       pixel_format, data_type, data.as_ptr() as *const _);
+        }
+    }
+
+    check_gl_errors!("uploading level {} of a {}x{} {:?} texture", level, width, height, uncompressed);
+
+    Ok(())
+}
+
+/// Applies the mipmap policy to a texture whose base level was just uploaded, with `stored` extra levels already uploaded.
+///
+/// Returns whether the texture has mipmaps to sample from.
+fn finish_mipmaps<T: GLGenericTexture + ?Sized>(texture: &mut T, policy: GLMipmapPolicy, stored: usize, compressed: bool) -> GLResult<bool> {
+    let max_level = match policy {
+        GLMipmapPolicy::Stored if stored > 0 => stored,
+        GLMipmapPolicy::None => 0,
+        // Drivers aren't required to generate mipmaps for compressed formats
+        _ if compressed => {
+            warn!("Mipmaps can't be generated for compressed textures, so only the base level will be sampled");
+
+            0
+        }
+        _ => {
+            try_rethrow!(texture.generate_mipmaps());
+
+            return Ok(true);
+        }
+    };
+
+    // Otherwise the texture is incomplete without a full chain
+    try_rethrow!(texture.bind());
+
+    unsafe { TexParameteri(texture.kind() as GLenum, TEXTURE_MAX_LEVEL, max_level as GLint); }
+
+    check_gl_errors!();
+
+    Ok(max_level > 0)
+}
+
+fn apply_options<T: GLGenericTexture + ?Sized>(texture: &mut T, options: &GLTextureOptions, mipmapped: bool) -> GLResult<()> {
+    try_rethrow!(texture.set_filtering(options.filter, if mipmapped { Some(options.filter) } else { None }));
+    try_rethrow!(texture.set_wrap_axes(options.wrap, options.wrap, options.wrap));
+
+    if options.anisotropy > 1.0 {
+        try_rethrow!(texture.set_anisotropy(options.anisotropy));
+    }
+
+    Ok(())
+}
+
+impl GLTexture {
+    /// Creates a 2D texture from a decoded image.
+    ///
+    /// Images are stored top row first, but OpenGL expects the bottom row first, so they should be flipped beforehand
+    /// if that matters. `GLMipmapPolicy::Stored` generates mipmaps, since images don't have any.
+    pub fn from_image(image: &DynamicImage, options: GLTextureOptions) -> GLResult<GLTexture> {
+        let (width, height) = image.dimensions();
+
+        let channels = match *image {
+            DynamicImage::ImageLuma8(_) => Channels::R,
+            DynamicImage::ImageLumaA8(_) => Channels::Rg,
+            DynamicImage::ImageRgb8(_) => Channels::Rgb,
+            DynamicImage::ImageRgba8(_) => Channels::Rgba,
+        };
+
+        let uncompressed = Uncompressed::new(channels, DataType::UnsignedByte);
+
+        let format = SpecificFormat { which: Which::None(uncompressed), srgb: options.srgb };
+
+        let mut texture = try_rethrow!(GLTexture2D::new());
+
+        try_rethrow!(upload_level(TEXTURE_2D, 0, &uncompressed, options.srgb, width as usize, height as usize, &image.raw_pixels()));
+
+        try_rethrow!(texture.set_formats(&format));
+
+        let mipmapped = try_rethrow!(finish_mipmaps(&mut texture, options.mipmaps, 0, false));
+
+        try_rethrow!(apply_options(&mut texture, &options, mipmapped));
+
+        Ok(GLTexture::Texture2D(texture))
+    }
+
+    /// Creates a 1D or 2D texture from a texture protocol, in whatever format it was stored in.
+    ///
+    /// Fails with `UnsupportedFormat` for compression formats the driver doesn't support,
+    /// and `Unsupported` for 3D textures and compressed 1D textures.
+    pub fn from_protocol_reader(reader: protocol::texture::Reader, options: GLTextureOptions) -> GLResult<GLTexture> {
+        let format = try_rethrow!(read_format(&reader));
+
+        let dimensions = reader.get_dimensions();
+
+        let width = dimensions.get_width() as usize;
+        let height = dimensions.get_height().max(1) as usize;
+
+        // The base level followed by any stored mipmaps
+        let mut levels = vec![try_throw!(reader.get_data().map_err(ProtocolError::from))];
+
+        if options.mipmaps == GLMipmapPolicy::Stored && reader.has_mipmaps() {
+            let mipmaps = try_throw!(reader.get_mipmaps().map_err(ProtocolError::from));
+
+            for i in 0..mipmaps.len() {
+                levels.push(try_throw!(mipmaps.get(i).map_err(ProtocolError::from)));
+            }
+        }
+
+        let stored = levels.len() - 1;
+
+        let upload_uncompressed = |kind: GLenum| -> GLResult<()> {
+            let uncompressed = match format.which {
+                Which::None(ref uncompressed) => uncompressed,
+                _ => throw!(GLError::Unsupported),
+            };
+
+            for (level, data) in levels.iter().enumerate() {
+                try_rethrow!(upload_level(kind, level, uncompressed, format.srgb, (width >> level).max(1), (height >> level).max(1), data));
+            }
+
+            Ok(())
+        };
+
+        let mut texture = match try_throw!(reader.get_kind().map_err(ProtocolError::from)) {
+            TextureKind::Texture1D => {
+                let mut texture = try_rethrow!(GLTexture1D::new());
+
+                try_rethrow!(upload_uncompressed(TEXTURE_1D));
+                try_rethrow!(texture.set_formats(&format));
+
+                GLTexture::Texture1D(texture)
+            }
+            TextureKind::Texture2D => {
+                let mut texture = try_rethrow!(GLTexture2D::new());
+
+                if format.is_compressed() {
+                    let compressed_levels: Vec<GLCompressedLevel> = levels.iter().enumerate().map(|(level, data)| {
+                        GLCompressedLevel { width: (width >> level).max(1), height: (height >> level).max(1), data: data }
+                    }).collect();
+
+                    try_rethrow!(texture.upload_compressed_mips(format.specific(), &compressed_levels));
+                } else {
+                    try_rethrow!(upload_uncompressed(TEXTURE_2D));
+                }
+
+                try_rethrow!(texture.set_formats(&format));
+
+                GLTexture::Texture2D(texture)
+            }
+            //TODO: Support 3D textures
+            TextureKind::Texture3D => throw!(GLError::Unsupported),
+        };
+
+        let mipmapped = try_rethrow!(finish_mipmaps(&mut texture, options.mipmaps, stored, format.is_compressed()));
+
+        try_rethrow!(apply_options(&mut texture, &options, mipmapped));
+
+        Ok(texture)
+    }
+}
+
+impl super::GLBaseTexture {
+    /// Remembers the format the texture was created with, for `format` and `internal_format`
+    fn set_formats(&mut self, format: &SpecificFormat) -> GLResult<()> {
+        match format.which {
+            Which::None(ref uncompressed) => {
+                let (pixel_format, internal_format, _) = try_rethrow!(uncompressed_format(uncompressed, format.srgb));
+
+                self.format = Some(pixel_format);
+                self.internal_format = Some(internal_format);
+            }
+            _ => {
+                self.format = None;
+                self.internal_format = Some(format.specific());
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -10,11 +10,20 @@ use std::path::Path;
 use image::{self, DynamicImage, GenericImage};
 
 use super::error::*;
+use super::debug::label_object;
 use super::shader::*;
+use super::state::GLStateCache;
+use super::capabilities::GLCapabilities;
 
 pub mod dimensions;
+pub mod upload;
+pub mod load;
+pub mod compressed;
 
 pub use self::dimensions::{GLDimensions, GLOneDimension, GLTwoDimensions, GLThreeDimensions};
+pub use self::upload::{GLTextureRegion, GLUploadRing, GLUploadTicket};
+pub use self::load::{GLMipmapPolicy, GLTextureOptions};
+pub use self::compressed::{GLCompressionFamily, GLCompressedBlock, GLCompressedLevel};
 
 #[derive(Copy, Clone, Debug)]
 pub enum GLTextureFilter {
@@ -22,6 +31,28 @@ pub enum GLTextureFilter {
     Nearest
 }
 
+impl GLTextureFilter {
+    /// Minification and magnification filter parameters for this filter, optionally blending between mipmaps with `mipmap`
+    pub fn parameters(self, mipmap: Option<GLTextureFilter>) -> (GLenum, GLenum) {
+        match self {
+            GLTextureFilter::Linear => {
+                (match mipmap {
+                    None => LINEAR,
+                    Some(GLTextureFilter::Nearest) => LINEAR_MIPMAP_NEAREST,
+                    Some(GLTextureFilter::Linear) => LINEAR_MIPMAP_LINEAR
+                }, LINEAR)
+            }
+            GLTextureFilter::Nearest => {
+                (match mipmap {
+                    None => NEAREST,
+                    Some(GLTextureFilter::Nearest) => NEAREST_MIPMAP_NEAREST,
+                    Some(GLTextureFilter::Linear) => NEAREST_MIPMAP_LINEAR
+                }, NEAREST)
+            }
+        }
+    }
+}
+
 #[repr(u32)]
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum GLTextureKind {
@@ -135,10 +166,17 @@ impl GLBaseTexture {
     #[inline(always)]
     pub fn internal_format(&self) -> Option<GLenum> { self.internal_format }
 
+    /// Names the texture in debuggers and debug messages
+    pub fn label(&self, label: &str) -> GLResult<()> {
+        label_object(TEXTURE, self.handle, label)
+    }
+
     fn delete(&mut self) -> GLResult<()> {
         if self.is_valid() {
             unsafe { DeleteTextures(1, &mut self.handle as *mut GLuint); }
 
+            GLStateCache::forget_texture(self.handle);
+
             check_gl_errors!();
         }
 
@@ -164,7 +202,7 @@ macro_rules! declare_gl_texture {
 
                 check_gl_errors!();
 
-                unsafe { BindTexture(GLTextureKind::$kind as GLenum, texture); }
+                GLStateCache::bind_texture(GLTextureKind::$kind as GLenum, texture);
 
                 check_gl_errors!();
 
@@ -192,7 +230,7 @@ macro_rules! declare_gl_texture {
             fn bind(&self) -> GLResult<()> {
                 try_rethrow!(self.check());
 
-                unsafe { BindTexture(GLTextureKind::$kind as GLenum, self.raw()); }
+                GLStateCache::bind_texture(GLTextureKind::$kind as GLenum, self.raw());
 
                 check_gl_errors!();
 
@@ -319,13 +357,91 @@ impl GLTextureVariant for GLTexture {
     }
 }
 
+/// Sets texture parameters with direct state access where it's available, otherwise by binding the texture first
+struct TextureParameters {
+    texture: GLuint,
+    kind: GLenum,
+    direct: bool,
+}
+
+impl TextureParameters {
+    fn new<T: Deref<Target=GLBaseTexture> + GLBindable + GLTextureVariant + ?Sized>(texture: &T) -> GLResult<TextureParameters> {
+        let direct = GLCapabilities::use_direct_state_access();
+
+        if direct {
+            try_rethrow!(texture.check());
+        } else {
+            try_rethrow!(texture.bind());
+        }
+
+        Ok(TextureParameters { texture: texture.raw(), kind: texture.kind() as GLenum, direct: direct })
+    }
+
+    unsafe fn int(&self, name: GLenum, value: GLint) {
+        if self.direct {
+            TextureParameteri(self.texture, name, value);
+        } else {
+            TexParameteri(self.kind, name, value);
+        }
+    }
+
+    unsafe fn float(&self, name: GLenum, value: GLfloat) {
+        if self.direct {
+            TextureParameterf(self.texture, name, value);
+        } else {
+            TexParameterf(self.kind, name, value);
+        }
+    }
+
+    unsafe fn floats(&self, name: GLenum, values: &[GLfloat]) {
+        if self.direct {
+            TextureParameterfv(self.texture, name, values.as_ptr());
+        } else {
+            TexParameterfv(self.kind, name, values.as_ptr());
+        }
+    }
+}
+
 impl GLGenericTexture for GLTexture {}
 
 pub trait GLGenericTexture: Deref<Target=GLBaseTexture> + DerefMut + GLBindable + GLTextureVariant {
+    /// Copies tightly packed rows of pixels into part of the texture's existing storage, waiting for the copy from client memory.
+    ///
+    /// Only 2D textures are supported.
+    fn upload_sub(&mut self, data: &[u8], format: GLenum, data_type: GLenum, region: GLTextureRegion) -> GLResult<()> {
+        upload::upload_sub(self, data, format, data_type, region)
+    }
+
+    /// Like `upload_sub`, but streams the pixels through `ring` so the transfer happens in the background.
+    ///
+    /// Falls back to `upload_sub` if pixel unpack buffers aren't available.
+    fn upload_async(&mut self, ring: &mut GLUploadRing, data: &[u8], format: GLenum, data_type: GLenum,
+                    region: GLTextureRegion) -> GLResult<GLUploadTicket> {
+        ring.upload(self, data, format, data_type, region)
+    }
+
+    /// Replaces mipmap level `level` of a 2D texture with block-compressed data in `internal_format`.
+    ///
+    /// Fails with `UnsupportedFormat` if the driver doesn't support the format, so it can be decoded on the CPU instead.
+    fn upload_compressed(&mut self, level: usize, internal_format: GLenum, width: usize, height: usize, data: &[u8]) -> GLResult<()> {
+        compressed::upload_compressed(self, level, internal_format, width, height, data)
+    }
+
+    /// Like `upload_compressed`, but replaces the whole mipmap chain starting at the base level
+    fn upload_compressed_mips(&mut self, internal_format: GLenum, levels: &[GLCompressedLevel]) -> GLResult<()> {
+        compressed::upload_compressed_mips(self, internal_format, levels)
+    }
+
     fn generate_mipmaps(&mut self) -> GLResult<()> {
-        try_rethrow!(self.bind());
+        if GLCapabilities::use_direct_state_access() {
+            try_rethrow!(self.check());
+
+            unsafe { GenerateTextureMipmap(self.raw()); }
+        } else {
+            try_rethrow!(self.bind());
 
-        unsafe { GenerateMipmap(self.kind() as GLenum); }
+            unsafe { GenerateMipmap(self.kind() as GLenum); }
+        }
 
         check_gl_errors!();
 
@@ -333,57 +449,89 @@ pub trait GLGenericTexture: Deref<Target=GLBaseTexture> + DerefMut + GLBindable
     }
 
     fn get_max_anisotropy(&self) -> GLResult<f32> {
-        try_rethrow!(self.bind());
+        Ok(try_rethrow!(GLCapabilities::current()).max_anisotropy)
+    }
+
+    /// Sets the anisotropic filtering level, clamped between `1.0` and the maximum supported level.
+    ///
+    /// Returns the level that was actually set, which is always `1.0` without anisotropic filtering.
+    fn set_anisotropy(&mut self, value: f32) -> GLResult<f32> {
+        let capabilities = try_rethrow!(GLCapabilities::current());
+
+        if !capabilities.anisotropic_filtering {
+            return Ok(1.0);
+        }
 
-        let mut max_anisoptopy: GLfloat = 0.0;
+        let value = value.max(1.0).min(capabilities.max_anisotropy);
 
-        unsafe { GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY_EXT, &mut max_anisoptopy as *mut _); }
+        let parameters = try_rethrow!(TextureParameters::new(self));
+
+        unsafe { parameters.float(TEXTURE_MAX_ANISOTROPY_EXT, value); }
 
         check_gl_errors!();
 
-        Ok(max_anisoptopy)
+        Ok(value)
     }
 
-    fn set_anisotropy(&mut self, value: f32) -> GLResult<()> {
-        try_rethrow!(self.bind());
+    /// Sets the wrap mode for each of the S, T and R texture coordinates.
+    ///
+    /// Coordinates that don't exist for the texture kind are ignored, so `r` does nothing for 2D textures.
+    fn set_wrap_axes(&mut self, s: GLTextureWrap, t: GLTextureWrap, r: GLTextureWrap) -> GLResult<()> {
+        let parameters = try_rethrow!(TextureParameters::new(self));
+
+        let dimensions = self.kind().dimensions();
+
+        unsafe {
+            parameters.int(TEXTURE_WRAP_S, s as GLint);
 
-        unsafe { TexParameterf(self.kind() as GLenum, TEXTURE_MAX_ANISOTROPY_EXT, value); }
+            if dimensions > 1 {
+                parameters.int(TEXTURE_WRAP_T, t as GLint);
+            }
+
+            if dimensions > 2 {
+                parameters.int(TEXTURE_WRAP_R, r as GLint);
+            }
+        }
 
         check_gl_errors!();
 
         Ok(())
     }
 
-    fn set_filtering(&mut self, filter: GLTextureFilter, mipmap: Option<GLTextureFilter>) -> GLResult<()> {
-        try_rethrow!(self.bind());
+    /// Sets the border color used by `GLTextureWrap::ClampToBorder`
+    fn set_border_color(&mut self, color: [f32; 4]) -> GLResult<()> {
+        let parameters = try_rethrow!(TextureParameters::new(self));
 
-        let min_filter;
-        let mag_filter;
+        unsafe { parameters.floats(TEXTURE_BORDER_COLOR, &color); }
 
-        match filter {
-            GLTextureFilter::Linear => {
-                min_filter = match mipmap {
-                    None => LINEAR,
-                    Some(GLTextureFilter::Nearest) => LINEAR_MIPMAP_NEAREST,
-                    Some(GLTextureFilter::Linear) => LINEAR_MIPMAP_LINEAR
-                } as GLint;
+        check_gl_errors!();
 
-                mag_filter = LINEAR as GLint;
-            }
-            GLTextureFilter::Nearest => {
-                min_filter = match mipmap {
-                    None => NEAREST,
-                    Some(GLTextureFilter::Nearest) => NEAREST_MIPMAP_NEAREST,
-                    Some(GLTextureFilter::Linear) => NEAREST_MIPMAP_LINEAR
-                } as GLint;
+        Ok(())
+    }
 
-                mag_filter = NEAREST as GLint;
-            }
+    /// Clamps sampling to the given level-of-detail range, and biases the computed level of detail.
+    fn set_lod_range(&mut self, base: f32, max: f32, bias: f32) -> GLResult<()> {
+        let parameters = try_rethrow!(TextureParameters::new(self));
+
+        unsafe {
+            parameters.float(TEXTURE_MIN_LOD, base);
+            parameters.float(TEXTURE_MAX_LOD, max);
+            parameters.float(TEXTURE_LOD_BIAS, bias);
         }
 
+        check_gl_errors!();
+
+        Ok(())
+    }
+
+    fn set_filtering(&mut self, filter: GLTextureFilter, mipmap: Option<GLTextureFilter>) -> GLResult<()> {
+        let parameters = try_rethrow!(TextureParameters::new(self));
+
+        let (min_filter, mag_filter) = filter.parameters(mipmap);
+
         unsafe {
-            TexParameteri(self.kind() as GLenum, TEXTURE_MIN_FILTER, min_filter);
-            TexParameteri(self.kind() as GLenum, TEXTURE_MAG_FILTER, mag_filter);
+            parameters.int(TEXTURE_MIN_FILTER, min_filter as GLint);
+            parameters.int(TEXTURE_MAG_FILTER, mag_filter as GLint);
         }
 
         check_gl_errors!();
@@ -406,10 +554,10 @@ pub trait GLDimensionalTexture<D: GLDimensions>: Deref<Target=GLBaseTexture> + D
     }
 
     fn set_wrap_dim(&mut self, mode: GLTextureWrap, dim: D) -> GLResult<()> {
-        try_rethrow!(self.bind());
+        let parameters = try_rethrow!(TextureParameters::new(self));
 
         unsafe {
-            TexParameteri(self.kind() as GLenum, dim.texture_wrap(), mode as GLint);
+            parameters.int(dim.texture_wrap(), mode as GLint);
         }
 
         check_gl_errors!();
@@ -432,6 +580,12 @@ impl GLTexture {
 
         let dims = self.kind.dimensions();
 
+        // Integer formats can't be specified with float data, even when there is no data
+        let data_type = match format {
+            RED_INTEGER | RG_INTEGER | RGB_INTEGER | RGBA_INTEGER => UNSIGNED_INT,
+            _ => FLOAT,
+        };
+
         if dims == 2 {
             unsafe {
                 TexImage2D(self.kind as GLenum,
@@ -441,7 +595,7 @@ impl GLTexture {
                            height as GLsizei,
                            0,
                            format,
-                           FLOAT,
+                           data_type,
                            ptr::null());
             }
         }
@@ -457,7 +611,7 @@ impl GLTexture {
     pub fn load_from_file<P: AsRef<Path>>(&mut self, path: P, face: Option<GLCubemapFace>) -> GLResult<()> {
         try_rethrow!(self.check());
 
-        let texture: DynamicImage = try_throw!(image::open(path));
+        let texture: DynamicImage = try_throw!(image::open(path.as_ref()));
 
         try_rethrow!(self.bind());
 
@@ -514,7 +668,7 @@ impl GLTexture {
         self.format = Some(format);
         self.internal_format = Some(iformat);
 
-        check_gl_errors!();
+        check_gl_errors!("TexImage2D for {:?}", path.as_ref());
 
         Ok(())
     }
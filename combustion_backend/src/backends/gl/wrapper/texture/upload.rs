@@ -0,0 +1,230 @@
+//! Streaming texture uploads through pixel unpack buffers
+
+use super::super::bindings::types::*;
+use super::super::bindings::*;
+
+use std::ptr;
+use std::time::Duration;
+
+use super::super::error::*;
+use super::super::buffer::*;
+use super::super::sync::GLFence;
+use super::super::framebuffer::pixel_size;
+use super::super::capabilities::GLCapabilities;
+
+use super::super::GLBindable;
+use super::{GLGenericTexture, GLTextureVariant, GLTextureKind};
+
+/// Rectangle of a texture's mipmap level to upload into, in pixels from the bottom left
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GLTextureRegion {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub level: usize,
+}
+
+impl GLTextureRegion {
+    /// The whole base level of a texture with the given size
+    pub fn new(width: usize, height: usize) -> GLTextureRegion {
+        GLTextureRegion { x: 0, y: 0, width: width, height: height, level: 0 }
+    }
+
+    pub fn at(self, x: usize, y: usize) -> GLTextureRegion {
+        GLTextureRegion { x: x, y: y, ..self }
+    }
+
+    pub fn with_level(self, level: usize) -> GLTextureRegion {
+        GLTextureRegion { level: level, ..self }
+    }
+}
+
+/// Identifies an upload made through a `GLUploadRing`, to check when the GPU is done with it
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct GLUploadTicket(u64);
+
+struct UploadSlot {
+    buffer: GLBuffer,
+    /// Signaled once the GPU has copied the slot into its texture, `None` if it already was
+    fence: Option<GLFence>,
+    ticket: u64,
+}
+
+/// Ring of pixel unpack buffers that texture data is copied into, so the GPU can transfer it to the texture in the background.
+///
+/// A slot is only reused once its last upload has finished, so uploading more than `slots` textures at once
+/// waits for the oldest one. Without buffer mapping or sync objects, uploads fall back to copying synchronously.
+pub struct GLUploadRing {
+    slots: Vec<UploadSlot>,
+    capacity: usize,
+    next: usize,
+    tickets: u64,
+}
+
+impl GLUploadRing {
+    pub fn new(slots: usize) -> GLUploadRing {
+        GLUploadRing {
+            slots: Vec::with_capacity(slots),
+            capacity: slots.max(1),
+            next: 0,
+            tickets: 0,
+        }
+    }
+
+    /// Whether uploads actually go through pixel unpack buffers
+    pub fn is_supported() -> bool {
+        MapBufferRange::is_loaded() && FenceSync::is_loaded()
+    }
+
+    /// Copies `data` into a free slot and starts transferring it into `region` of the texture, without waiting for the transfer.
+    ///
+    /// The texture's storage for the region must already exist, like after `load_empty`.
+    /// `data` holds tightly packed rows of the given format and data type, bottom row first.
+    pub fn upload<T: GLGenericTexture + ?Sized>(&mut self, texture: &T, data: &[u8], format: GLenum, data_type: GLenum,
+                                                region: GLTextureRegion) -> GLResult<GLUploadTicket> {
+        try_rethrow!(check_upload(texture, data, format, data_type, &region));
+
+        self.tickets += 1;
+
+        let ticket = self.tickets;
+
+        if !GLUploadRing::is_supported() {
+            try_rethrow!(sub_image(texture, data.as_ptr(), format, data_type, &region));
+
+            return Ok(GLUploadTicket(ticket));
+        }
+
+        let index = self.next;
+
+        self.next = (self.next + 1) % self.capacity;
+
+        if index == self.slots.len() {
+            self.slots.push(UploadSlot {
+                buffer: try_rethrow!(GLBuffer::new(GLBufferTarget::PixelUnpackBuffer)),
+                fence: None,
+                ticket: 0,
+            });
+        }
+
+        let slot = &mut self.slots[index];
+
+        // The ring wrapped around before the GPU got to this slot's last upload
+        if let Some(fence) = slot.fence.take() {
+            while !try_rethrow!(fence.wait(Duration::from_millis(100))) {}
+        }
+
+        if slot.buffer.num_bytes() < data.len() {
+            try_rethrow!(slot.buffer.allocate(data.len(), GLBufferUsage::StreamDraw));
+        }
+
+        {
+            let mut mapping = try_rethrow!(slot.buffer.map_range_write::<u8>(0, data.len()));
+
+            try_rethrow!(mapping.write(0, data));
+
+            try_rethrow!(mapping.unmap());
+        }
+
+        try_rethrow!(slot.buffer.bind());
+
+        // With the buffer bound, the pointer is an offset into it
+        let result = sub_image(texture, ptr::null(), format, data_type, &region);
+
+        // Otherwise any later synchronous uploads would read from the buffer as well
+        unsafe { BindBuffer(PIXEL_UNPACK_BUFFER, 0); }
+
+        try_rethrow!(result);
+
+        slot.fence = Some(try_rethrow!(GLFence::insert()));
+        slot.ticket = ticket;
+
+        Ok(GLUploadTicket(ticket))
+    }
+
+    /// Checks if the GPU has finished the upload, without waiting for it
+    pub fn is_complete(&mut self, ticket: GLUploadTicket) -> GLResult<bool> {
+        for slot in &mut self.slots {
+            if slot.ticket == ticket.0 {
+                let signaled = match slot.fence {
+                    Some(ref fence) => try_rethrow!(fence.is_signaled()),
+                    None => true,
+                };
+
+                if signaled {
+                    slot.fence = None;
+                }
+
+                return Ok(signaled);
+            }
+        }
+
+        // Synchronous uploads are done right away, and slots are only reused after their upload finished
+        Ok(true)
+    }
+
+    /// Blocks until every upload has finished
+    pub fn wait_all(&mut self) -> GLResult<()> {
+        for slot in &mut self.slots {
+            if let Some(fence) = slot.fence.take() {
+                while !try_rethrow!(fence.wait(Duration::from_millis(100))) {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn check_upload<T: GLGenericTexture + ?Sized>(texture: &T, data: &[u8], format: GLenum, data_type: GLenum,
+                                              region: &GLTextureRegion) -> GLResult<()> {
+    match texture.kind() {
+        GLTextureKind::Texture2D | GLTextureKind::Rectangle => {}
+        _ => throw!(GLError::InvalidOperation),
+    }
+
+    let max_size = try_rethrow!(GLCapabilities::current()).max_texture_size;
+
+    if region.x + region.width > max_size || region.y + region.height > max_size {
+        throw!(GLError::InvalidValue);
+    }
+
+    let size = region.width * region.height * try_rethrow!(pixel_size(format, data_type));
+
+    if size == 0 || data.len() < size {
+        throw!(GLError::InvalidValue);
+    }
+
+    Ok(())
+}
+
+/// Copies tightly packed rows into the region, from client memory or the bound pixel unpack buffer
+fn sub_image<T: GLGenericTexture + ?Sized>(texture: &T, data: *const u8, format: GLenum, data_type: GLenum,
+                                           region: &GLTextureRegion) -> GLResult<()> {
+    try_rethrow!(texture.bind());
+
+    unsafe {
+        PixelStorei(UNPACK_ALIGNMENT, 1);
+
+        TexSubImage2D(texture.kind() as GLenum,
+                      region.level as GLint,
+                      region.x as GLint,
+                      region.y as GLint,
+                      region.width as GLsizei,
+                      region.height as GLsizei,
+                      format,
+                      data_type,
+                      data as *const _);
+    }
+
+    check_gl_errors!();
+
+    Ok(())
+}
+
+/// Synchronous upload for `GLGenericTexture::upload_sub`
+pub fn upload_sub<T: GLGenericTexture + ?Sized>(texture: &T, data: &[u8], format: GLenum, data_type: GLenum,
+                                                region: GLTextureRegion) -> GLResult<()> {
+    try_rethrow!(check_upload(texture, data, format, data_type, &region));
+
+    sub_image(texture, data.as_ptr(), format, data_type, &region)
+}
@@ -0,0 +1,127 @@
+//! Assignment of texture units, so passes that bind many textures don't overwrite each other's bindings
+
+use fnv::FnvHashMap;
+
+use super::error::*;
+use super::capabilities::GLCapabilities;
+use super::state::GLStateCache;
+
+use super::GLBindable;
+
+/// Hands out texture units, from `0` up to `GL_MAX_COMBINED_TEXTURE_IMAGE_UNITS`.
+///
+/// The lowest units form a scratch range for textures that change every draw, such as those of materials,
+/// and are handed out again after each `reset_scratch`. Named bindings that stay in place, such as a shadow map,
+/// are reserved from the highest units down. The units in between are left to code with its own fixed layout,
+/// like the inputs of a pipeline stage.
+///
+/// Binding goes through `GLStateCache`, so rebinding the texture already bound to a unit is skipped.
+#[derive(Debug, Clone)]
+pub struct GLTextureUnits {
+    count: usize,
+    scratch: usize,
+    next_scratch: usize,
+    reserved: FnvHashMap<String, usize>,
+}
+
+impl GLTextureUnits {
+    /// Manages every texture unit of the current context, with the given number of scratch units
+    pub fn new(scratch: usize) -> GLResult<GLTextureUnits> {
+        GLTextureUnits::with_count(try_rethrow!(GLCapabilities::current()).max_texture_units, scratch)
+    }
+
+    /// Manages only the first `count` texture units. Fails with `InvalidValue` if the scratch range doesn't fit.
+    pub fn with_count(count: usize, scratch: usize) -> GLResult<GLTextureUnits> {
+        if scratch > count {
+            throw!(GLError::InvalidValue);
+        }
+
+        Ok(GLTextureUnits {
+            count: count,
+            scratch: scratch,
+            next_scratch: 0,
+            reserved: FnvHashMap::default(),
+        })
+    }
+
+    /// Number of texture units managed
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Reserves a unit for the named binding, or returns the one it already has.
+    ///
+    /// Fails with `TextureUnitsExhausted` once every unit above the scratch range is reserved.
+    pub fn reserve(&mut self, name: &str) -> GLResult<usize> {
+        if let Some(unit) = self.reserved.get(name) {
+            return Ok(*unit);
+        }
+
+        let free = (self.scratch..self.count).rev().find(|unit| !self.reserved.values().any(|reserved| reserved == unit));
+
+        match free {
+            Some(unit) => {
+                self.reserved.insert(name.to_string(), unit);
+
+                Ok(unit)
+            }
+            None => throw!(GLError::TextureUnitsExhausted),
+        }
+    }
+
+    /// Frees the unit of a named binding, returning whether there was one
+    pub fn release(&mut self, name: &str) -> bool {
+        self.reserved.remove(name).is_some()
+    }
+
+    /// The unit reserved for a named binding, or `InvalidValue` if there is none
+    pub fn get(&self, name: &str) -> GLResult<usize> {
+        match self.reserved.get(name) {
+            Some(unit) => Ok(*unit),
+            None => throw!(GLError::InvalidValue),
+        }
+    }
+
+    /// Makes the whole scratch range available again, such as before each draw
+    #[inline]
+    pub fn reset_scratch(&mut self) {
+        self.next_scratch = 0;
+    }
+
+    /// Hands out the next scratch unit, or fails with `TextureUnitsExhausted` if the range is used up
+    pub fn scratch(&mut self) -> GLResult<usize> {
+        if self.next_scratch >= self.scratch {
+            throw!(GLError::TextureUnitsExhausted);
+        }
+
+        self.next_scratch += 1;
+
+        Ok(self.next_scratch - 1)
+    }
+
+    /// Binds a texture to the given unit, leaving that unit active
+    pub fn bind<T: GLBindable + ?Sized>(unit: usize, texture: &T) -> GLResult<()> {
+        GLStateCache::active_texture(unit);
+
+        texture.bind()
+    }
+
+    /// Binds a texture to the unit of a named binding, returning the unit
+    pub fn bind_reserved<T: GLBindable + ?Sized>(&self, name: &str, texture: &T) -> GLResult<usize> {
+        let unit = try_rethrow!(self.get(name));
+
+        try_rethrow!(GLTextureUnits::bind(unit, texture));
+
+        Ok(unit)
+    }
+
+    /// Binds a texture to the next scratch unit, returning the unit
+    pub fn bind_scratch<T: GLBindable + ?Sized>(&mut self, texture: &T) -> GLResult<usize> {
+        let unit = try_rethrow!(self.scratch());
+
+        try_rethrow!(GLTextureUnits::bind(unit, texture));
+
+        Ok(unit)
+    }
+}
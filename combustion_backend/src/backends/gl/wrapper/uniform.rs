@@ -1,6 +1,8 @@
 use super::bindings::types::*;
 use super::bindings::*;
 
+use std::mem;
+
 use super::error::*;
 
 use nalgebra::*;
@@ -8,6 +10,10 @@ use nalgebra::*;
 macro_rules! impl_scalar_uniform {
     ($glFunc:ident:$rustType:ty as $glType:ty => $name:ident($($field:ident),+)) => {
         pub fn $name(&mut self, $($field: $rustType),+) -> GLResult<()> {
+            if !self.is_active() {
+                return Ok(());
+            }
+
             unsafe {
                 $glFunc(self.0, $($field as $glType),+);
             }
@@ -22,6 +28,10 @@ macro_rules! impl_scalar_uniform {
 macro_rules! impl_array_uniform {
     ($glFunc:ident:$rustType:ty as $glType:ty => $name:ident) => {
         pub fn $name(&mut self, values: Vec<$rustType>) -> GLResult<()> {
+            if !self.is_active() {
+                return Ok(());
+            }
+
             unsafe {
                 $glFunc(self.0,
                         values.len() as GLsizei,
@@ -39,6 +49,10 @@ macro_rules! impl_array_uniform {
 macro_rules! impl_matrix_uniform {
     ($glFunc:ident:$rustType:ty as $glType:ty => $name:ident($field:ident)) => {
         pub fn $name(&mut self, mat: &$field<$rustType>, transpose: bool) -> GLResult<()> {
+            if !self.is_active() {
+                return Ok(());
+            }
+
             unsafe {
                 $glFunc(self.0, 1, if transpose { TRUE } else { FALSE }, mat.as_ref() as *const _ as *const $glType);
             }
@@ -50,6 +64,47 @@ macro_rules! impl_matrix_uniform {
     }
 }
 
+macro_rules! impl_nalgebra_array_uniform {
+    ($glFunc:ident:$rustType:ty as $glType:ty => $name:ident($field:ident; $components:expr)) => {
+        pub fn $name(&mut self, values: &[$field<$rustType>]) -> GLResult<()> {
+            if !self.is_active() {
+                return Ok(());
+            }
+
+            // The slice is passed as a flat array of components, which relies on nalgebra's plain layout
+            assert_eq!(mem::size_of::<$field<$rustType>>(), $components * mem::size_of::<$rustType>());
+
+            unsafe {
+                $glFunc(self.0, values.len() as GLsizei, values.as_ptr() as *const $glType);
+            }
+
+            check_gl_errors!();
+
+            Ok(())
+        }
+    }
+}
+
+macro_rules! impl_matrix_array_uniform {
+    ($glFunc:ident:$rustType:ty as $glType:ty => $name:ident($field:ident; $components:expr)) => {
+        pub fn $name(&mut self, values: &[$field<$rustType>], transpose: bool) -> GLResult<()> {
+            if !self.is_active() {
+                return Ok(());
+            }
+
+            assert_eq!(mem::size_of::<$field<$rustType>>(), $components * mem::size_of::<$rustType>());
+
+            unsafe {
+                $glFunc(self.0, values.len() as GLsizei, if transpose { TRUE } else { FALSE }, values.as_ptr() as *const $glType);
+            }
+
+            check_gl_errors!();
+
+            Ok(())
+        }
+    }
+}
+
 macro_rules! impl_nalgebra_alias_uniform {
     ($uFunc:ident:$rustType:ty => $name:ident($field:ident => ($($inner_field:ident),+))) => {
         #[inline(always)]
@@ -59,10 +114,17 @@ macro_rules! impl_nalgebra_alias_uniform {
     }
 }
 
-#[derive(Eq, PartialEq)]
+/// Location of a uniform in a shader program. Setting an inactive uniform, at location `-1`, does nothing.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct GLUniform(pub GLint);
 
 impl GLUniform {
+    /// Whether the uniform exists in its program, rather than being a placeholder from `get_uniform_optional`
+    #[inline(always)]
+    pub fn is_active(&self) -> bool {
+        self.0 != -1
+    }
+
     impl_scalar_uniform!(Uniform1f:f32 as GLfloat => float1(x));
     impl_scalar_uniform!(Uniform2f:f32 as GLfloat => float2(x, y));
     impl_scalar_uniform!(Uniform3f:f32 as GLfloat => float3(x, y, z));
@@ -134,4 +196,34 @@ impl GLUniform {
     impl_nalgebra_alias_uniform!(uint2:u32 => point2ui(Point2 => (x, y)));
     impl_nalgebra_alias_uniform!(uint3:u32 => point3ui(Point3 => (x, y, z)));
     impl_nalgebra_alias_uniform!(uint4:u32 => point4ui(Point4 => (x, y, z, w)));
+
+    /////////////////
+
+    impl_nalgebra_array_uniform!(Uniform2fv:f32 as GLfloat => vec2fv(Vector2; 2));
+    impl_nalgebra_array_uniform!(Uniform3fv:f32 as GLfloat => vec3fv(Vector3; 3));
+    impl_nalgebra_array_uniform!(Uniform4fv:f32 as GLfloat => vec4fv(Vector4; 4));
+
+    impl_nalgebra_array_uniform!(Uniform2iv:i32 as GLint => vec2iv(Vector2; 2));
+    impl_nalgebra_array_uniform!(Uniform3iv:i32 as GLint => vec3iv(Vector3; 3));
+    impl_nalgebra_array_uniform!(Uniform4iv:i32 as GLint => vec4iv(Vector4; 4));
+
+    impl_nalgebra_array_uniform!(Uniform2fv:f32 as GLfloat => point2fv(Point2; 2));
+    impl_nalgebra_array_uniform!(Uniform3fv:f32 as GLfloat => point3fv(Point3; 3));
+    impl_nalgebra_array_uniform!(Uniform4fv:f32 as GLfloat => point4fv(Point4; 4));
+
+    impl_matrix_array_uniform!(UniformMatrix2fv:f32 as GLfloat => mat2v(Matrix2; 4));
+    impl_matrix_array_uniform!(UniformMatrix3fv:f32 as GLfloat => mat3v(Matrix3; 9));
+    impl_matrix_array_uniform!(UniformMatrix4fv:f32 as GLfloat => mat4v(Matrix4; 16));
+
+    /////////////////
+
+    /// Booleans are set as integers, which is how GLSL expects them
+    #[inline(always)]
+    pub fn bool1(&mut self, value: bool) -> GLResult<()> {
+        self.int1(value as i32)
+    }
+
+    pub fn bool1v(&mut self, values: &[bool]) -> GLResult<()> {
+        self.int1v(values.iter().map(|&value| value as i32).collect())
+    }
 }
\ No newline at end of file
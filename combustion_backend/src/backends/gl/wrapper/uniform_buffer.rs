@@ -0,0 +1,178 @@
+//! Uniform buffer objects and helpers for the std140 layout they use
+
+use super::bindings::types::*;
+use super::bindings::*;
+use super::GLObject;
+
+use std::marker::PhantomData;
+use std::mem;
+
+use super::error::*;
+use super::buffer::{GLBuffer, GLBufferTarget, GLBufferUsage};
+
+/// Serializes values into a byte buffer following the std140 alignment rules.
+///
+/// Scalars are aligned to 4 bytes, `vec2` to 8, and `vec3`, `vec4`, matrices, structs and array elements to 16.
+#[derive(Debug, Default, Clone)]
+pub struct Std140Writer {
+    data: Vec<u8>,
+}
+
+impl Std140Writer {
+    pub fn new() -> Std140Writer {
+        Std140Writer { data: Vec::new() }
+    }
+
+    /// Number of bytes written so far, including padding
+    #[inline]
+    pub fn len(&self) -> usize { self.data.len() }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.data.is_empty() }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] { &self.data }
+
+    /// Clears the writer for reuse without freeing its memory
+    #[inline]
+    pub fn clear(&mut self) { self.data.clear(); }
+
+    /// Pads with zeroes until the length is a multiple of `alignment`
+    pub fn align(&mut self, alignment: usize) -> &mut Std140Writer {
+        while self.data.len() % alignment != 0 {
+            self.data.push(0);
+        }
+
+        self
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        let bytes: [u8; 4] = unsafe { mem::transmute(value.to_le()) };
+
+        self.data.extend_from_slice(&bytes);
+    }
+
+    fn write_floats(&mut self, alignment: usize, values: &[f32]) -> &mut Std140Writer {
+        self.align(alignment);
+
+        for value in values {
+            self.write_u32(unsafe { mem::transmute(*value) });
+        }
+
+        self
+    }
+
+    pub fn float(&mut self, value: f32) -> &mut Std140Writer {
+        self.write_floats(4, &[value])
+    }
+
+    pub fn int(&mut self, value: i32) -> &mut Std140Writer {
+        self.align(4);
+        self.write_u32(value as u32);
+        self
+    }
+
+    /// GLSL booleans are stored as 32-bit integers
+    pub fn boolean(&mut self, value: bool) -> &mut Std140Writer {
+        self.int(value as i32)
+    }
+
+    pub fn vec2(&mut self, value: [f32; 2]) -> &mut Std140Writer {
+        self.write_floats(8, &value)
+    }
+
+    /// A `vec3` is aligned like a `vec4`, but a following scalar may occupy its fourth component
+    pub fn vec3(&mut self, value: [f32; 3]) -> &mut Std140Writer {
+        self.write_floats(16, &value)
+    }
+
+    pub fn vec4(&mut self, value: [f32; 4]) -> &mut Std140Writer {
+        self.write_floats(16, &value)
+    }
+
+    /// Writes a column-major 4x4 matrix as four `vec4` columns
+    pub fn mat4(&mut self, columns: &[[f32; 4]; 4]) -> &mut Std140Writer {
+        for column in columns {
+            self.vec4(*column);
+        }
+
+        self
+    }
+
+    /// Writes a struct, which starts and ends on a 16-byte boundary
+    pub fn structure<T: Std140>(&mut self, value: &T) -> &mut Std140Writer {
+        self.align(16);
+        value.write_std140(self);
+        self.align(16)
+    }
+
+    /// Writes an array of structs, padding it with zeroed elements up to `length`
+    pub fn array<T: Std140 + Default>(&mut self, values: &[T], length: usize) -> &mut Std140Writer {
+        let padding = T::default();
+
+        for i in 0..length {
+            self.structure(values.get(i).unwrap_or(&padding));
+        }
+
+        self
+    }
+}
+
+/// Types that can be written into a uniform block with the std140 layout.
+///
+/// Implementations must write their fields in the same order as the GLSL declaration.
+pub trait Std140 {
+    fn write_std140(&self, writer: &mut Std140Writer);
+}
+
+/// Uniform buffer holding a single `T` in the std140 layout
+pub struct GLUniformBuffer<T: Std140> {
+    buffer: GLBuffer,
+    writer: Std140Writer,
+    allocated: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Std140> GLUniformBuffer<T> {
+    pub fn new() -> GLResult<GLUniformBuffer<T>> {
+        Ok(GLUniformBuffer {
+            buffer: try_rethrow!(GLBuffer::new(GLBufferTarget::UniformBuffer)),
+            writer: Std140Writer::new(),
+            allocated: 0,
+            _marker: PhantomData,
+        })
+    }
+
+    #[inline]
+    pub fn buffer(&self) -> &GLBuffer { &self.buffer }
+
+    /// Uploads the value. The storage is only reallocated when its size changes, otherwise it's updated in place.
+    pub fn update(&mut self, value: &T) -> GLResult<()> {
+        self.writer.clear();
+
+        value.write_std140(&mut self.writer);
+
+        self.writer.align(16);
+
+        let size = self.writer.len();
+
+        if size != self.allocated {
+            try_rethrow!(self.buffer.buffer_slice(self.writer.as_bytes(), GLBufferUsage::DynamicDraw));
+
+            self.allocated = size;
+        } else {
+            try_rethrow!(self.buffer.upload_sub(0, self.writer.as_bytes()));
+        }
+
+        Ok(())
+    }
+
+    /// Binds the buffer to the given uniform block binding point
+    pub fn bind_base(&self, binding: GLuint) -> GLResult<()> {
+        unsafe { BindBufferBase(UNIFORM_BUFFER, binding, self.buffer.raw()); }
+
+        check_gl_errors!();
+
+        Ok(())
+    }
+}
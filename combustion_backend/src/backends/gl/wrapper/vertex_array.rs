@@ -8,6 +8,9 @@ use std::sync::Arc;
 use std::os::raw::c_void;
 
 use super::error::*;
+use super::debug::label_object;
+use super::buffer::GLBuffer;
+use super::capabilities::GLCapabilities;
 
 #[derive(Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct GLVertexArray(GLuint);
@@ -31,10 +34,24 @@ impl GLBindable for GLVertexArray {
 impl GLVertexArray {
     pub fn default() -> GLVertexArray { GLVertexArray(0) }
 
+    /// Binds the vertex array until the returned binding is dropped, which restores whatever was bound before
+    pub fn bind_scoped(&self) -> GLResult<GLVertexArrayBinding> {
+        let previous = try_rethrow!(current_binding());
+
+        try_rethrow!(self.bind());
+
+        Ok(GLVertexArrayBinding { _vao: self, previous: previous })
+    }
+
     pub fn new() -> GLResult<GLVertexArray> {
         let mut vao = 0;
 
-        unsafe { GenVertexArrays(1, &mut vao); }
+        // Created vertex arrays exist right away, rather than on their first bind
+        if GLCapabilities::use_direct_state_access() {
+            unsafe { CreateVertexArrays(1, &mut vao); }
+        } else {
+            unsafe { GenVertexArrays(1, &mut vao); }
+        }
 
         check_gl_errors!();
 
@@ -45,6 +62,11 @@ impl GLVertexArray {
         Ok(GLVertexArray(vao))
     }
 
+    /// Names the vertex array in debuggers and debug messages
+    pub fn label(&self, label: &str) -> GLResult<()> {
+        label_object(VERTEX_ARRAY, self.0, label)
+    }
+
     pub fn delete(&mut self) -> GLResult<()> {
         if self.is_valid() {
             unsafe { DeleteVertexArrays(1, &self.0 as *const GLuint); }
@@ -61,3 +83,193 @@ impl Drop for GLVertexArray {
         self.delete().expect("Could not drop GLVertexArray")
     }
 }
+
+/// Queries the currently bound vertex array
+fn current_binding() -> GLResult<GLuint> {
+    let mut binding: GLint = 0;
+
+    unsafe { GetIntegerv(VERTEX_ARRAY_BINDING, &mut binding as *mut _); }
+
+    check_gl_errors!();
+
+    Ok(binding as GLuint)
+}
+
+/// Keeps a vertex array bound, restoring the previous one when dropped. See `GLVertexArray::bind_scoped`.
+pub struct GLVertexArrayBinding<'a> {
+    _vao: &'a GLVertexArray,
+    previous: GLuint,
+}
+
+impl<'a> Drop for GLVertexArrayBinding<'a> {
+    fn drop(&mut self) {
+        unsafe { BindVertexArray(self.previous); }
+    }
+}
+
+/// A single vertex attribute, reading from whatever buffer is bound to `GL_ARRAY_BUFFER` when it's applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GLVertexAttribute {
+    pub index: GLuint,
+    /// Number of components, from 1 to 4
+    pub size: GLint,
+    pub data_type: GLenum,
+    /// Whether fixed-point data is normalized to `[0, 1]` or `[-1, 1]`. Ignored for integer attributes.
+    pub normalized: bool,
+    /// Whether the data reaches the shader as integers rather than being converted to floats
+    pub integer: bool,
+    /// Bytes between consecutive elements, or zero if they are tightly packed
+    pub stride: usize,
+    /// Byte offset of the first element in the buffer
+    pub offset: usize,
+    /// Number of instances drawn before advancing to the next element, or zero to advance once per vertex
+    pub divisor: GLuint,
+}
+
+impl GLVertexAttribute {
+    /// A per-vertex float attribute
+    pub fn new(index: GLuint, size: GLint, data_type: GLenum, normalized: bool, stride: usize, offset: usize) -> GLVertexAttribute {
+        GLVertexAttribute {
+            index: index,
+            size: size,
+            data_type: data_type,
+            normalized: normalized,
+            integer: false,
+            stride: stride,
+            offset: offset,
+            divisor: 0,
+        }
+    }
+
+    /// A per-vertex integer attribute
+    pub fn integer(index: GLuint, size: GLint, data_type: GLenum, stride: usize, offset: usize) -> GLVertexAttribute {
+        GLVertexAttribute { integer: true, ..GLVertexAttribute::new(index, size, data_type, false, stride, offset) }
+    }
+
+    /// The same attribute, but advancing once every `divisor` instances
+    pub fn instanced(self, divisor: GLuint) -> GLVertexAttribute {
+        GLVertexAttribute { divisor: divisor, ..self }
+    }
+
+    /// Enables and points the attribute of the bound vertex array at the bound array buffer
+    pub fn apply(&self) -> GLResult<()> {
+        if self.size < 1 || self.size > 4 {
+            throw!(GLError::InvalidValue);
+        }
+
+        let offset = self.offset as *const c_void;
+
+        unsafe {
+            EnableVertexAttribArray(self.index);
+
+            if self.integer {
+                VertexAttribIPointer(self.index, self.size, self.data_type, self.stride as GLsizei, offset);
+            } else {
+                VertexAttribPointer(self.index, self.size, self.data_type,
+                                    if self.normalized { TRUE } else { FALSE }, self.stride as GLsizei, offset);
+            }
+
+            VertexAttribDivisor(self.index, self.divisor);
+        }
+
+        check_gl_errors!();
+
+        Ok(())
+    }
+}
+
+/// Vertex structs that are stored interleaved in a single buffer, with a fixed attribute layout
+pub trait GLVertexLayout: Copy {
+    /// Every attribute of the vertex, with the stride set to the size of the vertex
+    fn attributes() -> Vec<GLVertexAttribute>;
+}
+
+/// Creates a vertex array and describes its attributes.
+///
+/// The vertex array stays bound while building, and whatever was bound before is restored by `finish`.
+///
+/// ```ignore
+/// let vao = GLVertexArrayBuilder::new()?
+///     .buffer(&vertices)?
+///     .attribute(0, 3, FLOAT, false, stride, 0)?
+///     .attribute(1, 2, FLOAT, false, stride, 12)?
+///     .element_buffer(&indices)?
+///     .finish();
+/// ```
+pub struct GLVertexArrayBuilder {
+    vao: GLVertexArray,
+    previous: GLuint,
+}
+
+impl GLVertexArrayBuilder {
+    pub fn new() -> GLResult<GLVertexArrayBuilder> {
+        let previous = try_rethrow!(current_binding());
+
+        Ok(GLVertexArrayBuilder {
+            vao: try_rethrow!(GLVertexArray::new()),
+            previous: previous,
+        })
+    }
+
+    /// Makes the following attributes read from `buffer`, which should be an array buffer
+    pub fn buffer(self, buffer: &GLBuffer) -> GLResult<GLVertexArrayBuilder> {
+        unsafe { BindBuffer(ARRAY_BUFFER, buffer.raw()); }
+
+        check_gl_errors!();
+
+        Ok(self)
+    }
+
+    /// Attaches an index buffer, which unlike array buffers is part of the vertex array state
+    pub fn element_buffer(self, buffer: &GLBuffer) -> GLResult<GLVertexArrayBuilder> {
+        unsafe { BindBuffer(ELEMENT_ARRAY_BUFFER, buffer.raw()); }
+
+        check_gl_errors!();
+
+        Ok(self)
+    }
+
+    /// Adds a per-vertex float attribute
+    pub fn attribute(self, index: GLuint, size: GLint, data_type: GLenum, normalized: bool,
+                     stride: usize, offset: usize) -> GLResult<GLVertexArrayBuilder> {
+        self.custom(GLVertexAttribute::new(index, size, data_type, normalized, stride, offset))
+    }
+
+    /// Adds a per-vertex integer attribute, for `int` and `uint` shader inputs
+    pub fn integer_attribute(self, index: GLuint, size: GLint, data_type: GLenum,
+                             stride: usize, offset: usize) -> GLResult<GLVertexArrayBuilder> {
+        self.custom(GLVertexAttribute::integer(index, size, data_type, stride, offset))
+    }
+
+    /// Adds an attribute with any combination of settings, such as an instanced one
+    pub fn custom(self, attribute: GLVertexAttribute) -> GLResult<GLVertexArrayBuilder> {
+        try_rethrow!(attribute.apply());
+
+        Ok(self)
+    }
+
+    /// Makes an already added attribute advance once every `divisor` instances instead of once per vertex
+    pub fn divisor(self, index: GLuint, divisor: GLuint) -> GLResult<GLVertexArrayBuilder> {
+        unsafe { VertexAttribDivisor(index, divisor); }
+
+        check_gl_errors!();
+
+        Ok(self)
+    }
+
+    /// Adds every attribute of the vertex struct `V`, read from the current buffer
+    pub fn with_layout<V: GLVertexLayout>(mut self) -> GLResult<GLVertexArrayBuilder> {
+        for attribute in V::attributes() {
+            self = try_rethrow!(self.custom(attribute));
+        }
+
+        Ok(self)
+    }
+
+    /// Restores the previously bound vertex array and returns the new one
+    pub fn finish(self) -> GLVertexArray {
+        unsafe { BindVertexArray(self.previous); }
+
+        self.vao
+    }
+}
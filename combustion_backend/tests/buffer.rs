@@ -0,0 +1,92 @@
+extern crate glfw;
+extern crate combustion_backend as backend;
+
+mod support;
+
+use backend::gl::*;
+
+use support::with_context;
+
+const DATA: [u32; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+fn read_back(buffer: &mut GLBuffer) -> Vec<u32> {
+    let len = buffer.num_elements::<u32>();
+
+    let mapping = buffer.map_range::<u32>(0, len).unwrap();
+
+    mapping.to_vec()
+}
+
+#[test]
+#[ignore]
+fn test_upload_tracks_size() {
+    with_context(|| {
+        let mut buffer = GLBuffer::array_buffer().unwrap();
+
+        assert_eq!(buffer.target(), GLBufferTarget::ArrayBuffer);
+        assert_eq!(buffer.num_bytes(), 0);
+
+        buffer.upload(&DATA, GLBufferUsage::StaticDraw).unwrap();
+
+        assert_eq!(buffer.num_bytes(), 32);
+        assert_eq!(buffer.num_elements::<u32>(), 8);
+
+        assert_eq!(read_back(&mut buffer), DATA.to_vec());
+    });
+}
+
+#[test]
+#[ignore]
+fn test_upload_sub() {
+    with_context(|| {
+        let mut buffer = GLBuffer::array_buffer().unwrap();
+
+        buffer.upload(&DATA, GLBufferUsage::DynamicDraw).unwrap();
+
+        buffer.upload_sub(6, &[70u32, 80]).unwrap();
+
+        assert_eq!(buffer.num_bytes(), 32);
+        assert_eq!(read_back(&mut buffer), vec![1, 2, 3, 4, 5, 6, 70, 80]);
+    });
+}
+
+#[test]
+#[ignore]
+fn test_upload_sub_out_of_bounds() {
+    with_context(|| {
+        let mut buffer = GLBuffer::uniform_buffer().unwrap();
+
+        buffer.upload(&DATA, GLBufferUsage::DynamicDraw).unwrap();
+
+        assert!(buffer.upload_sub(7, &[0u32, 0]).is_err());
+        assert!(buffer.upload_sub::<u32>(0, &[]).is_err());
+        assert!(buffer.upload_sub(usize::max_value(), &[0u32]).is_err());
+
+        // Nothing was written by the failed uploads
+        assert_eq!(read_back(&mut buffer), DATA.to_vec());
+    });
+}
+
+#[test]
+#[ignore]
+fn test_map_range_write() {
+    with_context(|| {
+        let mut buffer = GLBuffer::array_buffer().unwrap();
+
+        buffer.upload(&DATA, GLBufferUsage::DynamicDraw).unwrap();
+
+        {
+            let mut mapping = buffer.map_range_write::<u32>(2, 3).unwrap();
+
+            assert_eq!(mapping.len(), 3);
+
+            mapping.fill(0);
+
+            mapping.unmap().unwrap();
+        }
+
+        assert_eq!(read_back(&mut buffer), vec![1, 2, 0, 0, 0, 6, 7, 8]);
+
+        assert!(buffer.map_range::<u32>(6, 3).is_err());
+    });
+}
@@ -0,0 +1,39 @@
+extern crate glfw;
+extern crate combustion_backend as backend;
+
+mod support;
+
+use backend::gl::*;
+
+use support::with_context;
+
+#[test]
+#[ignore]
+fn test_query_meets_minimums() {
+    with_context(|| {
+        let capabilities = GLCapabilities::query().unwrap();
+
+        // Minimums required by the OpenGL 3.3 specification
+        assert!(capabilities.version >= (3, 3));
+        assert!(capabilities.max_texture_size >= 1024);
+        assert!(capabilities.max_color_attachments >= 8);
+        assert!(capabilities.max_draw_buffers >= 8);
+        assert!(capabilities.max_samples >= 4);
+        assert!(capabilities.max_anisotropy >= 1.0);
+        assert!(capabilities.rgtc_compression);
+
+        assert!(!format!("{}", capabilities).is_empty());
+    });
+}
+
+#[test]
+#[ignore]
+fn test_current_reuses_query() {
+    with_context(|| {
+        let queried = GLCapabilities::query().unwrap();
+        let current = GLCapabilities::current().unwrap();
+
+        assert_eq!(queried.max_texture_size, current.max_texture_size);
+        assert_eq!(queried.extensions, current.extensions);
+    });
+}
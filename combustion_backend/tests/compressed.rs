@@ -0,0 +1,96 @@
+extern crate glfw;
+extern crate combustion_backend as backend;
+
+mod support;
+
+use backend::gl::*;
+use backend::gl::bindings as glb;
+
+fn with_context<F>(f: F) where F: FnOnce() {
+    support::with_context(|| {
+        GLCapabilities::query().unwrap();
+
+        f()
+    })
+}
+
+#[test]
+fn test_block_sizes() {
+    let dxt1 = GLCompressedBlock::of(glb::COMPRESSED_RGBA_S3TC_DXT1_EXT).unwrap();
+    let dxt5 = GLCompressedBlock::of(glb::COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT).unwrap();
+    let astc = GLCompressedBlock::of(glb::COMPRESSED_SRGB8_ALPHA8_ASTC_12x10_KHR).unwrap();
+
+    assert_eq!(dxt1.level_size(4, 4), 8);
+    assert_eq!(dxt5.level_size(5, 5), 64);
+    // Smaller than a block still takes a whole one
+    assert_eq!(dxt5.level_size(1, 1), 16);
+    assert_eq!(astc.level_size(13, 10), 32);
+
+    assert_eq!(GLCompressionFamily::of(glb::COMPRESSED_RG_RGTC2), Some(GLCompressionFamily::Rgtc));
+    assert_eq!(GLCompressionFamily::of(glb::COMPRESSED_RGBA_ASTC_8x8_KHR), Some(GLCompressionFamily::Astc));
+    assert_eq!(GLCompressedBlock::of(glb::RGBA8), None);
+}
+
+#[test]
+#[ignore]
+fn test_upload_mip_chain() {
+    with_context(|| {
+        let mut texture = GLTexture2D::new().unwrap();
+
+        // RGTC is core since OpenGL 3.0, 8 bytes per 4x4 block
+        let blocks = [0u8; 8 * 4];
+
+        let levels = [
+            GLCompressedLevel { width: 8, height: 8, data: &blocks[..32] },
+            GLCompressedLevel { width: 4, height: 4, data: &blocks[..8] },
+            GLCompressedLevel { width: 2, height: 2, data: &blocks[..8] },
+        ];
+
+        texture.upload_compressed_mips(glb::COMPRESSED_RED_RGTC1, &levels).unwrap();
+
+        assert_eq!(texture.internal_format(), Some(glb::COMPRESSED_RED_RGTC1));
+
+        texture.bind().unwrap();
+
+        let (mut compressed, mut max_level) = (0, 0);
+
+        unsafe {
+            glb::GetTexLevelParameteriv(glb::TEXTURE_2D, 2, glb::TEXTURE_COMPRESSED, &mut compressed);
+            glb::GetTexParameteriv(glb::TEXTURE_2D, glb::TEXTURE_MAX_LEVEL, &mut max_level);
+        }
+
+        assert_eq!(compressed, glb::TRUE as _);
+        assert_eq!(max_level, 2);
+    });
+}
+
+#[test]
+#[ignore]
+fn test_invalid_uploads() {
+    with_context(|| {
+        let mut texture = GLTexture2D::new().unwrap();
+
+        // One byte short of a block
+        match texture.upload_compressed(0, glb::COMPRESSED_RED_RGTC1, 4, 4, &[0; 7]).unwrap_err().into_error() {
+            GLError::InvalidValue => {}
+            other => panic!("Expected an invalid value, got {:?}", other),
+        }
+
+        // The second level should be 4x4
+        let levels = [
+            GLCompressedLevel { width: 8, height: 8, data: &[0; 32] },
+            GLCompressedLevel { width: 4, height: 2, data: &[0; 8] },
+        ];
+
+        assert!(texture.upload_compressed_mips(glb::COMPRESSED_RED_RGTC1, &levels).is_err());
+
+        if !GLCapabilities::current().unwrap().astc_compression {
+            match texture.upload_compressed(0, glb::COMPRESSED_RGBA_ASTC_4x4_KHR, 4, 4, &[0; 16]).unwrap_err().into_error() {
+                GLError::UnsupportedFormat(format) => assert_eq!(format, glb::COMPRESSED_RGBA_ASTC_4x4_KHR),
+                other => panic!("Expected an unsupported format, got {:?}", other),
+            }
+        }
+
+        GLError::check().unwrap();
+    });
+}
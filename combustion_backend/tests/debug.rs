@@ -0,0 +1,102 @@
+extern crate glfw;
+#[macro_use]
+extern crate combustion_backend as backend;
+
+mod support;
+
+use backend::gl::*;
+use backend::gl::types::*;
+use backend::gl::bindings as glb;
+
+use support::with_context;
+
+fn object_label(identifier: GLenum, object: GLuint) -> String {
+    let mut label = vec![0u8; 64];
+    let mut length: GLsizei = 0;
+
+    unsafe {
+        glb::GetObjectLabel(identifier, object, label.len() as GLsizei, &mut length as *mut _, label.as_mut_ptr() as *mut _);
+    }
+
+    label.truncate(length as usize);
+
+    String::from_utf8(label).unwrap()
+}
+
+fn nested_groups() -> GLResult<()> {
+    gl_debug_group!("outer");
+
+    {
+        gl_debug_group!("inner");
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn test_labels() {
+    with_context(|| {
+        // Without KHR_debug labels do nothing, so there is nothing to check
+        if !glb::GetObjectLabel::is_loaded() {
+            return;
+        }
+
+        let buffer = GLBuffer::array_buffer().unwrap();
+        let texture = GLTexture2D::new().unwrap();
+
+        buffer.label("vertices").unwrap();
+        texture.label("albedo").unwrap();
+
+        assert_eq!(object_label(glb::BUFFER, buffer.raw()), "vertices");
+        assert_eq!(object_label(glb::TEXTURE, texture.raw()), "albedo");
+    });
+}
+
+#[test]
+#[ignore]
+fn test_debug_groups_balanced() {
+    with_context(|| {
+        nested_groups().unwrap();
+
+        let mut depth: GLint = 0;
+
+        unsafe { glb::GetIntegerv(glb::DEBUG_GROUP_STACK_DEPTH, &mut depth as *mut _); }
+
+        // Only the default group is left once every guard is dropped
+        assert_eq!(depth, 1);
+    });
+}
+
+#[test]
+fn test_severity_order() {
+    assert!(GLDebugSeverity::High.at_least(GLDebugSeverity::Medium));
+    assert!(GLDebugSeverity::Low.at_least(GLDebugSeverity::Low));
+    // DEBUG_SEVERITY_NOTIFICATION has the smallest value, but is the least severe
+    assert!(!GLDebugSeverity::Notification.at_least(GLDebugSeverity::Low));
+}
+
+#[test]
+fn test_filter_allows() {
+    let filter = GLDebugFilter {
+        min_severity: GLDebugSeverity::Medium,
+        allowed_sources: Some(vec![GLDebugSource::Api, GLDebugSource::ShaderCompiler]),
+        ..GLDebugFilter::default()
+    };
+
+    assert!(filter.allows(GLDebugSource::Api, GLDebugType::Error, GLDebugSeverity::High));
+    assert!(!filter.allows(GLDebugSource::Api, GLDebugType::Performance, GLDebugSeverity::Low));
+    assert!(!filter.allows(GLDebugSource::ThirdParty, GLDebugType::Error, GLDebugSeverity::High));
+}
+
+#[test]
+fn test_mute_list() {
+    mute_message(7);
+    mute_message(7);
+
+    assert_eq!(DEBUG_IGNORED.read().unwrap().iter().filter(|&&id| id == 7).count(), 1);
+
+    unmute_message(7);
+
+    assert!(!DEBUG_IGNORED.read().unwrap().contains(&7));
+}
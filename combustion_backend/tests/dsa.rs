@@ -0,0 +1,168 @@
+//! Each test runs with and without direct state access and checks that both give the same results.
+//! Drivers without direct state access only run the bind-based path.
+
+extern crate glfw;
+extern crate combustion_backend as backend;
+
+mod support;
+
+use std::ptr;
+use std::fmt::Debug;
+
+use backend::gl::*;
+use backend::gl::types::*;
+use backend::gl::bindings as glb;
+
+fn with_context<F>(f: F) where F: FnOnce() {
+    support::with_context(|| {
+        GLCapabilities::query().unwrap();
+
+        f()
+    })
+}
+
+/// Runs `f` with the bind-based path, then with direct state access if the driver has it, and compares the results
+fn both_paths<T: PartialEq + Debug, F: Fn() -> T>(f: F) {
+    GLCapabilities::set_direct_state_access_enabled(false);
+
+    assert!(!GLCapabilities::use_direct_state_access());
+
+    let fallback = f();
+
+    GLCapabilities::set_direct_state_access_enabled(true);
+
+    if GLCapabilities::use_direct_state_access() {
+        assert_eq!(f(), fallback);
+    }
+}
+
+fn texture_parameter(kind: GLenum, name: GLenum) -> GLint {
+    let mut value = 0;
+
+    unsafe { glb::GetTexParameteriv(kind, name, &mut value); }
+
+    value
+}
+
+fn texture_parameter_f(kind: GLenum, name: GLenum) -> GLfloat {
+    let mut value = 0.0;
+
+    unsafe { glb::GetTexParameterfv(kind, name, &mut value); }
+
+    value
+}
+
+#[test]
+#[ignore]
+fn test_texture_parameters() {
+    with_context(|| {
+        both_paths(|| {
+            let mut texture = GLTexture2D::new().unwrap();
+
+            texture.set_wrap_axes(GLTextureWrap::ClampToBorder, GLTextureWrap::MirroredRepeat, GLTextureWrap::Repeat).unwrap();
+            texture.set_filtering(GLTextureFilter::Nearest, Some(GLTextureFilter::Linear)).unwrap();
+            texture.set_lod_range(1.0, 4.0, 0.5).unwrap();
+            texture.set_border_color([0.25, 0.5, 0.75, 1.0]).unwrap();
+
+            // Unbound so reading the parameters back can't see state left over from the bind-based path
+            unsafe { glb::BindTexture(glb::TEXTURE_2D, 0); }
+
+            GLStateCache::invalidate();
+
+            texture.bind().unwrap();
+
+            let mut border = [0.0f32; 4];
+
+            unsafe { glb::GetTexParameterfv(glb::TEXTURE_2D, glb::TEXTURE_BORDER_COLOR, border.as_mut_ptr()); }
+
+            (texture_parameter(glb::TEXTURE_2D, glb::TEXTURE_WRAP_S),
+             texture_parameter(glb::TEXTURE_2D, glb::TEXTURE_WRAP_T),
+             texture_parameter(glb::TEXTURE_2D, glb::TEXTURE_MIN_FILTER),
+             texture_parameter(glb::TEXTURE_2D, glb::TEXTURE_MAG_FILTER),
+             texture_parameter_f(glb::TEXTURE_2D, glb::TEXTURE_MIN_LOD),
+             texture_parameter_f(glb::TEXTURE_2D, glb::TEXTURE_MAX_LOD),
+             texture_parameter_f(glb::TEXTURE_2D, glb::TEXTURE_LOD_BIAS),
+             border)
+        });
+    });
+}
+
+#[test]
+#[ignore]
+fn test_buffer_storage() {
+    with_context(|| {
+        both_paths(|| {
+            let mut buffer = GLBuffer::array_buffer().unwrap();
+
+            buffer.upload(&[1u32, 2, 3, 4, 5, 6], GLBufferUsage::DynamicDraw).unwrap();
+            buffer.upload_sub(1, &[20u32, 30]).unwrap();
+
+            {
+                let mut mapping = buffer.map_range_write::<u32>(4, 2).unwrap();
+
+                mapping.write(0, &[50, 60]).unwrap();
+
+                mapping.unmap().unwrap();
+            }
+
+            let contents = buffer.map_range::<u32>(0, 6).unwrap().to_vec();
+
+            let mut allocated = GLBuffer::new(GLBufferTarget::PixelPackBuffer).unwrap();
+
+            allocated.allocate(64, GLBufferUsage::StreamRead).unwrap();
+
+            (contents, buffer.num_bytes(), allocated.num_bytes())
+        });
+    });
+}
+
+#[test]
+#[ignore]
+fn test_vertex_array_creation() {
+    with_context(|| {
+        both_paths(|| {
+            let vao = GLVertexArray::new().unwrap();
+
+            let mut bound = 0;
+
+            unsafe { glb::GetIntegerv(glb::VERTEX_ARRAY_BINDING, &mut bound); }
+
+            (vao.is_valid(), bound as GLuint == vao.raw())
+        });
+    });
+}
+
+#[test]
+#[ignore]
+fn test_framebuffer_texture() {
+    with_context(|| {
+        both_paths(|| {
+            let mut texture = GLTexture2D::new().unwrap();
+
+            unsafe {
+                glb::TexImage2D(glb::TEXTURE_2D, 0, glb::RGBA8 as _, 4, 4, 0, glb::RGBA, glb::UNSIGNED_BYTE, ptr::null());
+            }
+
+            texture.set_filtering(GLTextureFilter::Nearest, None).unwrap();
+
+            let mut framebuffer = GLFramebuffer::new().unwrap();
+
+            framebuffer.texture(glb::COLOR_ATTACHMENT0, &texture).unwrap();
+
+            let mut attached = 0;
+
+            framebuffer.bind().unwrap();
+
+            unsafe {
+                glb::GetFramebufferAttachmentParameteriv(glb::FRAMEBUFFER, glb::COLOR_ATTACHMENT0,
+                                                         glb::FRAMEBUFFER_ATTACHMENT_OBJECT_NAME, &mut attached);
+            }
+
+            let complete = framebuffer.is_complete().unwrap();
+
+            DEFAULT_FRAMEBUFFER.bind().unwrap();
+
+            (attached as GLuint == texture.raw(), complete)
+        });
+    });
+}
@@ -0,0 +1,110 @@
+extern crate glfw;
+#[macro_use]
+extern crate trace_error;
+#[macro_use]
+extern crate combustion_backend as backend;
+
+mod support;
+
+use backend::gl::*;
+use backend::gl::bindings as glb;
+
+use support::with_context;
+
+#[test]
+fn test_driver_error_display() {
+    let err = GLError::Driver(vec![GLError::InvalidEnum, GLError::InvalidValue],
+                              GLErrorContext::new("src/texture.rs", 42, Some("TexImage2D for \"albedo.png\"".to_string())));
+
+    assert_eq!(err.to_string(), "Invalid Enum, Invalid Value at src/texture.rs:42 during TexImage2D for \"albedo.png\"");
+
+    let err = GLError::Driver(vec![GLError::InvalidOperation], GLErrorContext { location: None, operation: None });
+
+    assert_eq!(err.to_string(), "Invalid Operation at unknown location");
+}
+
+#[test]
+fn test_fatal_errors() {
+    let context = GLErrorContext::new("src/render.rs", 1, None);
+
+    assert!(!GLError::Driver(vec![GLError::InvalidValue], context.clone()).is_fatal());
+    assert!(GLError::Driver(vec![GLError::InvalidValue, GLError::OutOfMemory], context.clone()).is_fatal());
+    assert!(GLError::ContextLost.is_fatal());
+    assert!(!GLError::IncompleteFramebuffer.is_fatal());
+}
+
+#[test]
+#[ignore]
+fn test_check_drains_every_flag() {
+    with_context(|| {
+        unsafe {
+            glb::Enable(0xFFFF);
+            glb::Viewport(0, 0, -1, -1);
+        }
+
+        let err = GLError::check_at("test.rs", 7, Some("invalid calls".to_string())).unwrap_err().into_error();
+
+        match err {
+            GLError::Driver(errors, context) => {
+                assert_eq!(errors.len(), 2);
+
+                match (&errors[0], &errors[1]) {
+                    (&GLError::InvalidEnum, &GLError::InvalidValue) => {}
+                    other => panic!("Unexpected errors {:?}", other),
+                }
+
+                assert_eq!(context, GLErrorContext::new("test.rs", 7, Some("invalid calls".to_string())));
+            }
+            other => panic!("Expected driver errors, got {:?}", other),
+        }
+
+        // Nothing is left over for the next check
+        GLError::check().unwrap();
+    });
+}
+
+#[test]
+#[ignore]
+fn test_disable_check() {
+    with_context(|| {
+        unsafe {
+            GLError::disable_check();
+
+            glb::Enable(0xFFFF);
+        }
+
+        GLError::check().unwrap();
+
+        unsafe { GLError::enable_check(); }
+
+        // The flag is still set, since nothing was drained while disabled
+        assert!(GLError::check().is_err());
+    });
+}
+
+fn enable_invalid() -> GLResult<()> {
+    unsafe { glb::Enable(0xFFFF); }
+
+    check_gl_errors!("enabling an invalid capability");
+
+    Ok(())
+}
+
+fn checked_value() -> GLResult<u32> {
+    Ok(check_gl_errors!(=> 7))
+}
+
+#[test]
+#[ignore]
+fn test_check_macro_forms() {
+    with_context(|| {
+        match enable_invalid().unwrap_err().into_error() {
+            GLError::Driver(_, context) => {
+                assert_eq!(context.operation, Some("enabling an invalid capability".to_string()));
+            }
+            other => panic!("Expected driver errors, got {:?}", other),
+        }
+
+        assert_eq!(checked_value().unwrap(), 7);
+    });
+}
@@ -0,0 +1,58 @@
+extern crate glfw;
+extern crate combustion_backend as backend;
+
+mod support;
+
+use std::mem;
+
+use backend::gl::*;
+use backend::gl::bindings as glb;
+
+fn with_context<F>(f: F) where F: FnOnce() {
+    support::with_context(|| {
+        GLCapabilities::query().unwrap();
+
+        f()
+    })
+}
+
+#[test]
+fn test_command_layout() {
+    // Has to match DrawElementsIndirectCommand exactly
+    assert_eq!(mem::size_of::<GLDrawCommand>(), 20);
+
+    let command = GLDrawCommand::new(36, 72, 24).with_instances(1, 3);
+
+    assert_eq!(command, GLDrawCommand { count: 36, instance_count: 1, first_index: 72, base_vertex: 24, base_instance: 3 });
+}
+
+#[test]
+#[ignore]
+fn test_update_and_draw() {
+    with_context(|| {
+        let mut indirect = GLDrawIndirectBuffer::new().unwrap();
+
+        let commands = [
+            GLDrawCommand::new(3, 0, 0),
+            GLDrawCommand::new(3, 3, 4),
+        ];
+
+        indirect.update(&commands).unwrap();
+
+        assert_eq!(indirect.commands(), &commands[..]);
+
+        let vertex_array = GLVertexArray::new().unwrap();
+        let mut indices = GLBuffer::new(GLBufferTarget::ElementArrayBuffer).unwrap();
+
+        vertex_array.bind().unwrap();
+        indices.upload(&[0u32, 1, 2, 0, 1, 2], GLBufferUsage::StaticDraw).unwrap();
+
+        // Drawn with multi-draw indirect on OpenGL 4.3, and one at a time otherwise
+        indirect.draw(glb::TRIANGLES, glb::UNSIGNED_INT).unwrap();
+        indirect.draw_range(glb::TRIANGLES, glb::UNSIGNED_INT, 1..2).unwrap();
+
+        assert!(indirect.draw_range(glb::TRIANGLES, glb::UNSIGNED_INT, 1..3).is_err());
+
+        GLError::check().unwrap();
+    });
+}
@@ -0,0 +1,90 @@
+extern crate glfw;
+extern crate combustion_backend as backend;
+
+mod support;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::prelude::*;
+
+use backend::gl::*;
+
+use support::with_context;
+
+const VERTEX_SHADER: &'static str = "#version 330 core
+layout (location = 0) in vec2 position;
+
+void main() {
+    gl_Position = vec4(position, 0.0, 1.0);
+}";
+
+const FRAGMENT_SHADER: &'static str = "#version 330 core
+uniform vec4 fill;
+
+out vec4 color;
+
+void main() {
+    color = fill;
+}";
+
+fn build_program() -> GLShaderProgram {
+    GLShaderProgramBuilder::new().unwrap()
+        .attach_source(VERTEX_SHADER.to_string(), GLShaderVariant::VertexShader).unwrap()
+        .attach_source(FRAGMENT_SHADER.to_string(), GLShaderVariant::FragmentShader).unwrap()
+        .link().unwrap()
+        .finish()
+}
+
+fn cache_entries() -> Vec<::std::path::PathBuf> {
+    let directory = program_cache_directory().unwrap().unwrap();
+
+    fs::read_dir(directory).unwrap().map(|entry| entry.unwrap().path()).collect()
+}
+
+// The cache directory is global, so everything runs in one test rather than racing other threads
+#[test]
+#[ignore]
+fn test_program_cache() {
+    with_context(|| {
+        let directory = env::temp_dir().join("combustion_program_cache_test");
+
+        let _ = fs::remove_dir_all(&directory);
+
+        set_program_cache_directory(Some(directory.clone())).unwrap();
+
+        // Without any program binary formats nothing is ever cached, so there's nothing to test
+        if !is_program_cache_enabled().unwrap() {
+            return;
+        }
+
+        // First link compiles and caches the program
+        let program = build_program();
+
+        assert!(program.get_uniform("fill").unwrap().0 >= 0);
+
+        let entries = cache_entries();
+
+        assert_eq!(entries.len(), 1);
+
+        // Second one loads it
+        let program = build_program();
+
+        assert_eq!(program.get_info(GLProgramInfo::AttachedShaders).unwrap(), 0);
+        assert!(program.get_uniform("fill").unwrap().0 >= 0);
+
+        // Corrupted entries are discarded and replaced
+        File::create(&entries[0]).unwrap().write_all(b"not a program binary").unwrap();
+
+        let program = build_program();
+
+        assert_eq!(program.get_info(GLProgramInfo::AttachedShaders).unwrap(), 2);
+
+        let build_again = build_program();
+
+        assert_eq!(build_again.get_info(GLProgramInfo::AttachedShaders).unwrap(), 0);
+
+        set_program_cache_directory(None::<&str>).unwrap();
+
+        let _ = fs::remove_dir_all(&directory);
+    });
+}
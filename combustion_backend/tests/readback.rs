@@ -0,0 +1,125 @@
+extern crate glfw;
+extern crate combustion_backend as backend;
+
+mod support;
+
+use std::mem;
+use std::ptr;
+
+use backend::gl::*;
+use backend::gl::types::*;
+use backend::gl::bindings as glb;
+
+use support::with_context;
+
+const SIZE: usize = 4;
+
+const VERTEX_SHADER: &'static str = "#version 330 core
+layout (location = 0) in vec2 position;
+
+void main() {
+    gl_Position = vec4(position, 0.0, 1.0);
+}";
+
+const FRAGMENT_SHADER: &'static str = "#version 330 core
+uniform vec4 fill;
+
+out vec4 color;
+
+void main() {
+    color = fill;
+}";
+
+/// A single triangle much larger than the viewport, so it covers every pixel
+const TRIANGLE: [f32; 6] = [-1.0, -1.0, 3.0, -1.0, -1.0, 3.0];
+
+/// Renders the triangle with the given color into a framebuffer with a single attachment of the given format
+fn render_triangle(format: GLenum, internal_format: GLenum, fill: [f32; 4]) -> (GLFramebuffer, GLTexture) {
+    let framebuffer = GLFramebuffer::new().unwrap();
+
+    let mut texture = GLTexture::new(GLTextureKind::Texture2D).unwrap();
+
+    texture.load_empty(SIZE, SIZE, format, internal_format).unwrap();
+
+    unsafe {
+        glb::FramebufferTexture2D(glb::FRAMEBUFFER, glb::COLOR_ATTACHMENT0, glb::TEXTURE_2D, texture.raw(), 0);
+    }
+
+    assert!(framebuffer.is_complete().unwrap());
+
+    let shader = GLShaderProgramBuilder::new().unwrap()
+        .attach_shader(GLShader::from_source(VERTEX_SHADER.to_string(), GLShaderVariant::VertexShader).unwrap()).unwrap()
+        .attach_shader(GLShader::from_source(FRAGMENT_SHADER.to_string(), GLShaderVariant::FragmentShader).unwrap()).unwrap()
+        .link().unwrap()
+        .finish();
+
+    let vao = GLVertexArray::new().unwrap();
+
+    vao.bind().unwrap();
+
+    let mut buffer = GLBuffer::new(GLBufferTarget::ArrayBuffer).unwrap();
+
+    buffer.buffer_slice(&TRIANGLE, GLBufferUsage::StaticDraw).unwrap();
+
+    shader.use_program().unwrap();
+    shader.get_uniform("fill").unwrap().float4(fill[0], fill[1], fill[2], fill[3]).unwrap();
+
+    framebuffer.bind().unwrap();
+
+    unsafe {
+        glb::Viewport(0, 0, SIZE as GLsizei, SIZE as GLsizei);
+
+        glb::ClearColor(0.0, 0.0, 0.0, 0.0);
+        glb::Clear(glb::COLOR_BUFFER_BIT);
+
+        glb::EnableVertexAttribArray(0);
+        glb::VertexAttribPointer(0, 2, glb::FLOAT, glb::FALSE, 2 * mem::size_of::<f32>() as GLsizei, ptr::null());
+
+        glb::DrawArrays(glb::TRIANGLES, 0, 3);
+    }
+
+    (framebuffer, texture)
+}
+
+#[test]
+#[ignore]
+fn test_read_pixels_solid_color() {
+    with_context(|| {
+        let (framebuffer, _texture) = render_triangle(glb::RGBA, glb::RGBA8, [1.0, 0.0, 1.0, 1.0]);
+
+        let pixels = framebuffer.read_pixels(0, SIZE, SIZE, glb::RGBA, glb::UNSIGNED_BYTE).unwrap();
+
+        assert_eq!(pixels.len(), SIZE * SIZE * 4);
+
+        for pixel in pixels.chunks(4) {
+            assert_eq!(pixel, &[255, 0, 255, 255]);
+        }
+    });
+}
+
+#[test]
+#[ignore]
+fn test_read_pixels_float() {
+    with_context(|| {
+        // Outside of [0, 1], which would be clamped by an unsigned byte readback
+        let (framebuffer, _texture) = render_triangle(glb::RGBA, glb::RGBA32F, [2.5, -1.0, 0.25, 1.0]);
+
+        let pixels = framebuffer.read_pixels_f32(0, SIZE, SIZE, glb::RGBA).unwrap();
+
+        assert_eq!(pixels.len(), SIZE * SIZE * 4);
+
+        for pixel in pixels.chunks(4) {
+            assert_eq!(pixel, &[2.5, -1.0, 0.25, 1.0]);
+        }
+    });
+}
+
+#[test]
+#[ignore]
+fn test_read_pixels_invalid_format() {
+    with_context(|| {
+        let (framebuffer, _texture) = render_triangle(glb::RGBA, glb::RGBA8, [1.0; 4]);
+
+        assert!(framebuffer.read_pixels(0, SIZE, SIZE, glb::RGBA, glb::UNSIGNED_INT_8_8_8_8).is_err());
+    });
+}
@@ -0,0 +1,77 @@
+extern crate glfw;
+extern crate combustion_backend as backend;
+
+mod support;
+
+use std::ptr;
+
+use backend::gl::*;
+use backend::gl::types::*;
+use backend::gl::bindings as glb;
+
+use support::with_context;
+
+fn renderbuffer_parameter(renderbuffer: &GLRenderbuffer, name: GLenum) -> GLint {
+    let mut value = 0;
+
+    renderbuffer.bind().unwrap();
+
+    unsafe { glb::GetRenderbufferParameteriv(glb::RENDERBUFFER, name, &mut value); }
+
+    value
+}
+
+#[test]
+#[ignore]
+fn test_storage_formats() {
+    with_context(|| {
+        let mut renderbuffer = GLRenderbuffer::new().unwrap();
+
+        renderbuffer.set_storage(glb::STENCIL_INDEX8, 16, 8).unwrap();
+
+        assert_eq!(renderbuffer_parameter(&renderbuffer, glb::RENDERBUFFER_INTERNAL_FORMAT) as GLenum, glb::STENCIL_INDEX8);
+        assert_eq!(renderbuffer_parameter(&renderbuffer, glb::RENDERBUFFER_WIDTH), 16);
+        assert_eq!(renderbuffer_parameter(&renderbuffer, glb::RENDERBUFFER_HEIGHT), 8);
+
+        renderbuffer.set_storage_multisample(4, glb::DEPTH24_STENCIL8, 32, 32).unwrap();
+
+        assert_eq!(renderbuffer_parameter(&renderbuffer, glb::RENDERBUFFER_INTERNAL_FORMAT) as GLenum, glb::DEPTH24_STENCIL8);
+        assert!(renderbuffer_parameter(&renderbuffer, glb::RENDERBUFFER_SAMPLES) >= 4);
+    });
+}
+
+#[test]
+#[ignore]
+fn test_depth_stencil_attachment() {
+    with_context(|| {
+        let color = GLTexture2D::new().unwrap();
+
+        unsafe {
+            glb::TexImage2D(glb::TEXTURE_2D, 0, glb::RGBA8 as _, 4, 4, 0, glb::RGBA, glb::UNSIGNED_BYTE, ptr::null());
+        }
+
+        let mut depth_stencil = GLRenderbuffer::new().unwrap();
+
+        depth_stencil.set_storage(glb::DEPTH24_STENCIL8, 4, 4).unwrap();
+
+        let mut framebuffer = GLFramebuffer::new().unwrap();
+
+        framebuffer.texture(glb::COLOR_ATTACHMENT0, &color).unwrap();
+        framebuffer.renderbuffer(glb::DEPTH_STENCIL_ATTACHMENT, &depth_stencil).unwrap();
+
+        assert!(framebuffer.is_complete().unwrap());
+
+        let mut stencil_bits = 0;
+
+        framebuffer.bind().unwrap();
+
+        unsafe {
+            glb::GetFramebufferAttachmentParameteriv(glb::FRAMEBUFFER, glb::STENCIL_ATTACHMENT,
+                                                     glb::FRAMEBUFFER_ATTACHMENT_STENCIL_SIZE, &mut stencil_bits);
+        }
+
+        assert_eq!(stencil_bits, 8);
+
+        DEFAULT_FRAMEBUFFER.bind().unwrap();
+    });
+}
@@ -0,0 +1,113 @@
+extern crate glfw;
+extern crate combustion_backend as backend;
+
+mod support;
+
+use backend::gl::*;
+
+use backend::gl::GLShaderVariant::*;
+
+use support::with_context;
+
+const VERTEX_SHADER: &'static str = "#version 330 core
+layout (location = 0) in vec3 position;
+
+void main() {
+    gl_Position = vec4(position, 1.0);
+}";
+
+/// Emits each triangle twice, offset along x
+const GEOMETRY_SHADER: &'static str = "#version 330 core
+layout (triangles) in;
+layout (triangle_strip, max_vertices = 6) out;
+
+void main() {
+    for(int copy = 0; copy < 2; copy++) {
+        for(int i = 0; i < 3; i++) {
+            gl_Position = gl_in[i].gl_Position + vec4(float(copy), 0.0, 0.0, 0.0);
+            EmitVertex();
+        }
+
+        EndPrimitive();
+    }
+}";
+
+const FRAGMENT_SHADER: &'static str = "#version 330 core
+out vec4 color;
+
+void main() {
+    color = vec4(1.0);
+}";
+
+#[test]
+fn test_invalid_stage_combinations() {
+    assert!(validate_stages(&[]).is_err());
+    assert!(validate_stages(&[FragmentShader]).is_err());
+    assert!(validate_stages(&[ComputeShader, VertexShader]).is_err());
+    assert!(validate_stages(&[VertexShader, TessControlShader, FragmentShader]).is_err());
+}
+
+#[test]
+fn test_valid_stage_combinations() {
+    assert!(validate_stages(&[VertexShader]).is_ok());
+    assert!(validate_stages(&[VertexShader, FragmentShader]).is_ok());
+    assert!(validate_stages(&[VertexShader, GeometryShader, FragmentShader]).is_ok());
+}
+
+#[test]
+#[ignore]
+fn test_geometry_shader_links() {
+    with_context(|| {
+        let program = GLShaderProgramBuilder::new().unwrap()
+            .attach_source(VERTEX_SHADER.to_string(), VertexShader).unwrap()
+            .attach_source(GEOMETRY_SHADER.to_string(), GeometryShader).unwrap()
+            .attach_source(FRAGMENT_SHADER.to_string(), FragmentShader).unwrap()
+            .link().unwrap()
+            .finish();
+
+        assert_eq!(program.get_info(GLProgramInfo::AttachedShaders).unwrap(), 3);
+    });
+}
+
+#[test]
+#[ignore]
+fn test_shader_variant_queried() {
+    with_context(|| {
+        let shader = GLShader::from_source(GEOMETRY_SHADER.to_string(), GeometryShader).unwrap();
+
+        assert_eq!(shader.variant().unwrap(), GeometryShader);
+    });
+}
+
+#[test]
+#[ignore]
+fn test_patch_vertices() {
+    with_context(|| {
+        let capabilities = GLCapabilities::query().unwrap();
+
+        if !capabilities.tessellation_shaders {
+            assert!(set_patch_vertices(3).is_err());
+            return;
+        }
+
+        set_patch_vertices(3).unwrap();
+        set_default_tess_levels([4.0; 4], [4.0; 2]).unwrap();
+
+        assert!(set_patch_vertices(0).is_err());
+        assert!(set_patch_vertices(capabilities.max_patch_vertices + 1).is_err());
+    });
+}
+
+#[test]
+#[ignore]
+fn test_compile_error_returned() {
+    with_context(|| {
+        let source = "#version 330 core\nvoid main() { undeclared = 1.0; }".to_string();
+
+        // GLShader isn't Debug, so unwrap_err can't be used
+        match GLShader::from_source(source, FragmentShader).err().expect("Invalid shader compiled").into_error() {
+            GLError::ShaderCompile(log) => assert!(!log.is_empty()),
+            other => panic!("Expected a compile error, got {:?}", other),
+        }
+    });
+}
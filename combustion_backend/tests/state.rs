@@ -0,0 +1,91 @@
+extern crate glfw;
+extern crate combustion_backend as backend;
+
+mod support;
+
+use backend::gl::*;
+use backend::gl::bindings as glb;
+
+fn with_context<F>(f: F) where F: FnOnce() {
+    support::with_context(|| {
+        // Each test runs on its own thread, but start from a clean slate regardless
+        GLStateCache::invalidate();
+        GLStateCache::take_counters();
+
+        f()
+    })
+}
+
+fn is_enabled(capability: glb::types::GLenum) -> bool {
+    unsafe { glb::IsEnabled(capability) == glb::TRUE }
+}
+
+#[test]
+#[ignore]
+fn test_redundant_capabilities_skipped() {
+    with_context(|| {
+        GLStateCache::enable(glb::DEPTH_TEST);
+        GLStateCache::enable(glb::DEPTH_TEST);
+        GLStateCache::disable(glb::BLEND);
+        GLStateCache::disable(glb::BLEND);
+
+        assert!(is_enabled(glb::DEPTH_TEST));
+        assert!(!is_enabled(glb::BLEND));
+
+        assert_eq!(GLStateCache::take_counters(), GLStateCounters { issued: 2, skipped: 2 });
+        assert_eq!(GLStateCache::counters(), GLStateCounters::default());
+    });
+}
+
+#[test]
+#[ignore]
+fn test_invalidate() {
+    with_context(|| {
+        GLStateCache::enable(glb::CULL_FACE);
+
+        // Changed behind the cache's back
+        unsafe { glb::Disable(glb::CULL_FACE); }
+
+        GLStateCache::invalidate();
+        GLStateCache::enable(glb::CULL_FACE);
+
+        assert!(is_enabled(glb::CULL_FACE));
+        assert_eq!(GLStateCache::counters(), GLStateCounters { issued: 2, skipped: 0 });
+    });
+}
+
+#[test]
+#[ignore]
+fn test_texture_units_tracked_separately() {
+    with_context(|| {
+        let texture = GLTexture2D::new().unwrap();
+
+        GLStateCache::take_counters();
+
+        GLStateCache::active_texture(0);
+        texture.bind().unwrap();
+        GLStateCache::active_texture(1);
+        texture.bind().unwrap();
+
+        // Both units already have the texture
+        GLStateCache::active_texture(0);
+        texture.bind().unwrap();
+
+        assert_eq!(GLStateCache::take_counters(), GLStateCounters { issued: 5, skipped: 1 });
+    });
+}
+
+#[test]
+#[ignore]
+fn test_deleted_framebuffer_forgotten() {
+    with_context(|| {
+        let mut framebuffer = GLFramebuffer::new().unwrap();
+
+        framebuffer.delete().unwrap();
+
+        // Deleting the bound framebuffer bound the default one, so binding that is redundant
+        DEFAULT_FRAMEBUFFER.bind().unwrap();
+
+        assert_eq!(GLStateCache::counters().skipped, 1);
+    });
+}
@@ -0,0 +1,61 @@
+extern crate combustion_backend as backend;
+
+use backend::gl::*;
+
+#[derive(Default)]
+struct Light {
+    kind: i32,
+    position: [f32; 3],
+    intensity: f32,
+}
+
+impl Std140 for Light {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.int(self.kind).vec3(self.position).float(self.intensity);
+    }
+}
+
+#[test]
+fn test_scalar_alignment() {
+    let mut writer = Std140Writer::new();
+
+    writer.float(1.0).vec2([1.0, 2.0]);
+
+    // vec2 is aligned to 8 bytes
+    assert_eq!(writer.len(), 16);
+
+    writer.vec3([1.0, 2.0, 3.0]);
+
+    assert_eq!(writer.len(), 28);
+
+    // A scalar after a vec3 fills its fourth component
+    writer.int(1);
+
+    assert_eq!(writer.len(), 32);
+}
+
+#[test]
+fn test_mat4_size() {
+    let mut writer = Std140Writer::new();
+
+    writer.float(1.0).mat4(&[[0.0; 4]; 4]);
+
+    assert_eq!(writer.len(), 16 + 64);
+}
+
+#[test]
+fn test_struct_array_padding() {
+    let mut writer = Std140Writer::new();
+
+    let lights = [Light { kind: 1, position: [1.0, 2.0, 3.0], intensity: 2.0 }];
+
+    writer.array(&lights, 4).int(1);
+
+    // Each element is rounded up to 32 bytes, and missing elements are zeroed
+    assert_eq!(writer.len(), 4 * 32 + 4);
+
+    let bytes = writer.as_bytes();
+
+    assert_eq!(&bytes[0..4], &[1, 0, 0, 0]);
+    assert!(bytes[32..128].iter().all(|byte| *byte == 0));
+}
@@ -0,0 +1,34 @@
+//! Hidden window fixture shared by the tests that need an OpenGL 3.3 context.
+//!
+//! Those tests are ignored by default. Run them with `cargo test -- --ignored` on a machine with a display.
+
+// Each test file is its own crate and only uses some of these
+#![allow(dead_code)]
+
+use glfw::{self, Context, WindowHint, WindowMode};
+
+use backend::gl::bindings as glb;
+
+/// Runs `f` with a hidden 1x1 window's context current and the GL functions loaded
+pub fn with_context<F>(f: F) where F: FnOnce() {
+    with_sized_context(1, 1, f)
+}
+
+/// Like `with_context`, but for tests that render into the default framebuffer and need a given size
+pub fn with_sized_context<F>(width: u32, height: u32, f: F) where F: FnOnce() {
+    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).expect("Could not initialize GLFW");
+
+    glfw.window_hint(WindowHint::ContextVersion(3, 3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+    glfw.window_hint(WindowHint::OpenGlForwardCompat(true));
+    glfw.window_hint(WindowHint::Visible(false));
+
+    let (mut window, _) = glfw.create_window(width, height, "combustion_backend test", WindowMode::Windowed)
+                              .expect("Could not create window");
+
+    window.make_current();
+
+    glb::load_all_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+    f()
+}
@@ -0,0 +1,75 @@
+extern crate glfw;
+extern crate combustion_backend as backend;
+
+mod support;
+
+use std::time::Duration;
+
+use backend::gl::*;
+use backend::gl::bindings as glb;
+
+fn with_context<F>(f: F) where F: FnOnce() {
+    support::with_sized_context(4, 4, f)
+}
+
+#[test]
+#[ignore]
+fn test_fence_signaled_after_wait() {
+    with_context(|| {
+        let fence = GLFence::insert().unwrap();
+
+        assert!(fence.wait(Duration::from_secs(5)).unwrap());
+        assert!(fence.is_signaled().unwrap());
+    });
+}
+
+#[test]
+#[ignore]
+fn test_pool_recycles_slots() {
+    with_context(|| {
+        let mut pool = GLFencePool::new();
+
+        let first = pool.insert().unwrap();
+        let second = pool.insert().unwrap();
+
+        assert_eq!(pool.len(), 2);
+
+        pool.release(first).unwrap();
+
+        assert!(pool.is_signaled(first).is_err());
+        assert!(pool.release(first).is_err());
+
+        let third = pool.insert().unwrap();
+
+        assert_eq!(third, first);
+        assert_ne!(third, second);
+        assert_eq!(pool.len(), 2);
+    });
+}
+
+#[test]
+#[ignore]
+fn test_async_readback() {
+    with_context(|| {
+        unsafe {
+            glb::ClearColor(1.0, 0.0, 0.0, 1.0);
+            glb::Clear(glb::COLOR_BUFFER_BIT);
+        }
+
+        let mut readback = DEFAULT_FRAMEBUFFER.read_pixels_async(0, 4, 4, glb::RGBA, glb::UNSIGNED_BYTE).unwrap();
+
+        let mut pixels = None;
+
+        while pixels.is_none() {
+            pixels = readback.poll().unwrap();
+        }
+
+        let pixels = pixels.unwrap();
+
+        assert_eq!(pixels.len(), 4 * 4 * 4);
+        assert_eq!(&pixels[..4], &[255, 0, 0, 255]);
+
+        // The pixels can only be taken once
+        assert!(readback.poll().is_err());
+    });
+}
@@ -0,0 +1,81 @@
+extern crate glfw;
+extern crate image;
+extern crate combustion_backend as backend;
+
+mod support;
+
+use image::{DynamicImage, ImageBuffer};
+
+use backend::gl::*;
+use backend::gl::types::*;
+use backend::gl::bindings as glb;
+
+use support::with_context;
+
+fn level_parameter(level: GLint, name: GLenum) -> GLint {
+    let mut value = 0;
+
+    unsafe { glb::GetTexLevelParameteriv(glb::TEXTURE_2D, level, name, &mut value); }
+
+    value
+}
+
+fn texture_parameter(name: GLenum) -> GLint {
+    let mut value = 0;
+
+    unsafe { glb::GetTexParameteriv(glb::TEXTURE_2D, name, &mut value); }
+
+    value
+}
+
+#[test]
+#[ignore]
+fn test_srgb_image() {
+    with_context(|| {
+        let image = DynamicImage::ImageRgba8(ImageBuffer::new(8, 4));
+
+        let options = GLTextureOptions { srgb: true, ..GLTextureOptions::default() };
+
+        let texture = GLTexture::from_image(&image, options).unwrap();
+
+        assert_eq!(texture.internal_format(), Some(glb::SRGB8_ALPHA8));
+        assert_eq!(texture.format(), Some(glb::RGBA));
+
+        texture.bind().unwrap();
+
+        assert_eq!(level_parameter(0, glb::TEXTURE_WIDTH), 8);
+        assert_eq!(level_parameter(0, glb::TEXTURE_HEIGHT), 4);
+        assert_eq!(level_parameter(0, glb::TEXTURE_INTERNAL_FORMAT) as GLenum, glb::SRGB8_ALPHA8);
+
+        // Mipmaps are generated by default, since images don't store any
+        assert_eq!(level_parameter(3, glb::TEXTURE_WIDTH), 1);
+        assert_eq!(texture_parameter(glb::TEXTURE_MIN_FILTER) as GLenum, glb::LINEAR_MIPMAP_LINEAR);
+    });
+}
+
+#[test]
+#[ignore]
+fn test_image_without_mipmaps() {
+    with_context(|| {
+        let image = DynamicImage::ImageLuma8(ImageBuffer::new(3, 5));
+
+        let options = GLTextureOptions {
+            mipmaps: GLMipmapPolicy::None,
+            filter: GLTextureFilter::Nearest,
+            wrap: GLTextureWrap::ClampToEdge,
+            ..GLTextureOptions::default()
+        };
+
+        let texture = GLTexture::from_image(&image, options).unwrap();
+
+        // Luminance is never sRGB
+        assert_eq!(texture.internal_format(), Some(glb::R8));
+
+        texture.bind().unwrap();
+
+        assert_eq!(texture_parameter(glb::TEXTURE_MAX_LEVEL), 0);
+        assert_eq!(texture_parameter(glb::TEXTURE_MIN_FILTER) as GLenum, glb::NEAREST);
+        assert_eq!(texture_parameter(glb::TEXTURE_WRAP_S) as GLenum, glb::CLAMP_TO_EDGE);
+        assert_eq!(texture_parameter(glb::TEXTURE_WRAP_T) as GLenum, glb::CLAMP_TO_EDGE);
+    });
+}
@@ -0,0 +1,87 @@
+extern crate glfw;
+extern crate combustion_backend as backend;
+
+mod support;
+
+use backend::gl::*;
+
+fn with_context<F>(f: F) where F: FnOnce() {
+    support::with_context(|| {
+        GLStateCache::invalidate();
+
+        f()
+    })
+}
+
+#[test]
+fn test_reserved_from_the_top() {
+    let mut units = GLTextureUnits::with_count(8, 2).unwrap();
+
+    assert_eq!(units.reserve("shadow_map").unwrap(), 7);
+    assert_eq!(units.reserve("noise").unwrap(), 6);
+
+    // Reserving the same name again gives the same unit
+    assert_eq!(units.reserve("shadow_map").unwrap(), 7);
+    assert_eq!(units.get("noise").unwrap(), 6);
+    assert!(units.get("missing").is_err());
+
+    assert!(units.release("shadow_map"));
+    assert!(!units.release("shadow_map"));
+
+    assert_eq!(units.reserve("luminance").unwrap(), 7);
+}
+
+#[test]
+fn test_reservations_exhausted() {
+    let mut units = GLTextureUnits::with_count(4, 2).unwrap();
+
+    units.reserve("a").unwrap();
+    units.reserve("b").unwrap();
+
+    // The scratch range is never reserved
+    assert!(units.reserve("c").is_err());
+}
+
+#[test]
+fn test_scratch_range() {
+    let mut units = GLTextureUnits::with_count(4, 2).unwrap();
+
+    assert_eq!(units.scratch().unwrap(), 0);
+    assert_eq!(units.scratch().unwrap(), 1);
+    assert!(units.scratch().is_err());
+
+    units.reset_scratch();
+
+    assert_eq!(units.scratch().unwrap(), 0);
+
+    assert!(GLTextureUnits::with_count(2, 3).is_err());
+}
+
+#[test]
+#[ignore]
+fn test_redundant_binds_skipped() {
+    with_context(|| {
+        let mut units = GLTextureUnits::new(2).unwrap();
+
+        assert_eq!(units.count(), GLCapabilities::current().unwrap().max_texture_units);
+
+        let texture = GLTexture2D::new().unwrap();
+
+        units.reserve("texture").unwrap();
+
+        let unit = units.bind_reserved("texture", &texture).unwrap();
+
+        GLStateCache::take_counters();
+
+        // Both the unit and the texture on it are already in effect
+        units.bind_reserved("texture", &texture).unwrap();
+
+        assert_eq!(GLStateCache::take_counters(), GLStateCounters { issued: 0, skipped: 2 });
+
+        assert_eq!(units.bind_scratch(&texture).unwrap(), 0);
+        assert_eq!(units.bind_scratch(&texture).unwrap(), 1);
+        assert!(units.bind_scratch(&texture).is_err());
+
+        assert!(unit >= 2);
+    });
+}
@@ -0,0 +1,150 @@
+extern crate glfw;
+extern crate nalgebra;
+extern crate combustion_backend as backend;
+
+mod support;
+
+use nalgebra::{Eye, Matrix4, Vector3, Point3};
+
+use backend::gl::*;
+use backend::gl::bindings as glb;
+
+use support::with_context;
+
+const VERTEX_SHADER: &'static str = "#version 330 core
+layout (location = 0) in vec2 position;
+
+void main() {
+    gl_Position = vec4(position, 0.0, 1.0);
+}";
+
+/// `unused` is optimized out, since it doesn't contribute to the output
+const FRAGMENT_SHADER: &'static str = "#version 330 core
+uniform vec4 fill;
+uniform vec4 unused;
+
+out vec4 color;
+
+void main() {
+    vec4 discarded = unused;
+    color = fill;
+}";
+
+/// Uses every uniform, so none of them are optimized out
+const TYPED_FRAGMENT_SHADER: &'static str = "#version 330 core
+uniform mat4 matrices[2];
+uniform vec3 directions[3];
+uniform vec3 origin;
+uniform bool enabled;
+
+out vec4 color;
+
+void main() {
+    vec3 sum = origin + directions[0] + directions[1] + directions[2];
+    color = matrices[0] * matrices[1] * vec4(sum, enabled ? 1.0 : 0.0);
+}";
+
+fn build_program_with(fragment_shader: &str) -> GLShaderProgram {
+    GLShaderProgramBuilder::new().unwrap()
+        .attach_source(VERTEX_SHADER.to_string(), GLShaderVariant::VertexShader).unwrap()
+        .attach_source(fragment_shader.to_string(), GLShaderVariant::FragmentShader).unwrap()
+        .link().unwrap()
+        .finish()
+}
+
+fn build_program() -> GLShaderProgram {
+    build_program_with(FRAGMENT_SHADER)
+}
+
+/// Reads back a float uniform with `glGetUniformfv`
+fn read_floats<'a>(program: &GLShaderProgram, name: &str, values: &'a mut [f32]) -> &'a [f32] {
+    let location = program.get_uniform_location(name).unwrap();
+
+    unsafe { glb::GetUniformfv(program.raw(), location, values.as_mut_ptr()); }
+
+    GLError::check().unwrap();
+
+    values
+}
+
+#[test]
+fn test_inactive_uniform_ignored() {
+    // Returns before making any GL calls, so no context is needed
+    let mut uniform = GLUniform(-1);
+
+    assert!(!uniform.is_active());
+    assert!(uniform.float4(1.0, 2.0, 3.0, 4.0).is_ok());
+    assert!(uniform.int1v(vec![1, 2, 3]).is_ok());
+}
+
+#[test]
+#[ignore]
+fn test_missing_uniform() {
+    with_context(|| {
+        let program = build_program();
+
+        assert!(program.get_uniform("unused").is_err());
+        assert!(program.get_uniform("misspelled").is_err());
+
+        let mut optional = program.get_uniform_optional("unused").unwrap();
+
+        assert!(!optional.is_active());
+
+        program.use_program().unwrap();
+
+        optional.float4(1.0, 1.0, 1.0, 1.0).unwrap();
+    });
+}
+
+#[test]
+#[ignore]
+fn test_uniform_locations_cached() {
+    with_context(|| {
+        let program = build_program();
+
+        let uniform = program.get_uniform("fill").unwrap();
+
+        assert!(uniform.is_active());
+        assert_eq!(program.get_uniform_location("fill").unwrap(), uniform.0);
+        assert_eq!(program.get_uniform_optional("fill").unwrap(), uniform);
+    });
+}
+
+#[test]
+#[ignore]
+fn test_typed_setters_read_back() {
+    with_context(|| {
+        let program = build_program_with(TYPED_FRAGMENT_SHADER);
+
+        program.use_program().unwrap();
+
+        let translation = Matrix4::new(1.0, 0.0, 0.0, 5.0,
+                                       0.0, 1.0, 0.0, 6.0,
+                                       0.0, 0.0, 1.0, 7.0,
+                                       0.0, 0.0, 0.0, 1.0);
+
+        program.get_uniform("matrices").unwrap().mat4v(&[Matrix4::new_identity(4), translation], false).unwrap();
+
+        let directions = [Vector3::new(1.0, 2.0, 3.0), Vector3::new(4.0, 5.0, 6.0), Vector3::new(7.0, 8.0, 9.0)];
+
+        program.get_uniform("directions").unwrap().vec3fv(&directions).unwrap();
+        program.get_uniform("origin").unwrap().point3f(&Point3::new(0.5, 0.25, 0.125)).unwrap();
+        program.get_uniform("enabled").unwrap().bool1(true).unwrap();
+
+        // Column major, so the translation ends up in the last column
+        let mut matrix = [0.0; 16];
+
+        assert_eq!(&read_floats(&program, "matrices[1]", &mut matrix)[12..15], &[5.0, 6.0, 7.0]);
+
+        let mut vector = [0.0; 3];
+
+        assert_eq!(read_floats(&program, "directions[2]", &mut vector), &[7.0, 8.0, 9.0]);
+        assert_eq!(read_floats(&program, "origin", &mut vector), &[0.5, 0.25, 0.125]);
+
+        let mut enabled: i32 = 0;
+
+        unsafe { glb::GetUniformiv(program.raw(), program.get_uniform_location("enabled").unwrap(), &mut enabled); }
+
+        assert_eq!(enabled, 1);
+    });
+}
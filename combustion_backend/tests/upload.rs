@@ -0,0 +1,88 @@
+extern crate glfw;
+extern crate combustion_backend as backend;
+
+mod support;
+
+use std::ptr;
+
+use backend::gl::*;
+use backend::gl::bindings as glb;
+
+use support::with_context;
+
+/// 4x4 RGBA8 texture with uninitialized storage
+fn empty_texture() -> GLTexture2D {
+    let texture = GLTexture2D::new().unwrap();
+
+    unsafe {
+        glb::TexImage2D(glb::TEXTURE_2D, 0, glb::RGBA8 as _, 4, 4, 0, glb::RGBA, glb::UNSIGNED_BYTE, ptr::null());
+    }
+
+    texture
+}
+
+fn read_texture(texture: &GLTexture2D) -> Vec<u8> {
+    let mut pixels = vec![0u8; 4 * 4 * 4];
+
+    texture.bind().unwrap();
+
+    unsafe {
+        glb::PixelStorei(glb::PACK_ALIGNMENT, 1);
+        glb::GetTexImage(glb::TEXTURE_2D, 0, glb::RGBA, glb::UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut _);
+    }
+
+    pixels
+}
+
+#[test]
+#[ignore]
+fn test_upload_async() {
+    with_context(|| {
+        let mut ring = GLUploadRing::new(2);
+        let mut texture = empty_texture();
+
+        let data: Vec<u8> = (0..64).collect();
+
+        // More uploads than slots, so the ring has to wrap around
+        let tickets: Vec<_> = (0..3).map(|_| {
+            texture.upload_async(&mut ring, &data, glb::RGBA, glb::UNSIGNED_BYTE, GLTextureRegion::new(4, 4)).unwrap()
+        }).collect();
+
+        ring.wait_all().unwrap();
+
+        for ticket in tickets {
+            assert!(ring.is_complete(ticket).unwrap());
+        }
+
+        assert_eq!(read_texture(&texture), data);
+    });
+}
+
+#[test]
+#[ignore]
+fn test_upload_region() {
+    with_context(|| {
+        let mut texture = empty_texture();
+
+        texture.upload_sub(&[0u8; 64], glb::RGBA, glb::UNSIGNED_BYTE, GLTextureRegion::new(4, 4)).unwrap();
+        texture.upload_sub(&[255u8; 4], glb::RGBA, glb::UNSIGNED_BYTE, GLTextureRegion::new(1, 1).at(3, 0)).unwrap();
+
+        let pixels = read_texture(&texture);
+
+        assert_eq!(&pixels[12..16], &[255, 255, 255, 255]);
+        assert!(pixels[..12].iter().all(|&value| value == 0));
+    });
+}
+
+#[test]
+#[ignore]
+fn test_upload_too_little_data() {
+    with_context(|| {
+        let mut ring = GLUploadRing::new(1);
+        let mut texture = empty_texture();
+
+        let result = texture.upload_async(&mut ring, &[0u8; 63], glb::RGBA, glb::UNSIGNED_BYTE, GLTextureRegion::new(4, 4));
+
+        assert!(result.is_err());
+    });
+}
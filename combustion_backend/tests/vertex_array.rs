@@ -0,0 +1,135 @@
+extern crate glfw;
+extern crate combustion_backend as backend;
+extern crate combustion_protocols as protocols;
+
+mod support;
+
+use std::mem;
+
+use backend::gl::*;
+use backend::gl::types::*;
+use backend::gl::bindings as glb;
+
+use protocols::mesh::data::Vertex;
+
+use support::with_context;
+
+/// Queries a parameter of an attribute of the bound vertex array
+fn attribute_parameter(index: GLuint, parameter: GLenum) -> GLint {
+    let mut value = 0;
+
+    unsafe { glb::GetVertexAttribiv(index, parameter, &mut value as *mut _); }
+
+    value
+}
+
+fn bound_vertex_array() -> GLuint {
+    let mut binding = 0;
+
+    unsafe { glb::GetIntegerv(glb::VERTEX_ARRAY_BINDING, &mut binding as *mut _); }
+
+    binding as GLuint
+}
+
+#[test]
+fn test_mesh_vertex_layout() {
+    let attributes = Vertex::attributes();
+
+    assert_eq!(attributes.len(), 3);
+
+    for attribute in &attributes {
+        assert_eq!(attribute.stride, mem::size_of::<Vertex>());
+        assert_eq!(attribute.data_type, glb::FLOAT);
+        assert!(!attribute.integer);
+        assert_eq!(attribute.divisor, 0);
+    }
+
+    let offsets: Vec<_> = attributes.iter().map(|attribute| attribute.offset).collect();
+    let sizes: Vec<_> = attributes.iter().map(|attribute| attribute.size).collect();
+
+    assert_eq!(offsets, vec![0, 12, 24]);
+    assert_eq!(sizes, vec![3, 3, 2]);
+}
+
+#[test]
+#[ignore]
+fn test_builder_attributes() {
+    with_context(|| {
+        let mut buffer = GLBuffer::array_buffer().unwrap();
+
+        buffer.upload(&[0u32; 16], GLBufferUsage::StaticDraw).unwrap();
+
+        let vao = GLVertexArrayBuilder::new().unwrap()
+            .buffer(&buffer).unwrap()
+            .attribute(0, 3, glb::FLOAT, false, 16, 0).unwrap()
+            .integer_attribute(1, 1, glb::UNSIGNED_INT, 16, 12).unwrap()
+            .divisor(1, 2).unwrap()
+            .finish();
+
+        // The builder restores the previous binding
+        assert_eq!(bound_vertex_array(), 0);
+
+        let _binding = vao.bind_scoped().unwrap();
+
+        assert_eq!(attribute_parameter(0, glb::VERTEX_ATTRIB_ARRAY_ENABLED), 1);
+        assert_eq!(attribute_parameter(0, glb::VERTEX_ATTRIB_ARRAY_SIZE), 3);
+        assert_eq!(attribute_parameter(0, glb::VERTEX_ATTRIB_ARRAY_INTEGER), 0);
+        assert_eq!(attribute_parameter(0, glb::VERTEX_ATTRIB_ARRAY_DIVISOR), 0);
+
+        assert_eq!(attribute_parameter(1, glb::VERTEX_ATTRIB_ARRAY_INTEGER), 1);
+        assert_eq!(attribute_parameter(1, glb::VERTEX_ATTRIB_ARRAY_DIVISOR), 2);
+
+        assert_eq!(attribute_parameter(2, glb::VERTEX_ATTRIB_ARRAY_ENABLED), 0);
+    });
+}
+
+#[test]
+#[ignore]
+fn test_with_layout() {
+    with_context(|| {
+        let mut buffer = GLBuffer::array_buffer().unwrap();
+
+        buffer.upload(&[Vertex::default(); 3], GLBufferUsage::StaticDraw).unwrap();
+
+        let vao = GLVertexArrayBuilder::new().unwrap()
+            .buffer(&buffer).unwrap()
+            .with_layout::<Vertex>().unwrap()
+            .finish();
+
+        let _binding = vao.bind_scoped().unwrap();
+
+        for index in 0..3 {
+            assert_eq!(attribute_parameter(index, glb::VERTEX_ATTRIB_ARRAY_ENABLED), 1);
+            assert_eq!(attribute_parameter(index, glb::VERTEX_ATTRIB_ARRAY_STRIDE), mem::size_of::<Vertex>() as GLint);
+        }
+    });
+}
+
+#[test]
+#[ignore]
+fn test_scoped_binding() {
+    with_context(|| {
+        let outer = GLVertexArrayBuilder::new().unwrap().finish();
+        let inner = GLVertexArrayBuilder::new().unwrap().finish();
+
+        let _outer_binding = outer.bind_scoped().unwrap();
+
+        {
+            let _inner_binding = inner.bind_scoped().unwrap();
+
+            assert_eq!(bound_vertex_array(), inner.raw());
+        }
+
+        assert_eq!(bound_vertex_array(), outer.raw());
+    });
+}
+
+#[test]
+#[ignore]
+fn test_invalid_attribute_size() {
+    with_context(|| {
+        let result = GLVertexArrayBuilder::new().unwrap().attribute(0, 5, glb::FLOAT, false, 0, 0);
+
+        assert!(result.is_err());
+    });
+}
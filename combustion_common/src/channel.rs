@@ -0,0 +1,79 @@
+//! Helpers for channels between long-running threads
+
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+/// Takes the next message without blocking, or `None` if there isn't one yet.
+///
+/// Once every sender is gone no message can ever arrive, so the result of `on_disconnect` is returned instead.
+/// That lets a loop treat a sender that exited or panicked the same as one that asked it to stop.
+pub fn try_recv_or<T, F>(rx: &Receiver<T>, on_disconnect: F) -> Option<T> where F: FnOnce() -> T {
+    match rx.try_recv() {
+        Ok(message) => Some(message),
+        Err(TryRecvError::Empty) => None,
+        Err(TryRecvError::Disconnected) => Some(on_disconnect()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[derive(Debug, PartialEq)]
+    enum Signal {
+        Work(u32),
+        Stop,
+    }
+
+    #[test]
+    fn test_empty() {
+        let (_tx, rx) = mpsc::channel::<Signal>();
+
+        assert_eq!(try_recv_or(&rx, || Signal::Stop), None);
+    }
+
+    #[test]
+    fn test_drains_before_disconnect() {
+        let (tx, rx) = mpsc::channel();
+
+        tx.send(Signal::Work(1)).unwrap();
+        tx.send(Signal::Work(2)).unwrap();
+
+        drop(tx);
+
+        // Messages sent before the disconnect still arrive first
+        assert_eq!(try_recv_or(&rx, || Signal::Stop), Some(Signal::Work(1)));
+        assert_eq!(try_recv_or(&rx, || Signal::Stop), Some(Signal::Work(2)));
+        assert_eq!(try_recv_or(&rx, || Signal::Stop), Some(Signal::Stop));
+    }
+
+    #[test]
+    fn test_sender_panicked() {
+        let (tx, rx) = mpsc::channel::<Signal>();
+
+        let result = thread::spawn(move || {
+            let _tx = tx;
+
+            panic!("main thread died");
+        }).join();
+
+        assert!(result.is_err());
+
+        // A loop polling like this exits instead of waiting forever
+        let mut polls = 0;
+
+        loop {
+            polls += 1;
+
+            match try_recv_or(&rx, || Signal::Stop) {
+                Some(Signal::Stop) => break,
+                Some(other) => panic!("Unexpected {:?}", other),
+                None => assert!(polls < 10, "Disconnect was never noticed"),
+            }
+        }
+
+        assert_eq!(polls, 1);
+    }
+}
@@ -0,0 +1,201 @@
+//! Ring buffer of recent frame times, and the statistics reported from them
+
+use std::fmt;
+
+/// Statistics over the frame times in a `FrameTimes`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameStatsSummary {
+    /// Number of frames the statistics cover
+    pub frames: usize,
+    /// Average frames per second
+    pub fps_avg: f32,
+    /// Average frame time in milliseconds
+    pub average_ms: f32,
+    /// 95% of frames took this many milliseconds or less
+    pub p95_ms: f32,
+    /// 99% of frames took this many milliseconds or less
+    pub p99_ms: f32,
+    /// Longest frame time in milliseconds
+    pub longest_ms: f32,
+}
+
+impl fmt::Display for FrameStatsSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.1} FPS over {} frames ({:.2}ms average, {:.2}ms 95th percentile, {:.2}ms 99th percentile, {:.2}ms longest)",
+               self.fps_avg, self.frames, self.average_ms, self.p95_ms, self.p99_ms, self.longest_ms)
+    }
+}
+
+/// The last few frame times, in milliseconds.
+///
+/// Recording a frame is constant time, while the statistics sort a copy of the samples when asked for.
+#[derive(Debug, Clone)]
+pub struct FrameTimes {
+    times: Vec<f32>,
+    capacity: usize,
+    next: usize,
+}
+
+impl FrameTimes {
+    /// Create an empty buffer holding up to `capacity` frame times, which must be at least one
+    pub fn new(capacity: usize) -> FrameTimes {
+        assert!(capacity > 0, "FrameTimes needs room for at least one frame");
+
+        FrameTimes {
+            times: Vec::with_capacity(capacity),
+            capacity: capacity,
+            next: 0,
+        }
+    }
+
+    /// Record the time of one frame, replacing the oldest one if the buffer is full
+    pub fn push(&mut self, ms: f32) {
+        if self.times.len() < self.capacity {
+            self.times.push(ms);
+        } else {
+            self.times[self.next] = ms;
+        }
+
+        self.next = (self.next + 1) % self.capacity;
+    }
+
+    /// Number of frame times recorded, up to the capacity
+    #[inline(always)]
+    pub fn len(&self) -> usize { self.times.len() }
+
+    /// Whether no frames have been recorded yet
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool { self.times.is_empty() }
+
+    /// Most frame times kept at once
+    #[inline(always)]
+    pub fn capacity(&self) -> usize { self.capacity }
+
+    /// Forget every recorded frame time
+    pub fn clear(&mut self) {
+        self.times.clear();
+        self.next = 0;
+    }
+
+    /// Average frames per second, or zero with no frames recorded
+    pub fn fps_avg(&self) -> f32 {
+        let average = self.average_ms();
+
+        if average > 0.0 { 1000.0 / average } else { 0.0 }
+    }
+
+    /// Average frame time in milliseconds
+    pub fn average_ms(&self) -> f32 {
+        if self.times.is_empty() {
+            0.0
+        } else {
+            self.times.iter().sum::<f32>() / self.times.len() as f32
+        }
+    }
+
+    /// Longest frame time in milliseconds
+    pub fn longest_ms(&self) -> f32 {
+        self.times.iter().cloned().fold(0.0, f32::max)
+    }
+
+    /// Frame time that `percent` percent of frames took or less, using the nearest-rank method
+    pub fn percentile_ms(&self, percent: f32) -> f32 {
+        percentile(&self.sorted(), percent)
+    }
+
+    /// All the statistics at once, sorting the frame times only once
+    pub fn summary(&self) -> FrameStatsSummary {
+        let sorted = self.sorted();
+
+        FrameStatsSummary {
+            frames: sorted.len(),
+            fps_avg: self.fps_avg(),
+            average_ms: self.average_ms(),
+            p95_ms: percentile(&sorted, 95.0),
+            p99_ms: percentile(&sorted, 99.0),
+            longest_ms: sorted.last().cloned().unwrap_or(0.0),
+        }
+    }
+
+    fn sorted(&self) -> Vec<f32> {
+        let mut sorted = self.times.clone();
+
+        // Frame times are never NaN, but they shouldn't be able to cause a panic either
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(::std::cmp::Ordering::Equal));
+
+        sorted
+    }
+}
+
+fn percentile(sorted: &[f32], percent: f32) -> f32 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+
+    let rank = (percent.max(0.0).min(100.0) * sorted.len() as f32 / 100.0).ceil() as usize;
+
+    sorted[rank.max(1) - 1]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let mut times = FrameTimes::new(100);
+
+        // Shuffled, so the order they were recorded in doesn't matter
+        for i in 0..100 {
+            times.push(((i * 37) % 100 + 1) as f32);
+        }
+
+        assert_eq!(times.percentile_ms(95.0), 95.0);
+        assert_eq!(times.percentile_ms(99.0), 99.0);
+        assert_eq!(times.percentile_ms(100.0), 100.0);
+        assert_eq!(times.percentile_ms(0.0), 1.0);
+        assert_eq!(times.longest_ms(), 100.0);
+        assert_eq!(times.average_ms(), 50.5);
+    }
+
+    #[test]
+    fn test_single_spike() {
+        let mut times = FrameTimes::new(200);
+
+        for _ in 0..199 {
+            times.push(16.0);
+        }
+
+        times.push(250.0);
+
+        let summary = times.summary();
+
+        // One frame in 200 is too rare to show up in the 99th percentile
+        assert_eq!(summary.p95_ms, 16.0);
+        assert_eq!(summary.p99_ms, 16.0);
+        assert_eq!(summary.longest_ms, 250.0);
+        assert_eq!(summary.frames, 200);
+    }
+
+    #[test]
+    fn test_ring_overwrites_oldest() {
+        let mut times = FrameTimes::new(4);
+
+        for &ms in &[100.0, 10.0, 10.0, 10.0, 20.0, 20.0] {
+            times.push(ms);
+        }
+
+        // The 100ms frame has been replaced
+        assert_eq!(times.len(), 4);
+        assert_eq!(times.longest_ms(), 20.0);
+        assert_eq!(times.average_ms(), 15.0);
+        assert_eq!(times.fps_avg(), 1000.0 / 15.0);
+    }
+
+    #[test]
+    fn test_empty() {
+        let times = FrameTimes::new(8);
+
+        assert_eq!(times.summary(), FrameStatsSummary::default());
+    }
+}
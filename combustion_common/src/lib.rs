@@ -30,4 +30,8 @@ pub mod humanize;
 pub mod structures;
 pub mod color;
 pub mod streams;
-pub mod vfs;
\ No newline at end of file
+pub mod vfs;
+pub mod pacing;
+pub mod frame_times;
+pub mod channel;
+pub mod line_edit;
\ No newline at end of file
@@ -0,0 +1,170 @@
+//! Single-line text editing, for text entry like consoles and chat
+
+/// One change to a line of text, in the order it was typed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextEdit {
+    /// A typed character, inserted at the cursor
+    Char(char),
+    /// Text pasted from the clipboard, inserted at the cursor
+    Paste(String),
+    /// Removes the character before the cursor
+    Backspace,
+    /// Removes the character after the cursor
+    Delete,
+    /// Moves the cursor one character left
+    Left,
+    /// Moves the cursor one character right
+    Right,
+    /// Moves the cursor to the start of the line
+    Home,
+    /// Moves the cursor to the end of the line
+    End,
+    /// Finishes the line
+    Submit,
+}
+
+/// A line of text with a cursor, counted in characters rather than bytes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineBuffer {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl LineBuffer {
+    /// Creates an empty line
+    pub fn new() -> LineBuffer {
+        LineBuffer::default()
+    }
+
+    /// The line's text
+    pub fn text(&self) -> String {
+        self.chars.iter().cloned().collect()
+    }
+
+    /// Cursor position, in characters from the start of the line
+    #[inline]
+    pub fn cursor(&self) -> usize { self.cursor }
+
+    /// Whether the line has no text
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.chars.is_empty() }
+
+    /// Empties the line, returning its text
+    pub fn clear(&mut self) -> String {
+        let text = self.text();
+
+        self.chars.clear();
+        self.cursor = 0;
+
+        text
+    }
+
+    /// Inserts text at the cursor and moves the cursor past it. Control characters like line breaks are left out.
+    pub fn insert(&mut self, text: &str) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            self.chars.insert(self.cursor, c);
+            self.cursor += 1;
+        }
+    }
+
+    /// Applies an edit, returning the finished line's text for `TextEdit::Submit`
+    pub fn apply(&mut self, edit: &TextEdit) -> Option<String> {
+        match *edit {
+            TextEdit::Char(c) => {
+                let mut buffer = [0; 4];
+
+                self.insert(c.encode_utf8(&mut buffer));
+            }
+            TextEdit::Paste(ref text) => self.insert(text),
+            TextEdit::Backspace => {
+                if self.cursor > 0 {
+                    self.cursor -= 1;
+                    self.chars.remove(self.cursor);
+                }
+            }
+            TextEdit::Delete => {
+                if self.cursor < self.chars.len() {
+                    self.chars.remove(self.cursor);
+                }
+            }
+            TextEdit::Left => self.cursor = self.cursor.saturating_sub(1),
+            TextEdit::Right => self.cursor = (self.cursor + 1).min(self.chars.len()),
+            TextEdit::Home => self.cursor = 0,
+            TextEdit::End => self.cursor = self.chars.len(),
+            TextEdit::Submit => return Some(self.clear()),
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn typed(text: &str) -> LineBuffer {
+        let mut line = LineBuffer::new();
+
+        for c in text.chars() {
+            line.apply(&TextEdit::Char(c));
+        }
+
+        line
+    }
+
+    #[test]
+    fn test_editing() {
+        let mut line = typed("helo");
+
+        line.apply(&TextEdit::Left);
+        line.apply(&TextEdit::Char('l'));
+
+        assert_eq!(line.text(), "hello");
+        assert_eq!(line.cursor(), 4);
+
+        line.apply(&TextEdit::Home);
+        line.apply(&TextEdit::Delete);
+        line.apply(&TextEdit::End);
+        line.apply(&TextEdit::Backspace);
+
+        assert_eq!(line.text(), "ell");
+        assert_eq!(line.cursor(), 3);
+    }
+
+    #[test]
+    fn test_cursor_bounds() {
+        let mut line = typed("ab");
+
+        line.apply(&TextEdit::Right);
+        assert_eq!(line.cursor(), 2);
+
+        line.apply(&TextEdit::Home);
+        line.apply(&TextEdit::Left);
+        line.apply(&TextEdit::Backspace);
+
+        assert_eq!(line.cursor(), 0);
+        assert_eq!(line.text(), "ab");
+    }
+
+    #[test]
+    fn test_multibyte() {
+        // The cursor counts characters, so removing one never splits a UTF-8 sequence
+        let mut line = typed("añ€");
+
+        line.apply(&TextEdit::Left);
+        line.apply(&TextEdit::Backspace);
+
+        assert_eq!(line.text(), "a€");
+    }
+
+    #[test]
+    fn test_paste_and_submit() {
+        let mut line = typed("say ");
+
+        line.apply(&TextEdit::Paste("two\nlines".to_string()));
+
+        assert_eq!(line.apply(&TextEdit::Submit), Some("say twolines".to_string()));
+        assert!(line.is_empty());
+        assert_eq!(line.cursor(), 0);
+    }
+}
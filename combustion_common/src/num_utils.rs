@@ -4,6 +4,8 @@ use std::ops::{Add, Mul};
 
 use num_traits::{Num, Float};
 
+use nalgebra::Quaternion;
+
 /// Generic min function for any `PartialOrd`
 ///
 /// ```
@@ -199,6 +201,61 @@ pub trait LerpGenericExt: Sized {
 
 impl<T> LerpExt for T where T: Num + Copy {}
 
+/// Spherical linear interpolation between two rotation quaternions, taking the shortest path
+///
+/// The inputs don't need to be normalized, but the result always is.
+///
+/// ```
+/// extern crate nalgebra;
+/// extern crate combustion_common;
+///
+/// use combustion_common::num_utils::slerp;
+/// use nalgebra::Quaternion;
+///
+/// fn main() {
+///     let a = Quaternion::new(1.0f32, 0.0, 0.0, 0.0);
+///     let b = Quaternion::new(0.0f32, 0.0, 1.0, 0.0);
+///
+///     let half = slerp(a, b, 0.5);
+///
+///     assert!((half.w - 0.5f32.sqrt()).abs() < 1e-6);
+///     assert!((half.j - 0.5f32.sqrt()).abs() < 1e-6);
+/// }
+/// ```
+pub fn slerp<T: Float>(a: Quaternion<T>, b: Quaternion<T>, t: T) -> Quaternion<T> {
+    let normalize = |q: Quaternion<T>| {
+        let length = (q.w * q.w + q.i * q.i + q.j * q.j + q.k * q.k).sqrt();
+
+        Quaternion::new(q.w / length, q.i / length, q.j / length, q.k / length)
+    };
+
+    let a = normalize(a);
+    let mut b = normalize(b);
+
+    let mut dot = a.w * b.w + a.i * b.i + a.j * b.j + a.k * b.k;
+
+    // q and -q are the same rotation, so flip one to go the short way around
+    if dot < T::zero() {
+        b = Quaternion::new(-b.w, -b.i, -b.j, -b.k);
+        dot = -dot;
+    }
+
+    // Nearly parallel, where the sine below goes to zero, so fall back to normalized linear interpolation
+    let (s0, s1) = if dot > T::from(0.9995).unwrap() {
+        (T::one() - t, t)
+    } else {
+        let theta = dot.acos();
+        let sin_theta = theta.sin();
+
+        (((T::one() - t) * theta).sin() / sin_theta, (t * theta).sin() / sin_theta)
+    };
+
+    normalize(Quaternion::new(a.w * s0 + b.w * s1,
+                              a.i * s0 + b.i * s1,
+                              a.j * s0 + b.j * s1,
+                              a.k * s0 + b.k * s1))
+}
+
 /// Scales a value between the range `in_min` and `in_max` to the range of `out_min` to `out_max`
 ///
 /// ```
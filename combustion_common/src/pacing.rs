@@ -0,0 +1,73 @@
+//! Waiting for the next frame without spinning for the whole wait
+
+use time::{Duration, PreciseTime};
+
+use std::thread;
+
+/// How long before a deadline to stop sleeping and spin instead, since sleeps can overshoot by about this much
+pub const SPIN_THRESHOLD_MS: i64 = 1;
+
+/// Waits until `duration` has passed since `start`.
+///
+/// Most of the wait is spent parked, so the thread can still be woken early with `Thread::unpark`,
+/// after which it goes back to waiting. Only the last `SPIN_THRESHOLD_MS` are spent spinning.
+pub fn wait_for(start: PreciseTime, duration: Duration) {
+    let threshold = Duration::milliseconds(SPIN_THRESHOLD_MS);
+
+    loop {
+        let remaining = duration - start.to(PreciseTime::now());
+
+        if remaining <= Duration::zero() {
+            break;
+        }
+
+        if remaining > threshold {
+            thread::park_timeout((remaining - threshold).to_std().unwrap());
+        } else {
+            thread::yield_now();
+        }
+    }
+}
+
+/// Paces a loop to a fixed number of frames per second
+#[derive(Clone, Copy)]
+pub struct FramePacer {
+    interval: Duration,
+    frame_start: PreciseTime,
+}
+
+impl FramePacer {
+    /// Create a pacer for `fps` frames per second, with the first frame starting now
+    pub fn new(fps: f64) -> FramePacer {
+        let mut pacer = FramePacer { interval: Duration::zero(), frame_start: PreciseTime::now() };
+
+        pacer.set_fps(fps);
+
+        pacer
+    }
+
+    /// Change the frame rate, starting with the current frame. Rates that aren't positive don't wait at all.
+    pub fn set_fps(&mut self, fps: f64) {
+        self.interval = if fps > 0.0 && fps.is_finite() {
+            Duration::nanoseconds((1000000000.0 / fps) as i64)
+        } else {
+            Duration::zero()
+        };
+    }
+
+    /// Time each frame is paced to take
+    #[inline(always)]
+    pub fn interval(&self) -> Duration { self.interval }
+
+    /// Mark the start of a frame, which `wait` measures from
+    #[inline(always)]
+    pub fn begin_frame(&mut self) {
+        self.frame_start = PreciseTime::now();
+    }
+
+    /// Wait until the frame started by the last `begin_frame` is due to end
+    #[inline]
+    pub fn wait(&self) {
+        wait_for(self.frame_start, self.interval);
+    }
+}
@@ -11,6 +11,12 @@ pub use ::common::error::*;
 
 use ::backend::gl::GLError;
 
+use capnp;
+
+use ::ecs::schedule::ScheduleError;
+use ::ecs::scene::SceneError;
+use ::ecs::components::animation::SkeletonError;
+
 pub type AppResult<T> = Result<T, AppError>;
 
 #[derive(Debug)]
@@ -21,7 +27,11 @@ pub enum AppError {
     Utf8Error(Utf8Error),
     NulError(NulError),
     PoisonError(TypeId, Box<Error + 'static>),
-    InvalidScene
+    Capnp(capnp::Error),
+    InvalidScene,
+    Schedule(ScheduleError),
+    Scene(SceneError),
+    Skeleton(SkeletonError),
 }
 
 impl From<GLError> for AppError {
@@ -41,6 +51,30 @@ impl From<io::Error> for AppError {
     }
 }
 
+impl From<capnp::Error> for AppError {
+    fn from(err: capnp::Error) -> AppError {
+        AppError::Capnp(err)
+    }
+}
+
+impl From<ScheduleError> for AppError {
+    fn from(err: ScheduleError) -> AppError {
+        AppError::Schedule(err)
+    }
+}
+
+impl From<SceneError> for AppError {
+    fn from(err: SceneError) -> AppError {
+        AppError::Scene(err)
+    }
+}
+
+impl From<SkeletonError> for AppError {
+    fn from(err: SkeletonError) -> AppError {
+        AppError::Skeleton(err)
+    }
+}
+
 impl<T: 'static> From<PoisonError<T>> for AppError {
     fn from(err: PoisonError<T>) -> AppError {
         AppError::PoisonError(TypeId::of::<T>(), Box::from(err))
@@ -49,7 +83,15 @@ impl<T: 'static> From<PoisonError<T>> for AppError {
 
 impl Display for AppError {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "{}", self.description())
+        match *self {
+            //Includes every error flag, and where they were checked
+            AppError::GLError(ref err) => write!(f, "{}", err),
+            AppError::Capnp(ref err) => write!(f, "{}", err),
+            AppError::Schedule(ref err) => write!(f, "{}", err),
+            AppError::Scene(ref err) => write!(f, "{}", err),
+            AppError::Skeleton(ref err) => write!(f, "{}", err),
+            _ => write!(f, "{}", self.description())
+        }
     }
 }
 
@@ -62,6 +104,10 @@ impl Error for AppError {
             AppError::FromUtf8Error(ref err) => err.description(),
             AppError::NulError(ref err) => err.description(),
             AppError::PoisonError(_, ref err) => err.description(),
+            AppError::Capnp(ref err) => err.description(),
+            AppError::Schedule(ref err) => err.description(),
+            AppError::Scene(ref err) => err.description(),
+            AppError::Skeleton(ref err) => err.description(),
             AppError::InvalidScene => "Invalid Scene",
         }
     }
@@ -0,0 +1,191 @@
+//! Loading of files dropped onto the window, on a thread of its own so the event loop never waits on the disk
+
+use std::ascii::AsciiExt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+
+use capnp;
+use image::{self, DynamicImage, GenericImage};
+
+use common::vfs::BoxedVFS;
+use common::vfs::default::DefaultFS;
+
+use asset::asset::{Asset, AssetMedium, AssetFileFormat};
+use asset::assets::model::ModelAsset;
+use asset::assets::model::ModelFileFormat;
+use asset::assets::texture::formats::TextureFileFormat;
+
+use protocols::texture::protocol::texture as texture_protocol;
+
+use ::backend::gl::*;
+
+use super::render::RenderSignal;
+
+/// What a dropped file is loaded as, going by its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DroppedKind {
+    Model,
+    Texture,
+}
+
+/// Finds out what a path can be loaded as. Textures win for extensions both could load, like the standard formats.
+pub fn classify(path: &Path) -> Option<DroppedKind> {
+    let extension = match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => extension.to_ascii_lowercase(),
+        None => return None,
+    };
+
+    match TextureFileFormat::from_extension(&extension) {
+        Some(format) if format.can_import() => return Some(DroppedKind::Texture),
+        _ => {}
+    }
+
+    match ModelFileFormat::from_extension(&extension) {
+        Some(format) if format.can_import() => Some(DroppedKind::Model),
+        _ => None,
+    }
+}
+
+/// Texture data read and decoded on the loader thread, ready to be uploaded on the render thread
+pub enum TextureData {
+    Image(DynamicImage),
+    Native(capnp::message::Reader<capnp::serialize::OwnedSegments>),
+}
+
+/// A dropped texture, sent to the render thread with `RenderSignal::ShowTexture`
+pub struct LoadedTexture {
+    pub path: PathBuf,
+    pub data: TextureData,
+    /// Width and height in pixels
+    pub size: (u32, u32),
+}
+
+impl LoadedTexture {
+    /// Uploads the texture, which has to happen on the thread with the OpenGL context
+    pub fn upload(&self) -> GLResult<GLTexture> {
+        let options = GLTextureOptions { wrap: GLTextureWrap::ClampToEdge, ..GLTextureOptions::default() };
+
+        match self.data {
+            TextureData::Image(ref image) => GLTexture::from_image(image, options),
+            TextureData::Native(ref message) => {
+                let texture = try!(message.get_root::<texture_protocol::Reader>().map_err(|_| GLError::InvalidValue));
+
+                GLTexture::from_protocol_reader(texture, options)
+            }
+        }
+    }
+}
+
+fn read_texture(path: &Path) -> Result<LoadedTexture, String> {
+    let (data, size) = if path.extension().map_or(false, |extension| extension == ::protocols::texture::EXTENSION) {
+        let mut source = BufReader::new(try!(File::open(path).map_err(|err| err.to_string())));
+
+        let message = try!(capnp::serialize_packed::read_message(&mut source, capnp::message::ReaderOptions {
+            traversal_limit_in_words: u64::max_value(), nesting_limit: 64
+        }).map_err(|err| err.to_string()));
+
+        let size = {
+            let texture = try!(message.get_root::<texture_protocol::Reader>().map_err(|err| err.to_string()));
+
+            (texture.get_dimensions().get_width(), texture.get_dimensions().get_height())
+        };
+
+        (TextureData::Native(message), size)
+    } else {
+        let image = try!(image::open(path).map_err(|err| err.to_string()));
+        let size = image.dimensions();
+
+        (TextureData::Image(image), size)
+    };
+
+    Ok(LoadedTexture { path: path.to_path_buf(), data: data, size: size })
+}
+
+fn load_model(path: &Path) -> Result<ModelAsset, String> {
+    let vfs = Arc::new(Box::new(DefaultFS) as BoxedVFS);
+
+    ModelAsset::load(AssetMedium::File(path, vfs), ()).map_err(|err| err.to_string())
+}
+
+/// Loads dropped files one at a time on a background thread.
+///
+/// Textures are sent on to the render thread to be shown in the overlay, and models are only loaded and logged,
+/// since there's nothing to add them to the scene with yet.
+pub struct FileDropLoader {
+    tx: Option<mpsc::Sender<PathBuf>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl FileDropLoader {
+    pub fn spawn(render_tx: mpsc::Sender<RenderSignal>) -> FileDropLoader {
+        let (tx, rx) = mpsc::channel::<PathBuf>();
+
+        let thread = thread::Builder::new().name("File drop loader".to_string()).spawn(move || {
+            //Each file is finished before the next one is started, in the order they were dropped
+            for path in rx.iter() {
+                match classify(&path) {
+                    Some(DroppedKind::Texture) => {
+                        match read_texture(&path) {
+                            Ok(texture) => {
+                                info!("Loaded {}x{} texture {}", texture.size.0, texture.size.1, path.display());
+
+                                if render_tx.send(RenderSignal::ShowTexture(texture)).is_err() {
+                                    break;
+                                }
+                            }
+                            Err(err) => error!("Couldn't load texture {}: {}", path.display(), err),
+                        }
+                    }
+                    Some(DroppedKind::Model) => {
+                        match load_model(&path) {
+                            Ok(model) => info!("Loaded model {} with {} meshes", path.display(), model.meshes.len()),
+                            Err(err) => error!("Couldn't load model {}: {}", path.display(), err),
+                        }
+                    }
+                    None => warn!("Don't know how to load {}", path.display()),
+                }
+            }
+        });
+
+        match thread {
+            Ok(thread) => FileDropLoader { tx: Some(tx), thread: Some(thread) },
+            Err(err) => {
+                error!("Could not start file drop loader: {}", err);
+
+                FileDropLoader { tx: None, thread: None }
+            }
+        }
+    }
+
+    /// Queues dropped files for loading, returning immediately
+    pub fn load(&self, paths: Vec<PathBuf>) {
+        if let Some(ref tx) = self.tx {
+            for path in paths {
+                if tx.send(path).is_err() {
+                    error!("File drop loader has stopped");
+
+                    return;
+                }
+            }
+        } else {
+            warn!("Ignoring dropped files, since the file drop loader isn't running");
+        }
+    }
+}
+
+impl Drop for FileDropLoader {
+    fn drop(&mut self) {
+        //Closing the channel ends the thread once it's done with what's queued
+        self.tx.take();
+
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                error!("File drop loader panicked");
+            }
+        }
+    }
+}
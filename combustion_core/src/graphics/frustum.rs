@@ -0,0 +1,5 @@
+//! View frustum extraction and intersection tests for culling
+//!
+//! See `ecs::frustum`.
+
+pub use ecs::frustum::*;
@@ -1,7 +1,96 @@
-use glfw::{Glfw, Window, Monitor, WindowMode, VidMode, SwapInterval};
+use std::fmt;
+
+use glfw::{Glfw, Window, Monitor, WindowMode, VidMode};
+
+/// How the window covers the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FullscreenMode {
+    Windowed,
+    /// Takes over a monitor, at its current video mode unless another one was chosen with `Toggle::set_video_mode`
+    Exclusive,
+    /// A regular window moved and resized to cover a monitor, which switches in and out
+    /// without a mode change and doesn't minimize when focus is lost.
+    ///
+    /// GLFW can't remove the decorations of an existing window, so the title bar is left just above the monitor.
+    Borderless,
+}
+
+impl Default for FullscreenMode {
+    #[inline(always)]
+    fn default() -> FullscreenMode { FullscreenMode::Windowed }
+}
+
+/// Resolution and refresh rate of a monitor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VideoMode {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: u32,
+}
+
+impl<'a> From<&'a VidMode> for VideoMode {
+    fn from(mode: &'a VidMode) -> VideoMode {
+        VideoMode { width: mode.width, height: mode.height, refresh_rate: mode.refresh_rate }
+    }
+}
+
+impl fmt::Display for VideoMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{} at {}Hz", self.width, self.height, self.refresh_rate)
+    }
+}
+
+impl VideoMode {
+    /// The mode out of `modes` matching this one exactly, or else the one closest in size and then refresh rate
+    pub fn closest(&self, modes: &[VideoMode]) -> Option<VideoMode> {
+        modes.iter().cloned().min_by_key(|mode| {
+            let size = (mode.width as i64 - self.width as i64).abs() + (mode.height as i64 - self.height as i64).abs();
+            let refresh = (mode.refresh_rate as i64 - self.refresh_rate as i64).abs();
+
+            (size, refresh)
+        })
+    }
+}
+
+/// A connected monitor, as listed by `Toggle::monitors`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    /// Index for `Toggle::set_target_monitor`, which is only valid until monitors are connected or disconnected
+    pub index: usize,
+    pub name: String,
+    /// Position of the monitor's top left corner on the virtual desktop
+    pub position: (i32, i32),
+    /// The desktop video mode, if the monitor reports one
+    pub current_mode: Option<VideoMode>,
+    pub modes: Vec<VideoMode>,
+}
+
+impl MonitorInfo {
+    fn new(index: usize, monitor: &Monitor) -> MonitorInfo {
+        MonitorInfo {
+            index: index,
+            name: monitor.get_name(),
+            position: monitor.get_pos(),
+            current_mode: monitor.get_video_mode().as_ref().map(VideoMode::from),
+            modes: monitor.get_video_modes().iter().map(VideoMode::from).collect(),
+        }
+    }
+
+    fn contains(&self, (x, y): (i32, i32)) -> bool {
+        match self.current_mode {
+            Some(mode) => x >= self.position.0 && x < self.position.0 + mode.width as i32 &&
+                          y >= self.position.1 && y < self.position.1 + mode.height as i32,
+            None => false,
+        }
+    }
+}
 
 pub struct Toggle {
-    is_fullscreen: bool,
+    mode: FullscreenMode,
+    target_monitor: Option<usize>,
+    video_mode: Option<VideoMode>,
+    /// Name of the monitor the window is fullscreen on
+    monitor: Option<String>,
     last_size: (i32, i32),
     last_pos: (i32, i32)
 }
@@ -14,31 +103,179 @@ impl Default for Toggle {
 impl Toggle {
     pub fn new() -> Toggle {
         Toggle {
-            is_fullscreen: false,
+            mode: FullscreenMode::Windowed,
+            target_monitor: None,
+            video_mode: None,
+            monitor: None,
             last_pos: (0, 0),
             last_size: (0, 0)
         }
     }
 
+    #[inline(always)]
+    pub fn mode(&self) -> FullscreenMode { self.mode }
+
+    #[inline(always)]
+    pub fn is_fullscreen(&self) -> bool { self.mode != FullscreenMode::Windowed }
+
+    /// Every connected monitor and the video modes it supports
+    pub fn monitors(glfw: &mut Glfw) -> Vec<MonitorInfo> {
+        glfw.with_connected_monitors_mut(|_: &mut _, monitors: &[Monitor]| {
+            monitors.iter().enumerate().map(|(index, monitor)| MonitorInfo::new(index, monitor)).collect()
+        })
+    }
+
+    #[inline(always)]
+    pub fn target_monitor(&self) -> Option<usize> { self.target_monitor }
+
+    /// Chooses the monitor to go fullscreen on by its index in `monitors`, or `None` for whichever contains the center of the window.
+    ///
+    /// Takes effect the next time the window goes fullscreen.
+    #[inline(always)]
+    pub fn set_target_monitor(&mut self, index: Option<usize>) {
+        self.target_monitor = index;
+    }
+
+    #[inline(always)]
+    pub fn video_mode(&self) -> Option<VideoMode> { self.video_mode }
+
+    /// Chooses the video mode for exclusive fullscreen, or `None` for the monitor's desktop mode.
+    ///
+    /// If the monitor doesn't support it, the closest mode it does support is used instead.
+    #[inline(always)]
+    pub fn set_video_mode(&mut self, mode: Option<VideoMode>) {
+        self.video_mode = mode;
+    }
+
+    /// Switches between windowed and exclusive fullscreen, or back to windowed from borderless
     pub fn toggle(&mut self, glfw: &mut Glfw, window: &mut Window) {
-        if self.is_fullscreen {
-            window.set_monitor(WindowMode::Windowed, self.last_pos.0, self.last_pos.1, self.last_size.0 as u32, self.last_size.1 as u32, None);
-            info!("Window restored to {:?} at location {:?}", self.last_size, self.last_pos);
-        } else {
+        let mode = if self.is_fullscreen() { FullscreenMode::Windowed } else { FullscreenMode::Exclusive };
+
+        self.set_mode(glfw, window, mode);
+    }
+
+    /// Switches between windowed and borderless fullscreen, or back to windowed from exclusive
+    pub fn toggle_borderless(&mut self, glfw: &mut Glfw, window: &mut Window) {
+        let mode = if self.is_fullscreen() { FullscreenMode::Windowed } else { FullscreenMode::Borderless };
+
+        self.set_mode(glfw, window, mode);
+    }
+
+    /// Switches to `mode`. Going back to windowed restores the window's size and position from before it last left windowed mode,
+    /// however many fullscreen modes it went through since.
+    ///
+    /// If there's no monitor to go fullscreen on, the window is left as it is.
+    pub fn set_mode(&mut self, glfw: &mut Glfw, window: &mut Window, mode: FullscreenMode) {
+        if mode == self.mode {
+            return;
+        }
+
+        if self.mode == FullscreenMode::Windowed {
             self.last_pos = window.get_pos();
             self.last_size = window.get_size();
+        }
+
+        if mode == FullscreenMode::Windowed {
+            window.set_monitor(WindowMode::Windowed, self.last_pos.0, self.last_pos.1, self.last_size.0 as u32, self.last_size.1 as u32, None);
+            info!("Window restored to {:?} at location {:?}", self.last_size, self.last_pos);
+
+            self.mode = mode;
+            self.monitor = None;
+
+            return;
+        }
 
-            glfw.with_primary_monitor_mut(|_: &mut _, m: Option<&Monitor>| {
-                let monitor = m.unwrap();
+        let center = (self.last_pos.0 + self.last_size.0 / 2, self.last_pos.1 + self.last_size.1 / 2);
+        let target = self.target_monitor;
+        let wanted = self.video_mode;
 
-                let mode: VidMode = monitor.get_video_mode().unwrap();
+        let monitor = glfw.with_connected_monitors_mut(|_: &mut _, monitors: &[Monitor]| -> Option<String> {
+            let infos: Vec<MonitorInfo> = monitors.iter().enumerate().map(|(index, monitor)| MonitorInfo::new(index, monitor)).collect();
 
-                window.set_monitor(WindowMode::FullScreen(&monitor), 0, 0, mode.width, mode.height, Some(mode.refresh_rate));
+            let chosen = match target {
+                Some(index) if index < infos.len() => Some(index),
+                Some(index) => {
+                    warn!("Monitor {} isn't connected, using the one the window is on instead", index);
 
-                info!("{}x{} fullscreen enabled at {}Hz on monitor {}", mode.width, mode.height, mode.refresh_rate, monitor.get_name());
-            });
+                    None
+                }
+                None => None,
+            }.or_else(|| infos.iter().position(|info| info.contains(center)))
+             .or_else(|| if infos.is_empty() { None } else { Some(0) });
+
+            let (monitor, info) = match chosen {
+                Some(index) => (&monitors[index], &infos[index]),
+                None => {
+                    warn!("No monitors connected, staying windowed");
+
+                    return None;
+                }
+            };
+
+            let desktop = match info.current_mode {
+                Some(desktop) => desktop,
+                None => {
+                    warn!("Monitor {} has no video mode, staying windowed", info.name);
+
+                    return None;
+                }
+            };
+
+            if mode == FullscreenMode::Exclusive {
+                let video = match wanted {
+                    Some(wanted) => {
+                        let closest = wanted.closest(&info.modes).unwrap_or(desktop);
+
+                        if closest != wanted {
+                            warn!("Monitor {} doesn't support {}, using {} instead", info.name, wanted, closest);
+                        }
+
+                        closest
+                    }
+                    None => desktop,
+                };
+
+                window.set_monitor(WindowMode::FullScreen(monitor), 0, 0, video.width, video.height, Some(video.refresh_rate));
+
+                info!("{} fullscreen enabled on monitor {}", video, info.name);
+            } else {
+                window.set_monitor(WindowMode::Windowed, info.position.0, info.position.1, desktop.width, desktop.height, None);
+
+                info!("{}x{} borderless fullscreen enabled on monitor {}", desktop.width, desktop.height, info.name);
+            }
+
+            Some(info.name.clone())
+        });
+
+        if monitor.is_some() {
+            self.mode = mode;
+            self.monitor = monitor;
+        }
+    }
+
+    /// Call after a monitor is connected or disconnected, such as from GLFW's monitor callback.
+    ///
+    /// A target monitor that's gone is forgotten, and if the window was fullscreen on a monitor that was disconnected, it goes back to windowed.
+    pub fn monitors_changed(&mut self, glfw: &mut Glfw, window: &mut Window) {
+        let names: Vec<String> = Toggle::monitors(glfw).into_iter().map(|info| info.name).collect();
+
+        if let Some(index) = self.target_monitor {
+            if index >= names.len() {
+                warn!("Monitor {} was disconnected, going fullscreen on the one the window is on instead", index);
+
+                self.target_monitor = None;
+            }
         }
 
-        self.is_fullscreen = !self.is_fullscreen;
+        let disconnected = match self.monitor {
+            Some(ref name) => !names.contains(name),
+            None => false,
+        };
+
+        if disconnected {
+            warn!("Fullscreen monitor was disconnected, going back to windowed");
+
+            self.set_mode(glfw, window, FullscreenMode::Windowed);
+        }
     }
 }
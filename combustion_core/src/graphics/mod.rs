@@ -1,6 +1,17 @@
 pub mod render;
 pub mod fullscreen;
 pub mod pipeline;
+pub mod screenshot;
+pub mod frustum;
+pub mod vsync;
+pub mod scale;
+pub mod file_drop;
+pub mod shader_watch;
 
-pub use self::fullscreen::Toggle as FullscreenToggle;
-pub use self::render::RenderSignal;
\ No newline at end of file
+pub use self::fullscreen::{Toggle as FullscreenToggle, FullscreenMode, VideoMode, MonitorInfo};
+pub use self::frustum::Frustum;
+pub use self::vsync::VsyncMode;
+pub use self::scale::{content_scale, window_to_framebuffer};
+pub use self::render::{RenderSignal, RenderReply, FrameStats, FixedSteps};
+pub use self::file_drop::{FileDropLoader, LoadedTexture};
+pub use self::shader_watch::{ShaderWatcher, SHADER_DIRECTORY};
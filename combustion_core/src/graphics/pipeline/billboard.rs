@@ -0,0 +1,137 @@
+//! Camera-facing quads such as particles and labels, drawn instanced in the transparent pass
+//!
+//! Instances are placed and sorted by the billboard system, so this only uploads and draws them.
+
+use std::mem;
+
+use nalgebra::Matrix4;
+
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+use ::backend::gl::bindings as glb;
+
+use super::blocks::{CAMERA_BLOCK, CAMERA_BINDING};
+
+/// Unit quad from -0.5 to 0.5 facing +Z, as position and UV
+static QUAD_DATA: [f32; 16] = [
+    -0.5, 0.5, 0.0, 1.0,
+    -0.5, -0.5, 0.0, 0.0,
+    0.5, 0.5, 1.0, 1.0,
+    0.5, -0.5, 1.0, 0.0,
+];
+
+/// First of the four attribute locations holding the per-instance model matrix, one column each
+pub const BILLBOARD_MODEL_LOCATION: GLuint = 2;
+
+/// Attribute location of the per-instance tint
+pub const BILLBOARD_TINT_LOCATION: GLuint = 6;
+
+/// Floats per instance, for the model matrix followed by the tint
+const BILLBOARD_FLOATS: usize = 20;
+
+const BILLBOARD_UNIT: usize = 0;
+
+fn load_billboard_shader() -> GLResult<GLShaderProgram> {
+    let vertex_shader = try!(GLShader::from_file("shaders/billboard.vert", GLShaderVariant::VertexShader));
+    let fragment_shader = try!(GLShader::from_file("shaders/billboard.frag", GLShaderVariant::FragmentShader));
+
+    Ok(GLShaderProgramBuilder::new()?
+        .attach_shader(vertex_shader)?
+        .attach_shader(fragment_shader)?
+        .link()?
+        .uniform_block(CAMERA_BLOCK, CAMERA_BINDING)?
+        .finish())
+}
+
+pub struct BillboardRenderer {
+    shader: GLShaderProgram,
+    vao: GLVertexArray,
+    quad: GLBuffer,
+    instances: GLBuffer,
+    data: Vec<f32>,
+}
+
+impl BillboardRenderer {
+    pub fn new() -> GLResult<BillboardRenderer> {
+        let mut quad = try!(GLBuffer::array_buffer());
+
+        try!(quad.upload(&QUAD_DATA, GLBufferUsage::StaticDraw));
+
+        let stride = 4 * mem::size_of::<f32>();
+
+        let vao = GLVertexArrayBuilder::new()?
+            .buffer(&quad)?
+            .attribute(0, 2, glb::FLOAT, false, stride, 0)?
+            .attribute(1, 2, glb::FLOAT, false, stride, 2 * mem::size_of::<f32>())?
+            .finish();
+
+        Ok(BillboardRenderer {
+            shader: try!(load_billboard_shader()),
+            vao: vao,
+            quad: quad,
+            instances: try!(GLBuffer::array_buffer()),
+            data: Vec::new(),
+        })
+    }
+
+    /// Draws a batch of billboards sharing a texture, in the order given, or in their tint alone if `texture` is `None`.
+    ///
+    /// Depth testing and blending are left as they are, so this is meant to be called inside the transparent pass,
+    /// after the `Camera` uniform block has been set.
+    pub fn draw<'a, I>(&mut self, texture: Option<&GLTexture>, instances: I) -> GLResult<()>
+        where I: IntoIterator<Item = (&'a Matrix4<f32>, &'a [f32; 4])> {
+        self.data.clear();
+
+        for (m, tint) in instances {
+            self.data.extend_from_slice(&[
+                m.m11, m.m21, m.m31, m.m41,
+                m.m12, m.m22, m.m32, m.m42,
+                m.m13, m.m23, m.m33, m.m43,
+                m.m14, m.m24, m.m34, m.m44,
+            ]);
+
+            self.data.extend_from_slice(tint);
+        }
+
+        let count = self.data.len() / BILLBOARD_FLOATS;
+
+        if count == 0 {
+            return Ok(());
+        }
+
+        try!(self.shader.use_program());
+        try!(self.shader.get_uniform_optional("textured")?.bool1(texture.is_some()));
+
+        if let Some(texture) = texture {
+            try!(self.shader.get_uniform_optional("color")?.int1(BILLBOARD_UNIT as GLint));
+
+            GLStateCache::active_texture(BILLBOARD_UNIT);
+
+            try!(texture.bind());
+        }
+
+        try!(self.vao.bind());
+        try!(self.instances.bind());
+
+        //Reallocating the storage every time lets the driver orphan the old one instead of synchronizing
+        try!(self.instances.buffer_slice(&self.data, GLBufferUsage::StreamDraw));
+
+        let stride = BILLBOARD_FLOATS * mem::size_of::<f32>();
+
+        for column in 0..4 {
+            let offset = column * 4 * mem::size_of::<f32>();
+
+            try!(GLVertexAttribute::new(BILLBOARD_MODEL_LOCATION + column as GLuint, 4, glb::FLOAT, false, stride, offset).instanced(1).apply());
+        }
+
+        try!(GLVertexAttribute::new(BILLBOARD_TINT_LOCATION, 4, glb::FLOAT, false, stride, 16 * mem::size_of::<f32>()).instanced(1).apply());
+
+        unsafe {
+            glb::DrawArraysInstanced(glb::TRIANGLE_STRIP, 0, 4, count as GLsizei);
+        }
+
+        check_errors!();
+
+        Ok(())
+    }
+}
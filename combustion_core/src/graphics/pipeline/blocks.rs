@@ -0,0 +1,63 @@
+//! Uniform blocks shared by every shader that needs the camera or lights, updated once per frame
+
+use nalgebra::{Point3, Matrix4};
+
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+
+use super::lights::{self, Light, MAX_LIGHTS};
+
+/// Must match the `Camera` block in `shaders/lib/camera.glsl`
+pub const CAMERA_BLOCK: &'static str = "Camera";
+pub const CAMERA_BINDING: GLuint = 0;
+
+/// Must match the `Lights` block in `shaders/lib/lights.glsl`
+pub const LIGHTS_BLOCK: &'static str = "Lights";
+pub const LIGHTS_BINDING: GLuint = 1;
+
+/// Columns of the matrix, as expected by `Std140Writer::mat4`
+pub fn columns(m: &Matrix4<f32>) -> [[f32; 4]; 4] {
+    [
+        [m.m11, m.m21, m.m31, m.m41],
+        [m.m12, m.m22, m.m32, m.m42],
+        [m.m13, m.m23, m.m33, m.m43],
+        [m.m14, m.m24, m.m34, m.m44],
+    ]
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CameraBlock {
+    pub view: Matrix4<f32>,
+    pub projection: Matrix4<f32>,
+    pub position: Point3<f32>,
+}
+
+impl Std140 for CameraBlock {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.mat4(&columns(&self.view))
+              .mat4(&columns(&self.projection))
+              .mat4(&columns(&(self.projection * self.view)))
+              .vec3([self.position.x, self.position.y, self.position.z]);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LightsBlock {
+    pub lights: Vec<Light>,
+}
+
+impl Std140 for LightsBlock {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        let count = self.lights.len().min(MAX_LIGHTS);
+        let padding = Light::default();
+
+        //Written like `Std140Writer::array`, which needs `Light` to implement `Std140` in this crate
+        for i in 0..MAX_LIGHTS {
+            writer.align(16);
+            lights::write_std140(self.lights[..count].get(i).unwrap_or(&padding), writer);
+            writer.align(16);
+        }
+
+        writer.int(count as i32);
+    }
+}
@@ -0,0 +1,171 @@
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+use ::backend::gl::bindings as glb;
+
+use super::stage::Stage;
+use super::gbuffer::DepthStencilMode;
+use super::screen::ScreenQuad;
+use super::pingpong::PingPongStages;
+
+/// Upper limit on the number of downsample/blur iterations, and therefore the length of the chain
+pub const MAX_BLOOM_ITERATIONS: usize = 6;
+
+pub const BLOOM_STAGE_COMPONENTS: [(GLenum, GLenum); 1] = [
+    (glb::RGB, glb::RGB16F)
+];
+
+/// Adjustable bloom parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomSettings {
+    /// Bloom is disabled by default, in which case nothing is composited before tonemapping
+    pub enabled: bool,
+    /// Number of downsample/blur iterations, up to `MAX_BLOOM_ITERATIONS`
+    pub iterations: usize,
+    /// Luminance above which the lighting output starts to bloom
+    pub threshold: f32,
+    /// Number of separable gaussian blur passes, each one horizontal and one vertical, applied to the thresholded first level
+    pub blur_passes: usize,
+    /// Strength of the bloom when composited onto the screen
+    pub intensity: f32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> BloomSettings {
+        BloomSettings {
+            enabled: false,
+            iterations: 5,
+            threshold: 1.0,
+            blur_passes: 1,
+            intensity: 0.2,
+        }
+    }
+}
+
+fn load_screen_shader(fragment: &str) -> GLResult<GLShaderProgram> {
+    let vertex_shader = try!(GLShader::from_file("shaders/screen.vert", GLShaderVariant::VertexShader));
+    let fragment_shader = try!(GLShader::from_file(fragment, GLShaderVariant::FragmentShader));
+
+    Ok(GLShaderProgramBuilder::new()?
+        .attach_shader(vertex_shader)?
+        .attach_shader(fragment_shader)?
+        .link()?
+        .finish())
+}
+
+/// Size of the given level of the chain, where level 0 is half the pipeline resolution
+pub fn level_size(width: usize, height: usize, level: usize) -> (usize, usize) {
+    ((width >> (level + 1)).max(1), (height >> (level + 1)).max(1))
+}
+
+/// Chain of progressively half-sized stages used for bloom.
+///
+/// The first level holds the thresholded and blurred lighting output, and every level after that is a blurred downsample of the one before it.
+pub struct BloomChain {
+    /// The first level, which is blurred back and forth between a pair of stages
+    first: PingPongStages,
+    /// Every level after the first
+    levels: Vec<Stage>,
+    threshold_shader: GLShaderProgram,
+    downsample_shader: GLShaderProgram,
+    blur_shader: GLShaderProgram,
+    composite_shader: GLShaderProgram,
+}
+
+impl BloomChain {
+    pub fn new(width: usize, height: usize) -> GLResult<BloomChain> {
+        let (first_width, first_height) = level_size(width, height, 0);
+
+        let mut levels = Vec::with_capacity(MAX_BLOOM_ITERATIONS - 1);
+
+        for level in 1..MAX_BLOOM_ITERATIONS {
+            let (width, height) = level_size(width, height, level);
+
+            let mut stage = try!(Stage::new(width, height, Some(&BLOOM_STAGE_COMPONENTS), 1, DepthStencilMode::None));
+
+            try!(stage.set_filter(GLTextureFilter::Linear));
+            try!(stage.set_wrap(GLTextureWrap::ClampToEdge));
+
+            levels.push(stage);
+        }
+
+        Ok(BloomChain {
+            first: try!(PingPongStages::new(first_width, first_height, &BLOOM_STAGE_COMPONENTS)),
+            levels: levels,
+            threshold_shader: try!(load_screen_shader("shaders/bloom_threshold.frag")),
+            downsample_shader: try!(load_screen_shader("shaders/bloom_downsample.frag")),
+            blur_shader: try!(load_screen_shader("shaders/bloom_blur.frag")),
+            composite_shader: try!(load_screen_shader("shaders/bloom_composite.frag")),
+        })
+    }
+
+    /// The stage holding the result of the given level, up to `MAX_BLOOM_ITERATIONS`
+    #[inline]
+    pub fn level(&self, level: usize) -> &Stage {
+        if level == 0 { self.first.source() } else { &self.levels[level - 1] }
+    }
+
+    #[inline(always)]
+    pub fn first(&self) -> &PingPongStages { &self.first }
+
+    #[inline(always)]
+    pub fn first_mut(&mut self) -> &mut PingPongStages { &mut self.first }
+
+    #[inline(always)]
+    pub fn blur_shader(&self) -> &GLShaderProgram { &self.blur_shader }
+
+    #[inline(always)]
+    pub fn threshold_shader(&self) -> &GLShaderProgram { &self.threshold_shader }
+
+    #[inline(always)]
+    pub fn downsample_shader(&self) -> &GLShaderProgram { &self.downsample_shader }
+
+    #[inline(always)]
+    pub fn composite_shader(&self) -> &GLShaderProgram { &self.composite_shader }
+
+    /// Blurs the first level with `passes` horizontal and vertical gaussian passes, alternating between its pair of stages
+    pub fn blur(&mut self, passes: usize, screen: &ScreenQuad) -> GLResult<()> {
+        let shader = &self.blur_shader;
+
+        try!(shader.use_program());
+
+        self.first.iterate(passes * 2, |source, _, i| {
+            let (x, y) = if i % 2 == 0 { (1.0, 0.0) } else { (0.0, 1.0) };
+
+            try!(shader.get_uniform("direction")?.float2(x, y));
+
+            try!(bind_source(shader, "source", source, 0));
+
+            screen.draw()
+        })
+    }
+
+    /// Recomputes the size of every level from the new pipeline resolution
+    pub fn resize(&mut self, width: usize, height: usize) -> GLResult<()> {
+        let (first_width, first_height) = level_size(width, height, 0);
+
+        try!(self.first.resize(first_width, first_height));
+
+        for (level, stage) in self.levels.iter_mut().enumerate() {
+            let (width, height) = level_size(width, height, level + 1);
+
+            try!(stage.resize(width, height));
+        }
+
+        Ok(())
+    }
+}
+
+/// Binds the first component of `stage` to the given texture unit and sets the `name` uniform of `shader`
+pub fn bind_source(shader: &GLShaderProgram, name: &str, stage: &Stage, unit: usize) -> GLResult<()> {
+    let texture = try!(stage.gbuffer().and_then(|gbuffer| gbuffer.component(0)).ok_or(GLError::InvalidOperation));
+
+    try!(shader.get_uniform_optional(name)?.int1(unit as GLint));
+
+    GLStateCache::active_texture(unit);
+
+    check_errors!();
+
+    try!(texture.bind());
+
+    Ok(())
+}
@@ -0,0 +1,366 @@
+use nalgebra::Vector2;
+
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+
+use super::stage::{Stage, ClearValues};
+use super::gbuffer::DepthStencilMode;
+use super::screen::ScreenQuad;
+use super::pipeline::{Pipeline, NamedStage, StageInput, SHADOW_MAP_BINDING, SSAO_NOISE_BINDING};
+use super::shadow::ShadowStage;
+use super::ssao::{SsaoKernel, SsaoSettings};
+use super::bloom::{BloomChain, BloomSettings};
+use super::tonemap::{LuminanceTarget, TonemapSettings, LUMINANCE_BINDING};
+use super::material::MATERIAL_UNITS;
+use super::debug::{self, DebugView, DebugRaster, RasterMode};
+use super::skybox;
+use super::volume::LightVolumes;
+use super::instancing::InstancingSettings;
+use super::blocks::{LightsBlock, CAMERA_BLOCK, CAMERA_BINDING, LIGHTS_BLOCK, LIGHTS_BINDING};
+use super::timing::GpuTimer;
+use super::samplers::SamplerSet;
+use super::reload::{ProgramSource, ProgramSlot};
+
+/// Builds up a `Pipeline` from an ordered list of stages.
+///
+/// Each call to `stage` adds a new stage after all previous ones, and all other methods
+/// configure the most recently added stage.
+///
+/// ```ignore
+/// let pipeline = PipelineBuilder::new(1280, 720)
+///     .stage("geometry", Some(&GEOMETRY_STAGE_COMPONENTS), Some(geometry_shader))?
+///     .wrap(GLTextureWrap::ClampToEdge)?
+///     .stage("lighting", Some(&LIGHTING_STAGE_COMPONENTS), None)?
+///     .samples("geometry", &LIGHTING_STAGE_NAMES)?
+///     .finish();
+/// ```
+pub struct PipelineBuilder {
+    width: usize,
+    height: usize,
+    stages: Vec<NamedStage>,
+    shadow_stage: Option<ShadowStage>,
+    ssao_kernel: Option<SsaoKernel>,
+    bloom_chain: Option<BloomChain>,
+    luminance: Option<LuminanceTarget>,
+    depth_shader: Option<GLShaderProgram>,
+    forward_shader: Option<GLShaderProgram>,
+    light_volumes: Option<LightVolumes>,
+    sky_shader: Option<GLShaderProgram>,
+    debug_raster: Option<DebugRaster>,
+    program_sources: Vec<(ProgramSlot, ProgramSource)>,
+}
+
+impl PipelineBuilder {
+    pub fn new(width: usize, height: usize) -> PipelineBuilder {
+        PipelineBuilder {
+            width: width,
+            height: height,
+            stages: Vec::new(),
+            shadow_stage: None,
+            ssao_kernel: None,
+            bloom_chain: None,
+            luminance: None,
+            depth_shader: None,
+            forward_shader: None,
+            light_volumes: None,
+            sky_shader: None,
+            debug_raster: None,
+            program_sources: Vec::new(),
+        }
+    }
+
+    /// Adds a new stage with the given attachments and an optional shader program.
+    ///
+    /// A stage without any attachments renders to the default framebuffer.
+    pub fn stage(mut self, name: &str, attachments: Option<&[(GLenum, GLenum)]>, shader: Option<GLShaderProgram>) -> GLResult<PipelineBuilder> {
+        // Stage names are used for lookups, so they must be unique
+        if self.stages.iter().any(|stage| stage.name == name) {
+            return Err(GLError::InvalidValue);
+        }
+
+        let stage = try!(Stage::new(self.width, self.height, attachments, 1, DepthStencilMode::DepthStencilRenderbuffer));
+
+        self.stages.push(NamedStage {
+            name: name.to_string(),
+            stage: stage,
+            scale: 1.0,
+            shader: shader,
+            inputs: Vec::new(),
+            clear: ClearValues::default(),
+        });
+
+        Ok(self)
+    }
+
+    /// Recreates the last stage with multisampled attachments. Requesting more than `GL_MAX_SAMPLES` clamps with a warning.
+    ///
+    /// Filtering and wrapping don't apply to multisampled stages, and they can't be sampled by later stages,
+    /// so they have to be resolved with `Stage::resolve_to` first.
+    pub fn multisample(mut self, samples: u32) -> GLResult<PipelineBuilder> {
+        let (width, height) = (self.width, self.height);
+
+        let last = try!(self.last_mut());
+
+        let components = try!(last.stage.gbuffer().map(|gbuffer| gbuffer.components().to_vec()).ok_or(GLError::InvalidOperation));
+
+        let width = ((width as f32 * last.scale) as usize).max(1);
+        let height = ((height as f32 * last.scale) as usize).max(1);
+
+        let depth_stencil = last.stage.depth_stencil_mode();
+
+        last.stage = try!(Stage::new(width, height, Some(&components), samples, depth_stencil));
+
+        Ok(self)
+    }
+
+    /// Recreates the last stage with a different kind of depth and stencil storage.
+    /// Stages use a `DEPTH24_STENCIL8` renderbuffer by default.
+    ///
+    /// Call this before `shares_depth_with`, since it replaces the stage's attachments.
+    pub fn depth_stencil(mut self, mode: DepthStencilMode) -> GLResult<PipelineBuilder> {
+        let (width, height) = (self.width, self.height);
+
+        let last = try!(self.last_mut());
+
+        let components = try!(last.stage.gbuffer().map(|gbuffer| gbuffer.components().to_vec()).ok_or(GLError::InvalidOperation));
+
+        let width = ((width as f32 * last.scale) as usize).max(1);
+        let height = ((height as f32 * last.scale) as usize).max(1);
+
+        let samples = last.stage.samples();
+
+        last.stage = try!(Stage::new(width, height, Some(&components), samples, mode));
+
+        Ok(self)
+    }
+
+    /// Declares that the last stage samples the G-buffer components of a previous stage,
+    /// binding each component to the uniform of the same index in `names`.
+    pub fn samples(mut self, from: &str, names: &[&str]) -> GLResult<PipelineBuilder> {
+        let len = self.stages.len();
+
+        // Only stages that come before the last stage can be sampled
+        let index = match self.stages.iter().take(len.saturating_sub(1)).position(|stage| stage.name == from) {
+            Some(index) if self.stages[index].stage.gbuffer().is_some() => index,
+            _ => return Err(GLError::InvalidValue),
+        };
+
+        // Multisampled stages have to be resolved into another stage before they can be sampled
+        if self.stages[index].stage.samples() > 1 {
+            return Err(GLError::InvalidOperation);
+        }
+
+        try!(self.last_mut()).inputs.push(StageInput {
+            stage: index,
+            names: names.iter().map(|name| name.to_string()).collect(),
+        });
+
+        Ok(self)
+    }
+
+    /// Makes the last stage render with the depth-stencil buffer of a previous stage instead of its own
+    pub fn shares_depth_with(mut self, from: &str) -> GLResult<PipelineBuilder> {
+        let len = self.stages.len();
+
+        let index = try!(self.stages.iter().take(len.saturating_sub(1)).position(|stage| stage.name == from).ok_or(GLError::InvalidValue));
+
+        let (previous, last) = self.stages.split_at_mut(len - 1);
+
+        try!(last[0].stage.share_depth(&previous[index].stage));
+
+        Ok(self)
+    }
+
+    /// Sets what the last stage's attachments are cleared to. By default only color is cleared, to zero.
+    pub fn clear(mut self, values: ClearValues) -> GLResult<PipelineBuilder> {
+        try!(self.last_mut()).clear = values;
+
+        Ok(self)
+    }
+
+    /// Sets the texture filtering of the last stage's attachments
+    pub fn filter(mut self, filter: GLTextureFilter) -> GLResult<PipelineBuilder> {
+        try!(try!(self.last_mut()).stage.set_filter(filter));
+
+        Ok(self)
+    }
+
+    /// Sets the texture wrapping of the last stage's attachments
+    pub fn wrap(mut self, wrap: GLTextureWrap) -> GLResult<PipelineBuilder> {
+        try!(try!(self.last_mut()).stage.set_wrap(wrap));
+
+        Ok(self)
+    }
+
+    /// Scales the last stage relative to the pipeline resolution, e.g. `0.5` for a half-resolution stage
+    pub fn scale(mut self, scale: f32) -> GLResult<PipelineBuilder> {
+        let (width, height) = (self.width, self.height);
+
+        let last = try!(self.last_mut());
+
+        last.scale = scale;
+
+        try!(last.stage.resize(((width as f32 * scale) as usize).max(1),
+                               ((height as f32 * scale) as usize).max(1)));
+
+        Ok(self)
+    }
+
+    /// Creates the sample kernel and noise texture required by the SSAO stages
+    pub fn ssao(mut self) -> GLResult<PipelineBuilder> {
+        self.ssao_kernel = Some(try!(SsaoKernel::new()));
+
+        Ok(self)
+    }
+
+    /// Creates the chain of half-sized stages used by the bloom pass. Bloom itself is still disabled by default.
+    pub fn bloom(mut self) -> GLResult<PipelineBuilder> {
+        self.bloom_chain = Some(try!(BloomChain::new(self.width, self.height)));
+
+        Ok(self)
+    }
+
+    /// Creates the luminance target required for `Exposure::Auto`. Without it, automatic exposure falls back to manual exposure.
+    pub fn auto_exposure(mut self) -> GLResult<PipelineBuilder> {
+        self.luminance = Some(try!(LuminanceTarget::new()));
+
+        Ok(self)
+    }
+
+    /// Loads the shader for the optional depth pre-pass. The pre-pass itself is still disabled by default.
+    pub fn depth_prepass(self) -> GLResult<PipelineBuilder> {
+        let source = ProgramSource::new("depth_prepass")
+            .file("shaders/depth_prepass.vert", GLShaderVariant::VertexShader)
+            .file("shaders/depth_prepass.frag", GLShaderVariant::FragmentShader)
+            .block(CAMERA_BLOCK, CAMERA_BINDING);
+
+        self.program(ProgramSlot::DepthPrepass, source)
+    }
+
+    /// Loads the shader handed to the closure of `Pipeline::transparent_pass`
+    pub fn transparency(self) -> GLResult<PipelineBuilder> {
+        let source = ProgramSource::new("forward")
+            .file("shaders/deferred_geometry.vert", GLShaderVariant::VertexShader)
+            .file("shaders/forward.frag", GLShaderVariant::FragmentShader)
+            .block(CAMERA_BLOCK, CAMERA_BINDING)
+            .block(LIGHTS_BLOCK, LIGHTS_BINDING);
+
+        self.program(ProgramSlot::Forward, source)
+    }
+
+    /// Creates the sphere mesh and shaders for rendering point lights as light volumes instead of in the fullscreen lighting pass
+    pub fn light_volumes(mut self) -> GLResult<PipelineBuilder> {
+        self.light_volumes = Some(try!(LightVolumes::new()));
+
+        Ok(self)
+    }
+
+    /// Loads the shader used by `Pipeline::skybox_pass`. Nothing is drawn until a cubemap is given to `Pipeline::set_skybox`.
+    pub fn skybox(self) -> GLResult<PipelineBuilder> {
+        self.program(ProgramSlot::Sky, skybox::sky_shader_source())
+    }
+
+    /// Creates the shaders and overdraw stage required by `Pipeline::set_raster_mode`
+    pub fn debug_raster(mut self) -> GLResult<PipelineBuilder> {
+        self.debug_raster = Some(try!(DebugRaster::new(self.width, self.height)));
+
+        Ok(self)
+    }
+
+    /// Adds a shadow map with the given resolution. The shadow map is not affected by resizing the pipeline.
+    pub fn shadows(mut self, resolution: usize) -> GLResult<PipelineBuilder> {
+        self.shadow_stage = Some(try!(ShadowStage::new(resolution)));
+
+        Ok(self)
+    }
+
+    /// Builds the last stage's shader from `source`, which `Pipeline::reload_shaders` can build again later
+    pub fn shader_source(self, source: ProgramSource) -> GLResult<PipelineBuilder> {
+        let index = try!(self.stages.len().checked_sub(1).ok_or(GLError::InvalidOperation));
+
+        self.program(ProgramSlot::Stage(index), source)
+    }
+
+    /// Builds a program into its slot and remembers the source for reloading
+    fn program(mut self, slot: ProgramSlot, source: ProgramSource) -> GLResult<PipelineBuilder> {
+        let program = Some(try!(source.build()));
+
+        match slot {
+            ProgramSlot::Stage(index) => self.stages[index].shader = program,
+            ProgramSlot::DepthPrepass => self.depth_shader = program,
+            ProgramSlot::Forward => self.forward_shader = program,
+            ProgramSlot::Sky => self.sky_shader = program,
+            // Always loaded by `finish`
+            ProgramSlot::DebugView => return Err(GLError::InvalidValue),
+        }
+
+        self.program_sources.push((slot, source));
+
+        Ok(self)
+    }
+
+    fn last_mut(&mut self) -> GLResult<&mut NamedStage> {
+        self.stages.last_mut().ok_or(GLError::InvalidOperation)
+    }
+
+    pub fn finish(mut self) -> GLResult<Pipeline> {
+        let debug_source = debug::debug_shader_source();
+        let debug_shader = try!(debug_source.build());
+
+        self.program_sources.push((ProgramSlot::DebugView, debug_source));
+
+        for stage in &self.stages {
+            try!(stage.stage.label(&stage.name));
+
+            if let Some(ref shader) = stage.shader {
+                try!(shader.label(&stage.name));
+            }
+        }
+
+        let mut texture_units = try!(GLTextureUnits::new(MATERIAL_UNITS.len()));
+
+        for name in &[SHADOW_MAP_BINDING, SSAO_NOISE_BINDING, LUMINANCE_BINDING] {
+            try!(texture_units.reserve(name));
+        }
+
+        Ok(Pipeline {
+            stages: self.stages,
+            shadow_stage: self.shadow_stage,
+            shadow_matrix: None,
+            ssao_kernel: self.ssao_kernel,
+            ssao_ran: false,
+            ssao: SsaoSettings::default(),
+            bloom_chain: self.bloom_chain,
+            bloom_levels: 0,
+            bloom: BloomSettings::default(),
+            luminance: self.luminance,
+            tonemap: TonemapSettings::default(),
+            depth_shader: self.depth_shader,
+            depth_prepass: false,
+            forward_shader: self.forward_shader,
+            light_volumes: self.light_volumes,
+            instancing: InstancingSettings::default(),
+            fxaa: true,
+            sky_shader: self.sky_shader,
+            skybox: None,
+            skybox_srgb: false,
+            skybox_intensity: 1.0,
+            debug_shader: debug_shader,
+            debug_view: DebugView::default(),
+            debug_raster: self.debug_raster,
+            raster_mode: RasterMode::default(),
+            camera: None,
+            camera_block: try!(GLUniformBuffer::new()),
+            lights: LightsBlock::default(),
+            lights_block: try!(GLUniformBuffer::new()),
+            timer: GpuTimer::new(),
+            samplers: try!(SamplerSet::new()),
+            texture_units: texture_units,
+            screen: try!(ScreenQuad::new()),
+            resolution: Vector2::new(self.width as f32, self.height as f32),
+            window_size: Vector2::new(self.width as f32, self.height as f32),
+            render_scale: 1.0,
+            program_sources: self.program_sources,
+        })
+    }
+}
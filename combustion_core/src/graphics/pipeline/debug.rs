@@ -0,0 +1,132 @@
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+use ::backend::gl::bindings as glb;
+
+use super::stage::Stage;
+use super::gbuffer::DepthStencilMode;
+use super::blocks::{CAMERA_BLOCK, CAMERA_BINDING};
+use super::reload::ProgramSource;
+
+/// Overdraw is accumulated as a single float channel, so it doesn't saturate after a few layers
+pub const OVERDRAW_STAGE_COMPONENTS: [(GLenum, GLenum); 1] = [
+    (glb::RED, glb::R16F)
+];
+
+/// Which buffer the final pass displays. Anything other than `Final` replaces the tonemapped output.
+///
+/// Must match the `DEBUG_*` defines in `shaders/debug_view.frag`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum DebugView {
+    Final = 0,
+    Albedo = 1,
+    Normals = 2,
+    Depth = 3,
+    Position = 4,
+    Smoothness = 5,
+    Ssao = 6,
+    Bloom = 7,
+}
+
+impl Default for DebugView {
+    fn default() -> DebugView { DebugView::Final }
+}
+
+/// Every debug view in order, used for keybindings and cycling
+pub const DEBUG_VIEWS: [DebugView; 8] = [
+    DebugView::Final,
+    DebugView::Albedo,
+    DebugView::Normals,
+    DebugView::Depth,
+    DebugView::Position,
+    DebugView::Smoothness,
+    DebugView::Ssao,
+    DebugView::Bloom,
+];
+
+impl DebugView {
+    #[inline]
+    pub fn from_index(index: usize) -> Option<DebugView> {
+        DEBUG_VIEWS.get(index).cloned()
+    }
+
+    /// The next debug view, wrapping back around to `Final`
+    pub fn next(self) -> DebugView {
+        DEBUG_VIEWS[(self as usize + 1) % DEBUG_VIEWS.len()]
+    }
+}
+
+/// How the geometry pass rasterizes the scene
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RasterMode {
+    /// Normal filled polygons
+    Fill,
+    /// Polygon edges only, drawn in a constant color and displayed unlit
+    Wireframe,
+    /// Every fragment adds to a counter with depth testing disabled, displayed as a heatmap
+    Overdraw,
+}
+
+impl Default for RasterMode {
+    fn default() -> RasterMode { RasterMode::Fill }
+}
+
+/// Shader files of the program used by the final pass to display debug views
+pub fn debug_shader_source() -> ProgramSource {
+    ProgramSource::new("debug_view")
+        .file("shaders/screen.vert", GLShaderVariant::VertexShader)
+        .file("shaders/debug_view.frag", GLShaderVariant::FragmentShader)
+}
+
+fn load_program(vertex: &str, fragment: &str) -> GLResult<GLShaderProgram> {
+    let vertex_shader = try!(GLShader::from_file(vertex, GLShaderVariant::VertexShader));
+    let fragment_shader = try!(GLShader::from_file(fragment, GLShaderVariant::FragmentShader));
+
+    Ok(GLShaderProgramBuilder::new()?
+        .attach_shader(vertex_shader)?
+        .attach_shader(fragment_shader)?
+        .link()?
+        .uniform_block(CAMERA_BLOCK, CAMERA_BINDING)?
+        .finish())
+}
+
+/// Shaders and the accumulation stage used by the wireframe and overdraw raster modes
+pub struct DebugRaster {
+    overdraw: Stage,
+    wireframe_shader: GLShaderProgram,
+    overdraw_shader: GLShaderProgram,
+    overdraw_view_shader: GLShaderProgram,
+}
+
+impl DebugRaster {
+    pub fn new(width: usize, height: usize) -> GLResult<DebugRaster> {
+        let mut overdraw = try!(Stage::new(width, height, Some(&OVERDRAW_STAGE_COMPONENTS), 1, DepthStencilMode::None));
+
+        try!(overdraw.set_filter(GLTextureFilter::Nearest));
+        try!(overdraw.set_wrap(GLTextureWrap::ClampToEdge));
+
+        Ok(DebugRaster {
+            overdraw: overdraw,
+            wireframe_shader: try!(load_program("shaders/deferred_geometry.vert", "shaders/wireframe.frag")),
+            overdraw_shader: try!(load_program("shaders/deferred_geometry.vert", "shaders/overdraw.frag")),
+            overdraw_view_shader: try!(load_program("shaders/screen.vert", "shaders/overdraw_view.frag")),
+        })
+    }
+
+    #[inline(always)]
+    pub fn overdraw(&self) -> &Stage { &self.overdraw }
+
+    #[inline(always)]
+    pub fn wireframe_shader(&self) -> &GLShaderProgram { &self.wireframe_shader }
+
+    #[inline(always)]
+    pub fn overdraw_shader(&self) -> &GLShaderProgram { &self.overdraw_shader }
+
+    #[inline(always)]
+    pub fn overdraw_view_shader(&self) -> &GLShaderProgram { &self.overdraw_view_shader }
+
+    #[inline]
+    pub fn resize(&mut self, width: usize, height: usize) -> GLResult<()> {
+        self.overdraw.resize(width, height)
+    }
+}
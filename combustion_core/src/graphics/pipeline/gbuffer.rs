@@ -4,10 +4,51 @@ use ::backend::gl::*;
 use ::backend::gl::types::*;
 use ::backend::gl::bindings as glb;
 
+/// How a G-buffer stores depth and stencil
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthStencilMode {
+    /// Nothing is depth or stencil tested
+    None,
+    /// A `DEPTH_COMPONENT24` texture that later stages can sample, without stencil
+    DepthTexture,
+    /// A `DEPTH24_STENCIL8` renderbuffer, for depth and stencil that are never sampled
+    DepthStencilRenderbuffer,
+    /// A `DEPTH_COMPONENT32F` texture that later stages can sample, along with a `STENCIL_INDEX8` renderbuffer.
+    ///
+    /// Not every driver supports separate depth and stencil attachments, in which case the framebuffer is incomplete.
+    DepthTextureStencilRenderbuffer,
+}
+
+impl DepthStencilMode {
+    /// Internal format of the depth texture, if there is one
+    pub fn depth_texture_format(self) -> Option<GLenum> {
+        match self {
+            DepthStencilMode::DepthTexture => Some(glb::DEPTH_COMPONENT24),
+            DepthStencilMode::DepthTextureStencilRenderbuffer => Some(glb::DEPTH_COMPONENT32F),
+            _ => None,
+        }
+    }
+
+    /// Internal format of the renderbuffer and what it's attached to, if there is one
+    pub fn renderbuffer_format(self) -> Option<(GLenum, GLenum)> {
+        match self {
+            DepthStencilMode::DepthStencilRenderbuffer => Some((glb::DEPTH24_STENCIL8, glb::DEPTH_STENCIL_ATTACHMENT)),
+            DepthStencilMode::DepthTextureStencilRenderbuffer => Some((glb::STENCIL_INDEX8, glb::STENCIL_ATTACHMENT)),
+            _ => None,
+        }
+    }
+}
+
 pub struct Gbuffer {
     pub dimensions: (usize, usize),
     pub buffers: VecMap<GLTexture>,
-    depth_stencil_buffer: GLRenderbuffer,
+    /// Number of samples per pixel, where anything above 1 means the buffers are multisampled
+    samples: usize,
+    /// Format and internal format of each buffer, since multisampled textures can't be queried for them
+    components: Vec<(GLenum, GLenum)>,
+    depth_stencil: DepthStencilMode,
+    depth_texture: Option<GLTexture>,
+    depth_stencil_buffer: Option<GLRenderbuffer>,
 }
 
 pub const COLOR_ATTACHMENTS: [GLenum; 32] = [
@@ -30,37 +71,129 @@ pub const TEXTURES: [GLenum; 32] = [
     glb::TEXTURE30, glb::TEXTURE31,
 ];
 
+/// `GL_MAX_SAMPLES`, the upper limit for multisampled attachments
+pub fn max_samples() -> GLResult<usize> {
+    Ok(try!(GLCapabilities::current()).max_samples)
+}
+
+/// Allocates storage for a multisampled texture, which can't go through `load_empty`
+fn load_empty_multisample(texture: &GLTexture, samples: usize, width: usize, height: usize, internal_format: GLenum) -> GLResult<()> {
+    try!(texture.bind());
+
+    unsafe {
+        glb::TexImage2DMultisample(glb::TEXTURE_2D_MULTISAMPLE, samples as GLsizei, internal_format,
+                                   width as GLsizei, height as GLsizei, glb::TRUE);
+    }
+
+    check_errors!();
+
+    Ok(())
+}
+
+/// Allocates storage for a depth texture, multisampled or not
+fn load_empty_depth(texture: &mut GLTexture, samples: usize, width: usize, height: usize, internal_format: GLenum) -> GLResult<()> {
+    if samples > 1 {
+        load_empty_multisample(texture, samples, width, height, internal_format)
+    } else {
+        texture.load_empty(width, height, glb::DEPTH_COMPONENT, internal_format)
+    }
+}
+
+/// Allocates renderbuffer storage, multisampled or not
+fn set_renderbuffer_storage(renderbuffer: &mut GLRenderbuffer, samples: usize, width: usize, height: usize, internal_format: GLenum) -> GLResult<()> {
+    if samples > 1 {
+        renderbuffer.set_storage_multisample(samples, internal_format, width, height)
+    } else {
+        renderbuffer.set_storage(internal_format, width, height)
+    }
+}
+
 impl Gbuffer {
+    /// Creates a G-buffer with one texture per component. If `samples` is greater than 1, all attachments are multisampled,
+    /// in which case they can't be sampled directly and have to be resolved into a regular G-buffer first.
+    ///
+    /// `samples` is clamped to `GL_MAX_SAMPLES`.
     pub fn new(width: usize, height: usize, mut framebuffer: &mut GLFramebuffer,
-               components: &[(GLenum, GLenum)]) -> GLResult<Gbuffer> {
+               components: &[(GLenum, GLenum)], samples: usize, depth_stencil: DepthStencilMode) -> GLResult<Gbuffer> {
         try!(framebuffer.bind());
 
+        let samples = if samples > 1 {
+            let max_samples = try!(max_samples());
+
+            if samples > max_samples {
+                warn!("{} samples requested, but only {} are supported", samples, max_samples);
+            }
+
+            samples.min(max_samples)
+        } else { 1 };
+
         let mut buffers = VecMap::with_capacity(components.len());
         let mut attachments = Vec::with_capacity(components.len());
 
         for (i, &(format, internal_format)) in components.iter().enumerate() {
-            let mut buffer: GLTexture = try!(GLTexture::new(GLTextureKind::Texture2D));
+            let attachment = COLOR_ATTACHMENTS[i];
 
-            try!(buffer.load_empty(width, height, format, internal_format));
-            try!(buffer.set_filter(GLTextureFilter::Nearest, None));
+            if samples > 1 {
+                let buffer: GLTexture = try!(GLTexture::new(GLTextureKind::Texture2DMultisample));
 
-            let attachment = COLOR_ATTACHMENTS[i];
+                try!(load_empty_multisample(&buffer, samples, width, height, internal_format));
 
-            unsafe {
-                glb::FramebufferTexture2D(glb::FRAMEBUFFER, attachment, glb::TEXTURE_2D, buffer.raw(), 0);
-            }
+                try!(framebuffer.texture(attachment, &buffer));
 
-            check_errors!();
+                buffers.insert(i, buffer);
+            } else {
+                let mut buffer: GLTexture = try!(GLTexture::new(GLTextureKind::Texture2D));
+
+                try!(buffer.load_empty(width, height, format, internal_format));
+                try!(buffer.set_filter(GLTextureFilter::Nearest, None));
+
+                try!(framebuffer.texture(attachment, &buffer));
+
+                buffers.insert(i, buffer);
+            }
 
-            buffers.insert(i, buffer);
             attachments.push(attachment);
         }
 
-        let mut depth_stencil_buffer = try!(GLRenderbuffer::new());
+        let depth_texture = match depth_stencil.depth_texture_format() {
+            Some(internal_format) => {
+                let kind = if samples > 1 { GLTextureKind::Texture2DMultisample } else { GLTextureKind::Texture2D };
+
+                let mut texture = try!(GLTexture::new(kind));
+
+                try!(load_empty_depth(&mut texture, samples, width, height, internal_format));
 
-        try!(depth_stencil_buffer.set_storage(width, height));
+                if samples == 1 {
+                    try!(texture.set_filter(GLTextureFilter::Nearest, None));
+                }
 
-        try!(framebuffer.renderbuffer(&depth_stencil_buffer));
+                Some(texture)
+            }
+            None => None,
+        };
+
+        let depth_stencil_buffer = match depth_stencil.renderbuffer_format() {
+            Some((internal_format, _)) => {
+                let mut renderbuffer = try!(GLRenderbuffer::new());
+
+                try!(set_renderbuffer_storage(&mut renderbuffer, samples, width, height, internal_format));
+
+                Some(renderbuffer)
+            }
+            None => None,
+        };
+
+        let gbuffer = Gbuffer {
+            dimensions: (width, height),
+            buffers: buffers,
+            samples: samples,
+            components: components.to_vec(),
+            depth_stencil: depth_stencil,
+            depth_texture: depth_texture,
+            depth_stencil_buffer: depth_stencil_buffer,
+        };
+
+        try!(gbuffer.attach_depth_stencil(framebuffer));
 
         unsafe {
             glb::DrawBuffers(attachments.len() as GLsizei, attachments.as_ptr() as *const _);
@@ -69,11 +202,7 @@ impl Gbuffer {
         check_errors!();
 
         if framebuffer.is_complete()? {
-            Ok(Gbuffer {
-                dimensions: (width, height),
-                buffers: buffers,
-                depth_stencil_buffer: depth_stencil_buffer
-            })
+            Ok(gbuffer)
         } else {
             error!("Incomplete framebuffer from Gbuffer creation");
 
@@ -81,12 +210,68 @@ impl Gbuffer {
         }
     }
 
+    #[inline(always)]
+    pub fn samples(&self) -> usize { self.samples }
+
+    #[inline(always)]
+    pub fn depth_stencil_mode(&self) -> DepthStencilMode { self.depth_stencil }
+
+    /// The depth texture, which exists for `DepthStencilMode::DepthTexture` and `DepthTextureStencilRenderbuffer`.
+    ///
+    /// SSAO and similar effects can sample it directly, unless it's multisampled.
+    #[inline(always)]
+    pub fn depth_texture(&self) -> Option<&GLTexture> { self.depth_texture.as_ref() }
+
+    /// The depth-stencil or stencil renderbuffer, depending on the mode
+    #[inline(always)]
+    pub fn depth_stencil_buffer(&self) -> Option<&GLRenderbuffer> { self.depth_stencil_buffer.as_ref() }
+
+    /// Attaches this G-buffer's depth texture and renderbuffer to `framebuffer`, which may belong to another stage
+    pub fn attach_depth_stencil(&self, framebuffer: &mut GLFramebuffer) -> GLResult<()> {
+        if let Some(ref texture) = self.depth_texture {
+            try!(framebuffer.texture(glb::DEPTH_ATTACHMENT, texture));
+        }
+
+        if let (Some(renderbuffer), Some((_, attachment))) = (self.depth_stencil_buffer.as_ref(), self.depth_stencil.renderbuffer_format()) {
+            try!(framebuffer.renderbuffer(attachment, renderbuffer));
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn components(&self) -> &[(GLenum, GLenum)] { &self.components }
+
+    #[inline(always)]
+    pub fn is_multisampled(&self) -> bool { self.samples > 1 }
+
+    /// Format and internal format of the given component, which tell how its contents read back by `Stage::read_pixels` are laid out
+    #[inline]
+    pub fn component_format(&self, component: usize) -> Option<(GLenum, GLenum)> {
+        self.components.get(component).cloned()
+    }
+
+    /// Whether the given component has an unsigned integer format, which has to be cleared and read as integers
+    pub fn is_unsigned_integer(&self, component: usize) -> bool {
+        match self.components.get(component) {
+            Some(&(_, glb::R8UI)) | Some(&(_, glb::R16UI)) | Some(&(_, glb::R32UI)) |
+            Some(&(_, glb::RG8UI)) | Some(&(_, glb::RG16UI)) | Some(&(_, glb::RG32UI)) |
+            Some(&(_, glb::RGBA8UI)) | Some(&(_, glb::RGBA16UI)) | Some(&(_, glb::RGBA32UI)) => true,
+            _ => false,
+        }
+    }
+
     #[inline]
     pub fn component(&self, component: usize) -> Option<&GLTexture> {
         self.buffers.get(component)
     }
 
+    /// Multisampled buffers can't be filtered, so this does nothing for them
     pub fn set_filter(&mut self, filter: GLTextureFilter) -> GLResult<()> {
+        if self.is_multisampled() {
+            return Ok(());
+        }
+
         for (_, mut texture) in self.buffers.iter_mut() {
             try!(texture.set_filter(filter, None));
         }
@@ -94,41 +279,73 @@ impl Gbuffer {
         Ok(())
     }
 
+    /// Multisampled buffers can't be wrapped, so this does nothing for them
     pub fn set_wrap(&mut self, wrap: GLTextureWrap) -> GLResult<()> {
+        if self.is_multisampled() {
+            return Ok(());
+        }
+
         for(_, mut texture) in self.buffers.iter_mut() {
-            try!(texture.set_wrap(wrap));
+            try!(texture.set_wrap_axes(wrap, wrap, wrap));
         }
 
         Ok(())
     }
 
     pub fn bind_textures(&self, shader: &GLShaderProgram, names: &[&str]) -> GLResult<()> {
-        for ((i, texture), name) in self.buffers.iter().zip(names.iter()) {
-            let mut loc = try!(shader.get_uniform(name));
+        try!(self.bind_textures_at(shader, names, 0));
+
+        Ok(())
+    }
 
-            try!(loc.int1(i as GLint));
+    /// Same as `bind_textures`, but starts at the given texture unit so multiple G-buffers can be bound at once.
+    ///
+    /// Returns the number of texture units used.
+    pub fn bind_textures_at(&self, shader: &GLShaderProgram, names: &[&str], first_unit: usize) -> GLResult<usize> {
+        let mut used = 0;
 
-            unsafe {
-                glb::ActiveTexture(TEXTURES[i]);
-            }
+        for ((_, texture), name) in self.buffers.iter().zip(names.iter()) {
+            let unit = first_unit + used;
+
+            let mut loc = try!(shader.get_uniform_optional(name));
+
+            try!(loc.int1(unit as GLint));
+
+            GLStateCache::active_texture(unit);
 
             check_errors!();
 
             try!(texture.bind());
+
+            used += 1;
         }
 
-        Ok(())
+        Ok(used)
     }
 
     pub fn resize(&mut self, width: usize, height: usize) -> GLResult<()> {
-        for (_, mut buffer) in self.buffers.iter_mut() {
-            let format = buffer.format().unwrap();
-            let internal_format = buffer.internal_format().unwrap();
+        if self.is_multisampled() {
+            for (i, buffer) in self.buffers.iter() {
+                try!(load_empty_multisample(buffer, self.samples, width, height, self.components[i].1));
+            }
+
+        } else {
+            for (_, mut buffer) in self.buffers.iter_mut() {
+                let format = buffer.format().unwrap();
+                let internal_format = buffer.internal_format().unwrap();
 
-            try!(buffer.load_empty(width, height, format, internal_format));
+                try!(buffer.load_empty(width, height, format, internal_format));
+            }
         }
 
-        try!(self.depth_stencil_buffer.set_storage(width, height));
+        // Reallocated in place, so stages sharing them keep them attached
+        if let (Some(texture), Some(internal_format)) = (self.depth_texture.as_mut(), self.depth_stencil.depth_texture_format()) {
+            try!(load_empty_depth(texture, self.samples, width, height, internal_format));
+        }
+
+        if let (Some(renderbuffer), Some((internal_format, _))) = (self.depth_stencil_buffer.as_mut(), self.depth_stencil.renderbuffer_format()) {
+            try!(set_renderbuffer_storage(renderbuffer, self.samples, width, height, internal_format));
+        }
 
         self.dimensions = (width, height);
 
@@ -0,0 +1,124 @@
+use nalgebra::Matrix4;
+
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+use ::backend::gl::bindings as glb;
+
+use super::instancing::{self, InstanceBuffer};
+
+/// Where a mesh's indices and vertices start within a vertex array shared with other meshes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshRange {
+    pub first_index: u32,
+    /// Number of indices
+    pub count: u32,
+    /// Added to every index of the mesh, for meshes whose indices start at zero
+    pub base_vertex: i32,
+}
+
+struct BatchedDraw {
+    material: usize,
+    range: MeshRange,
+    transform: Matrix4<f32>,
+    inverse: Matrix4<f32>,
+    object_id: u32,
+}
+
+/// Collects draws of meshes sharing one vertex array over a frame, then submits them with one multi-draw per material.
+///
+/// Every draw is a single instance reading its transforms and object ID from an `InstanceBuffer`,
+/// so the geometry shader's `instanced` uniform must be set. Without multi-draw indirect the draws
+/// are made one at a time instead, with the same result.
+pub struct IndirectBatch {
+    draws: Vec<BatchedDraw>,
+    commands: Vec<GLDrawCommand>,
+    indirect: GLDrawIndirectBuffer,
+    instances: InstanceBuffer,
+}
+
+impl IndirectBatch {
+    pub fn new() -> GLResult<IndirectBatch> {
+        Ok(IndirectBatch {
+            draws: Vec::new(),
+            commands: Vec::new(),
+            indirect: try!(GLDrawIndirectBuffer::new()),
+            instances: try!(InstanceBuffer::new()),
+        })
+    }
+
+    /// Number of draws waiting for `submit`
+    #[inline]
+    pub fn len(&self) -> usize { self.draws.len() }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.draws.is_empty() }
+
+    /// Queues a draw of `range` with the material identified by `material`, such as the address of its `BoundMaterial`
+    pub fn push(&mut self, material: usize, range: MeshRange, transform: Matrix4<f32>, inverse: Matrix4<f32>, object_id: u32) {
+        self.draws.push(BatchedDraw {
+            material: material,
+            range: range,
+            transform: transform,
+            inverse: inverse,
+            object_id: object_id,
+        });
+    }
+
+    /// Draws and clears everything queued with `push`, using the index buffer of `vertex_array`.
+    ///
+    /// `bind_material` is called once before the draws of each material. Returns the number of materials drawn.
+    pub fn submit<F>(&mut self, vertex_array: &GLVertexArray, mut bind_material: F) -> GLResult<usize> where F: FnMut(usize) -> GLResult<()> {
+        if self.draws.is_empty() {
+            return Ok(0);
+        }
+
+        let batches = instancing::batches(&mut self.draws, |draw| draw.material);
+
+        // Uploaded in sorted order, so the base instance of each draw is its own index
+        try!(self.instances.upload(self.draws.iter().map(|draw| (draw.transform, draw.inverse, draw.object_id))));
+
+        self.commands.clear();
+
+        for (index, draw) in self.draws.iter().enumerate() {
+            let range = draw.range;
+
+            self.commands.push(GLDrawCommand::new(range.count, range.first_index, range.base_vertex).with_instances(1, index as u32));
+        }
+
+        try!(self.indirect.update(&self.commands));
+
+        try!(vertex_array.bind());
+
+        let multi_draw = GLDrawIndirectBuffer::is_supported();
+
+        if multi_draw {
+            try!(self.instances.bind_attributes());
+        }
+
+        for batch in &batches {
+            try!(bind_material(self.draws[batch.start].material));
+
+            if multi_draw {
+                try!(self.indirect.draw_range(glb::TRIANGLES, glb::UNSIGNED_INT, batch.clone()));
+
+                continue;
+            }
+
+            for (index, command) in self.commands[batch.clone()].iter().enumerate() {
+                // Without base instances, the attributes are pointed at the draw's own transforms instead
+                try!(self.instances.bind_attributes_from(batch.start + index));
+
+                unsafe {
+                    glb::DrawElementsBaseVertex(glb::TRIANGLES, command.count as GLsizei, glb::UNSIGNED_INT,
+                                                (command.first_index as usize * 4) as *const _, command.base_vertex);
+                }
+
+                check_errors!();
+            }
+        }
+
+        self.draws.clear();
+
+        Ok(batches.len())
+    }
+}
@@ -0,0 +1,198 @@
+use std::mem;
+use std::ops::Range;
+
+use nalgebra::Matrix4;
+
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+use ::backend::gl::bindings as glb;
+
+use components::gpu_buffer::Buffer;
+
+use super::indirect::MeshRange;
+
+/// First of the four attribute locations holding the per-instance model matrix, one column each
+pub const INSTANCE_MODEL_LOCATION: GLuint = 5;
+
+/// First of the four attribute locations holding the per-instance inverse-transpose model matrix, one column each
+pub const INSTANCE_MIT_LOCATION: GLuint = 9;
+
+/// Attribute location of the per-instance object ID, read as an unsigned integer
+pub const INSTANCE_OBJECT_ID_LOCATION: GLuint = 13;
+
+/// Floats per instance, for the model and inverse-transpose model matrices, followed by the bits of the object ID
+const INSTANCE_FLOATS: usize = 33;
+
+/// Controls automatic batching of identical meshes into instanced draws
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InstancingSettings {
+    pub enabled: bool,
+    /// Minimum number of identical meshes before they're drawn instanced rather than one at a time
+    pub threshold: usize,
+}
+
+impl Default for InstancingSettings {
+    fn default() -> InstancingSettings {
+        InstancingSettings {
+            enabled: true,
+            threshold: 4,
+        }
+    }
+}
+
+impl InstancingSettings {
+    #[inline]
+    pub fn should_instance(&self, count: usize) -> bool {
+        self.enabled && count >= self.threshold.max(1)
+    }
+}
+
+/// Appends the matrix in column-major order, optionally transposed
+fn push_matrix(data: &mut Vec<f32>, m: &Matrix4<f32>, transpose: bool) {
+    let columns = [
+        [m.m11, m.m21, m.m31, m.m41],
+        [m.m12, m.m22, m.m32, m.m42],
+        [m.m13, m.m23, m.m33, m.m43],
+        [m.m14, m.m24, m.m34, m.m44],
+    ];
+
+    for i in 0..4 {
+        for j in 0..4 {
+            data.push(if transpose { columns[j][i] } else { columns[i][j] });
+        }
+    }
+}
+
+/// Per-instance transforms, re-uploaded for every instanced draw
+pub struct InstanceBuffer {
+    buffer: GLBuffer,
+    data: Vec<f32>,
+    count: usize,
+}
+
+impl InstanceBuffer {
+    pub fn new() -> GLResult<InstanceBuffer> {
+        Ok(InstanceBuffer {
+            buffer: try!(GLBuffer::new(GLBufferTarget::ArrayBuffer)),
+            data: Vec::new(),
+            count: 0,
+        })
+    }
+
+    #[inline(always)]
+    pub fn buffer(&self) -> &GLBuffer { &self.buffer }
+
+    /// Number of instances in the last upload
+    #[inline(always)]
+    pub fn count(&self) -> usize { self.count }
+
+    /// Uploads the model matrix, inverse model matrix and object ID of every instance.
+    ///
+    /// The inverse is transposed here, like the `mit` uniform of the geometry shader.
+    pub fn upload<I>(&mut self, instances: I) -> GLResult<()> where I: IntoIterator<Item = (Matrix4<f32>, Matrix4<f32>, u32)> {
+        self.data.clear();
+
+        for (model, inverse, object_id) in instances {
+            push_matrix(&mut self.data, &model, false);
+            push_matrix(&mut self.data, &inverse, true);
+
+            // Stored as raw bits in the float data, and read back as an integer attribute
+            self.data.push(unsafe { mem::transmute::<u32, f32>(object_id) });
+        }
+
+        self.count = self.data.len() / INSTANCE_FLOATS;
+
+        try!(self.buffer.bind());
+
+        // Reallocating the storage every time lets the driver orphan the old one instead of synchronizing
+        try!(self.buffer.buffer_slice(&self.data, GLBufferUsage::StreamDraw));
+
+        Ok(())
+    }
+
+    /// Points the instance attributes of the currently bound vertex array at this buffer, advancing once per instance
+    pub fn bind_attributes(&self) -> GLResult<()> {
+        self.bind_attributes_from(0)
+    }
+
+    /// Same as `bind_attributes`, but the first instance drawn reads the transforms of instance `first_instance`.
+    ///
+    /// This stands in for the base instance of a draw where the driver doesn't support base instances.
+    pub fn bind_attributes_from(&self, first_instance: usize) -> GLResult<()> {
+        try!(self.buffer.bind());
+
+        let stride = INSTANCE_FLOATS * mem::size_of::<f32>();
+        let start = first_instance * stride;
+
+        for (matrix, first) in [INSTANCE_MODEL_LOCATION, INSTANCE_MIT_LOCATION].iter().enumerate() {
+            for column in 0..4 {
+                let offset = start + (matrix * 16 + column * 4) * mem::size_of::<f32>();
+
+                try!(GLVertexAttribute::new(first + column as GLuint, 4, glb::FLOAT, false, stride, offset).instanced(1).apply());
+            }
+        }
+
+        let offset = start + 32 * mem::size_of::<f32>();
+
+        try!(GLVertexAttribute::integer(INSTANCE_OBJECT_ID_LOCATION, 1, glb::UNSIGNED_INT, stride, offset).instanced(1).apply());
+
+        Ok(())
+    }
+}
+
+/// Indices of the mesh covered by `range`, or all of them if it's `None`
+pub fn mesh_range(mesh: &Buffer, range: Option<MeshRange>) -> MeshRange {
+    range.unwrap_or(MeshRange { first_index: 0, count: mesh.num_indices() as u32, base_vertex: 0 })
+}
+
+/// Draws a single instance of `range` of the mesh, which must already be bound
+pub fn draw_range(mesh: &Buffer, range: Option<MeshRange>) -> GLResult<()> {
+    let range = mesh_range(mesh, range);
+
+    unsafe {
+        glb::DrawElementsBaseVertex(glb::TRIANGLES, range.count as GLsizei, glb::UNSIGNED_INT,
+                                    (range.first_index as usize * mem::size_of::<u32>()) as *const _, range.base_vertex);
+    }
+
+    check_errors!();
+
+    Ok(())
+}
+
+/// Draws `count` instances of `range` of the mesh, reading each instance's transforms from `instances`.
+///
+/// The shader's `instanced` uniform must be set, and the `Camera` uniform block bound, which provides `view_projection` in place of `mvp`.
+pub fn draw_instanced(mesh: &Buffer, range: Option<MeshRange>, instances: &InstanceBuffer, count: usize) -> GLResult<()> {
+    try!(mesh.bind());
+
+    try!(instances.bind_attributes());
+
+    let range = mesh_range(mesh, range);
+
+    unsafe {
+        glb::DrawElementsInstancedBaseVertex(glb::TRIANGLES, range.count as GLsizei, glb::UNSIGNED_INT,
+                                             (range.first_index as usize * mem::size_of::<u32>()) as *const _,
+                                             count as GLsizei, range.base_vertex);
+    }
+
+    check_errors!();
+
+    Ok(())
+}
+
+/// Sorts the items by key and returns the ranges of items sharing the same key, so they can be drawn together
+pub fn batches<T, K, F>(items: &mut [T], mut key: F) -> Vec<Range<usize>> where K: Ord, F: FnMut(&T) -> K {
+    items.sort_by_key(|item| key(item));
+
+    let mut ranges = Vec::new();
+    let mut start = 0;
+
+    for i in 1..items.len() + 1 {
+        if i == items.len() || key(&items[i]) != key(&items[start]) {
+            ranges.push(start..i);
+            start = i;
+        }
+    }
+
+    ranges
+}
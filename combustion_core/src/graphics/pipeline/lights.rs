@@ -0,0 +1,18 @@
+//! Light data as uploaded to the lighting and forward shaders
+//!
+//! See `ecs::lights`, which gathering lights from the scene needs without depending on the renderer.
+
+pub use ecs::lights::*;
+
+use ::backend::gl::*;
+
+/// Writes a light as the `Light` struct in `shaders/lib/lights.glsl`
+pub fn write_std140(light: &Light, writer: &mut Std140Writer) {
+    writer.int(light.kind as i32)
+          .vec3([light.position.x, light.position.y, light.position.z])
+          .vec3([light.direction.x, light.direction.y, light.direction.z])
+          .vec3([light.color.x, light.color.y, light.color.z])
+          .float(light.intensity)
+          .float(light.radius)
+          .vec2([light.cone.0.cos(), light.cone.1.cos()]);
+}
@@ -0,0 +1,317 @@
+use std::cell::Cell;
+use std::mem;
+use std::sync::Arc;
+
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+use ::backend::gl::bindings as glb;
+
+use ::ecs::lod_settings::DebugMaterial;
+use ::ecs::material_overrides::Overridable;
+use ::ecs::components::material_override::Value;
+
+use super::samplers::SamplerHandle;
+
+/// Texture unit for the albedo texture, bound to the `color` sampler of the geometry shader
+pub const COLOR_UNIT: usize = 0;
+
+/// Texture unit for the tangent-space normal map, bound to the `normal_map` sampler of the geometry shader
+pub const NORMAL_MAP_UNIT: usize = 1;
+
+/// Texture unit for the roughness map, bound to the `roughness_map` sampler of the geometry shader
+pub const ROUGHNESS_MAP_UNIT: usize = 2;
+
+/// Textures of a single material, to be bound for each object drawn in the geometry pass
+#[derive(Clone, Copy, Default)]
+pub struct MaterialTextures<'a> {
+    pub color: Option<&'a GLTexture>,
+    /// Normal mapping requires the mesh to have tangents and bitangents
+    pub normal: Option<&'a GLTexture>,
+}
+
+impl<'a> MaterialTextures<'a> {
+    #[inline]
+    pub fn new(color: &'a GLTexture) -> MaterialTextures<'a> {
+        MaterialTextures { color: Some(color), normal: None }
+    }
+
+    #[inline]
+    pub fn with_normal_map(mut self, normal: &'a GLTexture) -> MaterialTextures<'a> {
+        self.normal = Some(normal);
+        self
+    }
+}
+
+fn bind_at(texture: &GLTexture, unit: usize) -> GLResult<()> {
+    GLStateCache::active_texture(unit);
+
+    check_errors!();
+
+    texture.bind()
+}
+
+/// Material texture units, which have to be cleared of samplers with `unbind_sampler_objects` once the geometry pass is done,
+/// so later passes sampling the same units get their textures' own filtering
+pub const MATERIAL_UNITS: [usize; 3] = [COLOR_UNIT, NORMAL_MAP_UNIT, ROUGHNESS_MAP_UNIT];
+
+/// Removes any sampler objects bound to the material texture units by `BoundMaterial::bind`
+pub fn unbind_sampler_objects() -> GLResult<()> {
+    for unit in MATERIAL_UNITS.iter() {
+        try!(GLSampler::unbind(*unit));
+    }
+
+    Ok(())
+}
+
+/// Binds the samplers of the geometry shader to their texture units. Done once per geometry pass.
+pub fn bind_samplers(shader: &GLShaderProgram) -> GLResult<()> {
+    try!(shader.get_uniform_optional("color")?.int1(COLOR_UNIT as GLint));
+    try!(shader.get_uniform_optional("normal_map")?.int1(NORMAL_MAP_UNIT as GLint));
+    try!(shader.get_uniform_optional("roughness_map")?.int1(ROUGHNESS_MAP_UNIT as GLint));
+
+    Ok(())
+}
+
+/// Binds the textures of a material for the next draw call in the geometry pass closure.
+///
+/// Normal mapping is enabled or disabled depending on whether the material has a normal map,
+/// since uniforms persist between draws.
+pub fn bind_material(shader: &GLShaderProgram, material: &MaterialTextures) -> GLResult<()> {
+    if let Some(color) = material.color {
+        try!(bind_at(color, COLOR_UNIT));
+    }
+
+    if let Some(normal) = material.normal {
+        try!(bind_at(normal, NORMAL_MAP_UNIT));
+    }
+
+    try!(shader.get_uniform_optional("normal_mapping")?.bool1(material.normal.is_some()));
+
+    Ok(())
+}
+
+/// Shared handle to a texture used by any number of materials
+pub type TextureHandle = Arc<GLTexture>;
+
+/// Shared handle to a material, which render items refer to
+pub type MaterialHandle = Arc<BoundMaterial>;
+
+/// Textures and scalar factors making up a material in the geometry pass.
+///
+/// Each factor is multiplied with its texture if there is one, or used on its own if not.
+#[derive(Clone)]
+pub struct MaterialDefinition {
+    pub diffuse: Option<TextureHandle>,
+    /// Normal mapping requires the mesh to have tangents and bitangents
+    pub normal: Option<TextureHandle>,
+    /// Single channel roughness texture, read from the red channel
+    pub roughness: Option<TextureHandle>,
+    pub diffuse_factor: [f32; 3],
+    pub roughness_factor: f32,
+    pub metallic_factor: f32,
+    /// Sampler used for every texture of the material, usually one from the pipeline's `SamplerSet`.
+    /// Without one, the textures are sampled with their own filtering and wrapping.
+    pub sampler: Option<SamplerHandle>,
+}
+
+impl Default for MaterialDefinition {
+    fn default() -> MaterialDefinition {
+        MaterialDefinition {
+            diffuse: None,
+            normal: None,
+            roughness: None,
+            diffuse_factor: [1.0, 1.0, 1.0],
+            roughness_factor: 0.2,
+            metallic_factor: 0.0,
+            sampler: None,
+        }
+    }
+}
+
+/// Uniform locations of the material inputs in a single shader program
+#[derive(Debug, Clone, Copy)]
+struct MaterialLocations {
+    program: GLuint,
+    color: GLint,
+    normal_map: GLint,
+    roughness_map: GLint,
+    diffuse_mapping: GLint,
+    normal_mapping: GLint,
+    roughness_mapping: GLint,
+    diffuse_factor: GLint,
+    roughness_factor: GLint,
+    metallic_factor: GLint,
+}
+
+impl MaterialLocations {
+    fn new(shader: &GLShaderProgram) -> GLResult<MaterialLocations> {
+        Ok(MaterialLocations {
+            program: shader.raw(),
+            color: try!(shader.get_uniform_optional("color")).0,
+            normal_map: try!(shader.get_uniform_optional("normal_map")).0,
+            roughness_map: try!(shader.get_uniform_optional("roughness_map")).0,
+            diffuse_mapping: try!(shader.get_uniform_optional("diffuse_mapping")).0,
+            normal_mapping: try!(shader.get_uniform_optional("normal_mapping")).0,
+            roughness_mapping: try!(shader.get_uniform_optional("roughness_mapping")).0,
+            diffuse_factor: try!(shader.get_uniform_optional("diffuse_factor")).0,
+            roughness_factor: try!(shader.get_uniform_optional("roughness_factor")).0,
+            metallic_factor: try!(shader.get_uniform_optional("metallic_factor")).0,
+        })
+    }
+}
+
+/// A material ready to be bound for draws in the geometry pass.
+///
+/// Uniform locations are looked up the first time the material is bound to a program,
+/// and looked up again only if it is bound to a different one.
+pub struct BoundMaterial {
+    definition: MaterialDefinition,
+    locations: Cell<Option<MaterialLocations>>,
+}
+
+unsafe impl Send for BoundMaterial {}
+
+unsafe impl Sync for BoundMaterial {}
+
+impl DebugMaterial for BoundMaterial {
+    fn flat(color: [f32; 3]) -> BoundMaterial {
+        BoundMaterial::new(MaterialDefinition { diffuse_factor: color, ..MaterialDefinition::default() })
+    }
+}
+
+/// Merged state of a material, with textures and samplers compared by address and factors by their bits
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MaterialKey {
+    textures: [usize; 3],
+    sampler: usize,
+    factors: [u32; 5],
+}
+
+#[inline]
+fn bits(value: f32) -> u32 {
+    unsafe { mem::transmute::<f32, u32>(value) }
+}
+
+impl MaterialKey {
+    pub fn new(definition: &MaterialDefinition) -> MaterialKey {
+        let address = |texture: &Option<TextureHandle>| texture.as_ref().map_or(0, |texture| &**texture as *const _ as usize);
+
+        let d = definition;
+
+        MaterialKey {
+            textures: [address(&d.diffuse), address(&d.normal), address(&d.roughness)],
+            sampler: d.sampler.as_ref().map_or(0, |sampler| &**sampler as *const _ as usize),
+            factors: [
+                bits(d.diffuse_factor[0]),
+                bits(d.diffuse_factor[1]),
+                bits(d.diffuse_factor[2]),
+                bits(d.roughness_factor),
+                bits(d.metallic_factor),
+            ],
+        }
+    }
+}
+
+/// Parameters are named after the fields of `MaterialDefinition`:
+///
+/// - `diffuse_factor`, a color
+/// - `roughness_factor` and `metallic_factor`, scalars
+/// - `diffuse`, `normal` and `roughness`, textures
+impl Overridable for BoundMaterial {
+    type Definition = MaterialDefinition;
+    type Key = MaterialKey;
+    type Texture = GLTexture;
+
+    #[inline(always)]
+    fn definition(&self) -> &MaterialDefinition { &self.definition }
+
+    #[inline]
+    fn from_definition(definition: MaterialDefinition) -> BoundMaterial {
+        BoundMaterial::new(definition)
+    }
+
+    #[inline]
+    fn key(definition: &MaterialDefinition) -> MaterialKey {
+        MaterialKey::new(definition)
+    }
+
+    fn apply(definition: &mut MaterialDefinition, name: &str, value: &Value<GLTexture>) -> bool {
+        match (name, value) {
+            ("diffuse_factor", &Value::Color(color)) => definition.diffuse_factor = color,
+            ("roughness_factor", &Value::Scalar(value)) => definition.roughness_factor = value,
+            ("metallic_factor", &Value::Scalar(value)) => definition.metallic_factor = value,
+            ("diffuse", &Value::Texture(ref handle)) => if let Some(texture) = handle.get() {
+                definition.diffuse = Some(texture);
+            },
+            ("normal", &Value::Texture(ref handle)) => if let Some(texture) = handle.get() {
+                definition.normal = Some(texture);
+            },
+            ("roughness", &Value::Texture(ref handle)) => if let Some(texture) = handle.get() {
+                definition.roughness = Some(texture);
+            },
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+impl BoundMaterial {
+    pub fn new(definition: MaterialDefinition) -> BoundMaterial {
+        BoundMaterial { definition: definition, locations: Cell::new(None) }
+    }
+
+    #[inline(always)]
+    pub fn definition(&self) -> &MaterialDefinition { &self.definition }
+
+    fn locations(&self, shader: &GLShaderProgram) -> GLResult<MaterialLocations> {
+        match self.locations.get() {
+            Some(locations) if locations.program == shader.raw() => Ok(locations),
+            _ => {
+                let locations = try!(MaterialLocations::new(shader));
+
+                self.locations.set(Some(locations));
+
+                Ok(locations)
+            }
+        }
+    }
+
+    /// Binds the material's textures to their units and uploads its factors to the given program, which must be in use.
+    ///
+    /// Every input is set, including disabling the maps the material doesn't have, since uniforms persist between draws.
+    /// The material's sampler is bound to each of its texture units as well.
+    pub fn bind(&self, shader: &GLShaderProgram) -> GLResult<()> {
+        let locations = try!(self.locations(shader));
+        let definition = &self.definition;
+
+        let maps = [
+            (&definition.diffuse, COLOR_UNIT, locations.color, locations.diffuse_mapping),
+            (&definition.normal, NORMAL_MAP_UNIT, locations.normal_map, locations.normal_mapping),
+            (&definition.roughness, ROUGHNESS_MAP_UNIT, locations.roughness_map, locations.roughness_mapping),
+        ];
+
+        for &(texture, unit, location, enabled) in maps.iter() {
+            if let Some(ref texture) = *texture {
+                try!(bind_at(texture, unit));
+                try!(GLUniform(location).int1(unit as GLint));
+            }
+
+            // Materials without a sampler of their own mustn't inherit the previous material's
+            match definition.sampler {
+                Some(ref sampler) => try!(sampler.bind(unit)),
+                None => try!(GLSampler::unbind(unit)),
+            }
+
+            try!(GLUniform(enabled).int1(texture.is_some() as GLint));
+        }
+
+        let diffuse = definition.diffuse_factor;
+
+        try!(GLUniform(locations.diffuse_factor).float3(diffuse[0], diffuse[1], diffuse[2]));
+        try!(GLUniform(locations.roughness_factor).float1(definition.roughness_factor));
+        try!(GLUniform(locations.metallic_factor).float1(definition.metallic_factor));
+
+        Ok(())
+    }
+}
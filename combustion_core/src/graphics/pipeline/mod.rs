@@ -1,8 +1,43 @@
 pub mod gbuffer;
 pub mod stage;
 pub mod pipeline;
+pub mod builder;
+pub mod lights;
+pub mod shadow;
+pub mod ssao;
+pub mod bloom;
+pub mod tonemap;
+pub mod debug;
+pub mod skybox;
+pub mod timing;
+pub mod volume;
+pub mod material;
+pub mod samplers;
+pub mod instancing;
+pub mod indirect;
+pub mod blocks;
 pub mod screen;
+pub mod pingpong;
+pub mod overlay;
+pub mod reload;
+pub mod billboard;
 
-pub use self::gbuffer::Gbuffer;
-pub use self::stage::Stage;
-pub use self::pipeline::Pipeline;
\ No newline at end of file
+pub use self::gbuffer::{Gbuffer, DepthStencilMode};
+pub use self::stage::{Stage, ClearValues, BlitTarget, BlitRect};
+pub use self::pingpong::PingPongStages;
+pub use self::pipeline::{Pipeline, NamedStage, StageInput};
+pub use self::builder::PipelineBuilder;
+pub use self::lights::{Light, LightKind};
+pub use self::shadow::ShadowStage;
+pub use self::ssao::SsaoSettings;
+pub use self::bloom::BloomSettings;
+pub use self::tonemap::{TonemapSettings, Tonemapper, Exposure};
+pub use self::debug::{DebugView, RasterMode};
+pub use self::timing::StageTimings;
+pub use self::material::{MaterialTextures, MaterialDefinition, BoundMaterial};
+pub use self::samplers::SamplerSet;
+pub use self::instancing::InstancingSettings;
+pub use self::indirect::{IndirectBatch, MeshRange};
+pub use self::overlay::TextureOverlay;
+pub use self::reload::{ProgramSource, ReloadSummary};
+pub use self::billboard::BillboardRenderer;
\ No newline at end of file
@@ -0,0 +1,108 @@
+//! A texture drawn over the corner of the finished frame, such as one dropped onto the window
+
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+use ::backend::gl::bindings as glb;
+
+use super::screen::ScreenQuad;
+
+/// Largest fraction of the window's height or width the overlay covers
+pub const OVERLAY_MAX_FRACTION: f32 = 0.35;
+
+/// Gap between the overlay and the window edges, in pixels
+pub const OVERLAY_MARGIN: f32 = 16.0;
+
+const OVERLAY_UNIT: usize = 0;
+
+fn load_overlay_shader() -> GLResult<GLShaderProgram> {
+    let vertex_shader = try!(GLShader::from_file("shaders/screen.vert", GLShaderVariant::VertexShader));
+    let fragment_shader = try!(GLShader::from_file("shaders/overlay.frag", GLShaderVariant::FragmentShader));
+
+    Ok(GLShaderProgramBuilder::new()?
+        .attach_shader(vertex_shader)?
+        .attach_shader(fragment_shader)?
+        .link()?
+        .finish())
+}
+
+pub struct TextureOverlay {
+    shader: GLShaderProgram,
+    screen: ScreenQuad,
+    texture: Option<(GLTexture, (u32, u32))>,
+}
+
+impl TextureOverlay {
+    pub fn new() -> GLResult<TextureOverlay> {
+        Ok(TextureOverlay {
+            shader: try!(load_overlay_shader()),
+            screen: try!(ScreenQuad::new()),
+            texture: None,
+        })
+    }
+
+    /// Shows a texture of the given size in pixels, replacing any previous one
+    pub fn set_texture(&mut self, texture: GLTexture, size: (u32, u32)) {
+        self.texture = Some((texture, size));
+    }
+
+    pub fn clear(&mut self) -> Option<GLTexture> {
+        self.texture.take().map(|(texture, _)| texture)
+    }
+
+    #[inline]
+    pub fn is_visible(&self) -> bool { self.texture.is_some() }
+
+    /// Draws the texture into the top right corner of the window, keeping its aspect ratio
+    pub fn draw(&self, window_width: f32, window_height: f32) -> GLResult<()> {
+        let (texture, (width, height)) = match self.texture {
+            Some((ref texture, size)) => (texture, size),
+            None => return Ok(()),
+        };
+
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        let scale = (window_width * OVERLAY_MAX_FRACTION / width as f32)
+            .min(window_height * OVERLAY_MAX_FRACTION / height as f32)
+            .min(1.0);
+
+        let (overlay_width, overlay_height) = (width as f32 * scale, height as f32 * scale);
+
+        gl_debug_group!("Texture overlay");
+
+        GLStateCache::bind_framebuffer(glb::FRAMEBUFFER, 0);
+
+        unsafe {
+            glb::Viewport((window_width - overlay_width - OVERLAY_MARGIN) as GLint,
+                          (window_height - overlay_height - OVERLAY_MARGIN) as GLint,
+                          overlay_width as GLsizei, overlay_height as GLsizei);
+        }
+
+        GLStateCache::disable(glb::DEPTH_TEST);
+        GLStateCache::disable(glb::STENCIL_TEST);
+        GLStateCache::disable(glb::CULL_FACE);
+        GLStateCache::disable(glb::BLEND);
+
+        check_errors!();
+
+        try!(self.shader.use_program());
+        try!(self.shader.get_uniform_optional("overlay")?.int1(OVERLAY_UNIT as GLint));
+
+        GLStateCache::active_texture(OVERLAY_UNIT);
+
+        check_errors!();
+
+        try!(texture.bind());
+        try!(self.screen.overlay());
+
+        //Leave the viewport covering the window, like the final pass does
+        unsafe {
+            glb::Viewport(0, 0, window_width as GLsizei, window_height as GLsizei);
+        }
+
+        check_errors!();
+
+        Ok(())
+    }
+}
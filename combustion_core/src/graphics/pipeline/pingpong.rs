@@ -0,0 +1,95 @@
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+use ::backend::gl::bindings as glb;
+
+use super::stage::Stage;
+use super::gbuffer::DepthStencilMode;
+
+/// Pair of identical stages for iterative effects, where each iteration reads the result of the last one
+/// and writes into the other stage.
+pub struct PingPongStages {
+    stages: [Stage; 2],
+    /// Whether the second stage is currently the source
+    flipped: bool,
+}
+
+impl PingPongStages {
+    pub fn new(width: usize, height: usize, components: &[(GLenum, GLenum)]) -> GLResult<PingPongStages> {
+        let mut stages = [
+            try!(Stage::new(width, height, Some(components), 1, DepthStencilMode::None)),
+            try!(Stage::new(width, height, Some(components), 1, DepthStencilMode::None)),
+        ];
+
+        for stage in stages.iter_mut() {
+            try!(stage.set_filter(GLTextureFilter::Linear));
+            try!(stage.set_wrap(GLTextureWrap::ClampToEdge));
+        }
+
+        Ok(PingPongStages { stages: stages, flipped: false })
+    }
+
+    /// The stage holding the result of the last iteration
+    #[inline]
+    pub fn source(&self) -> &Stage { &self.stages[self.flipped as usize] }
+
+    /// The stage the next iteration renders into
+    #[inline]
+    pub fn target(&self) -> &Stage { &self.stages[!self.flipped as usize] }
+
+    /// Makes the target the new source, after rendering into it
+    #[inline]
+    pub fn swap(&mut self) { self.flipped = !self.flipped; }
+
+    /// Width and height of both stages
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.stages[0].gbuffer().map_or((0, 0), |gbuffer| gbuffer.dimensions)
+    }
+
+    /// Runs `f` `n` times with the source, target and iteration index, swapping after each iteration.
+    ///
+    /// The target is bound and the viewport set to its size before every call, so `f` only has to bind the source
+    /// and draw. Afterwards `source` holds the final result. The viewport is left as is.
+    pub fn iterate<F>(&mut self, n: usize, mut f: F) -> GLResult<()> where F: FnMut(&Stage, &Stage, usize) -> GLResult<()> {
+        let (width, height) = self.dimensions();
+
+        for i in 0..n {
+            try!(self.target().bind());
+
+            unsafe {
+                glb::Viewport(0, 0, width as GLsizei, height as GLsizei);
+            }
+
+            check_errors!();
+
+            try!(f(self.source(), self.target(), i));
+
+            self.swap();
+        }
+
+        Ok(())
+    }
+
+    pub fn set_filter(&mut self, filter: GLTextureFilter) -> GLResult<()> {
+        for stage in self.stages.iter_mut() {
+            try!(stage.set_filter(filter));
+        }
+
+        Ok(())
+    }
+
+    pub fn set_wrap(&mut self, wrap: GLTextureWrap) -> GLResult<()> {
+        for stage in self.stages.iter_mut() {
+            try!(stage.set_wrap(wrap));
+        }
+
+        Ok(())
+    }
+
+    pub fn resize(&mut self, width: usize, height: usize) -> GLResult<()> {
+        for stage in self.stages.iter_mut() {
+            try!(stage.resize(width, height));
+        }
+
+        Ok(())
+    }
+}
@@ -1,19 +1,47 @@
-use nalgebra::Vector2;
+use nalgebra::{Vector2, Point3, Matrix4, Inverse, Norm};
 
 use ::backend::gl::*;
 use ::backend::gl::types::*;
 use ::backend::gl::bindings as glb;
 
 use super::gbuffer::Gbuffer;
-use super::stage::Stage;
+use super::stage::{Stage, ClearValues, BlitTarget};
 use super::screen::ScreenQuad;
-
-pub const GEOMETRY_STAGE_COMPONENTS: [(GLenum, GLenum); 3] = [
+use super::builder::PipelineBuilder;
+use super::lights::{self, Light, LightKind};
+use super::shadow::{ShadowStage, DEFAULT_SHADOW_RESOLUTION};
+use super::bloom::{self, BloomChain, BloomSettings};
+use super::debug::{DebugView, DebugRaster, RasterMode};
+use super::skybox::SKYBOX_UNIT;
+use super::timing::{GpuTimer, StageTimings, TimedPass};
+use super::volume::{LightVolumes, SPHERE_SCALE};
+use super::material;
+use super::samplers::SamplerSet;
+use super::instancing::InstancingSettings;
+use super::blocks::{CameraBlock, LightsBlock, CAMERA_BLOCK, CAMERA_BINDING, LIGHTS_BLOCK, LIGHTS_BINDING};
+use super::tonemap::{LuminanceTarget, TonemapSettings, Exposure, TONEMAP_STAGE, TONEMAP_STAGE_COMPONENTS, TONEMAP_STAGE_NAMES, LUMINANCE_RESOLUTION, LUMINANCE_BINDING};
+use super::reload::{ProgramSource, ProgramSlot, ReloadSummary};
+use super::ssao::{SsaoKernel, SsaoSettings, SSAO_STAGE, SSAO_BLUR_STAGE, SSAO_STAGE_COMPONENTS, SSAO_BLUR_STAGE_NAMES};
+
+pub const GEOMETRY_STAGE: &'static str = "geometry";
+/// Multisampled stage the geometry is rendered into when MSAA is enabled, which is then resolved into the geometry stage
+pub const GEOMETRY_MSAA_STAGE: &'static str = "geometry_msaa";
+pub const LIGHTING_STAGE: &'static str = "lighting";
+pub const FINAL_STAGE: &'static str = "final";
+
+pub const GEOMETRY_STAGE_COMPONENTS: [(GLenum, GLenum); 4] = [
     (glb::RGBA, glb::RGBA16F),
     (glb::RGBA, glb::RGBA32F),
     (glb::RGBA, glb::RGBA32F),
+    (glb::RED_INTEGER, glb::R32UI),
 ];
 
+/// Geometry stage component holding the object ID of each pixel, which isn't sampled by any later stage
+pub const OBJECT_ID_COMPONENT: usize = 3;
+
+/// Object ID of pixels nothing was drawn to, and of draws that don't set one
+pub const NO_OBJECT: u32 = ::std::u32::MAX;
+
 pub const LIGHTING_STAGE_NAMES: [&'static str; 3] = [
     "ColorSs",
     "NormalMs",
@@ -28,153 +56,1133 @@ pub const SCREEN_SHADER_NAMES: [&'static str; 1] = [
     "screen"
 ];
 
-pub struct Pipeline {
-    geometry_stage: Stage,
-    lighting_stage: Stage,
-    final_stage: Stage,
+/// Named texture unit the shadow map is bound to during the lighting pass, reserved clear of any G-buffer inputs
+pub const SHADOW_MAP_BINDING: &'static str = "shadow_map";
 
-    geometry_shader: GLShaderProgram,
-    screen_shader: GLShaderProgram,
+/// First texture unit used by the debug view for its own inputs, after the tonemapping stage's
+pub const DEBUG_FIRST_UNIT: usize = 1;
+
+/// Named texture unit the SSAO noise texture is bound to during the SSAO pass
+pub const SSAO_NOISE_BINDING: &'static str = "ssao_noise";
+
+/// Limits of the render scale. Below half resolution the G-buffer is too coarse to be useful,
+/// and above double resolution the cost grows far faster than the quality does.
+pub const MIN_RENDER_SCALE: f32 = 0.5;
+pub const MAX_RENDER_SCALE: f32 = 2.0;
+
+/// Declares that a stage samples the G-buffer components of a previous stage
+pub struct StageInput {
+    /// Index of the stage being sampled
+    pub stage: usize,
+    /// Uniform names to bind each component to, in component order
+    pub names: Vec<String>,
+}
 
-    screen: ScreenQuad,
-    resolution: Vector2<f32>
+/// A single stage of the pipeline, along with the shader used to render it and whatever it samples
+pub struct NamedStage {
+    pub name: String,
+    pub stage: Stage,
+    /// Size of the stage relative to the pipeline resolution
+    pub scale: f32,
+    pub shader: Option<GLShaderProgram>,
+    pub inputs: Vec<StageInput>,
+    /// What the stage's attachments are cleared to at the start of the pass rendering into it
+    pub clear: ClearValues,
+}
+
+pub struct Pipeline {
+    pub(super) stages: Vec<NamedStage>,
+    pub(super) shadow_stage: Option<ShadowStage>,
+    /// Light matrix of the last shadow pass, if any
+    pub(super) shadow_matrix: Option<Matrix4<f32>>,
+    pub(super) ssao_kernel: Option<SsaoKernel>,
+    /// Whether the SSAO passes ran since the last lighting pass
+    pub(super) ssao_ran: bool,
+    /// SSAO settings, which can be changed at any time
+    pub ssao: SsaoSettings,
+    pub(super) bloom_chain: Option<BloomChain>,
+    /// Number of bloom levels filled in since the last final pass
+    pub(super) bloom_levels: usize,
+    /// Bloom settings, which can be changed at any time
+    pub bloom: BloomSettings,
+    pub(super) luminance: Option<LuminanceTarget>,
+    /// Tonemapping and exposure settings, which can be changed at any time
+    pub tonemap: TonemapSettings,
+    pub(super) depth_shader: Option<GLShaderProgram>,
+    /// Renders depth before the geometry pass to avoid shading occluded fragments. Scenes with little overdraw may be faster without it.
+    pub depth_prepass: bool,
+    pub(super) forward_shader: Option<GLShaderProgram>,
+    pub(super) light_volumes: Option<LightVolumes>,
+    /// How the renderer batches identical meshes into instanced draws. Only read by the render loop, not the pipeline itself.
+    pub instancing: InstancingSettings,
+    /// Whether the final pass applies FXAA. Without it the tonemapped frame is blitted straight to the window.
+    pub fxaa: bool,
+    pub(super) sky_shader: Option<GLShaderProgram>,
+    pub(super) skybox: Option<GLTexture>,
+    /// Whether the skybox cubemap holds sRGB data that has to be linearized before lighting
+    pub skybox_srgb: bool,
+    /// Brightness of the skybox, which is rendered straight into the HDR lighting stage
+    pub skybox_intensity: f32,
+    pub(super) debug_shader: GLShaderProgram,
+    pub(super) debug_view: DebugView,
+    pub(super) debug_raster: Option<DebugRaster>,
+    pub(super) raster_mode: RasterMode,
+    /// View and projection matrices of the current frame, used by the depth debug view
+    pub(super) camera: Option<(Matrix4<f32>, Matrix4<f32>)>,
+    pub(super) camera_block: GLUniformBuffer<CameraBlock>,
+    /// Lights of the current frame, as given to `set_lights`
+    pub(super) lights: LightsBlock,
+    pub(super) lights_block: GLUniformBuffer<LightsBlock>,
+    pub(super) timer: GpuTimer,
+    pub(super) samplers: SamplerSet,
+    /// Scratch units for material textures, and the units reserved for the `*_BINDING` textures
+    pub(super) texture_units: GLTextureUnits,
+    pub(super) screen: ScreenQuad,
+    /// Internal rendering resolution, which is the window size multiplied by the render scale
+    pub(super) resolution: Vector2<f32>,
+    /// Size of the window framebuffer the final pass scales the image to
+    pub(super) window_size: Vector2<f32>,
+    pub(super) render_scale: f32,
+    /// What each reloadable program is built from, for `reload_shaders`
+    pub(super) program_sources: Vec<(ProgramSlot, ProgramSource)>,
 }
 
 impl Pipeline {
+    /// Creates the default deferred pipeline, consisting of a geometry, SSAO, lighting, tonemapping and final stage.
+    #[inline]
     pub fn new(width: usize, height: usize) -> GLResult<Pipeline> {
-        let geometry_vertex_shader = try!(GLShader::from_file("shaders/deferred_geometry.vert", GLShaderVariant::VertexShader));
-        let geometry_fragment_shader = try!(GLShader::from_file("shaders/deferred_geometry.frag", GLShaderVariant::FragmentShader));
-
-        let geometry_shader = GLShaderProgramBuilder::new()?
-            .attach_shader(geometry_vertex_shader)?
-            .attach_shader(geometry_fragment_shader)?
-            .link()?
-            .finish();
-
-        let screen_vertex_shader = try!(GLShader::from_file("shaders/screen.vert", GLShaderVariant::VertexShader));
-        let screen_fragment_shader = try!(GLShader::from_file("shaders/screen.frag", GLShaderVariant::FragmentShader));
-
-        let screen_shader = GLShaderProgramBuilder::new()?
-            .attach_shader(screen_vertex_shader)?
-            .attach_shader(screen_fragment_shader)?
-            .link()?
-            .finish();
-
-        let mut geometry_stage = try!(Stage::new(width, height, Some(&GEOMETRY_STAGE_COMPONENTS)));
-        let mut lighting_stage = try!(Stage::new(width, height, Some(&LIGHTING_STAGE_COMPONENTS)));
-        //TODO: Add transparency stage
-        let final_stage = try!(Stage::new(width, height, None));
-
-        try!(geometry_stage.set_wrap(GLTextureWrap::ClampToEdge));
-
-        //TODO: Move this to whatever stage is right before the screen stage
-        try!(lighting_stage.set_filter(GLTextureFilter::Linear));
-        try!(lighting_stage.set_wrap(GLTextureWrap::ClampToEdge));
-
-        Ok(Pipeline {
-            geometry_stage: geometry_stage,
-            lighting_stage: lighting_stage,
-            final_stage: final_stage,
-            geometry_shader: geometry_shader,
-            screen_shader: screen_shader,
-            screen: try!(ScreenQuad::new()),
-            resolution: Vector2::new(width as f32, height as f32)
-        })
+        Pipeline::with_samples(width, height, 1)
+    }
+
+    /// Same as `new`, but if `samples` is greater than 1 the geometry is rendered into a multisampled stage
+    /// and resolved into the geometry stage before anything samples it.
+    pub fn with_samples(width: usize, height: usize, samples: u32) -> GLResult<Pipeline> {
+        let geometry_source = ProgramSource::new(GEOMETRY_STAGE)
+            .file("shaders/deferred_geometry.vert", GLShaderVariant::VertexShader)
+            .file("shaders/deferred_geometry.frag", GLShaderVariant::FragmentShader)
+            .block(CAMERA_BLOCK, CAMERA_BINDING);
+
+        let lighting_source = ProgramSource::new(LIGHTING_STAGE)
+            .file("shaders/screen.vert", GLShaderVariant::VertexShader)
+            .file("shaders/lighting.frag", GLShaderVariant::FragmentShader)
+            .block(CAMERA_BLOCK, CAMERA_BINDING)
+            .block(LIGHTS_BLOCK, LIGHTS_BINDING);
+
+        let screen_pass = |name: &str, fragment: &str| {
+            ProgramSource::new(name)
+                .file("shaders/screen.vert", GLShaderVariant::VertexShader)
+                .file(fragment, GLShaderVariant::FragmentShader)
+        };
+
+        let builder = if samples > 1 {
+            PipelineBuilder::new(width, height)
+                .stage(GEOMETRY_MSAA_STAGE, Some(&GEOMETRY_STAGE_COMPONENTS), None)?
+                .shader_source(geometry_source)?
+                .multisample(samples)?
+                .clear(ClearValues::default().with_depth(1.0).with_stencil(0).with_integer(NO_OBJECT))?
+                .stage(GEOMETRY_STAGE, Some(&GEOMETRY_STAGE_COMPONENTS), None)?
+                .clear(ClearValues::default().with_depth(1.0).with_stencil(0).with_integer(NO_OBJECT))?
+        } else {
+            PipelineBuilder::new(width, height)
+                .stage(GEOMETRY_STAGE, Some(&GEOMETRY_STAGE_COMPONENTS), None)?
+                .shader_source(geometry_source)?
+                .clear(ClearValues::default().with_depth(1.0).with_stencil(0).with_integer(NO_OBJECT))?
+        };
+
+        builder
+            .wrap(GLTextureWrap::ClampToEdge)?
+            .stage(SSAO_STAGE, Some(&SSAO_STAGE_COMPONENTS), None)?
+            .shader_source(screen_pass(SSAO_STAGE, "shaders/ssao.frag"))?
+            .samples(GEOMETRY_STAGE, &LIGHTING_STAGE_NAMES)?
+            .scale(0.5)?
+            .filter(GLTextureFilter::Linear)?
+            .wrap(GLTextureWrap::ClampToEdge)?
+            .stage(SSAO_BLUR_STAGE, Some(&SSAO_STAGE_COMPONENTS), None)?
+            .shader_source(screen_pass(SSAO_BLUR_STAGE, "shaders/ssao_blur.frag"))?
+            .samples(SSAO_STAGE, &SSAO_BLUR_STAGE_NAMES)?
+            .scale(0.5)?
+            .filter(GLTextureFilter::Linear)?
+            .wrap(GLTextureWrap::ClampToEdge)?
+            //TODO: Move this to whatever stage is right before the screen stage
+            .stage(LIGHTING_STAGE, Some(&LIGHTING_STAGE_COMPONENTS), None)?
+            .shader_source(lighting_source)?
+            .samples(GEOMETRY_STAGE, &LIGHTING_STAGE_NAMES)?
+            .samples(SSAO_BLUR_STAGE, &["ssao_map"])?
+            .shares_depth_with(GEOMETRY_STAGE)?
+            .filter(GLTextureFilter::Linear)?
+            .wrap(GLTextureWrap::ClampToEdge)?
+            .stage(TONEMAP_STAGE, Some(&TONEMAP_STAGE_COMPONENTS), None)?
+            .shader_source(screen_pass(TONEMAP_STAGE, "shaders/tonemap.frag"))?
+            .samples(LIGHTING_STAGE, &TONEMAP_STAGE_NAMES)?
+            .filter(GLTextureFilter::Linear)?
+            .wrap(GLTextureWrap::ClampToEdge)?
+            .stage(FINAL_STAGE, None, None)?
+            .shader_source(screen_pass(FINAL_STAGE, "shaders/screen.frag"))?
+            .samples(TONEMAP_STAGE, &SCREEN_SHADER_NAMES)?
+            .clear(ClearValues::color(0.0, 0.0, 0.0, 1.0))?
+            .shadows(DEFAULT_SHADOW_RESOLUTION)?
+            .ssao()?
+            .bloom()?
+            .auto_exposure()?
+            .depth_prepass()?
+            .transparency()?
+            .light_volumes()?
+            .skybox()?
+            .debug_raster()?
+            .finish()
+    }
+
+    #[inline]
+    pub fn stages(&self) -> &[NamedStage] { &self.stages }
+
+    #[inline]
+    pub fn stage_index(&self, name: &str) -> Option<usize> {
+        self.stages.iter().position(|stage| stage.name == name)
+    }
+
+    #[inline]
+    pub fn stage(&self, name: &str) -> Option<&NamedStage> {
+        self.stages.iter().find(|stage| stage.name == name)
+    }
+
+    /// Builds every reloadable program again from its shader files, swapping in the ones that compile and link.
+    ///
+    /// A program that fails keeps its previous version and the compiler or linker error is logged,
+    /// so a mistake in a shader being edited doesn't stop rendering.
+    pub fn reload_shaders(&mut self) -> ReloadSummary {
+        let mut summary = ReloadSummary::default();
+
+        for &(slot, ref source) in &self.program_sources {
+            let program = match source.build() {
+                Ok(program) => program,
+                Err(err) => {
+                    error!("Could not reload shader program {}: {}", source, err);
+                    summary.failed += 1;
+                    continue;
+                }
+            };
+
+            let target = match slot {
+                ProgramSlot::Stage(index) => &mut self.stages[index].shader,
+                ProgramSlot::DepthPrepass => &mut self.depth_shader,
+                ProgramSlot::Forward => &mut self.forward_shader,
+                ProgramSlot::Sky => &mut self.sky_shader,
+                ProgramSlot::DebugView => {
+                    self.debug_shader = program;
+                    summary.reloaded += 1;
+                    continue;
+                }
+            };
+
+            //The previous program is deleted when it's dropped here
+            *target = Some(program);
+            summary.reloaded += 1;
+        }
+
+        summary
+    }
+
+    #[inline]
+    pub fn stage_mut(&mut self, name: &str) -> Option<&mut NamedStage> {
+        self.stages.iter_mut().find(|stage| stage.name == name)
+    }
+
+    /// Internal rendering resolution of every stage but the final one, before any per-stage scale
+    #[inline(always)]
+    pub fn resolution(&self) -> &Vector2<f32> { &self.resolution }
+
+    /// Size of the window framebuffer, as last given to `resize`
+    #[inline(always)]
+    pub fn window_size(&self) -> &Vector2<f32> { &self.window_size }
+
+    #[inline(always)]
+    pub fn render_scale(&self) -> f32 { self.render_scale }
+
+    /// Renders at `scale` times the window size, which the final pass then scales back up or down to the window.
+    /// The scale is clamped between `MIN_RENDER_SCALE` and `MAX_RENDER_SCALE`.
+    ///
+    /// Every stage is resized just like when the window is.
+    pub fn set_render_scale(&mut self, scale: f32) -> GLResult<()> {
+        let clamped = scale.max(MIN_RENDER_SCALE).min(MAX_RENDER_SCALE);
+
+        if clamped != scale {
+            warn!("Render scale {} clamped to {}", scale, clamped);
+        }
+
+        self.render_scale = clamped;
+
+        let (width, height) = (self.window_size.x as usize, self.window_size.y as usize);
+
+        self.resize_stages(width, height)
+    }
+
+    /// Sets how the final pass filters the image when scaling it to the window. Only matters with a render scale other than 1.
+    pub fn set_upscale_filter(&mut self, filter: GLTextureFilter) -> GLResult<()> {
+        let index = try!(self.index_of(TONEMAP_STAGE));
+
+        self.stages[index].stage.set_filter(filter)
+    }
+
+    /// Sets the color the final stage is cleared to before the tonemapped image is blended onto it
+    pub fn set_clear_color(&mut self, r: f32, g: f32, b: f32) -> GLResult<()> {
+        let stage = try!(self.stage_mut(FINAL_STAGE).ok_or(GLError::InvalidOperation));
+
+        stage.clear.color = Some([r, g, b, 1.0]);
+
+        Ok(())
+    }
+
+    /// Color the final stage is cleared to, if any
+    #[inline]
+    pub fn clear_color(&self) -> Option<[f32; 4]> {
+        self.stage(FINAL_STAGE).and_then(|stage| stage.clear.color)
+    }
+
+    #[inline(always)]
+    pub fn debug_view(&self) -> DebugView { self.debug_view }
+
+    /// Changes what the final pass displays. `DebugView::Final` shows the normal tonemapped output.
+    #[inline]
+    pub fn set_debug_view(&mut self, view: DebugView) {
+        if view != self.debug_view {
+            info!("Debug view: {:?}", view);
+        }
+
+        self.debug_view = view;
+    }
+
+    /// GPU time taken by each pass, as of the most recent frame whose timer queries have completed
+    #[inline(always)]
+    pub fn timings(&self) -> &StageTimings { self.timer.timings() }
+
+    /// Samplers shared by the pipeline's passes, which materials should use as well
+    #[inline(always)]
+    pub fn samplers(&self) -> &SamplerSet { &self.samplers }
+
+    #[inline(always)]
+    pub fn raster_mode(&self) -> RasterMode { self.raster_mode }
+
+    /// Changes how the geometry pass rasterizes the scene. Does nothing without the debug raster resources.
+    #[inline]
+    pub fn set_raster_mode(&mut self, mode: RasterMode) {
+        if self.debug_raster.is_none() {
+            warn!("Raster mode {:?} requires the debug raster resources", mode);
+            return;
+        }
+
+        if mode != self.raster_mode {
+            info!("Raster mode: {:?}", mode);
+        }
+
+        self.raster_mode = mode;
+    }
+
+    /// Sets the cubemap rendered behind everything else, as created by `skybox::load_cubemap`
+    #[inline]
+    pub fn set_skybox(&mut self, texture: GLTexture) {
+        self.skybox = Some(texture);
+    }
+
+    /// Removes the skybox and returns it, leaving the background black
+    #[inline]
+    pub fn clear_skybox(&mut self) -> Option<GLTexture> {
+        self.skybox.take()
+    }
+
+    #[inline]
+    pub fn skybox(&self) -> Option<&GLTexture> { self.skybox.as_ref() }
+
+    /// Sets the camera matrices for the current frame and uploads them to the `Camera` uniform block.
+    ///
+    /// Must be called before the geometry pass.
+    pub fn set_camera(&mut self, view: &Matrix4<f32>, projection: &Matrix4<f32>) -> GLResult<()> {
+        let inverse_view: Matrix4<f32> = try!(view.inverse().ok_or(GLError::InvalidValue));
+
+        self.camera = Some((*view, *projection));
+
+        try!(self.camera_block.update(&CameraBlock {
+            view: *view,
+            projection: *projection,
+            position: Point3::new(inverse_view.m14, inverse_view.m24, inverse_view.m34),
+        }));
+
+        self.camera_block.bind_base(CAMERA_BINDING)
+    }
+
+    /// Sets the lights for the current frame and uploads them to the `Lights` uniform block.
+    ///
+    /// Only the first `lights::MAX_LIGHTS` lights are uploaded. Must be called before the lighting pass.
+    pub fn set_lights(&mut self, lights: &[Light]) -> GLResult<()> {
+        if lights.len() > lights::MAX_LIGHTS {
+            warn!("Too many lights, only the first {} of {} will be rendered", lights::MAX_LIGHTS, lights.len());
+        }
+
+        self.lights.lights.clear();
+        self.lights.lights.extend_from_slice(&lights[..lights.len().min(lights::MAX_LIGHTS)]);
+
+        try!(self.lights_block.update(&self.lights));
+
+        self.lights_block.bind_base(LIGHTS_BINDING)
+    }
+
+    #[inline]
+    pub fn lights(&self) -> &[Light] { &self.lights.lights }
+
+    #[inline]
+    pub fn shadow_stage(&self) -> Option<&ShadowStage> { self.shadow_stage.as_ref() }
+
+    #[inline]
+    pub fn shadow_stage_mut(&mut self) -> Option<&mut ShadowStage> { self.shadow_stage.as_mut() }
+
+    fn index_of(&self, name: &str) -> GLResult<usize> {
+        self.stage_index(name).ok_or(GLError::InvalidValue)
+    }
+
+    /// Actual size of the stage at the given index, taking its scale into account
+    pub fn stage_size(&self, index: usize) -> (usize, usize) {
+        let scale = self.stages[index].scale;
+
+        (((self.resolution.x * scale) as usize).max(1),
+         ((self.resolution.y * scale) as usize).max(1))
+    }
+
+    /// Object ID the last geometry pass wrote at the given window position, with the origin at the top left like cursor positions.
+    ///
+    /// Returns `None` if no object was drawn there, or the position is outside the window.
+    /// Has to be called after the geometry pass of a frame and before the next one clears it.
+    pub fn pick(&self, x: f64, y: f64) -> GLResult<Option<u32>> {
+        let (window_width, window_height) = (self.window_size.x as f64, self.window_size.y as f64);
+
+        if x < 0.0 || y < 0.0 || x >= window_width || y >= window_height {
+            return Ok(None);
+        }
+
+        let stage = &self.stages[try!(self.index_of(GEOMETRY_STAGE))].stage;
+
+        let (width, height) = try!(stage.gbuffer().map(|gbuffer| gbuffer.dimensions).ok_or(GLError::InvalidOperation));
+
+        // Scale to the internal resolution, and flip to the bottom left origin
+        let px = ((x / window_width * width as f64) as usize).min(width - 1);
+        let py = height - 1 - ((y / window_height * height as f64) as usize).min(height - 1);
+
+        let id = try!(stage.read_pixel_u32(OBJECT_ID_COMPONENT, px, py));
+
+        Ok(if id == NO_OBJECT { None } else { Some(id) })
+    }
+
+    /// Binds the G-buffer components of every stage the given stage samples to `shader`
+    fn bind_inputs(&self, index: usize, shader: &GLShaderProgram) -> GLResult<()> {
+        let mut unit = 0;
+
+        for input in &self.stages[index].inputs {
+            if let Some(gbuffer) = self.stages[input.stage].stage.gbuffer() {
+                let names: Vec<&str> = input.names.iter().map(|name| name.as_str()).collect();
+
+                unit += try!(gbuffer.bind_textures_at(shader, &names, unit));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Renders a fullscreen quad into the named stage, sampling its declared inputs.
+    ///
+    /// If `shader` is `None`, the stage's own shader program is used.
+    ///
+    /// This is the building block for any additional post-processing stages inserted through the `PipelineBuilder`.
+    pub fn screen_pass<F>(&self, name: &str, shader: Option<&GLShaderProgram>, mut f: F) -> GLResult<()> where F: FnMut(&GLShaderProgram) -> GLResult<()> {
+        let index = try!(self.index_of(name));
+
+        gl_debug_group!(name);
+
+        let stage = &self.stages[index];
+
+        let shader = try!(shader.or(stage.shader.as_ref()).ok_or(GLError::InvalidOperation));
+
+        let (width, height) = self.stage_size(index);
+
+        try!(stage.stage.bind());
+
+        unsafe {
+            glb::Viewport(0, 0, width as GLsizei, height as GLsizei);
+
+            GLStateCache::disable(glb::DEPTH_TEST);
+            GLStateCache::disable(glb::CULL_FACE);
+            GLStateCache::disable(glb::BLEND);
+        }
+
+        check_errors!();
+
+        try!(shader.use_program());
+
+        let mut res_uniform = try!(shader.get_uniform_optional("resolution"));
+
+        try!(res_uniform.float2(width as f32, height as f32));
+
+        try!(self.bind_inputs(index, shader));
+
+        try!(f(shader));
+
+        try!(self.screen.draw());
+
+        if stage.scale != 1.0 {
+            unsafe {
+                glb::Viewport(0, 0, self.resolution.x as GLsizei, self.resolution.y as GLsizei);
+            }
+
+            check_errors!();
+        }
+
+        Ok(())
     }
 
     /// The Geometry pass is where all world objects are rendered to the G-Buffer.
     ///
     /// This pass gives some amount of control to the renderer, allowing it to bind shader uniforms and so forth.
+    ///
+    /// If the pipeline is multisampled, the geometry is rendered into the multisampled stage and resolved into the geometry stage afterwards.
+    ///
+    /// If the depth pre-pass is enabled, the closure is called twice: first with a depth-only shader and color writes masked off,
+    /// then with the geometry shader using `GL_EQUAL` depth testing and no depth writes. It must therefore not consume its render queue.
+    ///
+    /// The closure should bind each object's textures with `material::bind_material`, which also enables normal mapping if the
+    /// material has a normal map. Tangents and bitangents are expected at attribute locations 3 and 4.
+    ///
+    /// For `pick` to find an object, the closure has to set the `object_id` uniform before drawing it,
+    /// or give instanced draws their IDs through the instance buffer. Anything else is written as `NO_OBJECT`.
     pub fn geometry_pass<F>(&mut self, mut f: F) -> GLResult<()> where F: FnMut(&GLShaderProgram) -> GLResult<()> {
-        // When the geometry pass is called it invalidates any lighting stage results, so bind it really quick and clear it
-        try!(self.lighting_stage.bind());
+        let resolve_index = try!(self.index_of(GEOMETRY_STAGE));
+        let geometry_index = self.stage_index(GEOMETRY_MSAA_STAGE).unwrap_or(resolve_index);
 
-        unsafe {
-            glb::ClearColor(0.0, 0.0, 0.0, 0.0);
-            glb::Clear(glb::COLOR_BUFFER_BIT);
+        gl_debug_group!("Geometry pass");
+
+        self.timer.begin(TimedPass::Geometry);
+
+        // When the geometry pass is called it invalidates any later stage results, so bind them really quick and clear them
+        for later in &self.stages[geometry_index + 1..] {
+            if later.stage.gbuffer().is_some() {
+                try!(later.stage.bind());
+                try!(later.stage.clear(&later.clear));
+            }
         }
 
-        check_errors!();
+        let geometry = &self.stages[geometry_index];
 
-        try!(self.geometry_stage.bind());
+        try!(geometry.stage.bind());
+        try!(geometry.stage.clear(&geometry.clear));
 
         unsafe {
-            glb::ClearColor(0.0, 0.0, 0.0, 0.0);
-            glb::Clear(glb::COLOR_BUFFER_BIT | glb::DEPTH_BUFFER_BIT | glb::STENCIL_BUFFER_BIT);
+            //The final pass of the previous frame leaves the viewport at the window size
+            glb::Viewport(0, 0, self.resolution.x as GLsizei, self.resolution.y as GLsizei);
 
             //glb::Enable(glb::STENCIL_TEST);
 
-            glb::Enable(glb::DEPTH_TEST);
+            GLStateCache::enable(glb::DEPTH_TEST);
             glb::DepthFunc(glb::LESS);
 
-            glb::Enable(glb::CULL_FACE);
+            GLStateCache::enable(glb::CULL_FACE);
             glb::CullFace(glb::BACK);
 
             //Geometry pass cannot use blending at all
-            glb::Disable(glb::BLEND);
+            GLStateCache::disable(glb::BLEND);
+        }
+
+        check_errors!();
+
+        match (self.raster_mode, self.debug_raster.as_ref()) {
+            (RasterMode::Overdraw, Some(debug_raster)) => {
+                let result = Pipeline::overdraw_pass(debug_raster, f);
+
+                self.timer.end();
+
+                return result;
+            }
+            (RasterMode::Wireframe, Some(debug_raster)) => {
+                unsafe {
+                    glb::PolygonMode(glb::FRONT_AND_BACK, glb::LINE);
+
+                    //Show the edges of back faces as well
+                    GLStateCache::disable(glb::CULL_FACE);
+                }
+
+                check_errors!();
+
+                let shader = debug_raster.wireframe_shader();
+
+                try!(shader.use_program());
+
+                let result = f(shader);
+
+                unsafe {
+                    glb::PolygonMode(glb::FRONT_AND_BACK, glb::FILL);
+                    GLStateCache::enable(glb::CULL_FACE);
+                }
+
+                check_errors!();
+
+                try!(result);
+            }
+            _ => {
+                // The pre-pass renders into the same framebuffer, so the geometry pass reuses its depth attachment
+                let prepass = match (self.depth_prepass, self.depth_shader.as_ref()) {
+                    (true, Some(depth_shader)) => {
+                        unsafe {
+                            glb::ColorMask(glb::FALSE, glb::FALSE, glb::FALSE, glb::FALSE);
+                        }
+
+                        check_errors!();
+
+                        try!(depth_shader.use_program());
+
+                        try!(f(depth_shader));
+
+                        unsafe {
+                            glb::ColorMask(glb::TRUE, glb::TRUE, glb::TRUE, glb::TRUE);
+
+                            // Only the nearest surface passes, and depth is already complete
+                            glb::DepthFunc(glb::EQUAL);
+                            glb::DepthMask(glb::FALSE);
+                        }
+
+                        check_errors!();
+
+                        true
+                    }
+                    _ => false,
+                };
+
+                let shader = try!(geometry.shader.as_ref().ok_or(GLError::InvalidOperation));
+
+                try!(shader.use_program());
+
+                try!(material::bind_samplers(shader));
+
+                let result = f(shader);
+
+                // Later passes sample the G-buffer through the same units
+                try!(material::unbind_sampler_objects());
+
+                try!(result);
+
+                if prepass {
+                    unsafe {
+                        glb::DepthFunc(glb::LESS);
+                        glb::DepthMask(glb::TRUE);
+                    }
+                }
+
+                check_errors!();
+            }
+        }
+
+        if geometry_index != resolve_index {
+            try!(geometry.stage.resolve_to(&self.stages[resolve_index].stage));
+        }
+
+        self.timer.end();
+
+        Ok(())
+    }
+
+    /// Draws the geometry into the overdraw stage instead of the G-buffer, additively counting every fragment with depth testing disabled.
+    ///
+    /// The G-buffer is left cleared, so the rest of the frame only shows the overdraw heatmap.
+    fn overdraw_pass<F>(debug_raster: &DebugRaster, mut f: F) -> GLResult<()> where F: FnMut(&GLShaderProgram) -> GLResult<()> {
+        try!(debug_raster.overdraw().bind());
+        try!(debug_raster.overdraw().clear(&ClearValues::default()));
+
+        unsafe {
+            GLStateCache::disable(glb::DEPTH_TEST);
+
+            GLStateCache::enable(glb::BLEND);
+            glb::BlendFunc(glb::ONE, glb::ONE);
         }
 
         check_errors!();
 
-        try!(self.geometry_shader.use_program());
+        let shader = debug_raster.overdraw_shader();
+
+        try!(shader.use_program());
+
+        let result = f(shader);
 
-        try!(f(&self.geometry_shader));
+        GLStateCache::disable(glb::BLEND);
+        GLStateCache::enable(glb::DEPTH_TEST);
 
         check_errors!();
 
+        result
+    }
+
+    /// The Shadow pass renders shadow casters into the shadow map from the light's point of view.
+    ///
+    /// Like the geometry pass, the closure is responsible for drawing each caster and setting its `model` uniform.
+    /// Front faces are culled and polygon offset is applied to reduce shadow acne.
+    ///
+    /// The given matrix is remembered for the next lighting pass, which applies the shadows to the first directional light.
+    pub fn shadow_pass<F>(&mut self, light_view_proj: &Matrix4<f32>, mut f: F) -> GLResult<()> where F: FnMut(&GLShaderProgram) -> GLResult<()> {
+        gl_debug_group!("Shadow pass");
+
+        self.timer.begin(TimedPass::Shadow);
+
+        {
+            let shadow_stage = try!(self.shadow_stage.as_ref().ok_or(GLError::InvalidOperation));
+
+            let resolution = shadow_stage.resolution() as GLsizei;
+
+            try!(shadow_stage.framebuffer().bind());
+
+            unsafe {
+                glb::Viewport(0, 0, resolution, resolution);
+
+                glb::Clear(glb::DEPTH_BUFFER_BIT);
+
+                GLStateCache::enable(glb::DEPTH_TEST);
+                glb::DepthFunc(glb::LESS);
+
+                GLStateCache::enable(glb::CULL_FACE);
+                glb::CullFace(glb::FRONT);
+
+                GLStateCache::enable(glb::POLYGON_OFFSET_FILL);
+                glb::PolygonOffset(2.0, 4.0);
+
+                GLStateCache::disable(glb::BLEND);
+            }
+
+            check_errors!();
+
+            let shader = shadow_stage.shader();
+
+            try!(shader.use_program());
+
+            try!(shader.get_uniform("light_view_proj")?.mat4(light_view_proj, false));
+
+            try!(f(shader));
+
+            unsafe {
+                GLStateCache::disable(glb::POLYGON_OFFSET_FILL);
+                glb::CullFace(glb::BACK);
+
+                glb::Viewport(0, 0, self.resolution.x as GLsizei, self.resolution.y as GLsizei);
+            }
+
+            check_errors!();
+        }
+
+        self.shadow_matrix = Some(*light_view_proj);
+
+        self.timer.end();
+
+        Ok(())
+    }
+
+    /// The SSAO pass computes ambient occlusion from the geometry stage's G-Buffer at half resolution, then blurs it.
+    ///
+    /// If SSAO is disabled in the settings this does nothing, and the lighting pass will not apply any occlusion.
+    pub fn ssao_pass(&mut self, view: &Matrix4<f32>, projection: &Matrix4<f32>) -> GLResult<()> {
+        if !self.ssao.enabled || self.ssao_kernel.is_none() {
+            return Ok(());
+        }
+
+        gl_debug_group!("SSAO pass");
+
+        self.timer.begin(TimedPass::Ssao);
+
+        {
+            let settings = self.ssao;
+            let kernel = self.ssao_kernel.as_ref().unwrap();
+            let noise_unit = try!(self.texture_units.get(SSAO_NOISE_BINDING));
+
+            try!(self.screen_pass(SSAO_STAGE, None, |shader| {
+                try!(kernel.bind(shader, &settings, noise_unit));
+
+                try!(shader.get_uniform("view")?.mat4(view, false));
+                try!(shader.get_uniform("projection")?.mat4(projection, false));
+
+                Ok(())
+            }));
+
+            try!(self.screen_pass(SSAO_BLUR_STAGE, None, |_| Ok(())));
+        }
+
+        self.ssao_ran = true;
+
+        self.timer.end();
+
         Ok(())
     }
 
-    /// The Lighting pass applies custom shaders to the G-Buffer data to light the scene as desired.
+    /// The Lighting pass shades the G-Buffer data of the geometry stage with the lights given to `set_lights`, rendering into the lighting stage.
+    ///
+    /// The geometry stage's G-Buffer components are bound to `ColorSs`, `NormalMs` and `PositionDs`,
+    /// and the lights and camera are read from their uniform blocks.
+    ///
+    /// If a shadow pass was performed this frame, the shadow map and light matrix are bound as well,
+    /// and the first directional light is shadowed.
     ///
-    /// This pass gives almost no control to the renderer except a few uniforms and which shader to use in the first place.
+    /// With light volumes and a camera set by `set_camera`, point lights are skipped by the fullscreen pass
+    /// and each one is shaded only where its volume touches geometry. See `light_volume_pass`.
     ///
-    /// However, this pass can be repeated multiple times for varying lighting shaders
-    pub fn lighting_pass<F>(&mut self, shader: &GLShaderProgram, mut f: F) -> GLResult<()> where F: FnMut(&GLShaderProgram) -> GLResult<()> {
-        try!(self.lighting_stage.bind());
+    /// The closure can be used to bind any additional uniforms. It is called for the fullscreen shader
+    /// and again for the light volume shader.
+    pub fn lighting_pass<F>(&mut self, mut f: F) -> GLResult<()> where F: FnMut(&GLShaderProgram) -> GLResult<()> {
+        gl_debug_group!("Lighting pass");
+
+        self.timer.begin(TimedPass::Lighting);
+
+        let use_volumes = self.light_volumes.is_some() && self.camera.is_some();
+
+        // Shadows only apply to the frame they were rendered for
+        let shadow_matrix = self.shadow_matrix.take();
+
+        let shadow_light = shadow_matrix.and_then(|_| {
+            self.lights.lights.iter().position(|light| light.kind == LightKind::Directional)
+        });
+
+        // Likewise, only use the SSAO results if they were computed this frame
+        let ssao_enabled = self.ssao.enabled && ::std::mem::replace(&mut self.ssao_ran, false);
+
+        let shadow_stage = self.shadow_stage.as_ref();
+        let samplers = &self.samplers;
+        let shadow_map_unit = try!(self.texture_units.get(SHADOW_MAP_BINDING));
+
+        let mut result = self.screen_pass(LIGHTING_STAGE, None, |shader| {
+            try!(shader.get_uniform("ssao_enabled")?.bool1(ssao_enabled));
+            try!(shader.get_uniform("skip_point_lights")?.bool1(use_volumes));
+
+            match (shadow_light, shadow_matrix, shadow_stage) {
+                (Some(index), Some(ref matrix), Some(shadow_stage)) => {
+                    try!(shadow_stage.bind_shadow_map(shader, shadow_map_unit, samplers.shadow_compare()));
+                    try!(shader.get_uniform("light_matrix")?.mat4(matrix, false));
+                    try!(shader.get_uniform("shadow_light")?.int1(index as GLint));
+                }
+                _ => {
+                    try!(shader.get_uniform("shadow_light")?.int1(-1));
+                }
+            }
+
+            f(shader)
+        });
+
+        if result.is_ok() && use_volumes {
+            result = self.light_volume_pass(&mut f);
+        }
+
+        self.timer.end();
+
+        result
+    }
+
+    /// Additively shades each point light on top of the lighting stage, restricted to the pixels inside its light volume.
+    ///
+    /// Normally a sphere around the light is rendered twice. The first time marks the stencil buffer wherever geometry
+    /// lies between its front and back faces, using the depth buffer shared with the geometry stage, and the second time
+    /// shades only the marked pixels. When the camera is inside the volume the front faces are clipped away,
+    /// so instead the back faces are drawn with front-face culling, shading anything in front of them.
+    fn light_volume_pass<F>(&self, f: &mut F) -> GLResult<()> where F: FnMut(&GLShaderProgram) -> GLResult<()> {
+        let (volumes, &(ref view, ref projection)) = match (self.light_volumes.as_ref(), self.camera.as_ref()) {
+            (Some(volumes), Some(camera)) => (volumes, camera),
+            _ => return Ok(()),
+        };
+
+        let geometry_index = try!(self.index_of(GEOMETRY_STAGE));
+        let lighting_index = try!(self.index_of(LIGHTING_STAGE));
+
+        let gbuffer = try!(self.stages[geometry_index].stage.gbuffer().ok_or(GLError::InvalidOperation));
+
+        let view_projection = *projection * *view;
+
+        let inverse_view: Matrix4<f32> = try!(view.inverse().ok_or(GLError::InvalidValue));
+
+        let camera_position = Point3::new(inverse_view.m14, inverse_view.m24, inverse_view.m34);
+
+        // Recover the near plane from the perspective projection, since the volume is clipped before the camera is actually inside it
+        let near = projection.m34 / (projection.m33 - 1.0);
+
+        let stencil_shader = volumes.stencil_shader();
+        let point_shader = volumes.point_shader();
+
+        try!(self.stages[lighting_index].stage.bind());
+
+        try!(point_shader.use_program());
+
+        try!(point_shader.get_uniform("resolution")?.vec2f(&self.resolution));
+
+        try!(gbuffer.bind_textures_at(point_shader, &LIGHTING_STAGE_NAMES, 0));
+
+        try!(f(point_shader));
+
+        unsafe {
+            GLStateCache::enable(glb::STENCIL_TEST);
+            glb::DepthMask(glb::FALSE);
+
+            glb::BlendFunc(glb::ONE, glb::ONE);
+        }
+
+        check_errors!();
+
+        for (index, light) in self.lights.lights.iter().enumerate() {
+            if light.kind != LightKind::Point {
+                continue;
+            }
+
+            let radius = light.volume_radius() * SPHERE_SCALE;
+
+            if radius <= 0.0 {
+                continue;
+            }
+
+            let p = &light.position;
+
+            let model = Matrix4::new(radius, 0.0, 0.0, p.x,
+                                     0.0, radius, 0.0, p.y,
+                                     0.0, 0.0, radius, p.z,
+                                     0.0, 0.0, 0.0, 1.0);
+
+            let mvp = view_projection * model;
+
+            let inside = (*p - camera_position).norm() <= radius + near.abs();
+
+            if !inside {
+                try!(stencil_shader.use_program());
+
+                try!(stencil_shader.get_uniform("mvp")?.mat4(&mvp, false));
+
+                unsafe {
+                    glb::Clear(glb::STENCIL_BUFFER_BIT);
+
+                    glb::ColorMask(glb::FALSE, glb::FALSE, glb::FALSE, glb::FALSE);
+
+                    GLStateCache::enable(glb::DEPTH_TEST);
+                    glb::DepthFunc(glb::LESS);
+
+                    GLStateCache::disable(glb::CULL_FACE);
+                    GLStateCache::disable(glb::BLEND);
+
+                    // Geometry behind the front faces but in front of the back faces ends up non-zero
+                    glb::StencilFunc(glb::ALWAYS, 0, 0);
+                    glb::StencilOpSeparate(glb::BACK, glb::KEEP, glb::INCR_WRAP, glb::KEEP);
+                    glb::StencilOpSeparate(glb::FRONT, glb::KEEP, glb::DECR_WRAP, glb::KEEP);
+                }
+
+                check_errors!();
+
+                try!(volumes.sphere().draw());
+            }
+
+            try!(point_shader.use_program());
+
+            try!(point_shader.get_uniform("mvp")?.mat4(&mvp, false));
+
+            try!(point_shader.get_uniform("light_index")?.int1(index as GLint));
+
+            unsafe {
+                glb::ColorMask(glb::TRUE, glb::TRUE, glb::TRUE, glb::TRUE);
+
+                GLStateCache::enable(glb::BLEND);
+                GLStateCache::enable(glb::CULL_FACE);
+
+                if inside {
+                    glb::StencilFunc(glb::ALWAYS, 0, 0);
+                    glb::StencilOp(glb::KEEP, glb::KEEP, glb::KEEP);
+
+                    GLStateCache::enable(glb::DEPTH_TEST);
+                    glb::DepthFunc(glb::GEQUAL);
+
+                    glb::CullFace(glb::FRONT);
+                } else {
+                    glb::StencilFunc(glb::NOTEQUAL, 0, 0xFF);
+                    glb::StencilOp(glb::KEEP, glb::KEEP, glb::KEEP);
+
+                    GLStateCache::disable(glb::DEPTH_TEST);
+
+                    glb::CullFace(glb::BACK);
+                }
+            }
+
+            check_errors!();
+
+            try!(volumes.sphere().draw());
+        }
+
+        unsafe {
+            GLStateCache::disable(glb::STENCIL_TEST);
+            GLStateCache::disable(glb::BLEND);
+
+            GLStateCache::enable(glb::DEPTH_TEST);
+            glb::DepthFunc(glb::LESS);
+            glb::DepthMask(glb::TRUE);
+
+            GLStateCache::enable(glb::CULL_FACE);
+            glb::CullFace(glb::BACK);
+        }
+
+        check_errors!();
+
+        Ok(())
+    }
+
+    /// The Bloom pass thresholds the lighting stage output into the first level of the bloom chain and blurs it
+    /// with `BloomSettings::blur_passes` separable gaussian passes, then repeatedly downsamples and blurs it into the next,
+    /// progressively half-sized level.
+    ///
+    /// The levels are composited back additively in the final pass. If bloom is disabled this does nothing.
+    pub fn bloom_pass(&mut self) -> GLResult<()> {
+        if !self.bloom.enabled || self.bloom_chain.is_none() {
+            return Ok(());
+        }
+
+        gl_debug_group!("Bloom pass");
+
+        self.timer.begin(TimedPass::Bloom);
+
+        let settings = self.bloom;
+        let iterations = settings.iterations.min(bloom::MAX_BLOOM_ITERATIONS);
+
+        {
+            let lighting_index = try!(self.index_of(LIGHTING_STAGE));
+
+            let (resolution_x, resolution_y) = (self.resolution.x as usize, self.resolution.y as usize);
+
+            let chain = self.bloom_chain.as_mut().unwrap();
+
+            GLStateCache::disable(glb::DEPTH_TEST);
+            GLStateCache::disable(glb::CULL_FACE);
+            GLStateCache::disable(glb::BLEND);
+
+            check_errors!();
+
+            for level in 0..iterations {
+                let (width, height) = bloom::level_size(resolution_x, resolution_y, level);
+
+                {
+                    // The first level thresholds the lighting output, every other level downsamples the one before it
+                    let (stage, shader, source) = if level == 0 {
+                        (chain.first().target(), chain.threshold_shader(), &self.stages[lighting_index].stage)
+                    } else {
+                        (chain.level(level), chain.downsample_shader(), chain.level(level - 1))
+                    };
+
+                    try!(stage.bind());
+
+                    unsafe {
+                        glb::Viewport(0, 0, width as GLsizei, height as GLsizei);
+                    }
+
+                    check_errors!();
+
+                    try!(shader.use_program());
+
+                    try!(shader.get_uniform("resolution")?.float2(width as f32, height as f32));
+
+                    if level == 0 {
+                        try!(shader.get_uniform("threshold")?.float1(settings.threshold));
+                    }
+
+                    try!(bloom::bind_source(shader, "source", source, 0));
+
+                    try!(self.screen.draw());
+                }
+
+                if level == 0 {
+                    chain.first_mut().swap();
+
+                    try!(chain.blur(settings.blur_passes, &self.screen));
+                }
+            }
+
+            unsafe {
+                glb::Viewport(0, 0, self.resolution.x as GLsizei, self.resolution.y as GLsizei);
+            }
+
+            check_errors!();
+        }
+
+        self.bloom_levels = iterations;
+
+        self.timer.end();
+
+        Ok(())
+    }
+
+    /// The Skybox pass fills in every pixel not covered by the geometry pass with the skybox cubemap.
+    ///
+    /// A fullscreen quad on the far plane is drawn into the lighting stage with `GL_LEQUAL` depth testing against the shared
+    /// geometry depth buffer, and the view ray of each pixel is reconstructed from the camera given to `set_camera`.
+    ///
+    /// Without a skybox or camera nothing is drawn and the background stays black.
+    pub fn skybox_pass(&mut self) -> GLResult<()> {
+        let (skybox, shader, &(ref view, ref projection)) = match (self.skybox.as_ref(), self.sky_shader.as_ref(), self.camera.as_ref()) {
+            (Some(skybox), Some(shader), Some(camera)) => (skybox, shader, camera),
+            _ => return Ok(()),
+        };
+
+        let inverse_view_projection: Matrix4<f32> = try!((*projection * *view).inverse().ok_or(GLError::InvalidValue));
+
+        gl_debug_group!("Skybox pass");
+
+        self.timer.begin(TimedPass::Skybox);
+
+        let lighting_index = try!(self.index_of(LIGHTING_STAGE));
+
+        try!(self.stages[lighting_index].stage.bind());
 
-        //The lighting pass can't use anything other than the Gbuffer, basically
         unsafe {
-            glb::Disable(glb::DEPTH_TEST);
-            glb::Disable(glb::CULL_FACE);
-            glb::Disable(glb::BLEND);
+            GLStateCache::enable(glb::DEPTH_TEST);
+            glb::DepthFunc(glb::LEQUAL);
+            glb::DepthMask(glb::FALSE);
+
+            GLStateCache::disable(glb::CULL_FACE);
+            GLStateCache::disable(glb::BLEND);
         }
 
         check_errors!();
 
         try!(shader.use_program());
 
-        let mut res_uniform = try!(shader.get_uniform("resolution"));
+        try!(GLTextureUnits::bind(SKYBOX_UNIT, skybox));
 
-        try!(res_uniform.vec2f(&self.resolution));
+        try!(shader.get_uniform("skybox")?.int1(SKYBOX_UNIT as GLint));
+        try!(shader.get_uniform("srgb")?.bool1(self.skybox_srgb));
+        try!(shader.get_uniform("intensity")?.float1(self.skybox_intensity));
+        try!(shader.get_uniform("inverse_view_projection")?.mat4(&inverse_view_projection, false));
+
+        try!(self.screen.overlay());
 
-        try!(self.geometry_stage.gbuffer().unwrap().bind_textures(&shader, &LIGHTING_STAGE_NAMES));
+        unsafe {
+            glb::DepthFunc(glb::LESS);
+            glb::DepthMask(glb::TRUE);
+        }
 
-        try!(f(&shader));
+        check_errors!();
 
-        try!(self.screen.draw());
+        self.timer.end();
 
         Ok(())
     }
 
-    /// The Forward pass is traditional forward rendering, which is required for transparent objects or more complex shaders
+    /// The Forward pass is traditional forward rendering, which is required for more complex shaders
     /// that simple can't rely on the Gbuffer.
     ///
-    /// This stage accumulates it's results into the same framebuffer as the lighting stage, so blending of transparent objects
-    /// is done automatically.
+    /// This stage accumulates it's results into the same framebuffer as the lighting stage, which shares the geometry stage's
+    /// depth buffer, so forward rendered objects are correctly occluded by deferred ones and vice versa.
     pub fn forward_pass<F>(&mut self, mut f: F) -> GLResult<()> where F: FnMut() -> GLResult<()> {
-        try!(self.lighting_stage.bind());
+        let lighting_index = try!(self.index_of(LIGHTING_STAGE));
+
+        gl_debug_group!("Forward pass");
+
+        self.timer.begin(TimedPass::Forward);
+
+        try!(self.stages[lighting_index].stage.bind());
 
         unsafe {
-            glb::Enable(glb::DEPTH_TEST);
+            GLStateCache::enable(glb::DEPTH_TEST);
             glb::DepthFunc(glb::LESS);
 
-            glb::Clear(glb::DEPTH_BUFFER_BIT);
-
-            glb::Enable(glb::CULL_FACE);
+            GLStateCache::enable(glb::CULL_FACE);
             glb::CullFace(glb::BACK);
 
-            glb::Enable(glb::BLEND);
+            GLStateCache::enable(glb::BLEND);
             glb::BlendFunc(glb::SRC_ALPHA, glb::ONE_MINUS_SRC_ALPHA);
         }
 
@@ -182,50 +1190,360 @@ impl Pipeline {
 
         try!(f());
 
+        self.timer.end();
+
         Ok(())
     }
 
+    /// The Transparent pass forward renders alpha-blended geometry such as glass or particles on top of the lighting stage.
+    ///
+    /// Depth testing against the geometry stage's depth buffer is enabled, but depth writes are not, so transparent objects
+    /// never occlude each other. For correct blending they should be drawn back-to-front, so the closure receives the camera position
+    /// to sort by along with the forward shader, which reads the lights and camera from their uniform blocks.
+    ///
+    /// Like the geometry pass, the closure is responsible for setting the `model`, `mvp` and `mit` uniforms of every object,
+    /// as well as its `color` texture, `tint` and `smoothness`.
+    pub fn transparent_pass<F>(&mut self, view_position: &Point3<f32>, mut f: F) -> GLResult<()>
+        where F: FnMut(&GLShaderProgram, &Point3<f32>) -> GLResult<()> {
+        let lighting_index = try!(self.index_of(LIGHTING_STAGE));
+
+        let shader = try!(self.forward_shader.as_ref().ok_or(GLError::InvalidOperation));
+
+        gl_debug_group!("Transparent pass");
+
+        self.timer.begin(TimedPass::Transparent);
+
+        try!(self.stages[lighting_index].stage.bind());
+
+        unsafe {
+            GLStateCache::enable(glb::DEPTH_TEST);
+            glb::DepthFunc(glb::LESS);
+            glb::DepthMask(glb::FALSE);
+
+            //Both sides of transparent surfaces are visible
+            GLStateCache::disable(glb::CULL_FACE);
+
+            GLStateCache::enable(glb::BLEND);
+            glb::BlendFunc(glb::SRC_ALPHA, glb::ONE_MINUS_SRC_ALPHA);
+        }
+
+        check_errors!();
+
+        try!(shader.use_program());
+
+        let result = f(shader, view_position);
+
+        unsafe {
+            glb::DepthMask(glb::TRUE);
+            GLStateCache::enable(glb::CULL_FACE);
+            GLStateCache::disable(glb::BLEND);
+        }
+
+        check_errors!();
+
+        self.timer.end();
+
+        result
+    }
+
+    /// Additively composites the given number of bloom levels onto the lighting stage, before it is tonemapped
+    fn composite_bloom(&self, levels: usize) -> GLResult<()> {
+        if let Some(ref chain) = self.bloom_chain {
+            let lighting_index = try!(self.index_of(LIGHTING_STAGE));
+
+            try!(self.stages[lighting_index].stage.bind());
+
+            unsafe {
+                GLStateCache::disable(glb::DEPTH_TEST);
+                GLStateCache::disable(glb::CULL_FACE);
+
+                GLStateCache::enable(glb::BLEND);
+                glb::BlendFunc(glb::ONE, glb::ONE);
+            }
+
+            check_errors!();
+
+            let composite_shader = chain.composite_shader();
+
+            try!(composite_shader.use_program());
+
+            // Spread the intensity over every level so adding iterations widens the bloom without brightening it
+            let intensity = self.bloom.intensity / levels as f32;
+
+            try!(composite_shader.get_uniform("intensity")?.float1(intensity));
+
+            for level in 0..levels {
+                try!(bloom::bind_source(composite_shader, "bloom", chain.level(level), 0));
+
+                try!(self.screen.overlay());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Renders the log-luminance of the lighting stage into the luminance target and averages it through its mip chain
+    fn measure_luminance(&self) -> GLResult<()> {
+        if let Some(ref luminance) = self.luminance {
+            let lighting_index = try!(self.index_of(LIGHTING_STAGE));
+
+            try!(luminance.framebuffer().bind());
+
+            unsafe {
+                glb::Viewport(0, 0, LUMINANCE_RESOLUTION as GLsizei, LUMINANCE_RESOLUTION as GLsizei);
+
+                GLStateCache::disable(glb::DEPTH_TEST);
+                GLStateCache::disable(glb::CULL_FACE);
+                GLStateCache::disable(glb::BLEND);
+            }
+
+            check_errors!();
+
+            let shader = luminance.shader();
+
+            try!(shader.use_program());
+
+            try!(bloom::bind_source(shader, "hdr", &self.stages[lighting_index].stage, 0));
+
+            try!(self.screen.draw());
+
+            unsafe {
+                glb::Viewport(0, 0, self.resolution.x as GLsizei, self.resolution.y as GLsizei);
+            }
+
+            check_errors!();
+
+            try!(luminance.generate_mipmaps());
+        }
+
+        Ok(())
+    }
+
+    /// Tonemaps and gamma corrects the lighting stage into the tonemapping stage
+    fn tonemap_pass(&self) -> GLResult<()> {
+        let settings = self.tonemap;
+
+        let auto_exposure = match settings.exposure {
+            Exposure::Auto { .. } if self.luminance.is_some() && !settings.raw => {
+                try!(self.measure_luminance());
+
+                true
+            }
+            _ => false,
+        };
+
+        let luminance = self.luminance.as_ref();
+        let luminance_unit = try!(self.texture_units.get(LUMINANCE_BINDING));
+
+        self.screen_pass(TONEMAP_STAGE, None, |shader| {
+            try!(shader.get_uniform("tonemapper")?.int1(settings.tonemapper as GLint));
+            try!(shader.get_uniform("gamma")?.float1(settings.gamma));
+            try!(shader.get_uniform("raw")?.bool1(settings.raw));
+            try!(shader.get_uniform("auto_exposure")?.bool1(auto_exposure));
+
+            match settings.exposure {
+                Exposure::Manual(exposure) => {
+                    try!(shader.get_uniform("exposure")?.float1(exposure));
+                }
+                Exposure::Auto { key } => {
+                    try!(shader.get_uniform("key")?.float1(key));
+
+                    if let (true, Some(luminance)) = (auto_exposure, luminance) {
+                        try!(luminance.bind_luminance(shader, luminance_unit));
+                    }
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Renders the given debug view into the tonemapping stage instead of the tonemapped output
+    fn debug_view_pass(&self, debug_view: DebugView, bloom_enabled: bool) -> GLResult<()> {
+        let geometry_index = try!(self.index_of(GEOMETRY_STAGE));
+        let ssao_index = try!(self.index_of(SSAO_BLUR_STAGE));
+
+        let ssao_enabled = self.ssao.enabled;
+
+        self.screen_pass(TONEMAP_STAGE, Some(&self.debug_shader), |shader| {
+            try!(shader.get_uniform_optional("debug_view")?.int1(debug_view as GLint));
+
+            if let Some((ref view, ref projection)) = self.camera {
+                try!(shader.get_uniform_optional("view")?.mat4(view, false));
+                try!(shader.get_uniform_optional("projection")?.mat4(projection, false));
+            }
+
+            let mut unit = DEBUG_FIRST_UNIT;
+
+            if let Some(gbuffer) = self.stages[geometry_index].stage.gbuffer() {
+                unit += try!(gbuffer.bind_textures_at(shader, &LIGHTING_STAGE_NAMES, unit));
+            }
+
+            try!(shader.get_uniform_optional("ssao_enabled")?.bool1(ssao_enabled));
+
+            if ssao_enabled {
+                try!(bloom::bind_source(shader, "ssao_map", &self.stages[ssao_index].stage, unit));
+            }
+
+            unit += 1;
+
+            try!(shader.get_uniform_optional("bloom_enabled")?.bool1(bloom_enabled));
+
+            if let (true, Some(chain)) = (bloom_enabled, self.bloom_chain.as_ref()) {
+                try!(bloom::bind_source(shader, "bloom", chain.level(0), unit));
+            }
+
+            Ok(())
+        })
+    }
+
     /// The Screen pass renders the final result to a quad on the default framebuffer,
     /// effectively drawing it on the the screen.
     ///
-    /// This stage also applies FXAA, smoothing out aliasing artifacts
+    /// Any bloom levels rendered since the last final pass are composited first, then the HDR lighting output is
+    /// tonemapped and gamma corrected into the tonemapping stage. Finally, FXAA is applied, smoothing out aliasing artifacts.
+    /// With `fxaa` disabled, the tonemapping stage is blitted to the window instead of drawing the screen quad.
+    ///
+    /// If a debug view is selected, it replaces the tonemapped output.
     pub fn final_pass(&mut self) -> GLResult<()> {
-        try!(self.final_stage.bind());
+        gl_debug_group!("Final pass");
+
+        self.timer.begin(TimedPass::Final);
+
+        // Bloom only applies to the frame it was rendered for
+        let bloom_levels = ::std::mem::replace(&mut self.bloom_levels, 0);
+
+        if bloom_levels > 0 {
+            try!(self.composite_bloom(bloom_levels));
+        }
+
+        match (self.raster_mode, self.debug_raster.as_ref()) {
+            (RasterMode::Overdraw, Some(debug_raster)) => {
+                try!(self.screen_pass(TONEMAP_STAGE, Some(debug_raster.overdraw_view_shader()), |shader| {
+                    bloom::bind_source(shader, "overdraw", debug_raster.overdraw(), DEBUG_FIRST_UNIT)
+                }));
+            }
+            // The wire color is written straight into the albedo, so show it unlit
+            (RasterMode::Wireframe, Some(_)) => {
+                try!(self.debug_view_pass(DebugView::Albedo, false));
+            }
+            _ if self.debug_view != DebugView::Final => {
+                try!(self.debug_view_pass(self.debug_view, bloom_levels > 0));
+            }
+            _ => {
+                try!(self.tonemap_pass());
+            }
+        }
+
+        if !self.fxaa {
+            try!(self.present_blit());
+
+            // The final pass ends the frame
+            self.timer.next_frame();
+
+            return Ok(());
+        }
+
+        let final_index = try!(self.index_of(FINAL_STAGE));
+
+        let stage = &self.stages[final_index];
+
+        let shader = try!(stage.shader.as_ref().ok_or(GLError::InvalidOperation));
+
+        try!(stage.stage.bind());
+        try!(stage.stage.clear(&stage.clear));
 
         unsafe {
-            //No depth or culling for a single quad
-            glb::Disable(glb::DEPTH_TEST);
-            glb::Disable(glb::CULL_FACE);
+            //Every other stage renders at the internal resolution, but this one covers the whole window
+            glb::Viewport(0, 0, self.window_size.x as GLsizei, self.window_size.y as GLsizei);
+
+            //No depth, stencil or culling for a single quad
+            GLStateCache::disable(glb::DEPTH_TEST);
+            GLStateCache::disable(glb::STENCIL_TEST);
+            GLStateCache::disable(glb::CULL_FACE);
 
             //FXAA may take advantage of blending a bit
-            glb::Enable(glb::BLEND);
+            GLStateCache::enable(glb::BLEND);
             glb::BlendFunc(glb::ONE, glb::ONE);
         }
 
         check_errors!();
 
-        try!(self.screen_shader.use_program());
+        try!(shader.use_program());
 
-        let mut res_uniform = try!(self.screen_shader.get_uniform("resolution"));
+        let mut res_uniform = try!(shader.get_uniform_optional("resolution"));
 
         try!(res_uniform.vec2f(&self.resolution));
 
-        try!(self.lighting_stage.gbuffer().unwrap().bind_textures(&self.screen_shader, &SCREEN_SHADER_NAMES));
+        try!(self.bind_inputs(final_index, shader));
 
-        try!(self.screen.draw());
+        try!(self.screen.overlay());
+
+        // The final pass ends the frame
+        self.timer.next_frame();
 
         Ok(())
     }
 
+    /// Copies the tonemapped frame to the window, scaling it from the internal resolution, for when there's no shader work left
+    fn present_blit(&self) -> GLResult<()> {
+        let tonemap_index = try!(self.index_of(TONEMAP_STAGE));
+
+        let target = BlitTarget::Default(self.window_size.x as usize, self.window_size.y as usize);
+
+        // Blits ignore the viewport, but the rest of the frame may expect it to cover the window like after the screen quad
+        unsafe {
+            glb::Viewport(0, 0, self.window_size.x as GLsizei, self.window_size.y as GLsizei);
+        }
+
+        check_errors!();
+
+        self.stages[tonemap_index].stage.blit_to(target, glb::COLOR_BUFFER_BIT, glb::LINEAR)
+    }
+
+    /// Resizes every stage and bloom level to the new window size times the render scale, recreating their attachments.
+    /// The shadow map keeps its own resolution.
+    ///
+    /// Does nothing if the size hasn't actually changed.
     pub fn resize(&mut self, width: usize, height: usize) -> GLResult<()> {
-        try!(self.geometry_stage.resize(width, height));
-        try!(self.lighting_stage.resize(width, height));
-        try!(self.final_stage.resize(width, height));
+        if width == 0 || height == 0 {
+            return Err(GLError::InvalidValue);
+        }
+
+        if width == self.window_size.x as usize && height == self.window_size.y as usize {
+            return Ok(());
+        }
+
+        self.resize_stages(width, height)
+    }
+
+    /// Recomputes the internal resolution from the window size and render scale, and resizes everything to it
+    fn resize_stages(&mut self, window_width: usize, window_height: usize) -> GLResult<()> {
+        self.window_size = Vector2::new(window_width as f32, window_height as f32);
+
+        let width = ((window_width as f32 * self.render_scale) as usize).max(1);
+        let height = ((window_height as f32 * self.render_scale) as usize).max(1);
+
+        if width == self.resolution.x as usize && height == self.resolution.y as usize {
+            return Ok(());
+        }
 
         self.resolution = Vector2::new(width as f32, height as f32);
 
+        for index in 0..self.stages.len() {
+            let (width, height) = self.stage_size(index);
+
+            try!(self.stages[index].stage.resize(width, height));
+        }
+
+        if let Some(ref mut chain) = self.bloom_chain {
+            try!(chain.resize(width, height));
+        }
+
+        if let Some(ref mut debug_raster) = self.debug_raster {
+            try!(debug_raster.resize(width, height));
+        }
+
         Ok(())
     }
 }
-
-
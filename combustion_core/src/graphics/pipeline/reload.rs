@@ -0,0 +1,95 @@
+//! Rebuilding the pipeline's shader programs from their files, so shaders can be edited without restarting
+
+use std::fmt::{self, Display, Formatter};
+
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+
+/// Which of the pipeline's programs a `ProgramSource` builds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgramSlot {
+    /// The shader of the stage at this index
+    Stage(usize),
+    DepthPrepass,
+    Forward,
+    Sky,
+    DebugView,
+}
+
+/// The shader files and uniform block bindings of a program, kept so it can be built again after the files change
+#[derive(Debug, Clone)]
+pub struct ProgramSource {
+    name: String,
+    files: Vec<(String, GLShaderVariant)>,
+    blocks: Vec<(&'static str, GLuint)>,
+}
+
+impl ProgramSource {
+    /// Creates a source for the program with the given name, which it's labelled and logged with
+    pub fn new(name: &str) -> ProgramSource {
+        ProgramSource { name: name.to_string(), files: Vec::new(), blocks: Vec::new() }
+    }
+
+    /// Adds a shader file, with a path relative to the working directory
+    pub fn file(mut self, path: &str, variant: GLShaderVariant) -> ProgramSource {
+        self.files.push((path.to_string(), variant));
+        self
+    }
+
+    /// Binds the named uniform block to a binding point once the program is linked
+    pub fn block(mut self, name: &'static str, binding: GLuint) -> ProgramSource {
+        self.blocks.push((name, binding));
+        self
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str { &self.name }
+
+    /// Reads, compiles and links the files into a new program, and binds its uniform blocks.
+    ///
+    /// Uniform locations are cached when linking, so nothing looked up from a previous build of the program carries over.
+    pub fn build(&self) -> GLResult<GLShaderProgram> {
+        let mut builder = try!(GLShaderProgramBuilder::new());
+
+        for &(ref path, variant) in &self.files {
+            builder = try!(builder.attach_file(path, variant));
+        }
+
+        builder = try!(builder.link());
+
+        for &(name, binding) in &self.blocks {
+            builder = try!(builder.uniform_block(name, binding));
+        }
+
+        let program = builder.finish();
+
+        try!(program.label(&self.name));
+
+        Ok(program)
+    }
+}
+
+impl Display for ProgramSource {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        try!(write!(f, "{} (", self.name));
+
+        for (i, &(ref path, _)) in self.files.iter().enumerate() {
+            try!(write!(f, "{}{}", if i > 0 { ", " } else { "" }, path));
+        }
+
+        write!(f, ")")
+    }
+}
+
+/// Number of programs `Pipeline::reload_shaders` swapped in and kept the old version of
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReloadSummary {
+    pub reloaded: usize,
+    pub failed: usize,
+}
+
+impl Display for ReloadSummary {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} shader programs reloaded, {} failed", self.reloaded, self.failed)
+    }
+}
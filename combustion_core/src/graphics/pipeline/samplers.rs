@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+use ::backend::gl::bindings as glb;
+
+/// Shared handle to a sampler, which materials refer to instead of owning their own
+pub type SamplerHandle = Arc<GLSampler>;
+
+/// The samplers shared by every material and pass, so textures never have to be duplicated to be sampled differently
+pub struct SamplerSet {
+    linear_repeat: SamplerHandle,
+    nearest_clamp: SamplerHandle,
+    shadow_compare: SamplerHandle,
+}
+
+impl SamplerSet {
+    pub fn new() -> GLResult<SamplerSet> {
+        let mut linear_repeat = try!(GLSampler::new());
+
+        try!(linear_repeat.set_filtering(GLTextureFilter::Linear, Some(GLTextureFilter::Linear)));
+        try!(linear_repeat.set_wrap(GLTextureWrap::Repeat, GLTextureWrap::Repeat, GLTextureWrap::Repeat));
+
+        let max_anisotropy = try!(linear_repeat.get_max_anisotropy());
+        try!(linear_repeat.set_anisotropy(max_anisotropy));
+
+        let mut nearest_clamp = try!(GLSampler::new());
+
+        try!(nearest_clamp.set_filtering(GLTextureFilter::Nearest, None));
+        try!(nearest_clamp.set_wrap(GLTextureWrap::ClampToEdge, GLTextureWrap::ClampToEdge, GLTextureWrap::ClampToEdge));
+
+        let mut shadow_compare = try!(GLSampler::new());
+
+        try!(shadow_compare.set_filtering(GLTextureFilter::Linear, None));
+        try!(shadow_compare.set_wrap(GLTextureWrap::ClampToBorder, GLTextureWrap::ClampToBorder, GLTextureWrap::ClampToBorder));
+
+        // Anything outside of the shadow map is considered lit
+        try!(shadow_compare.set_border_color([1.0, 1.0, 1.0, 1.0]));
+
+        // Hardware depth comparison for sampler2DShadow
+        try!(shadow_compare.set_compare(Some(glb::LEQUAL)));
+
+        Ok(SamplerSet {
+            linear_repeat: Arc::new(linear_repeat),
+            nearest_clamp: Arc::new(nearest_clamp),
+            shadow_compare: Arc::new(shadow_compare),
+        })
+    }
+
+    /// Trilinear and anisotropic filtering with repeating coordinates, for material textures
+    #[inline(always)]
+    pub fn linear_repeat(&self) -> &SamplerHandle { &self.linear_repeat }
+
+    /// Unfiltered and clamped, for textures that hold data rather than images
+    #[inline(always)]
+    pub fn nearest_clamp(&self) -> &SamplerHandle { &self.nearest_clamp }
+
+    /// Filtered depth comparison against a white border, for shadow maps
+    #[inline(always)]
+    pub fn shadow_compare(&self) -> &SamplerHandle { &self.shadow_compare }
+}
@@ -1,5 +1,4 @@
 use std::mem;
-use std::ptr;
 
 use ::backend::gl::*;
 use ::backend::gl::types::*;
@@ -19,36 +18,17 @@ pub struct ScreenQuad {
 
 impl ScreenQuad {
     pub fn new() -> GLResult<ScreenQuad> {
+        let mut buffer = try!(GLBuffer::array_buffer());
 
-        let vao = try!(GLVertexArray::new());
+        try!(buffer.upload(&QUAD_DATA, GLBufferUsage::StaticDraw));
 
-        try!(vao.bind());
+        let stride = 5 * mem::size_of::<f32>();
 
-        let mut buffer = try!(GLBuffer::new(GLBufferTarget::ArrayBuffer));
-
-        try!(buffer.bind());
-
-        try!(buffer.buffer_slice(&QUAD_DATA, GLBufferUsage::StaticDraw));
-
-        let stride = 5 * mem::size_of::<f32>() as GLsizei;
-
-        unsafe {
-            glb::EnableVertexAttribArray(0);
-            glb::VertexAttribPointer(0, 3, glb::FLOAT, glb::FALSE, stride, ptr::null());
-        }
-
-        check_errors!();
-
-        unsafe {
-            let offset = ptr::null::<f32>().offset(3);
-
-            glb::EnableVertexAttribArray(1);
-            glb::VertexAttribPointer(1, 2, glb::FLOAT, glb::FALSE, stride, offset as *const _);
-        }
-
-        check_errors!();
-
-        try!(DEFAULT_VERTEXARRAY.bind());
+        let vao = GLVertexArrayBuilder::new()?
+            .buffer(&buffer)?
+            .attribute(0, 3, glb::FLOAT, false, stride, 0)?
+            .attribute(1, 2, glb::FLOAT, false, stride, 3 * mem::size_of::<f32>())?
+            .finish();
 
         Ok(ScreenQuad {
             vao: vao,
@@ -56,17 +36,23 @@ impl ScreenQuad {
         })
     }
 
-    pub fn draw(&mut self) -> GLResult<()> {
+    /// Clears the bound framebuffer's color and draws the quad over it with depth, stencil and culling disabled
+    pub fn draw(&self) -> GLResult<()> {
         unsafe {
             glb::Clear(glb::COLOR_BUFFER_BIT);
 
-            glb::Disable(glb::DEPTH_TEST);
-            glb::Disable(glb::STENCIL_TEST);
-            glb::Disable(glb::CULL_FACE);
+            GLStateCache::disable(glb::DEPTH_TEST);
+            GLStateCache::disable(glb::STENCIL_TEST);
+            GLStateCache::disable(glb::CULL_FACE);
         }
 
         check_errors!();
 
+        self.overlay()
+    }
+
+    /// Draws the quad without clearing or changing any state, for blending onto or depth testing against existing contents
+    pub fn overlay(&self) -> GLResult<()> {
         try!(self.vao.bind());
 
         check_errors!();
@@ -0,0 +1,116 @@
+use nalgebra::*;
+
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+use ::backend::gl::bindings as glb;
+
+pub const DEFAULT_SHADOW_RESOLUTION: usize = 2048;
+
+/// Depth-only stage for rendering shadow casters from a light's point of view.
+///
+/// Unlike the other stages, the shadow map has its own fixed resolution that is independent of the window size.
+pub struct ShadowStage {
+    resolution: usize,
+    framebuffer: GLFramebuffer,
+    depth: GLTexture,
+    shader: GLShaderProgram,
+}
+
+impl ShadowStage {
+    pub fn new(resolution: usize) -> GLResult<ShadowStage> {
+        let depth_vertex_shader = try!(GLShader::from_file("shaders/shadow_depth.vert", GLShaderVariant::VertexShader));
+        let depth_fragment_shader = try!(GLShader::from_file("shaders/shadow_depth.frag", GLShaderVariant::FragmentShader));
+
+        let shader = GLShaderProgramBuilder::new()?
+            .attach_shader(depth_vertex_shader)?
+            .attach_shader(depth_fragment_shader)?
+            .link()?
+            .finish();
+
+        let mut framebuffer = try!(GLFramebuffer::new());
+
+        try!(framebuffer.bind());
+
+        let mut depth: GLTexture = try!(GLTexture::new(GLTextureKind::Texture2D));
+
+        // Filtering and depth comparison come from the shadow sampler it's bound with, see `bind_shadow_map`
+        try!(depth.load_empty(resolution, resolution, glb::DEPTH_COMPONENT, glb::DEPTH_COMPONENT32F));
+
+        try!(framebuffer.texture(glb::DEPTH_ATTACHMENT, &depth));
+
+        unsafe {
+            // No color output at all
+            glb::DrawBuffer(glb::NONE);
+            glb::ReadBuffer(glb::NONE);
+        }
+
+        check_errors!();
+
+        if !framebuffer.is_complete()? {
+            error!("Incomplete framebuffer from ShadowStage creation");
+
+            return Err(GLError::IncompleteFramebuffer);
+        }
+
+        Ok(ShadowStage {
+            resolution: resolution,
+            framebuffer: framebuffer,
+            depth: depth,
+            shader: shader,
+        })
+    }
+
+    #[inline(always)]
+    pub fn resolution(&self) -> usize { self.resolution }
+
+    /// Changes the shadow map resolution. This is never done automatically.
+    pub fn set_resolution(&mut self, resolution: usize) -> GLResult<()> {
+        try!(self.depth.load_empty(resolution, resolution, glb::DEPTH_COMPONENT, glb::DEPTH_COMPONENT32F));
+
+        self.resolution = resolution;
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn depth_texture(&self) -> &GLTexture { &self.depth }
+
+    #[inline(always)]
+    pub fn shader(&self) -> &GLShaderProgram { &self.shader }
+
+    #[inline(always)]
+    pub fn framebuffer(&self) -> &GLFramebuffer { &self.framebuffer }
+
+    /// Binds the shadow map to the given texture unit and sets the `shadow_map` uniform of `shader`.
+    ///
+    /// `sampler` has to compare depth for `sampler2DShadow`, like `SamplerSet::shadow_compare`.
+    pub fn bind_shadow_map(&self, shader: &GLShaderProgram, unit: usize, sampler: &GLSampler) -> GLResult<()> {
+        try!(shader.get_uniform("shadow_map")?.int1(unit as GLint));
+
+        GLStateCache::active_texture(unit);
+
+        check_errors!();
+
+        try!(self.depth.bind());
+        try!(sampler.bind(unit));
+
+        Ok(())
+    }
+}
+
+/// Computes an orthographic view-projection matrix for a directional light,
+/// covering a cube of `extent` units around `center`.
+pub fn directional_light_matrix(direction: &Vector3<f32>, center: &Point3<f32>, extent: f32) -> Matrix4<f32> {
+    let direction = direction.normalize();
+
+    let eye = *center - direction * extent;
+
+    // Avoid a degenerate basis when the light points straight up or down
+    let up = if direction.y.abs() > 0.99 { Vector3::z() } else { Vector3::y() };
+
+    let view = Isometry3::look_at_rh(&eye, center, &up);
+
+    let projection = Orthographic3::new(-extent, extent, -extent, extent, 0.0, extent * 2.0);
+
+    projection.to_matrix() * view.to_homogeneous()
+}
@@ -0,0 +1,84 @@
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+use ::backend::gl::bindings as glb;
+use ::backend::gl::protocols::texture::{GLCompressedGenericFormats, GLCompressedSpecificFormats};
+
+use ::protocols::texture::protocol::DataType;
+use ::protocols::texture::data::format::Which;
+use ::protocols::texture::data::texture::{Texture, Cubemap};
+
+use super::reload::ProgramSource;
+
+/// Texture unit the skybox cubemap is bound to during the skybox pass
+pub const SKYBOX_UNIT: usize = 0;
+
+/// Shader files of the program used by `Pipeline::skybox_pass`
+pub fn sky_shader_source() -> ProgramSource {
+    ProgramSource::new("sky")
+        .file("shaders/sky.vert", GLShaderVariant::VertexShader)
+        .file("shaders/sky.frag", GLShaderVariant::FragmentShader)
+}
+
+/// Uploads a single face of a cubemap to the currently bound cubemap texture
+fn load_face(target: GLenum, face: &Texture) -> GLResult<()> {
+    let (width, height, _) = face.dimensions.to_tuple();
+
+    let data = face.data.as_slice();
+
+    if face.is_compressed() {
+        unsafe {
+            glb::CompressedTexImage2D(target, 0, face.format.specific(),
+                                      width as GLsizei, height as GLsizei, 0,
+                                      data.len() as GLsizei, data.as_ptr() as *const _);
+        }
+    } else {
+        let data_type = match face.format.which {
+            Which::None(ref uncompressed) => match uncompressed.data_type {
+                DataType::UnsignedByte | DataType::Unspecified => glb::UNSIGNED_BYTE,
+                DataType::Float => glb::FLOAT,
+                _ => return Err(GLError::InvalidValue),
+            },
+            _ => unreachable!(),
+        };
+
+        unsafe {
+            glb::TexImage2D(target, 0, face.format.specific() as GLint,
+                            width as GLsizei, height as GLsizei, 0,
+                            face.format.to_generic().generic(), data_type, data.as_ptr() as *const _);
+        }
+    }
+
+    check_errors!();
+
+    Ok(())
+}
+
+/// Creates a cubemap texture from the six faces of a texture protocol cubemap, ready to be given to `Pipeline::set_skybox`
+///
+/// Uncompressed faces must be stored as unsigned bytes or floats.
+pub fn load_cubemap(cubemap: &Cubemap) -> GLResult<GLTexture> {
+    let mut texture: GLTexture = try!(GLTexture::new(GLTextureKind::Cubemap));
+
+    try!(texture.bind());
+
+    // Same order as the GL_TEXTURE_CUBE_MAP_* face targets
+    let faces = [&cubemap.right, &cubemap.left, &cubemap.top, &cubemap.bottom, &cubemap.back, &cubemap.front];
+
+    for (i, face) in faces.iter().enumerate() {
+        try!(load_face(glb::TEXTURE_CUBE_MAP_POSITIVE_X + i as GLenum, face));
+    }
+
+    try!(texture.set_filter(GLTextureFilter::Linear, Some(GLTextureFilter::Linear)));
+    try!(texture.set_wrap_axes(GLTextureWrap::ClampToEdge, GLTextureWrap::ClampToEdge, GLTextureWrap::ClampToEdge));
+
+    unsafe {
+        glb::GenerateMipmap(glb::TEXTURE_CUBE_MAP);
+
+        // Avoids visible edges between faces
+        GLStateCache::enable(glb::TEXTURE_CUBE_MAP_SEAMLESS);
+    }
+
+    check_errors!();
+
+    Ok(texture)
+}
@@ -0,0 +1,128 @@
+use nalgebra::*;
+
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+use ::backend::gl::bindings as glb;
+
+pub const SSAO_STAGE: &'static str = "ssao";
+pub const SSAO_BLUR_STAGE: &'static str = "ssao_blur";
+
+/// Must match `MAX_KERNEL_SIZE` in `shaders/ssao.frag`
+pub const MAX_KERNEL_SIZE: usize = 64;
+
+/// Width and height of the rotation noise texture, which is tiled over the screen
+pub const NOISE_SIZE: usize = 4;
+
+/// Single-channel occlusion output
+pub const SSAO_STAGE_COMPONENTS: [(GLenum, GLenum); 1] = [
+    (glb::RED, glb::R16F)
+];
+
+pub const SSAO_BLUR_STAGE_NAMES: [&'static str; 1] = [
+    "ssao_input"
+];
+
+/// Adjustable SSAO parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SsaoSettings {
+    /// When disabled, the SSAO passes are skipped entirely and the lighting pass ignores their output
+    pub enabled: bool,
+    /// Number of hemisphere samples per pixel, up to `MAX_KERNEL_SIZE`
+    pub kernel_size: usize,
+    /// Sampling radius in world units
+    pub radius: f32,
+    /// Depth bias to avoid self-occlusion on flat surfaces
+    pub bias: f32,
+}
+
+impl Default for SsaoSettings {
+    fn default() -> SsaoSettings {
+        SsaoSettings {
+            enabled: true,
+            kernel_size: 32,
+            radius: 0.5,
+            bias: 0.025,
+        }
+    }
+}
+
+/// Tiny xorshift generator so the kernel is the same on every run
+struct XorShift(u32);
+
+impl XorShift {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+
+        (self.0 as f64 / u32::max_value() as f64) as f32
+    }
+}
+
+/// Sample kernel and rotation noise shared by every SSAO pass
+pub struct SsaoKernel {
+    samples: Vec<Vector3<f32>>,
+    noise: GLTexture,
+}
+
+impl SsaoKernel {
+    pub fn new() -> GLResult<SsaoKernel> {
+        let mut rng = XorShift(0x9E3779B9);
+
+        let samples = (0..MAX_KERNEL_SIZE).map(|i| {
+            let sample = Vector3::new(rng.next() * 2.0 - 1.0, rng.next() * 2.0 - 1.0, rng.next()).normalize();
+
+            // Distribute more samples closer to the origin
+            let scale = i as f32 / MAX_KERNEL_SIZE as f32;
+            let scale = 0.1 + 0.9 * scale * scale;
+
+            sample * rng.next() * scale
+        }).collect();
+
+        // Random rotations around the Z axis in tangent space
+        let noise_data: Vec<f32> = (0..NOISE_SIZE * NOISE_SIZE).flat_map(|_| {
+            vec![rng.next() * 2.0 - 1.0, rng.next() * 2.0 - 1.0]
+        }).collect();
+
+        let mut noise: GLTexture = try!(GLTexture::new(GLTextureKind::Texture2D));
+
+        try!(noise.bind());
+
+        unsafe {
+            glb::TexImage2D(glb::TEXTURE_2D, 0, glb::RG16F as GLint,
+                            NOISE_SIZE as GLsizei, NOISE_SIZE as GLsizei, 0,
+                            glb::RG, glb::FLOAT, noise_data.as_ptr() as *const _);
+        }
+
+        check_errors!();
+
+        try!(noise.set_filter(GLTextureFilter::Nearest, None));
+        try!(noise.set_wrap_axes(GLTextureWrap::Repeat, GLTextureWrap::Repeat, GLTextureWrap::Repeat));
+
+        Ok(SsaoKernel { samples: samples, noise: noise })
+    }
+
+    /// Uploads the kernel, noise texture and settings to the SSAO shader.
+    ///
+    /// The noise texture is bound to the given texture unit.
+    pub fn bind(&self, shader: &GLShaderProgram, settings: &SsaoSettings, unit: usize) -> GLResult<()> {
+        let kernel_size = settings.kernel_size.min(MAX_KERNEL_SIZE);
+
+        try!(shader.get_uniform("samples")?.vec3fv(&self.samples[..kernel_size]));
+
+        try!(shader.get_uniform("kernel_size")?.int1(kernel_size as GLint));
+        try!(shader.get_uniform("radius")?.float1(settings.radius));
+        try!(shader.get_uniform("bias")?.float1(settings.bias));
+        try!(shader.get_uniform("noise_size")?.float1(NOISE_SIZE as f32));
+
+        try!(shader.get_uniform("noise")?.int1(unit as GLint));
+
+        GLStateCache::active_texture(unit);
+
+        check_errors!();
+
+        try!(self.noise.bind());
+
+        Ok(())
+    }
+}
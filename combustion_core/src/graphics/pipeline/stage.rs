@@ -1,7 +1,74 @@
 use ::backend::gl::*;
 use ::backend::gl::types::*;
+use ::backend::gl::bindings as glb;
 
-use super::gbuffer::Gbuffer;
+use super::gbuffer::{Gbuffer, DepthStencilMode, COLOR_ATTACHMENTS};
+
+/// Values a stage's attachments are cleared to. Attachments with a value of `None` are left untouched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClearValues {
+    /// Applied to every floating point or normalized color attachment
+    pub color: Option<[f32; 4]>,
+    /// Applied to every unsigned integer color attachment, which can't be cleared with float values
+    pub integer: Option<[u32; 4]>,
+    pub depth: Option<f32>,
+    pub stencil: Option<GLint>,
+}
+
+/// Clears color attachments to zero, so G-buffer channels such as normals and positions are zero wherever nothing was drawn
+impl Default for ClearValues {
+    fn default() -> ClearValues {
+        ClearValues { color: Some([0.0; 4]), integer: Some([0; 4]), depth: None, stencil: None }
+    }
+}
+
+impl ClearValues {
+    pub fn color(r: f32, g: f32, b: f32, a: f32) -> ClearValues {
+        ClearValues { color: Some([r, g, b, a]), ..ClearValues::default() }
+    }
+
+    pub fn with_depth(self, depth: f32) -> ClearValues {
+        ClearValues { depth: Some(depth), ..self }
+    }
+
+    pub fn with_stencil(self, stencil: GLint) -> ClearValues {
+        ClearValues { stencil: Some(stencil), ..self }
+    }
+
+    /// Clears every channel of unsigned integer attachments to `value`
+    pub fn with_integer(self, value: u32) -> ClearValues {
+        ClearValues { integer: Some([value; 4]), ..self }
+    }
+}
+
+/// Where `Stage::blit_to` copies to
+#[derive(Clone, Copy)]
+pub enum BlitTarget<'a> {
+    /// Another stage with attachments, where each color attachment receives the source attachment with the same index
+    Stage(&'a Stage),
+    /// The back buffer of the window, with its width and height, which receives the first color attachment
+    Default(usize, usize),
+}
+
+/// Rectangle of a framebuffer in pixels from the bottom left
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlitRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl BlitRect {
+    pub fn new(width: usize, height: usize) -> BlitRect {
+        BlitRect { x: 0, y: 0, width: width, height: height }
+    }
+
+    /// Corners as given to `glBlitFramebuffer`
+    fn corners(&self) -> (GLint, GLint, GLint, GLint) {
+        (self.x as GLint, self.y as GLint, (self.x + self.width) as GLint, (self.y + self.height) as GLint)
+    }
+}
 
 pub struct Stage {
     gbuffer: Option<Gbuffer>,
@@ -9,12 +76,33 @@ pub struct Stage {
 }
 
 impl Stage {
-    pub fn new(width: usize, height: usize, components: Option<&[(GLenum, GLenum)]>) -> GLResult<Stage> {
+    /// Creates a new stage with the given attachments, or one that renders to the default framebuffer if there are none.
+    ///
+    /// If `samples` is greater than 1 the attachments are multisampled, and must be resolved with `resolve_to` before they can be sampled.
+    /// `depth_stencil` is ignored for stages rendering to the default framebuffer.
+    ///
+    /// Fails with `InvalidValue` if the size or number of attachments exceeds what the driver supports.
+    pub fn new(width: usize, height: usize, components: Option<&[(GLenum, GLenum)]>, samples: u32,
+               depth_stencil: DepthStencilMode) -> GLResult<Stage> {
         if let Some(components) = components {
+            let capabilities = try!(GLCapabilities::current());
+
+            if components.len() > capabilities.max_color_attachments {
+                error!("Stage has {} attachments, but only {} are supported", components.len(), capabilities.max_color_attachments);
+
+                return Err(GLError::InvalidValue);
+            }
+
+            if width > capabilities.max_texture_size || height > capabilities.max_texture_size {
+                error!("Stage size {}x{} exceeds the maximum texture size of {}", width, height, capabilities.max_texture_size);
+
+                return Err(GLError::InvalidValue);
+            }
+
             let mut framebuffer = try!(GLFramebuffer::new());
 
             Ok(Stage {
-                gbuffer: Some(Gbuffer::new(width, height, &mut framebuffer, components)?),
+                gbuffer: Some(Gbuffer::new(width, height, &mut framebuffer, components, samples as usize, depth_stencil)?),
                 framebuffer: framebuffer
             })
         } else {
@@ -28,6 +116,137 @@ impl Stage {
     #[inline(always)]
     pub fn gbuffer(&self) -> Option<&Gbuffer> { self.gbuffer.as_ref() }
 
+    /// How the stage stores depth and stencil, which is `DepthStencilMode::None` for stages without attachments
+    #[inline]
+    pub fn depth_stencil_mode(&self) -> DepthStencilMode {
+        self.gbuffer.as_ref().map_or(DepthStencilMode::None, |gbuffer| gbuffer.depth_stencil_mode())
+    }
+
+    /// Number of samples per pixel of the attachments, or 1 for stages without any
+    #[inline]
+    pub fn samples(&self) -> u32 {
+        self.gbuffer.as_ref().map_or(1, |gbuffer| gbuffer.samples() as u32)
+    }
+
+    /// Attaches the depth and stencil attachments of `other` to this stage in place of its own,
+    /// so anything rendered here is depth tested against what was rendered into `other`.
+    ///
+    /// Both stages must have attachments of the same size and sample count, and this stage must use the same
+    /// `DepthStencilMode` as `other`, so none of its own attachments are left over. Resizing keeps the attachments shared,
+    /// since their storage is reallocated in place.
+    pub fn share_depth(&mut self, other: &Stage) -> GLResult<()> {
+        let other_gbuffer = match (self.gbuffer.as_ref(), other.gbuffer.as_ref()) {
+            (Some(gbuffer), Some(other)) if gbuffer.depth_stencil_mode() == other.depth_stencil_mode() &&
+                                            other.depth_stencil_mode() != DepthStencilMode::None => other,
+            _ => return Err(GLError::InvalidOperation),
+        };
+
+        try!(other_gbuffer.attach_depth_stencil(&mut self.framebuffer));
+
+        if !self.framebuffer.is_complete()? {
+            error!("Incomplete framebuffer after sharing depth buffer");
+
+            return Err(GLError::IncompleteFramebuffer);
+        }
+
+        Ok(())
+    }
+
+    /// Copies every color attachment, along with depth and stencil, into the matching attachment of `target`,
+    /// resolving multisampled attachments in the process.
+    ///
+    /// Both stages must have attachments with matching dimensions.
+    pub fn resolve_to(&self, target: &Stage) -> GLResult<()> {
+        let (source_gbuffer, target_gbuffer) = match (self.gbuffer.as_ref(), target.gbuffer.as_ref()) {
+            (Some(source), Some(target)) => (source, target),
+            _ => return Err(GLError::InvalidOperation),
+        };
+
+        // Multisampled attachments can't be scaled while resolving
+        if source_gbuffer.dimensions != target_gbuffer.dimensions {
+            return Err(GLError::InvalidValue);
+        }
+
+        self.blit_to(BlitTarget::Stage(target), glb::COLOR_BUFFER_BIT | glb::DEPTH_BUFFER_BIT | glb::STENCIL_BUFFER_BIT, glb::NEAREST)
+    }
+
+    /// Copies the buffers selected by `mask` into the whole of `target`, scaling with `filter` if the sizes differ.
+    ///
+    /// Depth and stencil can only be copied with `GL_NEAREST`, so with `GL_LINEAR` they're copied separately from color.
+    /// The stage must have attachments. Leaves the default framebuffer bound.
+    pub fn blit_to(&self, target: BlitTarget, mask: GLbitfield, filter: GLenum) -> GLResult<()> {
+        let (width, height) = try!(self.gbuffer.as_ref().ok_or(GLError::InvalidOperation)).dimensions;
+
+        let (target_width, target_height) = match target {
+            BlitTarget::Stage(stage) => try!(stage.gbuffer.as_ref().ok_or(GLError::InvalidOperation)).dimensions,
+            BlitTarget::Default(width, height) => (width, height),
+        };
+
+        self.blit_rect_to(BlitRect::new(width, height), target, BlitRect::new(target_width, target_height), mask, filter)
+    }
+
+    /// Same as `blit_to`, but copies between the given rectangles of the source and target
+    pub fn blit_rect_to(&self, source: BlitRect, target: BlitTarget, dest: BlitRect, mask: GLbitfield, filter: GLenum) -> GLResult<()> {
+        let source_gbuffer = try!(self.gbuffer.as_ref().ok_or(GLError::InvalidOperation));
+
+        let all = glb::COLOR_BUFFER_BIT | glb::DEPTH_BUFFER_BIT | glb::STENCIL_BUFFER_BIT;
+
+        if mask == 0 || mask & !all != 0 || (filter != glb::NEAREST && filter != glb::LINEAR) {
+            return Err(GLError::InvalidValue);
+        }
+
+        let (target_framebuffer, color_pairs) = match target {
+            BlitTarget::Stage(stage) => {
+                let target_gbuffer = try!(stage.gbuffer.as_ref().ok_or(GLError::InvalidOperation));
+
+                (stage.framebuffer.raw(), source_gbuffer.buffers.len().min(target_gbuffer.buffers.len()))
+            }
+            BlitTarget::Default(..) => (0, 1),
+        };
+
+        let (sx0, sy0, sx1, sy1) = source.corners();
+        let (dx0, dy0, dx1, dy1) = dest.corners();
+
+        let depth_stencil = mask & (glb::DEPTH_BUFFER_BIT | glb::STENCIL_BUFFER_BIT);
+
+        GLStateCache::bind_framebuffer(glb::READ_FRAMEBUFFER, self.framebuffer.raw());
+        GLStateCache::bind_framebuffer(glb::DRAW_FRAMEBUFFER, target_framebuffer);
+
+        unsafe {
+            if mask & glb::COLOR_BUFFER_BIT != 0 {
+                // Copied one attachment at a time, since every read buffer would otherwise go to every draw buffer
+                for index in 0..color_pairs {
+                    glb::ReadBuffer(COLOR_ATTACHMENTS[index]);
+
+                    if target_framebuffer == 0 {
+                        glb::DrawBuffer(glb::BACK);
+                    } else {
+                        glb::DrawBuffers(1, &COLOR_ATTACHMENTS[index] as *const _);
+                    }
+
+                    glb::BlitFramebuffer(sx0, sy0, sx1, sy1, dx0, dy0, dx1, dy1, glb::COLOR_BUFFER_BIT, filter);
+                }
+
+                // Restore the target's draw buffers
+                if let BlitTarget::Stage(stage) = target {
+                    let count = stage.gbuffer.as_ref().map_or(0, |gbuffer| gbuffer.buffers.len());
+
+                    glb::DrawBuffers(count as GLsizei, COLOR_ATTACHMENTS.as_ptr());
+                }
+            }
+
+            if depth_stencil != 0 {
+                glb::BlitFramebuffer(sx0, sy0, sx1, sy1, dx0, dy0, dx1, dy1, depth_stencil, glb::NEAREST);
+            }
+        }
+
+        GLStateCache::bind_framebuffer(glb::FRAMEBUFFER, 0);
+
+        check_errors!();
+
+        Ok(())
+    }
+
     pub fn set_filter(&mut self, filter: GLTextureFilter) -> GLResult<()> {
         if let Some(mut gbuffer) = self.gbuffer.as_mut() {
             try!(gbuffer.set_filter(filter));
@@ -44,6 +263,21 @@ impl Stage {
         Ok(())
     }
 
+    /// Names the framebuffer and attachments in debuggers, where each attachment is suffixed with its index.
+    ///
+    /// Stages rendering to the default framebuffer have nothing to name.
+    pub fn label(&self, name: &str) -> GLResult<()> {
+        if let Some(gbuffer) = self.gbuffer.as_ref() {
+            try!(self.framebuffer.label(name));
+
+            for (index, buffer) in gbuffer.buffers.iter() {
+                try!(buffer.label(&format!("{} {}", name, index)));
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn resize(&mut self, width: usize, height: usize) -> GLResult<()> {
         if let Some(mut gbuffer) = self.gbuffer.as_mut() {
             try!(gbuffer.resize(width, height));
@@ -55,6 +289,113 @@ impl Stage {
     #[inline(always)]
     pub fn framebuffer(&self) -> &GLFramebuffer { &self.framebuffer }
 
+    /// The G-buffer to read from, which must exist and not be multisampled, along with the format of the component
+    fn readable(&self, component: usize) -> GLResult<(&Gbuffer, GLenum)> {
+        let gbuffer = try!(self.gbuffer.as_ref().ok_or(GLError::InvalidOperation));
+
+        // Multisampled attachments have to be resolved into another stage before they can be read
+        if gbuffer.is_multisampled() {
+            return Err(GLError::InvalidOperation);
+        }
+
+        let (format, _) = try!(gbuffer.component_format(component).ok_or(GLError::InvalidValue));
+
+        Ok((gbuffer, format))
+    }
+
+    /// Reads back a component of the stage as unsigned bytes, in tightly packed rows with the bottom row first.
+    ///
+    /// Each pixel has as many channels as the component's format, see `Gbuffer::component_format`.
+    /// Float attachments are clamped to `[0, 1]` by the conversion, so use `read_pixels_f32` for those.
+    pub fn read_pixels(&self, component: usize) -> GLResult<Vec<u8>> {
+        let (gbuffer, format) = try!(self.readable(component));
+        let (width, height) = gbuffer.dimensions;
+
+        self.framebuffer.read_pixels(component, width, height, format, glb::UNSIGNED_BYTE)
+    }
+
+    /// Starts reading back a component like `read_pixels`, without stalling the pipeline until the GPU has rendered it.
+    ///
+    /// Poll the returned readback once per frame, as the pixels are usually ready within a frame or two.
+    pub fn read_pixels_async(&self, component: usize) -> GLResult<GLPendingReadback> {
+        let (gbuffer, format) = try!(self.readable(component));
+        let (width, height) = gbuffer.dimensions;
+
+        self.framebuffer.read_pixels_async(component, width, height, format, glb::UNSIGNED_BYTE)
+    }
+
+    /// Same as `read_pixels`, but returns floats, for 16F and 32F attachments
+    pub fn read_pixels_f32(&self, component: usize) -> GLResult<Vec<f32>> {
+        let (gbuffer, format) = try!(self.readable(component));
+        let (width, height) = gbuffer.dimensions;
+
+        self.framebuffer.read_pixels_f32(component, width, height, format)
+    }
+
+    /// Reads a single pixel of an unsigned integer component with a `RED_INTEGER` format, such as object IDs.
+    ///
+    /// `x` and `y` are in pixels from the bottom left, and must be within the stage.
+    pub fn read_pixel_u32(&self, component: usize, x: usize, y: usize) -> GLResult<u32> {
+        let (gbuffer, format) = try!(self.readable(component));
+        let (width, height) = gbuffer.dimensions;
+
+        if format != glb::RED_INTEGER || !gbuffer.is_unsigned_integer(component) {
+            return Err(GLError::InvalidOperation);
+        }
+
+        if x >= width || y >= height {
+            return Err(GLError::InvalidValue);
+        }
+
+        let mut value: GLuint = 0;
+
+        unsafe {
+            GLStateCache::bind_framebuffer(glb::READ_FRAMEBUFFER, self.framebuffer.raw());
+            glb::ReadBuffer(COLOR_ATTACHMENTS[component]);
+            glb::PixelStorei(glb::PACK_ALIGNMENT, 1);
+
+            glb::ReadPixels(x as GLint, y as GLint, 1, 1, glb::RED_INTEGER, glb::UNSIGNED_INT, &mut value as *mut GLuint as *mut _);
+        }
+
+        check_errors!();
+
+        Ok(value)
+    }
+
+    /// Clears each attachment of the stage to its own value with `glClearBuffer*`, leaving the global clear color alone.
+    ///
+    /// The stage must already be bound. Like any clear, it respects the current color and depth write masks.
+    pub fn clear(&self, values: &ClearValues) -> GLResult<()> {
+        // The default framebuffer only has the single back buffer
+        let color_attachments = self.gbuffer.as_ref().map_or(1, |gbuffer| gbuffer.buffers.len());
+
+        unsafe {
+            for i in 0..color_attachments {
+                let unsigned = self.gbuffer.as_ref().map_or(false, |gbuffer| gbuffer.is_unsigned_integer(i));
+
+                // Clearing an integer attachment with the wrong type of value leaves it undefined
+                if unsigned {
+                    if let Some(ref integer) = values.integer {
+                        glb::ClearBufferuiv(glb::COLOR, i as GLint, integer.as_ptr());
+                    }
+                } else if let Some(ref color) = values.color {
+                    glb::ClearBufferfv(glb::COLOR, i as GLint, color.as_ptr());
+                }
+            }
+
+            match (values.depth, values.stencil) {
+                (Some(depth), Some(stencil)) => glb::ClearBufferfi(glb::DEPTH_STENCIL, 0, depth, stencil),
+                (Some(depth), None) => glb::ClearBufferfv(glb::DEPTH, 0, &depth),
+                (None, Some(stencil)) => glb::ClearBufferiv(glb::STENCIL, 0, &stencil),
+                (None, None) => {}
+            }
+        }
+
+        check_errors!();
+
+        Ok(())
+    }
+
     pub fn bind(&self) -> GLResult<()> {
         try!(self.framebuffer.bind());
 
@@ -0,0 +1,213 @@
+//! GPU timings of each pipeline pass, measured with `GL_TIME_ELAPSED` queries
+
+use std::fmt;
+
+use ::backend::gl::*;
+
+/// Every pass the pipeline measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimedPass {
+    Shadow = 0,
+    Geometry = 1,
+    Ssao = 2,
+    Lighting = 3,
+    Skybox = 4,
+    Forward = 5,
+    Transparent = 6,
+    Bloom = 7,
+    Final = 8,
+}
+
+pub const TIMED_PASSES: usize = 9;
+
+/// Every pass in index order
+const PASSES: [TimedPass; TIMED_PASSES] = [
+    TimedPass::Shadow,
+    TimedPass::Geometry,
+    TimedPass::Ssao,
+    TimedPass::Lighting,
+    TimedPass::Skybox,
+    TimedPass::Forward,
+    TimedPass::Transparent,
+    TimedPass::Bloom,
+    TimedPass::Final,
+];
+
+/// GPU time taken by each pass in milliseconds. Passes that didn't run, or drivers without timer queries, report zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StageTimings {
+    pub shadow: f32,
+    pub geometry: f32,
+    pub ssao: f32,
+    pub lighting: f32,
+    pub skybox: f32,
+    pub forward: f32,
+    pub transparent: f32,
+    pub bloom: f32,
+    /// Tonemapping, debug views and FXAA
+    pub final_pass: f32,
+}
+
+impl StageTimings {
+    pub fn get_mut(&mut self, pass: TimedPass) -> &mut f32 {
+        match pass {
+            TimedPass::Shadow => &mut self.shadow,
+            TimedPass::Geometry => &mut self.geometry,
+            TimedPass::Ssao => &mut self.ssao,
+            TimedPass::Lighting => &mut self.lighting,
+            TimedPass::Skybox => &mut self.skybox,
+            TimedPass::Forward => &mut self.forward,
+            TimedPass::Transparent => &mut self.transparent,
+            TimedPass::Bloom => &mut self.bloom,
+            TimedPass::Final => &mut self.final_pass,
+        }
+    }
+
+    pub fn total(&self) -> f32 {
+        self.shadow + self.geometry + self.ssao + self.lighting + self.skybox + self.forward + self.transparent + self.bloom + self.final_pass
+    }
+}
+
+impl fmt::Display for StageTimings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "shadow {:.2}ms, geometry {:.2}ms, ssao {:.2}ms, lighting {:.2}ms, skybox {:.2}ms, forward {:.2}ms, \
+                   transparent {:.2}ms, bloom {:.2}ms, final {:.2}ms, total {:.2}ms",
+               self.shadow, self.geometry, self.ssao, self.lighting, self.skybox, self.forward,
+               self.transparent, self.bloom, self.final_pass, self.total())
+    }
+}
+
+/// One set of queries for every pass, along with which of them were issued this frame
+struct QuerySet {
+    queries: Vec<GLQuery>,
+    issued: [bool; TIMED_PASSES],
+}
+
+/// Double-buffered timer queries, so the results of the previous frame are read while the current one is being recorded.
+///
+/// If timer queries aren't supported, or any query fails, the timer disables itself and every timing stays at zero.
+pub struct GpuTimer {
+    sets: Vec<QuerySet>,
+    current: usize,
+    active: bool,
+    timings: StageTimings,
+}
+
+impl GpuTimer {
+    pub fn new() -> GpuTimer {
+        let sets = if GLQuery::timer_supported() {
+            GpuTimer::create_sets().unwrap_or_else(|err| {
+                warn!("Could not create timer queries, GPU timings are disabled: {}", err);
+                Vec::new()
+            })
+        } else {
+            info!("Timer queries are not supported, GPU timings are disabled");
+            Vec::new()
+        };
+
+        GpuTimer {
+            sets: sets,
+            current: 0,
+            active: false,
+            timings: StageTimings::default(),
+        }
+    }
+
+    fn create_sets() -> GLResult<Vec<QuerySet>> {
+        let mut sets = Vec::with_capacity(2);
+
+        for _ in 0..2 {
+            let mut queries = Vec::with_capacity(TIMED_PASSES);
+
+            for _ in 0..TIMED_PASSES {
+                queries.push(try!(GLQuery::new()));
+            }
+
+            sets.push(QuerySet { queries: queries, issued: [false; TIMED_PASSES] });
+        }
+
+        Ok(sets)
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool { !self.sets.is_empty() }
+
+    #[inline]
+    pub fn timings(&self) -> &StageTimings { &self.timings }
+
+    fn disable(&mut self, err: GLError) {
+        warn!("Timer query failed, GPU timings are disabled: {}", err);
+
+        self.sets.clear();
+        self.active = false;
+        self.timings = StageTimings::default();
+    }
+
+    /// Starts timing the given pass, ending any pass that was left running by an early return
+    pub fn begin(&mut self, pass: TimedPass) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        self.end();
+
+        let result = {
+            let set = &mut self.sets[self.current];
+
+            set.issued[pass as usize] = true;
+            set.queries[pass as usize].begin_timer()
+        };
+
+        match result {
+            Ok(_) => self.active = true,
+            Err(err) => self.disable(err),
+        }
+    }
+
+    pub fn end(&mut self) {
+        if self.active {
+            self.active = false;
+
+            if let Err(err) = GLQuery::end_timer() {
+                self.disable(err);
+            }
+        }
+    }
+
+    /// Finishes the current frame, collecting the results of the previous one if they're available without stalling
+    pub fn next_frame(&mut self) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        self.end();
+
+        self.current = (self.current + 1) % self.sets.len();
+
+        match GpuTimer::collect(&self.sets[self.current]) {
+            Ok(Some(timings)) => self.timings = timings,
+            Ok(None) => {}
+            Err(err) => return self.disable(err),
+        }
+
+        self.sets[self.current].issued = [false; TIMED_PASSES];
+    }
+
+    fn collect(set: &QuerySet) -> GLResult<Option<StageTimings>> {
+        let mut timings = StageTimings::default();
+
+        for (index, query) in set.queries.iter().enumerate() {
+            if !set.issued[index] {
+                continue;
+            }
+
+            if !try!(query.is_available()) {
+                return Ok(None);
+            }
+
+            *timings.get_mut(PASSES[index]) = try!(query.result()) as f32 / 1_000_000.0;
+        }
+
+        Ok(Some(timings))
+    }
+}
@@ -0,0 +1,138 @@
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+use ::backend::gl::bindings as glb;
+
+pub const TONEMAP_STAGE: &'static str = "tonemap";
+
+/// Tonemapped, gamma-corrected output, ready for FXAA
+pub const TONEMAP_STAGE_COMPONENTS: [(GLenum, GLenum); 1] = [
+    (glb::RGBA, glb::RGBA8)
+];
+
+pub const TONEMAP_STAGE_NAMES: [&'static str; 1] = [
+    "hdr"
+];
+
+/// Resolution of the luminance target used for automatic exposure. Its last mip level is the average of the whole screen.
+pub const LUMINANCE_RESOLUTION: usize = 256;
+
+/// Named texture unit the luminance target is bound to during the tonemapping pass
+pub const LUMINANCE_BINDING: &'static str = "luminance";
+
+/// Must match the `TONEMAP_*` defines in `shaders/tonemap.frag`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum Tonemapper {
+    Reinhard = 0,
+    Aces = 1,
+}
+
+/// How the exposure for the tonemapping pass is determined
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Exposure {
+    /// Fixed exposure multiplier
+    Manual(f32),
+    /// Exposure computed from the average scene luminance, such that it maps to the given middle-grey key value
+    Auto { key: f32 },
+}
+
+/// Adjustable tonemapping parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TonemapSettings {
+    pub tonemapper: Tonemapper,
+    pub exposure: Exposure,
+    /// Gamma used for the one and only conversion to display space
+    pub gamma: f32,
+    /// Skips tonemapping and gamma correction entirely, to inspect the raw HDR values
+    pub raw: bool,
+}
+
+impl Default for TonemapSettings {
+    fn default() -> TonemapSettings {
+        TonemapSettings {
+            tonemapper: Tonemapper::Aces,
+            exposure: Exposure::Manual(1.0),
+            gamma: 2.2,
+            raw: false,
+        }
+    }
+}
+
+/// Small fixed-size target the log-luminance of the lighting output is rendered into for automatic exposure.
+///
+/// After rendering, mipmaps are generated so the smallest level holds the average log-luminance of the screen.
+pub struct LuminanceTarget {
+    framebuffer: GLFramebuffer,
+    texture: GLTexture,
+    shader: GLShaderProgram,
+}
+
+impl LuminanceTarget {
+    pub fn new() -> GLResult<LuminanceTarget> {
+        let vertex_shader = try!(GLShader::from_file("shaders/screen.vert", GLShaderVariant::VertexShader));
+        let fragment_shader = try!(GLShader::from_file("shaders/luminance.frag", GLShaderVariant::FragmentShader));
+
+        let shader = GLShaderProgramBuilder::new()?
+            .attach_shader(vertex_shader)?
+            .attach_shader(fragment_shader)?
+            .link()?
+            .finish();
+
+        let mut framebuffer = try!(GLFramebuffer::new());
+
+        try!(framebuffer.bind());
+
+        let mut texture: GLTexture = try!(GLTexture::new(GLTextureKind::Texture2D));
+
+        try!(texture.load_empty(LUMINANCE_RESOLUTION, LUMINANCE_RESOLUTION, glb::RED, glb::R16F));
+        try!(texture.set_filter(GLTextureFilter::Linear, Some(GLTextureFilter::Linear)));
+        try!(texture.set_wrap_axes(GLTextureWrap::ClampToEdge, GLTextureWrap::ClampToEdge, GLTextureWrap::ClampToEdge));
+
+        try!(framebuffer.texture(glb::COLOR_ATTACHMENT0, &texture));
+        try!(texture.generate_mipmaps());
+
+        if !framebuffer.is_complete()? {
+            error!("Incomplete framebuffer from LuminanceTarget creation");
+
+            return Err(GLError::IncompleteFramebuffer);
+        }
+
+        Ok(LuminanceTarget {
+            framebuffer: framebuffer,
+            texture: texture,
+            shader: shader,
+        })
+    }
+
+    #[inline(always)]
+    pub fn shader(&self) -> &GLShaderProgram { &self.shader }
+
+    #[inline(always)]
+    pub fn framebuffer(&self) -> &GLFramebuffer { &self.framebuffer }
+
+    /// Regenerates the mip chain after the luminance has been rendered
+    pub fn generate_mipmaps(&self) -> GLResult<()> {
+        try!(self.texture.bind());
+
+        unsafe {
+            glb::GenerateMipmap(glb::TEXTURE_2D);
+        }
+
+        check_errors!();
+
+        Ok(())
+    }
+
+    /// Binds the luminance target to the given texture unit and sets the `luminance_map` uniform of `shader`
+    pub fn bind_luminance(&self, shader: &GLShaderProgram, unit: usize) -> GLResult<()> {
+        try!(shader.get_uniform("luminance_map")?.int1(unit as GLint));
+
+        GLStateCache::active_texture(unit);
+
+        check_errors!();
+
+        try!(self.texture.bind());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use std::f32::consts::PI;
+use std::mem;
+use std::ptr;
+
+use ::backend::gl::*;
+use ::backend::gl::types::*;
+use ::backend::gl::bindings as glb;
+
+use super::blocks::{CAMERA_BLOCK, CAMERA_BINDING, LIGHTS_BLOCK, LIGHTS_BINDING};
+
+/// Horizontal and vertical subdivisions of the light volume sphere
+pub const SPHERE_SEGMENTS: usize = 16;
+pub const SPHERE_RINGS: usize = 12;
+
+/// The tessellated sphere lies partially inside the unit sphere, so it is enlarged enough to fully contain it
+pub const SPHERE_SCALE: f32 = 1.1;
+
+/// Triangle list of positions on the unit sphere
+fn sphere_vertices(segments: usize, rings: usize) -> Vec<f32> {
+    let point = |segment: usize, ring: usize| {
+        let theta = ring as f32 / rings as f32 * PI;
+        let phi = segment as f32 / segments as f32 * 2.0 * PI;
+
+        [theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin()]
+    };
+
+    let mut vertices = Vec::with_capacity(segments * rings * 6 * 3);
+
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let a = point(segment, ring);
+            let b = point(segment + 1, ring);
+            let c = point(segment, ring + 1);
+            let d = point(segment + 1, ring + 1);
+
+            // Counter-clockwise when viewed from outside
+            for vertex in &[a, b, c, c, b, d] {
+                vertices.extend_from_slice(vertex);
+            }
+        }
+    }
+
+    vertices
+}
+
+/// Unit sphere mesh drawn around point lights
+pub struct Sphere {
+    vao: GLVertexArray,
+    buffer: GLBuffer,
+    count: usize,
+}
+
+impl Sphere {
+    pub fn new() -> GLResult<Sphere> {
+        let vertices = sphere_vertices(SPHERE_SEGMENTS, SPHERE_RINGS);
+
+        let vao = try!(GLVertexArray::new());
+
+        try!(vao.bind());
+
+        let mut buffer = try!(GLBuffer::new(GLBufferTarget::ArrayBuffer));
+
+        try!(buffer.bind());
+
+        try!(buffer.buffer_slice(&vertices, GLBufferUsage::StaticDraw));
+
+        unsafe {
+            glb::EnableVertexAttribArray(0);
+            glb::VertexAttribPointer(0, 3, glb::FLOAT, glb::FALSE, 3 * mem::size_of::<f32>() as GLsizei, ptr::null());
+        }
+
+        check_errors!();
+
+        try!(DEFAULT_VERTEXARRAY.bind());
+
+        Ok(Sphere {
+            vao: vao,
+            buffer: buffer,
+            count: vertices.len() / 3,
+        })
+    }
+
+    /// Draws the sphere with whatever state and shader is currently set
+    pub fn draw(&self) -> GLResult<()> {
+        try!(self.vao.bind());
+
+        unsafe {
+            glb::DrawArrays(glb::TRIANGLES, 0, self.count as GLsizei);
+        }
+
+        check_errors!();
+
+        Ok(())
+    }
+}
+
+/// Sphere mesh and shaders used to render point lights as stenciled light volumes
+pub struct LightVolumes {
+    sphere: Sphere,
+    stencil_shader: GLShaderProgram,
+    point_shader: GLShaderProgram,
+}
+
+fn load_volume_shader(fragment: &str) -> GLResult<GLShaderProgram> {
+    let vertex_shader = try!(GLShader::from_file("shaders/light_volume.vert", GLShaderVariant::VertexShader));
+    let fragment_shader = try!(GLShader::from_file(fragment, GLShaderVariant::FragmentShader));
+
+    Ok(GLShaderProgramBuilder::new()?
+        .attach_shader(vertex_shader)?
+        .attach_shader(fragment_shader)?
+        .link()?
+        .uniform_block(CAMERA_BLOCK, CAMERA_BINDING)?
+        .uniform_block(LIGHTS_BLOCK, LIGHTS_BINDING)?
+        .finish())
+}
+
+impl LightVolumes {
+    pub fn new() -> GLResult<LightVolumes> {
+        Ok(LightVolumes {
+            sphere: try!(Sphere::new()),
+            stencil_shader: try!(load_volume_shader("shaders/light_stencil.frag")),
+            point_shader: try!(load_volume_shader("shaders/point_light.frag")),
+        })
+    }
+
+    #[inline(always)]
+    pub fn sphere(&self) -> &Sphere { &self.sphere }
+
+    /// Only writes depth, for marking pixels inside the volume in the stencil buffer
+    #[inline(always)]
+    pub fn stencil_shader(&self) -> &GLShaderProgram { &self.stencil_shader }
+
+    /// Shades the G-buffer with the single light in `lights[0]`
+    #[inline(always)]
+    pub fn point_shader(&self) -> &GLShaderProgram { &self.point_shader }
+}
@@ -1,6 +1,5 @@
 use glfw::{self, Context, WindowEvent};
 use std::mem;
-use std::ptr;
 use std::sync::mpsc;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -10,6 +9,7 @@ use std::time::Duration as StdDuration;
 use time::{Duration, PreciseTime};
 use std::fs::File;
 use std::io::BufReader;
+use std::path::PathBuf;
 use nalgebra::*;
 use lazy;
 
@@ -25,82 +25,454 @@ use ::backend::gl::bindings as glb;
 use error::*;
 
 use common::utils;
+use common::pacing;
+use common::channel;
+use common::frame_times::{FrameTimes, FrameStatsSummary};
 
 use components;
 use resources;
+use resources::render_queue::RenderItem;
+use resources::billboard_list::BillboardBatch;
 use systems;
+use systems::schedule::SystemTiming;
 
 use scene::{Scene, SourceMap};
-
-use super::pipeline::Pipeline;
+use scene::hot_reload;
+use scene::storage::{ComponentRegistry, BuiltinComponents};
+use scene::debug;
+
+use super::pipeline::{Pipeline, Light, LightKind, Exposure, DebugView, RasterMode};
+use super::pipeline::material::{BoundMaterial, MaterialDefinition, MaterialHandle};
+use super::pipeline::instancing::{self, InstanceBuffer};
+use super::pipeline::shadow::directional_light_matrix;
+use super::screenshot;
+use super::vsync::{self, VsyncMode};
+use super::file_drop::LoadedTexture;
+use super::pipeline::{TextureOverlay, BillboardRenderer};
+
+use input::gamepad::{GamepadState, GamepadEvent};
+use input::camera::CameraInput;
+use input::text::TextEdit;
 
 pub enum RenderSignal {
     Stop,
-    Pause,
+    /// Stops drawing and presenting frames, while fixed updates keep running, such as while the window is minimized
+    PauseRendering,
+    /// Stops both drawing and fixed updates, freezing game time
+    PauseAll,
+    /// Undoes either kind of pause
     Resume,
+    /// The framebuffer was resized, in pixels rather than window coordinates
     ViewportResize(i32, i32),
+    /// Framebuffer pixels per window coordinate changed, such as when the window moved to a high-DPI display
+    ContentScale(f32, f32),
+    /// Sets a fixed exposure for tonemapping, disabling automatic exposure
+    SetExposure(f32),
+    /// Selects which buffer is displayed by the final pass
+    DebugView(DebugView),
+    /// Switches the geometry pass between normal, wireframe and overdraw rasterization
+    RasterMode(RasterMode),
+    /// Sets the color the screen is cleared to each frame
+    ClearColor(f32, f32, f32),
+    /// Renders at the given multiple of the window size, between `MIN_RENDER_SCALE` and `MAX_RENDER_SCALE`
+    RenderScale(f32),
+    /// Saves the next rendered frame as a PNG, to a timestamped file in `screenshots/` if no path is given
+    Screenshot(Option<PathBuf>),
+    /// Finds the object at the given window position in the next rendered frame, replying with `RenderReply::Pick`
+    Pick(f64, f64),
+    /// Paces frames to the given rate, starting with the next frame
+    SetTargetFps(f64),
+    /// Renders frames as fast as possible, for benchmarking. `SetTargetFps` turns the limiter back on.
+    Uncapped,
+    /// Changes vertical sync, which also turns off the frame rate limiter unless `RenderLoopState::set_limit_with_vsync` was set
+    SetVsync(VsyncMode),
+    /// Latest state of every gamepad slot, merged into the gamepad resource
+    Gamepads(GamepadState),
+    /// A gamepad was connected or disconnected, which is pushed to the event queue
+    GamepadEvent(GamepadEvent),
+    /// Mouse-look and movement input, merged into the camera controller resource
+    CameraInput(CameraInput),
+    /// The main thread captured or released the cursor for mouse-look
+    CursorCapture(bool),
+    /// The main thread started or stopped capturing text for the console
+    TextCapture(bool),
+    /// Typed text or an editing key, while text is being captured
+    Text(TextEdit),
+    /// Shows a dropped texture in the overlay, replacing the previous one
+    ShowTexture(LoadedTexture),
+    /// Hides the texture overlay
+    HideOverlay,
+    /// Builds the pipeline's shader programs again from their files, keeping the old version of any that fail
+    ReloadShaders,
+    /// Applies changes to a scene file to the entities loaded from it, sent by a `SceneWatcher`
+    ReloadScene(PathBuf),
+    /// Logs everything the debug registry can describe about the entity with the given ID, such as a picked object
+    DumpEntity(u32),
+    /// Logs the number of entities and components in the scene, with an estimate of their memory use
+    DumpWorld,
     Event(WindowEvent)
 }
 
+/// Frame timing statistics periodically sent from the render thread
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameStats {
+    pub frame_number: u64,
+    /// Time spent rendering the last frame on the render thread, excluding any waiting for the target frame rate
+    pub cpu_ms: f32,
+    /// Total GPU time of all pipeline passes, as measured by timer queries. Zero if they're unsupported.
+    pub gpu_ms: f32,
+    /// Average frames per second since the previous statistics were sent
+    pub fps_avg: f32,
+    /// Objects submitted to the geometry pass in the last frame
+    pub objects_drawn: usize,
+    /// Objects skipped in the last frame for being outside the view frustum
+    pub objects_culled: usize,
+    /// Number of times a material was bound in the last frame's geometry pass
+    pub material_binds: usize,
+    /// OpenGL state changes issued in the last frame
+    pub state_changes_issued: u64,
+    /// OpenGL state changes skipped in the last frame for already being in effect
+    pub state_changes_skipped: u64,
+    /// Fixed update ticks run before the last frame
+    pub ticks: u32,
+    /// Fixed update ticks run since the render loop started
+    pub total_ticks: u64,
+    /// Frame time statistics over the last `FRAME_TIME_SAMPLES` frames
+    pub summary: FrameStatsSummary,
+    /// Frames dropped because of an error since the render loop started
+    pub failed_frames: u64,
+    /// Framebuffer pixels per window coordinate on each axis
+    pub content_scale: (f32, f32),
+    /// Time each scene system took in the last update, in the order they start
+    pub systems: Vec<SystemTiming>,
+}
+
+/// Number of recent frame times kept by `RenderLoopState` for its statistics
+pub const FRAME_TIME_SAMPLES: usize = 240;
+
+/// Fixed update ticks due for one frame, from `RenderLoopState::advance`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedSteps {
+    pub ticks: u32,
+    /// Length of each tick, in seconds
+    pub dt: systems::Delta,
+    /// How far the frame is between the last tick and the next, from 0 to 1
+    pub alpha: f32,
+}
+
+/// Everything the render thread sends back to the main thread
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderReply {
+    Stats(FrameStats),
+    /// Result of a `RenderSignal::Pick`, with the object ID at the requested position if there was any
+    Pick { x: f64, y: f64, object: Option<u32> },
+    /// The render loop gave up after errors, and the render thread is exiting.
+    /// `consecutive_failures` is how many frames in a row had failed, including the last one.
+    Stopped { frame: u64, consecutive_failures: u32 },
+    /// The console line changed, with the cursor position in characters
+    Console { line: String, cursor: usize },
+}
+
+/// Default number of frames in a row that can fail before the render loop gives up
+pub const MAX_CONSECUTIVE_FAILURES: u32 = 30;
+
 pub struct RenderLoopState {
     total_frames: u64,
-    refresh_rate: f64,
-    target_diff: Duration,
-    paused: bool,
+    target_fps: Option<f64>,
+    target_diff: Option<Duration>,
+    vsync: VsyncMode,
+    limit_with_vsync: bool,
+    tick_rate: f64,
+    accumulator: f64,
+    max_frame_time: f64,
+    total_ticks: u64,
+    frame_times: FrameTimes,
+    failed_frames: u64,
+    consecutive_failures: u32,
+    max_consecutive_failures: u32,
+    content_scale: (f32, f32),
+    stats_interval: Duration,
+    render_paused: bool,
+    simulation_paused: bool,
 }
 
 impl<'a> RenderLoopState {
-    pub fn new(refresh_rate: f64) -> RenderLoopState {
-        RenderLoopState {
+    pub fn new(target_fps: f64) -> RenderLoopState {
+        let mut state = RenderLoopState {
             total_frames: 0,
-            refresh_rate: refresh_rate, //utils::round_multiple(refresh_rate, 10) as f32
-            target_diff: Duration::nanoseconds((1000000000.0 / refresh_rate) as i64),
-            paused: true,
-        }
+            target_fps: None,
+            target_diff: None,
+            vsync: VsyncMode::default(),
+            limit_with_vsync: false,
+            tick_rate: 60.0,
+            accumulator: 0.0,
+            max_frame_time: 0.25,
+            total_ticks: 0,
+            frame_times: FrameTimes::new(FRAME_TIME_SAMPLES),
+            failed_frames: 0,
+            consecutive_failures: 0,
+            max_consecutive_failures: MAX_CONSECUTIVE_FAILURES,
+            content_scale: (1.0, 1.0),
+            stats_interval: Duration::milliseconds(500),
+            render_paused: true,
+            simulation_paused: true,
+        };
+
+        state.set_target_fps(target_fps);
+
+        state
     }
 
+    /// Whether frames are neither drawn nor presented
+    #[inline(always)]
+    pub fn render_paused(&self) -> bool { self.render_paused }
+
+    /// Whether fixed updates are stopped, which also means rendering is
     #[inline(always)]
-    pub fn paused(&self) -> bool { self.paused }
+    pub fn simulation_paused(&self) -> bool { self.simulation_paused }
 
+    /// Stops drawing frames, but keeps running fixed updates
     #[inline(always)]
-    pub fn pause(&mut self) { self.paused = true; }
+    pub fn pause_rendering(&mut self) {
+        self.render_paused = true;
+        self.simulation_paused = false;
+    }
+
+    /// Stops drawing frames and running fixed updates
+    #[inline(always)]
+    pub fn pause_all(&mut self) {
+        self.render_paused = true;
+        self.simulation_paused = true;
+    }
 
     #[inline(always)]
-    pub fn unpause(&mut self) { self.paused = false; }
+    pub fn unpause(&mut self) {
+        self.render_paused = false;
+        self.simulation_paused = false;
+    }
 
     #[inline(always)]
     pub fn total_frames(&self) -> u64 { self.total_frames }
 
+    /// The frame rate being paced to, or `None` if uncapped
     #[inline(always)]
-    pub fn refresh_rate(&self) -> f64 { self.refresh_rate }
+    pub fn target_fps(&self) -> Option<f64> { self.target_fps }
+
+    /// Changes the frame rate being paced to, taking effect on the next frame.
+    ///
+    /// Frame counts and statistics carry on as before. Rates that aren't positive leave the limiter off.
+    pub fn set_target_fps(&mut self, target_fps: f64) {
+        if target_fps > 0.0 && target_fps.is_finite() {
+            self.target_fps = Some(target_fps);
+            self.target_diff = Some(Duration::nanoseconds((1000000000.0 / target_fps) as i64));
+        } else {
+            self.uncap();
+        }
+    }
 
+    /// Turns off frame pacing until the next `set_target_fps`
     #[inline(always)]
-    pub fn set_refresh_rate(&mut self, refresh_rate: f64) {
-        self.refresh_rate = refresh_rate;
-        self.target_diff = Duration::nanoseconds((1000000000.0 / refresh_rate) as i64);
+    pub fn uncap(&mut self) {
+        self.target_fps = None;
+        self.target_diff = None;
     }
+
+    /// The vertical sync mode in effect, or the one `start` will apply if it hasn't been called yet
+    #[inline(always)]
+    pub fn vsync(&self) -> VsyncMode { self.vsync }
+
+    /// Sets the vertical sync mode `start` applies, before the render thread is started.
+    /// Once it's running, send `RenderSignal::SetVsync` instead.
+    #[inline(always)]
+    pub fn set_vsync(&mut self, vsync: VsyncMode) {
+        self.vsync = vsync;
+    }
+
+    /// Whether the frame rate limiter keeps running while vsync is on, such as to cap a high refresh rate display
+    #[inline(always)]
+    pub fn limit_with_vsync(&self) -> bool { self.limit_with_vsync }
+
+    #[inline(always)]
+    pub fn set_limit_with_vsync(&mut self, value: bool) {
+        self.limit_with_vsync = value;
+    }
+
+    /// How long each frame is paced to take, or `None` if frames aren't being limited
+    pub fn frame_interval(&self) -> Option<Duration> {
+        if self.vsync.is_synced() && !self.limit_with_vsync {
+            None
+        } else {
+            self.target_diff
+        }
+    }
+
+    /// Fixed update ticks per second
+    #[inline(always)]
+    pub fn tick_rate(&self) -> f64 { self.tick_rate }
+
+    /// Changes how many fixed update ticks run per second. Time already accumulated carries over to the new rate.
+    pub fn set_tick_rate(&mut self, tick_rate: f64) {
+        if tick_rate > 0.0 && tick_rate.is_finite() {
+            self.tick_rate = tick_rate;
+        } else {
+            warn!("Invalid tick rate {}, keeping {}Hz", tick_rate, self.tick_rate);
+        }
+    }
+
+    /// Length of each fixed update tick
+    #[inline]
+    pub fn tick_interval(&self) -> Duration {
+        Duration::nanoseconds((1000000000.0 / self.tick_rate) as i64)
+    }
+
+    #[inline(always)]
+    pub fn total_ticks(&self) -> u64 { self.total_ticks }
+
+    /// Longest frame time `advance` accounts for, in seconds.
+    ///
+    /// Anything longer, such as while the window is dragged or the process is stopped in a debugger,
+    /// is dropped rather than caught up on, so ticks that take longer than real time can't pile up forever.
+    #[inline(always)]
+    pub fn max_frame_time(&self) -> f64 { self.max_frame_time }
+
+    #[inline(always)]
+    pub fn set_max_frame_time(&mut self, seconds: f64) {
+        self.max_frame_time = seconds.max(0.0);
+    }
+
+    /// Adds `frame_time` seconds to the accumulator, and takes out as many whole ticks as it now holds
+    pub fn advance(&mut self, frame_time: f64) -> FixedSteps {
+        let dt = 1.0 / self.tick_rate;
+
+        self.accumulator += frame_time.max(0.0).min(self.max_frame_time);
+
+        let mut ticks = 0;
+
+        while self.accumulator >= dt {
+            self.accumulator -= dt;
+            ticks += 1;
+        }
+
+        self.total_ticks += ticks as u64;
+
+        FixedSteps {
+            ticks: ticks,
+            dt: dt as systems::Delta,
+            alpha: (self.accumulator / dt) as f32,
+        }
+    }
+
+    /// Times of the last `FRAME_TIME_SAMPLES` rendered frames, from the start of one to the start of the next
+    #[inline(always)]
+    pub fn frame_times(&self) -> &FrameTimes { &self.frame_times }
+
+    /// Average FPS, percentile and longest frame times over the recent frames
+    pub fn stats_summary(&self) -> FrameStatsSummary {
+        self.frame_times.summary()
+    }
+
+    /// Frames dropped because of an error
+    #[inline(always)]
+    pub fn failed_frames(&self) -> u64 { self.failed_frames }
+
+    #[inline(always)]
+    pub fn max_consecutive_failures(&self) -> u32 { self.max_consecutive_failures }
+
+    /// Sets how many frames in a row can fail before the render loop stops, replying with `RenderReply::Stopped`.
+    /// A single successful frame resets the count.
+    #[inline(always)]
+    pub fn set_max_consecutive_failures(&mut self, failures: u32) {
+        self.max_consecutive_failures = failures.max(1);
+    }
+
+    /// Framebuffer pixels per window coordinate, for sizing anything drawn in window coordinates like UI and text
+    #[inline(always)]
+    pub fn content_scale(&self) -> (f32, f32) { self.content_scale }
+
+    #[inline(always)]
+    pub fn set_content_scale(&mut self, scale: (f32, f32)) {
+        self.content_scale = scale;
+    }
+
+    #[inline(always)]
+    pub fn stats_interval(&self) -> Duration { self.stats_interval }
+
+    /// Sets how often `FrameStats` are sent to the main thread
+    #[inline(always)]
+    pub fn set_stats_interval(&mut self, interval: Duration) {
+        self.stats_interval = interval;
+    }
+}
+
+/// How often a paused render loop checks whether the main thread is still there, if nothing wakes it sooner
+pub const PAUSED_POLL_INTERVAL_MS: u64 = 250;
+
+/// Takes the next signal without blocking, or `None` if there isn't one.
+///
+/// If the main thread has gone away without sending `RenderSignal::Stop`, this returns one for it.
+pub fn next_signal(rx: &mpsc::Receiver<RenderSignal>) -> Option<RenderSignal> {
+    channel::try_recv_or(rx, || {
+        warn!("Render signal channel disconnected, stopping");
+
+        RenderSignal::Stop
+    })
 }
 
-pub fn start(mut state: &mut RenderLoopState, mut context: glfw::RenderContext, rx: &mpsc::Receiver<RenderSignal>) -> AppResult<()> {
-    info!("Targeting {}Hz", state.refresh_rate);
+/// Whether a failed frame should stop the render loop, rather than just being dropped
+fn is_fatal(err: &AppError) -> bool {
+    match *err {
+        AppError::GLError(ref err) => err.is_fatal(),
+        _ => true,
+    }
+}
+
+/// Runs the render loop until a `RenderSignal::Stop` is received.
+///
+/// Errors while setting up are returned. After that, a frame that fails is logged and dropped, unless the error
+/// left the context unusable or too many frames have failed in a row. Then the loop replies with `RenderReply::Stopped`
+/// and returns normally, since the render thread has shut down rather than crashed.
+///
+/// Frame statistics are sent through `reply_tx` every `RenderLoopState::stats_interval`, along with the results of any picks.
+/// If the channel is full, or the receiving end has hung up, replies are dropped rather than blocking the render thread,
+/// so the channel should have room for a few picks on top of the statistics.
+///
+/// `update` is called for every fixed update tick, right before the scene's systems are run with the same `dt`.
+pub fn start<F>(mut state: &mut RenderLoopState, mut context: glfw::RenderContext, rx: &mpsc::Receiver<RenderSignal>,
+                reply_tx: &mpsc::SyncSender<RenderReply>, mut update: F) -> AppResult<()>
+    where F: FnMut(&mut Scene, systems::Delta) -> AppResult<()> {
+    state.vsync = vsync::apply(state.vsync);
+
+    match state.target_fps {
+        Some(fps) => info!("Targeting {}Hz with vsync {:?}", fps, state.vsync),
+        None => info!("Frame rate uncapped with vsync {:?}", state.vsync),
+    }
 
     let mut scene = try!(Scene::new());
     let mut pipeline = try!(Pipeline::new(1280, 720));
+    let mut instance_buffer = try!(InstanceBuffer::new());
+    let mut overlay = try!(TextureOverlay::new());
+    let mut billboard_renderer = try!(BillboardRenderer::new());
+
+    //Until the first resize, billboards sized in pixels go by the size the pipeline was created with
+    scene.with_world(|world| {
+        world.write_resource::<resources::camera_matrices::Resource>().set_viewport(pipeline.window_size().x, pipeline.window_size().y)
+    });
 
     //TODO: Remove this
     try!(::game::entities::test_entities::load(&mut scene));
 
     let mut delta: systems::Delta = 0.0;
     let mut last = PreciseTime::now();
+    let mut last_timings = PreciseTime::now();
 
-    let lighting_shader_frag = gl::GLShaderBuilder::new(gl::GLShaderVariant::FragmentShader)?.file("shaders/deferred_lighting.frag")?.compile()?.finish();
-    let lighting_shader_vert = gl::GLShaderBuilder::new(gl::GLShaderVariant::VertexShader)?.file("shaders/deferred_lighting.vert")?.compile()?.finish();
+    let mut stats_start = PreciseTime::now();
+    let mut stats_frames: u64 = 0;
 
-    let lighting_shader = gl::GLShaderProgramBuilder::new()?
-        .attach_shader(lighting_shader_frag)?
-        .attach_shader(lighting_shader_vert)?
-        .link()?
-        .finish();
+    let mut objects_drawn = 0;
+    let mut objects_culled = 0;
+    let mut material_binds = 0;
+    let mut state_changes = gl::GLStateCounters::default();
+    let mut frame_ticks = 0;
 
     //////////////////
 
@@ -165,25 +537,48 @@ pub fn start(mut state: &mut RenderLoopState, mut context: glfw::RenderContext,
             }
         }
 
-        check_errors!();
-
-        active_texture.set_filter(GLTextureFilter::Linear, Some(GLTextureFilter::Linear)).expect_logged("Couldn't set texture filtering");
-
-        let max_anisotropy = active_texture.get_max_anisotropy().expect_logged("Couldn't get max anisotropy value");
-        active_texture.set_anisotropy(max_anisotropy).expect_logged("Couldn't set max anisotropy");
+        check_errors!("uploading {}x{} texture {:?}", width, height, specific_format);
 
+        //Filtering comes from the material's sampler, so only the mipmaps are needed here
         active_texture.generate_mipmap().expect_logged("Couldn't generate mipmaps");
 
         active_texture
     };
 
+    //Used for every render item without a material of its own
+    let default_material: MaterialHandle = Arc::new(BoundMaterial::new(MaterialDefinition {
+        diffuse: Some(Arc::new(texture)),
+        sampler: Some(pipeline.samplers().linear_repeat().clone()),
+        ..MaterialDefinition::default()
+    }));
+
     //////////////////
 
-    //This is constantly swapped out for the render queue resource
+    //Frame packets from the render submission system, which runs with the rest of the scene's systems
+    let mut frames = scene.with_world(|world| world.write_resource::<resources::frame_packet::Resource>().connect());
+
+    //Refilled every frame from the newest frame packet
     let mut final_render_queue = Vec::with_capacity(resources::render_queue::RENDER_QUEUE_SIZE);
 
+    //Likewise, the lights gathered by the light gathering system
+    let mut lights: Vec<Light> = Vec::new();
+
+    //And the billboards, already sorted back to front by the billboard system
+    let mut billboards: Vec<BillboardBatch> = Vec::new();
+
+    //Resize requests are coalesced into this until the next unpaused frame, so only the last size is ever applied
+    let mut pending_viewport_size = None;
+
+    //Likewise, a requested screenshot waits until a frame is actually rendered
+    let mut pending_screenshot: Option<Option<PathBuf>> = None;
+
+    //Screenshots being read back, which are saved once the GPU gets to them
+    let mut capturing: Vec<screenshot::PendingScreenshot> = Vec::new();
+
+    //Picks are answered after the next geometry pass, in the order they were requested
+    let mut pending_picks: Vec<(f64, f64)> = Vec::new();
+
     'render: loop {
-        let mut viewport_size = None;
 
         // Step one: process events
         if scene.with_world(|world| -> bool {
@@ -191,23 +586,156 @@ pub fn start(mut state: &mut RenderLoopState, mut context: glfw::RenderContext,
 
             let mut event_queue = world.write_resource::<EventQueue>();
 
-            for signal in rx.try_iter() {
+            while let Some(signal) = next_signal(rx) {
                 match signal {
                     RenderSignal::Stop => {
                         //TODO: Clean up entities
                         return true;
                     },
-                    RenderSignal::ViewportResize(width, height) => {
-                        viewport_size = Some((width, height));
+                    RenderSignal::ViewportResize(width, height) if width > 0 && height > 0 => {
+                        use components::camera::Component as Camera;
+                        use resources::active_camera::Resource as ActiveCamera;
+                        use resources::camera_matrices::Resource as CameraMatrices;
+
+                        pending_viewport_size = Some((width, height));
+
+                        let mut cameras = world.write::<Camera>();
+
+                        for camera in (&mut cameras).iter() {
+                            camera.resize(width as f32, height as f32);
+                        }
+
+                        //Fixed updates may not run before the next frame, so the projection is refreshed right away
+                        if let Some(camera) = world.read_resource::<ActiveCamera>().entity().and_then(|entity| cameras.get(entity)) {
+                            world.write_resource::<CameraMatrices>().set_projection(camera.projection());
+                        }
+
+                        //Billboards sized in pixels are scaled by the viewport size
+                        world.write_resource::<CameraMatrices>().set_viewport(width as f32, height as f32);
                     },
+                    RenderSignal::ViewportResize(..) => {},
+                    RenderSignal::ContentScale(x, y) => {
+                        state.set_content_scale((x, y));
+                        info!("Content scale set to {}x{}", x, y);
+                    }
                     RenderSignal::Resume => {
                         state.unpause();
                         info!("Resuming...");
                     },
-                    RenderSignal::Pause => {
-                        state.pause();
+                    RenderSignal::PauseRendering => {
+                        state.pause_rendering();
+                        info!("Pausing rendering...");
+                    }
+                    RenderSignal::PauseAll => {
+                        state.pause_all();
                         info!("Pausing...");
                     }
+                    RenderSignal::SetExposure(exposure) => {
+                        pipeline.tonemap.exposure = Exposure::Manual(exposure);
+                    }
+                    RenderSignal::DebugView(view) => {
+                        pipeline.set_debug_view(view);
+                    }
+                    RenderSignal::RasterMode(mode) => {
+                        pipeline.set_raster_mode(mode);
+                    }
+                    RenderSignal::RenderScale(scale) => {
+                        match pipeline.set_render_scale(scale) {
+                            Ok(_) => info!("Render scale set to {}", pipeline.render_scale()),
+                            Err(err) => error!("Could not set render scale: {}", err),
+                        }
+                    }
+                    RenderSignal::ClearColor(r, g, b) => {
+                        if let Err(err) = pipeline.set_clear_color(r, g, b) {
+                            error!("Could not set clear color: {}", err);
+                        }
+                    }
+                    RenderSignal::Screenshot(path) => {
+                        pending_screenshot = Some(path);
+                    }
+                    RenderSignal::Pick(x, y) => {
+                        pending_picks.push((x, y));
+                    }
+                    RenderSignal::SetTargetFps(fps) => {
+                        state.set_target_fps(fps);
+
+                        match state.target_fps {
+                            Some(fps) => info!("Targeting {}Hz", fps),
+                            None => warn!("Invalid target frame rate {}, leaving it uncapped", fps),
+                        }
+                    }
+                    RenderSignal::Uncapped => {
+                        state.uncap();
+                        info!("Frame rate uncapped");
+                    }
+                    RenderSignal::SetVsync(mode) => {
+                        state.vsync = vsync::apply(mode);
+                        info!("Vsync {:?}", state.vsync);
+                    }
+                    RenderSignal::Gamepads(gamepads) => {
+                        world.write_resource::<resources::gamepad::Resource>().merge(gamepads);
+                    }
+                    RenderSignal::GamepadEvent(event) => {
+                        event_queue.push(Event::Gamepad(event));
+                    }
+                    RenderSignal::CameraInput(input) => {
+                        world.write_resource::<resources::camera_controller::Resource>().input.merge(input);
+                    }
+                    RenderSignal::CursorCapture(captured) => {
+                        world.write_resource::<resources::camera_controller::Resource>().set_captured(captured);
+                    }
+                    RenderSignal::TextCapture(active) => {
+                        let mut text_input = world.write_resource::<resources::text_input::Resource>();
+
+                        text_input.active = active;
+                        text_input.clear();
+
+                        //Show the line as it is when the console opens
+                        world.write_resource::<resources::console::Resource>().changed = active;
+                    }
+                    RenderSignal::Text(edit) => {
+                        world.write_resource::<resources::text_input::Resource>().push(edit);
+                    }
+                    RenderSignal::ShowTexture(texture) => {
+                        match texture.upload() {
+                            Ok(uploaded) => {
+                                overlay.set_texture(uploaded, texture.size);
+                                info!("Showing {} in the overlay", texture.path.display());
+                            }
+                            Err(err) => error!("Could not upload {}: {}", texture.path.display(), err),
+                        }
+                    }
+                    RenderSignal::HideOverlay => {
+                        overlay.clear();
+                    }
+                    RenderSignal::ReloadShaders => {
+                        let summary = pipeline.reload_shaders();
+
+                        if summary.failed > 0 {
+                            warn!("{}", summary);
+                        } else {
+                            info!("{}", summary);
+                        }
+                    }
+                    RenderSignal::ReloadScene(path) => {
+                        let registry = ComponentRegistry::builtin();
+
+                        match scene.with_world(|world| hot_reload::reload(world, &registry, &path)) {
+                            Ok(report) => info!("Reloaded {}: {}", path.display(), report),
+                            Err(err) => error!("Could not reload {}: {}", path.display(), err),
+                        }
+                    }
+                    RenderSignal::DumpEntity(id) => {
+                        let found = world.entities().iter().find(|entity| entity.get_id() as u32 == id);
+
+                        match found {
+                            Some(entity) => info!("{}", debug::dump_entity(world, entity)),
+                            None => warn!("There is no entity {} to dump", id),
+                        }
+                    }
+                    RenderSignal::DumpWorld => {
+                        info!("{}", debug::dump_world_summary(world));
+                    }
                     RenderSignal::Event(event) => {
                         event_queue.push(Event::WindowEvent(event));
                     }
@@ -221,34 +749,64 @@ pub fn start(mut state: &mut RenderLoopState, mut context: glfw::RenderContext,
 
         let before = PreciseTime::now();
 
-        if state.paused {
+        //Ticks follow scaled time, so a time scale of zero stops them altogether
+        let scaled_delta = scene.with_world(|world| world.write_resource::<resources::time::Resource>().advance(delta as f64));
+
+        //Step two, run every fixed update tick due since the last frame, so the frame shows their results.
+        //This carries on while rendering is paused, so game time keeps up while the window is minimized
+        if !state.simulation_paused {
+            let steps = state.advance(scaled_delta);
+
+            for _ in 0..steps.ticks {
+                try!(update(&mut scene, steps.dt));
+
+                scene.update(steps.dt);
+                scene.wait();
+            }
+
+            frame_ticks = steps.ticks;
+
+            scene.with_world(|world| {
+                //Every tick this frame has seen the button edges, so start collecting them again
+                if steps.ticks > 0 {
+                    world.write_resource::<resources::gamepad::Resource>().clear_edges();
+                }
+
+                world.write_resource::<resources::time::Resource>().set_fixed_steps(steps.ticks, steps.dt, steps.alpha);
+            });
+        }
+
+        //The main thread has no way to draw text, so it shows the console line in the window title instead
+        scene.with_world(|world| {
+            let mut console = world.write_resource::<resources::console::Resource>();
+
+            if console.changed {
+                //If the channel is full, it's tried again next frame
+                if reply_tx.try_send(RenderReply::Console { line: console.line.text(), cursor: console.line.cursor() }).is_ok() {
+                    console.changed = false;
+                }
+            }
+        });
+
+        if state.simulation_paused {
             //Run the scene planner, but with a zero delta because it's paused.
             scene.update(0.0);
-        } else {
-            // Steps two, buffer GPU data, get render items, and get the view/projection matrices
-            let (view_position, view, projection) = try!(scene.with_world_sources(|world: &mut specs::World, mut sources: &mut SourceMap| -> AppResult<_> {
-                use resources::render_queue::{RenderItem, Resource as RenderQueue};
+        } else if !state.render_paused {
+            let viewport_size = pending_viewport_size.take();
 
-                use components::transform::Component as Transform;
-                use components::position::Component as Position;
+            // Step three, buffer GPU data and take the newest frame packet from the render submission system
+            try!(scene.with_world_sources(|world: &mut specs::World, mut sources: &mut SourceMap| -> AppResult<()> {
                 use components::mesh::Component as Mesh;
                 use components::gpu_buffer::Component as GPU_Buffer;
                 use components::renderable::Component as Renderable;
-                use components::camera::Component as Camera;
-
-                use resources::camera::Resource as CameraResource;
 
-                let ref transforms = world.read::<Transform>();
-                let ref positions = world.read::<Position>();
                 let ref meshes = world.read::<Mesh>();
 
                 let ref mut gpu_buffers = world.write::<GPU_Buffer>();
-                let ref mut renderables = world.write::<Renderable>();
+                let ref renderables = world.read::<Renderable>();
 
                 let ref entities = world.entities();
 
-                let mut render_queue = world.write_resource::<RenderQueue>();
-
                 for (_, ref mut gpu_buffer, entity) in (renderables, gpu_buffers, entities).iter() {
                     if gpu_buffer.dirty {
                         if let Some(mesh) = meshes.get(entity) {
@@ -264,146 +822,315 @@ pub fn start(mut state: &mut RenderLoopState, mut context: glfw::RenderContext,
 
                         gpu_buffer.dirty = false;
                     }
-
-                    let (matrix, inverse) = if let Some(transform) = transforms.get(entity) {
-                        (transform.matrix, transform.inverse)
-                    } else {
-                        (Matrix4::new_identity(4), Some(Matrix4::new_identity(4)))
-                    };
-
-                    render_queue.push(RenderItem {
-                        buffer: gpu_buffer.buffer(),
-                        transform: matrix,
-                        inverse: inverse
-                    });
                 }
 
-                let mut cameras = world.write::<Camera>();
+                Ok(())
+            }));
 
-                let camera_entity = world.read_resource::<CameraResource>().entity();
+            let stale_frames = frames.stale();
 
-                let mut view_position = Point3::new(0.0, 0.0, 0.0);
-                let mut view_matrix = Matrix4::new_identity(4);
-                let mut projection_matrix = Matrix4::new_identity(4);
+            frames.update();
 
-                if let Some(mut camera) = cameras.get_mut(camera_entity) {
-                    use components::camera::Kind;
+            if frames.stale() > stale_frames {
+                debug!("Render loop fell behind, skipped {} frame packets", frames.stale() - stale_frames);
+            }
 
-                    //If the window was resized, adjust the projection accordingly.
-                    if let Some((width, height)) = viewport_size {
-                        camera.kind.resize(width as f32, height as f32, None);
-                    }
+            //The packet is kept in case nothing newer arrives before the next frame, so culling works on a copy
+            final_render_queue.clear();
+            lights.clear();
+            billboards.clear();
+
+            let camera = match frames.current() {
+                Some(packet) => {
+                    final_render_queue.extend(packet.items.iter().cloned());
+                    lights.extend_from_slice(&packet.lights);
+                    billboards.extend_from_slice(&packet.billboards);
 
-                    projection_matrix = camera.kind.to_homogeneous();
+                    packet.camera
                 }
+                None => resources::camera_matrices::Resource::new(),
+            };
+
+            let (view_position, view, projection) = (camera.position, camera.view, camera.projection);
 
-                if let Some(position) = positions.get(camera_entity) {
-                    view_position = position.0;
+            //Steps four through eleven render the frame. Errors there only drop the frame, unless the context can't recover
+            let frame: AppResult<()> = (|| {
+                //Step four, resize viewport and buffers if necessary
+                if let Some((width, height)) = viewport_size {
+                    unsafe { glb::Viewport(0, 0, width as GLsizei, height as GLsizei); }
+
+                    check_errors!("resizing the viewport to {}x{}", width, height);
+
+                    try!(pipeline.resize(width as usize, height as usize));
+
+                    info!("Viewport resized to {}x{}", width, height);
                 }
 
-                if let Some(transform) = transforms.get(camera_entity) {
-                    view_matrix = transform.matrix;
+                //The camera and lights are uploaded to uniform buffers once, and shared by every pass after this
+                try!(pipeline.set_camera(&view, &projection));
+                try!(pipeline.set_lights(&lights));
+
+                //Step five, render shadow casters for the first directional light
+                if let Some(sun) = lights.iter().find(|light| light.kind == LightKind::Directional) {
+                    let light_view_proj = directional_light_matrix(&sun.direction, &view_position, 50.0);
+
+                    try!(pipeline.shadow_pass(&light_view_proj, |shader: &gl::GLShaderProgram| {
+                        use components::gpu_buffer::BufferField;
+
+                        let mut model_uniform = try!(shader.get_uniform("model"));
+
+                        for item in final_render_queue.iter().filter(|item| item.cast_shadows) {
+                            let buffer_lock = item.buffer.read().unwrap();
+                            let buffer = try!(buffer_lock.get());
+
+                            try!(buffer.bind());
+
+                            try!(buffer.bind_attrib_arrays(&[BufferField::Vertex]));
+
+                            try!(model_uniform.mat4(&item.transform, false));
+
+                            try!(instancing::draw_range(buffer, item.range));
+                        }
+
+                        Ok(())
+                    }));
                 }
 
-                render_queue.swap(&mut final_render_queue);
+                //Step six, the geometry rendering
+                //Shadow casters outside the view can still cast shadows into it, so culled items are only dropped after the shadow pass
+                let submitted = final_render_queue.len();
 
-                Ok((view_position, view_matrix, projection_matrix))
-            }));
+                final_render_queue.retain(|item| item.visible);
 
-            //Step three, set off the system updates
-            scene.update(delta);
+                objects_culled = submitted - final_render_queue.len();
 
-            //Step four, resize viewport and buffers if necessary
-            if let Some((width, height)) = viewport_size {
-                use ::resources::projection::Resource as Projection;
+                objects_drawn = final_render_queue.len();
 
-                unsafe { glb::Viewport(0, 0, width as GLsizei, height as GLsizei); }
+                //Sorting puts identical meshes next to each other, so they can be drawn instanced
+                //Sorting by material first keeps material changes to a minimum
+                let batches = instancing::batches(&mut final_render_queue, RenderItem::sort_key);
 
-                check_errors!();
+                let instancing_settings = pipeline.instancing;
 
-                try!(pipeline.resize(width as usize, height as usize));
+                material_binds = 0;
 
-                info!("Viewport resized to {}x{}", width, height);
-            }
+                try!(pipeline.geometry_pass(|shader: &gl::GLShaderProgram| {
+                    use components::gpu_buffer::BufferField;
 
-            //Step five, the geometry rendering
-            try!(pipeline.geometry_pass(|shader: &gl::GLShaderProgram| {
-                use components::gpu_buffer::BufferField;
+                    //Each call has its own program, so the first material always has to be bound
+                    let mut bound_material: Option<*const BoundMaterial> = None;
 
-                let mut mvp_uniform = try!(shader.get_uniform("mvp"));
-                let mut model_uniform = try!(shader.get_uniform("model"));
-                let mut mit_uniform = try!(shader.get_uniform("mit"));
+                    //The depth pre-pass program only has `mvp`, so every lookup is optional
+                    let mut mvp_uniform = try!(shader.get_uniform_optional("mvp"));
+                    let mut model_uniform = try!(shader.get_uniform_optional("model"));
+                    let mut mit_uniform = try!(shader.get_uniform_optional("mit"));
+                    let mut instanced_uniform = try!(shader.get_uniform_optional("instanced"));
+                    let mut object_id_uniform = try!(shader.get_uniform_optional("object_id"));
 
-                //Draining the render queue instead of clearing it allows for the memory to be reused.
-                for item in final_render_queue.drain(..) {
-                    //TODO: Handle poison errors
-                    let buffer_lock = item.buffer.read().unwrap();
-                    let buffer = try!(buffer_lock.get());
+                    //Iterate instead of draining, since the depth pre-pass submits everything twice
+                    for batch in &batches {
+                        let items = &final_render_queue[batch.clone()];
+
+                        //TODO: Handle poison errors
+                        let buffer_lock = items[0].buffer.read().unwrap();
+                        let buffer = try!(buffer_lock.get());
+
+                        try!(buffer.bind());
+
+                        try!(buffer.bind_attrib_arrays(&[BufferField::Vertex, BufferField::Normal, BufferField::Uv, BufferField::Tangent, BufferField::Bitangent]));
+
+                        let material = items[0].material.as_ref().unwrap_or(&default_material);
+
+                        //Batches are sorted by material, so it only has to be bound when it changes
+                        if bound_material.map_or(true, |bound| bound != &**material as *const _) {
+                            try!(material.bind(shader));
+
+                            bound_material = Some(&**material as *const _);
+                            material_binds += 1;
+                        }
 
-                    try!(buffer.bind());
+                        if instancing_settings.should_instance(items.len()) {
+                            try!(instance_buffer.upload(items.iter().map(|item| {
+                                (item.transform, item.inverse.unwrap_or(Matrix4::new_identity(4)), item.object_id)
+                            })));
 
-                    try!(buffer.bind_attrib_arrays(&[BufferField::Vertex, BufferField::Normal, BufferField::Uv, BufferField::Tangent, BufferField::Bitangent]));
+                            try!(instanced_uniform.int1(1));
+
+                            try!(instancing::draw_instanced(buffer, items[0].range, &instance_buffer, items.len()));
+
+                            try!(instanced_uniform.int1(0));
+
+                            continue;
+                        }
+
+                        for item in items {
+                            let mvp = projection * view * item.transform;
+                            let inverse = item.inverse.unwrap_or(Matrix4::new_identity(4));
+
+                            try!(mvp_uniform.mat4(&mvp, false));
+                            try!(model_uniform.mat4(&item.transform, false));
+                            try!(mit_uniform.mat4(&inverse, true));
+                            try!(object_id_uniform.uint1(item.object_id));
+
+                            try!(instancing::draw_range(buffer, item.range));
+                        }
+                    }
+
+                    Ok(())
+                }));
+
+                //Clearing the render queue instead of reallocating it allows for the memory to be reused.
+                final_render_queue.clear();
+
+                //Object IDs are only valid until the next geometry pass, so picks are answered right away
+                for (x, y) in pending_picks.drain(..) {
+                    let object = match pipeline.pick(x, y) {
+                        Ok(object) => object,
+                        Err(err) => {
+                            error!("Could not pick object at ({}, {}): {}", x, y, err);
+                            None
+                        }
+                    };
 
-                    unsafe {
-                        glb::ActiveTexture(glb::TEXTURE0);
+                    if let Err(mpsc::TrySendError::Full(_)) = reply_tx.try_send(RenderReply::Pick { x: x, y: y, object: object }) {
+                        warn!("Dropped pick result at ({}, {}), since the reply channel is full", x, y);
                     }
+                }
 
-                    check_errors!();
+                //Step seven, ambient occlusion
+                try!(pipeline.ssao_pass(&view, &projection));
 
-                    try!(texture.bind());
+                //Step eight, the lighting pass
+                try!(pipeline.lighting_pass(|_| Ok(())));
 
-                    let mvp = projection * view * item.transform;
-                    let inverse = item.inverse.unwrap_or(Matrix4::new_identity(4));
+                try!(pipeline.skybox_pass());
 
-                    try!(mvp_uniform.mat4(&mvp, false));
-                    try!(model_uniform.mat4(&item.transform, false));
-                    try!(mit_uniform.mat4(&inverse, true));
+                try!(pipeline.forward_pass(|| {
+                    //TODO: Render transparent or 2D items here
+                    Ok(())
+                }));
 
-                    unsafe {
-                        glb::DrawElements(
-                            glb::TRIANGLES,
-                            buffer.num_indices() as GLint,
-                            glb::UNSIGNED_INT,
-                            ptr::null()
-                        );
+                try!(pipeline.transparent_pass(&view_position, |_, _| {
+                    for batch in &billboards {
+                        try!(billboard_renderer.draw(batch.texture.as_ref().map(|texture| &**texture),
+                                                     batch.instances.iter().map(|instance| (&instance.transform, &instance.tint))));
                     }
 
-                    check_errors!();
+                    Ok(())
+                }));
+
+                //Step nine, bloom
+                try!(pipeline.bloom_pass());
+
+                //Step ten, render out to the screen
+                try!(pipeline.final_pass());
+
+                //Any dropped texture goes on top of the finished frame
+                try!(overlay.draw(pipeline.window_size().x, pipeline.window_size().y));
+
+                //Step eleven, capture the frame if requested, before it's swapped away
+                if let Some(path) = pending_screenshot.take() {
+                    let (width, height) = (pipeline.window_size().x as usize, pipeline.window_size().y as usize);
+
+                    match screenshot::capture(width, height, path) {
+                        Ok(pending) => capturing.push(pending),
+                        Err(err) => error!("Could not read framebuffer for screenshot: {}", err),
+                    }
                 }
 
                 Ok(())
-            }));
+            })();
 
-            //Step six, the lighting pass
-            try!(pipeline.lighting_pass(&lighting_shader, |shader: &gl::GLShaderProgram| {
-                try!(shader.get_uniform("view_position")?.point3f(&view_position));
-                try!(shader.get_uniform("view")?.mat4(&view, false));
-                try!(shader.get_uniform("projection")?.mat4(&projection, false));
+            match frame {
+                Ok(()) => state.consecutive_failures = 0,
+                Err(err) => {
+                    state.failed_frames += 1;
+                    state.consecutive_failures += 1;
 
-                Ok(())
-            }));
+                    error!("Frame {} failed ({} in a row): {}", state.total_frames, state.consecutive_failures, err);
 
-            try!(pipeline.forward_pass(|| {
-                //TODO: Render transparent or 2D items here
-                Ok(())
-            }));
+                    if is_fatal(&err) || state.consecutive_failures >= state.max_consecutive_failures {
+                        error!("Stopping the render loop after {} failed frames", state.failed_frames);
 
-            //Step seven, render out to the screen
-            try!(pipeline.final_pass());
+                        //This one is worth waiting for room in the channel
+                        let _ = reply_tx.send(RenderReply::Stopped {
+                            frame: state.total_frames,
+                            consecutive_failures: state.consecutive_failures,
+                        });
 
-            //Step eight, swap the buffers
+                        return Ok(());
+                    }
+
+                    //Whatever the failed pass left bound can't be trusted anymore
+                    gl::GLStateCache::invalidate();
+                }
+            }
+
+            //Step twelve, swap the buffers
             context.swap_buffers();
 
+            capturing = capturing.into_iter().filter_map(|mut pending| if pending.poll() { None } else { Some(pending) }).collect();
+
             //Done! kind of
             state.total_frames += 1;
+
+            state_changes = gl::GLStateCache::take_counters();
+
+            if last_timings.to(PreciseTime::now()) >= Duration::seconds(1) {
+                info!("GPU timings: {}", pipeline.timings());
+
+                last_timings = PreciseTime::now();
+            }
         }
 
-        //By having no less than target_diff on the GPU, we can maintain a steady frame rate near the monitor refresh rate
+        //By waiting out the rest of the frame interval, we can maintain a steady frame rate near the monitor refresh rate
 
         let gpu_diff = before.to(PreciseTime::now());
 
-        if state.target_diff > gpu_diff {
-            thread::park_timeout((state.target_diff - gpu_diff).to_std().unwrap());
+        if !state.render_paused {
+            stats_frames += 1;
+
+            let now = PreciseTime::now();
+            let elapsed = stats_start.to(now);
+
+            if elapsed >= state.stats_interval {
+                let stats = FrameStats {
+                    frame_number: state.total_frames,
+                    cpu_ms: gpu_diff.num_microseconds().unwrap_or(0) as f32 / 1000.0,
+                    gpu_ms: pipeline.timings().total(),
+                    fps_avg: stats_frames as f32 / (elapsed.num_microseconds().unwrap_or(1) as f32 / 1_000_000.0),
+                    objects_drawn: objects_drawn,
+                    objects_culled: objects_culled,
+                    material_binds: material_binds,
+                    state_changes_issued: state_changes.issued,
+                    state_changes_skipped: state_changes.skipped,
+                    ticks: frame_ticks,
+                    total_ticks: state.total_ticks,
+                    summary: state.stats_summary(),
+                    failed_frames: state.failed_frames,
+                    content_scale: state.content_scale,
+                    systems: scene.system_timings(),
+                };
+
+                // Never block the render thread on the main thread, it will get the next ones
+                let _ = reply_tx.try_send(RenderReply::Stats(stats));
+
+                stats_start = now;
+                stats_frames = 0;
+            }
+        }
+
+        if state.simulation_paused {
+            //Nothing changes while paused, so wait for the event thread to send something,
+            //but not forever in case it's gone without saying so
+            thread::park_timeout(StdDuration::from_millis(PAUSED_POLL_INTERVAL_MS));
+        } else if state.render_paused {
+            //Only fixed updates are running, so there's no point waking up more often than they're due
+            pacing::wait_for(before, state.tick_interval());
+        } else if let Some(interval) = state.frame_interval() {
+            //Sleeps until just before the frame is due, then spins for the rest.
+            //With vsync on there's no interval, since swapping the buffers has already waited for the display
+            pacing::wait_for(before, interval);
         }
 
         // Wait on planner to finish AFTER the GPU timeout has finished, so as to not incur double waiting
@@ -415,6 +1142,15 @@ pub fn start(mut state: &mut RenderLoopState, mut context: glfw::RenderContext,
 
         delta = (cpu_diff.num_microseconds().unwrap_or(0) as systems::Delta) / 1_000_000.0;
 
+        if !state.render_paused {
+            state.frame_times.push(delta * 1000.0);
+        }
+
+        //Time spent paused isn't caught up on afterwards
+        if state.simulation_paused {
+            delta = 0.0;
+        }
+
         last = now;
     }
 
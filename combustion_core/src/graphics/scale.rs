@@ -0,0 +1,19 @@
+use glfw::Window;
+
+/// Framebuffer pixels per window coordinate on each axis, which is more than one on high-DPI displays
+pub fn content_scale(window: &Window) -> (f32, f32) {
+    let (width, height) = window.get_size();
+    let (framebuffer_width, framebuffer_height) = window.get_framebuffer_size();
+
+    // A minimized window can have a size of zero
+    let axis = |framebuffer: i32, window: i32| if window > 0 && framebuffer > 0 { framebuffer as f32 / window as f32 } else { 1.0 };
+
+    (axis(framebuffer_width, width), axis(framebuffer_height, height))
+}
+
+/// Converts a position in window coordinates, like the cursor position, into framebuffer pixels
+pub fn window_to_framebuffer(window: &Window, (x, y): (f64, f64)) -> (f64, f64) {
+    let (scale_x, scale_y) = content_scale(window);
+
+    (x * scale_x as f64, y * scale_y as f64)
+}
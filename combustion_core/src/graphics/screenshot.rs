@@ -0,0 +1,96 @@
+//! Capturing the default framebuffer to PNG files
+
+use std::path::{Path, PathBuf};
+use std::fs;
+use std::thread;
+
+use chrono::Local;
+use image::{self, ImageBuffer};
+
+use ::backend::gl::*;
+use ::backend::gl::bindings as glb;
+
+/// Directory screenshots without an explicit path are saved to
+pub const SCREENSHOT_DIRECTORY: &'static str = "screenshots";
+
+/// Timestamped path inside `SCREENSHOT_DIRECTORY`, such as `screenshots/2017-02-14_18-30-05.123.png`
+pub fn default_path() -> PathBuf {
+    let name = format!("{}.png", Local::now().format("%Y-%m-%d_%H-%M-%S%.3f"));
+
+    Path::new(SCREENSHOT_DIRECTORY).join(name)
+}
+
+/// Reads the default framebuffer as tightly packed RGB bytes, bottom row first like OpenGL returns them
+pub fn read_framebuffer(width: usize, height: usize) -> GLResult<Vec<u8>> {
+    GLFramebuffer::default().read_pixels(0, width, height, glb::RGB, glb::UNSIGNED_BYTE)
+}
+
+/// Screenshot whose pixels are still being read back from the GPU
+pub struct PendingScreenshot {
+    readback: GLPendingReadback,
+    width: usize,
+    height: usize,
+    path: Option<PathBuf>,
+}
+
+/// Starts reading back the default framebuffer without waiting for the frame to finish rendering
+pub fn capture(width: usize, height: usize, path: Option<PathBuf>) -> GLResult<PendingScreenshot> {
+    let readback = try!(GLFramebuffer::default().read_pixels_async(0, width, height, glb::RGB, glb::UNSIGNED_BYTE));
+
+    Ok(PendingScreenshot { readback: readback, width: width, height: height, path: path })
+}
+
+impl PendingScreenshot {
+    /// Saves the screenshot with `save_async` once its pixels are ready, returning whether it's done, successfully or not
+    pub fn poll(&mut self) -> bool {
+        match self.readback.poll() {
+            Ok(Some(pixels)) => {
+                save_async(pixels, self.width, self.height, self.path.take());
+                true
+            }
+            Ok(None) => false,
+            Err(err) => {
+                error!("Could not read framebuffer for screenshot: {}", err);
+                true
+            }
+        }
+    }
+}
+
+/// Flips the rows of the raw pixels and encodes them to a PNG on a worker thread, so the render thread doesn't hitch.
+///
+/// Any failure is logged rather than propagated.
+pub fn save_async(pixels: Vec<u8>, width: usize, height: usize, path: Option<PathBuf>) {
+    let spawned = thread::Builder::new().name("Screenshot".to_string()).spawn(move || {
+        let path = path.unwrap_or_else(default_path);
+
+        if let Some(parent) = path.parent() {
+            if let Err(err) = fs::create_dir_all(parent) {
+                error!("Could not create screenshot directory {}: {}", parent.display(), err);
+                return;
+            }
+        }
+
+        let row = width * 3;
+
+        // OpenGL puts the origin at the bottom left, images at the top left
+        let flipped: Vec<u8> = pixels.chunks(row).rev().flat_map(|row| row.iter().cloned()).collect();
+
+        let buffer = match ImageBuffer::from_raw(width as u32, height as u32, flipped) {
+            Some(buffer) => buffer,
+            None => {
+                error!("Invalid screenshot dimensions {}x{}", width, height);
+                return;
+            }
+        };
+
+        match image::ImageRgb8(buffer).save(&path) {
+            Ok(_) => info!("Saved screenshot to {}", path.display()),
+            Err(err) => error!("Could not save screenshot to {}: {}", path.display(), err),
+        }
+    });
+
+    if let Err(err) = spawned {
+        error!("Could not spawn screenshot thread: {}", err);
+    }
+}
@@ -0,0 +1,125 @@
+//! Watching the shader directory for edits, so shaders are reloaded as soon as they're saved
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use super::render::RenderSignal;
+
+/// Directory shaders are loaded from, relative to the working directory
+pub const SHADER_DIRECTORY: &'static str = "shaders";
+
+/// How often the shader directory is checked for changes
+pub const SHADER_POLL_INTERVAL_MS: u64 = 500;
+
+/// Latest modification time and number of files under a directory, which changes whenever a file is saved, added or removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Snapshot {
+    modified: Option<SystemTime>,
+    files: usize,
+}
+
+fn snapshot(directory: &Path, into: &mut Snapshot) -> io::Result<()> {
+    for entry in try!(fs::read_dir(directory)) {
+        let entry = try!(entry);
+        let metadata = try!(entry.metadata());
+
+        if metadata.is_dir() {
+            try!(snapshot(&entry.path(), into));
+        } else {
+            let modified = try!(metadata.modified());
+
+            into.files += 1;
+            into.modified = Some(into.modified.map_or(modified, |latest| latest.max(modified)));
+        }
+    }
+
+    Ok(())
+}
+
+fn take_snapshot(directory: &Path) -> Option<Snapshot> {
+    let mut result = Snapshot::default();
+
+    match snapshot(directory, &mut result) {
+        Ok(_) => Some(result),
+        Err(err) => {
+            warn!("Could not check {} for shader changes: {}", directory.display(), err);
+            None
+        }
+    }
+}
+
+/// Polls the shader directory on a thread of its own, sending `RenderSignal::ReloadShaders` whenever anything in it changes.
+///
+/// Included files are in the same directory, so editing one reloads every program, not just those that include it.
+pub struct ShaderWatcher {
+    running: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl ShaderWatcher {
+    pub fn spawn<P: AsRef<Path>>(directory: P, render_tx: mpsc::Sender<RenderSignal>) -> ShaderWatcher {
+        let directory: PathBuf = directory.as_ref().to_path_buf();
+
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+
+        let thread = thread::Builder::new().name("Shader watcher".to_string()).spawn(move || {
+            let mut last = take_snapshot(&directory);
+
+            info!("Watching {} for shader changes", directory.display());
+
+            while thread_running.load(Ordering::SeqCst) {
+                //Unparked early when the watcher is dropped
+                thread::park_timeout(Duration::from_millis(SHADER_POLL_INTERVAL_MS));
+
+                if !thread_running.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                let current = take_snapshot(&directory);
+
+                //Unreadable directories are skipped rather than treated as changes, since editors may briefly replace files
+                if current.is_some() && current != last {
+                    if last.is_some() {
+                        info!("Shaders changed, reloading");
+
+                        if render_tx.send(RenderSignal::ReloadShaders).is_err() {
+                            break;
+                        }
+                    }
+
+                    last = current;
+                }
+            }
+        });
+
+        match thread {
+            Ok(thread) => ShaderWatcher { running: running, thread: Some(thread) },
+            Err(err) => {
+                error!("Could not start shader watcher: {}", err);
+
+                ShaderWatcher { running: running, thread: None }
+            }
+        }
+    }
+}
+
+impl Drop for ShaderWatcher {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+
+            if thread.join().is_err() {
+                error!("Shader watcher panicked");
+            }
+        }
+    }
+}
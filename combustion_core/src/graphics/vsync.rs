@@ -0,0 +1,60 @@
+use std::ffi::CString;
+
+use glfw::ffi;
+
+/// How buffer swaps wait for the display's vertical blank
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VsyncMode {
+    Off,
+    On,
+    /// Waits for vertical blank unless the frame is late, in which case it tears rather than waiting for the next one.
+    /// Needs `EXT_swap_control_tear`, and falls back to `On` without it.
+    Adaptive,
+}
+
+impl Default for VsyncMode {
+    #[inline(always)]
+    fn default() -> VsyncMode { VsyncMode::On }
+}
+
+impl VsyncMode {
+    /// Whether swaps wait for the display, which paces frames on its own
+    #[inline]
+    pub fn is_synced(self) -> bool {
+        self != VsyncMode::Off
+    }
+
+    fn swap_interval(self) -> i32 {
+        match self {
+            VsyncMode::Off => 0,
+            VsyncMode::On => 1,
+            VsyncMode::Adaptive => -1,
+        }
+    }
+}
+
+/// Whether the current context supports adaptive vsync
+pub fn adaptive_supported() -> bool {
+    ["WGL_EXT_swap_control_tear", "GLX_EXT_swap_control_tear"].iter().any(|name| {
+        let name = CString::new(*name).unwrap();
+
+        unsafe { ffi::glfwExtensionSupported(name.as_ptr()) != 0 }
+    })
+}
+
+/// Sets the swap interval of the context current on this thread, returning the mode that was actually applied.
+///
+/// This has to be called from the thread that owns the context, which is the render thread once it's started.
+pub fn apply(mode: VsyncMode) -> VsyncMode {
+    let mode = if mode == VsyncMode::Adaptive && !adaptive_supported() {
+        warn!("Adaptive vsync is unsupported, using regular vsync instead");
+
+        VsyncMode::On
+    } else {
+        mode
+    };
+
+    unsafe { ffi::glfwSwapInterval(mode.swap_interval()); }
+
+    mode
+}
@@ -0,0 +1,476 @@
+//! Key bindings from logical actions to key chords, loaded from a file of `action = chord` lines like these:
+//!
+//! ```text
+//! # Comments start with a hash
+//! quit = Escape
+//! toggle_borderless = Shift+F11
+//! ```
+//!
+//! Actions missing from the file keep their default chords, and an action can be listed more than once to bind several chords.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use glfw::{self, Key, Modifiers};
+
+/// File the bindings are loaded from, relative to the working directory
+pub const BINDINGS_FILE: &'static str = "bindings.cfg";
+
+/// Compiled-in bindings, used for every action the bindings file doesn't mention
+pub const DEFAULT_BINDINGS: &'static str = "
+quit = Escape
+toggle_fullscreen = F11
+toggle_borderless = Shift+F11
+screenshot = F12
+toggle_wireframe = F9
+toggle_overdraw = F10
+toggle_title_stats = Insert
+toggle_uncapped = Home
+cycle_vsync = End
+pause = Pause
+toggle_mouse_look = M
+toggle_console = GraveAccent
+copy_stats = Ctrl+C
+hide_overlay = Backspace
+reload_shaders = Ctrl+R
+toggle_shader_watch = Ctrl+Shift+R
+toggle_inspect = I
+dump_world = Shift+I
+debug_view_next = Tab
+debug_view_1 = F1
+debug_view_2 = F2
+debug_view_3 = F3
+debug_view_4 = F4
+debug_view_5 = F5
+debug_view_6 = F6
+debug_view_7 = F7
+debug_view_8 = F8
+";
+
+/// Number of debug views that can be bound directly
+pub const DEBUG_VIEW_BINDINGS: u8 = 8;
+
+/// Something a key chord can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputAction {
+    Quit,
+    ToggleFullscreen,
+    ToggleBorderless,
+    Screenshot,
+    ToggleWireframe,
+    ToggleOverdraw,
+    ToggleTitleStats,
+    ToggleUncapped,
+    CycleVsync,
+    Pause,
+    /// Captures the cursor to turn the camera, or releases it
+    ToggleMouseLook,
+    /// Opens the console, or closes it along with Escape
+    ToggleConsole,
+    /// Copies the latest frame statistics to the clipboard
+    CopyStats,
+    /// Hides the texture shown by dropping one onto the window
+    HideOverlay,
+    /// Rebuilds the pipeline's shader programs from their files
+    ReloadShaders,
+    /// Starts or stops reloading shaders whenever their files change
+    ToggleShaderWatch,
+    /// Starts or stops logging everything about each object clicked on
+    ToggleInspect,
+    /// Logs how many of each component there are, and roughly how much memory they take
+    DumpWorld,
+    DebugViewNext,
+    /// Shows the debug view with the given index, counting from zero
+    DebugView(u8),
+}
+
+impl InputAction {
+    /// The action's name in bindings files
+    pub fn name(&self) -> String {
+        match *self {
+            InputAction::Quit => "quit".to_string(),
+            InputAction::ToggleFullscreen => "toggle_fullscreen".to_string(),
+            InputAction::ToggleBorderless => "toggle_borderless".to_string(),
+            InputAction::Screenshot => "screenshot".to_string(),
+            InputAction::ToggleWireframe => "toggle_wireframe".to_string(),
+            InputAction::ToggleOverdraw => "toggle_overdraw".to_string(),
+            InputAction::ToggleTitleStats => "toggle_title_stats".to_string(),
+            InputAction::ToggleUncapped => "toggle_uncapped".to_string(),
+            InputAction::CycleVsync => "cycle_vsync".to_string(),
+            InputAction::Pause => "pause".to_string(),
+            InputAction::ToggleMouseLook => "toggle_mouse_look".to_string(),
+            InputAction::ToggleConsole => "toggle_console".to_string(),
+            InputAction::CopyStats => "copy_stats".to_string(),
+            InputAction::HideOverlay => "hide_overlay".to_string(),
+            InputAction::ReloadShaders => "reload_shaders".to_string(),
+            InputAction::ToggleShaderWatch => "toggle_shader_watch".to_string(),
+            InputAction::ToggleInspect => "toggle_inspect".to_string(),
+            InputAction::DumpWorld => "dump_world".to_string(),
+            InputAction::DebugViewNext => "debug_view_next".to_string(),
+            InputAction::DebugView(index) => format!("debug_view_{}", index + 1),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<InputAction> {
+        Some(match name {
+            "quit" => InputAction::Quit,
+            "toggle_fullscreen" => InputAction::ToggleFullscreen,
+            "toggle_borderless" => InputAction::ToggleBorderless,
+            "screenshot" => InputAction::Screenshot,
+            "toggle_wireframe" => InputAction::ToggleWireframe,
+            "toggle_overdraw" => InputAction::ToggleOverdraw,
+            "toggle_title_stats" => InputAction::ToggleTitleStats,
+            "toggle_uncapped" => InputAction::ToggleUncapped,
+            "cycle_vsync" => InputAction::CycleVsync,
+            "pause" => InputAction::Pause,
+            "toggle_mouse_look" => InputAction::ToggleMouseLook,
+            "toggle_console" => InputAction::ToggleConsole,
+            "copy_stats" => InputAction::CopyStats,
+            "hide_overlay" => InputAction::HideOverlay,
+            "reload_shaders" => InputAction::ReloadShaders,
+            "toggle_shader_watch" => InputAction::ToggleShaderWatch,
+            "toggle_inspect" => InputAction::ToggleInspect,
+            "dump_world" => InputAction::DumpWorld,
+            "debug_view_next" => InputAction::DebugViewNext,
+            _ if name.starts_with("debug_view_") => {
+                match name["debug_view_".len()..].parse::<u8>() {
+                    Ok(number) if number >= 1 && number <= DEBUG_VIEW_BINDINGS => InputAction::DebugView(number - 1),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        })
+    }
+}
+
+impl Display for InputAction {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Names of every key that can be bound, as written in bindings files
+static KEY_NAMES: &'static [(&'static str, Key)] = &[
+    ("A", Key::A), ("B", Key::B), ("C", Key::C), ("D", Key::D), ("E", Key::E), ("F", Key::F), ("G", Key::G),
+    ("H", Key::H), ("I", Key::I), ("J", Key::J), ("K", Key::K), ("L", Key::L), ("M", Key::M), ("N", Key::N),
+    ("O", Key::O), ("P", Key::P), ("Q", Key::Q), ("R", Key::R), ("S", Key::S), ("T", Key::T), ("U", Key::U),
+    ("V", Key::V), ("W", Key::W), ("X", Key::X), ("Y", Key::Y), ("Z", Key::Z),
+    ("0", Key::Num0), ("1", Key::Num1), ("2", Key::Num2), ("3", Key::Num3), ("4", Key::Num4),
+    ("5", Key::Num5), ("6", Key::Num6), ("7", Key::Num7), ("8", Key::Num8), ("9", Key::Num9),
+    ("F1", Key::F1), ("F2", Key::F2), ("F3", Key::F3), ("F4", Key::F4), ("F5", Key::F5), ("F6", Key::F6),
+    ("F7", Key::F7), ("F8", Key::F8), ("F9", Key::F9), ("F10", Key::F10), ("F11", Key::F11), ("F12", Key::F12),
+    ("Escape", Key::Escape), ("Enter", Key::Enter), ("Tab", Key::Tab), ("Backspace", Key::Backspace),
+    ("Space", Key::Space), ("Insert", Key::Insert), ("Delete", Key::Delete), ("Home", Key::Home), ("End", Key::End),
+    ("PageUp", Key::PageUp), ("PageDown", Key::PageDown), ("Pause", Key::Pause), ("PrintScreen", Key::PrintScreen),
+    ("Left", Key::Left), ("Right", Key::Right), ("Up", Key::Up), ("Down", Key::Down),
+    ("Minus", Key::Minus), ("Equal", Key::Equal), ("GraveAccent", Key::GraveAccent),
+];
+
+fn key_from_name(name: &str) -> Option<Key> {
+    KEY_NAMES.iter().find(|&&(key_name, _)| key_name.eq_ignore_ascii_case(name)).map(|&(_, key)| key)
+}
+
+fn key_name(key: Key) -> Option<&'static str> {
+    KEY_NAMES.iter().find(|&&(_, named)| named == key).map(|&(name, _)| name)
+}
+
+/// A key pressed while holding exactly the given modifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub key: Key,
+    pub modifiers: Modifiers,
+}
+
+impl Chord {
+    pub fn new(key: Key, modifiers: Modifiers) -> Chord {
+        Chord { key: key, modifiers: modifiers }
+    }
+
+    /// A key without any modifiers
+    pub fn key(key: Key) -> Chord {
+        Chord::new(key, Modifiers::empty())
+    }
+
+    /// Parses chords like `F11` or `Ctrl+Shift+S`, ignoring case and spaces
+    pub fn parse(text: &str) -> Option<Chord> {
+        let mut parts: Vec<&str> = text.split('+').map(|part| part.trim()).collect();
+
+        let key = match parts.pop().and_then(key_from_name) {
+            Some(key) => key,
+            None => return None,
+        };
+
+        let mut modifiers = Modifiers::empty();
+
+        for part in parts {
+            modifiers |= match &*part.to_lowercase() {
+                "shift" => glfw::Shift,
+                "ctrl" | "control" => glfw::Control,
+                "alt" => glfw::Alt,
+                "super" => glfw::Super,
+                _ => return None,
+            };
+        }
+
+        Some(Chord::new(key, modifiers))
+    }
+}
+
+impl Display for Chord {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for &(modifier, name) in &[(glfw::Control, "Ctrl"), (glfw::Alt, "Alt"), (glfw::Super, "Super"), (glfw::Shift, "Shift")] {
+            if self.modifiers.contains(modifier) {
+                try!(write!(f, "{}+", name));
+            }
+        }
+
+        match key_name(self.key) {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{:?}", self.key),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BindingError {
+    Io(io::Error),
+    /// A line that isn't `action = chord`, with its line number
+    Syntax(usize, String),
+    /// A chord with an unknown key or modifier, with its line number
+    InvalidChord(usize, String),
+    /// A chord bound to two different actions
+    DuplicateChord(Chord, InputAction, InputAction),
+}
+
+impl From<io::Error> for BindingError {
+    fn from(err: io::Error) -> BindingError {
+        BindingError::Io(err)
+    }
+}
+
+impl Display for BindingError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            BindingError::Io(ref err) => write!(f, "{}", err),
+            BindingError::Syntax(line, ref text) => write!(f, "Expected `action = chord` on line {}, found \"{}\"", line, text),
+            BindingError::InvalidChord(line, ref text) => write!(f, "Invalid chord \"{}\" on line {}", text, line),
+            BindingError::DuplicateChord(chord, first, second) => write!(f, "{} is bound to both {} and {}", chord, first, second),
+        }
+    }
+}
+
+impl Error for BindingError {
+    fn description(&self) -> &str {
+        match *self {
+            BindingError::Io(ref err) => err.description(),
+            BindingError::Syntax(..) => "Syntax Error",
+            BindingError::InvalidChord(..) => "Invalid Chord",
+            BindingError::DuplicateChord(..) => "Duplicate Chord",
+        }
+    }
+}
+
+/// Which action each key chord triggers
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    actions: HashMap<Chord, InputAction>,
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        Bindings::empty().with_text(DEFAULT_BINDINGS).expect("Invalid default bindings")
+    }
+}
+
+impl Bindings {
+    /// Bindings with nothing bound
+    pub fn empty() -> Bindings {
+        Bindings { actions: HashMap::new() }
+    }
+
+    /// The default bindings, with anything in `text` replacing the defaults for the actions it mentions
+    pub fn parse(text: &str) -> Result<Bindings, BindingError> {
+        Bindings::default().with_text(text)
+    }
+
+    /// Loads bindings from a file with `parse`, or the defaults if the file doesn't exist
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Bindings, BindingError> {
+        let mut text = String::new();
+
+        match File::open(path.as_ref()) {
+            Ok(mut file) => { try!(file.read_to_string(&mut text)); }
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                info!("No bindings file at {}, using the defaults", path.as_ref().display());
+
+                return Ok(Bindings::default());
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        Bindings::parse(&text)
+    }
+
+    fn with_text(mut self, text: &str) -> Result<Bindings, BindingError> {
+        let mut entries = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut sides = line.splitn(2, '=');
+
+            let (name, chord) = match (sides.next(), sides.next()) {
+                (Some(name), Some(chord)) => (name.trim(), chord.trim()),
+                _ => return Err(BindingError::Syntax(index + 1, line.to_string())),
+            };
+
+            let action = match InputAction::from_name(name) {
+                Some(action) => action,
+                None => {
+                    warn!("Ignoring unknown action \"{}\" on line {} of the bindings", name, index + 1);
+
+                    continue;
+                }
+            };
+
+            match Chord::parse(chord) {
+                Some(chord) => entries.push((action, chord)),
+                None => return Err(BindingError::InvalidChord(index + 1, chord.to_string())),
+            }
+        }
+
+        // Actions that are mentioned lose their previous chords, so moving one doesn't conflict with its old self
+        self.actions.retain(|_, action| !entries.iter().any(|&(mentioned, _)| mentioned == *action));
+
+        for (action, chord) in entries {
+            try!(self.bind(action, chord));
+        }
+
+        Ok(self)
+    }
+
+    /// The action bound to a key pressed with the given modifiers
+    #[inline]
+    pub fn action(&self, key: Key, modifiers: Modifiers) -> Option<InputAction> {
+        self.actions.get(&Chord::new(key, modifiers)).cloned()
+    }
+
+    /// Every chord bound to an action
+    pub fn chords(&self, action: InputAction) -> Vec<Chord> {
+        self.actions.iter().filter(|&(_, &bound)| bound == action).map(|(&chord, _)| chord).collect()
+    }
+
+    /// Adds a chord to an action, failing if it's already bound to a different one
+    pub fn bind(&mut self, action: InputAction, chord: Chord) -> Result<(), BindingError> {
+        match self.actions.get(&chord) {
+            Some(&existing) if existing != action => return Err(BindingError::DuplicateChord(chord, existing, action)),
+            _ => {}
+        }
+
+        self.actions.insert(chord, action);
+
+        Ok(())
+    }
+
+    /// Replaces every chord of an action with `chord`, failing without changing anything if it's bound to a different action
+    pub fn rebind(&mut self, action: InputAction, chord: Chord) -> Result<(), BindingError> {
+        match self.actions.get(&chord) {
+            Some(&existing) if existing != action => return Err(BindingError::DuplicateChord(chord, existing, action)),
+            _ => {}
+        }
+
+        self.actions.retain(|_, bound| *bound != action);
+        self.actions.insert(chord, action);
+
+        Ok(())
+    }
+
+    /// Removes a chord, returning the action it was bound to
+    pub fn unbind(&mut self, chord: Chord) -> Option<InputAction> {
+        self.actions.remove(&chord)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use glfw::{self, Key};
+
+    #[test]
+    fn test_parse_chord() {
+        assert_eq!(Chord::parse("F11"), Some(Chord::key(Key::F11)));
+        assert_eq!(Chord::parse(" ctrl + Shift+r "), Some(Chord::new(Key::R, glfw::Control | glfw::Shift)));
+        assert_eq!(Chord::parse("Hyper+R"), None);
+        assert_eq!(Chord::parse("Ctrl+"), None);
+    }
+
+    #[test]
+    fn test_chord_display_round_trip() {
+        let chord = Chord::new(Key::S, glfw::Shift | glfw::Control);
+
+        assert_eq!(chord.to_string(), "Ctrl+Shift+S");
+        assert_eq!(Chord::parse(&chord.to_string()), Some(chord));
+    }
+
+    #[test]
+    fn test_default_bindings() {
+        let bindings = Bindings::default();
+
+        assert_eq!(bindings.action(Key::Escape, glfw::Modifiers::empty()), Some(InputAction::Quit));
+        assert_eq!(bindings.action(Key::F11, glfw::Shift), Some(InputAction::ToggleBorderless));
+        assert_eq!(bindings.action(Key::F3, glfw::Modifiers::empty()), Some(InputAction::DebugView(2)));
+    }
+
+    #[test]
+    fn test_parse_replaces_mentioned_actions() {
+        let bindings = Bindings::parse("# Quit with Q instead\nquit = Q\nquit = Ctrl+Q\nunknown_action = F1\n").unwrap();
+
+        let mut chords = bindings.chords(InputAction::Quit);
+
+        chords.sort_by_key(|chord| chord.modifiers.bits());
+
+        assert_eq!(chords, vec![Chord::key(Key::Q), Chord::new(Key::Q, glfw::Control)]);
+        assert_eq!(bindings.action(Key::Escape, glfw::Modifiers::empty()), None);
+
+        // Unmentioned actions keep their defaults
+        assert_eq!(bindings.action(Key::F12, glfw::Modifiers::empty()), Some(InputAction::Screenshot));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        match Bindings::parse("quit Escape") {
+            Err(BindingError::Syntax(1, _)) => {}
+            other => panic!("Expected a syntax error, got {:?}", other),
+        }
+
+        match Bindings::parse("\nquit = Nope") {
+            Err(BindingError::InvalidChord(2, _)) => {}
+            other => panic!("Expected an invalid chord, got {:?}", other),
+        }
+
+        match Bindings::parse("pause = F12") {
+            Err(BindingError::DuplicateChord(_, InputAction::Screenshot, InputAction::Pause)) => {}
+            other => panic!("Expected a duplicate chord, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_action_names() {
+        for index in 0..DEBUG_VIEW_BINDINGS {
+            let action = InputAction::DebugView(index);
+
+            assert_eq!(InputAction::from_name(&action.name()), Some(action));
+        }
+
+        assert_eq!(InputAction::from_name("toggle_console"), Some(InputAction::ToggleConsole));
+        assert_eq!(InputAction::from_name("debug_view_0"), None);
+    }
+}
@@ -0,0 +1,162 @@
+//! Mouse and keyboard state for the mouse-look camera, gathered on the main thread and sent to the render thread
+
+use glfw::{Action, CursorMode, Key, Window};
+
+/// Keys held to move the camera, in the order of the `MovementKeys` bits
+static MOVEMENT_KEYS: [Key; 8] = [
+    Key::W, Key::S, Key::A, Key::D, Key::Space, Key::LeftControl, Key::LeftShift, Key::RightShift,
+];
+
+const FORWARD: u8 = 1 << 0;
+const BACKWARD: u8 = 1 << 1;
+const LEFT: u8 = 1 << 2;
+const RIGHT: u8 = 1 << 3;
+const UP: u8 = 1 << 4;
+const DOWN: u8 = 1 << 5;
+const FAST: u8 = (1 << 6) | (1 << 7);
+
+/// Camera movement since the last `CameraInput` was sent
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CameraInput {
+    /// Cursor movement in window coordinates, to the right and down
+    pub look: (f32, f32),
+    /// Held movement directions as right, up and forward, each from -1 to 1
+    pub movement: (f32, f32, f32),
+    /// Whether the speed modifier is held
+    pub fast: bool,
+}
+
+impl CameraInput {
+    /// Takes the movement keys from a newer input, adding up cursor movement from both
+    pub fn merge(&mut self, newer: CameraInput) {
+        self.look = (self.look.0 + newer.look.0, self.look.1 + newer.look.1);
+        self.movement = newer.movement;
+        self.fast = newer.fast;
+    }
+
+    /// Removes the cursor movement, once it's been applied
+    #[inline]
+    pub fn take_look(&mut self) -> (f32, f32) {
+        ::std::mem::replace(&mut self.look, (0.0, 0.0))
+    }
+}
+
+/// Tracks cursor capture and held movement keys on the main thread
+#[derive(Debug, Clone, Default)]
+pub struct MouseLook {
+    captured: bool,
+    /// Whether `resume` captures the cursor again, because `suspend` released it
+    suspended: bool,
+    last_cursor: Option<(f64, f64)>,
+    held: u8,
+}
+
+fn axis(held: u8, positive: u8, negative: u8) -> f32 {
+    (if held & positive != 0 { 1.0 } else { 0.0 }) - (if held & negative != 0 { 1.0 } else { 0.0 })
+}
+
+impl MouseLook {
+    pub fn new() -> MouseLook {
+        MouseLook::default()
+    }
+
+    #[inline]
+    pub fn is_captured(&self) -> bool { self.captured }
+
+    /// Hides and captures the cursor so it turns the camera, or gives it back.
+    ///
+    /// The render thread should be told with `RenderSignal::CursorCapture` so it can ignore the jump when capture starts.
+    pub fn set_captured(&mut self, window: &mut Window, captured: bool) {
+        self.captured = captured;
+        //An explicit change replaces whatever `suspend` remembered
+        self.suspended = false;
+        //The first position after capturing can be anywhere, so it's only used as the starting point
+        self.last_cursor = None;
+
+        window.set_cursor_mode(if captured { CursorMode::Disabled } else { CursorMode::Normal });
+    }
+
+    /// Releases the cursor while the window loses focus or the game is paused, returning whether it was captured.
+    ///
+    /// `resume` captures it again, but only if it was captured when this was called.
+    pub fn suspend(&mut self, window: &mut Window) -> bool {
+        if !self.captured {
+            return false;
+        }
+
+        self.set_captured(window, false);
+        self.suspended = true;
+
+        true
+    }
+
+    /// Captures the cursor again if `suspend` released it, returning whether it did
+    pub fn resume(&mut self, window: &mut Window) -> bool {
+        if !self.suspended {
+            return false;
+        }
+
+        self.set_captured(window, true);
+
+        true
+    }
+
+    #[inline]
+    pub fn toggle_capture(&mut self, window: &mut Window) {
+        let captured = !self.captured;
+
+        self.set_captured(window, captured);
+    }
+
+    /// Forgets held keys, such as when the window loses focus and their releases would be missed
+    pub fn release_all(&mut self) -> CameraInput {
+        self.held = 0;
+
+        self.input((0.0, 0.0))
+    }
+
+    fn input(&self, look: (f32, f32)) -> CameraInput {
+        CameraInput {
+            look: look,
+            movement: (axis(self.held, RIGHT, LEFT), axis(self.held, UP, DOWN), axis(self.held, FORWARD, BACKWARD)),
+            fast: self.held & FAST != 0,
+        }
+    }
+
+    /// Input to send for a cursor position event, if the cursor is captured
+    pub fn cursor_moved(&mut self, x: f64, y: f64) -> Option<CameraInput> {
+        if !self.captured {
+            return None;
+        }
+
+        let look = match self.last_cursor {
+            Some((last_x, last_y)) => ((x - last_x) as f32, (y - last_y) as f32),
+            None => (0.0, 0.0),
+        };
+
+        self.last_cursor = Some((x, y));
+
+        Some(self.input(look))
+    }
+
+    #[inline]
+    pub fn is_movement_key(key: Key) -> bool {
+        MOVEMENT_KEYS.contains(&key)
+    }
+
+    /// Input to send for a key event, if it's a movement key
+    pub fn key(&mut self, key: Key, action: Action) -> Option<CameraInput> {
+        let bit = match MOVEMENT_KEYS.iter().position(|&movement_key| movement_key == key) {
+            Some(index) => 1 << index,
+            None => return None,
+        };
+
+        match action {
+            Action::Press => self.held |= bit,
+            Action::Release => self.held &= !bit,
+            Action::Repeat => return None,
+        }
+
+        Some(self.input((0.0, 0.0)))
+    }
+}
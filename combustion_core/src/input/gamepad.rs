@@ -0,0 +1,461 @@
+//! Gamepad and joystick polling.
+//!
+//! GLFW 3.2 has no gamepad mappings, so pads are matched against the built-in layouts below by their reported
+//! button and axis counts. Pads that don't match still report raw buttons and axes.
+
+use glfw::{Glfw, JoystickId};
+
+/// Most pads tracked at once. Any more are ignored until one of them disconnects.
+pub const MAX_GAMEPADS: usize = 4;
+
+/// Axis values closer to zero than this are treated as zero
+pub const DEFAULT_DEAD_ZONE: f32 = 0.15;
+
+/// Only the first 32 buttons of a joystick are tracked
+pub const MAX_BUTTONS: usize = 32;
+
+/// Every joystick GLFW can report, checked in order when a pad slot is free
+static JOYSTICKS: [JoystickId; 16] = [
+    JoystickId::Joystick1, JoystickId::Joystick2, JoystickId::Joystick3, JoystickId::Joystick4,
+    JoystickId::Joystick5, JoystickId::Joystick6, JoystickId::Joystick7, JoystickId::Joystick8,
+    JoystickId::Joystick9, JoystickId::Joystick10, JoystickId::Joystick11, JoystickId::Joystick12,
+    JoystickId::Joystick13, JoystickId::Joystick14, JoystickId::Joystick15, JoystickId::Joystick16,
+];
+
+/// Buttons of the standard gamepad layout, named after an Xbox controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    A,
+    B,
+    X,
+    Y,
+    LeftBumper,
+    RightBumper,
+    Back,
+    Start,
+    Guide,
+    LeftThumb,
+    RightThumb,
+    DpadUp,
+    DpadRight,
+    DpadDown,
+    DpadLeft,
+}
+
+/// Axes of the standard gamepad layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+/// Raw button and axis indices of a standard gamepad, indexed by `GamepadButton` and `GamepadAxis`
+#[derive(Debug)]
+pub struct GamepadLayout {
+    pub name: &'static str,
+    pub button_count: usize,
+    pub axis_count: usize,
+    pub buttons: [Option<usize>; 15],
+    pub axes: [Option<usize>; 6],
+}
+
+/// XInput pads as reported by GLFW on Windows, with the d-pad as buttons and no guide button
+#[cfg(windows)]
+pub static STANDARD_LAYOUTS: &'static [GamepadLayout] = &[
+    GamepadLayout {
+        name: "XInput",
+        button_count: 14,
+        axis_count: 6,
+        buttons: [Some(0), Some(1), Some(2), Some(3), Some(4), Some(5), Some(6), Some(7), None,
+                  Some(8), Some(9), Some(10), Some(11), Some(12), Some(13)],
+        axes: [Some(0), Some(1), Some(2), Some(3), Some(4), Some(5)],
+    },
+];
+
+/// XInput pads as reported by the Linux `xpad` driver, with the d-pad as a pair of axes GLFW 3.2 can't map to buttons
+#[cfg(not(windows))]
+pub static STANDARD_LAYOUTS: &'static [GamepadLayout] = &[
+    GamepadLayout {
+        name: "xpad",
+        button_count: 11,
+        axis_count: 8,
+        buttons: [Some(0), Some(1), Some(2), Some(3), Some(4), Some(5), Some(6), Some(7), Some(8),
+                  Some(9), Some(10), None, None, None, None],
+        axes: [Some(0), Some(1), Some(3), Some(4), Some(2), Some(5)],
+    },
+];
+
+impl GamepadLayout {
+    /// The standard layout matching a joystick's button and axis counts, if any
+    pub fn find(button_count: usize, axis_count: usize) -> Option<&'static GamepadLayout> {
+        STANDARD_LAYOUTS.iter().find(|layout| layout.button_count == button_count && layout.axis_count == axis_count)
+    }
+
+    /// Whether a raw axis is one of the triggers, which rest at -1 rather than in the middle
+    pub fn is_trigger(&self, index: usize) -> bool {
+        self.axes[GamepadAxis::LeftTrigger as usize] == Some(index) ||
+        self.axes[GamepadAxis::RightTrigger as usize] == Some(index)
+    }
+}
+
+/// Connection changes found by `Gamepads::poll`, with the pad slot they happened in
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadEvent {
+    /// A joystick took a pad slot, with its name
+    Connected(usize, String),
+    Disconnected(usize),
+}
+
+/// One connected joystick
+#[derive(Debug, Clone)]
+pub struct Pad {
+    pub id: JoystickId,
+    pub name: String,
+    /// Standard layout of the pad, if it's a known gamepad
+    pub layout: Option<&'static GamepadLayout>,
+    /// Axis values with the dead zone applied, from -1 to 1, or from 0 to 1 for the triggers of a standard layout
+    pub axes: Vec<f32>,
+    /// Bits of the raw buttons currently held down
+    buttons: u32,
+    /// Bits of the raw buttons pressed since edges were last cleared
+    pressed: u32,
+    /// Bits of the raw buttons released since edges were last cleared
+    released: u32,
+}
+
+impl Pad {
+    fn new(id: JoystickId, name: String, button_count: usize, axis_count: usize) -> Pad {
+        Pad {
+            id: id,
+            name: name,
+            layout: GamepadLayout::find(button_count, axis_count),
+            axes: vec![0.0; axis_count],
+            buttons: 0,
+            pressed: 0,
+            released: 0,
+        }
+    }
+
+    #[inline]
+    pub fn is_standard(&self) -> bool { self.layout.is_some() }
+
+    fn button_bit(&self, button: GamepadButton) -> Option<u32> {
+        self.layout.and_then(|layout| layout.buttons[button as usize]).map(|index| 1 << index)
+    }
+
+    fn update_buttons(&mut self, buttons: u32) {
+        self.pressed |= buttons & !self.buttons;
+        self.released |= self.buttons & !buttons;
+        self.buttons = buttons;
+    }
+
+    #[inline]
+    pub fn is_raw_pressed(&self, index: usize) -> bool {
+        index < MAX_BUTTONS && self.buttons & (1 << index) != 0
+    }
+
+    #[inline]
+    pub fn raw_just_pressed(&self, index: usize) -> bool {
+        index < MAX_BUTTONS && self.pressed & (1 << index) != 0
+    }
+
+    #[inline]
+    pub fn raw_just_released(&self, index: usize) -> bool {
+        index < MAX_BUTTONS && self.released & (1 << index) != 0
+    }
+
+    /// Whether a standard button is held down. Always false for pads without a standard layout.
+    pub fn is_pressed(&self, button: GamepadButton) -> bool {
+        self.button_bit(button).map_or(false, |bit| self.buttons & bit != 0)
+    }
+
+    /// Whether a standard button went down since edges were last cleared, even if it's been released again
+    pub fn just_pressed(&self, button: GamepadButton) -> bool {
+        self.button_bit(button).map_or(false, |bit| self.pressed & bit != 0)
+    }
+
+    /// Whether a standard button came up since edges were last cleared
+    pub fn just_released(&self, button: GamepadButton) -> bool {
+        self.button_bit(button).map_or(false, |bit| self.released & bit != 0)
+    }
+
+    /// Value of a standard axis, or zero for pads without a standard layout. Triggers go from 0 when released to 1.
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.layout.and_then(|layout| layout.axes[axis as usize])
+                   .and_then(|index| self.axes.get(index).cloned())
+                   .unwrap_or(0.0)
+    }
+}
+
+/// State of every pad slot, as sent to the render thread and published to the world as a resource.
+///
+/// Button edges accumulate until `clear_edges` is called, so a press shorter than a fixed update tick isn't lost.
+#[derive(Debug, Clone)]
+pub struct GamepadState {
+    /// Always `MAX_GAMEPADS` slots long
+    pub pads: Vec<Option<Pad>>,
+}
+
+impl Default for GamepadState {
+    #[inline(always)]
+    fn default() -> GamepadState { GamepadState::new() }
+}
+
+impl GamepadState {
+    pub fn new() -> GamepadState {
+        GamepadState { pads: vec![None; MAX_GAMEPADS] }
+    }
+
+    #[inline]
+    pub fn pad(&self, slot: usize) -> Option<&Pad> {
+        self.pads.get(slot).and_then(|pad| pad.as_ref())
+    }
+
+    /// Connected pads with their slots
+    pub fn connected<'a>(&'a self) -> Box<Iterator<Item = (usize, &'a Pad)> + 'a> {
+        Box::new(self.pads.iter().enumerate().filter_map(|(slot, pad)| pad.as_ref().map(|pad| (slot, pad))))
+    }
+
+    #[inline]
+    pub fn any_connected(&self) -> bool {
+        self.pads.iter().any(|pad| pad.is_some())
+    }
+
+    #[inline]
+    pub fn is_pressed(&self, slot: usize, button: GamepadButton) -> bool {
+        self.pad(slot).map_or(false, |pad| pad.is_pressed(button))
+    }
+
+    #[inline]
+    pub fn just_pressed(&self, slot: usize, button: GamepadButton) -> bool {
+        self.pad(slot).map_or(false, |pad| pad.just_pressed(button))
+    }
+
+    #[inline]
+    pub fn just_released(&self, slot: usize, button: GamepadButton) -> bool {
+        self.pad(slot).map_or(false, |pad| pad.just_released(button))
+    }
+
+    #[inline]
+    pub fn axis(&self, slot: usize, axis: GamepadAxis) -> f32 {
+        self.pad(slot).map_or(0.0, |pad| pad.axis(axis))
+    }
+
+    /// Takes the current buttons and axes from a newer state, keeping the edges of both
+    pub fn merge(&mut self, newer: GamepadState) {
+        for (pad, newer) in self.pads.iter_mut().zip(newer.pads.into_iter()) {
+            *pad = match (pad.take(), newer) {
+                (Some(old), Some(mut newer)) => {
+                    if old.id == newer.id {
+                        newer.pressed |= old.pressed;
+                        newer.released |= old.released;
+                    }
+
+                    Some(newer)
+                }
+                (_, newer) => newer,
+            };
+        }
+    }
+
+    /// Forgets button edges, once everything interested in them has had a chance to see them
+    pub fn clear_edges(&mut self) {
+        for pad in self.pads.iter_mut().filter_map(|pad| pad.as_mut()) {
+            pad.pressed = 0;
+            pad.released = 0;
+        }
+    }
+}
+
+/// Scales an axis value so the dead zone maps to zero and the rest still covers the full range
+pub fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value.abs() <= dead_zone {
+        0.0
+    } else {
+        value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone)
+    }
+}
+
+/// Applies the dead zone to raw axis values.
+///
+/// Triggers of a standard layout are first moved from -1 to 1 into 0 to 1, so the dead zone is around their resting position.
+pub fn read_axes(raw: &[f32], layout: Option<&GamepadLayout>, dead_zone: f32) -> Vec<f32> {
+    raw.iter().enumerate().map(|(index, &value)| {
+        if layout.map_or(false, |layout| layout.is_trigger(index)) {
+            apply_dead_zone((value + 1.0) * 0.5, dead_zone)
+        } else {
+            apply_dead_zone(value, dead_zone)
+        }
+    }).collect()
+}
+
+/// Polls GLFW joysticks into pad slots, from the thread that owns GLFW
+pub struct Gamepads {
+    state: GamepadState,
+    dead_zone: f32,
+    callback: Option<Box<FnMut(&GamepadEvent)>>,
+}
+
+impl Default for Gamepads {
+    fn default() -> Gamepads { Gamepads::new() }
+}
+
+impl Gamepads {
+    pub fn new() -> Gamepads {
+        Gamepads {
+            state: GamepadState::new(),
+            dead_zone: DEFAULT_DEAD_ZONE,
+            callback: None,
+        }
+    }
+
+    #[inline]
+    pub fn state(&self) -> &GamepadState { &self.state }
+
+    #[inline]
+    pub fn dead_zone(&self) -> f32 { self.dead_zone }
+
+    /// Sets the dead zone applied to every axis, clamped between 0 and 0.95
+    pub fn set_dead_zone(&mut self, dead_zone: f32) {
+        self.dead_zone = dead_zone.max(0.0).min(0.95);
+    }
+
+    /// Calls `callback` with every connection and disconnection found by `poll`
+    pub fn set_callback<F>(&mut self, callback: F) where F: FnMut(&GamepadEvent) + 'static {
+        self.callback = Some(Box::new(callback));
+    }
+
+    fn is_tracked(&self, id: JoystickId) -> bool {
+        self.state.pads.iter().any(|pad| pad.as_ref().map_or(false, |pad| pad.id == id))
+    }
+
+    /// Reads every joystick, returning connection changes since the last poll.
+    ///
+    /// Pads keep their slot for as long as they stay connected, and new ones take the first free slot.
+    pub fn poll(&mut self, glfw: &Glfw) -> Vec<GamepadEvent> {
+        let mut events = Vec::new();
+
+        for slot in 0..MAX_GAMEPADS {
+            let id = match self.state.pads[slot] {
+                Some(ref pad) => pad.id,
+                None => continue,
+            };
+
+            let joystick = glfw.get_joystick(id);
+
+            if !joystick.is_present() {
+                self.state.pads[slot] = None;
+                events.push(GamepadEvent::Disconnected(slot));
+                continue;
+            }
+
+            let dead_zone = self.dead_zone;
+            let buttons = joystick.get_buttons();
+            let axes = joystick.get_axes();
+
+            let pad = self.state.pads[slot].as_mut().unwrap();
+
+            pad.update_buttons(buttons.iter().take(MAX_BUTTONS).enumerate()
+                                      .fold(0, |bits, (index, &state)| if state != 0 { bits | (1 << index) } else { bits }));
+
+            pad.axes = read_axes(&axes, pad.layout, dead_zone);
+        }
+
+        for &id in JOYSTICKS.iter() {
+            let free = match self.state.pads.iter().position(|pad| pad.is_none()) {
+                Some(free) => free,
+                None => break,
+            };
+
+            if self.is_tracked(id) {
+                continue;
+            }
+
+            let joystick = glfw.get_joystick(id);
+
+            if joystick.is_present() {
+                let name = joystick.get_name();
+                let pad = Pad::new(id, name.clone(), joystick.get_buttons().len(), joystick.get_axes().len());
+
+                if pad.is_standard() {
+                    info!("Gamepad \"{}\" connected in slot {}", name, free);
+                } else {
+                    info!("Joystick \"{}\" connected in slot {}, without a standard layout", name, free);
+                }
+
+                self.state.pads[free] = Some(pad);
+                events.push(GamepadEvent::Connected(free, name));
+            }
+        }
+
+        if let Some(ref mut callback) = self.callback {
+            for event in &events {
+                callback(event);
+            }
+        }
+
+        events
+    }
+
+    /// The state to send on to the render thread, clearing button edges so each one is only sent once
+    pub fn take_state(&mut self) -> GamepadState {
+        let state = self.state.clone();
+
+        self.state.clear_edges();
+
+        state
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dead_zone() {
+        assert_eq!(apply_dead_zone(0.1, 0.2), 0.0);
+        assert_eq!(apply_dead_zone(-0.2, 0.2), 0.0);
+        assert_eq!(apply_dead_zone(1.0, 0.2), 1.0);
+        assert_eq!(apply_dead_zone(-1.0, 0.2), -1.0);
+        assert!((apply_dead_zone(0.6, 0.2) - 0.5).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn test_trigger_axes() {
+        let layout = &STANDARD_LAYOUTS[0];
+
+        let left = layout.axes[GamepadAxis::LeftTrigger as usize].unwrap();
+        let right = layout.axes[GamepadAxis::RightTrigger as usize].unwrap();
+        let stick = layout.axes[GamepadAxis::LeftX as usize].unwrap();
+
+        //Released triggers and a centered stick
+        let mut raw = vec![0.0; layout.axis_count];
+        raw[left] = -1.0;
+        raw[right] = -1.0;
+
+        let axes = read_axes(&raw, Some(layout), 0.2);
+
+        assert_eq!(axes[left], 0.0);
+        assert_eq!(axes[right], 0.0);
+        assert_eq!(axes[stick], 0.0);
+
+        //Fully pulled triggers, and a trigger pulled a little past its dead zone
+        raw[left] = 1.0;
+        raw[right] = -0.2;
+
+        let axes = read_axes(&raw, Some(layout), 0.2);
+
+        assert_eq!(axes[left], 1.0);
+        assert!((axes[right] - 0.25).abs() < 1.0e-6);
+
+        //Without a layout every axis is centered
+        let axes = read_axes(&raw, None, 0.2);
+
+        assert_eq!(axes[left], 1.0);
+        assert_eq!(axes[right], 0.0);
+    }
+}
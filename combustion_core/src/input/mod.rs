@@ -0,0 +1,9 @@
+pub mod bindings;
+pub mod gamepad;
+pub mod camera;
+pub mod text;
+
+pub use self::bindings::{Bindings, BindingError, Chord, InputAction, BINDINGS_FILE};
+pub use self::gamepad::{Gamepads, GamepadState, GamepadEvent, GamepadButton, GamepadAxis};
+pub use self::camera::{CameraInput, MouseLook};
+pub use self::text::{TextCapture, TextEdit};
@@ -0,0 +1,112 @@
+//! Text capture on the main thread, turning character and editing key events into `TextEdit`s
+//!
+//! While capture is active, key bindings and camera movement should be skipped so typing doesn't trigger them.
+
+use glfw::{self, Action, Key, Modifiers, Window};
+
+pub use common::line_edit::TextEdit;
+
+#[derive(Debug, Clone, Default)]
+pub struct TextCapture {
+    active: bool,
+    /// Set when capture began with a printable key, whose character event follows its key event
+    skip_next_char: bool,
+}
+
+impl TextCapture {
+    pub fn new() -> TextCapture {
+        TextCapture::default()
+    }
+
+    #[inline]
+    pub fn is_active(&self) -> bool { self.active }
+
+    /// Starts sending typed text and editing keys to `char` and `key` instead of the bindings
+    #[inline]
+    pub fn begin(&mut self) {
+        self.active = true;
+        self.skip_next_char = false;
+    }
+
+    /// Same as `begin`, but for capture started by pressing `key`, so a printable key doesn't type itself.
+    ///
+    /// Chords with Ctrl, Alt or Super don't produce a character event, so nothing is skipped for them.
+    pub fn begin_from_key(&mut self, key: Key, modifiers: Modifiers) {
+        self.begin();
+
+        //Printable keys have the values of their ASCII characters
+        self.skip_next_char = (key as i32) < 256 && (modifiers - glfw::Shift).is_empty();
+    }
+
+    #[inline]
+    pub fn end(&mut self) {
+        self.active = false;
+    }
+
+    /// Edit for a `WindowEvent::Char`, if capture is active
+    pub fn char(&mut self, c: char) -> Option<TextEdit> {
+        if !self.active {
+            None
+        } else if self.skip_next_char {
+            self.skip_next_char = false;
+
+            None
+        } else {
+            Some(TextEdit::Char(c))
+        }
+    }
+
+    /// Edit for a key press or repeat, if capture is active and it's an editing key.
+    ///
+    /// Ctrl+V pastes the clipboard, which has to be read here because only the main thread can.
+    pub fn key(&self, window: &Window, key: Key, action: Action, modifiers: Modifiers) -> Option<TextEdit> {
+        if !self.active || action == Action::Release {
+            return None;
+        }
+
+        Some(match key {
+            Key::Backspace => TextEdit::Backspace,
+            Key::Delete => TextEdit::Delete,
+            Key::Left => TextEdit::Left,
+            Key::Right => TextEdit::Right,
+            Key::Home => TextEdit::Home,
+            Key::End => TextEdit::End,
+            Key::Enter | Key::KpEnter => TextEdit::Submit,
+            Key::V if modifiers.contains(glfw::Control) => TextEdit::Paste(window.get_clipboard_string()),
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_begin_from_printable_key() {
+        let mut capture = TextCapture::new();
+
+        capture.begin_from_key(Key::GraveAccent, Modifiers::empty());
+
+        assert_eq!(capture.char('`'), None);
+        assert_eq!(capture.char('a'), Some(TextEdit::Char('a')));
+
+        capture.begin_from_key(Key::GraveAccent, glfw::Shift);
+
+        assert_eq!(capture.char('~'), None);
+    }
+
+    #[test]
+    fn test_begin_from_chord() {
+        let mut capture = TextCapture::new();
+
+        //Ctrl+T never types a 't', so the first character typed afterwards has to get through
+        capture.begin_from_key(Key::T, glfw::Control);
+
+        assert_eq!(capture.char('a'), Some(TextEdit::Char('a')));
+
+        capture.begin_from_key(Key::F1, Modifiers::empty());
+
+        assert_eq!(capture.char('a'), Some(TextEdit::Char('a')));
+    }
+}
@@ -6,13 +6,16 @@
 extern crate lazy_static;
 #[macro_use]
 extern crate enum_primitive;
+extern crate chrono;
+extern crate image;
 extern crate libc;
 extern crate nalgebra;
 extern crate num_traits;
 extern crate time;
 extern crate num_cpus;
 extern crate vec_map;
-extern crate lazy;
+extern crate glfw;
+extern crate capnp;
 
 #[macro_use]
 pub extern crate combustion_common as common;
@@ -34,5 +37,6 @@ pub mod scheduler;
 //pub mod storage;
 //pub mod scene;
 //pub mod graphics;
+pub mod input;
 //pub mod game;
 //pub mod scripting;
\ No newline at end of file
@@ -0,0 +1,73 @@
+//! Readable reports of entities and the whole world, for working out why something looks wrong
+//!
+//! See `ecs::debug`, which this re-exports along with formatters for the components only the engine has.
+
+pub use ecs::debug::*;
+
+/// Registries with every built-in component
+pub trait BuiltinFormatters {
+    /// Creates a registry with every built-in component
+    fn builtin() -> Self;
+}
+
+impl BuiltinFormatters for DebugRegistry {
+    fn builtin() -> DebugRegistry {
+        let mut registry = DebugRegistry::new();
+
+        builtin::register_all(&mut registry);
+
+        registry
+    }
+}
+
+/// Formatters of the built-in components
+pub mod builtin {
+    use ecs;
+
+    use components;
+    use backend::gl::GLTexture;
+
+    use super::{DebugRegistry, asset_state};
+
+    pub fn register_all(registry: &mut DebugRegistry) {
+        use components::mesh_renderer::{Mesh, Material, Component as MeshRenderer};
+
+        ecs::debug::builtin::register_all::<Mesh, Material, GLTexture>(registry);
+
+        //Replaces the generic one, since only here is it known what's in a mesh
+        registry.register("mesh_renderer", |renderer: &MeshRenderer| {
+            let mut text = format!("mesh {}{}{}", asset_state(&renderer.mesh),
+                                   if renderer.visible { "" } else { ", hidden" },
+                                   if renderer.cast_shadows { "" } else { ", no shadows" });
+
+            if let Some(mesh) = renderer.mesh.get() {
+                text.push_str(&format!(", {} submeshes", mesh.submeshes.len()));
+            }
+
+            for (index, material) in renderer.materials.iter().enumerate() {
+                text.push_str(&format!("\nmaterial {}: {}", index, asset_state(material)));
+            }
+
+            text
+        });
+
+        registry.register_debug::<components::scene_id::Component>("scene_id");
+        registry.register_debug::<components::light::Component>("light");
+        registry.register("instanced", |_: &components::instanced::Component| "yes".to_string());
+
+        registry.register_debug::<components::position::Component>("position");
+        registry.register_debug::<components::isometry::Component>("isometry");
+        registry.register_debug::<components::rotation::Component>("rotation");
+        registry.register("quaternion_rotation", |rotation: &components::quaternion_rotation::Component| format!("{:?}", rotation.0));
+        registry.register_debug::<components::scale::Component>("scale");
+        registry.register("matrix_transform", |transform: &components::transform::Component| {
+            let m = &transform.matrix;
+
+            format!("translation ({:.3}, {:.3}, {:.3})", m.m14, m.m24, m.m34)
+        });
+        registry.register_debug::<components::constraints::lookat::Component>("lookat");
+
+        registry.register_debug::<components::physics::Component>("physics");
+        registry.register("effector", |effector: &components::effector::Component| format!("ran: {}", effector.ran));
+    }
+}
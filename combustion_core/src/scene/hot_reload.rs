@@ -0,0 +1,15 @@
+//! Reloading a scene file into a running world, keeping whatever happened at runtime that the file doesn't change
+//!
+//! See `ecs::hot_reload`, which this re-exports along with a watcher that tells the render loop to reload.
+
+use std::path::Path;
+use std::sync::mpsc;
+
+use graphics::render::RenderSignal;
+
+pub use ecs::hot_reload::*;
+
+/// Polls a scene file on a thread of its own, sending `RenderSignal::ReloadScene` whenever it changes
+pub fn watch<P: AsRef<Path>>(path: P, render_tx: mpsc::Sender<RenderSignal>) -> SceneWatcher {
+    SceneWatcher::spawn(path, render_tx, RenderSignal::ReloadScene)
+}
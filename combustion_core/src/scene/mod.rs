@@ -3,5 +3,8 @@ pub mod mesh_cache;
 pub mod graph;
 pub mod sourcemap;
 pub mod loading;
+pub mod storage;
+pub mod hot_reload;
+pub mod debug;
 
 pub use self::scene::*;
\ No newline at end of file
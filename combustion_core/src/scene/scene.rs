@@ -9,16 +9,19 @@ use error::*;
 use ::common::structures::freelist::FreelistVecMap;
 pub use ::scene::sourcemap::SourceMap;
 pub use ::scene::graph::SceneGraph;
+use ::scene::debug::{DebugRegistry, BuiltinFormatters};
 
 use resources;
 use entities::camera::Entity as Camera;
 use systems;
+use systems::schedule::{SystemTiming, SystemTimings};
 
 use entities::Entity;
 
 pub struct Scene<'a> {
     pub planner: specs::Planner<systems::Delta>,
     pub sources: SourceMap<'a>,
+    timings: SystemTimings,
 }
 
 impl<'a> Scene<'a> {
@@ -37,10 +40,72 @@ impl<'a> Scene<'a> {
             //Event queue resource
             world.add_resource(resources::event_queue::Resource::new());
 
-            //Render queue resource
-            world.add_resource(resources::render_queue::Resource::new());
+            //Frame packets sent to the render loop, connected once it starts
+            world.add_resource(resources::frame_packet::Resource::new());
+
+            //Frame and fixed timestep timing, updated by the render loop every frame
+            world.add_resource(resources::time::Resource::new());
+
+            //Mouse-look camera resource, fed by the render loop from the main thread's input
+            world.add_resource(resources::camera_controller::Resource::new());
+
+            //Text input and console resources, fed by the render loop while the main thread captures text
+            world.add_resource(resources::text_input::Resource::new());
+            world.add_resource(resources::console::Resource::new());
+
+            //Gamepad resource, updated by the render loop whenever the main thread sends new state
+            world.add_resource(resources::gamepad::Resource::new());
 
             let camera = try!(Camera::new(&mut world));
+
+            //The default camera is viewed through until another is made active
+            world.add_resource(resources::active_camera::Resource::new(camera.raw()));
+            world.add_resource(resources::camera_matrices::Resource::new());
+
+            //Skybox and ambient color, saved and loaded with the rest of the scene
+            world.add_resource(resources::scene_settings::Resource::new());
+
+            //Entities by name, updated by the name index system
+            world.add_resource(resources::name_index::Resource::new());
+
+            //Prefabs, loaded the first time they're spawned
+            world.add_resource(resources::prefab_store::builtin());
+
+            //Parent links seen by the hierarchy system, so only changed transforms are recomputed
+            world.add_resource(resources::hierarchy::Resource::new());
+
+            //Clip events fired by the animation system this tick
+            world.add_resource(resources::animation_events::Resource::new());
+
+            //Entities inside the view frustum, found by the culling system for render submission
+            world.add_resource(resources::visible_set::Resource::new());
+
+            //Loose octree over world bounds, updated by the spatial index system
+            world.add_resource(resources::spatial_index::Resource::new());
+
+            //Billboards facing the camera, batched by texture by the billboard system for render submission
+            world.add_resource(resources::billboard_list::Resource::new());
+
+            //Level of detail bias, hysteresis and debug view, used by the LOD system and render submission
+            world.add_resource(resources::lod_settings::Resource::new());
+
+            //Materials merged with material override components by render submission, shared between identical merges
+            world.add_resource(resources::material_overrides::Resource::new());
+
+            //Component formatters for entity dumps, which games can add their own components to
+            world.add_resource(DebugRegistry::builtin());
+
+            //Event types registered with resources::events::register, updated by the events system
+            world.add_resource(resources::events::Registry::new());
+
+            //Interned group names and the members of every group
+            world.add_resource(resources::groups::Resource::new());
+
+            //Events sent by behavior components
+            resources::events::register::<::components::behavior::BehaviorEvent>(&mut world);
+
+            //Lights gathered each update for the lighting pass
+            world.add_resource(resources::light_list::Resource::new());
             world.add_resource::<resources::camera::Resource>(camera.into());
 
             let graph = SceneGraph::new(&world);
@@ -49,13 +114,12 @@ impl<'a> Scene<'a> {
             specs::Planner::new(world, num_cpus::get())
         };
 
-        planner.add_system(systems::constraints::System, "ConstrainSystem",
-                           systems::Priorities::Constraints as specs::Priority);
+        //Engine systems, then game systems, ordered by the data they use and what they have to follow
+        let mut schedule = systems::schedule();
 
-        planner.add_system(systems::transform::System, "TransformSystem",
-                           systems::Priorities::Transforms as specs::Priority);
+        ::game::scene::add_systems(&mut schedule);
 
-        ::game::scene::add_systems(&mut planner);
+        let timings = try!(schedule.build(&mut planner));
 
         planner.dispatch(0.0);
         planner.wait();
@@ -63,9 +127,16 @@ impl<'a> Scene<'a> {
         Ok(Scene {
             planner: planner,
             sources: SourceMap::new(),
+            timings: timings,
         })
     }
 
+    /// How long each system took the last time it ran, in the order they start
+    #[inline]
+    pub fn system_timings(&self) -> Vec<SystemTiming> {
+        self.timings.get()
+    }
+
     #[inline]
     pub fn camera(&mut self) -> RwLockReadGuard<resources::camera::Resource> {
         self.planner.mut_world().read_resource()
@@ -0,0 +1,64 @@
+//! Saving and loading a composed world as a `scene.capnp` protocol message
+//!
+//! Entities and their components are saved and loaded by `ecs::scene`, with a `ComponentRegistry` for `AppError`.
+//! The built-in components are serialized by `ecs::builtin`, along with the scene settings.
+
+use capnp;
+use capnp::any_pointer;
+
+use specs;
+
+use protocols::scene::protocol;
+
+use ecs::scene;
+
+use error::*;
+
+pub use ecs::scene::{SaveContext, LoadContext};
+pub use ecs::builtin::{save_world, load_world};
+
+/// Serialize and deserialize functions for every component type that can be saved, by name
+pub type ComponentRegistry = scene::ComponentRegistry<AppError>;
+
+/// Registries with every built-in component
+pub trait BuiltinComponents {
+    /// Creates a registry with every built-in component
+    fn builtin() -> Self;
+}
+
+impl BuiltinComponents for ComponentRegistry {
+    fn builtin() -> ComponentRegistry {
+        let mut registry = ComponentRegistry::new();
+
+        builtin::register_all(&mut registry);
+
+        registry
+    }
+}
+
+/// Creates an entity for every one in `entities_reader`, then loads their components. Scene settings are left alone.
+///
+/// See `ecs::scene::load_entities`.
+#[inline]
+pub fn load_entities<'a, I, U>(world: &mut specs::World, registry: &ComponentRegistry,
+                               entities_reader: capnp::struct_list::Reader<'a, protocol::entity::Owned>,
+                               scene_id: I, unregistered: U) -> AppResult<Vec<specs::Entity>>
+    where I: FnMut(u32) -> u32,
+          U: FnMut(&mut specs::World, specs::Entity, &str, any_pointer::Reader<'a>) -> AppResult<()> {
+    scene::load_entities(world, registry, entities_reader, scene_id, unregistered)
+}
+
+/// Serialization of the built-in components
+pub mod builtin {
+    use ecs;
+
+    use error::*;
+
+    use super::ComponentRegistry;
+
+    pub fn register_all(registry: &mut ComponentRegistry) {
+        use components::mesh_renderer::{Mesh, Material};
+
+        ecs::builtin::register_all::<AppError, Mesh, Material>(registry);
+    }
+}
@@ -0,0 +1,41 @@
+//! The AnimationEvents resource holds clip events fired by the animation systems during the current tick
+//!
+//! The queue is emptied by the animation system at the start of every tick, before the skeletal animation system adds to it,
+//! so systems running after both see each event once.
+
+use std::ops::{Deref, DerefMut};
+
+use specs;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationEvent {
+    /// Entity whose animator fired the event
+    pub entity: specs::Entity,
+    /// Name given to the event in the clip
+    pub name: String,
+    /// Time of the event in the clip
+    pub time: f32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Resource {
+    pub queue: Vec<AnimationEvent>
+}
+
+impl Resource {
+    pub fn new() -> Resource {
+        Resource { queue: Vec::new() }
+    }
+}
+
+impl Deref for Resource {
+    type Target = Vec<AnimationEvent>;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target { &self.queue }
+}
+
+impl DerefMut for Resource {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.queue }
+}
@@ -0,0 +1,156 @@
+//! The BillboardList resource holds this update's billboards, batched by texture by the billboard system for render submission
+//!
+//! Textures are whatever type `T` the billboard components were given.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use nalgebra::Matrix4;
+
+/// A single billboard, as an instance of a unit quad
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BillboardInstance {
+    /// Places a quad from -0.5 to 0.5 on X and Y, facing +Z, in the world
+    pub transform: Matrix4<f32>,
+    pub tint: [f32; 4],
+    /// Distance in front of the camera, which instances are sorted by
+    pub depth: f32,
+}
+
+/// Billboards sharing a texture, drawn together
+pub struct BillboardBatch<T> {
+    /// `None` draws solid quads in each instance's tint
+    pub texture: Option<Arc<T>>,
+    /// Sorted back to front, so they blend over each other correctly
+    pub instances: Vec<BillboardInstance>,
+}
+
+impl<T> Clone for BillboardBatch<T> {
+    fn clone(&self) -> BillboardBatch<T> {
+        BillboardBatch { texture: self.texture.clone(), instances: self.instances.clone() }
+    }
+}
+
+/// Groups instances by `key`, keeping the first value of each group, then sorts each group back to front
+/// and the groups by their farthest instance, so batches are drawn roughly back to front too
+pub fn batch<T, K, I, F>(instances: I, key: F) -> Vec<(T, Vec<BillboardInstance>)> where I: IntoIterator<Item = (T, BillboardInstance)>,
+                                                                                       K: Eq + Hash,
+                                                                                       F: Fn(&T) -> K {
+    let mut indices = HashMap::new();
+    let mut batches: Vec<(T, Vec<BillboardInstance>)> = Vec::new();
+
+    for (value, instance) in instances {
+        let index = *indices.entry(key(&value)).or_insert(batches.len());
+
+        if index == batches.len() {
+            batches.push((value, vec![instance]));
+        } else {
+            batches[index].1.push(instance);
+        }
+    }
+
+    let farther = |a: f32, b: f32| b.partial_cmp(&a).unwrap_or(Ordering::Equal);
+
+    for &mut (_, ref mut instances) in &mut batches {
+        //Stable, so billboards at the same depth keep the order they were submitted in
+        instances.sort_by(|a, b| farther(a.depth, b.depth));
+    }
+
+    batches.sort_by(|a, b| farther(a.1[0].depth, b.1[0].depth));
+
+    batches
+}
+
+pub struct Resource<T> {
+    batches: Vec<BillboardBatch<T>>,
+}
+
+impl<T> Default for Resource<T> {
+    #[inline(always)]
+    fn default() -> Resource<T> { Resource::new() }
+}
+
+impl<T> Clone for Resource<T> {
+    fn clone(&self) -> Resource<T> {
+        Resource { batches: self.batches.clone() }
+    }
+}
+
+impl<T> Resource<T> {
+    pub fn new() -> Resource<T> {
+        Resource { batches: Vec::new() }
+    }
+
+    /// Batches of this update, farthest first
+    #[inline]
+    pub fn batches(&self) -> &[BillboardBatch<T>] { &self.batches }
+
+    /// Number of billboards in every batch
+    pub fn len(&self) -> usize {
+        self.batches.iter().map(|batch| batch.instances.len()).sum()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.batches.is_empty() }
+
+    /// Replaces the batches with the given billboards, batched by texture
+    pub fn set<I>(&mut self, instances: I) where I: IntoIterator<Item = (Option<Arc<T>>, BillboardInstance)> {
+        let texture_key = |texture: &Option<Arc<T>>| texture.as_ref().map_or(0, |texture| &**texture as *const T as usize);
+
+        self.batches = batch(instances, texture_key).into_iter().map(|(texture, instances)| {
+            BillboardBatch { texture: texture, instances: instances }
+        }).collect();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use nalgebra::Eye;
+
+    fn instance(depth: f32) -> BillboardInstance {
+        BillboardInstance { transform: Matrix4::new_identity(4), tint: [1.0, 1.0, 1.0, depth], depth: depth }
+    }
+
+    #[test]
+    fn test_batching() {
+        let instances = vec![
+            ("smoke", instance(5.0)),
+            ("label", instance(2.0)),
+            ("smoke", instance(20.0)),
+            ("smoke", instance(5.0)),
+            ("label", instance(1.0)),
+            ("spark", instance(8.0)),
+        ];
+
+        let batches = batch(instances, |texture| *texture);
+
+        //Batches are ordered by their farthest billboard
+        let textures: Vec<&str> = batches.iter().map(|&(texture, _)| texture).collect();
+
+        assert_eq!(textures, vec!["smoke", "spark", "label"]);
+
+        let depths: Vec<Vec<f32>> = batches.iter().map(|&(_, ref instances)| instances.iter().map(|instance| instance.depth).collect()).collect();
+
+        assert_eq!(depths, vec![vec![20.0, 5.0, 5.0], vec![8.0], vec![2.0, 1.0]]);
+    }
+
+    #[test]
+    fn test_untextured() {
+        let mut list = Resource::<()>::new();
+
+        list.set(vec![(None, instance(1.0)), (None, instance(3.0))]);
+
+        assert_eq!(list.batches().len(), 1);
+        assert_eq!(list.len(), 2);
+        assert!(list.batches()[0].texture.is_none());
+        assert_eq!(list.batches()[0].instances[0].depth, 3.0);
+
+        list.set(Vec::new());
+
+        assert!(list.is_empty());
+    }
+}
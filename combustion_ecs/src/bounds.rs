@@ -0,0 +1,99 @@
+//! Axis-aligned bounding boxes
+
+use nalgebra::{Point3, Matrix4};
+
+/// Axis-aligned bounding box, in object space unless transformed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl Bounds {
+    /// Smallest box containing every point, or `None` if there are no points
+    pub fn from_points<I>(points: I) -> Option<Bounds> where I: IntoIterator<Item = Point3<f32>> {
+        points.into_iter().fold(None, |bounds, point| {
+            Some(match bounds {
+                None => Bounds { min: point, max: point },
+                Some(Bounds { min, max }) => Bounds {
+                    min: Point3::new(min.x.min(point.x), min.y.min(point.y), min.z.min(point.z)),
+                    max: Point3::new(max.x.max(point.x), max.y.max(point.y), max.z.max(point.z)),
+                }
+            })
+        })
+    }
+
+    /// Axis-aligned box containing this box after being transformed by `matrix`
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> Bounds {
+        let m = matrix;
+
+        // Start from the translation, then add each axis' contribution at whichever extent minimizes or maximizes it
+        let mut min = [m.m14, m.m24, m.m34];
+        let mut max = min;
+
+        let rows = [[m.m11, m.m12, m.m13], [m.m21, m.m22, m.m23], [m.m31, m.m32, m.m33]];
+        let (lower, upper) = ([self.min.x, self.min.y, self.min.z], [self.max.x, self.max.y, self.max.z]);
+
+        for i in 0..3 {
+            for j in 0..3 {
+                let a = rows[i][j] * lower[j];
+                let b = rows[i][j] * upper[j];
+
+                min[i] += a.min(b);
+                max[i] += a.max(b);
+            }
+        }
+
+        Bounds {
+            min: Point3::new(min[0], min[1], min[2]),
+            max: Point3::new(max[0], max[1], max[2]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_points() {
+        assert_eq!(Bounds::from_points(Vec::new()), None);
+
+        let points = vec![Point3::new(1.0, -2.0, 3.0), Point3::new(-1.0, 4.0, 0.0), Point3::new(0.0, 0.0, 5.0)];
+
+        assert_eq!(Bounds::from_points(points), Some(Bounds { min: Point3::new(-1.0, -2.0, 0.0), max: Point3::new(1.0, 4.0, 5.0) }));
+    }
+
+    #[test]
+    fn test_transform() {
+        let bounds = Bounds { min: Point3::new(-1.0, -2.0, -3.0), max: Point3::new(1.0, 2.0, 3.0) };
+
+        // Swap x and y, flip z and move everything along x
+        let matrix = Matrix4::new(0.0, 1.0, 0.0, 10.0,
+                                  1.0, 0.0, 0.0, 0.0,
+                                  0.0, 0.0, -1.0, 0.0,
+                                  0.0, 0.0, 0.0, 1.0);
+
+        assert_eq!(bounds.transform(&matrix), Bounds { min: Point3::new(8.0, -1.0, -3.0), max: Point3::new(12.0, 1.0, 3.0) });
+    }
+
+    #[test]
+    fn test_transform_rotated() {
+        let bounds = Bounds { min: Point3::new(-1.0, -1.0, -1.0), max: Point3::new(1.0, 1.0, 1.0) };
+
+        // An eighth of a turn about Z puts the box's corners on the axes, so it grows by the square root of two along X and Y
+        let (sin, cos) = (0.25 * ::std::f32::consts::PI).sin_cos();
+
+        let matrix = Matrix4::new(cos, -sin, 0.0, 0.0,
+                                  sin, cos, 0.0, 0.0,
+                                  0.0, 0.0, 1.0, 0.0,
+                                  0.0, 0.0, 0.0, 1.0);
+
+        let rotated = bounds.transform(&matrix);
+        let diagonal = 2.0f32.sqrt();
+
+        assert!((rotated.max.x - diagonal).abs() < 1e-5 && (rotated.max.y - diagonal).abs() < 1e-5);
+        assert!((rotated.min.x + diagonal).abs() < 1e-5 && (rotated.min.y + diagonal).abs() < 1e-5);
+        assert_eq!((rotated.min.z, rotated.max.z), (-1.0, 1.0));
+    }
+}
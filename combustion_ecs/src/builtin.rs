@@ -0,0 +1,631 @@
+//! Serialization of the built-in components, and saving and loading whole worlds along with the scene settings
+//!
+//! Registered functions return any error type `E` a `ComponentRegistry` can be made for. The mesh renderer is
+//! registered for whatever mesh and material types `M` and `T` the renderer uses.
+
+use std::io::{Write, BufRead};
+use std::path::Path;
+
+use capnp;
+use capnp::any_pointer;
+
+use specs;
+
+use asset::handle::AssetHandle;
+
+use protocols::scene::protocol;
+
+use scene::{self, ComponentRegistry, SaveContext, LoadContext, SceneError};
+use scene_settings::Resource as SceneSettings;
+
+use components::name::Component as Name;
+use components::hierarchy::parent::Component as Parent;
+use components::hierarchy::transform::Component as Transform;
+use components::mesh_renderer::Component as MeshRenderer;
+use components::point_light::Component as PointLight;
+use components::spot_light::Component as SpotLight;
+use components::directional_light::Component as DirectionalLight;
+use components::camera::{Component as Camera, ProjectionKind};
+use components::behavior::{Component as BehaviorComponent, Behavior};
+
+/// Asset path of a handle, or an empty string for assets that weren't loaded from a file
+fn asset_path<T>(handle: &AssetHandle<T>) -> String {
+    match handle.path() {
+        Some(path) => path.to_string_lossy().into_owned(),
+        None => {
+            warn!("Saving an asset that wasn't loaded from a file, which will never load again");
+            String::new()
+        }
+    }
+}
+
+fn asset_handle<T>(path: &str) -> AssetHandle<T> {
+    if path.is_empty() { AssetHandle::pending() } else { AssetHandle::at(Path::new(path)) }
+}
+
+/// Registers every built-in component, with the mesh renderer drawing meshes of type `M` with materials of type `T`
+pub fn register_all<E, M, T>(registry: &mut ComponentRegistry<E>)
+    where E: From<capnp::Error> + From<SceneError>,
+          M: Send + Sync + 'static,
+          T: Send + Sync + 'static {
+    registry.register("name", |name: &Name, _: &SaveContext, builder: any_pointer::Builder| {
+        builder.init_as::<protocol::name::Builder>().set_name(&name.0);
+
+        Ok(())
+    }, |reader: any_pointer::Reader, _: &LoadContext| {
+        let reader = try!(reader.get_as::<protocol::name::Reader>());
+
+        Ok(Name(try!(reader.get_name()).to_string()))
+    });
+
+    registry.register("parent", |parent: &Parent, context: &SaveContext, builder: any_pointer::Builder| {
+        let id = try!(context.id(parent.0).ok_or(SceneError::UnsavedEntity(parent.0)));
+
+        builder.init_as::<protocol::parent::Builder>().set_entity(id);
+
+        Ok(())
+    }, |reader: any_pointer::Reader, context: &LoadContext| {
+        let id = try!(reader.get_as::<protocol::parent::Reader>()).get_entity();
+
+        Ok(Parent(try!(context.entity(id).ok_or(SceneError::MissingEntity(id)))))
+    });
+
+    registry.register("transform", |transform: &Transform, _: &SaveContext, builder: any_pointer::Builder| {
+        let mut builder = builder.init_as::<protocol::transform::Builder>();
+
+        builder.borrow().init_translation().set_vector(transform.translation());
+        builder.borrow().init_rotation().set_quaternion(transform.rotation());
+        builder.init_scale().set_vector(transform.scale());
+
+        Ok(())
+    }, |reader: any_pointer::Reader, _: &LoadContext| {
+        let reader = try!(reader.get_as::<protocol::transform::Reader>());
+
+        Ok(Transform::from_parts(try!(reader.get_translation()).get_vector(),
+                                 try!(reader.get_rotation()).get_quaternion(),
+                                 try!(reader.get_scale()).get_vector()))
+    });
+
+    registry.register("mesh_renderer", |renderer: &MeshRenderer<M, T>, _: &SaveContext, builder: any_pointer::Builder| {
+        let mut builder = builder.init_as::<protocol::mesh_renderer::Builder>();
+
+        builder.set_mesh(&asset_path(&renderer.mesh));
+        builder.set_cast_shadows(renderer.cast_shadows);
+        builder.set_visible(renderer.visible);
+
+        let mut materials = builder.init_materials(renderer.materials.len() as u32);
+
+        for (i, material) in renderer.materials.iter().enumerate() {
+            materials.set(i as u32, &asset_path(material));
+        }
+
+        Ok(())
+    }, |reader: any_pointer::Reader, _: &LoadContext| {
+        let reader = try!(reader.get_as::<protocol::mesh_renderer::Reader>());
+
+        let mut materials = Vec::new();
+
+        for material in try!(reader.get_materials()).iter() {
+            materials.push(asset_handle(try!(material)));
+        }
+
+        Ok(MeshRenderer {
+            mesh: asset_handle(try!(reader.get_mesh())),
+            materials: materials,
+            cast_shadows: reader.get_cast_shadows(),
+            visible: reader.get_visible(),
+        })
+    });
+
+    registry.register("point_light", |light: &PointLight, _: &SaveContext, builder: any_pointer::Builder| {
+        let mut builder = builder.init_as::<protocol::point_light::Builder>();
+
+        builder.set_enabled(light.enabled);
+        builder.borrow().init_color().set_vector(&light.color);
+        builder.set_intensity(light.intensity);
+        builder.set_radius(light.radius);
+
+        Ok(())
+    }, |reader: any_pointer::Reader, _: &LoadContext| {
+        let reader = try!(reader.get_as::<protocol::point_light::Reader>());
+
+        Ok(PointLight {
+            enabled: reader.get_enabled(),
+            color: try!(reader.get_color()).get_vector(),
+            intensity: reader.get_intensity(),
+            radius: reader.get_radius(),
+        })
+    });
+
+    registry.register("spot_light", |light: &SpotLight, _: &SaveContext, builder: any_pointer::Builder| {
+        let mut builder = builder.init_as::<protocol::spot_light::Builder>();
+
+        builder.set_enabled(light.enabled);
+        builder.borrow().init_color().set_vector(&light.color);
+        builder.set_intensity(light.intensity);
+        builder.set_radius(light.radius);
+        builder.set_inner_angle(light.inner_angle);
+        builder.set_outer_angle(light.outer_angle);
+
+        Ok(())
+    }, |reader: any_pointer::Reader, _: &LoadContext| {
+        let reader = try!(reader.get_as::<protocol::spot_light::Reader>());
+
+        Ok(SpotLight {
+            enabled: reader.get_enabled(),
+            color: try!(reader.get_color()).get_vector(),
+            intensity: reader.get_intensity(),
+            radius: reader.get_radius(),
+            inner_angle: reader.get_inner_angle(),
+            outer_angle: reader.get_outer_angle(),
+        })
+    });
+
+    registry.register("directional_light", |light: &DirectionalLight, _: &SaveContext, builder: any_pointer::Builder| {
+        let mut builder = builder.init_as::<protocol::directional_light::Builder>();
+
+        builder.set_enabled(light.enabled);
+        builder.borrow().init_color().set_vector(&light.color);
+        builder.set_intensity(light.intensity);
+
+        Ok(())
+    }, |reader: any_pointer::Reader, _: &LoadContext| {
+        let reader = try!(reader.get_as::<protocol::directional_light::Reader>());
+
+        Ok(DirectionalLight {
+            enabled: reader.get_enabled(),
+            color: try!(reader.get_color()).get_vector(),
+            intensity: reader.get_intensity(),
+        })
+    });
+
+    registry.register("camera", |camera: &Camera, _: &SaveContext, builder: any_pointer::Builder| {
+        let mut builder = builder.init_as::<protocol::camera::Builder>();
+
+        builder.set_kind(match camera.kind {
+            ProjectionKind::Perspective => protocol::ProjectionKind::Perspective,
+            ProjectionKind::Orthographic => protocol::ProjectionKind::Orthographic,
+        });
+
+        builder.set_fov(camera.fov);
+        builder.set_height(camera.height);
+        builder.set_near(camera.near);
+        builder.set_far(camera.far);
+
+        Ok(())
+    }, |reader: any_pointer::Reader, _: &LoadContext| {
+        let reader = try!(reader.get_as::<protocol::camera::Reader>());
+
+        let kind = match reader.get_kind() {
+            Ok(protocol::ProjectionKind::Perspective) => ProjectionKind::Perspective,
+            Ok(protocol::ProjectionKind::Orthographic) => ProjectionKind::Orthographic,
+            Err(_) => return Err(SceneError::InvalidValue("camera projection").into()),
+        };
+
+        //The aspect ratio is set when the render loop resizes every camera to the viewport
+        let mut camera = Camera::perspective(reader.get_fov(), reader.get_near(), reader.get_far());
+
+        camera.kind = kind;
+        camera.height = reader.get_height();
+
+        Ok(camera)
+    });
+
+    registry.register("behavior", |behavior: &BehaviorComponent, context: &SaveContext, builder: any_pointer::Builder| {
+        let mut builder = builder.init_as::<protocol::behavior::Builder>();
+
+        builder.set_enabled(behavior.enabled);
+        builder.set_elapsed(behavior.elapsed);
+
+        match behavior.behavior {
+            Behavior::Rotate { ref axis, rate } => {
+                let mut rotate = builder.init_rotate();
+
+                rotate.borrow().init_axis().set_vector(axis);
+                rotate.set_rate(rate);
+            }
+            Behavior::Oscillate { ref axis, amplitude, frequency } => {
+                let mut oscillate = builder.init_oscillate();
+
+                oscillate.borrow().init_axis().set_vector(axis);
+                oscillate.set_amplitude(amplitude);
+                oscillate.set_frequency(frequency);
+            }
+            Behavior::LookAt { target, ref up } => {
+                //The target has to be in the scene too, or there would be nothing to remap it to when loading
+                let id = try!(context.id(target).ok_or(SceneError::UnsavedEntity(target)));
+
+                let mut look_at = builder.init_look_at();
+
+                look_at.set_target(id);
+                look_at.init_up().set_vector(up);
+            }
+            Behavior::Custom(_) => {
+                warn!("Saving a custom behavior, which has to be attached again by code after the scene is loaded");
+
+                builder.set_custom(());
+            }
+        }
+
+        Ok(())
+    }, |reader: any_pointer::Reader, context: &LoadContext| {
+        let reader = try!(reader.get_as::<protocol::behavior::Reader>());
+
+        let mut behavior = match reader.which() {
+            Ok(protocol::behavior::Rotate(rotate)) => {
+                let rotate = try!(rotate);
+
+                BehaviorComponent::rotate(try!(rotate.get_axis()).get_vector(), rotate.get_rate())
+            }
+            Ok(protocol::behavior::Oscillate(oscillate)) => {
+                let oscillate = try!(oscillate);
+
+                BehaviorComponent::oscillate(try!(oscillate.get_axis()).get_vector(), oscillate.get_amplitude(), oscillate.get_frequency())
+            }
+            Ok(protocol::behavior::LookAt(look_at)) => {
+                let look_at = try!(look_at);
+                let id = look_at.get_target();
+
+                let target = try!(context.entity(id).ok_or(SceneError::MissingEntity(id)));
+
+                BehaviorComponent::new(Behavior::LookAt { target: target, up: try!(look_at.get_up()).get_vector() })
+            }
+            Ok(protocol::behavior::Custom(())) => {
+                warn!("Loaded a custom behavior, which does nothing until it is replaced by code");
+
+                let mut custom = BehaviorComponent::custom(|_| {});
+
+                custom.enabled = false;
+
+                return Ok(custom);
+            }
+            Err(_) => return Err(SceneError::InvalidValue("behavior").into()),
+        };
+
+        behavior.enabled = reader.get_enabled();
+        behavior.elapsed = reader.get_elapsed();
+
+        Ok(behavior)
+    });
+}
+
+/// Writes every entity with at least one registered component, along with the scene settings
+pub fn save_world<E, W: Write>(world: &specs::World, registry: &ComponentRegistry<E>, writer: &mut W) -> Result<(), E>
+    where E: From<capnp::Error> {
+    let mut message = capnp::message::Builder::new_default();
+
+    {
+        let root = message.init_root::<protocol::root_scene::Builder>();
+        let mut scene = root.init_scene();
+
+        {
+            let settings = world.read_resource::<SceneSettings>();
+            let mut settings_builder = scene.borrow().init_settings();
+
+            if let Some(ref skybox) = settings.skybox {
+                settings_builder.set_skybox(&skybox.to_string_lossy());
+            }
+
+            settings_builder.init_ambient().set_vector(&settings.ambient);
+        }
+
+        try!(scene::save_entities(world, registry, scene));
+    }
+
+    try!(capnp::serialize_packed::write_message(writer, &message).map_err(capnp::Error::from));
+
+    Ok(())
+}
+
+/// Adds every entity in the scene to the world, and replaces the scene settings with those of the scene.
+///
+/// Entities already in the world are left alone, and components nobody registered are skipped.
+/// Returns the created entities in the order they were saved.
+pub fn load_world<E, R: BufRead>(world: &mut specs::World, registry: &ComponentRegistry<E>, reader: &mut R) -> Result<Vec<specs::Entity>, E>
+    where E: From<capnp::Error> + From<SceneError> {
+    let message = try!(capnp::serialize_packed::read_message(reader, capnp::message::ReaderOptions::new()));
+
+    let root = try!(message.get_root::<protocol::root_scene::Reader>());
+    let scene = try!(root.get_scene());
+
+    let created = try!(scene::load_entities(world, registry, try!(scene.get_entities()), |id| id, |_, entity, name, _| {
+        warn!("Skipping unknown component {:?} of scene entity {:?}", name, entity);
+
+        Ok(())
+    }));
+
+    {
+        let settings_reader = try!(scene.get_settings());
+        let skybox = try!(settings_reader.get_skybox());
+
+        let mut settings = world.write_resource::<SceneSettings>();
+
+        settings.skybox = if skybox.is_empty() { None } else { Some(Path::new(skybox).to_path_buf()) };
+        settings.ambient = try!(settings_reader.get_ambient()).get_vector();
+    }
+
+    Ok(created)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use specs::Join;
+
+    use nalgebra::{Vector3, Quaternion};
+
+    use components;
+    use scene::SceneId;
+
+    #[derive(Debug)]
+    enum TestError {
+        Capnp(capnp::Error),
+        Scene(SceneError),
+    }
+
+    impl From<capnp::Error> for TestError {
+        fn from(err: capnp::Error) -> TestError { TestError::Capnp(err) }
+    }
+
+    impl From<SceneError> for TestError {
+        fn from(err: SceneError) -> TestError { TestError::Scene(err) }
+    }
+
+    struct TestMesh;
+    struct TestMaterial;
+
+    type Renderer = MeshRenderer<TestMesh, TestMaterial>;
+
+    fn builtin() -> ComponentRegistry<TestError> {
+        let mut registry = ComponentRegistry::new();
+
+        register_all::<TestError, TestMesh, TestMaterial>(&mut registry);
+
+        registry
+    }
+
+    fn new_world() -> specs::World {
+        let mut world = specs::World::new();
+
+        components::register_all(&mut world);
+
+        world.register::<Renderer>();
+        world.register::<SceneId>();
+
+        world.add_resource(SceneSettings::new());
+
+        world
+    }
+
+    fn round_trip(world: &specs::World) -> (specs::World, Vec<specs::Entity>) {
+        let registry = builtin();
+
+        let mut buffer = Vec::new();
+
+        save_world(world, &registry, &mut buffer).unwrap();
+
+        let mut loaded = new_world();
+
+        let entities = load_world(&mut loaded, &registry, &mut Cursor::new(buffer)).unwrap();
+
+        (loaded, entities)
+    }
+
+    fn find(world: &specs::World, name: &str) -> specs::Entity {
+        let names = world.read::<Name>();
+
+        (&names, &world.entities()).iter().find(|&(ref entity_name, _)| entity_name.0 == name).map(|(_, entity)| entity).unwrap()
+    }
+
+    #[test]
+    fn test_hierarchy_round_trip() {
+        let mut world = new_world();
+
+        let root = world.create_now()
+                        .with(Name::new("root"))
+                        .with(Transform::from_translation(1.0, 2.0, 3.0))
+                        .build();
+
+        let child = world.create_now()
+                         .with(Name::new("child"))
+                         .with(Transform::from_parts(Vector3::new(0.0, 0.0, 0.0), Quaternion::new(0.0, 1.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0)))
+                         .with(Parent(root))
+                         .build();
+
+        world.create_now()
+             .with(Name::new("grandchild"))
+             .with(Transform::from_parts(Vector3::new(0.0, 0.0, 0.0), Quaternion::new(1.0, 0.0, 0.0, 0.0), Vector3::new(2.0, 2.0, 2.0)))
+             .with(Parent(child))
+             .build();
+
+        let (loaded, entities) = round_trip(&world);
+
+        assert_eq!(entities.len(), 3);
+
+        let (root, child, grandchild) = (find(&loaded, "root"), find(&loaded, "child"), find(&loaded, "grandchild"));
+
+        let parents = loaded.read::<Parent>();
+        let transforms = loaded.read::<Transform>();
+
+        assert!(parents.get(root).is_none());
+        assert_eq!(parents.get(child).map(|parent| parent.0), Some(root));
+        assert_eq!(parents.get(grandchild).map(|parent| parent.0), Some(child));
+
+        assert_eq!(transforms.get(root).unwrap().translation(), &Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(transforms.get(child).unwrap().rotation(), &Quaternion::new(0.0, 1.0, 0.0, 0.0));
+        assert_eq!(transforms.get(grandchild).unwrap().scale(), &Vector3::new(2.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn test_builtin_components_round_trip() {
+        let mut world = new_world();
+
+        let renderer = Renderer {
+            cast_shadows: false,
+            ..Renderer::new(AssetHandle::at("models/crate.cmdl"))
+        }.with_material(AssetHandle::at("materials/wood.cmat"))
+         .with_material(AssetHandle::at("materials/metal.cmat"));
+
+        let spot = SpotLight::new(Vector3::new(1.0, 1.0, 1.0), 4.0, 20.0, 0.25, 0.5);
+
+        let mut point = PointLight::new(Vector3::new(1.0, 0.5, 0.25), 2.0, 10.0);
+
+        point.enabled = false;
+
+        world.create_now()
+             .with(Name::new("everything"))
+             .with(Transform::new())
+             .with(renderer)
+             .with(point)
+             .with(spot)
+             .with(DirectionalLight::new(Vector3::new(1.0, 1.0, 0.9), 3.0))
+             .with(Camera::orthographic(10.0, 0.5, 100.0))
+             .build();
+
+        {
+            let mut settings = world.write_resource::<SceneSettings>();
+
+            settings.skybox = Some("textures/sky.ctex".into());
+            settings.ambient = Vector3::new(0.1, 0.2, 0.3);
+        }
+
+        let (loaded, _) = round_trip(&world);
+
+        let entity = find(&loaded, "everything");
+
+        let renderers = loaded.read::<Renderer>();
+        let renderer = renderers.get(entity).unwrap();
+
+        assert_eq!(renderer.mesh.path(), Some(Path::new("models/crate.cmdl")));
+        assert_eq!(renderer.materials.iter().map(|material| material.path().unwrap().to_path_buf()).collect::<Vec<_>>(),
+                   vec![Path::new("materials/wood.cmat").to_path_buf(), Path::new("materials/metal.cmat").to_path_buf()]);
+        assert!(!renderer.cast_shadows);
+        assert!(renderer.visible);
+
+        // Nothing is loaded yet, so the entity waits for its assets
+        assert!(!renderer.mesh.is_loaded());
+
+        let points = loaded.read::<PointLight>();
+        let point = points.get(entity).unwrap();
+
+        assert!(!point.enabled);
+        assert_eq!(point.color, Vector3::new(1.0, 0.5, 0.25));
+        assert_eq!((point.intensity, point.radius), (2.0, 10.0));
+
+        let spots = loaded.read::<SpotLight>();
+        let spot = spots.get(entity).unwrap();
+
+        assert_eq!((spot.intensity, spot.radius, spot.inner_angle, spot.outer_angle), (4.0, 20.0, 0.25, 0.5));
+
+        let directionals = loaded.read::<DirectionalLight>();
+
+        assert_eq!(directionals.get(entity).unwrap().intensity, 3.0);
+
+        let cameras = loaded.read::<Camera>();
+        let camera = cameras.get(entity).unwrap();
+
+        assert_eq!(camera.kind, ProjectionKind::Orthographic);
+        assert_eq!((camera.height, camera.near, camera.far), (10.0, 0.5, 100.0));
+
+        let transforms = loaded.read::<Transform>();
+
+        assert_eq!(transforms.get(entity).unwrap().scale(), &Vector3::new(1.0, 1.0, 1.0));
+
+        let settings = loaded.read_resource::<SceneSettings>();
+
+        assert_eq!(*settings, SceneSettings { skybox: Some("textures/sky.ctex".into()), ambient: Vector3::new(0.1, 0.2, 0.3) });
+    }
+
+    #[test]
+    fn test_behavior_round_trip() {
+        let mut world = new_world();
+
+        let target = world.create_now().with(Name::new("target")).build();
+
+        let mut spinner = BehaviorComponent::rotate(Vector3::new(0.0, 1.0, 0.0), 2.0);
+
+        spinner.elapsed = 1.5;
+
+        world.create_now().with(Name::new("spinner")).with(spinner).build();
+        world.create_now().with(Name::new("bobber")).with(BehaviorComponent::oscillate(Vector3::new(1.0, 0.0, 0.0), 0.5, 4.0)).build();
+        world.create_now().with(Name::new("watcher")).with(BehaviorComponent::look_at(target)).build();
+        world.create_now().with(Name::new("custom")).with(BehaviorComponent::custom(|_| {})).build();
+
+        let (loaded, _) = round_trip(&world);
+
+        let behaviors = loaded.read::<BehaviorComponent>();
+
+        let spinner = behaviors.get(find(&loaded, "spinner")).unwrap();
+
+        assert!(spinner.enabled);
+        assert_eq!(spinner.elapsed, 1.5);
+        assert!(if let Behavior::Rotate { axis, rate } = spinner.behavior { axis == Vector3::new(0.0, 1.0, 0.0) && rate == 2.0 } else { false });
+
+        let bobber = &behaviors.get(find(&loaded, "bobber")).unwrap().behavior;
+
+        assert!(if let Behavior::Oscillate { amplitude, frequency, .. } = *bobber { amplitude == 0.5 && frequency == 4.0 } else { false });
+
+        // The target is remapped to its newly loaded entity
+        let watcher = &behaviors.get(find(&loaded, "watcher")).unwrap().behavior;
+        let target = find(&loaded, "target");
+
+        assert!(if let Behavior::LookAt { target: loaded_target, up } = *watcher { loaded_target == target && up == Vector3::new(0.0, 1.0, 0.0) } else { false });
+
+        // Custom behaviors can't be loaded, so they come back disabled
+        let custom = behaviors.get(find(&loaded, "custom")).unwrap();
+
+        assert!(!custom.enabled);
+        assert!(if let Behavior::Custom(_) = custom.behavior { true } else { false });
+    }
+
+    #[test]
+    fn test_unsaved_parent_is_rejected() {
+        let mut world = new_world();
+
+        //Nothing registered is on the parent, so it isn't saved
+        let parent = world.create_now().build();
+
+        world.create_now().with(Name::new("child")).with(Parent(parent)).build();
+
+        let mut buffer = Vec::new();
+
+        match save_world(&world, &builtin(), &mut buffer) {
+            Err(TestError::Scene(SceneError::UnsavedEntity(entity))) => assert_eq!(entity, parent),
+            result => panic!("Expected an unsaved entity error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_unknown_components_are_skipped() {
+        let mut world = new_world();
+
+        world.create_now().with(Name::new("light")).with(PointLight::white(1.0, 1.0)).build();
+
+        let mut buffer = Vec::new();
+
+        save_world(&world, &builtin(), &mut buffer).unwrap();
+
+        // A registry that only knows about names, like a tool without the lighting components
+        let mut names_only = ComponentRegistry::<TestError>::new();
+
+        names_only.register("name", |name: &Name, _: &SaveContext, builder: any_pointer::Builder| {
+            builder.init_as::<protocol::name::Builder>().set_name(&name.0);
+
+            Ok(())
+        }, |reader: any_pointer::Reader, _: &LoadContext| {
+            Ok(Name(try!(try!(reader.get_as::<protocol::name::Reader>()).get_name()).to_string()))
+        });
+
+        let mut loaded = new_world();
+
+        let entities = load_world(&mut loaded, &names_only, &mut Cursor::new(buffer)).unwrap();
+
+        assert!(!names_only.contains("point_light"));
+        assert_eq!(entities.len(), 1);
+        assert!(loaded.read::<PointLight>().get(entities[0]).is_none());
+        assert_eq!(loaded.read::<Name>().get(entities[0]), Some(&Name::new("light")));
+    }
+}
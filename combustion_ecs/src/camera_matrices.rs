@@ -0,0 +1,70 @@
+//! The CameraMatrices resource holds the matrices of the active camera, as used by rendering
+
+use nalgebra::{Matrix4, Point3, Eye};
+
+#[derive(Copy, Clone, Debug)]
+pub struct Resource {
+    /// Transformation from world space to view space
+    pub view: Matrix4<f32>,
+    pub projection: Matrix4<f32>,
+    /// `projection * view`
+    pub view_projection: Matrix4<f32>,
+    /// Camera position in world space
+    pub position: Point3<f32>,
+    /// Size of the viewport in pixels, or zero before it's first known
+    pub viewport: (f32, f32),
+}
+
+impl Default for Resource {
+    #[inline(always)]
+    fn default() -> Resource { Resource::new() }
+}
+
+impl Resource {
+    pub fn new() -> Resource {
+        Resource {
+            view: Matrix4::new_identity(4),
+            projection: Matrix4::new_identity(4),
+            view_projection: Matrix4::new_identity(4),
+            position: Point3::new(0.0, 0.0, 0.0),
+            viewport: (0.0, 0.0),
+        }
+    }
+
+    pub fn set_view(&mut self, view: Matrix4<f32>, position: Point3<f32>) {
+        self.view = view;
+        self.position = position;
+        self.view_projection = self.projection * self.view;
+    }
+
+    pub fn set_projection(&mut self, projection: Matrix4<f32>) {
+        self.projection = projection;
+        self.view_projection = self.projection * self.view;
+    }
+
+    pub fn set_viewport(&mut self, width: f32, height: f32) {
+        self.viewport = (width, height);
+    }
+
+    /// Distance in front of the camera, along its view direction
+    pub fn view_depth(&self, point: &Point3<f32>) -> f32 {
+        let v = &self.view;
+
+        -(v.m31 * point.x + v.m32 * point.y + v.m33 * point.z + v.m34)
+    }
+
+    /// World units covered by a pixel at `depth` in front of the camera,
+    /// or `None` if the viewport size isn't known or the depth is behind a perspective camera
+    pub fn world_per_pixel(&self, depth: f32) -> Option<f32> {
+        let p = &self.projection;
+
+        //Clip space W, which is the depth for perspective projections and one for orthographic ones
+        let w = p.m44 - p.m43 * depth;
+
+        if self.viewport.1 > 0.0 && p.m22 != 0.0 && w > 0.0 {
+            Some(2.0 * w / (p.m22 * self.viewport.1))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,22 @@
+//! Always visible component
+//!
+//! Tags an entity to skip frustum culling, such as one whose vertices are moved in a shader and outgrow its bounds
+
+use specs;
+
+#[derive(Clone, Copy)]
+pub struct Component;
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Default for Component {
+    #[inline(always)]
+    fn default() -> Component { Component }
+}
+
+impl Component {
+    #[inline(always)]
+    pub fn new() -> Component { Component }
+}
@@ -0,0 +1,292 @@
+//! Animator component, playing an animation clip on its entity
+//!
+//! The clip is an asset handle, so nothing is played until it finishes loading.
+
+use std::ops::{Deref, DerefMut};
+
+use specs;
+
+use asset::handle::AssetHandle;
+
+use super::clip::{AnimationClip, ClipEvent, Timeline};
+
+/// What happens when playback reaches either end of the clip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WrapMode {
+    /// Stop at the end
+    Once,
+    /// Jump back to the other end and carry on
+    Loop,
+    /// Turn around and play back the other way
+    PingPong,
+}
+
+/// Where playback is in a timeline, and how it moves along
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Playback {
+    /// Position in the timeline, in seconds
+    pub time: f32,
+    /// Playback rate, where negative values play in reverse
+    pub speed: f32,
+    pub wrap: WrapMode,
+    pub playing: bool,
+    /// Set while ping-pong playback is heading back the other way
+    bounced: bool,
+    /// Set when events at the current time have already been fired, after reaching either end
+    fired_current: bool,
+}
+
+impl Default for Playback {
+    #[inline(always)]
+    fn default() -> Playback { Playback::new() }
+}
+
+impl Playback {
+    /// Plays once from the start at normal speed
+    pub fn new() -> Playback {
+        Playback {
+            time: 0.0,
+            speed: 1.0,
+            wrap: WrapMode::Once,
+            playing: true,
+            bounced: false,
+            fired_current: false,
+        }
+    }
+
+    /// Starts playing again from `time`
+    pub fn play_from(&mut self, time: f32) {
+        self.time = time;
+        self.playing = true;
+        self.bounced = false;
+        self.fired_current = false;
+    }
+
+    /// Moves playback along by `delta` seconds, scaled by the speed, and calls `fired` for every event passed.
+    ///
+    /// Events exactly at either end of the timeline are fired when playback reaches them, and any others when playback
+    /// leaves their time, so each is fired once per pass. At most one full cycle of events is fired per call.
+    ///
+    /// Zero-length timelines have nowhere to go, so they fire their events once and stop whatever the wrap mode.
+    pub fn advance<T: Timeline + ?Sized>(&mut self, timeline: &T, delta: f32, fired: &mut FnMut(&ClipEvent)) {
+        if !self.playing {
+            return;
+        }
+
+        let duration = timeline.duration();
+
+        if duration <= 0.0 {
+            if !self.fired_current {
+                timeline.fire_events(0.0, 0.0, true, true, fired);
+            }
+
+            self.time = 0.0;
+            self.playing = false;
+            self.fired_current = true;
+            return;
+        }
+
+        let mut step = self.speed * delta * if self.bounced { -1.0 } else { 1.0 };
+
+        if !step.is_finite() {
+            return;
+        }
+
+        self.time = self.time.max(0.0).min(duration);
+
+        //Skip whole cycles of a huge step instead of firing every event in them
+        let period = if self.wrap == WrapMode::PingPong { 2.0 * duration } else { duration };
+
+        if self.wrap != WrapMode::Once && step.abs() > period {
+            step %= period;
+        }
+
+        while step != 0.0 {
+            let target = self.time + step;
+            let include_from = !self.fired_current;
+
+            //Carrying on within the timeline
+            if target > 0.0 && target < duration {
+                timeline.fire_events(self.time, target, include_from, false, fired);
+
+                self.time = target;
+                self.fired_current = false;
+                break;
+            }
+
+            //Otherwise an end was reached, so fire everything up to and including it
+            let (end, start) = if step > 0.0 { (duration, 0.0) } else { (0.0, duration) };
+
+            timeline.fire_events(self.time, end, include_from, true, fired);
+
+            let leftover = target - end;
+
+            match self.wrap {
+                WrapMode::Once => {
+                    self.time = end;
+                    self.playing = false;
+                    self.fired_current = true;
+                    break;
+                }
+                WrapMode::Loop => {
+                    self.time = start;
+                    self.fired_current = false;
+                    step = leftover;
+                }
+                WrapMode::PingPong => {
+                    self.time = end;
+                    self.bounced = !self.bounced;
+                    self.fired_current = true;
+                    step = -leftover;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Component {
+    pub clip: AssetHandle<AnimationClip>,
+    pub playback: Playback,
+}
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Component {
+    /// Creates an animator that plays the clip once from the start
+    pub fn new(clip: AssetHandle<AnimationClip>) -> Component {
+        Component { clip: clip, playback: Playback::new() }
+    }
+
+    #[inline]
+    pub fn with_speed(mut self, speed: f32) -> Component {
+        self.playback.speed = speed;
+        self
+    }
+
+    #[inline]
+    pub fn with_wrap(mut self, wrap: WrapMode) -> Component {
+        self.playback.wrap = wrap;
+        self
+    }
+}
+
+impl Deref for Component {
+    type Target = Playback;
+
+    #[inline(always)]
+    fn deref(&self) -> &Playback { &self.playback }
+}
+
+impl DerefMut for Component {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Playback { &mut self.playback }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use nalgebra::Vector3;
+
+    use super::super::clip::Interpolation;
+
+    fn clip() -> AnimationClip {
+        AnimationClip::new()
+            .with_translation(0.0, Vector3::new(0.0, 0.0, 0.0), Interpolation::Linear)
+            .with_translation(1.0, Vector3::new(4.0, 0.0, 0.0), Interpolation::Linear)
+            .with_event(0.0, "start")
+            .with_event(0.5, "middle")
+            .with_event(1.0, "end")
+    }
+
+    /// Advances once, returning the names of the events fired
+    fn advance(animator: &mut Component, clip: &AnimationClip, delta: f32) -> Vec<String> {
+        let mut fired = Vec::new();
+
+        animator.advance(clip, delta, &mut |event| fired.push(event.name.clone()));
+
+        fired
+    }
+
+    #[test]
+    fn test_play_once() {
+        let clip = clip();
+        let mut animator = Component::new(AssetHandle::pending());
+
+        assert_eq!(advance(&mut animator, &clip, 0.25), vec!["start"]);
+        assert_eq!(advance(&mut animator, &clip, 0.25), Vec::<String>::new());
+        assert_eq!(advance(&mut animator, &clip, 0.25), vec!["middle"]);
+        assert_eq!(clip.sample(animator.time).translation, Some(Vector3::new(3.0, 0.0, 0.0)));
+
+        //Going past the end stops exactly on it
+        assert_eq!(advance(&mut animator, &clip, 0.5), vec!["end"]);
+        assert_eq!(animator.time, 1.0);
+        assert!(!animator.playing);
+
+        //Resuming at the end doesn't fire the last event again
+        animator.playing = true;
+
+        assert_eq!(advance(&mut animator, &clip, 0.25), Vec::<String>::new());
+        assert!(!animator.playing);
+    }
+
+    #[test]
+    fn test_reversed_playback() {
+        let clip = clip();
+        let mut animator = Component::new(AssetHandle::pending()).with_speed(-1.0);
+
+        animator.play_from(1.0);
+
+        assert_eq!(advance(&mut animator, &clip, 0.25), vec!["end"]);
+        assert_eq!(clip.sample(animator.time).translation, Some(Vector3::new(3.0, 0.0, 0.0)));
+
+        assert_eq!(advance(&mut animator, &clip, 0.5), vec!["middle"]);
+        assert_eq!(animator.time, 0.25);
+
+        assert_eq!(advance(&mut animator, &clip, 0.5), vec!["start"]);
+        assert_eq!(animator.time, 0.0);
+        assert!(!animator.playing);
+    }
+
+    #[test]
+    fn test_loop_and_ping_pong() {
+        let clip = clip();
+        let mut looping = Component::new(AssetHandle::pending()).with_wrap(WrapMode::Loop);
+
+        assert_eq!(advance(&mut looping, &clip, 0.75), vec!["start", "middle"]);
+        assert_eq!(advance(&mut looping, &clip, 0.75), vec!["end", "start"]);
+        assert_eq!(looping.time, 0.5);
+        assert!(looping.playing);
+
+        let mut ping_pong = Component::new(AssetHandle::pending()).with_wrap(WrapMode::PingPong);
+
+        assert_eq!(advance(&mut ping_pong, &clip, 0.75), vec!["start", "middle"]);
+
+        //Turning around at the end fires its event once, then heads back
+        assert_eq!(advance(&mut ping_pong, &clip, 0.75), vec!["end"]);
+        assert_eq!(ping_pong.time, 0.5);
+
+        assert_eq!(advance(&mut ping_pong, &clip, 0.75), vec!["middle", "start"]);
+        assert_eq!(ping_pong.time, 0.25);
+
+        //Huge steps only fire a single cycle's worth of events
+        assert!(advance(&mut ping_pong, &clip, 1000.25).len() <= 4);
+    }
+
+    #[test]
+    fn test_zero_length_clip() {
+        let clip = AnimationClip::new().with_event(0.0, "only");
+        let mut animator = Component::new(AssetHandle::pending()).with_wrap(WrapMode::Loop);
+
+        assert_eq!(advance(&mut animator, &clip, 0.25), vec!["only"]);
+        assert!(!animator.playing);
+
+        animator.playing = true;
+
+        assert_eq!(advance(&mut animator, &clip, 0.25), Vec::<String>::new());
+        assert_eq!(animator.time, 0.0);
+    }
+}
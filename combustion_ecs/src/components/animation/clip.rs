@@ -0,0 +1,298 @@
+//! Animation clips, which are keyframes of translation, rotation and scale along with named events
+
+use nalgebra::{Vector3, Quaternion};
+
+use common::num_utils::{LerpExt, slerp};
+
+use components::hierarchy::transform::Component as Transform;
+
+/// How to get from one keyframe to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Hold the keyframe's value until the next keyframe
+    Step,
+    /// Interpolate at a constant rate, spherically for rotations
+    Linear,
+    /// Like `Linear`, but easing in and out of each keyframe
+    Smooth,
+}
+
+impl Interpolation {
+    /// Adjusts the fraction of the way between two keyframes, or returns `None` to hold the first one
+    fn weight(self, t: f32) -> Option<f32> {
+        match self {
+            Interpolation::Step => None,
+            Interpolation::Linear => Some(t),
+            Interpolation::Smooth => Some(t * t * (3.0 - 2.0 * t)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe<T> {
+    /// Time of the keyframe, in seconds from the start of the clip
+    pub time: f32,
+    pub value: T,
+    /// Interpolation from this keyframe to the next
+    pub interpolation: Interpolation,
+}
+
+/// Named event fired when playback passes its time
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipEvent {
+    pub time: f32,
+    pub name: String,
+}
+
+/// Values of every animated property at a single point in a clip. Properties without keyframes are left alone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    pub translation: Option<Vector3<f32>>,
+    pub rotation: Option<Quaternion<f32>>,
+    pub scale: Option<Vector3<f32>>,
+}
+
+impl Pose {
+    /// Writes the animated properties into a transform
+    pub fn apply(&self, transform: &mut Transform) {
+        if let Some(translation) = self.translation {
+            transform.set_translation(translation);
+        }
+
+        if let Some(rotation) = self.rotation {
+            transform.set_rotation(rotation);
+        }
+
+        if let Some(scale) = self.scale {
+            transform.set_scale(scale);
+        }
+    }
+}
+
+/// Anything played back over time that fires events along the way
+pub trait Timeline {
+    /// Length of the timeline, in seconds
+    fn duration(&self) -> f32;
+
+    /// Calls `fired` with every event passed going from `from` to `to`, in the order they're passed.
+    ///
+    /// Playback can go either way, so `to` may be before `from`. The ends are only included if asked for.
+    fn fire_events(&self, from: f32, to: f32, include_from: bool, include_to: bool, fired: &mut FnMut(&ClipEvent));
+}
+
+/// Fires events from a list sorted by time, for implementing `Timeline::fire_events`
+pub fn fire_sorted_events(events: &[ClipEvent], from: f32, to: f32, include_from: bool, include_to: bool, fired: &mut FnMut(&ClipEvent)) {
+    let passed = |time: f32| {
+        let after_from = if from <= to { time > from } else { time < from };
+        let before_to = if from <= to { time < to } else { time > to };
+
+        (after_from || (include_from && time == from)) && (before_to || (include_to && time == to))
+    };
+
+    if from <= to {
+        for event in events.iter().filter(|event| passed(event.time)) {
+            fired(event);
+        }
+    } else {
+        for event in events.iter().rev().filter(|event| passed(event.time)) {
+            fired(event);
+        }
+    }
+}
+
+/// Keyframe animation of a transform, with keyframes kept sorted by time
+#[derive(Debug, Clone, Default)]
+pub struct AnimationClip {
+    translation: Vec<Keyframe<Vector3<f32>>>,
+    rotation: Vec<Keyframe<Quaternion<f32>>>,
+    scale: Vec<Keyframe<Vector3<f32>>>,
+    events: Vec<ClipEvent>,
+    duration: f32,
+}
+
+impl AnimationClip {
+    /// Creates an empty clip, which has zero length
+    pub fn new() -> AnimationClip {
+        AnimationClip::default()
+    }
+
+    pub fn with_translation(mut self, time: f32, translation: Vector3<f32>, interpolation: Interpolation) -> AnimationClip {
+        self.add_translation(time, translation, interpolation);
+        self
+    }
+
+    pub fn with_rotation(mut self, time: f32, rotation: Quaternion<f32>, interpolation: Interpolation) -> AnimationClip {
+        self.add_rotation(time, rotation, interpolation);
+        self
+    }
+
+    pub fn with_scale(mut self, time: f32, scale: Vector3<f32>, interpolation: Interpolation) -> AnimationClip {
+        self.add_scale(time, scale, interpolation);
+        self
+    }
+
+    pub fn with_event<S: Into<String>>(mut self, time: f32, name: S) -> AnimationClip {
+        self.add_event(time, name);
+        self
+    }
+
+    pub fn add_translation(&mut self, time: f32, translation: Vector3<f32>, interpolation: Interpolation) {
+        insert_sorted(&mut self.translation, Keyframe { time: time, value: translation, interpolation: interpolation }, |key| key.time);
+        self.extend_duration(time);
+    }
+
+    pub fn add_rotation(&mut self, time: f32, rotation: Quaternion<f32>, interpolation: Interpolation) {
+        insert_sorted(&mut self.rotation, Keyframe { time: time, value: rotation, interpolation: interpolation }, |key| key.time);
+        self.extend_duration(time);
+    }
+
+    pub fn add_scale(&mut self, time: f32, scale: Vector3<f32>, interpolation: Interpolation) {
+        insert_sorted(&mut self.scale, Keyframe { time: time, value: scale, interpolation: interpolation }, |key| key.time);
+        self.extend_duration(time);
+    }
+
+    pub fn add_event<S: Into<String>>(&mut self, time: f32, name: S) {
+        insert_sorted(&mut self.events, ClipEvent { time: time, name: name.into() }, |event| event.time);
+        self.extend_duration(time);
+    }
+
+    #[inline]
+    pub fn events(&self) -> &[ClipEvent] { &self.events }
+
+    /// Samples every animated property at `time`, which is clamped to the clip
+    pub fn sample(&self, time: f32) -> Pose {
+        Pose {
+            translation: sample_track(&self.translation, time, lerp_vector),
+            rotation: sample_track(&self.rotation, time, slerp),
+            scale: sample_track(&self.scale, time, lerp_vector),
+        }
+    }
+
+    fn extend_duration(&mut self, time: f32) {
+        if time > self.duration {
+            self.duration = time;
+        }
+    }
+}
+
+impl Timeline for AnimationClip {
+    /// Time of the last keyframe or event
+    #[inline]
+    fn duration(&self) -> f32 { self.duration }
+
+    #[inline]
+    fn fire_events(&self, from: f32, to: f32, include_from: bool, include_to: bool, fired: &mut FnMut(&ClipEvent)) {
+        fire_sorted_events(&self.events, from, to, include_from, include_to, fired);
+    }
+}
+
+/// Inserts after anything at the same time, so the last one added takes over from there
+fn insert_sorted<T, F>(items: &mut Vec<T>, item: T, time: F) where F: Fn(&T) -> f32 {
+    let index = items.iter().position(|existing| time(existing) > time(&item)).unwrap_or(items.len());
+
+    items.insert(index, item);
+}
+
+fn sample_track<T: Copy, F>(keys: &[Keyframe<T>], time: f32, interpolate: F) -> Option<T> where F: Fn(T, T, f32) -> T {
+    let last = match keys.last() {
+        Some(last) => last,
+        None => return None,
+    };
+
+    if time >= last.time {
+        return Some(last.value);
+    }
+
+    //There's always a key after `time`, since it's before the last one
+    let next = keys.iter().position(|key| key.time > time).unwrap();
+
+    if next == 0 {
+        return Some(keys[0].value);
+    }
+
+    let (a, b) = (&keys[next - 1], &keys[next]);
+
+    //Sampling exactly on a key gives exactly its value, rather than something interpolated to be nearly it
+    if time == a.time {
+        return Some(a.value);
+    }
+
+    Some(match a.interpolation.weight((time - a.time) / (b.time - a.time)) {
+        Some(t) => interpolate(a.value, b.value, t),
+        None => a.value,
+    })
+}
+
+fn lerp_vector(a: Vector3<f32>, b: Vector3<f32>, t: f32) -> Vector3<f32> {
+    Vector3::new(a.x.lerp(t, b.x), a.y.lerp(t, b.y), a.z.lerp(t, b.z))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ramp() -> AnimationClip {
+        AnimationClip::new()
+            .with_translation(0.0, Vector3::new(0.0, 0.0, 0.0), Interpolation::Linear)
+            .with_translation(1.0, Vector3::new(2.0, 0.0, 0.0), Interpolation::Step)
+            .with_translation(2.0, Vector3::new(4.0, 0.0, 0.0), Interpolation::Linear)
+    }
+
+    #[test]
+    fn test_sample_on_and_between_keys() {
+        let clip = ramp();
+
+        assert_eq!(clip.duration(), 2.0);
+
+        assert_eq!(clip.sample(0.0).translation, Some(Vector3::new(0.0, 0.0, 0.0)));
+        assert_eq!(clip.sample(0.5).translation, Some(Vector3::new(1.0, 0.0, 0.0)));
+        assert_eq!(clip.sample(1.0).translation, Some(Vector3::new(2.0, 0.0, 0.0)));
+        assert_eq!(clip.sample(2.0).translation, Some(Vector3::new(4.0, 0.0, 0.0)));
+
+        //Stepped keys hold their value until the next key
+        assert_eq!(clip.sample(1.5).translation, Some(Vector3::new(2.0, 0.0, 0.0)));
+
+        //Sampling outside the clip holds the first or last key
+        assert_eq!(clip.sample(-1.0).translation, Some(Vector3::new(0.0, 0.0, 0.0)));
+        assert_eq!(clip.sample(5.0).translation, Some(Vector3::new(4.0, 0.0, 0.0)));
+
+        //Only animated properties are part of the pose
+        assert_eq!(clip.sample(0.5).rotation, None);
+        assert_eq!(clip.sample(0.5).scale, None);
+    }
+
+    #[test]
+    fn test_sample_rotation_and_easing() {
+        let clip = AnimationClip::new()
+            .with_rotation(0.0, Quaternion::new(1.0, 0.0, 0.0, 0.0), Interpolation::Linear)
+            .with_rotation(1.0, Quaternion::new(0.0, 0.0, 1.0, 0.0), Interpolation::Linear)
+            .with_scale(0.0, Vector3::new(1.0, 1.0, 1.0), Interpolation::Smooth)
+            .with_scale(1.0, Vector3::new(2.0, 2.0, 2.0), Interpolation::Linear);
+
+        let rotation = clip.sample(0.5).rotation.unwrap();
+
+        assert!((rotation.w - 0.5f32.sqrt()).abs() < 1e-6);
+        assert!((rotation.j - 0.5f32.sqrt()).abs() < 1e-6);
+
+        assert_eq!(clip.sample(1.0).rotation, Some(Quaternion::new(0.0, 0.0, 1.0, 0.0)));
+
+        //Eased halfway is still halfway, but a quarter of the way in is slower
+        assert_eq!(clip.sample(0.5).scale, Some(Vector3::new(1.5, 1.5, 1.5)));
+        assert_eq!(clip.sample(0.25).scale, Some(Vector3::new(1.15625, 1.15625, 1.15625)));
+    }
+
+    #[test]
+    fn test_zero_length_clip() {
+        let empty = AnimationClip::new();
+
+        assert_eq!(empty.duration(), 0.0);
+        assert_eq!(empty.sample(1.0), Pose { translation: None, rotation: None, scale: None });
+
+        let single = AnimationClip::new().with_translation(0.0, Vector3::new(1.0, 2.0, 3.0), Interpolation::Linear);
+
+        assert_eq!(single.duration(), 0.0);
+        assert_eq!(single.sample(0.0).translation, Some(Vector3::new(1.0, 2.0, 3.0)));
+        assert_eq!(single.sample(-1.0).translation, Some(Vector3::new(1.0, 2.0, 3.0)));
+        assert_eq!(single.sample(1.0).translation, Some(Vector3::new(1.0, 2.0, 3.0)));
+    }
+}
@@ -0,0 +1,20 @@
+//! Keyframe animation components
+//!
+//! An `animator::Component` plays an `AnimationClip` on its entity, and the animation system writes the sampled pose
+//! into the entity's `hierarchy::transform::Component` every tick. A `skeletal_animator::Component` plays a
+//! `SkeletalClip` on a `Skeleton` instead, producing the skinning matrices for each of its bones.
+
+use specs;
+
+pub mod clip;
+pub mod animator;
+pub mod skeleton;
+pub mod skeletal_animator;
+
+pub use self::clip::AnimationClip;
+pub use self::skeleton::{Skeleton, SkeletalClip, SkeletonError};
+
+pub fn register_all(world: &mut specs::World) {
+    ecs_register_mod!(world, animator);
+    ecs_register_mod!(world, skeletal_animator);
+}
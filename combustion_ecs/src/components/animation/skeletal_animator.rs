@@ -0,0 +1,84 @@
+//! Skeletal animator component, playing a skeletal clip on a skeleton and keeping the resulting skinning matrices
+//!
+//! Nothing is played until both the skeleton and clip finish loading.
+
+use std::ops::{Deref, DerefMut};
+
+use nalgebra::Matrix4;
+
+use specs;
+
+use asset::handle::AssetHandle;
+
+use super::animator::{Playback, WrapMode};
+use super::skeleton::{Skeleton, SkeletalClip};
+
+#[derive(Debug, Clone)]
+pub struct Component {
+    pub skeleton: AssetHandle<Skeleton>,
+    pub clip: AssetHandle<SkeletalClip>,
+    pub playback: Playback,
+    /// Local matrix of each bone, reused between updates
+    locals: Vec<Matrix4<f32>>,
+    /// Model space matrix of each bone from the last update
+    bones: Vec<Matrix4<f32>>,
+    /// Skinning matrix of each bone from the last update
+    palette: Vec<Matrix4<f32>>,
+}
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Component {
+    /// Creates an animator that plays the clip once from the start
+    pub fn new(skeleton: AssetHandle<Skeleton>, clip: AssetHandle<SkeletalClip>) -> Component {
+        Component {
+            skeleton: skeleton,
+            clip: clip,
+            playback: Playback::new(),
+            locals: Vec::new(),
+            bones: Vec::new(),
+            palette: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn with_speed(mut self, speed: f32) -> Component {
+        self.playback.speed = speed;
+        self
+    }
+
+    #[inline]
+    pub fn with_wrap(mut self, wrap: WrapMode) -> Component {
+        self.playback.wrap = wrap;
+        self
+    }
+
+    /// Samples the clip at the current time and recomputes every bone's matrices
+    pub fn update_pose(&mut self, skeleton: &Skeleton, clip: &SkeletalClip) {
+        clip.sample(skeleton, self.playback.time, &mut self.locals);
+        skeleton.compose(&self.locals, &mut self.bones);
+        skeleton.palette(&self.bones, &mut self.palette);
+    }
+
+    /// Model space matrix of each bone, for attaching things to them
+    #[inline]
+    pub fn bones(&self) -> &[Matrix4<f32>] { &self.bones }
+
+    /// Skinning matrix of each bone, in the same order as the skeleton's bones
+    #[inline]
+    pub fn palette(&self) -> &[Matrix4<f32>] { &self.palette }
+}
+
+impl Deref for Component {
+    type Target = Playback;
+
+    #[inline(always)]
+    fn deref(&self) -> &Playback { &self.playback }
+}
+
+impl DerefMut for Component {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Playback { &mut self.playback }
+}
@@ -0,0 +1,381 @@
+//! Skeletons and the bone animation clips played on them
+//!
+//! Bones are stored parents first, so one pass down the list composes every bone onto its already composed parent.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use nalgebra::{Matrix4, Vector3, Vector4, Eye, Inverse};
+
+use components::hierarchy::transform::Component as Transform;
+
+use super::clip::{AnimationClip, ClipEvent, Timeline, fire_sorted_events};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkeletonError {
+    /// A bone comes before its parent, so it can't be composed onto it
+    ParentAfterChild(String),
+    /// More than one bone has the same name
+    DuplicateBone(String),
+    /// A bone's rest transform can't be inverted, so the skeleton can't be bound at rest
+    SingularRest(String),
+}
+
+impl Display for SkeletonError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            SkeletonError::ParentAfterChild(ref bone) => write!(f, "Bone {:?} comes before its parent", bone),
+            SkeletonError::DuplicateBone(ref bone) => write!(f, "More than one bone is named {:?}", bone),
+            SkeletonError::SingularRest(ref bone) => {
+                write!(f, "Bone {:?} can't be bound at rest, since its rest transform can't be inverted", bone)
+            }
+        }
+    }
+}
+
+impl Error for SkeletonError {
+    fn description(&self) -> &str {
+        match *self {
+            SkeletonError::ParentAfterChild(_) => "Bone before its parent",
+            SkeletonError::DuplicateBone(_) => "Duplicate bone name",
+            SkeletonError::SingularRest(_) => "Bone rest transform can't be inverted",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Bone {
+    pub name: String,
+    /// Index of the parent bone, which always comes earlier in the skeleton
+    pub parent: Option<usize>,
+    /// Transform relative to the parent bone while nothing animates it
+    pub rest: Transform,
+    /// Inverse of the bone's model space matrix in the pose the mesh was modelled in
+    pub inverse_bind: Matrix4<f32>,
+}
+
+impl Bone {
+    /// Creates a bone whose inverse bind matrix is filled in later, such as by `Skeleton::bind_rest_pose`
+    pub fn new<S: Into<String>>(name: S, parent: Option<usize>, rest: Transform) -> Bone {
+        Bone { name: name.into(), parent: parent, rest: rest, inverse_bind: Matrix4::new_identity(4) }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Skeleton {
+    bones: Vec<Bone>,
+    /// Index of each bone by name
+    names: HashMap<String, usize>,
+}
+
+impl Skeleton {
+    /// Creates a skeleton, checking that every parent comes before its children and that bone names are unique
+    pub fn new(bones: Vec<Bone>) -> Result<Skeleton, SkeletonError> {
+        let mut names = HashMap::with_capacity(bones.len());
+
+        for (index, bone) in bones.iter().enumerate() {
+            if let Some(parent) = bone.parent {
+                if parent >= index {
+                    return Err(SkeletonError::ParentAfterChild(bone.name.clone()));
+                }
+            }
+
+            if names.insert(bone.name.clone(), index).is_some() {
+                return Err(SkeletonError::DuplicateBone(bone.name.clone()));
+            }
+        }
+
+        Ok(Skeleton { bones: bones, names: names })
+    }
+
+    /// Sets every bone's inverse bind matrix from the rest pose, for meshes modelled around the skeleton at rest
+    pub fn bind_rest_pose(&mut self) -> Result<(), SkeletonError> {
+        let mut locals = Vec::with_capacity(self.bones.len());
+        let mut models = Vec::with_capacity(self.bones.len());
+
+        self.rest_pose(&mut locals);
+        self.compose(&locals, &mut models);
+
+        for (bone, model) in self.bones.iter_mut().zip(models) {
+            bone.inverse_bind = match model.inverse() {
+                Some(inverse) => inverse,
+                None => return Err(SkeletonError::SingularRest(bone.name.clone())),
+            };
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize { self.bones.len() }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.bones.is_empty() }
+
+    #[inline]
+    pub fn bones(&self) -> &[Bone] { &self.bones }
+
+    /// Index of the bone with the given name
+    #[inline]
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.names.get(name).cloned()
+    }
+
+    /// Local matrix of every bone at rest
+    pub fn rest_pose(&self, locals: &mut Vec<Matrix4<f32>>) {
+        locals.clear();
+        locals.extend(self.bones.iter().map(|bone| bone.rest.matrix()));
+    }
+
+    /// Composes local bone matrices down the hierarchy into model space matrices
+    pub fn compose(&self, locals: &[Matrix4<f32>], models: &mut Vec<Matrix4<f32>>) {
+        models.clear();
+
+        for (bone, local) in self.bones.iter().zip(locals) {
+            let model = match bone.parent {
+                Some(parent) => models[parent] * *local,
+                None => *local,
+            };
+
+            models.push(model);
+        }
+    }
+
+    /// Skinning matrices for each bone, taking vertices from the bind pose to where the bones are now
+    pub fn palette(&self, models: &[Matrix4<f32>], palette: &mut Vec<Matrix4<f32>>) {
+        palette.clear();
+        palette.extend(self.bones.iter().zip(models).map(|(bone, model)| *model * bone.inverse_bind));
+    }
+}
+
+/// Animation of a single bone, relative to its parent
+#[derive(Debug, Clone)]
+pub struct BoneChannel {
+    pub bone: String,
+    /// Events in the channel's clip are ignored, and go in the skeletal clip instead
+    pub clip: AnimationClip,
+}
+
+/// Animation of a whole skeleton, with a channel for each animated bone
+#[derive(Debug, Clone, Default)]
+pub struct SkeletalClip {
+    channels: Vec<BoneChannel>,
+    events: Vec<ClipEvent>,
+    duration: f32,
+}
+
+impl SkeletalClip {
+    pub fn new() -> SkeletalClip {
+        SkeletalClip::default()
+    }
+
+    pub fn with_channel<S: Into<String>>(mut self, bone: S, clip: AnimationClip) -> SkeletalClip {
+        self.add_channel(bone, clip);
+        self
+    }
+
+    pub fn with_event<S: Into<String>>(mut self, time: f32, name: S) -> SkeletalClip {
+        self.add_event(time, name);
+        self
+    }
+
+    pub fn add_channel<S: Into<String>>(&mut self, bone: S, clip: AnimationClip) {
+        if clip.duration() > self.duration {
+            self.duration = clip.duration();
+        }
+
+        self.channels.push(BoneChannel { bone: bone.into(), clip: clip });
+    }
+
+    pub fn add_event<S: Into<String>>(&mut self, time: f32, name: S) {
+        let index = self.events.iter().position(|event| event.time > time).unwrap_or(self.events.len());
+
+        self.events.insert(index, ClipEvent { time: time, name: name.into() });
+
+        if time > self.duration {
+            self.duration = time;
+        }
+    }
+
+    #[inline]
+    pub fn channels(&self) -> &[BoneChannel] { &self.channels }
+
+    /// Local matrix of every bone at `time`. Bones without a channel stay at rest,
+    /// and channels for bones the skeleton doesn't have are skipped, so clips can be shared between similar skeletons.
+    pub fn sample(&self, skeleton: &Skeleton, time: f32, locals: &mut Vec<Matrix4<f32>>) {
+        let mut poses: Vec<Transform> = skeleton.bones().iter().map(|bone| bone.rest.clone()).collect();
+
+        for channel in &self.channels {
+            if let Some(index) = skeleton.find(&channel.bone) {
+                channel.clip.sample(time).apply(&mut poses[index]);
+            }
+        }
+
+        locals.clear();
+        locals.extend(poses.iter().map(Transform::matrix));
+    }
+}
+
+impl Timeline for SkeletalClip {
+    /// Time of the last keyframe in any channel, or the last event
+    #[inline]
+    fn duration(&self) -> f32 { self.duration }
+
+    #[inline]
+    fn fire_events(&self, from: f32, to: f32, include_from: bool, include_to: bool, fired: &mut FnMut(&ClipEvent)) {
+        fire_sorted_events(&self.events, from, to, include_from, include_to, fired);
+    }
+}
+
+/// Skins a vertex position on the CPU, the same way the skinning vertex shader would,
+/// as a reference to check GPU skinning against.
+///
+/// Influences are pairs of bone index and weight, with weights expected to add up to one.
+pub fn skin_position(palette: &[Matrix4<f32>], position: Vector3<f32>, influences: &[(usize, f32)]) -> Vector3<f32> {
+    let point = Vector4::new(position.x, position.y, position.z, 1.0);
+
+    let mut skinned = Vector4::new(0.0, 0.0, 0.0, 0.0);
+    let mut total = 0.0;
+
+    for &(bone, weight) in influences {
+        if let Some(matrix) = palette.get(bone) {
+            skinned = skinned + (*matrix * point) * weight;
+            total += weight;
+        }
+    }
+
+    //Vertices not attached to any bone stay where they are
+    if total <= 0.0 {
+        return position;
+    }
+
+    Vector3::new(skinned.x, skinned.y, skinned.z)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use nalgebra::Quaternion;
+
+    use asset::handle::AssetHandle;
+
+    use super::super::clip::Interpolation;
+    use super::super::skeletal_animator::Component as SkeletalAnimator;
+
+    fn close(a: Vector3<f32>, b: Vector3<f32>) -> bool {
+        (a.x - b.x).abs() < 1e-5 && (a.y - b.y).abs() < 1e-5 && (a.z - b.z).abs() < 1e-5
+    }
+
+    /// Upper arm at the origin, with the forearm one unit above it and the hand one unit above that
+    fn arm() -> Skeleton {
+        let mut skeleton = Skeleton::new(vec![
+            Bone::new("upper", None, Transform::new()),
+            Bone::new("fore", Some(0), Transform::from_translation(0.0, 1.0, 0.0)),
+            Bone::new("hand", Some(1), Transform::from_translation(0.0, 1.0, 0.0)),
+        ]).unwrap();
+
+        skeleton.bind_rest_pose().unwrap();
+
+        skeleton
+    }
+
+    /// Swings the upper arm a quarter turn about Z over one second
+    fn swing() -> SkeletalClip {
+        let quarter = Quaternion::new(0.5f32.sqrt(), 0.0, 0.0, 0.5f32.sqrt());
+
+        SkeletalClip::new()
+            .with_channel("upper", AnimationClip::new()
+                .with_rotation(0.0, Quaternion::new(1.0, 0.0, 0.0, 0.0), Interpolation::Linear)
+                .with_rotation(1.0, quarter, Interpolation::Linear))
+            .with_channel("missing", AnimationClip::new()
+                .with_translation(2.0, Vector3::new(5.0, 0.0, 0.0), Interpolation::Linear))
+            .with_event(0.5, "halfway")
+    }
+
+    fn skin(skeleton: &Skeleton, clip: &SkeletalClip, time: f32, position: Vector3<f32>, influences: &[(usize, f32)]) -> Vector3<f32> {
+        let (mut locals, mut models, mut palette) = (Vec::new(), Vec::new(), Vec::new());
+
+        clip.sample(skeleton, time, &mut locals);
+        skeleton.compose(&locals, &mut models);
+        skeleton.palette(&models, &mut palette);
+
+        skin_position(&palette, position, influences)
+    }
+
+    #[test]
+    fn test_invalid_skeletons() {
+        assert_eq!(Skeleton::new(vec![
+            Bone::new("child", Some(1), Transform::new()),
+            Bone::new("parent", None, Transform::new()),
+        ]).err(), Some(SkeletonError::ParentAfterChild("child".to_string())));
+
+        assert_eq!(Skeleton::new(vec![
+            Bone::new("bone", None, Transform::new()),
+            Bone::new("bone", Some(0), Transform::new()),
+        ]).err(), Some(SkeletonError::DuplicateBone("bone".to_string())));
+
+        let mut flat = Skeleton::new(vec![
+            Bone::new("flat", None, Transform::from_parts(Vector3::new(0.0, 0.0, 0.0), Quaternion::new(1.0, 0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 1.0))),
+        ]).unwrap();
+
+        assert_eq!(flat.bind_rest_pose(), Err(SkeletonError::SingularRest("flat".to_string())));
+    }
+
+    #[test]
+    fn test_rest_pose_leaves_mesh_alone() {
+        let skeleton = arm();
+        let clip = SkeletalClip::new();
+
+        assert_eq!(skeleton.find("hand"), Some(2));
+
+        let position = Vector3::new(0.25, 1.5, -0.5);
+
+        assert!(close(skin(&skeleton, &clip, 0.0, position, &[(1, 0.5), (2, 0.5)]), position));
+        assert!(close(skin(&skeleton, &clip, 0.0, position, &[]), position));
+    }
+
+    #[test]
+    fn test_cpu_skinning_follows_bones() {
+        let skeleton = arm();
+        let clip = swing();
+
+        //Channels for bones the skeleton doesn't have still count towards the length
+        assert_eq!(clip.duration(), 2.0);
+
+        //Sampling exactly on the first key is the rest pose
+        assert!(close(skin(&skeleton, &clip, 0.0, Vector3::new(0.0, 2.0, 0.0), &[(2, 1.0)]), Vector3::new(0.0, 2.0, 0.0)));
+
+        //Turning the upper arm carries the rest of the arm around with it
+        assert!(close(skin(&skeleton, &clip, 1.0, Vector3::new(0.0, 2.0, 0.0), &[(2, 1.0)]), Vector3::new(-2.0, 0.0, 0.0)));
+        assert!(close(skin(&skeleton, &clip, 1.0, Vector3::new(0.0, 1.5, 0.0), &[(0, 0.5), (1, 0.5)]), Vector3::new(-1.5, 0.0, 0.0)));
+
+        //Halfway through the turn, the elbow has swung an eighth of the way round
+        let elbow = skin(&skeleton, &clip, 0.5, Vector3::new(0.0, 1.0, 0.0), &[(1, 1.0)]);
+
+        assert!(close(elbow, Vector3::new(-(0.5f32.sqrt()), 0.5f32.sqrt(), 0.0)));
+    }
+
+    #[test]
+    fn test_animator_palette() {
+        let skeleton = arm();
+        let clip = swing();
+
+        let mut animator = SkeletalAnimator::new(AssetHandle::loaded(skeleton.clone()), AssetHandle::loaded(clip.clone())).with_speed(2.0);
+
+        let mut fired = Vec::new();
+
+        animator.advance(&clip, 0.5, &mut |event| fired.push(event.name.clone()));
+        animator.update_pose(&skeleton, &clip);
+
+        assert_eq!(fired, vec!["halfway"]);
+        assert_eq!(animator.time, 1.0);
+        assert_eq!(animator.palette().len(), skeleton.len());
+
+        let hand = animator.bones()[2];
+
+        assert!(close(Vector3::new(hand.m14, hand.m24, hand.m34), Vector3::new(-2.0, 0.0, 0.0)));
+        assert!(close(skin_position(animator.palette(), Vector3::new(0.0, 2.0, 0.0), &[(2, 1.0)]), Vector3::new(-2.0, 0.0, 0.0)));
+    }
+}
@@ -0,0 +1,231 @@
+//! Behavior component, for small one-off behaviors that don't deserve a system of their own
+//!
+//! The behavior system runs every enabled behavior once per fixed tick, handing it a `BehaviorContext` with the
+//! entity's transform, the time resource and the behavior event bus. A behavior that panics is disabled, leaving
+//! every other behavior running.
+//!
+//! The built-in behaviors are saved with the scene. Custom behaviors are closures, so they have to be attached again
+//! by code after a scene is loaded.
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::sync::Arc;
+use std::f32::consts::PI;
+
+use specs;
+use nalgebra::{Vector3, Point3, Matrix4, Quaternion, Norm, Cross};
+
+use ::components::hierarchy::transform::Component as Transform;
+use ::events::Events;
+use ::time::Resource as Time;
+
+/// Sent by behaviors through `BehaviorContext::send`, for game code to react to
+#[derive(Clone, Debug, PartialEq)]
+pub struct BehaviorEvent {
+    /// Entity whose behavior sent the event
+    pub entity: specs::Entity,
+    pub name: String,
+}
+
+/// Everything a behavior can see and change during a tick
+pub struct BehaviorContext<'a> {
+    pub entity: specs::Entity,
+    pub transform: &'a mut Transform,
+    pub time: &'a Time,
+    /// Length of this tick, in seconds
+    pub delta: f32,
+    /// Seconds the behavior had run for before this tick
+    pub elapsed: f32,
+    pub events: &'a mut Events<BehaviorEvent>,
+    world_matrices: &'a Fn(specs::Entity) -> Option<Matrix4<f32>>,
+}
+
+impl<'a> BehaviorContext<'a> {
+    pub fn new(entity: specs::Entity, transform: &'a mut Transform, time: &'a Time, delta: f32, elapsed: f32,
+               events: &'a mut Events<BehaviorEvent>, world_matrices: &'a Fn(specs::Entity) -> Option<Matrix4<f32>>) -> BehaviorContext<'a> {
+        BehaviorContext {
+            entity: entity,
+            transform: transform,
+            time: time,
+            delta: delta,
+            elapsed: elapsed,
+            events: events,
+            world_matrices: world_matrices,
+        }
+    }
+
+    /// World transform of any entity as of the last hierarchy update, including this one
+    #[inline]
+    pub fn world_matrix(&self, entity: specs::Entity) -> Option<Matrix4<f32>> {
+        (self.world_matrices)(entity)
+    }
+
+    /// World position of any entity as of the last hierarchy update
+    pub fn world_position(&self, entity: specs::Entity) -> Option<Point3<f32>> {
+        self.world_matrix(entity).map(|m| Point3::new(m.m14, m.m24, m.m34))
+    }
+
+    /// Sends a behavior event from this entity
+    pub fn send<S: Into<String>>(&mut self, name: S) {
+        self.events.send(BehaviorEvent { entity: self.entity, name: name.into() });
+    }
+}
+
+pub type CustomBehavior = Arc<Fn(&mut BehaviorContext) + Send + Sync>;
+
+#[derive(Clone)]
+pub enum Behavior {
+    /// Spins around `axis` in the entity's local space, at `rate` radians per second
+    Rotate { axis: Vector3<f32>, rate: f32 },
+    /// Moves back and forth along `axis` by up to `amplitude` either side of where it started, `frequency` times per second
+    Oscillate { axis: Vector3<f32>, amplitude: f32, frequency: f32 },
+    /// Turns to face `target`, keeping `up` as close to up as it can. Assumes the entity's parent, if any, isn't rotated.
+    LookAt { target: specs::Entity, up: Vector3<f32> },
+    Custom(CustomBehavior),
+}
+
+impl Debug for Behavior {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            Behavior::Rotate { ref axis, rate } => write!(f, "Rotate {{ axis: {:?}, rate: {:?} }}", axis, rate),
+            Behavior::Oscillate { ref axis, amplitude, frequency } => {
+                write!(f, "Oscillate {{ axis: {:?}, amplitude: {:?}, frequency: {:?} }}", axis, amplitude, frequency)
+            }
+            Behavior::LookAt { target, ref up } => write!(f, "LookAt {{ target: {:?}, up: {:?} }}", target, up),
+            Behavior::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+impl Behavior {
+    /// Runs the behavior for one tick
+    pub fn run(&self, context: &mut BehaviorContext) {
+        match *self {
+            Behavior::Rotate { axis, rate } => {
+                let length = axis.norm();
+
+                if length > 0.0 {
+                    let axis = axis / length;
+                    let (sin, cos) = (rate * context.delta * 0.5).sin_cos();
+
+                    let turn = Quaternion::new(cos, axis.x * sin, axis.y * sin, axis.z * sin);
+
+                    let rotation = normalize(*context.transform.rotation() * turn);
+
+                    context.transform.set_rotation(rotation);
+                }
+            }
+            Behavior::Oscillate { axis, amplitude, frequency } => {
+                //Moving by the change in offset, rather than to an absolute offset, leaves the transform free to be moved by other things too
+                let offset = |time: f32| (time * frequency * 2.0 * PI).sin() * amplitude;
+
+                let change = offset(context.elapsed + context.delta) - offset(context.elapsed);
+
+                let translation = *context.transform.translation() + axis * change;
+
+                context.transform.set_translation(translation);
+            }
+            Behavior::LookAt { target, up } => {
+                let (from, to) = match (context.world_position(context.entity), context.world_position(target)) {
+                    (Some(from), Some(to)) => (from, to),
+                    _ => return,
+                };
+
+                if let Some(rotation) = look_rotation(to - from, up) {
+                    context.transform.set_rotation(rotation);
+                }
+            }
+            Behavior::Custom(ref behavior) => behavior(context),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Component {
+    pub behavior: Behavior,
+    /// Disabled behaviors are skipped. Behaviors that panic are disabled automatically.
+    pub enabled: bool,
+    /// Seconds the behavior has run for
+    pub elapsed: f32,
+}
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Component {
+    #[inline]
+    pub fn new(behavior: Behavior) -> Component {
+        Component { behavior: behavior, enabled: true, elapsed: 0.0 }
+    }
+
+    #[inline]
+    pub fn rotate(axis: Vector3<f32>, rate: f32) -> Component {
+        Component::new(Behavior::Rotate { axis: axis, rate: rate })
+    }
+
+    #[inline]
+    pub fn oscillate(axis: Vector3<f32>, amplitude: f32, frequency: f32) -> Component {
+        Component::new(Behavior::Oscillate { axis: axis, amplitude: amplitude, frequency: frequency })
+    }
+
+    #[inline]
+    pub fn look_at(target: specs::Entity) -> Component {
+        Component::new(Behavior::LookAt { target: target, up: Vector3::new(0.0, 1.0, 0.0) })
+    }
+
+    #[inline]
+    pub fn custom<F>(behavior: F) -> Component where F: Fn(&mut BehaviorContext) + Send + Sync + 'static {
+        Component::new(Behavior::Custom(Arc::new(behavior)))
+    }
+}
+
+fn normalize(q: Quaternion<f32>) -> Quaternion<f32> {
+    let length = (q.w * q.w + q.i * q.i + q.j * q.j + q.k * q.k).sqrt();
+
+    Quaternion::new(q.w / length, q.i / length, q.j / length, q.k / length)
+}
+
+/// Rotation turning -Z towards `direction` with Y as close to `up` as it can be,
+/// or `None` if the direction is zero or parallel to `up`
+pub fn look_rotation(direction: Vector3<f32>, up: Vector3<f32>) -> Option<Quaternion<f32>> {
+    let length = direction.norm();
+
+    if length <= 0.0 {
+        return None;
+    }
+
+    let z = -direction / length;
+    let x = up.cross(&z);
+
+    let x_length = x.norm();
+
+    if x_length <= 1e-6 {
+        return None;
+    }
+
+    let x = x / x_length;
+    let y = z.cross(&x);
+
+    //Columns of the rotation matrix are the new axes, converted to a quaternion from whichever diagonal term is largest
+    let (m11, m12, m13) = (x.x, y.x, z.x);
+    let (m21, m22, m23) = (x.y, y.y, z.y);
+    let (m31, m32, m33) = (x.z, y.z, z.z);
+
+    let trace = m11 + m22 + m33;
+
+    let q = if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+        Quaternion::new(0.25 * s, (m32 - m23) / s, (m13 - m31) / s, (m21 - m12) / s)
+    } else if m11 > m22 && m11 > m33 {
+        let s = (1.0 + m11 - m22 - m33).sqrt() * 2.0;
+        Quaternion::new((m32 - m23) / s, 0.25 * s, (m12 + m21) / s, (m13 + m31) / s)
+    } else if m22 > m33 {
+        let s = (1.0 + m22 - m11 - m33).sqrt() * 2.0;
+        Quaternion::new((m13 - m31) / s, (m12 + m21) / s, 0.25 * s, (m23 + m32) / s)
+    } else {
+        let s = (1.0 + m33 - m11 - m22).sqrt() * 2.0;
+        Quaternion::new((m21 - m12) / s, (m13 + m31) / s, (m23 + m32) / s, 0.25 * s)
+    };
+
+    Some(normalize(q))
+}
@@ -0,0 +1,144 @@
+//! Billboard component, a quad turned to face the camera for things like particles, labels and health bars
+//!
+//! The billboard system turns every visible billboard into an instance of a unit quad facing the active camera,
+//! batched by texture for the transparent pass. Billboards are centered on their entity's world position, and ignore
+//! its rotation and scale.
+//!
+//! What a texture is is up to the renderer, as `T`.
+
+use nalgebra::{Point3, Vector3, Norm, Cross};
+
+use specs;
+
+use asset::handle::AssetHandle;
+
+use ::camera_matrices::Resource as CameraMatrices;
+
+/// How a billboard turns to face the camera
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Facing {
+    /// Parallel to the screen, like a particle
+    Spherical,
+    /// Upright, only turning around the world Y axis, like a tree far in the distance
+    Cylindrical,
+    /// Only turning around an axis in world space. A zero axis faces the camera like `Spherical`.
+    Axis(Vector3<f32>),
+}
+
+impl Facing {
+    /// Right, up and normal axes of a billboard at `position`, all normalized.
+    ///
+    /// Billboards turning around an axis keep the axis as their up, and face the camera as closely as they can. When the
+    /// camera is on the axis, such as looking straight down on an upright billboard, any way around the axis faces it
+    /// equally badly, so they line up with the camera's right instead.
+    pub fn basis(&self, position: &Point3<f32>, camera: &CameraMatrices) -> (Vector3<f32>, Vector3<f32>, Vector3<f32>) {
+        let v = &camera.view;
+
+        //Rows of the view matrix are the camera's axes in world space
+        let right = normalized(Vector3::new(v.m11, v.m12, v.m13)).unwrap_or(Vector3::new(1.0, 0.0, 0.0));
+        let up = normalized(Vector3::new(v.m21, v.m22, v.m23)).unwrap_or(Vector3::new(0.0, 1.0, 0.0));
+        let back = normalized(Vector3::new(v.m31, v.m32, v.m33)).unwrap_or(Vector3::new(0.0, 0.0, 1.0));
+
+        let axis = match *self {
+            Facing::Spherical => return (right, up, back),
+            Facing::Cylindrical => Vector3::new(0.0, 1.0, 0.0),
+            Facing::Axis(axis) => match normalized(axis) {
+                Some(axis) => axis,
+                None => return (right, up, back),
+            },
+        };
+
+        //Removes the part of a vector along the axis
+        let flatten = |v: Vector3<f32>| normalized(v - axis * dot(&axis, &v));
+
+        match flatten(camera.position - *position) {
+            Some(normal) => (axis.cross(&normal), axis, normal),
+            None => {
+                let right = flatten(right).unwrap_or_else(|| perpendicular(&axis));
+
+                (right, axis, right.cross(&axis))
+            }
+        }
+    }
+}
+
+/// Size of a billboard
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Size {
+    /// Width and height in world units
+    World(f32, f32),
+    /// Width and height in pixels, so it's the same size on screen however far away it is
+    Pixels(f32, f32),
+}
+
+pub struct Component<T> {
+    pub size: Size,
+    /// Texture to draw, or `None` for a solid quad in the tint color. Billboards aren't drawn until their texture has loaded.
+    pub texture: Option<AssetHandle<T>>,
+    pub facing: Facing,
+    /// Color multiplied with the texture, including alpha
+    pub tint: [f32; 4],
+    pub visible: bool,
+}
+
+impl<T> specs::Component for Component<T> where T: Send + Sync + 'static {
+    type Storage = specs::VecStorage<Component<T>>;
+}
+
+//The texture handle is shared rather than copied, so cloning doesn't need the texture to be `Clone`
+impl<T> Clone for Component<T> {
+    fn clone(&self) -> Component<T> {
+        Component {
+            size: self.size,
+            texture: self.texture.clone(),
+            facing: self.facing,
+            tint: self.tint,
+            visible: self.visible,
+        }
+    }
+}
+
+impl<T> Component<T> {
+    /// Untextured white billboard facing the camera like a particle
+    #[inline]
+    pub fn new(size: Size) -> Component<T> {
+        Component { size: size, texture: None, facing: Facing::Spherical, tint: [1.0, 1.0, 1.0, 1.0], visible: true }
+    }
+
+    #[inline]
+    pub fn with_texture(mut self, texture: AssetHandle<T>) -> Component<T> {
+        self.texture = Some(texture);
+        self
+    }
+
+    #[inline]
+    pub fn with_facing(mut self, facing: Facing) -> Component<T> {
+        self.facing = facing;
+        self
+    }
+
+    #[inline]
+    pub fn with_tint(mut self, tint: [f32; 4]) -> Component<T> {
+        self.tint = tint;
+        self
+    }
+}
+
+#[inline]
+fn dot(a: &Vector3<f32>, b: &Vector3<f32>) -> f32 {
+    a.x * b.x + a.y * b.y + a.z * b.z
+}
+
+/// Normalized vector, or `None` if it's too short to have a direction
+fn normalized(v: Vector3<f32>) -> Option<Vector3<f32>> {
+    let length = v.norm();
+
+    if length > 1e-6 { Some(v / length) } else { None }
+}
+
+/// Some normalized vector perpendicular to a normalized axis
+fn perpendicular(axis: &Vector3<f32>) -> Vector3<f32> {
+    let other = if axis.x.abs() < 0.9 { Vector3::new(1.0, 0.0, 0.0) } else { Vector3::new(0.0, 1.0, 0.0) };
+
+    axis.cross(&other).normalize()
+}
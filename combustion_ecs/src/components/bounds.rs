@@ -0,0 +1,83 @@
+//! Bounds component, the local space bounding box of an entity used for culling
+//!
+//! By default the box follows the entity's mesh renderer, copied from the bounds stored with the mesh asset
+//! once it has loaded. Entities without a mesh, or wanting a different box, can set one manually instead.
+//!
+//! The bounds system turns this into a `world_bounds::Component` whenever either the box or the world transform changes.
+
+use specs;
+
+pub use bounds::Bounds;
+
+/// Meshes carrying the bounds stored with their asset, which bounds following the mesh are copied from
+pub trait MeshBounds {
+    fn bounds(&self) -> Option<Bounds>;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Component {
+    local: Option<Bounds>,
+    manual: bool,
+    dirty: bool,
+}
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Default for Component {
+    #[inline(always)]
+    fn default() -> Component { Component::from_mesh() }
+}
+
+impl Component {
+    /// Bounds following the entity's mesh renderer, which are empty until the mesh has loaded
+    pub fn from_mesh() -> Component {
+        Component { local: None, manual: false, dirty: true }
+    }
+
+    /// Bounds set manually, which are never replaced by the mesh's
+    pub fn manual(bounds: Bounds) -> Component {
+        Component { local: Some(bounds), manual: true, dirty: true }
+    }
+
+    /// Local space bounding box, or `None` if the mesh hasn't loaded or has no bounds
+    #[inline]
+    pub fn local(&self) -> Option<&Bounds> { self.local.as_ref() }
+
+    #[inline]
+    pub fn is_manual(&self) -> bool { self.manual }
+
+    /// Replaces the bounding box, and stops following the mesh
+    pub fn set_manual(&mut self, bounds: Bounds) {
+        self.local = Some(bounds);
+        self.manual = true;
+        self.dirty = true;
+    }
+
+    /// Goes back to following the mesh, picked up from it next update
+    pub fn follow_mesh(&mut self) {
+        self.local = None;
+        self.manual = false;
+        self.dirty = true;
+    }
+
+    /// Copies in the mesh's bounds, unless set manually. Returns whether they changed.
+    pub fn sync_mesh(&mut self, bounds: Option<Bounds>) -> bool {
+        if self.manual || self.local == bounds {
+            return false;
+        }
+
+        self.local = bounds;
+        self.dirty = true;
+
+        true
+    }
+
+    /// Checks if the bounds changed since the world bounds were last computed
+    #[inline]
+    pub fn is_dirty(&self) -> bool { self.dirty }
+
+    #[inline]
+    pub fn clear_dirty(&mut self) { self.dirty = false; }
+}
@@ -0,0 +1,77 @@
+//! Camera component, describing the projection of an entity that can be viewed through
+//!
+//! Which camera is viewed through is chosen by the active camera resource, and the camera system
+//! combines it with the entity's transform into the camera matrices resource.
+
+use specs;
+use nalgebra::{Matrix4, Perspective3, Orthographic3};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProjectionKind {
+    Perspective,
+    /// Parallel projection, centered on the camera
+    Orthographic,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Component {
+    pub kind: ProjectionKind,
+    /// Vertical field of view in radians, for perspective projections
+    pub fov: f32,
+    /// Height of the view in world units, for orthographic projections
+    pub height: f32,
+    pub near: f32,
+    pub far: f32,
+    /// Width over height of the viewport, updated whenever the viewport is resized
+    pub aspect: f32,
+}
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Component {
+    #[inline(always)]
+    pub fn perspective(fov: f32, near: f32, far: f32) -> Component {
+        Component {
+            kind: ProjectionKind::Perspective,
+            fov: fov,
+            height: 1.0,
+            near: near,
+            far: far,
+            aspect: 16.0 / 9.0,
+        }
+    }
+
+    #[inline(always)]
+    pub fn orthographic(height: f32, near: f32, far: f32) -> Component {
+        Component {
+            kind: ProjectionKind::Orthographic,
+            fov: 0.0,
+            height: height,
+            near: near,
+            far: far,
+            aspect: 16.0 / 9.0,
+        }
+    }
+
+    /// Updates the aspect ratio for a viewport size in pixels. Empty viewports, such as minimized windows, are ignored.
+    pub fn resize(&mut self, width: f32, height: f32) {
+        if width > 0.0 && height > 0.0 {
+            self.aspect = width / height;
+        }
+    }
+
+    pub fn projection(&self) -> Matrix4<f32> {
+        match self.kind {
+            ProjectionKind::Perspective => {
+                Perspective3::new(self.aspect, self.fov, self.near, self.far).to_matrix()
+            }
+            ProjectionKind::Orthographic => {
+                let (half_width, half_height) = (self.height * self.aspect * 0.5, self.height * 0.5);
+
+                Orthographic3::new(-half_width, half_width, -half_height, half_height, self.near, self.far).to_matrix()
+            }
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! Directional light component, infinitely far away and shining down the entity's local -Z axis
+
+use specs;
+use nalgebra::Vector3;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Component {
+    pub enabled: bool,
+    /// Linear RGB color
+    pub color: Vector3<f32>,
+    pub intensity: f32,
+}
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Component {
+    #[inline(always)]
+    pub fn new(color: Vector3<f32>, intensity: f32) -> Component {
+        Component { enabled: true, color: color, intensity: intensity }
+    }
+}
@@ -0,0 +1,55 @@
+//! Group component, listing the named groups an entity belongs to
+//!
+//! Groups are joined and left through the groups resource, or `GroupsExt` on the world, which keep its index of
+//! every group's members up to date. Changing a group component any other way leaves the index out of date.
+
+use smallvec::SmallVec;
+
+use specs;
+
+use ::groups::GroupId;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Component(SmallVec<[GroupId; 4]>);
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Component {
+    #[inline(always)]
+    pub fn new() -> Component { Component(SmallVec::new()) }
+
+    /// Groups the entity belongs to, in the order it joined them
+    #[inline]
+    pub fn ids(&self) -> &[GroupId] { &self.0 }
+
+    #[inline]
+    pub fn contains(&self, id: GroupId) -> bool {
+        self.0.iter().any(|group| *group == id)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+    /// Returns false if the entity was already in the group
+    pub fn insert(&mut self, id: GroupId) -> bool {
+        if self.contains(id) {
+            false
+        } else {
+            self.0.push(id);
+            true
+        }
+    }
+
+    /// Returns false if the entity wasn't in the group
+    pub fn remove(&mut self, id: GroupId) -> bool {
+        match self.0.iter().position(|group| *group == id) {
+            Some(index) => {
+                self.0.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! Transform hierarchy components
+//!
+//! Entities with a `transform::Component` are positioned relative to the entity in their `parent::Component`, if any,
+//! and the hierarchy system writes the combined result into their `world_transform::Component`. When a parent is
+//! deleted without its children, the orphan system handles them according to their `orphan_policy::Component`.
+
+use specs;
+
+pub mod transform;
+pub mod parent;
+pub mod world_transform;
+pub mod orphan_policy;
+
+pub fn register_all(world: &mut specs::World) {
+    ecs_register_mod!(world, transform);
+    ecs_register_mod!(world, parent);
+    ecs_register_mod!(world, world_transform);
+    ecs_register_mod!(world, orphan_policy);
+}
@@ -0,0 +1,22 @@
+//! Orphan policy component, deciding what happens to an entity when its parent is deleted without it
+//!
+//! Entities without one are despawned along with their parent, the same as `HierarchyExt::despawn_recursive` does.
+
+use specs;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Component {
+    /// Delete the entity and everything below it
+    Despawn,
+    /// Remove the entity's parent link, making its transform relative to the world instead
+    Detach,
+}
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Default for Component {
+    #[inline(always)]
+    fn default() -> Component { Component::Despawn }
+}
@@ -0,0 +1,20 @@
+//! Parent link component, making an entity's transform relative to another entity's
+
+use specs;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Component(pub specs::Entity);
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Component {
+    #[inline(always)]
+    pub fn new(parent: specs::Entity) -> Component {
+        Component(parent)
+    }
+
+    #[inline(always)]
+    pub fn entity(&self) -> specs::Entity { self.0 }
+}
@@ -0,0 +1,107 @@
+//! Local transform component, relative to the parent entity or to the world if there is none
+//!
+//! Changes go through the `set_*` methods so the hierarchy system knows which world transforms to recompute.
+
+use specs;
+
+use nalgebra::{Vector3, Quaternion, UnitQuaternion, Matrix4, ToHomogeneous, Eye};
+use nalgebra::to_rotation_matrix;
+use num_traits::One;
+
+#[derive(Clone, Debug)]
+pub struct Component {
+    translation: Vector3<f32>,
+    /// Rotation quaternion, which is normalized whenever the matrix is computed
+    rotation: Quaternion<f32>,
+    scale: Vector3<f32>,
+    /// Set whenever the transform changes, until the hierarchy system has recomputed the world transform
+    dirty: bool,
+}
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Default for Component {
+    #[inline(always)]
+    fn default() -> Component { Component::new() }
+}
+
+impl Component {
+    /// Creates an identity transform that does nothing
+    #[inline(always)]
+    pub fn new() -> Component {
+        Component::from_parts(Vector3::new(0.0, 0.0, 0.0), Quaternion::one(), Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    #[inline(always)]
+    pub fn from_translation(x: f32, y: f32, z: f32) -> Component {
+        Component::from_parts(Vector3::new(x, y, z), Quaternion::one(), Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    /// New transforms start out dirty, since their world transform hasn't been computed yet
+    #[inline(always)]
+    pub fn from_parts(translation: Vector3<f32>, rotation: Quaternion<f32>, scale: Vector3<f32>) -> Component {
+        Component { translation: translation, rotation: rotation, scale: scale, dirty: true }
+    }
+
+    #[inline(always)]
+    pub fn translation(&self) -> &Vector3<f32> { &self.translation }
+
+    #[inline(always)]
+    pub fn rotation(&self) -> &Quaternion<f32> { &self.rotation }
+
+    #[inline(always)]
+    pub fn scale(&self) -> &Vector3<f32> { &self.scale }
+
+    #[inline]
+    pub fn set_translation(&mut self, translation: Vector3<f32>) {
+        self.translation = translation;
+        self.dirty = true;
+    }
+
+    #[inline]
+    pub fn set_rotation(&mut self, rotation: Quaternion<f32>) {
+        self.rotation = rotation;
+        self.dirty = true;
+    }
+
+    #[inline]
+    pub fn set_scale(&mut self, scale: Vector3<f32>) {
+        self.scale = scale;
+        self.dirty = true;
+    }
+
+    /// Checks if the transform has changed since the hierarchy system last saw it
+    #[inline(always)]
+    pub fn is_dirty(&self) -> bool { self.dirty }
+
+    /// Forces the world transform of this entity and everything below it to be recomputed next update
+    #[inline(always)]
+    pub fn mark_dirty(&mut self) { self.dirty = true; }
+
+    /// Called by the hierarchy system once the world transform is up to date
+    #[inline(always)]
+    pub fn clear_dirty(&mut self) { self.dirty = false; }
+
+    #[inline(always)]
+    pub fn unit_rotation(&self) -> UnitQuaternion<f32> {
+        UnitQuaternion::new(&self.rotation)
+    }
+
+    /// Matrix that scales, then rotates, then translates
+    pub fn matrix(&self) -> Matrix4<f32> {
+        let mut translation_matrix = Matrix4::new_identity(4);
+        let mut scale_matrix = Matrix4::new_identity(4);
+
+        translation_matrix.m14 = self.translation.x;
+        translation_matrix.m24 = self.translation.y;
+        translation_matrix.m34 = self.translation.z;
+
+        scale_matrix.m11 = self.scale.x;
+        scale_matrix.m22 = self.scale.y;
+        scale_matrix.m33 = self.scale.z;
+
+        translation_matrix * to_rotation_matrix(&self.unit_rotation()).to_homogeneous() * scale_matrix
+    }
+}
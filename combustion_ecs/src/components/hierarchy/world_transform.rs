@@ -0,0 +1,34 @@
+//! World transform component
+//!
+//! Written by the hierarchy system each update, combining an entity's transform with those of all its ancestors.
+
+use nalgebra::{Matrix4, Eye};
+
+use specs;
+
+#[derive(Clone, Debug)]
+pub struct Component {
+    /// Transformation from the entity's local space to world space
+    pub matrix: Matrix4<f32>,
+    /// Inverse (if it exists) of the transformation matrix
+    pub inverse: Option<Matrix4<f32>>,
+}
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Default for Component {
+    #[inline(always)]
+    fn default() -> Component { Component::new() }
+}
+
+impl Component {
+    /// Create a new identity transform
+    pub fn new() -> Component {
+        Component {
+            matrix: Matrix4::new_identity(4),
+            inverse: None,
+        }
+    }
+}
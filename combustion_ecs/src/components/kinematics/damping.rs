@@ -0,0 +1,30 @@
+//! Damping component, slowing the entity's velocities over time like drag
+
+use specs;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Component {
+    /// Rate linear velocity decays at, where velocity is scaled by `e^(-linear * dt)` each tick
+    pub linear: f32,
+    /// Rate angular velocity decays at, in the same way
+    pub angular: f32,
+}
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Component {
+    #[inline(always)]
+    pub fn new(linear: f32, angular: f32) -> Component {
+        Component { linear: linear, angular: angular }
+    }
+
+    /// Factor to scale linear velocity by over a tick of length `dt`
+    #[inline]
+    pub fn linear_factor(&self, dt: f32) -> f32 { (-self.linear * dt).exp() }
+
+    /// Factor to scale angular velocity by over a tick of length `dt`
+    #[inline]
+    pub fn angular_factor(&self, dt: f32) -> f32 { (-self.angular * dt).exp() }
+}
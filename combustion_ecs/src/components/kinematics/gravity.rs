@@ -0,0 +1,30 @@
+//! Gravity component, accelerating the entity's velocity in units per second squared
+
+use specs;
+
+use nalgebra::Vector3;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Component(pub Vector3<f32>);
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Default for Component {
+    #[inline(always)]
+    fn default() -> Component { Component::earth() }
+}
+
+impl Component {
+    #[inline(always)]
+    pub fn new(x: f32, y: f32, z: f32) -> Component {
+        Component(Vector3::new(x, y, z))
+    }
+
+    /// Standard gravity, pulling down the Y axis
+    #[inline(always)]
+    pub fn earth() -> Component {
+        Component::new(0.0, -9.80665, 0.0)
+    }
+}
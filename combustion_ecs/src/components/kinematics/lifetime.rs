@@ -0,0 +1,27 @@
+//! Lifetime component, deleting the entity once its time runs out
+
+use specs;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Component {
+    /// Seconds left before the entity is deleted
+    pub remaining: f32,
+}
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Component {
+    #[inline(always)]
+    pub fn new(seconds: f32) -> Component {
+        Component { remaining: seconds }
+    }
+
+    /// Counts down by `dt`, returning whether the entity has expired
+    #[inline]
+    pub fn tick(&mut self, dt: f32) -> bool {
+        self.remaining -= dt;
+        self.remaining <= 0.0
+    }
+}
@@ -0,0 +1,21 @@
+//! Kinematic motion components
+//!
+//! Entities with a `hierarchy::transform::Component` are moved by their `velocity::Component` and turned by their
+//! `angular_velocity::Component` every tick, relative to their parent. `gravity::Component` and `damping::Component`
+//! change those velocities over time, and `lifetime::Component` deletes the entity once it runs out.
+
+use specs;
+
+pub mod velocity;
+pub mod angular_velocity;
+pub mod gravity;
+pub mod damping;
+pub mod lifetime;
+
+pub fn register_all(world: &mut specs::World) {
+    ecs_register_mod!(world, velocity);
+    ecs_register_mod!(world, angular_velocity);
+    ecs_register_mod!(world, gravity);
+    ecs_register_mod!(world, damping);
+    ecs_register_mod!(world, lifetime);
+}
@@ -0,0 +1,19 @@
+//! Linear velocity component, in units per second
+
+use specs;
+
+use nalgebra::Vector3;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Component(pub Vector3<f32>);
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Component {
+    #[inline(always)]
+    pub fn new(x: f32, y: f32, z: f32) -> Component {
+        Component(Vector3::new(x, y, z))
+    }
+}
@@ -0,0 +1,170 @@
+//! Level of detail component, swapping an entity's mesh for simpler ones as it gets smaller on screen
+//!
+//! Level zero is the mesh renderer's own mesh, and each level in the component is a simpler mesh drawn with the
+//! renderer's materials. The LOD system picks the level of every visible entity from how much of the screen its world
+//! bounds cover, and render submission draws the chosen level's mesh.
+//!
+//! Meshes are of whatever type `M` the mesh renderer draws.
+
+use specs;
+
+use asset::handle::AssetHandle;
+
+/// A simpler mesh, and the screen coverage below which it's used
+pub struct Level<M> {
+    pub mesh: AssetHandle<M>,
+    /// Fraction of the screen's height covered by the entity's bounding sphere
+    pub coverage: f32,
+}
+
+impl<M> Clone for Level<M> {
+    fn clone(&self) -> Level<M> {
+        Level { mesh: self.mesh.clone(), coverage: self.coverage }
+    }
+}
+
+pub struct Component<M> {
+    /// Levels after the renderer's own mesh, from most to least detailed, with decreasing coverage
+    pub levels: Vec<Level<M>>,
+    /// Level being drawn, written by the LOD system
+    pub current: usize,
+}
+
+impl<M> specs::Component for Component<M> where M: Send + Sync + 'static {
+    type Storage = specs::VecStorage<Component<M>>;
+}
+
+//Handles are shared rather than copied, so cloning doesn't need the meshes to be `Clone`
+impl<M> Clone for Component<M> {
+    fn clone(&self) -> Component<M> {
+        Component { levels: self.levels.clone(), current: self.current }
+    }
+}
+
+impl<M> Component<M> {
+    #[inline(always)]
+    pub fn new() -> Component<M> {
+        Component { levels: Vec::new(), current: 0 }
+    }
+
+    /// Adds a simpler level, used once the entity covers less than `coverage` of the screen's height
+    #[inline]
+    pub fn with_level(mut self, mesh: AssetHandle<M>, coverage: f32) -> Component<M> {
+        self.levels.push(Level { mesh: mesh, coverage: coverage });
+        self
+    }
+
+    /// Number of levels, including the renderer's own mesh
+    #[inline]
+    pub fn len(&self) -> usize { self.levels.len() + 1 }
+
+    /// Mesh of the current level, which is `base` for level zero
+    pub fn mesh<'a>(&'a self, base: &'a AssetHandle<M>) -> &'a AssetHandle<M> {
+        match self.current {
+            0 => base,
+            level => self.levels.get(level - 1).map_or(base, |level| &level.mesh),
+        }
+    }
+
+    /// Moves to the level for `coverage`, returning whether it changed.
+    ///
+    /// Each boundary only switches once the coverage is past it by the `hysteresis` fraction, so an entity sitting
+    /// right on a boundary doesn't flicker between two levels.
+    pub fn select(&mut self, coverage: f32, hysteresis: f32) -> bool {
+        let previous = self.current;
+
+        let mut level = self.current.min(self.levels.len());
+
+        while level < self.levels.len() && coverage < self.levels[level].coverage * (1.0 - hysteresis) {
+            level += 1;
+        }
+
+        while level > 0 && coverage > self.levels[level - 1].coverage * (1.0 + hysteresis) {
+            level -= 1;
+        }
+
+        self.current = level;
+
+        level != previous
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct TestMesh;
+
+    fn lod() -> Component<TestMesh> {
+        Component::new()
+            .with_level(AssetHandle::pending(), 0.5)
+            .with_level(AssetHandle::pending(), 0.25)
+            .with_level(AssetHandle::pending(), 0.1)
+    }
+
+    #[test]
+    fn test_select() {
+        let mut lod = lod();
+
+        assert_eq!(lod.len(), 4);
+
+        assert!(!lod.select(1.0, 0.1));
+        assert_eq!(lod.current, 0);
+
+        //Far past several boundaries at once
+        assert!(lod.select(0.05, 0.1));
+        assert_eq!(lod.current, 3);
+
+        assert!(lod.select(0.3, 0.1));
+        assert_eq!(lod.current, 1);
+
+        //Without hysteresis, the boundaries are exact
+        assert!(!lod.select(0.49, 0.0));
+        assert_eq!(lod.current, 1);
+        assert!(lod.select(0.51, 0.0));
+        assert_eq!(lod.current, 0);
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let mut lod = lod();
+
+        //Wobbling around the first boundary by less than the hysteresis never switches
+        for &coverage in &[0.5, 0.48, 0.52, 0.46, 0.54, 0.5] {
+            assert!(!lod.select(coverage, 0.1));
+            assert_eq!(lod.current, 0);
+        }
+
+        //Only past it by the hysteresis, and then going back needs to go past it by as much the other way
+        assert!(lod.select(0.44, 0.1));
+        assert_eq!(lod.current, 1);
+
+        for &coverage in &[0.5, 0.54, 0.46, 0.5] {
+            assert!(!lod.select(coverage, 0.1));
+            assert_eq!(lod.current, 1);
+        }
+
+        assert!(lod.select(0.56, 0.1));
+        assert_eq!(lod.current, 0);
+    }
+
+    #[test]
+    fn test_removed_levels() {
+        let mut lod = lod();
+
+        lod.select(0.05, 0.1);
+        lod.levels.truncate(1);
+
+        //The current level no longer exists, so it's clamped before selecting
+        assert!(lod.select(0.05, 0.1));
+        assert_eq!(lod.current, 1);
+
+        let base = AssetHandle::pending();
+
+        assert!(lod.mesh(&base).same_slot(&lod.levels[0].mesh));
+
+        lod.current = 5;
+
+        assert!(lod.mesh(&base).same_slot(&base));
+    }
+}
@@ -0,0 +1,113 @@
+//! Material override component, replacing some parameters of an entity's materials without touching the shared ones
+//!
+//! Render submission merges the overrides over every material of the entity's mesh renderer. Entities with the same
+//! materials and overrides end up sharing a merged material, so they're still batched together. Parts drawn with the
+//! renderer's default material, because they have none of their own, are left alone.
+//!
+//! Which parameters there are, and what they're named, is up to the material. See `material_overrides::Overridable`.
+//! Textures overriding texture parameters are of whatever type `T` the material is textured with.
+
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+
+use specs;
+
+use asset::handle::AssetHandle;
+
+/// Value replacing one of a material's parameters
+pub enum Value<T> {
+    Color([f32; 3]),
+    Scalar(f32),
+    /// The material's own texture is drawn until this one has loaded
+    Texture(AssetHandle<T>),
+}
+
+//Written out, so neither needs anything of the texture type
+impl<T> Clone for Value<T> {
+    fn clone(&self) -> Value<T> {
+        match *self {
+            Value::Color(color) => Value::Color(color),
+            Value::Scalar(value) => Value::Scalar(value),
+            Value::Texture(ref handle) => Value::Texture(handle.clone()),
+        }
+    }
+}
+
+impl<T> Debug for Value<T> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            Value::Color(ref color) => write!(f, "Color({:?})", color),
+            Value::Scalar(value) => write!(f, "Scalar({:?})", value),
+            Value::Texture(ref handle) => write!(f, "Texture({:?})", handle),
+        }
+    }
+}
+
+pub struct Component<T> {
+    //Ordered, so merging always applies the same overrides in the same order
+    parameters: BTreeMap<String, Value<T>>,
+}
+
+impl<T> specs::Component for Component<T> where T: Send + Sync + 'static {
+    type Storage = specs::VecStorage<Component<T>>;
+}
+
+impl<T> Clone for Component<T> {
+    fn clone(&self) -> Component<T> {
+        Component { parameters: self.parameters.clone() }
+    }
+}
+
+impl<T> Debug for Component<T> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_map().entries(self.parameters.iter()).finish()
+    }
+}
+
+impl<T> Default for Component<T> {
+    #[inline(always)]
+    fn default() -> Component<T> { Component::new() }
+}
+
+impl<T> Component<T> {
+    #[inline(always)]
+    pub fn new() -> Component<T> {
+        Component { parameters: BTreeMap::new() }
+    }
+
+    #[inline]
+    pub fn with<S: Into<String>>(mut self, name: S, value: Value<T>) -> Component<T> {
+        self.set(name, value);
+        self
+    }
+
+    /// Overrides a parameter, returning the previous override of it
+    #[inline]
+    pub fn set<S: Into<String>>(&mut self, name: S, value: Value<T>) -> Option<Value<T>> {
+        self.parameters.insert(name.into(), value)
+    }
+
+    /// Stops overriding a parameter, returning its override
+    #[inline]
+    pub fn remove(&mut self, name: &str) -> Option<Value<T>> {
+        self.parameters.remove(name)
+    }
+
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&Value<T>> {
+        self.parameters.get(name)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.parameters.is_empty() }
+
+    #[inline]
+    pub fn len(&self) -> usize { self.parameters.len() }
+
+    /// Overrides by name, in order
+    #[inline]
+    pub fn iter(&self) -> btree_map::Iter<String, Value<T>> {
+        self.parameters.iter()
+    }
+}
@@ -0,0 +1,47 @@
+//! Mesh renderer component, drawing a mesh with one material per submesh
+//!
+//! The mesh and materials are asset handles, so entities can be created before their assets finish loading.
+//! Until every one of them has loaded, the entity isn't drawn at all.
+//!
+//! What a mesh and a material are is up to the renderer, as `M` and `T`.
+
+use specs;
+
+use asset::handle::AssetHandle;
+
+pub struct Component<M, T> {
+    pub mesh: AssetHandle<M>,
+    /// Materials indexed by the mesh's submeshes. Submeshes without one use the renderer's default material.
+    pub materials: Vec<AssetHandle<T>>,
+    pub cast_shadows: bool,
+    pub visible: bool,
+}
+
+impl<M, T> specs::Component for Component<M, T> where M: Send + Sync + 'static, T: Send + Sync + 'static {
+    type Storage = specs::VecStorage<Component<M, T>>;
+}
+
+//Handles are shared rather than copied, so cloning doesn't need the assets to be `Clone`
+impl<M, T> Clone for Component<M, T> {
+    fn clone(&self) -> Component<M, T> {
+        Component {
+            mesh: self.mesh.clone(),
+            materials: self.materials.clone(),
+            cast_shadows: self.cast_shadows,
+            visible: self.visible,
+        }
+    }
+}
+
+impl<M, T> Component<M, T> {
+    #[inline(always)]
+    pub fn new(mesh: AssetHandle<M>) -> Component<M, T> {
+        Component { mesh: mesh, materials: Vec::new(), cast_shadows: true, visible: true }
+    }
+
+    #[inline]
+    pub fn with_material(mut self, material: AssetHandle<T>) -> Component<M, T> {
+        self.materials.push(material);
+        self
+    }
+}
@@ -0,0 +1,48 @@
+//! Components the systems and scene storage in this crate are written against
+//!
+//! Games re-export these in their own components module, alongside the components of their own.
+
+use specs;
+
+pub mod name;
+pub mod camera;
+pub mod point_light;
+pub mod spot_light;
+pub mod directional_light;
+pub mod mesh_renderer;
+pub mod billboard;
+pub mod lod;
+pub mod material_override;
+pub mod bounds;
+pub mod world_bounds;
+pub mod renderable;
+pub mod always_visible;
+pub mod scene_origin;
+pub mod behavior;
+pub mod tag;
+pub mod group;
+
+pub mod hierarchy;
+pub mod animation;
+pub mod kinematics;
+
+/// Registers every component here except the mesh renderer, billboards, levels of detail and material overrides, whose
+/// mesh, material and texture types are up to the renderer
+pub fn register_all(world: &mut specs::World) {
+    ecs_register_mod!(world, name);
+    ecs_register_mod!(world, camera);
+    ecs_register_mod!(world, point_light);
+    ecs_register_mod!(world, spot_light);
+    ecs_register_mod!(world, directional_light);
+    ecs_register_mod!(world, bounds);
+    ecs_register_mod!(world, world_bounds);
+    ecs_register_mod!(world, renderable);
+    ecs_register_mod!(world, always_visible);
+    ecs_register_mod!(world, scene_origin);
+    ecs_register_mod!(world, behavior);
+    ecs_register_mod!(world, group);
+
+    hierarchy::register_all(world);
+    animation::register_all(world);
+    kinematics::register_all(world);
+}
@@ -0,0 +1,15 @@
+//! Name component, for finding entities and showing them in tools
+
+use specs;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Component(pub String);
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Component {
+    #[inline(always)]
+    pub fn new<S: Into<String>>(name: S) -> Component { Component(name.into()) }
+}
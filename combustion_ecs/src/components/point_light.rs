@@ -0,0 +1,31 @@
+//! Point light component, shining in every direction from the entity's world position
+
+use specs;
+use nalgebra::Vector3;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Component {
+    pub enabled: bool,
+    /// Linear RGB color
+    pub color: Vector3<f32>,
+    pub intensity: f32,
+    /// Distance at which the light has fallen to a quarter of its intensity, as used by `attenuation_radius` in `shaders/lib/attenuation.glsl`
+    pub radius: f32,
+}
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Component {
+    #[inline(always)]
+    pub fn new(color: Vector3<f32>, intensity: f32, radius: f32) -> Component {
+        Component { enabled: true, color: color, intensity: intensity, radius: radius }
+    }
+
+    /// A white light
+    #[inline(always)]
+    pub fn white(intensity: f32, radius: f32) -> Component {
+        Component::new(Vector3::new(1.0, 1.0, 1.0), intensity, radius)
+    }
+}
@@ -0,0 +1,36 @@
+//! Scene origin component, given to entities loaded from a scene file that's reloaded when it changes
+//!
+//! Alongside the entity's stable ID in the file, it keeps the data of each of its components twice: as the file had it,
+//! to tell which components an edit to the file changed, and as the entity had it right after loading, to tell which
+//! have been changed at runtime since. Entities without one are left alone by reloading.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use specs;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Component {
+    path: Arc<PathBuf>,
+    /// ID of the entity in the scene file
+    pub id: u32,
+    /// Serialized data of each component in the file, by component name
+    pub file: HashMap<String, Vec<u8>>,
+    /// Serialized data of each component loaded from the file, as it was right after it was loaded
+    pub loaded: HashMap<String, Vec<u8>>,
+}
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Component {
+    pub fn new(path: Arc<PathBuf>, id: u32) -> Component {
+        Component { path: path, id: id, file: HashMap::new(), loaded: HashMap::new() }
+    }
+
+    /// Scene file the entity was loaded from
+    #[inline]
+    pub fn path(&self) -> &Path { &self.path }
+}
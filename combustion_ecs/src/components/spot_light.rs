@@ -0,0 +1,37 @@
+//! Spotlight component, shining a cone down the entity's local -Z axis from its world position
+
+use specs;
+use nalgebra::Vector3;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Component {
+    pub enabled: bool,
+    /// Linear RGB color
+    pub color: Vector3<f32>,
+    pub intensity: f32,
+    /// Distance at which the light has fallen to a quarter of its intensity, as used by `attenuation_radius` in `shaders/lib/attenuation.glsl`
+    pub radius: f32,
+    /// Angle from the center of the cone in radians, inside which the light is at full intensity
+    pub inner_angle: f32,
+    /// Angle from the center of the cone in radians, outside which there is no light
+    pub outer_angle: f32,
+}
+
+impl specs::Component for Component {
+    type Storage = specs::VecStorage<Component>;
+}
+
+impl Component {
+    #[inline(always)]
+    pub fn new(color: Vector3<f32>, intensity: f32, radius: f32, inner_angle: f32, outer_angle: f32) -> Component {
+        Component {
+            enabled: true,
+            color: color,
+            intensity: intensity,
+            radius: radius,
+            //An inner angle past the outer angle would make the falloff run backwards
+            inner_angle: inner_angle.min(outer_angle),
+            outer_angle: outer_angle,
+        }
+    }
+}
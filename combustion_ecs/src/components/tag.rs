@@ -0,0 +1,89 @@
+//! Tag component, a zero sized marker for sorting entities into kinds like enemies or pickups
+//!
+//! Any type can be used as the kind, usually an empty struct declared next to the code that cares about it:
+//!
+//! ```ignore
+//! pub struct Enemy;
+//!
+//! world.register::<Tag<Enemy>>();
+//! world.create_now().with(Tag::<Enemy>::new()).build();
+//! ```
+//!
+//! Each kind is its own component type, so it has to be registered by whoever declares it.
+//! Tags can't be saved with a scene. Use a group for anything that has to be.
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::marker::PhantomData;
+
+use specs;
+use specs::Join;
+
+/// `fn() -> T` keeps the tag `Send` and `Sync` whatever `T` is
+pub struct Component<T: 'static>(PhantomData<fn() -> T>);
+
+impl<T: 'static> specs::Component for Component<T> {
+    type Storage = specs::VecStorage<Component<T>>;
+}
+
+impl<T: 'static> Clone for Component<T> {
+    #[inline(always)]
+    fn clone(&self) -> Component<T> { Component::new() }
+}
+
+impl<T: 'static> Copy for Component<T> {}
+
+impl<T: 'static> Default for Component<T> {
+    #[inline(always)]
+    fn default() -> Component<T> { Component::new() }
+}
+
+impl<T: 'static> Debug for Component<T> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "Tag")
+    }
+}
+
+impl<T: 'static> Component<T> {
+    #[inline(always)]
+    pub fn new() -> Component<T> { Component(PhantomData) }
+}
+
+/// Every entity with the tag `T`
+pub fn tagged<T: 'static>(world: &specs::World) -> Vec<specs::Entity> {
+    let tags = world.read::<Component<T>>();
+
+    (&tags, &world.entities()).iter().map(|(_, entity)| entity).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::mem;
+
+    struct Enemy;
+    struct Pickup;
+
+    #[test]
+    fn test_tags() {
+        assert_eq!(mem::size_of::<Component<Enemy>>(), 0);
+
+        let mut world = specs::World::new();
+
+        world.register::<Component<Enemy>>();
+        world.register::<Component<Pickup>>();
+
+        let goblin = world.create_now().with(Component::<Enemy>::new()).build();
+        let coin = world.create_now().with(Component::<Pickup>::new()).build();
+        let mimic = world.create_now().with(Component::<Enemy>::new()).with(Component::<Pickup>::new()).build();
+
+        assert_eq!(tagged::<Enemy>(&world), vec![goblin, mimic]);
+        assert_eq!(tagged::<Pickup>(&world), vec![coin, mimic]);
+
+        world.delete_now(goblin);
+        world.write::<Component<Pickup>>().remove(mimic);
+
+        assert_eq!(tagged::<Enemy>(&world), vec![mimic]);
+        assert_eq!(tagged::<Pickup>(&world), vec![coin]);
+    }
+}
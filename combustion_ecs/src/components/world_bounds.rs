@@ -0,0 +1,69 @@
+//! World bounds component
+//!
+//! Written by the bounds system whenever an entity's bounds or world transform change. This is what culling reads,
+//! so an entity without one is always drawn.
+//!
+//! Changes are tracked, so the spatial index system only has to update the entities that moved.
+
+use nalgebra::{Point3, Vector3, Matrix4, Norm};
+
+use specs;
+
+use ::storage::TrackedStorage;
+
+use bounds::Bounds;
+
+/// Sphere enclosing a bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sphere {
+    pub center: Point3<f32>,
+    pub radius: f32,
+}
+
+impl Sphere {
+    /// Sphere containing `bounds` after being transformed by `matrix`.
+    ///
+    /// Non-uniform scale stretches the box unevenly, so the radius is scaled by the largest axis to stay conservative.
+    pub fn enclosing(bounds: &Bounds, matrix: &Matrix4<f32>) -> Sphere {
+        let m = matrix;
+
+        let center = Point3::new((bounds.min.x + bounds.max.x) * 0.5,
+                                 (bounds.min.y + bounds.max.y) * 0.5,
+                                 (bounds.min.z + bounds.max.z) * 0.5);
+
+        let half_diagonal = (bounds.max - bounds.min).norm() * 0.5;
+
+        let scale = Vector3::new(m.m11, m.m21, m.m31).norm()
+            .max(Vector3::new(m.m12, m.m22, m.m32).norm())
+            .max(Vector3::new(m.m13, m.m23, m.m33).norm());
+
+        Sphere {
+            center: Point3::new(m.m11 * center.x + m.m12 * center.y + m.m13 * center.z + m.m14,
+                                m.m21 * center.x + m.m22 * center.y + m.m23 * center.z + m.m24,
+                                m.m31 * center.x + m.m32 * center.y + m.m33 * center.z + m.m34),
+            radius: half_diagonal * scale,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Component {
+    /// Axis-aligned box containing the transformed local bounds
+    pub aabb: Bounds,
+    /// Sphere containing the transformed local bounds
+    pub sphere: Sphere,
+}
+
+impl specs::Component for Component {
+    type Storage = TrackedStorage<Component>;
+}
+
+impl Component {
+    /// Transforms local bounds into world space
+    pub fn new(local: &Bounds, matrix: &Matrix4<f32>) -> Component {
+        Component {
+            aabb: local.transform(matrix),
+            sphere: Sphere::enclosing(local, matrix),
+        }
+    }
+}
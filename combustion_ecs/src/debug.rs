@@ -0,0 +1,395 @@
+//! Readable reports of entities and the whole world, for working out why something looks wrong
+//!
+//! Components are described by whatever registered a formatter for them with the `DebugRegistry` resource. Every
+//! component in this crate has one in `builtin`, and games can register their own components the same way:
+//!
+//! ```ignore
+//! world.write_resource::<DebugRegistry>().register("health", |health: &Health| format!("{} of {}", health.current, health.max));
+//! ```
+
+use std::fmt::Debug;
+use std::mem;
+
+use nalgebra::{Point3, Vector3};
+
+use specs;
+use specs::Join;
+
+use asset::handle::AssetHandle;
+
+use bounds::Bounds;
+
+type FormatFn = Box<Fn(&specs::World, specs::Entity) -> Option<String> + Send + Sync>;
+type CountFn = Box<Fn(&specs::World) -> usize + Send + Sync>;
+
+struct Registration {
+    name: String,
+    /// Size of the component itself, not counting anything it owns on the heap
+    size: usize,
+    format: FormatFn,
+    count: CountFn,
+}
+
+/// Debug formatters for every component type that can be described, by name
+pub struct DebugRegistry {
+    components: Vec<Registration>,
+}
+
+impl Default for DebugRegistry {
+    #[inline(always)]
+    fn default() -> DebugRegistry { DebugRegistry::new() }
+}
+
+impl DebugRegistry {
+    /// Creates a registry without any components
+    pub fn new() -> DebugRegistry {
+        DebugRegistry { components: Vec::new() }
+    }
+
+    /// Registers a formatter for a component type under `name`, replacing any registered under the same name before.
+    ///
+    /// The component type has to be registered with every world the registry is used with.
+    pub fn register<C, F>(&mut self, name: &str, format: F) -> &mut DebugRegistry
+        where C: specs::Component,
+              F: Fn(&C) -> String + Send + Sync + 'static {
+        let registration = Registration {
+            name: name.to_string(),
+            size: mem::size_of::<C>(),
+            format: Box::new(move |world, entity| world.read::<C>().get(entity).map(|component| format(component))),
+            count: Box::new(|world| (&*world.read::<C>()).iter().count()),
+        };
+
+        match self.components.iter().position(|existing| existing.name == name) {
+            Some(index) => self.components[index] = registration,
+            None => self.components.push(registration),
+        }
+
+        self
+    }
+
+    /// Registers a component type under `name`, formatted with its `Debug` implementation
+    pub fn register_debug<C>(&mut self, name: &str) -> &mut DebugRegistry where C: specs::Component + Debug {
+        self.register(name, |component: &C| format!("{:?}", component))
+    }
+
+    /// Checks if a component type was registered under `name`
+    pub fn contains(&self, name: &str) -> bool {
+        self.components.iter().any(|registration| registration.name == name)
+    }
+}
+
+/// Names of `entity` and its ancestors from the root down, separated by slashes. Unnamed entities are shown by ID.
+pub fn hierarchy_path(world: &specs::World, entity: specs::Entity) -> String {
+    use components::name::Component as Name;
+    use components::hierarchy::parent::Component as Parent;
+
+    let names = world.read::<Name>();
+    let parents = world.read::<Parent>();
+
+    let mut path = Vec::new();
+    let mut current = Some(entity);
+
+    while let Some(entity) = current {
+        //Parent links can form a loop until the hierarchy system breaks it
+        if path.iter().any(|&(visited, _)| visited == entity) {
+            path.push((entity, "...".to_string()));
+            break;
+        }
+
+        let name = names.get(entity).map_or_else(|| format!("#{}", entity.get_id()), |name| name.0.clone());
+
+        path.push((entity, name));
+
+        current = parents.get(entity).map(|parent| parent.entity());
+    }
+
+    let names: Vec<String> = path.into_iter().rev().map(|(_, name)| name).collect();
+
+    names.join("/")
+}
+
+/// Everything the registry can describe about `entity`, one component per line
+pub fn dump_entity(world: &specs::World, entity: specs::Entity) -> String {
+    let registry = world.read_resource::<DebugRegistry>();
+
+    if !world.entities().iter().any(|alive| alive == entity) {
+        return format!("Entity {:?} doesn't exist", entity);
+    }
+
+    let mut report = format!("Entity {:?} at {}", entity, hierarchy_path(world, entity));
+
+    for registration in &registry.components {
+        if let Some(text) = (registration.format)(world, entity) {
+            report.push_str(&format!("\n    {}: {}", registration.name, text.replace("\n", "\n        ")));
+        }
+    }
+
+    report
+}
+
+/// Number of entities, and how many of each registered component there are along with an estimate of their memory use
+pub fn dump_world_summary(world: &specs::World) -> String {
+    let registry = world.read_resource::<DebugRegistry>();
+
+    let entities = world.entities().iter().count();
+
+    let mut counts: Vec<(&str, usize, usize)> = registry.components.iter().map(|registration| {
+        (registration.name.as_str(), (registration.count)(world), registration.size)
+    }).filter(|&(_, count, _)| count > 0).collect();
+
+    //Largest first, since that's where the memory goes, and by name between equals
+    counts.sort_by(|a, b| a.0.cmp(b.0));
+    counts.sort_by(|a, b| (b.1 * b.2).cmp(&(a.1 * a.2)));
+
+    let total: usize = counts.iter().map(|&(_, count, size)| count * size).sum();
+
+    let mut report = format!("{} entities, {} bytes of components in use (estimated, not counting storage or heap data)", entities, total);
+
+    for &(name, count, size) in &counts {
+        report.push_str(&format!("\n    {}: {} x {} bytes = {} bytes", name, count, size, count * size));
+    }
+
+    report
+}
+
+/// Whether an asset has loaded, and where it's from
+pub fn asset_state<T>(handle: &AssetHandle<T>) -> String {
+    let state = if handle.is_loaded() { "loaded" } else { "pending" };
+
+    match handle.path() {
+        Some(path) => format!("{} ({})", state, path.display()),
+        None => state.to_string(),
+    }
+}
+
+fn point(p: &Point3<f32>) -> String {
+    format!("({:.3}, {:.3}, {:.3})", p.x, p.y, p.z)
+}
+
+fn vector(v: &Vector3<f32>) -> String {
+    format!("({:.3}, {:.3}, {:.3})", v.x, v.y, v.z)
+}
+
+fn bounds(bounds: &Bounds) -> String {
+    format!("{} to {}", point(&bounds.min), point(&bounds.max))
+}
+
+/// Formatters of the components in this crate
+pub mod builtin {
+    use components;
+
+    use super::{DebugRegistry, asset_state, bounds, point, vector};
+
+    use nalgebra::{Point3, Vector3, Norm};
+
+    /// Registers every component in this crate, where `M`, `T` and `X` are the mesh, material and texture types of
+    /// the mesh renderers, levels of detail, billboards and material overrides in the worlds the registry is used with
+    pub fn register_all<M, T, X>(registry: &mut DebugRegistry)
+        where M: Send + Sync + 'static,
+              T: Send + Sync + 'static,
+              X: Send + Sync + 'static {
+        use components::hierarchy::world_transform::Component as WorldTransform;
+        use components::world_bounds::Component as WorldBounds;
+        use components::mesh_renderer::Component as MeshRenderer;
+        use components::lod::Component as Lod;
+        use components::billboard::Component as Billboard;
+        use components::material_override::Component as MaterialOverride;
+
+        registry.register_debug::<components::name::Component>("name");
+        registry.register_debug::<components::scene_origin::Component>("scene_origin");
+        registry.register_debug::<components::group::Component>("group");
+
+        registry.register_debug::<components::hierarchy::parent::Component>("parent");
+        registry.register_debug::<components::hierarchy::orphan_policy::Component>("orphan_policy");
+
+        registry.register("transform", |transform: &components::hierarchy::transform::Component| {
+            format!("translation {}, rotation {:?}, scale {}{}", vector(transform.translation()), transform.rotation(),
+                    vector(transform.scale()), if transform.is_dirty() { ", dirty" } else { "" })
+        });
+
+        registry.register("world_transform", |world_transform: &WorldTransform| {
+            let m = &world_transform.matrix;
+
+            let scale = Vector3::new(Vector3::new(m.m11, m.m21, m.m31).norm(),
+                                     Vector3::new(m.m12, m.m22, m.m32).norm(),
+                                     Vector3::new(m.m13, m.m23, m.m33).norm());
+
+            format!("translation {}, scale {}{}", point(&Point3::new(m.m14, m.m24, m.m34)), vector(&scale),
+                    if world_transform.inverse.is_none() { ", not invertible" } else { "" })
+        });
+
+        registry.register("bounds", |local: &components::bounds::Component| {
+            let source = if local.is_manual() { "manual" } else { "from mesh" };
+
+            match local.local() {
+                Some(local) => format!("{}, {}", bounds(local), source),
+                None => format!("none yet, {}", source),
+            }
+        });
+
+        registry.register("world_bounds", |world_bounds: &WorldBounds| {
+            format!("{}, sphere at {} with radius {:.3}", bounds(&world_bounds.aabb), point(&world_bounds.sphere.center),
+                    world_bounds.sphere.radius)
+        });
+
+        registry.register("renderable", |_: &components::renderable::Component| "yes".to_string());
+        registry.register("always_visible", |_: &components::always_visible::Component| "yes".to_string());
+
+        registry.register("mesh_renderer", |renderer: &MeshRenderer<M, T>| {
+            let mut text = format!("mesh {}{}{}", asset_state(&renderer.mesh),
+                                   if renderer.visible { "" } else { ", hidden" },
+                                   if renderer.cast_shadows { "" } else { ", no shadows" });
+
+            for (index, material) in renderer.materials.iter().enumerate() {
+                text.push_str(&format!("\nmaterial {}: {}", index, asset_state(material)));
+            }
+
+            text
+        });
+
+        registry.register("lod", |lod: &Lod<M>| {
+            let mut text = format!("level {} of {}", lod.current, lod.len());
+
+            for (index, level) in lod.levels.iter().enumerate() {
+                text.push_str(&format!("\nlevel {} below {:.3} coverage: {}", index + 1, level.coverage, asset_state(&level.mesh)));
+            }
+
+            text
+        });
+
+        registry.register_debug::<MaterialOverride<X>>("material_override");
+
+        registry.register("billboard", |billboard: &Billboard<X>| {
+            format!("{:?}, {:?} facing, tint {:?}, texture {}{}", billboard.size, billboard.facing, billboard.tint,
+                    billboard.texture.as_ref().map_or_else(|| "none".to_string(), |texture| asset_state(texture)),
+                    if billboard.visible { "" } else { ", hidden" })
+        });
+
+        registry.register_debug::<components::camera::Component>("camera");
+        registry.register_debug::<components::point_light::Component>("point_light");
+        registry.register_debug::<components::spot_light::Component>("spot_light");
+        registry.register_debug::<components::directional_light::Component>("directional_light");
+
+        registry.register_debug::<components::behavior::Component>("behavior");
+
+        registry.register_debug::<components::animation::animator::Component>("animator");
+        registry.register_debug::<components::animation::skeletal_animator::Component>("skeletal_animator");
+
+        registry.register_debug::<components::kinematics::velocity::Component>("velocity");
+        registry.register_debug::<components::kinematics::angular_velocity::Component>("angular_velocity");
+        registry.register_debug::<components::kinematics::gravity::Component>("gravity");
+        registry.register_debug::<components::kinematics::damping::Component>("damping");
+        registry.register_debug::<components::kinematics::lifetime::Component>("lifetime");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use components;
+    use components::name::Component as Name;
+    use components::hierarchy::parent::Component as Parent;
+    use components::mesh_renderer::Component as MeshRenderer;
+    use components::lod::Component as Lod;
+    use components::billboard::Component as Billboard;
+    use components::material_override::Component as MaterialOverride;
+
+    struct TestMesh;
+    struct TestMaterial;
+    struct TestTexture;
+
+    type Renderer = MeshRenderer<TestMesh, TestMaterial>;
+
+    /// Components only this test knows about
+    #[derive(Debug)]
+    struct Health(u32);
+
+    impl specs::Component for Health {
+        type Storage = specs::VecStorage<Health>;
+    }
+
+    fn new_world() -> specs::World {
+        let mut world = specs::World::new();
+
+        components::register_all(&mut world);
+
+        world.register::<Renderer>();
+        world.register::<Lod<TestMesh>>();
+        world.register::<Billboard<TestTexture>>();
+        world.register::<MaterialOverride<TestTexture>>();
+        world.register::<Health>();
+
+        let mut registry = DebugRegistry::new();
+
+        builtin::register_all::<TestMesh, TestMaterial, TestTexture>(&mut registry);
+
+        world.add_resource(registry);
+
+        world
+    }
+
+    #[test]
+    fn test_dump_entity() {
+        let mut world = new_world();
+
+        let root = world.create_now().with(Name("level".to_string())).build();
+        let unnamed = world.create_now().with(Parent::new(root)).build();
+
+        let entity = world.create_now()
+            .with(Name("crate".to_string()))
+            .with(Parent::new(unnamed))
+            .with(Renderer::new(AssetHandle::at("meshes/crate.mesh")))
+            .with(Health(30))
+            .build();
+
+        assert_eq!(hierarchy_path(&world, entity), format!("level/#{}/crate", unnamed.get_id()));
+
+        let report = dump_entity(&world, entity);
+
+        assert!(report.contains("mesh_renderer: mesh pending (meshes/crate.mesh)"));
+        assert!(report.contains("name: Component(\"crate\")"));
+
+        //Unregistered components are left out until they're registered
+        assert!(!report.contains("Health"));
+
+        world.write_resource::<DebugRegistry>().register_debug::<Health>("health");
+
+        assert!(dump_entity(&world, entity).contains("health: Health(30)"));
+
+        world.delete_now(entity);
+
+        assert!(dump_entity(&world, entity).contains("doesn't exist"));
+    }
+
+    #[test]
+    fn test_parent_loops() {
+        let mut world = new_world();
+
+        let first = world.create_now().with(Name("first".to_string())).build();
+        let second = world.create_now().with(Name("second".to_string())).with(Parent::new(first)).build();
+
+        world.write::<Parent>().insert(first, Parent::new(second));
+
+        assert_eq!(hierarchy_path(&world, second), ".../first/second");
+    }
+
+    #[test]
+    fn test_world_summary() {
+        let mut world = new_world();
+
+        for i in 0..3 {
+            world.create_now().with(Name(format!("entity {}", i))).build();
+        }
+
+        world.create_now().with(Health(1)).build();
+
+        let summary = dump_world_summary(&world);
+
+        assert!(summary.starts_with("4 entities"));
+        assert!(summary.contains(&format!("name: 3 x {} bytes", mem::size_of::<Name>())));
+
+        //Unused components aren't listed
+        assert!(!summary.contains("lod"));
+    }
+}
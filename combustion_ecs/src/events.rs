@@ -0,0 +1,387 @@
+//! Typed events, for systems to tell each other about things that happened without depending on each other
+//!
+//! Each event type has its own `Events<T>` resource, added with `register`. Any system can send events into it, and any
+//! number of systems can read them through their own `EventReader`, which remembers what that system has already seen.
+//!
+//! Events are double buffered. The `UpdateEvents` system calls `update_events` at the start of every update, which drops the
+//! events sent two updates ago, so an event is readable for the rest of the update it was sent in and all of the next.
+//! Readers that fall further behind than that miss events, which they count in `EventReader::missed`.
+
+use std::mem;
+use std::iter::Chain;
+use std::marker::PhantomData;
+use std::slice;
+use std::vec;
+
+use specs;
+
+#[derive(Clone, Debug)]
+struct Event<T> {
+    /// Position of the event among every event of its type ever sent
+    id: usize,
+    event: T,
+}
+
+/// Events of one type sent during the current and previous update
+#[derive(Clone, Debug)]
+pub struct Events<T> {
+    previous: Vec<Event<T>>,
+    current: Vec<Event<T>>,
+    /// Events ever sent, and so the ID of the next one
+    count: usize,
+}
+
+impl<T> Default for Events<T> {
+    #[inline(always)]
+    fn default() -> Events<T> { Events::new() }
+}
+
+impl<T> Events<T> {
+    pub fn new() -> Events<T> {
+        Events { previous: Vec::new(), current: Vec::new(), count: 0 }
+    }
+
+    /// Sends an event, which readers can see until the end of the next update
+    pub fn send(&mut self, event: T) {
+        self.current.push(Event { id: self.count, event: event });
+        self.count += 1;
+    }
+
+    /// Number of events still buffered
+    #[inline]
+    pub fn len(&self) -> usize { self.previous.len() + self.current.len() }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.previous.is_empty() && self.current.is_empty() }
+
+    /// Starts a new update, dropping the events sent before the previous one. Returns how many were dropped.
+    pub fn update(&mut self) -> usize {
+        let dropped = self.previous.len();
+
+        self.previous.clear();
+
+        mem::swap(&mut self.previous, &mut self.current);
+
+        dropped
+    }
+
+    /// Takes every buffered event, oldest first, for when there's only ever one consumer.
+    ///
+    /// Readers that hadn't seen the drained events yet count them as missed.
+    pub fn drain(&mut self) -> Drain<T> {
+        Drain { inner: self.previous.drain(..).chain(self.current.drain(..)) }
+    }
+
+    /// ID of the oldest buffered event, or of the next event if none are
+    fn oldest(&self) -> usize {
+        self.previous.first().or_else(|| self.current.first()).map_or(self.count, |event| event.id)
+    }
+
+    /// Buffered events with an ID of at least `from`, oldest first
+    fn since(&self, from: usize) -> Iter<T> {
+        let skip = |events: &[Event<T>]| events.first().map_or(0, |first| from.saturating_sub(first.id).min(events.len()));
+
+        let previous = &self.previous[skip(&self.previous[..])..];
+        let current = &self.current[skip(&self.current[..])..];
+
+        Iter { inner: previous.iter().chain(current.iter()) }
+    }
+}
+
+/// Unread events, oldest first
+pub struct Iter<'a, T: 'a> {
+    inner: Chain<slice::Iter<'a, Event<T>>, slice::Iter<'a, Event<T>>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        self.inner.next().map(|event| &event.event)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Events taken out of an `Events` buffer, oldest first
+pub struct Drain<'a, T: 'a> {
+    inner: Chain<vec::Drain<'a, Event<T>>, vec::Drain<'a, Event<T>>>,
+}
+
+impl<'a, T> Iterator for Drain<'a, T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.inner.next().map(|event| event.event)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Keeps track of which events of one type a system has already read.
+///
+/// Each system reading events should own a reader of its own, usually as a field of the system.
+#[derive(Debug)]
+pub struct EventReader<T> {
+    /// ID of the next event to read, or `None` if the reader hasn't read anything yet
+    next: Option<usize>,
+    missed: usize,
+    _marker: PhantomData<fn(&T)>,
+}
+
+impl<T> Clone for EventReader<T> {
+    fn clone(&self) -> EventReader<T> {
+        EventReader { next: self.next, missed: self.missed, _marker: PhantomData }
+    }
+}
+
+impl<T> Default for EventReader<T> {
+    #[inline(always)]
+    fn default() -> EventReader<T> { EventReader::new() }
+}
+
+impl<T> EventReader<T> {
+    /// Creates a reader whose first read returns every event still buffered, including those sent before it existed
+    pub fn new() -> EventReader<T> {
+        EventReader { next: None, missed: 0, _marker: PhantomData }
+    }
+
+    /// Creates a reader that skips every event already sent
+    pub fn current(events: &Events<T>) -> EventReader<T> {
+        EventReader { next: Some(events.count), missed: 0, _marker: PhantomData }
+    }
+
+    /// Events sent since the last read, oldest first
+    pub fn read<'a>(&mut self, events: &'a Events<T>) -> Iter<'a, T> {
+        let oldest = events.oldest();
+
+        let from = match self.next {
+            Some(next) if next < oldest => {
+                self.missed += oldest - next;
+                oldest
+            }
+            Some(next) => next,
+            None => oldest,
+        };
+
+        self.next = Some(events.count);
+
+        events.since(from)
+    }
+
+    /// Total number of events dropped before this reader got to them
+    #[inline]
+    pub fn missed(&self) -> usize { self.missed }
+}
+
+/// Updates every registered event type at once, so `UpdateEvents` doesn't need to know about them
+#[derive(Default)]
+pub struct Registry {
+    updaters: Vec<fn(&specs::World)>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    /// Starts a new update for every registered event type
+    pub fn update_events(&self, world: &specs::World) {
+        for update in &self.updaters {
+            update(world);
+        }
+    }
+}
+
+/// Adds an `Events<T>` resource to the world, and has `UpdateEvents` update it every frame.
+///
+/// The `Registry` resource has to have been added already.
+pub fn register<T: Send + Sync + 'static>(world: &mut specs::World) {
+    fn update<T: Send + Sync + 'static>(world: &specs::World) {
+        world.write_resource::<Events<T>>().update();
+    }
+
+    world.add_resource(Events::<T>::new());
+    world.write_resource::<Registry>().updaters.push(update::<T>);
+}
+
+/// Starts a new update for every registered event type. Add it with the highest priority, so it runs before anything
+/// else and events sent during an update stay readable for the whole of the next one, whatever order the sending and
+/// reading systems run in.
+pub struct UpdateEvents;
+
+impl<C> specs::System<C> for UpdateEvents {
+    fn run(&mut self, arg: specs::RunArg, _: C) {
+        arg.fetch(|world| world.read_resource::<Registry>().update_events(world));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Damaged(u32);
+
+    fn read(reader: &mut EventReader<Damaged>, events: &Events<Damaged>) -> Vec<u32> {
+        reader.read(events).map(|event| event.0).collect()
+    }
+
+    #[test]
+    fn test_multiple_readers() {
+        let mut events = Events::new();
+
+        let mut a = EventReader::new();
+        let mut b = EventReader::new();
+
+        events.send(Damaged(1));
+        events.send(Damaged(2));
+
+        assert_eq!(read(&mut a, &events), vec![1, 2]);
+
+        events.send(Damaged(3));
+
+        // Each reader sees every event once, however its reads line up with the others'
+        assert_eq!(read(&mut a, &events), vec![3]);
+        assert_eq!(read(&mut b, &events), vec![1, 2, 3]);
+        assert_eq!(read(&mut a, &events), vec![]);
+
+        events.update();
+        events.send(Damaged(4));
+
+        assert_eq!(read(&mut a, &events), vec![4]);
+        assert_eq!(read(&mut b, &events), vec![4]);
+        assert_eq!(a.missed() + b.missed(), 0);
+    }
+
+    #[test]
+    fn test_late_registration() {
+        let mut events = Events::new();
+
+        events.send(Damaged(1));
+        events.update();
+        events.send(Damaged(2));
+
+        // A new reader still sees what's buffered from the previous update
+        let mut late = EventReader::new();
+
+        assert_eq!(read(&mut late, &events), vec![1, 2]);
+
+        // Unless it asks to start from now
+        let mut current = EventReader::current(&events);
+
+        assert_eq!(read(&mut current, &events), vec![]);
+
+        events.send(Damaged(3));
+
+        assert_eq!(read(&mut current, &events), vec![3]);
+
+        // Events dropped before a reader existed aren't counted as missed by it
+        events.update();
+        events.update();
+        events.send(Damaged(4));
+
+        let mut later = EventReader::new();
+
+        assert_eq!(read(&mut later, &events), vec![4]);
+        assert_eq!(later.missed(), 0);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut events = Events::new();
+        let mut reader = EventReader::new();
+
+        assert_eq!(read(&mut reader, &events), vec![]);
+
+        events.send(Damaged(1));
+
+        // Still readable an update later
+        assert_eq!(events.update(), 0);
+        events.send(Damaged(2));
+
+        assert_eq!(events.len(), 2);
+
+        // But dropped after two
+        assert_eq!(events.update(), 1);
+        events.send(Damaged(3));
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(read(&mut reader, &events), vec![2, 3]);
+        assert_eq!(reader.missed(), 1);
+
+        // Falling behind by a lot drops everything that was buffered
+        for i in 4..10 {
+            events.send(Damaged(i));
+            events.update();
+        }
+
+        assert_eq!(read(&mut reader, &events), vec![9]);
+        assert_eq!(reader.missed(), 6);
+
+        // Nothing is buffered after two updates without sending
+        events.update();
+        events.update();
+
+        assert!(events.is_empty());
+        assert_eq!(read(&mut reader, &events), vec![]);
+        assert_eq!(reader.missed(), 6);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut events = Events::new();
+        let mut reader = EventReader::new();
+
+        events.send(Damaged(1));
+        events.update();
+        events.send(Damaged(2));
+
+        assert_eq!(events.drain().collect::<Vec<_>>(), vec![Damaged(1), Damaged(2)]);
+        assert!(events.is_empty());
+
+        events.send(Damaged(3));
+
+        assert_eq!(read(&mut reader, &events), vec![3]);
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Resized(u32, u32);
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Loaded(&'static str);
+
+    #[test]
+    fn test_registered_events_are_updated() {
+        let mut world = specs::World::new();
+
+        world.add_resource(Registry::new());
+
+        register::<Resized>(&mut world);
+        register::<Loaded>(&mut world);
+
+        let mut planner: ::Planner = specs::Planner::new(world, 4);
+
+        planner.add_system(UpdateEvents, "UpdateEvents", 0);
+
+        planner.mut_world().write_resource::<Events<Resized>>().send(Resized(800, 600));
+        planner.mut_world().write_resource::<Events<Loaded>>().send(Loaded("crate.mesh"));
+
+        // Both types are kept through one update, then dropped after the second
+        for &expected in &[1, 0] {
+            planner.dispatch(0.0);
+            planner.wait();
+
+            assert_eq!(planner.mut_world().read_resource::<Events<Resized>>().len(), expected);
+            assert_eq!(planner.mut_world().read_resource::<Events<Loaded>>().len(), expected);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Channel for sending whole frames of render data from the world to a render loop on another thread
+//!
+//! A system sends a frame each update through the `FrameSender` resource, and the render loop keeps only the newest
+//! through a `FrameReceiver`. The frame being rendered and the one being built are always separate, so the world can
+//! carry on updating while a frame is drawn.
+
+use std::sync::{mpsc, Mutex};
+
+/// Data sent once per update, holding only shared handles and plain data so it can cross threads
+pub trait Frame: Send + 'static {
+    /// An empty frame, numbered by the order frames are sent in
+    fn new(sequence: u64) -> Self;
+}
+
+/// Render loop end of the frame channel, holding on to the newest frame until another arrives
+pub struct FrameReceiver<F: Frame> {
+    rx: mpsc::Receiver<F>,
+    current: Option<F>,
+    stale: u64,
+}
+
+impl<F: Frame> FrameReceiver<F> {
+    /// Takes every frame sent since the last call, keeping only the newest. Returns whether there was a new one.
+    ///
+    /// If none arrived, the current frame is kept, so the last update is drawn again.
+    pub fn update(&mut self) -> bool {
+        let mut received = false;
+
+        while let Ok(frame) = self.rx.try_recv() {
+            //The frame taken earlier in this call was never rendered
+            if received {
+                self.stale += 1;
+            }
+
+            self.current = Some(frame);
+            received = true;
+        }
+
+        received
+    }
+
+    /// Newest frame received, or `None` before the first one arrives
+    #[inline]
+    pub fn current(&self) -> Option<&F> { self.current.as_ref() }
+
+    /// Number of frames replaced by newer ones before they could be rendered
+    #[inline]
+    pub fn stale(&self) -> u64 { self.stale }
+}
+
+/// Sending end of the frame channel, kept in the world as a resource
+pub struct FrameSender<F: Frame> {
+    //Senders can't be shared between threads, but the resource has to be
+    tx: Option<Mutex<mpsc::Sender<F>>>,
+    sequence: u64,
+}
+
+impl<F: Frame> Default for FrameSender<F> {
+    #[inline(always)]
+    fn default() -> FrameSender<F> { FrameSender::new() }
+}
+
+impl<F: Frame> FrameSender<F> {
+    /// Creates a sender that drops every frame until `connect` is called
+    pub fn new() -> FrameSender<F> {
+        FrameSender { tx: None, sequence: 0 }
+    }
+
+    /// Opens a new frame channel, returning its receiving end. Any previous receiver stops getting frames.
+    pub fn connect(&mut self) -> FrameReceiver<F> {
+        let (tx, rx) = mpsc::channel();
+
+        self.tx = Some(Mutex::new(tx));
+
+        FrameReceiver { rx: rx, current: None, stale: 0 }
+    }
+
+    /// Starts the next frame, numbered in the order frames are sent
+    pub fn next_frame(&mut self) -> F {
+        let frame = F::new(self.sequence);
+
+        self.sequence += 1;
+
+        frame
+    }
+
+    /// Sends a frame to the render loop. Returns `false` if there's no receiver, in which case the frame is dropped.
+    pub fn send(&mut self, frame: F) -> bool {
+        let sent = match self.tx {
+            Some(ref tx) => tx.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).send(frame).is_ok(),
+            None => false,
+        };
+
+        //Nothing can receive on a disconnected channel again, so stop trying
+        if !sent {
+            self.tx = None;
+        }
+
+        sent
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestFrame {
+        sequence: u64,
+        position: (f32, f32, f32),
+    }
+
+    impl Frame for TestFrame {
+        fn new(sequence: u64) -> TestFrame {
+            TestFrame { sequence: sequence, position: (0.0, 0.0, 0.0) }
+        }
+    }
+
+    #[test]
+    fn test_unconnected() {
+        let mut sender = FrameSender::<TestFrame>::new();
+
+        let frame = sender.next_frame();
+
+        assert!(!sender.send(frame));
+    }
+
+    #[test]
+    fn test_newest_frame_wins() {
+        let mut sender = FrameSender::<TestFrame>::new();
+        let mut receiver = sender.connect();
+
+        assert!(!receiver.update());
+        assert!(receiver.current().is_none());
+
+        for _ in 0..3 {
+            let frame = sender.next_frame();
+
+            assert!(sender.send(frame));
+        }
+
+        assert!(receiver.update());
+
+        // The two older frames are dropped without being rendered
+        assert_eq!(receiver.current().map(|frame| frame.sequence), Some(2));
+        assert_eq!(receiver.stale(), 2);
+    }
+
+    #[test]
+    fn test_keeps_current_frame() {
+        let mut sender = FrameSender::<TestFrame>::new();
+        let mut receiver = sender.connect();
+
+        let mut frame = sender.next_frame();
+
+        frame.position = (1.0, 2.0, 3.0);
+
+        assert!(sender.send(frame));
+        assert!(receiver.update());
+
+        // Nothing new arrived, so the same frame is rendered again, and isn't counted as stale
+        assert!(!receiver.update());
+        assert_eq!(receiver.current().map(|frame| frame.sequence), Some(0));
+        assert_eq!(receiver.current().map(|frame| frame.position), Some((1.0, 2.0, 3.0)));
+        assert_eq!(receiver.stale(), 0);
+
+        let frame = sender.next_frame();
+
+        assert!(sender.send(frame));
+        assert!(receiver.update());
+        assert_eq!(receiver.current().map(|frame| frame.sequence), Some(1));
+        assert_eq!(receiver.stale(), 0);
+    }
+
+    #[test]
+    fn test_disconnected_receiver() {
+        let mut sender = FrameSender::<TestFrame>::new();
+        let receiver = sender.connect();
+
+        drop(receiver);
+
+        let frame = sender.next_frame();
+
+        assert!(!sender.send(frame));
+        assert!(sender.tx.is_none());
+    }
+
+    #[test]
+    fn test_reconnect() {
+        let mut sender = FrameSender::<TestFrame>::new();
+        let mut first = sender.connect();
+        let mut second = sender.connect();
+
+        let frame = sender.next_frame();
+
+        assert!(sender.send(frame));
+
+        // Only the newest receiver gets frames
+        assert!(!first.update());
+        assert!(second.update());
+        assert_eq!(second.current().map(|frame| frame.sequence), Some(0));
+    }
+}
@@ -0,0 +1,180 @@
+//! View frustum extraction and intersection tests for culling
+
+use nalgebra::{Point3, Matrix4};
+
+/// Plane in the form `ax + by + cz + d = 0`, with the normal `(a, b, c)` pointing into the frustum
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+}
+
+impl Plane {
+    /// Scales the plane so its normal has unit length, making `distance` return true distances
+    pub fn normalize(self) -> Plane {
+        let length = (self.a * self.a + self.b * self.b + self.c * self.c).sqrt();
+
+        if length > 0.0 {
+            Plane { a: self.a / length, b: self.b / length, c: self.c / length, d: self.d / length }
+        } else {
+            self
+        }
+    }
+
+    /// Signed distance from the plane, positive on the inside
+    #[inline]
+    pub fn distance(&self, point: &Point3<f32>) -> f32 {
+        self.a * point.x + self.b * point.y + self.c * point.z + self.d
+    }
+}
+
+/// The six planes bounding everything visible to a camera
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near and far planes, in that order
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extracts the frustum planes from a combined view-projection matrix, giving world space planes.
+    ///
+    /// A projection matrix alone gives view space planes, and a full model-view-projection matrix gives object space planes.
+    pub fn from_matrix(m: &Matrix4<f32>) -> Frustum {
+        let plane = |sign: f32, a: f32, b: f32, c: f32, d: f32| {
+            Plane { a: m.m41 + sign * a, b: m.m42 + sign * b, c: m.m43 + sign * c, d: m.m44 + sign * d }.normalize()
+        };
+
+        Frustum {
+            planes: [
+                plane(1.0, m.m11, m.m12, m.m13, m.m14),
+                plane(-1.0, m.m11, m.m12, m.m13, m.m14),
+                plane(1.0, m.m21, m.m22, m.m23, m.m24),
+                plane(-1.0, m.m21, m.m22, m.m23, m.m24),
+                plane(1.0, m.m31, m.m32, m.m33, m.m34),
+                plane(-1.0, m.m31, m.m32, m.m33, m.m34),
+            ]
+        }
+    }
+
+    /// Conservative test of an axis-aligned bounding box against the frustum.
+    ///
+    /// Boxes near the corners of the frustum may pass despite being outside of it, but visible boxes never fail.
+    pub fn intersects_aabb(&self, min: &Point3<f32>, max: &Point3<f32>) -> bool {
+        for plane in &self.planes {
+            // The corner furthest along the plane normal
+            let positive = Point3::new(if plane.a >= 0.0 { max.x } else { min.x },
+                                       if plane.b >= 0.0 { max.y } else { min.y },
+                                       if plane.c >= 0.0 { max.z } else { min.z });
+
+            if plane.distance(&positive) < 0.0 {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Conservative test of a bounding sphere against the frustum, like `intersects_aabb`
+    pub fn intersects_sphere(&self, center: &Point3<f32>, radius: f32) -> bool {
+        self.planes.iter().all(|plane| plane.distance(center) >= -radius)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// OpenGL perspective projection at the origin looking down -Z, with a 90 degree field of view,
+    /// a square aspect ratio and near and far planes at 1 and 100
+    fn perspective() -> Matrix4<f32> {
+        let (near, far) = (1.0, 100.0);
+
+        Matrix4::new(1.0, 0.0, 0.0, 0.0,
+                     0.0, 1.0, 0.0, 0.0,
+                     0.0, 0.0, (far + near) / (near - far), 2.0 * far * near / (near - far),
+                     0.0, 0.0, -1.0, 0.0)
+    }
+
+    fn assert_plane(plane: &Plane, expected: (f32, f32, f32, f32)) {
+        assert!((plane.a - expected.0).abs() < 1e-4 && (plane.b - expected.1).abs() < 1e-4 &&
+                    (plane.c - expected.2).abs() < 1e-4 && (plane.d - expected.3).abs() < 1e-4,
+                "{:?} isn't {:?}", plane, expected);
+    }
+
+    #[test]
+    fn test_planes_from_identity() {
+        //Without any projection the frustum is the unit cube of normalized device coordinates
+        let frustum = Frustum::from_matrix(&Matrix4::new(1.0, 0.0, 0.0, 0.0,
+                                                         0.0, 1.0, 0.0, 0.0,
+                                                         0.0, 0.0, 1.0, 0.0,
+                                                         0.0, 0.0, 0.0, 1.0));
+
+        assert_plane(&frustum.planes[0], (1.0, 0.0, 0.0, 1.0));
+        assert_plane(&frustum.planes[1], (-1.0, 0.0, 0.0, 1.0));
+        assert_plane(&frustum.planes[2], (0.0, 1.0, 0.0, 1.0));
+        assert_plane(&frustum.planes[3], (0.0, -1.0, 0.0, 1.0));
+        assert_plane(&frustum.planes[4], (0.0, 0.0, 1.0, 1.0));
+        assert_plane(&frustum.planes[5], (0.0, 0.0, -1.0, 1.0));
+    }
+
+    #[test]
+    fn test_planes_from_perspective() {
+        let frustum = Frustum::from_matrix(&perspective());
+
+        let diagonal = 0.5f32.sqrt();
+
+        assert_plane(&frustum.planes[0], (diagonal, 0.0, -diagonal, 0.0));
+        assert_plane(&frustum.planes[1], (-diagonal, 0.0, -diagonal, 0.0));
+        assert_plane(&frustum.planes[2], (0.0, diagonal, -diagonal, 0.0));
+        assert_plane(&frustum.planes[3], (0.0, -diagonal, -diagonal, 0.0));
+
+        //Normalized planes give true distances
+        assert!((frustum.planes[4].distance(&Point3::new(0.0, 0.0, -1.0))).abs() < 1e-4);
+        assert!((frustum.planes[4].distance(&Point3::new(0.0, 0.0, -3.0)) - 2.0).abs() < 1e-4);
+        assert!((frustum.planes[5].distance(&Point3::new(0.0, 0.0, -100.0))).abs() < 1e-3);
+        assert!((frustum.planes[5].distance(&Point3::new(0.0, 0.0, -90.0)) - 10.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_intersects_aabb() {
+        let frustum = Frustum::from_matrix(&perspective());
+
+        let aabb = |min: (f32, f32, f32), max: (f32, f32, f32)| {
+            frustum.intersects_aabb(&Point3::new(min.0, min.1, min.2), &Point3::new(max.0, max.1, max.2))
+        };
+
+        //Inside
+        assert!(aabb((-1.0, -1.0, -11.0), (1.0, 1.0, -9.0)));
+
+        //Behind the camera, off to the side, and past the far plane
+        assert!(!aabb((-1.0, -1.0, 4.0), (1.0, 1.0, 6.0)));
+        assert!(!aabb((29.0, -1.0, -11.0), (31.0, 1.0, -9.0)));
+        assert!(!aabb((-1.0, -1.0, -120.0), (1.0, 1.0, -110.0)));
+
+        //Straddling the left, near and far planes
+        assert!(aabb((-12.0, -1.0, -11.0), (-8.0, 1.0, -9.0)));
+        assert!(aabb((-0.5, -0.5, -1.5), (0.5, 0.5, 0.5)));
+        assert!(aabb((-1.0, -1.0, -105.0), (1.0, 1.0, -95.0)));
+
+        //Containing the whole frustum
+        assert!(aabb((-500.0, -500.0, -500.0), (500.0, 500.0, 500.0)));
+    }
+
+    #[test]
+    fn test_intersects_sphere() {
+        let frustum = Frustum::from_matrix(&perspective());
+
+        //Inside, and behind the camera
+        assert!(frustum.intersects_sphere(&Point3::new(0.0, 0.0, -50.0), 1.0));
+        assert!(!frustum.intersects_sphere(&Point3::new(0.0, 0.0, 10.0), 1.0));
+
+        //Reaching past the near plane from behind the camera
+        assert!(frustum.intersects_sphere(&Point3::new(0.0, 0.0, 10.0), 12.0));
+
+        //The left plane at z = -10 is at x = -10, so the first straddles it and the second misses it
+        assert!(frustum.intersects_sphere(&Point3::new(-11.0, 0.0, -10.0), 1.0));
+        assert!(!frustum.intersects_sphere(&Point3::new(-13.0, 0.0, -10.0), 1.0));
+    }
+}
@@ -0,0 +1,260 @@
+//! The groups resource interns group names and indexes the members of every group
+//!
+//! Entities join and leave groups through `add` and `remove`, which update both the entity's group component and the
+//! index, so finding every member of a group never has to search the world. Deleted entities, and ones whose group
+//! component was removed, are pruned from the index by the groups system.
+
+use std::collections::HashMap;
+
+use specs;
+
+use ::components::group::Component as Group;
+
+/// Interned group name, only meaningful to the groups resource that created it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GroupId(u32);
+
+#[derive(Clone, Debug, Default)]
+pub struct Resource {
+    /// Name of each group, indexed by id
+    names: Vec<String>,
+    ids: HashMap<String, GroupId>,
+    /// Members of each group, indexed by id, in the order they joined
+    members: Vec<Vec<specs::Entity>>,
+}
+
+impl Resource {
+    pub fn new() -> Resource {
+        Resource::default()
+    }
+
+    /// Id of the group with the given name, creating the group if it doesn't exist yet
+    pub fn intern(&mut self, name: &str) -> GroupId {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+
+        let id = GroupId(self.names.len() as u32);
+
+        self.names.push(name.to_string());
+        self.members.push(Vec::new());
+        self.ids.insert(name.to_string(), id);
+
+        id
+    }
+
+    /// Id of the group with the given name, if anything has ever joined it
+    #[inline]
+    pub fn id(&self, name: &str) -> Option<GroupId> {
+        self.ids.get(name).cloned()
+    }
+
+    #[inline]
+    pub fn name(&self, id: GroupId) -> &str {
+        &self.names[id.0 as usize]
+    }
+
+    /// Members of a group, in the order they joined
+    #[inline]
+    pub fn members(&self, id: GroupId) -> &[specs::Entity] {
+        &self.members[id.0 as usize]
+    }
+
+    /// Members of the group with the given name, in the order they joined
+    pub fn members_of(&self, name: &str) -> &[specs::Entity] {
+        match self.id(name) {
+            Some(id) => self.members(id),
+            None => &[],
+        }
+    }
+
+    /// Number of groups that have been interned
+    #[inline]
+    pub fn len(&self) -> usize { self.names.len() }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.names.is_empty() }
+
+    /// Adds `entity`, whose group component is `group`, to the named group.
+    ///
+    /// Returns false if it was already a member.
+    pub fn add(&mut self, entity: specs::Entity, group: &mut Group, name: &str) -> bool {
+        let id = self.intern(name);
+
+        if group.insert(id) {
+            self.members[id.0 as usize].push(entity);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Removes `entity`, whose group component is `group`, from the named group.
+    ///
+    /// Returns false if it wasn't a member.
+    pub fn remove(&mut self, entity: specs::Entity, group: &mut Group, name: &str) -> bool {
+        match self.id(name) {
+            Some(id) if group.remove(id) => {
+                self.members[id.0 as usize].retain(|member| *member != entity);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes `entity`, whose group component is `group`, from every group it belongs to
+    pub fn remove_all(&mut self, entity: specs::Entity, group: &mut Group) {
+        for id in group.ids().to_vec() {
+            group.remove(id);
+
+            self.members[id.0 as usize].retain(|member| *member != entity);
+        }
+    }
+
+    /// Drops every member `belongs` returns false for, such as deleted entities. Returns how many were dropped.
+    pub fn prune<F>(&mut self, mut belongs: F) -> usize where F: FnMut(specs::Entity, GroupId) -> bool {
+        let mut pruned = 0;
+
+        for (id, members) in self.members.iter_mut().enumerate() {
+            let before = members.len();
+
+            members.retain(|member| belongs(*member, GroupId(id as u32)));
+
+            pruned += before - members.len();
+        }
+
+        pruned
+    }
+}
+
+/// Group queries and changes straight on the world, for code outside of systems
+pub trait GroupsExt {
+    /// Members of the named group, in the order they joined
+    fn members_of(&self, name: &str) -> Vec<specs::Entity>;
+
+    /// Names of the groups an entity belongs to, in the order it joined them
+    fn groups_of(&self, entity: specs::Entity) -> Vec<String>;
+
+    /// Adds an entity to the named group, giving it a group component if it needs one.
+    ///
+    /// Returns false if it was already a member.
+    fn add_to_group(&mut self, entity: specs::Entity, name: &str) -> bool;
+
+    /// Removes an entity from the named group. Returns false if it wasn't a member.
+    fn remove_from_group(&mut self, entity: specs::Entity, name: &str) -> bool;
+}
+
+impl GroupsExt for specs::World {
+    fn members_of(&self, name: &str) -> Vec<specs::Entity> {
+        self.read_resource::<Resource>().members_of(name).to_vec()
+    }
+
+    fn groups_of(&self, entity: specs::Entity) -> Vec<String> {
+        let index = self.read_resource::<Resource>();
+
+        match self.read::<Group>().get(entity) {
+            Some(group) => group.ids().iter().map(|id| index.name(*id).to_string()).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn add_to_group(&mut self, entity: specs::Entity, name: &str) -> bool {
+        let mut index = self.write_resource::<Resource>();
+        let mut groups = self.write::<Group>();
+
+        let added = match groups.get_mut(entity) {
+            Some(group) => Some(index.add(entity, group, name)),
+            None => None,
+        };
+
+        added.unwrap_or_else(|| {
+            let mut group = Group::new();
+
+            index.add(entity, &mut group, name);
+            groups.insert(entity, group);
+
+            true
+        })
+    }
+
+    fn remove_from_group(&mut self, entity: specs::Entity, name: &str) -> bool {
+        let mut index = self.write_resource::<Resource>();
+        let mut groups = self.write::<Group>();
+
+        match groups.get_mut(entity) {
+            Some(group) => index.remove(entity, group, name),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn new_world() -> specs::World {
+        let mut world = specs::World::new();
+
+        world.register::<Group>();
+        world.add_resource(Resource::new());
+
+        world
+    }
+
+    #[test]
+    fn test_interning() {
+        let mut index = Resource::new();
+
+        let enemies = index.intern("enemies");
+        let destructible = index.intern("destructible");
+
+        assert!(enemies != destructible);
+        assert_eq!(index.intern("enemies"), enemies);
+        assert_eq!(index.id("destructible"), Some(destructible));
+        assert_eq!(index.id("allies"), None);
+        assert_eq!(index.name(enemies), "enemies");
+        assert_eq!(index.len(), 2);
+
+        assert!(index.members_of("allies").is_empty());
+    }
+
+    #[test]
+    fn test_add_and_remove() {
+        let mut world = new_world();
+
+        let goblin = world.create_now().build();
+        let barrel = world.create_now().build();
+        let troll = world.create_now().build();
+
+        assert!(world.add_to_group(goblin, "enemies"));
+        assert!(world.add_to_group(barrel, "destructible"));
+        assert!(world.add_to_group(troll, "enemies"));
+        assert!(world.add_to_group(goblin, "destructible"));
+
+        // Adding twice changes nothing
+        assert!(!world.add_to_group(goblin, "enemies"));
+
+        assert_eq!(world.members_of("enemies"), vec![goblin, troll]);
+        assert_eq!(world.members_of("destructible"), vec![barrel, goblin]);
+        assert_eq!(world.groups_of(goblin), vec!["enemies".to_string(), "destructible".to_string()]);
+
+        assert!(world.remove_from_group(goblin, "enemies"));
+        assert!(!world.remove_from_group(goblin, "enemies"));
+        assert!(!world.remove_from_group(barrel, "allies"));
+
+        assert_eq!(world.members_of("enemies"), vec![troll]);
+        assert_eq!(world.groups_of(goblin), vec!["destructible".to_string()]);
+
+        {
+            let mut index = world.write_resource::<Resource>();
+            let mut groups = world.write::<Group>();
+
+            index.remove_all(goblin, groups.get_mut(goblin).unwrap());
+
+            assert!(groups.get(goblin).unwrap().is_empty());
+        }
+
+        assert_eq!(world.members_of("destructible"), vec![barrel]);
+        assert!(world.groups_of(goblin).is_empty());
+    }
+}
@@ -0,0 +1,249 @@
+//! The Hierarchy resource tracks what the hierarchy system saw last update, so only changed subtrees are recomputed
+//!
+//! `HierarchyExt` deletes entities along with everything below them.
+
+use std::collections::{HashMap, HashSet};
+
+use specs;
+use specs::Join;
+
+use components::hierarchy::parent::Component as Parent;
+
+#[derive(Clone, Debug, Default)]
+pub struct Resource {
+    /// Parent each entity was positioned relative to last update, for entities that had one
+    parents: HashMap<specs::Entity, specs::Entity>,
+    /// Set to recompute every world transform next update, whether anything changed or not
+    force: bool,
+    /// Entities whose world transform was recomputed last update
+    changed: Vec<specs::Entity>,
+}
+
+impl Resource {
+    pub fn new() -> Resource {
+        Resource::default()
+    }
+
+    /// Recomputes every world transform next update, for when something has changed them without going through
+    /// the transform component, like writing the world transforms directly.
+    #[inline]
+    pub fn force_update_all(&mut self) {
+        self.force = true;
+    }
+
+    /// Number of world transforms recomputed last update
+    #[inline]
+    pub fn updated(&self) -> usize { self.changed.len() }
+
+    /// Entities whose world transform was recomputed last update, for systems that derive data from it
+    #[inline]
+    pub fn changed(&self) -> &[specs::Entity] { &self.changed }
+
+    /// Checks and clears the forced update flag at the start of an update
+    #[inline]
+    pub fn take_force(&mut self) -> bool {
+        ::std::mem::replace(&mut self.force, false)
+    }
+
+    /// Records the parent of an entity this update, returning whether it differs from last update.
+    pub fn set_parent(&mut self, entity: specs::Entity, parent: Option<specs::Entity>) -> bool {
+        match parent {
+            Some(parent) => self.parents.insert(entity, parent) != Some(parent),
+            None => self.parents.remove(&entity).is_some(),
+        }
+    }
+
+    /// Forgets entities that no longer have a transform, and stores which world transforms were recomputed
+    pub fn finish<F>(&mut self, changed: Vec<specs::Entity>, has_transform: F) where F: Fn(specs::Entity) -> bool {
+        self.parents.retain(|entity, _| has_transform(*entity));
+        self.changed = changed;
+    }
+}
+
+/// Children of every entity with any, from each child's parent link
+pub fn child_index<I>(links: I) -> HashMap<specs::Entity, Vec<specs::Entity>> where I: IntoIterator<Item = (specs::Entity, specs::Entity)> {
+    let mut children: HashMap<specs::Entity, Vec<specs::Entity>> = HashMap::new();
+
+    for (child, parent) in links {
+        children.entry(parent).or_insert_with(Vec::new).push(child);
+    }
+
+    children
+}
+
+/// An entity followed by everything below it, each before its own children.
+///
+/// Entities are only listed once, even if the parent links loop back around.
+pub fn subtree(children: &HashMap<specs::Entity, Vec<specs::Entity>>, root: specs::Entity) -> Vec<specs::Entity> {
+    let mut listed = HashSet::new();
+    let mut stack = vec![root];
+    let mut subtree = Vec::new();
+
+    while let Some(entity) = stack.pop() {
+        if listed.insert(entity) {
+            subtree.push(entity);
+
+            if let Some(children) = children.get(&entity) {
+                stack.extend(children.iter().rev());
+            }
+        }
+    }
+
+    subtree
+}
+
+/// Hierarchy changes straight on the world, for code outside of systems
+pub trait HierarchyExt {
+    /// Deletes an entity and everything below it, whatever their orphan policies.
+    ///
+    /// Returns how many entities were deleted, which is zero if the entity was already deleted.
+    fn despawn_recursive(&mut self, entity: specs::Entity) -> usize;
+}
+
+impl HierarchyExt for specs::World {
+    fn despawn_recursive(&mut self, entity: specs::Entity) -> usize {
+        let doomed = {
+            let parents = self.read::<Parent>();
+            let entities = self.entities();
+
+            if !Join::iter(&entities).any(|alive| alive == entity) {
+                return 0;
+            }
+
+            //Built from the parent links as they are now, so anything reparented since the last hierarchy update is where it should be
+            let children = child_index((&parents, &entities).iter().map(|(parent, child)| (child, parent.0)));
+
+            subtree(&children, entity)
+        };
+
+        for entity in &doomed {
+            self.delete_now(*entity);
+        }
+
+        doomed.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use components;
+    use components::hierarchy::transform::Component as Transform;
+
+    fn new_world() -> specs::World {
+        let mut world = specs::World::new();
+
+        components::hierarchy::register_all(&mut world);
+
+        world
+    }
+
+    fn spawn(world: &mut specs::World, parent: Option<specs::Entity>) -> specs::Entity {
+        let builder = world.create_now().with(Transform::new());
+
+        match parent {
+            Some(parent) => builder.with(Parent::new(parent)).build(),
+            None => builder.build(),
+        }
+    }
+
+    fn alive(world: &specs::World) -> HashSet<specs::Entity> {
+        Join::iter(&world.entities()).collect()
+    }
+
+    #[test]
+    fn test_despawn_deep_hierarchy() {
+        let mut world = new_world();
+
+        let root = spawn(&mut world, None);
+        let other = spawn(&mut world, None);
+
+        //A long chain, with a short branch off every tenth link
+        let mut chain = vec![root];
+        let mut branches = Vec::new();
+
+        for depth in 1..1000 {
+            let link = spawn(&mut world, Some(chain[depth - 1]));
+
+            if depth % 10 == 0 {
+                branches.push(spawn(&mut world, Some(link)));
+            }
+
+            chain.push(link);
+        }
+
+        let kept = spawn(&mut world, Some(other));
+
+        //Deleting from the middle takes everything below, but nothing above
+        assert_eq!(world.despawn_recursive(chain[500]), 500 + 50);
+
+        let remaining = alive(&world);
+
+        assert!(chain[..500].iter().all(|link| remaining.contains(link)));
+        assert!(chain[500..].iter().all(|link| !remaining.contains(link)));
+        assert_eq!(branches.iter().filter(|branch| remaining.contains(branch)).count(), 49);
+
+        assert_eq!(world.despawn_recursive(root), 500 + 49);
+        assert_eq!(world.despawn_recursive(root), 0);
+
+        assert_eq!(alive(&world), vec![other, kept].into_iter().collect());
+    }
+
+    #[test]
+    fn test_reparent_before_despawn() {
+        let mut world = new_world();
+
+        let a = spawn(&mut world, None);
+        let b = spawn(&mut world, None);
+        let child = spawn(&mut world, Some(a));
+        let grandchild = spawn(&mut world, Some(child));
+
+        //No hierarchy update has run since the move, and it doesn't need to
+        world.write::<Parent>().insert(child, Parent::new(b));
+
+        assert_eq!(world.despawn_recursive(a), 1);
+        assert!(alive(&world).contains(&grandchild));
+
+        assert_eq!(world.despawn_recursive(b), 3);
+        assert!(alive(&world).is_empty());
+    }
+
+    #[test]
+    fn test_despawn_while_iterating() {
+        let mut world = new_world();
+
+        let root = spawn(&mut world, None);
+        let mut everything = vec![root];
+
+        for i in 0..30 {
+            let parent = everything[i / 2];
+
+            everything.push(spawn(&mut world, Some(parent)));
+        }
+
+        //Deleting every entity in turn, from the bottom up and then the top down, reaches each one once
+        let mut deleted = 0;
+
+        let every_third = everything.iter().rev().enumerate().filter(|&(i, _)| i % 3 == 0).map(|(_, entity)| entity);
+
+        for entity in every_third.chain(everything.iter()) {
+            deleted += world.despawn_recursive(*entity);
+        }
+
+        assert_eq!(deleted, everything.len());
+        assert!(alive(&world).is_empty());
+    }
+
+    #[test]
+    fn test_subtree_survives_cycles() {
+        let mut world = new_world();
+
+        let a = world.create_now().build();
+        let b = world.create_now().build();
+
+        let children = child_index(vec![(a, b), (b, a)]);
+
+        assert_eq!(subtree(&children, a), vec![a, b]);
+    }
+}
@@ -0,0 +1,499 @@
+//! Reloading a scene file into a running world, keeping whatever happened at runtime that the file doesn't change
+//!
+//! Entities loaded with `reload` are given a `scene_origin::Component` with their ID in the file. Reloading the same
+//! file again matches entities up by that ID: entities new to the file are spawned, those gone from it are deleted,
+//! and only components the file changed are loaded again. Anything spawned at runtime is left alone.
+//!
+//! A component changed both at runtime and in the file takes the file's version, with a warning about the runtime
+//! changes it overwrote. Entities from the file that were deleted at runtime are spawned again.
+//!
+//! Like the rest of the scene storage, reloading returns whatever error type `E` the registry is for, which also has
+//! to be convertible from IO errors for reading the file.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use capnp;
+use capnp::any_pointer;
+
+use specs;
+use specs::Join;
+
+use protocols::scene::protocol;
+
+use scene::{ComponentRegistry, SaveContext, LoadContext, SceneError, SceneId};
+use scene_settings::Resource as SceneSettings;
+
+use components::scene_origin::Component as SceneOrigin;
+
+/// How often a watched scene file is checked for changes
+pub const SCENE_POLL_INTERVAL_MS: u64 = 500;
+
+/// What reloading a scene changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadReport {
+    /// Entities spawned for IDs that weren't loaded yet, in the order they're in the file
+    pub added: Vec<specs::Entity>,
+    /// Entities deleted for no longer being in the file
+    pub removed: Vec<specs::Entity>,
+    /// Components loaded again or removed because the file changed them
+    pub updated: usize,
+    /// Of those, components that had also been changed at runtime
+    pub overwritten: usize,
+}
+
+impl Display for ReloadReport {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{} entities added, {} removed, {} components updated, {} runtime changes overwritten",
+               self.added.len(), self.removed.len(), self.updated, self.overwritten)
+    }
+}
+
+/// Copies component data into a message of its own, so the same data always gives the same bytes
+fn data_bytes<E>(reader: any_pointer::Reader) -> Result<Vec<u8>, E> where E: From<capnp::Error> + From<io::Error> {
+    let mut message = capnp::message::Builder::new_default();
+
+    try!(message.set_root(reader));
+
+    let mut bytes = Vec::new();
+
+    try!(capnp::serialize::write_message(&mut bytes, &message));
+
+    Ok(bytes)
+}
+
+/// Current data of a component, or `None` if the entity doesn't have it or it can't be saved
+fn component_bytes<E>(registry: &ComponentRegistry<E>, name: &str, world: &specs::World, entity: specs::Entity,
+                   context: &SaveContext) -> Option<Vec<u8>> {
+    if !registry.has_component(name, world, entity) {
+        return None;
+    }
+
+    let mut message = capnp::message::Builder::new_default();
+
+    if registry.save_component(name, world, entity, context, message.init_root::<any_pointer::Builder>()).is_err() {
+        return None;
+    }
+
+    let mut bytes = Vec::new();
+
+    capnp::serialize::write_message(&mut bytes, &message).ok().map(|_| bytes)
+}
+
+/// Loads the scene file at `path`, or applies its changes to the entities already loaded from it
+pub fn reload<E, P: AsRef<Path>>(world: &mut specs::World, registry: &ComponentRegistry<E>, path: P) -> Result<ReloadReport, E>
+    where E: From<capnp::Error> + From<SceneError> + From<io::Error> {
+    let path = path.as_ref();
+    let mut reader = BufReader::new(try!(File::open(path)));
+
+    reload_from(world, registry, path, &mut reader)
+}
+
+/// Like `reload`, but reads the scene from `reader` instead of the file at `path`.
+///
+/// Loading a scene for the first time is the same as reloading it with nothing loaded from it yet.
+pub fn reload_from<E, P: AsRef<Path>, R: BufRead>(world: &mut specs::World, registry: &ComponentRegistry<E>, path: P,
+                                                  reader: &mut R) -> Result<ReloadReport, E>
+    where E: From<capnp::Error> + From<SceneError> + From<io::Error> {
+    let path = Arc::new(path.as_ref().to_path_buf());
+
+    let message = try!(capnp::serialize_packed::read_message(reader, capnp::message::ReaderOptions::new()));
+
+    let root = try!(message.get_root::<protocol::root_scene::Reader>());
+    let scene = try!(root.get_scene());
+    let entities_reader = try!(scene.get_entities());
+
+    let mut report = ReloadReport::default();
+
+    //Entities already loaded from this file, by their ID in it
+    let mut loaded: HashMap<u32, specs::Entity> = {
+        let origins = world.read::<SceneOrigin>();
+
+        (&origins, &world.entities()).iter().filter(|&(origin, _)| origin.path() == path.as_path()).map(|(origin, entity)| (origin.id, entity)).collect()
+    };
+
+    let mut ids = HashSet::with_capacity(entities_reader.len() as usize);
+
+    for entity_reader in entities_reader.iter() {
+        if !ids.insert(entity_reader.get_id()) {
+            return Err(SceneError::DuplicateEntity(entity_reader.get_id()).into());
+        }
+    }
+
+    let gone: Vec<u32> = loaded.keys().filter(|id| !ids.contains(id)).cloned().collect();
+
+    for id in gone {
+        let entity = loaded.remove(&id).unwrap();
+
+        world.delete_now(entity);
+        report.removed.push(entity);
+    }
+
+    //New entities are created up front, so components can refer to entities that come later
+    for entity_reader in entities_reader.iter() {
+        let id = entity_reader.get_id();
+
+        if !loaded.contains_key(&id) {
+            let entity = world.create_now().with(SceneId(id)).with(SceneOrigin::new(path.clone(), id)).build();
+
+            loaded.insert(id, entity);
+            report.added.push(entity);
+        }
+    }
+
+    let load_context = LoadContext::new(loaded.clone());
+    let save_context = SaveContext::new(loaded.iter().map(|(id, entity)| (*entity, *id)).collect());
+
+    for entity_reader in entities_reader.iter() {
+        let id = entity_reader.get_id();
+        let entity = loaded[&id];
+
+        let (old_file, mut baseline) = {
+            let origins = world.read::<SceneOrigin>();
+            let origin = origins.get(entity).unwrap();
+
+            (origin.file.clone(), origin.loaded.clone())
+        };
+
+        let mut file = HashMap::new();
+
+        for component_reader in try!(entity_reader.get_components()).iter() {
+            let name = try!(component_reader.get_name());
+
+            if !registry.contains(name) {
+                warn!("Skipping unknown component {:?} of scene entity {}", name, id);
+                continue;
+            }
+
+            let bytes = try!(data_bytes::<E>(component_reader.get_data()));
+
+            if old_file.get(name) != Some(&bytes) {
+                if old_file.contains_key(name) || registry.has_component(name, world, entity) {
+                    if component_bytes(registry, name, world, entity, &save_context).as_ref() != baseline.get(name) {
+                        warn!("Overwriting runtime changes to {:?} of scene entity {} with the changes to {}", name, id, path.display());
+
+                        report.overwritten += 1;
+                    }
+
+                    report.updated += 1;
+                }
+
+                try!(registry.load_component(name, world, entity, &load_context, component_reader.get_data()));
+
+                if let Some(bytes) = component_bytes(registry, name, world, entity, &save_context) {
+                    baseline.insert(name.to_string(), bytes);
+                }
+            }
+
+            file.insert(name.to_string(), bytes);
+        }
+
+        //Components the file had last time, but doesn't any more
+        for name in old_file.keys().filter(|name| !file.contains_key(*name)) {
+            if component_bytes(registry, name, world, entity, &save_context).as_ref() != baseline.get(name) {
+                warn!("Removing {:?} of scene entity {}, which was changed at runtime, for being removed from {}", name, id, path.display());
+
+                report.overwritten += 1;
+            }
+
+            registry.remove_component(name, world, entity);
+            baseline.remove(name);
+
+            report.updated += 1;
+        }
+
+        let mut origins = world.write::<SceneOrigin>();
+        let origin = origins.get_mut(entity).unwrap();
+
+        origin.file = file;
+        origin.loaded = baseline;
+    }
+
+    {
+        let settings_reader = try!(scene.get_settings());
+        let skybox = try!(settings_reader.get_skybox());
+
+        let mut settings = world.write_resource::<SceneSettings>();
+
+        settings.skybox = if skybox.is_empty() { None } else { Some(Path::new(skybox).to_path_buf()) };
+        settings.ambient = try!(settings_reader.get_ambient()).get_vector();
+    }
+
+    Ok(report)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => Some(modified),
+        Err(err) => {
+            warn!("Could not check {} for changes: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+/// Polls a scene file on a thread of its own, sending a message made from its path whenever it changes
+pub struct SceneWatcher {
+    running: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl SceneWatcher {
+    /// Watches the file at `path`, sending `signal(path)` down `tx` each time it changes until the receiver hangs up
+    pub fn spawn<P, S, F>(path: P, tx: mpsc::Sender<S>, signal: F) -> SceneWatcher
+        where P: AsRef<Path>,
+              S: Send + 'static,
+              F: Fn(PathBuf) -> S + Send + 'static {
+        let path: PathBuf = path.as_ref().to_path_buf();
+
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+
+        let thread = thread::Builder::new().name("Scene watcher".to_string()).spawn(move || {
+            let mut last = modified(&path);
+
+            info!("Watching {} for changes", path.display());
+
+            while thread_running.load(Ordering::SeqCst) {
+                //Unparked early when the watcher is dropped
+                thread::park_timeout(Duration::from_millis(SCENE_POLL_INTERVAL_MS));
+
+                if !thread_running.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                let current = modified(&path);
+
+                //Missing files are skipped rather than treated as changes, since editors may briefly replace them
+                if current.is_some() && current != last {
+                    if last.is_some() {
+                        info!("{} changed, reloading", path.display());
+
+                        if tx.send(signal(path.clone())).is_err() {
+                            break;
+                        }
+                    }
+
+                    last = current;
+                }
+            }
+        });
+
+        match thread {
+            Ok(thread) => SceneWatcher { running: running, thread: Some(thread) },
+            Err(err) => {
+                error!("Could not start scene watcher: {}", err);
+
+                SceneWatcher { running: running, thread: None }
+            }
+        }
+    }
+}
+
+impl Drop for SceneWatcher {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+
+            if thread.join().is_err() {
+                error!("Scene watcher panicked");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use nalgebra::Vector3;
+
+    use builtin;
+    use components;
+    use components::name::Component as Name;
+    use components::hierarchy::parent::Component as Parent;
+    use components::hierarchy::transform::Component as Transform;
+    use components::point_light::Component as PointLight;
+    use components::mesh_renderer::Component as MeshRenderer;
+
+    #[derive(Debug)]
+    enum TestError {
+        Capnp(capnp::Error),
+        Scene(SceneError),
+        Io(io::Error),
+    }
+
+    impl From<capnp::Error> for TestError {
+        fn from(err: capnp::Error) -> TestError { TestError::Capnp(err) }
+    }
+
+    impl From<SceneError> for TestError {
+        fn from(err: SceneError) -> TestError { TestError::Scene(err) }
+    }
+
+    impl From<io::Error> for TestError {
+        fn from(err: io::Error) -> TestError { TestError::Io(err) }
+    }
+
+    struct TestMesh;
+    struct TestMaterial;
+
+    fn registry() -> ComponentRegistry<TestError> {
+        let mut registry = ComponentRegistry::new();
+
+        builtin::register_all::<TestError, TestMesh, TestMaterial>(&mut registry);
+
+        registry
+    }
+
+    const PATH: &'static str = "scenes/fixture.cscn";
+
+    fn new_world() -> specs::World {
+        let mut world = specs::World::new();
+
+        components::register_all(&mut world);
+
+        world.register::<MeshRenderer<TestMesh, TestMaterial>>();
+        world.register::<SceneId>();
+
+        world.add_resource(SceneSettings::new());
+
+        world
+    }
+
+    fn save(world: &specs::World) -> Vec<u8> {
+        let mut buffer = Vec::new();
+
+        builtin::save_world(world, &registry(), &mut buffer).unwrap();
+
+        buffer
+    }
+
+    /// Two versions of a scene file, from before and after an edit
+    fn fixture() -> (Vec<u8>, Vec<u8>) {
+        let before = {
+            let mut world = new_world();
+
+            world.create_now().with(SceneId(0)).with(Name::new("floor")).with(Transform::new()).build();
+            world.create_now().with(SceneId(1)).with(Name::new("crate")).with(Transform::from_translation(1.0, 0.0, 0.0))
+                 .with(PointLight::new(Vector3::new(1.0, 1.0, 1.0), 1.0, 5.0)).build();
+            world.create_now().with(SceneId(2)).with(Name::new("lamp")).with(PointLight::new(Vector3::new(1.0, 1.0, 1.0), 2.0, 5.0)).build();
+            world.create_now().with(SceneId(3)).with(Name::new("doomed")).build();
+
+            save(&world)
+        };
+
+        //The crate is moved and loses its light, the lamp is brightened, one entity is deleted and another added
+        let after = {
+            let mut world = new_world();
+
+            let floor = world.create_now().with(SceneId(0)).with(Name::new("floor")).with(Transform::new()).build();
+            world.create_now().with(SceneId(1)).with(Name::new("crate")).with(Transform::from_translation(2.0, 0.0, 0.0)).build();
+            world.create_now().with(SceneId(2)).with(Name::new("lamp")).with(PointLight::new(Vector3::new(1.0, 1.0, 1.0), 4.0, 5.0)).build();
+            world.create_now().with(SceneId(4)).with(Name::new("rug")).with(Parent::new(floor)).build();
+
+            save(&world)
+        };
+
+        (before, after)
+    }
+
+    fn find(world: &specs::World, id: u32) -> Option<specs::Entity> {
+        let origins = world.read::<SceneOrigin>();
+
+        (&origins, &world.entities()).iter().find(|&(origin, _)| origin.id == id).map(|(_, entity)| entity)
+    }
+
+    #[test]
+    fn test_reload_edited_scene() {
+        let (before, after) = fixture();
+
+        let registry = registry();
+        let mut world = new_world();
+
+        let report = reload_from(&mut world, &registry, PATH, &mut Cursor::new(before)).unwrap();
+
+        assert_eq!(report.added.len(), 4);
+        assert_eq!(report.updated, 0);
+
+        let (floor, krate, lamp, doomed) = (find(&world, 0).unwrap(), find(&world, 1).unwrap(), find(&world, 2).unwrap(), find(&world, 3).unwrap());
+
+        //Runtime changes: a new entity, the crate pushed around and the lamp renamed
+        let player = world.create_now().with(Name::new("player")).with(Transform::new()).build();
+
+        world.write::<Transform>().get_mut(krate).unwrap().set_translation(Vector3::new(1.5, 0.0, 0.0));
+        world.write::<Name>().get_mut(lamp).unwrap().0 = "renamed lamp".to_string();
+
+        let report = reload_from(&mut world, &registry, PATH, &mut Cursor::new(after)).unwrap();
+
+        let rug = find(&world, 4).unwrap();
+
+        assert_eq!(report.added, vec![rug]);
+        assert_eq!(report.removed, vec![doomed]);
+
+        //The crate's transform and light, and the lamp's light
+        assert_eq!(report.updated, 3);
+
+        //Only the crate's transform was changed on both sides
+        assert_eq!(report.overwritten, 1);
+
+        //Existing entities are updated in place
+        assert_eq!(find(&world, 0), Some(floor));
+        assert_eq!(find(&world, 1), Some(krate));
+        assert!(find(&world, 3).is_none());
+
+        assert_eq!(world.read::<Transform>().get(krate).unwrap().translation(), &Vector3::new(2.0, 0.0, 0.0));
+        assert!(world.read::<PointLight>().get(krate).is_none());
+        assert_eq!(world.read::<PointLight>().get(lamp).unwrap().intensity, 4.0);
+
+        //The file didn't change the lamp's name, so the runtime change stays
+        assert_eq!(world.read::<Name>().get(lamp).unwrap().0, "renamed lamp");
+
+        //References to other entities in the file are resolved to the entities already loaded
+        assert_eq!(world.read::<Parent>().get(rug), Some(&Parent::new(floor)));
+
+        //Entities spawned at runtime are left alone
+        assert_eq!(world.read::<Name>().get(player).unwrap().0, "player");
+        assert!(world.read::<SceneOrigin>().get(player).is_none());
+
+        //Reloading an unchanged file changes nothing, even with runtime changes since
+        world.write::<Transform>().get_mut(floor).unwrap().set_translation(Vector3::new(0.0, 1.0, 0.0));
+
+        let (_, after) = fixture();
+
+        let report = reload_from(&mut world, &registry, PATH, &mut Cursor::new(after)).unwrap();
+
+        assert_eq!(report, ReloadReport::default());
+        assert_eq!(world.read::<Transform>().get(floor).unwrap().translation(), &Vector3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_files_are_kept_apart() {
+        let (before, after) = fixture();
+
+        let registry = registry();
+        let mut world = new_world();
+
+        reload_from(&mut world, &registry, "scenes/a.cscn", &mut Cursor::new(before)).unwrap();
+
+        //The same IDs from another file are different entities
+        let report = reload_from(&mut world, &registry, "scenes/b.cscn", &mut Cursor::new(after)).unwrap();
+
+        assert_eq!(report.added.len(), 4);
+        assert!(report.removed.is_empty());
+
+        assert_eq!(world.read::<SceneOrigin>().iter().count(), 8);
+    }
+}
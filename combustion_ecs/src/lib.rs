@@ -1,16 +1,60 @@
-#![feature(box_syntax)]
+#![feature(box_syntax, core_intrinsics)]
 
 extern crate specs;
 extern crate petgraph;
 //extern crate num_cpus;
 extern crate fnv;
+extern crate nalgebra;
+extern crate num_traits;
+extern crate capnp;
+extern crate smallvec;
+extern crate combustion_common as common;
+extern crate combustion_protocols as protocols;
+extern crate combustion_asset as asset;
+
+#[macro_use]
+extern crate slog;
+#[macro_use]
+extern crate slog_scope;
 
 #[macro_use]
 extern crate trace_error;
 
 pub mod error;
 pub mod builder;
+#[macro_use]
 pub mod macros;
+pub mod storage;
+pub mod events;
+pub mod schedule;
+pub mod bounds;
+pub mod frustum;
+pub mod lights;
+pub mod light_list;
+pub mod frame_channel;
+pub mod spatial_index;
+pub mod scene;
+pub mod scene_settings;
+pub mod prefab_store;
+pub mod name_index;
+pub mod hierarchy;
+pub mod animation_events;
+pub mod visible_set;
+pub mod camera_matrices;
+pub mod billboard_list;
+pub mod lod_settings;
+pub mod material_overrides;
+pub mod time;
+pub mod groups;
+pub mod builtin;
+pub mod hot_reload;
+pub mod debug;
+pub mod components;
+pub mod systems;
+#[macro_use]
+pub mod spawn;
+#[cfg(test)]
+mod testing;
 
 pub type Delta = f64;
 pub type Planner = specs::Planner<Delta>;
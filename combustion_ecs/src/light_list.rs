@@ -0,0 +1,109 @@
+//! The LightList resource holds every enabled light in the scene, gathered each update for the lighting pass
+
+use lights::{Light, MAX_LIGHTS};
+
+#[derive(Clone, Debug)]
+pub struct Resource {
+    lights: Vec<Light>,
+    /// Lights left out of the current list for going over `MAX_LIGHTS`
+    dropped: usize,
+    /// Lights left out of the previous list, so the warning is only logged when the number changes
+    last_dropped: usize,
+}
+
+impl Default for Resource {
+    #[inline(always)]
+    fn default() -> Resource { Resource::new() }
+}
+
+impl Resource {
+    pub fn new() -> Resource {
+        Resource {
+            lights: Vec::with_capacity(MAX_LIGHTS),
+            dropped: 0,
+            last_dropped: 0,
+        }
+    }
+
+    /// Empties the list before gathering lights again
+    pub fn clear(&mut self) {
+        self.lights.clear();
+        self.dropped = 0;
+    }
+
+    /// Adds a light, unless the list already holds `MAX_LIGHTS`. Returns whether it was added.
+    pub fn push(&mut self, light: Light) -> bool {
+        if self.lights.len() < MAX_LIGHTS {
+            self.lights.push(light);
+            true
+        } else {
+            self.dropped += 1;
+            false
+        }
+    }
+
+    /// Warns if lights were left out, once each time the number left out changes
+    pub fn finish(&mut self) {
+        if self.dropped != self.last_dropped && self.dropped > 0 {
+            warn!("Too many lights, only the first {} of {} will be rendered", MAX_LIGHTS, MAX_LIGHTS + self.dropped);
+        }
+
+        self.last_dropped = self.dropped;
+    }
+
+    #[inline]
+    pub fn lights(&self) -> &[Light] { &self.lights }
+
+    /// Number of lights left out of the list for going over `MAX_LIGHTS`
+    #[inline]
+    pub fn dropped(&self) -> usize { self.dropped }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use nalgebra::{Point3, Vector3};
+
+    fn point(intensity: f32) -> Light {
+        Light::point(Point3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0), intensity, 1.0)
+    }
+
+    #[test]
+    fn test_gathering() {
+        let mut list = Resource::new();
+
+        assert!(list.push(point(1.0)));
+        assert!(list.push(point(2.0)));
+
+        assert_eq!(list.lights().len(), 2);
+        assert_eq!(list.lights()[1].intensity, 2.0);
+        assert_eq!(list.dropped(), 0);
+
+        list.clear();
+
+        assert!(list.lights().is_empty());
+    }
+
+    #[test]
+    fn test_capping() {
+        let mut list = Resource::new();
+
+        for i in 0..MAX_LIGHTS + 3 {
+            assert_eq!(list.push(point(i as f32)), i < MAX_LIGHTS);
+        }
+
+        list.finish();
+
+        // The first lights are kept, so lights gathered first take priority
+        assert_eq!(list.lights().len(), MAX_LIGHTS);
+        assert_eq!(list.lights()[MAX_LIGHTS - 1].intensity, (MAX_LIGHTS - 1) as f32);
+        assert_eq!(list.dropped(), 3);
+
+        list.clear();
+        list.push(point(1.0));
+        list.finish();
+
+        assert_eq!(list.dropped(), 0);
+    }
+}
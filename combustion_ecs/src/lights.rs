@@ -0,0 +1,78 @@
+//! Light data gathered from the scene for the lighting and forward passes
+
+use nalgebra::{Point3, Vector3};
+
+/// Must match `MAX_LIGHTS` in `shaders/lib/lights.glsl`
+pub const MAX_LIGHTS: usize = 32;
+
+/// Brightness below which a point light is considered to have no effect, which determines the size of its light volume
+pub const LIGHT_CUTOFF: f32 = 0.005;
+
+/// Must match the `*_LIGHT` defines in `shaders/lib/lights.glsl`
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightKind {
+    Directional = 1,
+    Point = 2,
+    Spot = 3,
+}
+
+/// Light data as uploaded to the lighting and forward shaders
+#[derive(Debug, Clone, Copy)]
+pub struct Light {
+    pub kind: LightKind,
+    /// Position of the light. Ignored for directional lights.
+    pub position: Point3<f32>,
+    /// Direction the light is pointing. Ignored for point lights.
+    pub direction: Vector3<f32>,
+    pub color: Vector3<f32>,
+    pub intensity: f32,
+    /// Radius used for attenuation. Ignored for directional lights.
+    pub radius: f32,
+    /// Inner and outer cone angles for spotlights, in radians
+    pub cone: (f32, f32),
+}
+
+impl Light {
+    pub fn directional(direction: Vector3<f32>, color: Vector3<f32>, intensity: f32) -> Light {
+        Light {
+            kind: LightKind::Directional,
+            position: Point3::new(0.0, 0.0, 0.0),
+            direction: direction,
+            color: color,
+            intensity: intensity,
+            radius: 0.0,
+            cone: (0.0, 0.0),
+        }
+    }
+
+    pub fn point(position: Point3<f32>, color: Vector3<f32>, intensity: f32, radius: f32) -> Light {
+        Light {
+            kind: LightKind::Point,
+            position: position,
+            direction: Vector3::new(0.0, 0.0, -1.0),
+            color: color,
+            intensity: intensity,
+            radius: radius,
+            cone: (0.0, 0.0),
+        }
+    }
+
+    /// Distance at which the attenuation in `shaders/lib/attenuation.glsl` falls below `LIGHT_CUTOFF`
+    pub fn volume_radius(&self) -> f32 {
+        let peak = self.intensity * self.color.x.max(self.color.y).max(self.color.z);
+
+        if peak <= LIGHT_CUTOFF {
+            0.0
+        } else {
+            self.radius * ((peak / LIGHT_CUTOFF).sqrt() - 1.0)
+        }
+    }
+}
+
+impl Default for Light {
+    /// A black point light, used to pad the lights uniform block
+    fn default() -> Light {
+        Light::point(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0), 0.0, 1.0)
+    }
+}
@@ -0,0 +1,77 @@
+//! The LodSettings resource holds the global settings used by the LOD system to pick each entity's level of detail
+//!
+//! It also holds the flat colored materials of type `T` that levels are drawn in when debugging.
+
+use std::sync::Arc;
+
+/// Materials that can be made in a single flat color, for the LOD debug view
+pub trait DebugMaterial {
+    fn flat(color: [f32; 3]) -> Self;
+}
+
+/// Colors of each level in the debug view, repeating for levels past the last
+pub const DEBUG_COLORS: [[f32; 3]; 6] = [
+    [0.1, 0.9, 0.1],
+    [0.1, 0.5, 1.0],
+    [1.0, 0.9, 0.1],
+    [1.0, 0.5, 0.1],
+    [0.9, 0.1, 0.1],
+    [0.8, 0.1, 0.9],
+];
+
+pub struct Resource<T> {
+    /// Positive values switch to simpler levels sooner, and negative ones later. Each step of one halves or doubles
+    /// the screen coverage each entity is treated as having.
+    pub bias: f32,
+    /// Fraction of a level's coverage that has to be crossed past its boundary before switching
+    pub hysteresis: f32,
+    debug: bool,
+    debug_materials: Vec<Arc<T>>,
+}
+
+impl<T: DebugMaterial> Default for Resource<T> {
+    #[inline(always)]
+    fn default() -> Resource<T> { Resource::new() }
+}
+
+//Materials are shared rather than copied, so cloning doesn't need them to be `Clone`
+impl<T> Clone for Resource<T> {
+    fn clone(&self) -> Resource<T> {
+        Resource {
+            bias: self.bias,
+            hysteresis: self.hysteresis,
+            debug: self.debug,
+            debug_materials: self.debug_materials.clone(),
+        }
+    }
+}
+
+impl<T: DebugMaterial> Resource<T> {
+    pub fn new() -> Resource<T> {
+        let debug_materials = DEBUG_COLORS.iter().map(|color| Arc::new(T::flat(*color))).collect();
+
+        Resource { bias: 0.0, hysteresis: 0.1, debug: false, debug_materials: debug_materials }
+    }
+}
+
+impl<T> Resource<T> {
+    /// Screen coverage after applying the bias
+    #[inline]
+    pub fn biased(&self, coverage: f32) -> f32 {
+        coverage * (-self.bias).exp2()
+    }
+
+    /// Whether entities with levels of detail are drawn in a flat color for their current level
+    #[inline]
+    pub fn is_debug(&self) -> bool { self.debug }
+
+    #[inline]
+    pub fn set_debug(&mut self, debug: bool) {
+        self.debug = debug;
+    }
+
+    /// Material overriding every material of an entity drawn at `level`, when debugging
+    pub fn debug_material(&self, level: usize) -> Arc<T> {
+        self.debug_materials[level % self.debug_materials.len()].clone()
+    }
+}
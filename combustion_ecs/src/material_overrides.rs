@@ -0,0 +1,304 @@
+//! The MaterialOverrides resource holds the materials merged from shared materials and material override components
+//!
+//! Merged materials are looked up by their merged state, so every entity ending up with the same parameters draws with
+//! the same material, and is batched with the others like it. The render submission system merges overrides
+//! each update, and collects the merged materials nothing used, so removing an override releases its material.
+//!
+//! What the parameters of a material are is up to its type `T`, through `Overridable`.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::Arc;
+
+use ::components::material_override::{Component as MaterialOverride, Value};
+
+/// Materials whose parameters can be overridden by name
+pub trait Overridable: Sized {
+    /// Parameters of a material, which overrides are applied to
+    type Definition: Clone;
+    /// Merged state of a material, equal for materials that would draw the same
+    type Key: Eq + Hash;
+    /// Textures that can override the material's texture parameters
+    type Texture;
+
+    fn definition(&self) -> &Self::Definition;
+
+    /// Creates a merged material
+    fn from_definition(definition: Self::Definition) -> Self;
+
+    fn key(definition: &Self::Definition) -> Self::Key;
+
+    /// Applies a single override, returning false if the definition has no parameter of that name and kind
+    fn apply(definition: &mut Self::Definition, name: &str, value: &Value<Self::Texture>) -> bool;
+}
+
+pub struct Resource<T: Overridable> {
+    /// Merged materials, and whether each has been used since the last collection
+    merged: HashMap<T::Key, (Arc<T>, bool)>,
+    /// Parameter names already warned about
+    warned: HashSet<String>,
+}
+
+impl<T: Overridable> Default for Resource<T> {
+    #[inline(always)]
+    fn default() -> Resource<T> { Resource::new() }
+}
+
+impl<T: Overridable> Resource<T> {
+    pub fn new() -> Resource<T> {
+        Resource { merged: HashMap::new(), warned: HashSet::new() }
+    }
+
+    /// Number of merged materials kept
+    #[inline]
+    pub fn len(&self) -> usize { self.merged.len() }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.merged.is_empty() }
+
+    /// Material with the overrides merged over `base`. If they don't change anything, that's `base` itself.
+    ///
+    /// Overrides naming a parameter the material doesn't have, or giving it the wrong kind of value,
+    /// are ignored with a warning the first time each name is seen.
+    pub fn merge(&mut self, base: &Arc<T>, overrides: &MaterialOverride<T::Texture>) -> Arc<T> {
+        let mut definition = base.definition().clone();
+
+        for (name, value) in overrides.iter() {
+            if !T::apply(&mut definition, name, value) && !self.warned.contains(name) {
+                warn!("Materials have no {:?} parameter taking {:?}, so overriding it does nothing", name, value);
+
+                self.warned.insert(name.clone());
+            }
+        }
+
+        let key = T::key(&definition);
+
+        if key == T::key(base.definition()) {
+            return base.clone();
+        }
+
+        let entry = self.merged.entry(key).or_insert_with(|| (Arc::new(T::from_definition(definition)), false));
+
+        entry.1 = true;
+
+        entry.0.clone()
+    }
+
+    /// Forgets the merged materials that haven't been used since the last call.
+    ///
+    /// Frame packets hold on to the materials they draw with, so this only stops new merges from reusing them.
+    pub fn collect(&mut self) {
+        self.merged.retain(|_, entry| {
+            let used = entry.1;
+
+            entry.1 = false;
+
+            used
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::mem;
+
+    use specs;
+
+    struct TestTexture;
+
+    #[derive(Clone)]
+    struct Definition {
+        diffuse: Option<Arc<TestTexture>>,
+        diffuse_factor: [f32; 3],
+        roughness_factor: f32,
+        metallic_factor: f32,
+    }
+
+    struct TestMaterial(Definition);
+
+    impl Overridable for TestMaterial {
+        type Definition = Definition;
+        type Key = (usize, [u32; 5]);
+        type Texture = TestTexture;
+
+        fn definition(&self) -> &Definition { &self.0 }
+
+        fn from_definition(definition: Definition) -> TestMaterial { TestMaterial(definition) }
+
+        fn key(d: &Definition) -> (usize, [u32; 5]) {
+            let bits = |value: f32| unsafe { mem::transmute::<f32, u32>(value) };
+
+            (d.diffuse.as_ref().map_or(0, |texture| &**texture as *const _ as usize),
+             [bits(d.diffuse_factor[0]), bits(d.diffuse_factor[1]), bits(d.diffuse_factor[2]), bits(d.roughness_factor), bits(d.metallic_factor)])
+        }
+
+        fn apply(definition: &mut Definition, name: &str, value: &Value<TestTexture>) -> bool {
+            match (name, value) {
+                ("diffuse_factor", &Value::Color(color)) => definition.diffuse_factor = color,
+                ("roughness_factor", &Value::Scalar(value)) => definition.roughness_factor = value,
+                ("metallic_factor", &Value::Scalar(value)) => definition.metallic_factor = value,
+                ("diffuse", &Value::Texture(ref handle)) => if let Some(texture) = handle.get() {
+                    definition.diffuse = Some(texture);
+                },
+                _ => return false,
+            }
+
+            true
+        }
+    }
+
+    type MaterialOverride = ::components::material_override::Component<TestTexture>;
+
+    fn material(roughness: f32) -> Arc<TestMaterial> {
+        Arc::new(TestMaterial(Definition { diffuse: None, diffuse_factor: [1.0, 1.0, 1.0], roughness_factor: roughness, metallic_factor: 0.0 }))
+    }
+
+    fn same(a: &Arc<TestMaterial>, b: &Arc<TestMaterial>) -> bool {
+        &**a as *const _ == &**b as *const _
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut overrides = Resource::<TestMaterial>::new();
+
+        let base = material(0.5);
+        let red = MaterialOverride::new().with("diffuse_factor", Value::Color([1.0, 0.0, 0.0]));
+
+        let first = overrides.merge(&base, &red);
+        let second = overrides.merge(&base, &red.clone());
+
+        //Identical overrides of the same material share the merged one
+        assert!(!same(&first, &base));
+        assert!(same(&first, &second));
+        assert_eq!(first.definition().diffuse_factor, [1.0, 0.0, 0.0]);
+        assert_eq!(first.definition().roughness_factor, 0.5);
+
+        //So do different overrides ending up the same
+        let rough = overrides.merge(&material(0.9), &red.clone().with("roughness_factor", Value::Scalar(0.5)));
+
+        assert!(same(&rough, &first));
+        assert_eq!(overrides.len(), 1);
+
+        //Overriding a parameter with the value it already has changes nothing
+        let white = MaterialOverride::new().with("diffuse_factor", Value::Color([1.0, 1.0, 1.0]));
+
+        assert!(same(&overrides.merge(&base, &white), &base));
+        assert_eq!(overrides.len(), 1);
+    }
+
+    #[test]
+    fn test_unknown_parameters() {
+        let mut overrides = Resource::<TestMaterial>::new();
+
+        let base = material(0.5);
+
+        let unknown = MaterialOverride::new()
+            .with("emissive", Value::Color([1.0, 1.0, 1.0]))
+            .with("roughness_factor", Value::Color([0.1, 0.1, 0.1]))
+            .with("metallic_factor", Value::Scalar(1.0));
+
+        //The rest are still applied
+        let merged = overrides.merge(&base, &unknown);
+
+        assert_eq!(merged.definition().metallic_factor, 1.0);
+        assert_eq!(merged.definition().roughness_factor, 0.5);
+
+        overrides.merge(&base, &unknown);
+
+        let mut warned: Vec<&str> = overrides.warned.iter().map(|name| name.as_str()).collect();
+
+        warned.sort();
+
+        assert_eq!(warned, vec!["emissive", "roughness_factor"]);
+    }
+
+    #[test]
+    fn test_collect() {
+        let mut overrides = Resource::<TestMaterial>::new();
+
+        let base = material(0.5);
+
+        let red = MaterialOverride::new().with("diffuse_factor", Value::Color([1.0, 0.0, 0.0]));
+        let blue = MaterialOverride::new().with("diffuse_factor", Value::Color([0.0, 0.0, 1.0]));
+
+        let first = overrides.merge(&base, &red);
+
+        overrides.merge(&base, &blue);
+        overrides.collect();
+
+        assert_eq!(overrides.len(), 2);
+
+        //Only red is used in the next update, so blue is dropped after it
+        assert!(same(&overrides.merge(&base, &red), &first));
+
+        overrides.collect();
+
+        assert_eq!(overrides.len(), 1);
+
+        overrides.collect();
+
+        assert!(overrides.is_empty());
+    }
+
+    /// Merges the overrides of every entity for an update, like render submission does, returning each one's material
+    fn submit(world: &specs::World, base: &Arc<TestMaterial>, entities: &[specs::Entity]) -> Vec<usize> {
+        let mut merged_materials = world.write_resource::<Resource<TestMaterial>>();
+        let material_overrides = world.read::<MaterialOverride>();
+
+        let materials = entities.iter().map(|&entity| {
+            let material = match material_overrides.get(entity) {
+                Some(material_override) => merged_materials.merge(base, material_override),
+                None => base.clone(),
+            };
+
+            &*material as *const _ as usize
+        }).collect();
+
+        merged_materials.collect();
+
+        materials
+    }
+
+    #[test]
+    fn test_overrides_across_updates() {
+        let mut world = specs::World::new();
+
+        world.register::<MaterialOverride>();
+        world.add_resource(Resource::<TestMaterial>::new());
+
+        let material = material(0.5);
+        let base = &*material as *const _ as usize;
+
+        let red = MaterialOverride::new().with("diffuse_factor", Value::Color([1.0, 0.0, 0.0]));
+
+        let entities = [
+            world.create_now().with(red.clone()).build(),
+            world.create_now().with(red.clone()).build(),
+            world.create_now().build(),
+        ];
+
+        //Identical overrides share a merged material, which the entity without any doesn't use
+        let drawn = submit(&world, &material, &entities);
+
+        assert!(drawn[0] != base);
+        assert_eq!(drawn[0], drawn[1]);
+        assert_eq!(drawn[2], base);
+
+        //Removing an override goes straight back to the shared material
+        world.write::<MaterialOverride>().remove(entities[0]);
+
+        let drawn = submit(&world, &material, &entities);
+
+        assert_eq!(drawn[0], base);
+        assert!(drawn[1] != base);
+        assert_eq!(world.read_resource::<Resource<TestMaterial>>().len(), 1);
+
+        //And once nothing uses the merged material, it's dropped
+        world.write::<MaterialOverride>().remove(entities[1]);
+
+        assert_eq!(submit(&world, &material, &entities), vec![base, base, base]);
+        assert!(world.read_resource::<Resource<TestMaterial>>().is_empty());
+    }
+}
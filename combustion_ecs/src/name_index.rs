@@ -0,0 +1,171 @@
+//! The NameIndex resource finds entities by their name component, kept up to date by the name index system
+
+use std::collections::{HashMap, HashSet};
+
+use specs;
+
+#[derive(Clone, Debug, Default)]
+pub struct Resource {
+    /// Entities with each name, in the order they were given it
+    entities: HashMap<String, Vec<specs::Entity>>,
+    /// Name of each entity in the index
+    names: HashMap<specs::Entity, String>,
+    /// Names already warned about being used more than once
+    duplicates: HashSet<String>,
+}
+
+impl Resource {
+    pub fn new() -> Resource {
+        Resource::default()
+    }
+
+    /// Entity with the given name. If several share it, the first to be given it is returned.
+    pub fn find(&self, name: &str) -> Option<specs::Entity> {
+        self.entities.get(name).and_then(|entities| entities.first().cloned())
+    }
+
+    /// Every entity with a name starting with `prefix`, sorted by name
+    pub fn find_all(&self, prefix: &str) -> Vec<specs::Entity> {
+        let mut found: Vec<(&String, &Vec<specs::Entity>)> = self.entities.iter().filter(|&(name, _)| name.starts_with(prefix)).collect();
+
+        found.sort_by(|a, b| a.0.cmp(b.0));
+
+        found.into_iter().flat_map(|(_, entities)| entities.iter().cloned()).collect()
+    }
+
+    /// Name of an entity in the index
+    pub fn name(&self, entity: specs::Entity) -> Option<&str> {
+        self.names.get(&entity).map(|name| name.as_str())
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize { self.names.len() }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.names.is_empty() }
+
+    /// Brings the index up to date with every named entity in the world.
+    ///
+    /// Entities left out have been deleted or had their name removed, so they're dropped from the index.
+    pub fn sync<'a, I>(&mut self, current: I) where I: IntoIterator<Item = (specs::Entity, &'a str)> {
+        let mut seen = HashSet::with_capacity(self.names.len());
+
+        for (entity, name) in current {
+            seen.insert(entity);
+
+            if self.names.get(&entity).map_or(false, |existing| existing == name) {
+                continue;
+            }
+
+            self.remove(entity);
+            self.insert(entity, name);
+        }
+
+        if seen.len() < self.names.len() {
+            let stale: Vec<specs::Entity> = self.names.keys().filter(|entity| !seen.contains(entity)).cloned().collect();
+
+            for entity in stale {
+                self.remove(entity);
+            }
+        }
+    }
+
+    fn insert(&mut self, entity: specs::Entity, name: &str) {
+        let entities = self.entities.entry(name.to_string()).or_insert_with(Vec::new);
+
+        entities.push(entity);
+
+        if entities.len() > 1 && self.duplicates.insert(name.to_string()) {
+            warn!("More than one entity is named {:?}, so finding it by name returns the first", name);
+        }
+
+        self.names.insert(entity, name.to_string());
+    }
+
+    fn remove(&mut self, entity: specs::Entity) {
+        if let Some(name) = self.names.remove(&entity) {
+            let empty = match self.entities.get_mut(&name) {
+                Some(entities) => {
+                    entities.retain(|existing| *existing != entity);
+                    entities.is_empty()
+                }
+                None => false,
+            };
+
+            if empty {
+                self.entities.remove(&name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use specs::Join;
+
+    use components::name::Component as Name;
+
+    fn sync_world(index: &mut Resource, world: &specs::World) {
+        let names = world.read::<Name>();
+        let entities = world.entities();
+
+        index.sync((&names, &entities).iter().map(|(name, entity)| (entity, name.0.as_str())));
+    }
+
+    #[test]
+    fn test_index_follows_world() {
+        let mut world = specs::World::new();
+
+        world.register::<Name>();
+
+        let mut index = Resource::new();
+
+        let player = world.create_now().with(Name::new("player")).build();
+        let crate_a = world.create_now().with(Name::new("crate a")).build();
+        let crate_b = world.create_now().with(Name::new("crate b")).build();
+
+        sync_world(&mut index, &world);
+
+        assert_eq!(index.find("player"), Some(player));
+        assert_eq!(index.find_all("crate"), vec![crate_a, crate_b]);
+        assert_eq!(index.len(), 3);
+
+        // Renaming moves the entity to its new name
+        world.write::<Name>().get_mut(player).unwrap().0 = "hero".to_string();
+
+        sync_world(&mut index, &world);
+
+        assert_eq!(index.find("player"), None);
+        assert_eq!(index.find("hero"), Some(player));
+        assert_eq!(index.name(player), Some("hero"));
+
+        // Duplicates are allowed, and the first entity given the name is found
+        world.write::<Name>().get_mut(crate_b).unwrap().0 = "crate a".to_string();
+
+        sync_world(&mut index, &world);
+
+        assert_eq!(index.find("crate a"), Some(crate_a));
+        assert_eq!(index.find_all("crate"), vec![crate_a, crate_b]);
+
+        // Deleted entities and removed names are dropped
+        world.delete_now(crate_a);
+        world.write::<Name>().remove(player);
+
+        sync_world(&mut index, &world);
+
+        assert_eq!(index.find("crate a"), Some(crate_b));
+        assert_eq!(index.find("hero"), None);
+        assert_eq!(index.name(crate_a), None);
+        assert_eq!(index.len(), 1);
+
+        // A new entity reusing the deleted entity's slot isn't mistaken for it
+        let barrel = world.create_now().with(Name::new("barrel")).build();
+
+        sync_world(&mut index, &world);
+
+        assert_eq!(index.find("barrel"), Some(barrel));
+        assert_eq!(index.find_all(""), vec![barrel, crate_b]);
+    }
+}
@@ -0,0 +1,487 @@
+//! The PrefabStore resource holds prefabs, which are scene fragments spawned into the world as many times as needed
+//!
+//! A prefab has a single root entity, the only one without a parent, and any number of descendants. Entities with a
+//! `prefab` component spawn the prefab at its path as a child of themselves, so prefabs can be built out of others.
+//!
+//! Prefabs are loaded with a `ComponentRegistry` for any error type `E` that can also be made from I/O errors.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use capnp;
+
+use specs;
+use specs::Join;
+
+use protocols::scene::protocol;
+
+use scene::{self, ComponentRegistry, SceneError, SceneId};
+
+use components::name::Component as Name;
+use components::hierarchy::parent::Component as Parent;
+use components::hierarchy::transform::Component as Transform;
+
+/// Name of the component referring to another prefab, which is handled by prefabs rather than the component registry
+pub const PREFAB_COMPONENT: &'static str = "prefab";
+
+/// Changes made to a single spawned instance of a prefab
+#[derive(Clone, Debug, Default)]
+pub struct Overrides {
+    /// Replaces the transform of the root entity
+    pub transform: Option<Transform>,
+    /// Appended to the name of every named entity spawned, including those of nested prefabs
+    pub name_suffix: Option<String>,
+}
+
+type Message = capnp::message::Reader<capnp::serialize::OwnedSegments>;
+
+pub struct Prefab<E> {
+    path: PathBuf,
+    //Readers can't be shared between threads, but the resource has to be
+    message: Mutex<Message>,
+    /// Index of the root entity in the scene fragment
+    root: usize,
+    /// Every prefab referred to by this one, loaded along with it
+    nested: HashMap<PathBuf, Arc<Prefab<E>>>,
+    registry: Arc<ComponentRegistry<E>>,
+}
+
+impl<E> Prefab<E> where E: From<capnp::Error> + From<SceneError> {
+    #[inline]
+    pub fn path(&self) -> &Path { &self.path }
+
+    /// Spawns a copy of the prefab with its root entity at `transform`, returning the root entity
+    pub fn spawn(&self, world: &mut specs::World, transform: Transform) -> Result<specs::Entity, E> {
+        self.spawn_with(world, &Overrides { transform: Some(transform), name_suffix: None })
+    }
+
+    /// Spawns a copy of the prefab with `overrides` applied, returning the root entity.
+    ///
+    /// Every entity spawned is new, with a scene ID not used by any other entity in the world.
+    pub fn spawn_with(&self, world: &mut specs::World, overrides: &Overrides) -> Result<specs::Entity, E> {
+        let message = self.message.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let scene = try!(try!(message.get_root::<protocol::root_scene::Reader>()).get_scene());
+
+        let mut next_id = {
+            let scene_ids = world.read::<SceneId>();
+
+            (&scene_ids).iter().map(|id| id.0 + 1).max().unwrap_or(0)
+        };
+
+        let mut references = Vec::new();
+
+        let created = try!(scene::load_entities(world, &self.registry, try!(scene.get_entities()), |_| {
+            next_id += 1;
+            next_id - 1
+        }, |_, entity, name, data| {
+            if name == PREFAB_COMPONENT {
+                let path = try!(try!(data.get_as::<protocol::prefab_reference::Reader>()).get_path());
+
+                references.push((entity, PathBuf::from(path)));
+            } else {
+                warn!("Skipping unknown component {:?} in prefab {}", name, self.path.display());
+            }
+
+            Ok(())
+        }));
+
+        let root = created[self.root];
+
+        if let Some(ref transform) = overrides.transform {
+            world.write::<Transform>().insert(root, transform.clone());
+        }
+
+        if let Some(ref suffix) = overrides.name_suffix {
+            let mut names = world.write::<Name>();
+
+            for &entity in &created {
+                if let Some(name) = names.get_mut(entity) {
+                    name.0.push_str(suffix);
+                }
+            }
+        }
+
+        //Nested prefabs keep the transforms they were saved with, relative to the entity referring to them
+        let nested_overrides = Overrides { transform: None, name_suffix: overrides.name_suffix.clone() };
+
+        for (entity, path) in references {
+            let nested_root = try!(self.nested[&path].spawn_with(world, &nested_overrides));
+
+            world.write::<Parent>().insert(nested_root, Parent(entity));
+        }
+
+        Ok(root)
+    }
+}
+
+/// Finds the root entity and every prefab referred to, checking there's only one root
+fn inspect<E>(path: &Path, message: &Message) -> Result<(usize, Vec<PathBuf>), E>
+    where E: From<capnp::Error> + From<SceneError> {
+    let scene = try!(try!(message.get_root::<protocol::root_scene::Reader>()).get_scene());
+
+    let mut roots = Vec::new();
+    let mut references = Vec::new();
+
+    for (i, entity_reader) in try!(scene.get_entities()).iter().enumerate() {
+        let mut has_parent = false;
+
+        for component_reader in try!(entity_reader.get_components()).iter() {
+            match try!(component_reader.get_name()) {
+                "parent" => has_parent = true,
+                PREFAB_COMPONENT => {
+                    let reference = try!(try!(component_reader.get_data().get_as::<protocol::prefab_reference::Reader>()).get_path());
+
+                    references.push(PathBuf::from(reference));
+                }
+                _ => {}
+            }
+        }
+
+        if !has_parent {
+            roots.push(i);
+        }
+    }
+
+    if roots.len() != 1 {
+        return Err(SceneError::PrefabRoots(path.to_path_buf(), roots.len()).into());
+    }
+
+    Ok((roots[0], references))
+}
+
+pub struct Resource<E> {
+    prefabs: HashMap<PathBuf, Arc<Prefab<E>>>,
+    registry: Arc<ComponentRegistry<E>>,
+}
+
+impl<E> Resource<E> where E: From<capnp::Error> + From<SceneError> + From<io::Error> {
+    /// Creates an empty store, spawning every component in `registry`
+    pub fn with_registry(registry: ComponentRegistry<E>) -> Resource<E> {
+        Resource { prefabs: HashMap::new(), registry: Arc::new(registry) }
+    }
+
+    /// The prefab at `path`, if it has been loaded
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<Arc<Prefab<E>>> {
+        self.prefabs.get(path.as_ref()).cloned()
+    }
+
+    /// Loads the prefab at `path` and every prefab it refers to, unless it was loaded before
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<Arc<Prefab<E>>, E> {
+        let path = path.as_ref();
+
+        if let Some(prefab) = self.get(path) {
+            return Ok(prefab);
+        }
+
+        let mut reader = BufReader::new(try!(File::open(path)));
+
+        self.parse(path, &mut reader, &mut Vec::new())
+    }
+
+    /// Reads a prefab from `reader`, to be stored under `path`. Prefabs it refers to are loaded from their files,
+    /// unless they were loaded before.
+    pub fn load_from<P: AsRef<Path>, R: BufRead>(&mut self, path: P, reader: &mut R) -> Result<Arc<Prefab<E>>, E> {
+        self.parse(path.as_ref(), reader, &mut Vec::new())
+    }
+
+    /// `loading` holds the prefabs that are partway through loading, which any reference back to is a cycle
+    fn parse<R: BufRead>(&mut self, path: &Path, reader: &mut R, loading: &mut Vec<PathBuf>) -> Result<Arc<Prefab<E>>, E> {
+        let message = try!(capnp::serialize_packed::read_message(reader, capnp::message::ReaderOptions::new()));
+
+        let (root, references) = try!(inspect::<E>(path, &message));
+
+        loading.push(path.to_path_buf());
+
+        let mut nested = HashMap::new();
+
+        for reference in references {
+            if loading.contains(&reference) {
+                return Err(SceneError::PrefabCycle(path.to_path_buf(), reference).into());
+            }
+
+            let prefab = match self.get(&reference) {
+                Some(prefab) => prefab,
+                None => {
+                    let mut reader = BufReader::new(try!(File::open(&reference)));
+
+                    try!(self.parse(&reference, &mut reader, loading))
+                }
+            };
+
+            nested.insert(reference, prefab);
+        }
+
+        loading.pop();
+
+        let prefab = Arc::new(Prefab {
+            path: path.to_path_buf(),
+            message: Mutex::new(message),
+            root: root,
+            nested: nested,
+            registry: self.registry.clone(),
+        });
+
+        self.prefabs.insert(path.to_path_buf(), prefab.clone());
+
+        Ok(prefab)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use nalgebra::Vector3;
+
+    use builtin;
+    use components;
+    use components::point_light::Component as PointLight;
+    use components::mesh_renderer::Component as MeshRenderer;
+    use scene_settings::Resource as SceneSettings;
+
+    #[derive(Debug)]
+    enum TestError {
+        Capnp(capnp::Error),
+        Scene(SceneError),
+        Io(io::Error),
+    }
+
+    impl From<capnp::Error> for TestError {
+        fn from(err: capnp::Error) -> TestError { TestError::Capnp(err) }
+    }
+
+    impl From<SceneError> for TestError {
+        fn from(err: SceneError) -> TestError { TestError::Scene(err) }
+    }
+
+    impl From<io::Error> for TestError {
+        fn from(err: io::Error) -> TestError { TestError::Io(err) }
+    }
+
+    struct TestMesh;
+    struct TestMaterial;
+
+    fn registry() -> ComponentRegistry<TestError> {
+        let mut registry = ComponentRegistry::new();
+
+        builtin::register_all::<TestError, TestMesh, TestMaterial>(&mut registry);
+
+        registry
+    }
+
+    fn new_store() -> Resource<TestError> {
+        Resource::with_registry(registry())
+    }
+
+    fn new_world() -> specs::World {
+        let mut world = specs::World::new();
+
+        components::register_all(&mut world);
+
+        world.register::<MeshRenderer<TestMesh, TestMaterial>>();
+        world.register::<SceneId>();
+
+        world.add_resource(SceneSettings::new());
+
+        world
+    }
+
+    /// Writes a fragment of `(id, name, parent, prefab)` entities, to refer to prefabs that aren't saved worlds
+    fn fragment(entities: &[(u32, &str, Option<u32>, Option<&str>)]) -> Vec<u8> {
+        let mut message = capnp::message::Builder::new_default();
+
+        {
+            let scene = message.init_root::<protocol::root_scene::Builder>().init_scene();
+            let mut entities_builder = scene.init_entities(entities.len() as u32);
+
+            for (i, &(id, name, parent, prefab)) in entities.iter().enumerate() {
+                let mut entity_builder = entities_builder.borrow().get(i as u32);
+
+                entity_builder.set_id(id);
+
+                let count = 1 + parent.is_some() as u32 + prefab.is_some() as u32;
+                let mut components = entity_builder.init_components(count);
+
+                {
+                    let mut component = components.borrow().get(0);
+
+                    component.set_name("name");
+                    component.init_data().init_as::<protocol::name::Builder>().set_name(name);
+                }
+
+                let mut next = 1;
+
+                if let Some(parent) = parent {
+                    let mut component = components.borrow().get(next);
+
+                    component.set_name("parent");
+                    component.init_data().init_as::<protocol::parent::Builder>().set_entity(parent);
+
+                    next += 1;
+                }
+
+                if let Some(prefab) = prefab {
+                    let mut component = components.borrow().get(next);
+
+                    component.set_name(PREFAB_COMPONENT);
+                    component.init_data().init_as::<protocol::prefab_reference::Builder>().set_path(prefab);
+                }
+            }
+        }
+
+        let mut buffer = Vec::new();
+
+        capnp::serialize_packed::write_message(&mut buffer, &message).unwrap();
+
+        buffer
+    }
+
+    fn name_of(world: &specs::World, entity: specs::Entity) -> String {
+        world.read::<Name>().get(entity).unwrap().0.clone()
+    }
+
+    fn parent_of(world: &specs::World, entity: specs::Entity) -> Option<specs::Entity> {
+        world.read::<Parent>().get(entity).map(|parent| parent.0)
+    }
+
+    fn children_of(world: &specs::World, entity: specs::Entity) -> Vec<specs::Entity> {
+        let parents = world.read::<Parent>();
+        let entities = world.entities();
+
+        (&parents, &entities).iter().filter(|&(parent, _)| parent.0 == entity).map(|(_, child)| child).collect()
+    }
+
+    #[test]
+    fn test_spawned_instances_are_independent() {
+        let mut source = new_world();
+
+        let root = source.create_now()
+                         .with(Name::new("enemy"))
+                         .with(Transform::new())
+                         .build();
+
+        source.create_now()
+              .with(Name::new("lamp"))
+              .with(Transform::from_translation(0.0, 1.0, 0.0))
+              .with(PointLight::white(1.0, 5.0))
+              .with(Parent(root))
+              .build();
+
+        let mut buffer = Vec::new();
+
+        builtin::save_world(&source, &registry(), &mut buffer).unwrap();
+
+        let mut store = new_store();
+        let prefab = store.load_from("prefabs/enemy.cscn", &mut Cursor::new(buffer)).unwrap();
+
+        let mut world = new_world();
+
+        let first = prefab.spawn(&mut world, Transform::from_translation(5.0, 0.0, 0.0)).unwrap();
+
+        let second = prefab.spawn_with(&mut world, &Overrides {
+            transform: Some(Transform::from_translation(-5.0, 0.0, 0.0)),
+            name_suffix: Some(" 2".to_string()),
+        }).unwrap();
+
+        assert!(first != second);
+
+        let (first_lamp, second_lamp) = (children_of(&world, first), children_of(&world, second));
+
+        assert_eq!(first_lamp.len(), 1);
+        assert_eq!(second_lamp.len(), 1);
+
+        let (first_lamp, second_lamp) = (first_lamp[0], second_lamp[0]);
+
+        assert_eq!(name_of(&world, first), "enemy");
+        assert_eq!(name_of(&world, first_lamp), "lamp");
+        assert_eq!(name_of(&world, second), "enemy 2");
+        assert_eq!(name_of(&world, second_lamp), "lamp 2");
+
+        {
+            let transforms = world.read::<Transform>();
+
+            assert_eq!(transforms.get(first).unwrap().translation(), &Vector3::new(5.0, 0.0, 0.0));
+            assert_eq!(transforms.get(second).unwrap().translation(), &Vector3::new(-5.0, 0.0, 0.0));
+
+            // Only the root transform is overridden
+            assert_eq!(transforms.get(second_lamp).unwrap().translation(), &Vector3::new(0.0, 1.0, 0.0));
+        }
+
+        // Changing one instance leaves the other alone
+        world.write::<PointLight>().get_mut(first_lamp).unwrap().intensity = 10.0;
+
+        assert_eq!(world.read::<PointLight>().get(second_lamp).unwrap().intensity, 1.0);
+
+        let scene_ids = world.read::<SceneId>();
+        let mut ids: Vec<_> = [first, first_lamp, second, second_lamp].iter().map(|&entity| scene_ids.get(entity).unwrap().0).collect();
+
+        ids.sort();
+        ids.dedup();
+
+        assert_eq!(ids.len(), 4);
+    }
+
+    #[test]
+    fn test_nested_prefabs() {
+        let mut store = new_store();
+
+        store.load_from("prefabs/weapon.cscn", &mut Cursor::new(fragment(&[
+            (0, "weapon", None, None),
+            (1, "muzzle", Some(0), None),
+        ]))).unwrap();
+
+        let enemy = store.load_from("prefabs/enemy.cscn", &mut Cursor::new(fragment(&[
+            (7, "enemy", None, None),
+            (3, "hand", Some(7), Some("prefabs/weapon.cscn")),
+        ]))).unwrap();
+
+        let mut world = new_world();
+
+        let root = enemy.spawn(&mut world, Transform::new()).unwrap();
+
+        let hand = children_of(&world, root)[0];
+
+        assert_eq!(name_of(&world, hand), "hand");
+
+        let weapon = children_of(&world, hand);
+
+        assert_eq!(weapon.len(), 1);
+        assert_eq!(name_of(&world, weapon[0]), "weapon");
+
+        let muzzle = children_of(&world, weapon[0]);
+
+        assert_eq!(muzzle.len(), 1);
+        assert_eq!(parent_of(&world, muzzle[0]), Some(weapon[0]));
+    }
+
+    #[test]
+    fn test_cycles_are_rejected() {
+        let mut store = new_store();
+
+        let result = store.load_from("prefabs/loop.cscn", &mut Cursor::new(fragment(&[
+            (0, "loop", None, None),
+            (1, "again", Some(0), Some("prefabs/loop.cscn")),
+        ])));
+
+        assert!(if let Err(TestError::Scene(SceneError::PrefabCycle(..))) = result { true } else { false });
+        assert!(store.get("prefabs/loop.cscn").is_none());
+    }
+
+    #[test]
+    fn test_single_root() {
+        let mut store = new_store();
+
+        let result = store.load_from("prefabs/pair.cscn", &mut Cursor::new(fragment(&[
+            (0, "left", None, None),
+            (1, "right", None, None),
+        ])));
+
+        assert!(if let Err(TestError::Scene(SceneError::PrefabRoots(_, 2))) = result { true } else { false });
+    }
+}
@@ -0,0 +1,553 @@
+//! Saving and loading entities as the entity list of a `scene.capnp` protocol message
+//!
+//! Components are serialized by whatever registered them with a `ComponentRegistry`, under a name that's
+//! written along with their data. Loading a scene with a component nobody registered hands it to the caller instead,
+//! so scenes saved by a game with extra components can still be opened by tools that don't know about them.
+//!
+//! Registered functions return whatever error type `E` the registry is for, which only has to be convertible from
+//! Cap'n Proto errors and `SceneError`.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use capnp;
+use capnp::any_pointer;
+
+use specs;
+use specs::Join;
+
+use protocols::scene::protocol;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SceneError {
+    /// More than one entity in a scene has the same ID
+    DuplicateEntity(u32),
+    /// A component being saved refers to an entity that has nothing to save, so it won't be in the scene
+    UnsavedEntity(specs::Entity),
+    /// A component being loaded refers to a scene ID that no entity in the scene has
+    MissingEntity(u32),
+    /// A component being loaded has a value its type doesn't know about, such as an unknown enum variant
+    InvalidValue(&'static str),
+    /// A prefab has some other number of entities without a parent than the single root it needs
+    PrefabRoots(PathBuf, usize),
+    /// A prefab refers to another prefab that refers back to it
+    PrefabCycle(PathBuf, PathBuf),
+}
+
+impl Display for SceneError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            SceneError::DuplicateEntity(id) => write!(f, "Scene has more than one entity with ID {}", id),
+            SceneError::UnsavedEntity(entity) => {
+                write!(f, "Entity {:?} is referred to, but has nothing that can be saved, so it isn't in the scene", entity)
+            }
+            SceneError::MissingEntity(id) => write!(f, "Scene entity {} is referred to, but isn't in the scene", id),
+            SceneError::InvalidValue(what) => write!(f, "Scene has an invalid {}", what),
+            SceneError::PrefabRoots(ref path, roots) => {
+                write!(f, "Prefab {} has {} entities without a parent, but needs exactly one root", path.display(), roots)
+            }
+            SceneError::PrefabCycle(ref path, ref reference) => {
+                write!(f, "Prefab {} refers to {}, which refers back to it", path.display(), reference.display())
+            }
+        }
+    }
+}
+
+impl Error for SceneError {
+    fn description(&self) -> &str {
+        match *self {
+            SceneError::DuplicateEntity(_) => "Duplicate scene entity",
+            SceneError::UnsavedEntity(_) => "Reference to an unsaved entity",
+            SceneError::MissingEntity(_) => "Reference to a missing scene entity",
+            SceneError::InvalidValue(_) => "Invalid value in scene",
+            SceneError::PrefabRoots(_, _) => "Prefab without a single root",
+            SceneError::PrefabCycle(_, _) => "Prefab refers back to itself",
+        }
+    }
+}
+
+/// Scene ID component, given to entities loaded from a scene so saving the world again gives them the same IDs.
+///
+/// Entities without one are given a new ID when saved.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SceneId(pub u32);
+
+impl specs::Component for SceneId {
+    type Storage = specs::VecStorage<SceneId>;
+}
+
+type HasFn = Box<Fn(&specs::World, specs::Entity) -> bool + Send + Sync>;
+type SaveFn<E> = Box<for<'a> Fn(&specs::World, specs::Entity, &SaveContext, any_pointer::Builder<'a>) -> Result<(), E> + Send + Sync>;
+type LoadFn<E> = Box<for<'a> Fn(&mut specs::World, specs::Entity, &LoadContext, any_pointer::Reader<'a>) -> Result<(), E> + Send + Sync>;
+type RemoveFn = Box<Fn(&mut specs::World, specs::Entity) + Send + Sync>;
+
+struct Registration<E> {
+    name: String,
+    has: HasFn,
+    save: SaveFn<E>,
+    load: LoadFn<E>,
+    remove: RemoveFn,
+}
+
+/// Scene IDs of every entity being saved, for components that refer to other entities
+pub struct SaveContext {
+    ids: HashMap<specs::Entity, u32>,
+}
+
+impl SaveContext {
+    pub fn new(ids: HashMap<specs::Entity, u32>) -> SaveContext {
+        SaveContext { ids: ids }
+    }
+
+    /// Scene ID of `entity`, or `None` if it isn't being saved
+    #[inline]
+    pub fn id(&self, entity: specs::Entity) -> Option<u32> {
+        self.ids.get(&entity).cloned()
+    }
+}
+
+/// Entities created for every scene ID being loaded, for components that refer to other entities
+pub struct LoadContext {
+    entities: HashMap<u32, specs::Entity>,
+}
+
+impl LoadContext {
+    pub fn new(entities: HashMap<u32, specs::Entity>) -> LoadContext {
+        LoadContext { entities: entities }
+    }
+
+    /// Entity created for the scene ID, or `None` if there's no such entity in the scene
+    #[inline]
+    pub fn entity(&self, id: u32) -> Option<specs::Entity> {
+        self.entities.get(&id).cloned()
+    }
+}
+
+/// Serialize and deserialize functions for every component type that can be saved, by name
+pub struct ComponentRegistry<E> {
+    components: Vec<Registration<E>>,
+}
+
+impl<E> Default for ComponentRegistry<E> {
+    #[inline(always)]
+    fn default() -> ComponentRegistry<E> { ComponentRegistry::new() }
+}
+
+impl<E> ComponentRegistry<E> {
+    /// Creates a registry without any components
+    pub fn new() -> ComponentRegistry<E> {
+        ComponentRegistry { components: Vec::new() }
+    }
+
+    /// Registers a component type under `name`, replacing any registered under the same name before.
+    ///
+    /// `save` writes the component's data, usually by calling `init_as` on the builder with a struct from its protocol,
+    /// and `load` reads it back with `get_as`.
+    pub fn register<C, S, L>(&mut self, name: &str, save: S, load: L) -> &mut ComponentRegistry<E>
+        where C: specs::Component,
+              S: for<'a> Fn(&C, &SaveContext, any_pointer::Builder<'a>) -> Result<(), E> + Send + Sync + 'static,
+              L: for<'a> Fn(any_pointer::Reader<'a>, &LoadContext) -> Result<C, E> + Send + Sync + 'static {
+        let registration = Registration {
+            name: name.to_string(),
+            has: Box::new(|world, entity| world.read::<C>().get(entity).is_some()),
+            save: Box::new(move |world, entity, context, builder| {
+                match world.read::<C>().get(entity) {
+                    Some(component) => save(component, context, builder),
+                    None => Ok(()),
+                }
+            }),
+            load: Box::new(move |world, entity, context, reader| {
+                let component = try!(load(reader, context));
+
+                world.write::<C>().insert(entity, component);
+
+                Ok(())
+            }),
+            remove: Box::new(|world, entity| { world.write::<C>().remove(entity); }),
+        };
+
+        match self.components.iter().position(|existing| existing.name == name) {
+            Some(index) => self.components[index] = registration,
+            None => self.components.push(registration),
+        }
+
+        self
+    }
+
+    /// Checks if a component type was registered under `name`
+    pub fn contains(&self, name: &str) -> bool {
+        self.components.iter().any(|registration| registration.name == name)
+    }
+
+    /// Checks if `entity` has the component registered under `name`
+    pub fn has_component(&self, name: &str, world: &specs::World, entity: specs::Entity) -> bool {
+        self.get(name).map_or(false, |registration| (registration.has)(world, entity))
+    }
+
+    /// Writes the component registered under `name` of `entity`, if it has one.
+    ///
+    /// Writes nothing for a component nobody registered.
+    pub fn save_component(&self, name: &str, world: &specs::World, entity: specs::Entity, context: &SaveContext,
+                          builder: any_pointer::Builder) -> Result<(), E> {
+        match self.get(name) {
+            Some(registration) => (registration.save)(world, entity, context, builder),
+            None => Ok(()),
+        }
+    }
+
+    /// Reads the component registered under `name` and gives it to `entity`, replacing any it already has.
+    ///
+    /// Does nothing for a component nobody registered.
+    pub fn load_component(&self, name: &str, world: &mut specs::World, entity: specs::Entity, context: &LoadContext,
+                          reader: any_pointer::Reader) -> Result<(), E> {
+        match self.get(name) {
+            Some(registration) => (registration.load)(world, entity, context, reader),
+            None => Ok(()),
+        }
+    }
+
+    /// Removes the component registered under `name` from `entity`
+    pub fn remove_component(&self, name: &str, world: &mut specs::World, entity: specs::Entity) {
+        if let Some(registration) = self.get(name) {
+            (registration.remove)(world, entity);
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&Registration<E>> {
+        self.components.iter().find(|registration| registration.name == name)
+    }
+}
+
+/// Writes every entity with at least one registered component into the entity list of `scene`
+pub fn save_entities<E>(world: &specs::World, registry: &ComponentRegistry<E>, scene: protocol::scene::Builder) -> Result<(), E>
+    where E: From<capnp::Error> {
+    let mut saved: Vec<(specs::Entity, Vec<&Registration<E>>)> = Vec::new();
+
+    for entity in world.entities().iter() {
+        let registered: Vec<_> = registry.components.iter().filter(|registration| (registration.has)(world, entity)).collect();
+
+        if !registered.is_empty() {
+            saved.push((entity, registered));
+        }
+    }
+
+    //Entities loaded from a scene keep their IDs, and the rest are numbered after the highest of those
+    let mut context = SaveContext { ids: HashMap::new() };
+
+    {
+        let scene_ids = world.read::<SceneId>();
+
+        let mut next_id = saved.iter().filter_map(|&(entity, _)| scene_ids.get(entity)).map(|id| id.0 + 1).max().unwrap_or(0);
+
+        for &(entity, _) in &saved {
+            let id = match scene_ids.get(entity) {
+                Some(id) if !context.ids.values().any(|existing| *existing == id.0) => id.0,
+                _ => {
+                    next_id += 1;
+                    next_id - 1
+                }
+            };
+
+            context.ids.insert(entity, id);
+        }
+    }
+
+    let mut entities_builder = scene.init_entities(saved.len() as u32);
+
+    for (i, &(entity, ref registered)) in saved.iter().enumerate() {
+        let mut entity_builder = entities_builder.borrow().get(i as u32);
+
+        entity_builder.set_id(context.ids[&entity]);
+
+        let mut components_builder = entity_builder.init_components(registered.len() as u32);
+
+        for (j, registration) in registered.iter().enumerate() {
+            let mut component_builder = components_builder.borrow().get(j as u32);
+
+            component_builder.set_name(&registration.name);
+
+            try!((registration.save)(world, entity, &context, component_builder.init_data()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates an entity for every one in `entities_reader`, then loads their components.
+///
+/// `scene_id` gives each entity its scene ID from the one it was saved with, and components nobody registered
+/// are passed to `unregistered` along with their entity. Returns the created entities in the order they were saved.
+pub fn load_entities<'a, E, I, U>(world: &mut specs::World, registry: &ComponentRegistry<E>,
+                                  entities_reader: capnp::struct_list::Reader<'a, protocol::entity::Owned>,
+                                  mut scene_id: I, mut unregistered: U) -> Result<Vec<specs::Entity>, E>
+    where E: From<capnp::Error> + From<SceneError>,
+          I: FnMut(u32) -> u32,
+          U: FnMut(&mut specs::World, specs::Entity, &str, any_pointer::Reader<'a>) -> Result<(), E> {
+    //Every entity is created up front, so components can refer to entities that come later
+    let mut context = LoadContext { entities: HashMap::new() };
+    let mut created = Vec::with_capacity(entities_reader.len() as usize);
+
+    for entity_reader in entities_reader.iter() {
+        let id = entity_reader.get_id();
+
+        if context.entities.contains_key(&id) {
+            //Nothing has been loaded into the entities yet, so they're only created and deleted again
+            for entity in created {
+                world.delete_now(entity);
+            }
+
+            return Err(SceneError::DuplicateEntity(id).into());
+        }
+
+        let entity = world.create_now().with(SceneId(scene_id(id))).build();
+
+        context.entities.insert(id, entity);
+        created.push(entity);
+    }
+
+    for (entity_reader, &entity) in entities_reader.iter().zip(created.iter()) {
+        for component_reader in try!(entity_reader.get_components()).iter() {
+            let name = try!(component_reader.get_name());
+
+            match registry.get(name) {
+                Some(registration) => try!((registration.load)(world, entity, &context, component_reader.get_data())),
+                None => try!(unregistered(world, entity, name, component_reader.get_data())),
+            }
+        }
+    }
+
+    Ok(created)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Cursor;
+
+    #[derive(Debug)]
+    enum TestError {
+        Capnp(capnp::Error),
+        Scene(SceneError),
+        MissingEntity,
+    }
+
+    impl From<capnp::Error> for TestError {
+        fn from(err: capnp::Error) -> TestError { TestError::Capnp(err) }
+    }
+
+    impl From<SceneError> for TestError {
+        fn from(err: SceneError) -> TestError { TestError::Scene(err) }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Label(String);
+
+    impl specs::Component for Label {
+        type Storage = specs::VecStorage<Label>;
+    }
+
+    /// Refers to another entity, saved by its scene ID
+    #[derive(Clone, Debug, PartialEq)]
+    struct Link(specs::Entity);
+
+    impl specs::Component for Link {
+        type Storage = specs::VecStorage<Link>;
+    }
+
+    fn new_world() -> specs::World {
+        let mut world = specs::World::new();
+
+        world.register::<SceneId>();
+        world.register::<Label>();
+        world.register::<Link>();
+
+        world
+    }
+
+    fn label_registry() -> ComponentRegistry<TestError> {
+        let mut registry = ComponentRegistry::new();
+
+        registry.register("label", |label: &Label, _: &SaveContext, builder: any_pointer::Builder| {
+            builder.init_as::<protocol::name::Builder>().set_name(&label.0);
+
+            Ok(())
+        }, |reader: any_pointer::Reader, _: &LoadContext| {
+            Ok(Label(try!(try!(reader.get_as::<protocol::name::Reader>()).get_name()).to_string()))
+        });
+
+        registry
+    }
+
+    fn registry() -> ComponentRegistry<TestError> {
+        let mut registry = label_registry();
+
+        registry.register("link", |link: &Link, context: &SaveContext, builder: any_pointer::Builder| {
+            let id = try!(context.id(link.0).ok_or(TestError::MissingEntity));
+
+            builder.init_as::<protocol::parent::Builder>().set_entity(id);
+
+            Ok(())
+        }, |reader: any_pointer::Reader, context: &LoadContext| {
+            let id = try!(reader.get_as::<protocol::parent::Reader>()).get_entity();
+
+            context.entity(id).map(Link).ok_or(TestError::MissingEntity)
+        });
+
+        registry
+    }
+
+    fn save(world: &specs::World, registry: &ComponentRegistry<TestError>) -> Vec<u8> {
+        let mut message = capnp::message::Builder::new_default();
+
+        save_entities(world, registry, message.init_root::<protocol::root_scene::Builder>().init_scene()).unwrap();
+
+        let mut buffer = Vec::new();
+
+        capnp::serialize_packed::write_message(&mut buffer, &message).unwrap();
+
+        buffer
+    }
+
+    /// Loads every entity, keeping their scene IDs and collecting the names of unregistered components
+    fn load(world: &mut specs::World, registry: &ComponentRegistry<TestError>, buffer: &[u8])
+            -> Result<(Vec<specs::Entity>, Vec<String>), TestError> {
+        let message = try!(capnp::serialize_packed::read_message(&mut Cursor::new(buffer), capnp::message::ReaderOptions::new()));
+
+        let scene = try!(try!(message.get_root::<protocol::root_scene::Reader>()).get_scene());
+
+        let mut skipped = Vec::new();
+
+        let created = try!(load_entities(world, registry, try!(scene.get_entities()), |id| id, |_, _, name, _| {
+            skipped.push(name.to_string());
+
+            Ok(())
+        }));
+
+        Ok((created, skipped))
+    }
+
+    fn find(world: &specs::World, label: &str) -> specs::Entity {
+        let labels = world.read::<Label>();
+
+        (&labels, &world.entities()).iter().find(|&(ref entity_label, _)| entity_label.0 == label).map(|(_, entity)| entity).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut world = new_world();
+
+        let a = world.create_now().with(Label("a".to_string())).build();
+
+        world.create_now().with(Label("b".to_string())).with(Link(a)).build();
+
+        //Entities without any registered component aren't saved
+        world.create_now().build();
+
+        let registry = registry();
+
+        let mut loaded = new_world();
+
+        let (entities, skipped) = load(&mut loaded, &registry, &save(&world, &registry)).unwrap();
+
+        assert_eq!(entities.len(), 2);
+        assert!(skipped.is_empty());
+
+        //The link is remapped to the newly loaded entity
+        let (a, b) = (find(&loaded, "a"), find(&loaded, "b"));
+
+        assert_eq!(loaded.read::<Link>().get(b), Some(&Link(a)));
+        assert!(loaded.read::<Link>().get(a).is_none());
+    }
+
+    #[test]
+    fn test_ids_are_stable() {
+        let mut world = new_world();
+
+        world.create_now().with(Label("a".to_string())).build();
+        world.create_now().with(Label("b".to_string())).build();
+
+        let registry = registry();
+
+        let mut loaded = new_world();
+
+        load(&mut loaded, &registry, &save(&world, &registry)).unwrap();
+
+        //New entities are numbered after the loaded ones
+        loaded.create_now().with(Label("c".to_string())).build();
+
+        let mut reloaded = new_world();
+
+        load(&mut reloaded, &registry, &save(&loaded, &registry)).unwrap();
+
+        let ids = |world: &specs::World| {
+            let scene_ids = world.read::<SceneId>();
+
+            ["a", "b", "c"].iter().map(|label| scene_ids.get(find(world, label)).map(|id| id.0)).collect::<Vec<_>>()
+        };
+
+        let (loaded_ids, reloaded_ids) = (ids(&loaded), ids(&reloaded));
+
+        assert_eq!(loaded_ids[..2], reloaded_ids[..2]);
+        assert!(loaded_ids[0] != loaded_ids[1]);
+
+        //The entity created after loading is saved with an ID after the others
+        assert_eq!(loaded_ids[2], None);
+        assert!(reloaded_ids[2] > loaded_ids[0].max(loaded_ids[1]));
+    }
+
+    #[test]
+    fn test_unknown_components_are_passed_on() {
+        let mut world = new_world();
+
+        let a = world.create_now().with(Label("a".to_string())).build();
+
+        world.create_now().with(Label("b".to_string())).with(Link(a)).build();
+
+        let buffer = save(&world, &registry());
+
+        //A registry that only knows about labels, like a tool without the components a game added
+        let labels_only = label_registry();
+
+        let mut loaded = new_world();
+
+        let (entities, skipped) = load(&mut loaded, &labels_only, &buffer).unwrap();
+
+        assert!(!labels_only.contains("link"));
+        assert_eq!(entities.len(), 2);
+        assert_eq!(skipped, vec!["link".to_string()]);
+        assert!((&loaded.read::<Link>()).iter().next().is_none());
+    }
+
+    #[test]
+    fn test_duplicate_ids_are_rejected() {
+        let mut message = capnp::message::Builder::new_default();
+
+        {
+            let scene = message.init_root::<protocol::root_scene::Builder>().init_scene();
+            let mut entities_builder = scene.init_entities(2);
+
+            for i in 0..2 {
+                let mut entity_builder = entities_builder.borrow().get(i);
+
+                entity_builder.set_id(7);
+                entity_builder.init_components(0);
+            }
+        }
+
+        let mut buffer = Vec::new();
+
+        capnp::serialize_packed::write_message(&mut buffer, &message).unwrap();
+
+        let mut world = new_world();
+
+        match load(&mut world, &registry(), &buffer) {
+            Err(TestError::Scene(SceneError::DuplicateEntity(7))) => {}
+            result => panic!("Expected a duplicate entity error, got {:?}", result),
+        }
+
+        assert_eq!((&world.entities()).iter().count(), 0);
+    }
+}
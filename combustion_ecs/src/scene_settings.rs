@@ -0,0 +1,27 @@
+//! The SceneSettings resource holds rendering settings for the whole scene, saved and loaded along with its entities
+
+use std::path::PathBuf;
+
+use nalgebra::Vector3;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Resource {
+    /// Skybox texture, or `None` to clear to the clear color instead
+    pub skybox: Option<PathBuf>,
+    /// Linear RGB ambient light color
+    pub ambient: Vector3<f32>,
+}
+
+impl Default for Resource {
+    #[inline(always)]
+    fn default() -> Resource { Resource::new() }
+}
+
+impl Resource {
+    pub fn new() -> Resource {
+        Resource {
+            skybox: None,
+            ambient: Vector3::new(0.03, 0.03, 0.03),
+        }
+    }
+}
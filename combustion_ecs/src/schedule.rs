@@ -0,0 +1,487 @@
+//! Explicit system scheduling, from the data each system uses and the systems it has to follow
+//!
+//! Every system added to a `Schedule` declares the components and resources it reads and writes, along with any
+//! systems it has to run after or before. Building the schedule sorts the systems so every constraint is met and adds
+//! them to the planner with priorities in that order.
+//!
+//! The planner starts systems in priority order on its thread pool, and a system waits for the locks of any data an
+//! earlier system is still using. So systems sharing data run in schedule order, while the rest run in parallel.
+//! An ordering constraint between systems that share no data only orders when they start.
+//!
+//! Two systems writing the same data have to be ordered one way or the other, or the schedule fails to build, so
+//! which writes first never depends on the order they happened to be added in.
+
+use std::any::{Any, TypeId};
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::intrinsics;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use specs;
+
+pub type ScheduleResult<T> = Result<T, ScheduleError>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleError {
+    /// Two systems were added with the same name
+    DuplicateSystem(&'static str),
+    /// A system has to run after or before one that was never added
+    UnknownSystem { system: &'static str, constraint: &'static str },
+    /// Systems that have to run after each other in a loop, each running before the next and the last before the first
+    Cycle(Vec<&'static str>),
+    /// Two systems write the same data, but neither has to run before the other
+    ConflictingWrites { first: &'static str, second: &'static str, data: &'static str },
+}
+
+impl Display for ScheduleError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            ScheduleError::DuplicateSystem(name) => write!(f, "More than one system is named {}", name),
+            ScheduleError::UnknownSystem { system, constraint } => {
+                write!(f, "{} is ordered against {}, which isn't in the schedule", system, constraint)
+            }
+            ScheduleError::Cycle(ref systems) => {
+                write!(f, "Systems have to run before themselves: {} -> {}", systems.join(" -> "), systems[0])
+            }
+            ScheduleError::ConflictingWrites { first, second, data } => {
+                write!(f, "{} and {} both write {}, but neither is ordered before the other", first, second, data)
+            }
+        }
+    }
+}
+
+impl Error for ScheduleError {
+    fn description(&self) -> &str {
+        match *self {
+            ScheduleError::DuplicateSystem(_) => "Duplicate system name",
+            ScheduleError::UnknownSystem { .. } => "Ordered against an unknown system",
+            ScheduleError::Cycle(_) => "Cyclic system dependencies",
+            ScheduleError::ConflictingWrites { .. } => "Unordered systems write the same data",
+        }
+    }
+}
+
+/// A component storage or resource a system uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Data {
+    id: TypeId,
+    resource: bool,
+    name: &'static str,
+}
+
+impl Data {
+    fn of<T: Any>(resource: bool) -> Data {
+        Data { id: TypeId::of::<T>(), resource: resource, name: type_name::<T>() }
+    }
+}
+
+/// Full path of the type `T`, which names the data two conflicting systems both write
+#[inline]
+fn type_name<T>() -> &'static str {
+    //Only reads the name the compiler gave the type, so there is nothing unsafe about it
+    unsafe { intrinsics::type_name::<T>() }
+}
+
+/// A system in a schedule, along with what it uses and what it has to follow
+///
+/// Data is told apart by type, and named only to say which data two conflicting systems both write.
+pub struct ScheduledSystem<C> {
+    name: &'static str,
+    system: Box<specs::System<C> + Send>,
+    reads: Vec<Data>,
+    writes: Vec<Data>,
+    after: Vec<&'static str>,
+    before: Vec<&'static str>,
+}
+
+impl<C> ScheduledSystem<C> {
+    #[inline]
+    pub fn name(&self) -> &'static str { self.name }
+
+    /// Declares that the system reads components of type `T`
+    pub fn reads<T: Any>(&mut self) -> &mut ScheduledSystem<C> {
+        self.reads.push(Data::of::<T>(false));
+        self
+    }
+
+    /// Declares that the system writes components of type `T`
+    pub fn writes<T: Any>(&mut self) -> &mut ScheduledSystem<C> {
+        self.writes.push(Data::of::<T>(false));
+        self
+    }
+
+    /// Declares that the system reads the resource `T`
+    pub fn reads_resource<T: Any>(&mut self) -> &mut ScheduledSystem<C> {
+        self.reads.push(Data::of::<T>(true));
+        self
+    }
+
+    /// Declares that the system writes the resource `T`
+    pub fn writes_resource<T: Any>(&mut self) -> &mut ScheduledSystem<C> {
+        self.writes.push(Data::of::<T>(true));
+        self
+    }
+
+    /// Runs the system after the named one
+    pub fn after(&mut self, name: &'static str) -> &mut ScheduledSystem<C> {
+        self.after.push(name);
+        self
+    }
+
+    /// Runs the system before the named one, for systems added later than the one they have to precede
+    pub fn before(&mut self, name: &'static str) -> &mut ScheduledSystem<C> {
+        self.before.push(name);
+        self
+    }
+}
+
+/// How long a system took the last time it ran, in milliseconds, including any time spent waiting for its data
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SystemTiming {
+    pub name: &'static str,
+    pub ms: f32,
+}
+
+/// Shared timings of every system in a built schedule, in schedule order
+#[derive(Debug, Clone, Default)]
+pub struct SystemTimings(Arc<Mutex<Vec<SystemTiming>>>);
+
+impl SystemTimings {
+    /// Copies the latest timings of every system
+    pub fn get(&self) -> Vec<SystemTiming> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// Total time of every system, which is more than the update took when some of them ran in parallel
+    pub fn total_ms(&self) -> f32 {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).iter().map(|timing| timing.ms).sum()
+    }
+
+    fn record(&self, slot: usize, ms: f32) {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())[slot].ms = ms;
+    }
+}
+
+/// Runs a scheduled system and records how long it took
+struct Timed<C> {
+    system: Box<specs::System<C> + Send>,
+    slot: usize,
+    timings: SystemTimings,
+}
+
+impl<C> specs::System<C> for Timed<C> {
+    fn run(&mut self, arg: specs::RunArg, delta: C) {
+        let start = Instant::now();
+
+        self.system.run(arg, delta);
+
+        let elapsed = start.elapsed();
+
+        self.timings.record(self.slot, elapsed.as_secs() as f32 * 1000.0 + elapsed.subsec_nanos() as f32 / 1_000_000.0);
+    }
+}
+
+/// Systems run with the context `C` passed to `specs::Planner::dispatch`, usually the frame delta
+pub struct Schedule<C> {
+    systems: Vec<ScheduledSystem<C>>,
+}
+
+impl<C> Default for Schedule<C> {
+    fn default() -> Schedule<C> {
+        Schedule { systems: Vec::new() }
+    }
+}
+
+impl<C: 'static> Schedule<C> {
+    pub fn new() -> Schedule<C> {
+        Schedule::default()
+    }
+
+    /// Adds a system, which then declares its data and ordering on the returned entry
+    pub fn add<S>(&mut self, system: S, name: &'static str) -> &mut ScheduledSystem<C> where S: specs::System<C> + Send + 'static {
+        self.systems.push(ScheduledSystem {
+            name: name,
+            system: Box::new(system),
+            reads: Vec::new(),
+            writes: Vec::new(),
+            after: Vec::new(),
+            before: Vec::new(),
+        });
+
+        self.systems.last_mut().unwrap()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize { self.systems.len() }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.systems.is_empty() }
+
+    /// Names of every system in the order they'll start, or why they can't be ordered
+    pub fn order(&self) -> ScheduleResult<Vec<&'static str>> {
+        Ok(try!(self.sort()).into_iter().map(|index| self.systems[index].name).collect())
+    }
+
+    /// Checks the schedule and adds every system to the planner, returning the timings they'll record as they run
+    pub fn build(self, planner: &mut specs::Planner<C>) -> ScheduleResult<SystemTimings> {
+        let order = try!(self.sort());
+
+        let timings = SystemTimings(Arc::new(Mutex::new(order.iter().map(|&index| {
+            SystemTiming { name: self.systems[index].name, ms: 0.0 }
+        }).collect())));
+
+        let count = order.len();
+
+        let mut systems: Vec<Option<ScheduledSystem<C>>> = self.systems.into_iter().map(Some).collect();
+
+        for (slot, index) in order.into_iter().enumerate() {
+            let system = systems[index].take().unwrap();
+
+            // Higher priorities start first
+            planner.add_system(Timed { system: system.system, slot: slot, timings: timings.clone() },
+                               system.name, (count - slot) as specs::Priority);
+        }
+
+        Ok(timings)
+    }
+
+    /// Every system's index in the order they'll start
+    fn sort(&self) -> ScheduleResult<Vec<usize>> {
+        let count = self.systems.len();
+
+        let mut indices = HashMap::with_capacity(count);
+
+        for (index, system) in self.systems.iter().enumerate() {
+            if indices.insert(system.name, index).is_some() {
+                return Err(ScheduleError::DuplicateSystem(system.name));
+            }
+        }
+
+        // Systems that have to run after each system
+        let mut successors = vec![Vec::new(); count];
+
+        for (index, system) in self.systems.iter().enumerate() {
+            for &name in &system.after {
+                match indices.get(name) {
+                    Some(&earlier) => successors[earlier].push(index),
+                    None => return Err(ScheduleError::UnknownSystem { system: system.name, constraint: name }),
+                }
+            }
+
+            for &name in &system.before {
+                match indices.get(name) {
+                    Some(&later) => successors[index].push(later),
+                    None => return Err(ScheduleError::UnknownSystem { system: system.name, constraint: name }),
+                }
+            }
+        }
+
+        let mut remaining = vec![0; count];
+
+        for &successor in successors.iter().flat_map(|successors| successors.iter()) {
+            remaining[successor] += 1;
+        }
+
+        // Of the systems free to go next, the one added first goes, so unconstrained systems keep the order they were added in
+        let mut ready: BTreeSet<usize> = (0..count).filter(|&index| remaining[index] == 0).collect();
+        let mut order = Vec::with_capacity(count);
+
+        loop {
+            let index = match ready.iter().next() {
+                Some(&index) => index,
+                None => break,
+            };
+
+            ready.remove(&index);
+            order.push(index);
+
+            for &successor in &successors[index] {
+                remaining[successor] -= 1;
+
+                if remaining[successor] == 0 {
+                    ready.insert(successor);
+                }
+            }
+        }
+
+        if order.len() < count {
+            return Err(ScheduleError::Cycle(self.find_cycle(&successors, &remaining)));
+        }
+
+        try!(self.check_writes(&order, &successors));
+
+        Ok(order)
+    }
+
+    /// Names a loop among the systems left unsorted, which all have at least one unsorted system before them
+    fn find_cycle(&self, successors: &[Vec<usize>], remaining: &[usize]) -> Vec<&'static str> {
+        let mut predecessor = vec![None; successors.len()];
+
+        for (index, successors) in successors.iter().enumerate() {
+            for &successor in successors {
+                if remaining[index] > 0 && remaining[successor] > 0 {
+                    predecessor[successor] = Some(index);
+                }
+            }
+        }
+
+        // Walking backwards from any unsorted system has to come back around to somewhere it has already been
+        let mut path = Vec::new();
+        let mut current = remaining.iter().position(|&count| count > 0).unwrap();
+
+        while !path.contains(&current) {
+            path.push(current);
+            current = predecessor[current].unwrap();
+        }
+
+        let start = path.iter().position(|&index| index == current).unwrap();
+
+        path[start..].iter().rev().map(|&index| self.systems[index].name).collect()
+    }
+
+    fn check_writes(&self, order: &[usize], successors: &[Vec<usize>]) -> ScheduleResult<()> {
+        let count = self.systems.len();
+
+        // Whether each system is ordered before every other, directly or through others, built from the end of the order back
+        let mut reaches = vec![vec![false; count]; count];
+
+        for &index in order.iter().rev() {
+            for &successor in &successors[index] {
+                reaches[index][successor] = true;
+
+                for later in 0..count {
+                    if reaches[successor][later] {
+                        reaches[index][later] = true;
+                    }
+                }
+            }
+        }
+
+        for (a, first) in order.iter().map(|&index| (index, &self.systems[index])) {
+            for (b, second) in order.iter().map(|&index| (index, &self.systems[index])) {
+                if a >= b || reaches[a][b] || reaches[b][a] {
+                    continue;
+                }
+
+                if let Some(data) = first.writes.iter().find(|data| second.writes.contains(data)) {
+                    return Err(ScheduleError::ConflictingWrites { first: first.name, second: second.name, data: data.name });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Default)]
+    struct Log(Vec<&'static str>);
+
+    #[derive(Default)]
+    struct Other;
+
+    struct Logger(&'static str);
+
+    impl specs::System<::Delta> for Logger {
+        fn run(&mut self, arg: specs::RunArg, _: ::Delta) {
+            let mut log = arg.fetch(|world| world.write_resource::<Log>());
+
+            log.0.push(self.0);
+        }
+    }
+
+    fn new_planner() -> ::Planner {
+        let mut world = specs::World::new();
+
+        world.add_resource(Log::default());
+
+        specs::Planner::new(world, 4)
+    }
+
+    #[test]
+    fn test_ordering() {
+        let mut schedule: Schedule<::Delta> = Schedule::new();
+
+        schedule.add(Logger("submit"), "submit").writes_resource::<Log>().after("cull");
+        schedule.add(Logger("cull"), "cull").writes_resource::<Log>().after("bounds");
+        schedule.add(Logger("transform"), "transform").writes_resource::<Log>();
+        schedule.add(Logger("bounds"), "bounds").writes_resource::<Log>().after("transform");
+        schedule.add(Logger("input"), "input").writes_resource::<Log>().before("transform");
+
+        assert_eq!(schedule.order().unwrap(), vec!["input", "transform", "bounds", "cull", "submit"]);
+
+        let mut planner = new_planner();
+
+        let timings = schedule.build(&mut planner).unwrap();
+
+        planner.dispatch(0.0);
+        planner.wait();
+
+        assert_eq!(planner.mut_world().read_resource::<Log>().0, vec!["input", "transform", "bounds", "cull", "submit"]);
+
+        assert_eq!(timings.get().iter().map(|timing| timing.name).collect::<Vec<_>>(), vec!["input", "transform", "bounds", "cull", "submit"]);
+        assert!(timings.get().iter().all(|timing| timing.ms >= 0.0));
+    }
+
+    #[test]
+    fn test_cycle_fails_to_build() {
+        let mut schedule: Schedule<::Delta> = Schedule::new();
+
+        schedule.add(Logger("a"), "a").after("c");
+        schedule.add(Logger("b"), "b").after("a");
+        schedule.add(Logger("c"), "c").after("b");
+        schedule.add(Logger("d"), "d");
+
+        let mut planner = new_planner();
+
+        match schedule.build(&mut planner) {
+            Err(ScheduleError::Cycle(systems)) => {
+                assert_eq!(systems.len(), 3);
+
+                // Each system runs after the one before it, and the first after the last
+                for (i, name) in systems.iter().enumerate() {
+                    let next = systems[(i + 1) % systems.len()];
+
+                    assert!(match (*name, next) { ("a", "b") | ("b", "c") | ("c", "a") => true, _ => false }, "{:?}", systems);
+                }
+            }
+            result => panic!("Expected a cycle, got {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_invalid_schedules() {
+        let mut schedule: Schedule<::Delta> = Schedule::new();
+
+        schedule.add(Logger("a"), "a").writes_resource::<Log>().reads_resource::<Other>();
+        schedule.add(Logger("b"), "b").writes_resource::<Log>();
+
+        assert_eq!(schedule.order(), Err(ScheduleError::ConflictingWrites { first: "a", second: "b", data: type_name::<Log>() }));
+
+        // Ordering them through a third system is enough
+        schedule.add(Logger("c"), "c").reads_resource::<Other>().after("a").before("b");
+
+        assert_eq!(schedule.order(), Ok(vec!["a", "c", "b"]));
+
+        // Reading what another system writes needs no ordering, and neither does writing components of the same type as a resource
+        schedule.add(Logger("d"), "d").reads_resource::<Log>().writes::<Log>().writes_resource::<Other>();
+
+        assert!(schedule.order().is_ok());
+
+        schedule.add(Logger("e"), "e").after("f");
+
+        assert_eq!(schedule.order(), Err(ScheduleError::UnknownSystem { system: "e", constraint: "f" }));
+
+        let mut schedule: Schedule<::Delta> = Schedule::new();
+
+        schedule.add(Logger("a"), "a");
+        schedule.add(Logger("a"), "a");
+
+        assert_eq!(schedule.order(), Err(ScheduleError::DuplicateSystem("a")));
+    }
+}
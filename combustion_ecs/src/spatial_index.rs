@@ -0,0 +1,628 @@
+//! The SpatialIndex resource is a loose octree over world bounds, for finding what's near a box, sphere or ray
+//! without testing every entity
+//!
+//! The spatial index system keeps it up to date from the changes to world bounds, so it's as current as the world
+//! bounds were when the system last ran.
+//!
+//! Each entity is kept in the deepest node whose loose bounds, twice the size of the node's cell, contain its box,
+//! found from the center and size of the box alone. Boxes too big for any child, or outside the root cell, stay in the
+//! root, which is always searched. Nodes split once they hold more than `SPLIT_THRESHOLD` entities, but never below
+//! `MAX_DEPTH`, so piles of entities in one place and huge boxes can't make the tree any deeper.
+//!
+//! Nodes are never merged as entities move out of them. `rebuild` starts again from scratch, fitting the root cell to
+//! the boxes given and dropping empty nodes.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use nalgebra::{Point3, Vector3, Norm};
+
+use specs;
+
+pub use ::bounds::Bounds;
+
+/// Depth of the deepest nodes, with the root at zero
+pub const MAX_DEPTH: usize = 12;
+
+/// Number of entities a node can hold before it's split
+pub const SPLIT_THRESHOLD: usize = 8;
+
+/// Half the size of the root cell before the index is first rebuilt
+pub const DEFAULT_HALF_SIZE: f32 = 512.0;
+
+/// An entity found by a query
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub entity: specs::Entity,
+    /// Distance from the center of a box or sphere query to the nearest point of the entity's box,
+    /// or along a ray to where it enters the box. Zero if the query starts inside the box.
+    pub distance: f32,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    center: Point3<f32>,
+    /// Half the size of the node's cell. The loose bounds reach twice as far.
+    half: f32,
+    depth: usize,
+    /// Index of the first of the node's eight children, which are always created together
+    children: Option<usize>,
+    /// IDs of the entities kept in this node
+    entities: Vec<specs::Index>,
+}
+
+impl Node {
+    fn new(center: Point3<f32>, half: f32, depth: usize) -> Node {
+        Node { center: center, half: half, depth: depth, children: None, entities: Vec::new() }
+    }
+
+    fn loose(&self) -> Bounds {
+        let (c, h) = (self.center, self.half * 2.0);
+
+        Bounds {
+            min: Point3::new(c.x - h, c.y - h, c.z - h),
+            max: Point3::new(c.x + h, c.y + h, c.z + h),
+        }
+    }
+
+    /// Checks if a box with the given center and extent is inside the node's loose bounds
+    fn fits(&self, center: &Point3<f32>, extent: f32) -> bool {
+        extent <= self.half && self.contains(center)
+    }
+
+    /// Checks if a point is inside the node's cell
+    fn contains(&self, point: &Point3<f32>) -> bool {
+        (point.x - self.center.x).abs() <= self.half &&
+            (point.y - self.center.y).abs() <= self.half &&
+            (point.z - self.center.z).abs() <= self.half
+    }
+
+    /// Which child's cell a point is in
+    fn octant(&self, point: &Point3<f32>) -> usize {
+        (if point.x >= self.center.x { 1 } else { 0 }) |
+            (if point.y >= self.center.y { 2 } else { 0 }) |
+            (if point.z >= self.center.z { 4 } else { 0 })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    entity: specs::Entity,
+    bounds: Bounds,
+    node: usize,
+    /// Whether the center of the box is outside the root cell
+    outside: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Resource {
+    nodes: Vec<Node>,
+    /// Entries by entity ID
+    entries: HashMap<specs::Index, Entry>,
+    outside: usize,
+    stale: bool,
+}
+
+impl Default for Resource {
+    #[inline(always)]
+    fn default() -> Resource { Resource::new() }
+}
+
+impl Resource {
+    /// Empty index, which the spatial index system fills from scratch the first time it runs
+    pub fn new() -> Resource {
+        Resource {
+            nodes: vec![Node::new(Point3::new(0.0, 0.0, 0.0), DEFAULT_HALF_SIZE, 0)],
+            entries: HashMap::new(),
+            outside: 0,
+            stale: true,
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    /// Box the entity was last indexed with
+    pub fn get(&self, entity: specs::Entity) -> Option<&Bounds> {
+        self.entries.get(&entity.get_id()).and_then(|entry| if entry.entity == entity { Some(&entry.bounds) } else { None })
+    }
+
+    /// Depth of the deepest node
+    pub fn depth(&self) -> usize {
+        self.nodes.iter().map(|node| node.depth).max().unwrap_or(0)
+    }
+
+    #[inline]
+    pub fn node_count(&self) -> usize { self.nodes.len() }
+
+    /// Has the spatial index system rebuild the index from scratch next time it runs
+    #[inline]
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    /// Checks if the index should be rebuilt, because it was invalidated or too many boxes have moved out of the root cell
+    pub fn needs_rebuild(&self) -> bool {
+        self.stale || (self.outside > SPLIT_THRESHOLD && self.outside * 4 > self.entries.len())
+    }
+
+    /// Replaces everything in the index, with the root cell fitted to the given boxes
+    pub fn rebuild<I>(&mut self, entities: I) where I: IntoIterator<Item = (specs::Entity, Bounds)> {
+        let entities: Vec<(specs::Entity, Bounds)> = entities.into_iter().collect();
+
+        let centers = Bounds::from_points(entities.iter().map(|&(_, ref bounds)| center_and_extent(bounds).0).filter(is_finite));
+
+        let (center, half) = match centers {
+            Some(Bounds { min, max }) => {
+                let half = (max.x - min.x).max(max.y - min.y).max(max.z - min.z) * 0.5;
+
+                (Point3::new((min.x + max.x) * 0.5, (min.y + max.y) * 0.5, (min.z + max.z) * 0.5), half.max(1.0))
+            }
+            None => (Point3::new(0.0, 0.0, 0.0), DEFAULT_HALF_SIZE),
+        };
+
+        self.nodes = vec![Node::new(center, half, 0)];
+        self.entries.clear();
+        self.outside = 0;
+        self.stale = false;
+
+        for (entity, bounds) in entities {
+            self.insert(entity, bounds);
+        }
+    }
+
+    /// Adds an entity to the index, or moves it if it's already there
+    pub fn insert(&mut self, entity: specs::Entity, bounds: Bounds) {
+        let (center, extent) = center_and_extent(&bounds);
+
+        let node = self.place(0, &center, extent);
+        let outside = is_finite(&center) && !self.nodes[0].contains(&center);
+
+        let id = entity.get_id();
+
+        if let Some(entry) = self.entries.get_mut(&id) {
+            if entry.entity == entity && entry.node == node && entry.outside == outside {
+                entry.bounds = bounds;
+
+                return;
+            }
+        }
+
+        self.remove_by_id(id);
+
+        self.nodes[node].entities.push(id);
+        self.entries.insert(id, Entry { entity: entity, bounds: bounds, node: node, outside: outside });
+
+        if outside {
+            self.outside += 1;
+        }
+
+        self.split(node);
+    }
+
+    /// Removes an entity from the index, returning the box it was indexed with
+    pub fn remove(&mut self, entity: specs::Entity) -> Option<Bounds> {
+        match self.entries.get(&entity.get_id()) {
+            Some(entry) if entry.entity == entity => {}
+            _ => return None,
+        }
+
+        self.remove_by_id(entity.get_id())
+    }
+
+    /// Removes whichever entity with the given ID is in the index, for when the entity itself may already be deleted
+    pub fn remove_by_id(&mut self, id: specs::Index) -> Option<Bounds> {
+        let entry = match self.entries.remove(&id) {
+            Some(entry) => entry,
+            None => return None,
+        };
+
+        if let Some(position) = self.nodes[entry.node].entities.iter().position(|&other| other == id) {
+            self.nodes[entry.node].entities.swap_remove(position);
+        }
+
+        if entry.outside {
+            self.outside -= 1;
+        }
+
+        Some(entry.bounds)
+    }
+
+    /// Entities whose boxes overlap `bounds`, nearest to its center first
+    pub fn query_aabb(&self, bounds: &Bounds) -> Vec<Hit> {
+        let (center, _) = center_and_extent(bounds);
+
+        self.search(|node| overlaps(node, bounds),
+                    |entity| if overlaps(entity, bounds) { Some(box_distance(entity, &center)) } else { None })
+    }
+
+    /// Entities whose boxes are within `radius` of `center`, nearest first
+    pub fn query_sphere(&self, center: &Point3<f32>, radius: f32) -> Vec<Hit> {
+        self.search(|node| box_distance(node, center) <= radius, |entity| {
+            let distance = box_distance(entity, center);
+
+            if distance <= radius { Some(distance) } else { None }
+        })
+    }
+
+    /// Entities whose boxes a ray enters within `max_distance` of its origin, nearest first.
+    ///
+    /// The direction doesn't have to be normalized, but can't be zero.
+    pub fn raycast(&self, origin: &Point3<f32>, direction: &Vector3<f32>, max_distance: f32) -> Vec<Hit> {
+        let length = direction.norm();
+
+        if !(length > 0.0) {
+            return Vec::new();
+        }
+
+        let direction = *direction / length;
+
+        self.search(|node| ray_distance(node, origin, &direction, max_distance).is_some(),
+                    |entity| ray_distance(entity, origin, &direction, max_distance))
+    }
+
+    /// Every entity in a node whose loose bounds pass `test_node`, with the distances given by `test_entity`, sorted
+    fn search<N, E>(&self, test_node: N, test_entity: E) -> Vec<Hit> where N: Fn(&Bounds) -> bool,
+                                                                          E: Fn(&Bounds) -> Option<f32> {
+        let mut hits = Vec::new();
+
+        //The root is always searched, since it holds everything outside its own bounds
+        let mut stack = vec![0];
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+
+            for id in &node.entities {
+                let entry = &self.entries[id];
+
+                if let Some(distance) = test_entity(&entry.bounds) {
+                    hits.push(Hit { entity: entry.entity, distance: distance });
+                }
+            }
+
+            if let Some(first) = node.children {
+                for child in first..(first + 8) {
+                    if test_node(&self.nodes[child].loose()) {
+                        stack.push(child);
+                    }
+                }
+            }
+        }
+
+        sort_hits(&mut hits);
+
+        hits
+    }
+
+    /// Deepest node below `node` that a box with the given center and extent fits in
+    fn place(&self, mut node: usize, center: &Point3<f32>, extent: f32) -> usize {
+        while let Some(first) = self.nodes[node].children {
+            let child = first + self.nodes[node].octant(center);
+
+            if !self.nodes[child].fits(center, extent) {
+                break;
+            }
+
+            node = child;
+        }
+
+        node
+    }
+
+    /// Splits a node holding too many entities, moving down those that fit in its children
+    fn split(&mut self, node: usize) {
+        if self.nodes[node].children.is_some() || self.nodes[node].entities.len() <= SPLIT_THRESHOLD ||
+            self.nodes[node].depth >= MAX_DEPTH {
+            return;
+        }
+
+        let (center, half, depth) = (self.nodes[node].center, self.nodes[node].half * 0.5, self.nodes[node].depth + 1);
+
+        let first = self.nodes.len();
+
+        for octant in 0..8 {
+            let offset = |bit: usize| if octant & bit != 0 { half } else { -half };
+
+            self.nodes.push(Node::new(Point3::new(center.x + offset(1), center.y + offset(2), center.z + offset(4)), half, depth));
+        }
+
+        self.nodes[node].children = Some(first);
+
+        let entities = ::std::mem::replace(&mut self.nodes[node].entities, Vec::new());
+
+        for id in entities {
+            let (center, extent) = center_and_extent(&self.entries[&id].bounds);
+
+            let target = self.place(node, &center, extent);
+
+            self.nodes[target].entities.push(id);
+            self.entries.get_mut(&id).unwrap().node = target;
+        }
+
+        for child in first..(first + 8) {
+            self.split(child);
+        }
+    }
+}
+
+fn is_finite(point: &Point3<f32>) -> bool {
+    point.x.is_finite() && point.y.is_finite() && point.z.is_finite()
+}
+
+/// Center of a box, and half the length of its longest side
+fn center_and_extent(bounds: &Bounds) -> (Point3<f32>, f32) {
+    let (min, max) = (bounds.min, bounds.max);
+
+    let center = Point3::new((min.x + max.x) * 0.5, (min.y + max.y) * 0.5, (min.z + max.z) * 0.5);
+
+    (center, (max.x - min.x).max(max.y - min.y).max(max.z - min.z) * 0.5)
+}
+
+/// Checks if two boxes overlap, including just touching
+pub fn overlaps(a: &Bounds, b: &Bounds) -> bool {
+    a.min.x <= b.max.x && b.min.x <= a.max.x &&
+        a.min.y <= b.max.y && b.min.y <= a.max.y &&
+        a.min.z <= b.max.z && b.min.z <= a.max.z
+}
+
+/// Distance from a point to the nearest point of a box, which is zero inside it
+pub fn box_distance(bounds: &Bounds, point: &Point3<f32>) -> f32 {
+    let axis = |p: f32, min: f32, max: f32| (min - p).max(p - max).max(0.0);
+
+    Vector3::new(axis(point.x, bounds.min.x, bounds.max.x),
+                 axis(point.y, bounds.min.y, bounds.max.y),
+                 axis(point.z, bounds.min.z, bounds.max.z)).norm()
+}
+
+/// Distance along a normalized ray to where it enters a box, if it does within `max_distance`. Zero if it starts inside.
+pub fn ray_distance(bounds: &Bounds, origin: &Point3<f32>, direction: &Vector3<f32>, max_distance: f32) -> Option<f32> {
+    let origin = [origin.x, origin.y, origin.z];
+    let direction = [direction.x, direction.y, direction.z];
+
+    let (min, max) = ([bounds.min.x, bounds.min.y, bounds.min.z], [bounds.max.x, bounds.max.y, bounds.max.z]);
+
+    let (mut near, mut far) = (0.0f32, max_distance);
+
+    for i in 0..3 {
+        if direction[i] == 0.0 {
+            //Parallel to the slab, so it's either always inside it or never
+            if origin[i] < min[i] || origin[i] > max[i] {
+                return None;
+            }
+        } else {
+            let a = (min[i] - origin[i]) / direction[i];
+            let b = (max[i] - origin[i]) / direction[i];
+
+            near = near.max(a.min(b));
+            far = far.min(a.max(b));
+        }
+    }
+
+    if near <= far { Some(near) } else { None }
+}
+
+/// Sorts hits nearest first, with ties broken by entity ID so results don't depend on the shape of the tree
+fn sort_hits(hits: &mut Vec<Hit>) {
+    hits.sort_by(|a, b| {
+        match a.distance.partial_cmp(&b.distance) {
+            Some(Ordering::Equal) | None => a.entity.get_id().cmp(&b.entity.get_id()),
+            Some(ordering) => ordering,
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use ::testing::Lcg;
+
+    fn point(random: &mut Lcg, size: f32) -> Point3<f32> {
+        Point3::new(random.range(-size, size), random.range(-size, size), random.range(-size, size))
+    }
+
+    fn cube(center: Point3<f32>, half: f32) -> Bounds {
+        Bounds {
+            min: Point3::new(center.x - half, center.y - half, center.z - half),
+            max: Point3::new(center.x + half, center.y + half, center.z + half),
+        }
+    }
+
+    /// Mostly small boxes spread around, with a few huge ones, a pile all in one place, and some flat or empty ones
+    fn random_bounds(random: &mut Lcg) -> Bounds {
+        match random.next(10) {
+            0 => cube(point(random, 100.0), random.range(50.0, 500.0)),
+            1 => cube(Point3::new(5.0, 5.0, 5.0), 0.0),
+            2 => {
+                let min = point(random, 100.0);
+
+                Bounds { min: min, max: Point3::new(min.x + random.range(0.0, 10.0), min.y, min.z + random.range(0.0, 10.0)) }
+            }
+            _ => cube(point(random, 100.0), random.range(0.0, 4.0)),
+        }
+    }
+
+    /// Same as a query, but testing every box
+    fn brute_force<F>(boxes: &HashMap<specs::Entity, Bounds>, test: F) -> Vec<Hit> where F: Fn(&Bounds) -> Option<f32> {
+        let mut hits: Vec<Hit> = boxes.iter().filter_map(|(&entity, bounds)| test(bounds).map(|distance| Hit { entity: entity, distance: distance })).collect();
+
+        sort_hits(&mut hits);
+
+        hits
+    }
+
+    fn check_queries(index: &Resource, boxes: &HashMap<specs::Entity, Bounds>, random: &mut Lcg) {
+        assert_eq!(index.len(), boxes.len());
+        assert!(index.depth() <= MAX_DEPTH);
+
+        for _ in 0..20 {
+            let query = cube(point(random, 150.0), random.range(0.0, 40.0));
+            let (center, _) = center_and_extent(&query);
+
+            assert_eq!(index.query_aabb(&query),
+                       brute_force(boxes, |bounds| if overlaps(bounds, &query) { Some(box_distance(bounds, &center)) } else { None }));
+
+            let (center, radius) = (point(random, 150.0), random.range(0.0, 40.0));
+
+            assert_eq!(index.query_sphere(&center, radius), brute_force(boxes, |bounds| {
+                let distance = box_distance(bounds, &center);
+
+                if distance <= radius { Some(distance) } else { None }
+            }));
+
+            let origin = point(random, 200.0);
+            let direction = Vector3::new(random.range(-1.0, 1.0), random.range(-1.0, 1.0), random.range(-1.0, 1.0));
+
+            //Rays straight along an axis are a special case of the slab test, so test some of those too
+            let direction = if random.next(4) == 0 { Vector3::new(0.0, 0.0, direction.z.signum()) } else { direction };
+
+            let length = direction.norm();
+
+            if length > 0.0 {
+                let normalized = direction / length;
+
+                assert_eq!(index.raycast(&origin, &direction, 300.0),
+                           brute_force(boxes, |bounds| ray_distance(bounds, &origin, &normalized, 300.0)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut world = specs::World::new();
+        let mut random = Lcg(11);
+
+        let mut index = Resource::new();
+        let mut boxes = HashMap::new();
+        let mut alive = Vec::new();
+
+        for round in 0..30 {
+            //Add some, move some and remove some, checking incremental updates give the same answers as testing everything
+            for _ in 0..random.next(60) {
+                let entity = world.create_now().build();
+                let bounds = random_bounds(&mut random);
+
+                index.insert(entity, bounds);
+                boxes.insert(entity, bounds);
+                alive.push(entity);
+            }
+
+            for _ in 0..random.next(30) {
+                if alive.is_empty() {
+                    break;
+                }
+
+                let entity = alive[random.next(alive.len())];
+                let bounds = random_bounds(&mut random);
+
+                index.insert(entity, bounds);
+                boxes.insert(entity, bounds);
+            }
+
+            for _ in 0..random.next(20) {
+                if alive.is_empty() {
+                    break;
+                }
+
+                let entity = alive.swap_remove(random.next(alive.len()));
+
+                assert_eq!(index.remove(entity), boxes.remove(&entity));
+
+                world.delete_now(entity);
+            }
+
+            check_queries(&index, &boxes, &mut random);
+
+            //Rebuilding from scratch gives the same answers too
+            if round % 10 == 9 {
+                index.rebuild(boxes.iter().map(|(&entity, &bounds)| (entity, bounds)));
+
+                assert!(!index.needs_rebuild());
+
+                check_queries(&index, &boxes, &mut random);
+            }
+        }
+    }
+
+    #[test]
+    fn test_degenerate_scenes() {
+        let mut world = specs::World::new();
+
+        let mut index = Resource::new();
+
+        //Everything in exactly the same place can't be separated by splitting, so it stops at the deepest level
+        let piled: Vec<specs::Entity> = (0..500).map(|_| {
+            let entity = world.create_now().build();
+
+            index.insert(entity, cube(Point3::new(1.0, 2.0, 3.0), 0.0));
+
+            entity
+        }).collect();
+
+        //Boxes bigger than the whole root cell never leave the root
+        for _ in 0..50 {
+            index.insert(world.create_now().build(), cube(Point3::new(0.0, 0.0, 0.0), 1.0e6));
+        }
+
+        assert_eq!(index.len(), 550);
+        assert!(index.depth() <= MAX_DEPTH);
+        assert!(index.node_count() <= 8 * MAX_DEPTH + 1);
+
+        assert_eq!(index.query_sphere(&Point3::new(1.0, 2.0, 3.0), 0.0).len(), 550);
+        assert_eq!(index.query_aabb(&cube(Point3::new(-100.0, 0.0, 0.0), 1.0)).len(), 50);
+
+        //The same after rebuilding, with the root fitted tightly around the pile
+        index.rebuild(piled.iter().map(|&entity| (entity, cube(Point3::new(1.0, 2.0, 3.0), 0.0))));
+
+        assert_eq!(index.len(), 500);
+        assert!(index.depth() <= MAX_DEPTH);
+
+        let hits = index.raycast(&Point3::new(1.0, 2.0, -10.0), &Vector3::new(0.0, 0.0, 2.0), 100.0);
+
+        assert_eq!(hits.len(), 500);
+        assert!(hits.iter().all(|hit| (hit.distance - 13.0).abs() < 1e-4));
+
+        //Empty rebuilds and zero directions are fine too
+        index.rebuild(Vec::new());
+
+        assert!(index.is_empty());
+        assert!(index.raycast(&Point3::new(0.0, 0.0, 0.0), &Vector3::new(0.0, 0.0, 0.0), 100.0).is_empty());
+    }
+
+    #[test]
+    fn test_moving_out_of_the_root() {
+        let mut world = specs::World::new();
+
+        let mut index = Resource::new();
+
+        let entities: Vec<specs::Entity> = (0..40).map(|i| {
+            let entity = world.create_now().build();
+
+            index.insert(entity, cube(Point3::new(i as f32, 0.0, 0.0), 0.5));
+
+            entity
+        }).collect();
+
+        let indexed: Vec<(specs::Entity, Bounds)> = entities.iter().map(|&entity| (entity, *index.get(entity).unwrap())).collect();
+
+        index.rebuild(indexed);
+
+        assert!(!index.needs_rebuild());
+
+        //Everything wandering off far outside the fitted root is still found, and eventually asks for a rebuild
+        for (i, &entity) in entities.iter().enumerate() {
+            index.insert(entity, cube(Point3::new(10000.0 + i as f32, 0.0, 0.0), 0.5));
+        }
+
+        assert!(index.needs_rebuild());
+        assert_eq!(index.query_sphere(&Point3::new(10000.0, 0.0, 0.0), 2.0).len(), 3);
+
+        let nearest = index.raycast(&Point3::new(20000.0, 0.0, 0.0), &Vector3::new(-1.0, 0.0, 0.0), 1.0e5);
+
+        assert_eq!(nearest.len(), 40);
+        assert_eq!(nearest[0].entity, entities[39]);
+    }
+}
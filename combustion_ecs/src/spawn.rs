@@ -0,0 +1,221 @@
+//! Fluent helpers for spawning entities, and the `entity!` macro built on them
+//!
+//! Some components only make sense alongside others. A parent link does nothing without a transform to make relative,
+//! so `child_of` only exists once `with_transform` has been called, and leaving it out is a compile error:
+//!
+//! ```ignore
+//! let lamp = entity!(world,
+//!     named("lamp"),
+//!     with_transform(Transform::from_translation(0.0, 2.0, 0.0)),
+//!     child_of(statue),
+//!     with_point_light(PointLight::white(4.0, 6.0))
+//! );
+//! ```
+
+use std::marker::PhantomData;
+
+use specs;
+
+use components::name::Component as Name;
+use components::bounds::Component as Bounds;
+use components::point_light::Component as PointLight;
+use components::mesh_renderer::Component as MeshRenderer;
+use components::hierarchy::transform::Component as Transform;
+use components::hierarchy::parent::Component as Parent;
+
+/// Spawns an entity in `$world` by chaining calls to `Spawn` methods, and returns it
+#[macro_export]
+macro_rules! entity {
+    ($world:expr $(, $method:ident ( $($arg:expr),* ))* $(,)*) => {
+        $crate::spawn::Spawn::new($world.create_now()) $(.$method($($arg),*))* .build()
+    };
+}
+
+/// State of a `Spawn` before it has a transform
+pub struct Unplaced;
+
+/// State of a `Spawn` once it has a transform
+pub struct Placed;
+
+/// Entity being built, which knows whether it has been given a transform yet
+pub struct Spawn<'a, S> {
+    builder: specs::EntityBuilder<'a>,
+    _state: PhantomData<S>,
+}
+
+impl<'a> Spawn<'a, Unplaced> {
+    #[inline]
+    pub fn new(builder: specs::EntityBuilder<'a>) -> Spawn<'a, Unplaced> {
+        Spawn { builder: builder, _state: PhantomData }
+    }
+}
+
+impl<'a, S> Spawn<'a, S> {
+    /// Adds any other component
+    #[inline]
+    pub fn with<T: specs::Component>(self, component: T) -> Spawn<'a, S> {
+        Spawn { builder: self.builder.with(component), _state: PhantomData }
+    }
+
+    #[inline]
+    pub fn with_transform(self, transform: Transform) -> Spawn<'a, Placed> {
+        Spawn { builder: self.builder.with(transform), _state: PhantomData }
+    }
+
+    /// Draws the renderer's mesh once it has loaded, with bounds following it for culling
+    pub fn with_renderer<M, T>(self, renderer: MeshRenderer<M, T>) -> Spawn<'a, S>
+        where M: Send + Sync + 'static, T: Send + Sync + 'static {
+        self.with(renderer).with(Bounds::from_mesh())
+    }
+
+    #[inline]
+    pub fn with_point_light(self, light: PointLight) -> Spawn<'a, S> {
+        self.with(light)
+    }
+
+    #[inline]
+    pub fn named<N: Into<String>>(self, name: N) -> Spawn<'a, S> {
+        self.with(Name::new(name))
+    }
+
+    #[inline]
+    pub fn build(self) -> specs::Entity {
+        self.builder.build()
+    }
+}
+
+impl<'a> Spawn<'a, Placed> {
+    /// Positions the entity relative to `parent`
+    #[inline]
+    pub fn child_of(self, parent: specs::Entity) -> Spawn<'a, Placed> {
+        self.with(Parent::new(parent))
+    }
+}
+
+/// Starts a `Spawn` straight from a specs entity builder
+pub trait EntityBuilderExt<'a> {
+    fn with_transform(self, transform: Transform) -> Spawn<'a, Placed>;
+
+    fn with_renderer<M, T>(self, renderer: MeshRenderer<M, T>) -> Spawn<'a, Unplaced>
+        where M: Send + Sync + 'static, T: Send + Sync + 'static;
+
+    fn with_point_light(self, light: PointLight) -> Spawn<'a, Unplaced>;
+
+    fn named<N: Into<String>>(self, name: N) -> Spawn<'a, Unplaced>;
+}
+
+impl<'a> EntityBuilderExt<'a> for specs::EntityBuilder<'a> {
+    #[inline]
+    fn with_transform(self, transform: Transform) -> Spawn<'a, Placed> {
+        Spawn::new(self).with_transform(transform)
+    }
+
+    #[inline]
+    fn with_renderer<M, T>(self, renderer: MeshRenderer<M, T>) -> Spawn<'a, Unplaced>
+        where M: Send + Sync + 'static, T: Send + Sync + 'static {
+        Spawn::new(self).with_renderer(renderer)
+    }
+
+    #[inline]
+    fn with_point_light(self, light: PointLight) -> Spawn<'a, Unplaced> {
+        Spawn::new(self).with_point_light(light)
+    }
+
+    #[inline]
+    fn named<N: Into<String>>(self, name: N) -> Spawn<'a, Unplaced> {
+        Spawn::new(self).named(name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::path::Path;
+
+    use asset::handle::AssetHandle;
+
+    use components;
+
+    struct TestMesh;
+    struct TestMaterial;
+
+    type Renderer = MeshRenderer<TestMesh, TestMaterial>;
+
+    fn new_world() -> specs::World {
+        let mut world = specs::World::new();
+
+        components::hierarchy::register_all(&mut world);
+
+        world.register::<Name>();
+        world.register::<Bounds>();
+        world.register::<PointLight>();
+        world.register::<Renderer>();
+
+        world
+    }
+
+    #[test]
+    fn test_builder_and_macro_agree() {
+        let mut world = new_world();
+
+        let parent = world.create_now().with_transform(Transform::new()).named("parent").build();
+
+        let by_builder = world.create_now()
+                              .named("child")
+                              .with_transform(Transform::from_translation(1.0, 0.0, 0.0))
+                              .child_of(parent)
+                              .with_renderer(Renderer::new(AssetHandle::at("models/cube.cmdl")))
+                              .build();
+
+        let by_macro = entity!(world,
+            named("child"),
+            with_transform(Transform::from_translation(1.0, 0.0, 0.0)),
+            child_of(parent),
+            with_renderer(Renderer::new(AssetHandle::at("models/cube.cmdl"))),
+        );
+
+        for &entity in &[by_builder, by_macro] {
+            assert_eq!(world.read::<Name>().get(entity), Some(&Name::new("child")));
+            assert_eq!(world.read::<Parent>().get(entity), Some(&Parent::new(parent)));
+            assert_eq!(world.read::<Transform>().get(entity).unwrap().translation().x, 1.0);
+            assert_eq!(world.read::<Bounds>().get(entity), Some(&Bounds::from_mesh()));
+
+            let renderers = world.read::<Renderer>();
+            let path = renderers.get(entity).unwrap().mesh.path().map(|path| path.to_path_buf());
+
+            assert_eq!(path, Some(Path::new("models/cube.cmdl").to_path_buf()));
+        }
+    }
+
+    #[test]
+    fn test_nested_spawns() {
+        let mut world = new_world();
+
+        let root = entity!(world, named("root"), with_transform(Transform::new()));
+
+        let statue = entity!(world,
+            named("statue"),
+            with_transform(Transform::from_translation(0.0, 0.5, 0.0)),
+            child_of(root),
+            with_renderer(Renderer::new(AssetHandle::at("models/buddha.cmdl")))
+        );
+
+        let lamp = entity!(world,
+            named("lamp"),
+            with_transform(Transform::from_translation(0.0, 2.0, 0.0)),
+            child_of(statue),
+            with_point_light(PointLight::white(4.0, 6.0))
+        );
+
+        let parents = world.read::<Parent>();
+
+        assert!(parents.get(root).is_none());
+        assert_eq!(parents.get(statue).map(Parent::entity), Some(root));
+        assert_eq!(parents.get(lamp).map(Parent::entity), Some(statue));
+
+        assert!(world.read::<Renderer>().get(statue).is_some());
+        assert!(world.read::<Renderer>().get(lamp).is_none());
+        assert!(world.read::<PointLight>().get(lamp).is_some());
+    }
+}
@@ -0,0 +1,367 @@
+//! Component storage that records which components changed, for systems that only want to process those
+//!
+//! `TrackedStorage` wraps any other storage, logging every insertion, mutable access and removal. Each system reading
+//! the changes owns a `ReaderId`, which remembers how far through the log it has read, so any number of systems can
+//! read the same stream of changes. The log is cleared at the end of every frame by a `ClearChanges` system.
+//!
+//! Immutable access never counts as a change, so immutable joins are free of false positives. Mutable joins do mark
+//! every component joined as modified, whether it was written or not, so systems that only write some of the components
+//! they visit should join immutably and `get_mut` just the ones they change.
+
+use std::cell::RefCell;
+use std::marker::PhantomData;
+
+use specs;
+use specs::{Index, Join, UnprotectedStorage};
+
+/// What happened to a component
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Inserted,
+    Modified,
+    Removed,
+}
+
+impl ChangeKind {
+    #[inline]
+    fn bit(self) -> u8 {
+        match self {
+            ChangeKind::Inserted => 1,
+            ChangeKind::Modified => 2,
+            ChangeKind::Removed => 4,
+        }
+    }
+}
+
+/// Indices of the components that changed since a reader last read, each sorted and listed once.
+///
+/// A component can appear in more than one list, like one inserted and then modified in the same frame.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changes {
+    pub inserted: Vec<Index>,
+    pub modified: Vec<Index>,
+    pub removed: Vec<Index>,
+}
+
+impl Changes {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inserted.is_empty() && self.modified.is_empty() && self.removed.is_empty()
+    }
+
+    /// Checks if the component at `index` was inserted or modified, the usual reason to process it again
+    pub fn is_dirty(&self, index: Index) -> bool {
+        self.inserted.binary_search(&index).is_ok() || self.modified.binary_search(&index).is_ok()
+    }
+}
+
+/// Position of one consumer in a `TrackedStorage`'s change log
+#[derive(Debug, Clone, Default)]
+pub struct ReaderId {
+    /// Sequence number of the next change to read, or `None` if the reader hasn't read anything yet
+    next: Option<usize>,
+    missed: usize,
+}
+
+impl ReaderId {
+    pub fn new() -> ReaderId {
+        ReaderId::default()
+    }
+
+    /// Number of changes cleared before this reader got to them
+    #[inline]
+    pub fn missed(&self) -> usize { self.missed }
+}
+
+/// Storage wrapper logging changes to its components
+pub struct TrackedStorage<T, S = specs::VecStorage<T>> {
+    inner: S,
+    log: Vec<(Index, ChangeKind)>,
+    /// Sequence number of the first change in the log
+    offset: usize,
+    /// Frames ended so far
+    generation: u64,
+    /// Generation each index was last logged in, and the kinds of change logged for it then
+    logged: Vec<(u64, u8)>,
+    _marker: PhantomData<fn(T)>,
+}
+
+impl<T, S> TrackedStorage<T, S> {
+    /// Logs a change, unless the same change was already logged for the index this frame
+    fn record(&mut self, index: Index, kind: ChangeKind) {
+        let i = index as usize;
+
+        if i >= self.logged.len() {
+            self.logged.resize(i + 1, (0, 0));
+        }
+
+        let entry = &mut self.logged[i];
+
+        if entry.0 != self.generation {
+            *entry = (self.generation, 0);
+        }
+
+        if entry.1 & kind.bit() == 0 {
+            entry.1 |= kind.bit();
+
+            self.log.push((index, kind));
+        }
+    }
+
+    /// Frames ended so far
+    #[inline]
+    pub fn generation(&self) -> u64 { self.generation }
+
+    /// Changes logged since `reader` last read, which won't be returned to it again
+    pub fn read_changes(&self, reader: &mut ReaderId) -> Changes {
+        let end = self.offset + self.log.len();
+
+        let from = match reader.next {
+            Some(next) if next < self.offset => {
+                reader.missed += self.offset - next;
+                self.offset
+            }
+            Some(next) => next,
+            None => self.offset,
+        };
+
+        reader.next = Some(end);
+
+        let mut changes = Changes::default();
+
+        for &(index, kind) in &self.log[(from - self.offset)..] {
+            match kind {
+                ChangeKind::Inserted => changes.inserted.push(index),
+                ChangeKind::Modified => changes.modified.push(index),
+                ChangeKind::Removed => changes.removed.push(index),
+            }
+        }
+
+        for list in &mut [&mut changes.inserted, &mut changes.modified, &mut changes.removed] {
+            list.sort();
+            list.dedup();
+        }
+
+        changes
+    }
+
+    /// Clears the log and starts a new frame. Readers that haven't read this frame's changes miss them.
+    pub fn end_frame(&mut self) {
+        self.offset += self.log.len();
+        self.log.clear();
+        self.generation += 1;
+    }
+}
+
+impl<T, S> UnprotectedStorage<T> for TrackedStorage<T, S> where S: UnprotectedStorage<T> {
+    fn new() -> Self {
+        TrackedStorage {
+            inner: S::new(),
+            log: Vec::new(),
+            offset: 0,
+            generation: 0,
+            logged: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    unsafe fn clean<F>(&mut self, has: F) where F: Fn(Index) -> bool {
+        let cleaned = RefCell::new(Vec::new());
+
+        self.inner.clean(|index| {
+            let present = has(index);
+
+            if present {
+                cleaned.borrow_mut().push(index);
+            }
+
+            present
+        });
+
+        for index in cleaned.into_inner() {
+            self.record(index, ChangeKind::Removed);
+        }
+    }
+
+    #[inline]
+    unsafe fn get(&self, index: Index) -> &T {
+        self.inner.get(index)
+    }
+
+    #[inline]
+    unsafe fn get_mut(&mut self, index: Index) -> &mut T {
+        self.record(index, ChangeKind::Modified);
+        self.inner.get_mut(index)
+    }
+
+    #[inline]
+    unsafe fn insert(&mut self, index: Index, value: T) {
+        self.record(index, ChangeKind::Inserted);
+        self.inner.insert(index, value)
+    }
+
+    #[inline]
+    unsafe fn remove(&mut self, index: Index) -> T {
+        self.record(index, ChangeKind::Removed);
+        self.inner.remove(index)
+    }
+}
+
+/// The tracked storage under a component storage fetched from the world, like `tracked(&world.read::<T>())`
+pub fn tracked<'a, J, T, S>(storage: J) -> &'a TrackedStorage<T, S> where J: Join<Value = &'a TrackedStorage<T, S>> {
+    storage.open().1
+}
+
+/// The tracked storage under a mutable component storage, for ending the frame
+pub fn tracked_mut<'a, J, T, S>(storage: J) -> &'a mut TrackedStorage<T, S> where J: Join<Value = &'a mut TrackedStorage<T, S>> {
+    storage.open().1
+}
+
+/// Clears the change log of one tracked component type. Add it with the lowest priority, so it runs after every reader.
+pub struct ClearChanges<T>(PhantomData<fn(T)>);
+
+impl<T> ClearChanges<T> {
+    pub fn new() -> ClearChanges<T> {
+        ClearChanges(PhantomData)
+    }
+}
+
+impl<T, S, C> specs::System<C> for ClearChanges<T> where T: specs::Component<Storage = TrackedStorage<T, S>>,
+                                                         S: UnprotectedStorage<T> + Send + Sync + 'static {
+    fn run(&mut self, arg: specs::RunArg, _: C) {
+        let mut storage = arg.fetch(|world| world.write::<T>());
+
+        tracked_mut(&mut storage).end_frame();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Health(u32);
+
+    impl specs::Component for Health {
+        type Storage = TrackedStorage<Health>;
+    }
+
+    fn new_world() -> specs::World {
+        let mut world = specs::World::new();
+
+        world.register::<Health>();
+
+        world
+    }
+
+    fn changes(world: &specs::World, reader: &mut ReaderId) -> Changes {
+        tracked(&world.read::<Health>()).read_changes(reader)
+    }
+
+    fn end_frame(world: &specs::World) {
+        tracked_mut(&mut world.write::<Health>()).end_frame();
+    }
+
+    #[test]
+    fn test_multiple_readers() {
+        let mut world = new_world();
+
+        let a = world.create_now().with(Health(10)).build();
+        let b = world.create_now().with(Health(10)).build();
+
+        let mut render = ReaderId::new();
+        let mut audio = ReaderId::new();
+
+        let first = changes(&world, &mut render);
+
+        assert_eq!(first.inserted, vec![a.get_id(), b.get_id()]);
+        assert!(first.modified.is_empty());
+
+        // Writing the same component twice is one modification
+        world.write::<Health>().get_mut(a).unwrap().0 -= 1;
+        world.write::<Health>().get_mut(a).unwrap().0 -= 1;
+
+        let second = changes(&world, &mut render);
+
+        assert!(second.inserted.is_empty());
+        assert_eq!(second.modified, vec![a.get_id()]);
+        assert!(second.is_dirty(a.get_id()));
+        assert!(!second.is_dirty(b.get_id()));
+
+        // A reader that hasn't read yet sees everything this frame
+        let all = changes(&world, &mut audio);
+
+        assert_eq!(all.inserted, vec![a.get_id(), b.get_id()]);
+        assert_eq!(all.modified, vec![a.get_id()]);
+
+        // Nothing new since either read
+        assert!(changes(&world, &mut render).is_empty());
+        assert!(changes(&world, &mut audio).is_empty());
+
+        end_frame(&world);
+
+        world.write::<Health>().get_mut(b).unwrap().0 += 5;
+        world.write::<Health>().remove(a);
+
+        for reader in &mut [&mut render, &mut audio] {
+            let next = changes(&world, reader);
+
+            assert_eq!(next.modified, vec![b.get_id()]);
+            assert_eq!(next.removed, vec![a.get_id()]);
+            assert_eq!(reader.missed(), 0);
+        }
+    }
+
+    #[test]
+    fn test_immutable_access_is_not_a_change() {
+        let mut world = new_world();
+
+        for i in 0..10 {
+            world.create_now().with(Health(i)).build();
+        }
+
+        let mut reader = ReaderId::new();
+
+        changes(&world, &mut reader);
+
+        let total: u32 = (&world.read::<Health>(), &world.entities()).iter().map(|(health, _)| health.0).sum();
+
+        assert_eq!(total, 45);
+
+        assert!(changes(&world, &mut reader).is_empty());
+
+        // Mutable joins can't tell reads from writes, so everything joined counts as modified
+        for health in (&mut world.write::<Health>()).iter() {
+            let _ = health.0;
+        }
+
+        assert_eq!(changes(&world, &mut reader).modified.len(), 10);
+    }
+
+    #[test]
+    fn test_cleared_at_end_of_frame() {
+        let mut world = new_world();
+
+        let mut reader = ReaderId::new();
+
+        let entity = world.create_now().with(Health(1)).build();
+
+        end_frame(&world);
+
+        // Changes from before the frame ended are gone for readers that hadn't read them
+        assert!(changes(&world, &mut reader).is_empty());
+        assert_eq!(tracked(&world.read::<Health>()).generation(), 1);
+
+        // A change logged last frame can be logged again this frame
+        world.write::<Health>().get_mut(entity).unwrap().0 = 2;
+
+        end_frame(&world);
+
+        world.write::<Health>().get_mut(entity).unwrap().0 = 3;
+
+        let next = changes(&world, &mut reader);
+
+        assert_eq!(next.modified, vec![entity.get_id()]);
+        assert_eq!(reader.missed(), 1);
+    }
+}
@@ -0,0 +1,45 @@
+//! Animation system, which plays every animator's clip and writes the sampled pose into its transform each tick
+
+use specs;
+use specs::Join;
+
+pub struct System;
+
+impl<C: Into<f64>> specs::System<C> for System {
+    fn run(&mut self, arg: specs::RunArg, delta: C) {
+        use ::components::animation::animator::Component as Animator;
+        use ::components::hierarchy::transform::Component as Transform;
+
+        use ::animation_events::{AnimationEvent, Resource as AnimationEvents};
+
+        let delta = delta.into() as f32;
+
+        let (ref mut events, ref mut animators, ref mut transforms, ref entities) = arg.fetch(|world| {
+            (
+                world.write_resource::<AnimationEvents>(),
+                world.write::<Animator>(),
+                world.write::<Transform>(),
+                world.entities(),
+            )
+        });
+
+        events.clear();
+
+        for (animator, transform, entity) in (animators, transforms, entities).iter() {
+            if !animator.playing {
+                continue;
+            }
+
+            let clip = match animator.clip.get() {
+                Some(clip) => clip,
+                None => continue,
+            };
+
+            animator.advance(&*clip, delta, &mut |event| {
+                events.push(AnimationEvent { entity: entity, name: event.name.clone(), time: event.time });
+            });
+
+            clip.sample(animator.time).apply(transform);
+        }
+    }
+}
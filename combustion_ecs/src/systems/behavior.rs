@@ -0,0 +1,229 @@
+//! Behavior system, which runs every enabled behavior component once per fixed tick
+//!
+//! A panic inside a behavior is caught, and only that behavior is disabled.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use specs;
+use specs::Join;
+
+pub struct System;
+
+impl<C: Into<f64>> specs::System<C> for System {
+    fn run(&mut self, arg: specs::RunArg, delta: C) {
+        use ::components::behavior::{Component as Behavior, BehaviorContext, BehaviorEvent};
+        use ::components::hierarchy::transform::Component as Transform;
+        use ::components::hierarchy::world_transform::Component as WorldTransform;
+
+        use ::events::Events;
+        use ::time::Resource as Time;
+
+        let delta = delta.into() as f32;
+
+        let (ref mut behaviors, ref mut transforms, ref mut events, ref time, ref world_transforms, ref entities) = arg.fetch(|world| {
+            (
+                world.write::<Behavior>(),
+                world.write::<Transform>(),
+                world.write_resource::<Events<BehaviorEvent>>(),
+                world.read_resource::<Time>(),
+                world.read::<WorldTransform>(),
+                world.entities(),
+            )
+        });
+
+        let world_matrices = |entity| world_transforms.get(entity).map(|world| world.matrix);
+
+        for (behavior, entity) in (&mut *behaviors, entities).iter() {
+            if !behavior.enabled {
+                continue;
+            }
+
+            let transform = match transforms.get_mut(entity) {
+                Some(transform) => transform,
+                None => continue,
+            };
+
+            let result = {
+                let mut context = BehaviorContext::new(entity, transform, time, delta, behavior.elapsed, events, &world_matrices);
+
+                panic::catch_unwind(AssertUnwindSafe(|| behavior.behavior.run(&mut context)))
+            };
+
+            match result {
+                Ok(()) => behavior.elapsed += delta,
+                Err(_) => {
+                    error!("Behavior {:?} of entity {:?} panicked, so it has been disabled", behavior.behavior, entity);
+
+                    behavior.enabled = false;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::f32::consts::PI;
+
+    use nalgebra::{Vector3, Norm};
+
+    use ::components::behavior::{Component as Behavior, BehaviorEvent};
+    use ::components::hierarchy::transform::Component as Transform;
+
+    use ::events::{self, Events, Registry, EventReader};
+    use ::time::Resource as Time;
+
+    const TICK: f32 = 0.25;
+
+    fn new_planner() -> ::Planner {
+        let mut world = specs::World::new();
+
+        ::components::hierarchy::register_all(&mut world);
+
+        world.register::<Behavior>();
+
+        world.add_resource(Time::new());
+        world.add_resource(Registry::new());
+
+        events::register::<BehaviorEvent>(&mut world);
+
+        specs::Planner::new(world, 4)
+    }
+
+    fn step(planner: &mut ::Planner, ticks: usize) {
+        for _ in 0..ticks {
+            planner.dispatch(TICK as ::Delta);
+            planner.wait();
+        }
+    }
+
+    fn close(a: Vector3<f32>, b: Vector3<f32>) -> bool {
+        (a - b).norm() < 1e-4
+    }
+
+    fn translation(planner: &mut ::Planner, entity: specs::Entity) -> Vector3<f32> {
+        *planner.mut_world().read::<Transform>().get(entity).unwrap().translation()
+    }
+
+    #[test]
+    fn test_built_in_behaviors() {
+        let mut planner = new_planner();
+
+        planner.add_system(::systems::hierarchy::System, "HierarchySystem", 1);
+        planner.add_system(System, "BehaviorSystem", 2);
+
+        let (spinner, bobber, watcher) = {
+            let world = planner.mut_world();
+
+            let spinner = world.create_now()
+                               .with(Transform::new())
+                               .with(Behavior::rotate(Vector3::new(0.0, 2.0, 0.0), PI))
+                               .build();
+
+            let bobber = world.create_now()
+                              .with(Transform::from_translation(0.0, 5.0, 0.0))
+                              .with(Behavior::oscillate(Vector3::new(0.0, 1.0, 0.0), 2.0, 1.0))
+                              .build();
+
+            let target = world.create_now().with(Transform::from_translation(10.0, 0.0, 0.0)).build();
+
+            let watcher = world.create_now().with(Transform::new()).with(Behavior::look_at(target)).build();
+
+            (spinner, bobber, watcher)
+        };
+
+        //A quarter of a cycle in is the top of the swing
+        step(&mut planner, 1);
+
+        assert!(close(translation(&mut planner, bobber), Vector3::new(0.0, 7.0, 0.0)));
+
+        //Behaviors run before the hierarchy, so the watcher can only find its target from the second tick on
+        step(&mut planner, 1);
+
+        {
+            let transforms = planner.mut_world().read::<Transform>();
+
+            //Half a turn a second around Y, for half a second
+            let rotation = *transforms.get(spinner).unwrap().rotation();
+            let eighth = (PI / 4.0).cos();
+
+            assert!((rotation.w - eighth).abs() < 1e-4 && (rotation.j - eighth).abs() < 1e-4, "{:?}", rotation);
+
+            //-Z turned to point along +X
+            let matrix = transforms.get(watcher).unwrap().matrix();
+
+            assert!(close(Vector3::new(-matrix.m13, -matrix.m23, -matrix.m33), Vector3::new(1.0, 0.0, 0.0)));
+        }
+
+        assert!(close(translation(&mut planner, bobber), Vector3::new(0.0, 5.0, 0.0)));
+
+        //A whole cycle in, the oscillation is back where it started
+        step(&mut planner, 2);
+
+        assert!(close(translation(&mut planner, bobber), Vector3::new(0.0, 5.0, 0.0)));
+    }
+
+    #[test]
+    fn test_custom_behaviors_and_panics() {
+        let mut planner = new_planner();
+
+        planner.add_system(System, "BehaviorSystem", 0);
+
+        let (climber, faulty, survivor) = {
+            let world = planner.mut_world();
+
+            //Climbs a unit a second, and says so once it has passed a height of one
+            let climber = world.create_now()
+                               .with(Transform::new())
+                               .with(Behavior::custom(|context| {
+                                   let translation = *context.transform.translation() + Vector3::new(0.0, context.delta, 0.0);
+
+                                   context.transform.set_translation(translation);
+
+                                   if context.elapsed < 1.0 && context.elapsed + context.delta >= 1.0 {
+                                       context.send("climbed");
+                                   }
+                               }))
+                               .build();
+
+            let faulty = world.create_now()
+                              .with(Transform::new())
+                              .with(Behavior::custom(|context| {
+                                  if context.elapsed > 0.0 {
+                                      panic!("faulty behavior");
+                                  }
+                              }))
+                              .build();
+
+            let survivor = world.create_now().with(Transform::new()).with(Behavior::rotate(Vector3::new(0.0, 1.0, 0.0), 1.0)).build();
+
+            (climber, faulty, survivor)
+        };
+
+        let mut reader = EventReader::new();
+
+        step(&mut planner, 4);
+
+        assert!(close(translation(&mut planner, climber), Vector3::new(0.0, 1.0, 0.0)));
+
+        {
+            let world = planner.mut_world();
+            let events = world.read_resource::<Events<BehaviorEvent>>();
+            let sent: Vec<_> = reader.read(&events).cloned().collect();
+
+            assert_eq!(sent, vec![BehaviorEvent { entity: climber, name: "climbed".to_string() }]);
+
+            //Only the panicking behavior is disabled, and it stops where it failed
+            let behaviors = world.read::<Behavior>();
+
+            assert!(!behaviors.get(faulty).unwrap().enabled);
+            assert_eq!(behaviors.get(faulty).unwrap().elapsed, TICK);
+
+            assert!(behaviors.get(climber).unwrap().enabled);
+            assert!(behaviors.get(survivor).unwrap().enabled);
+            assert_eq!(behaviors.get(survivor).unwrap().elapsed, 4.0 * TICK);
+        }
+    }
+}
@@ -0,0 +1,298 @@
+//! Billboard system, which turns billboards to face the active camera and batches them by texture for render submission
+//!
+//! Billboards sized in pixels are scaled by their depth, so they need the viewport size from the camera matrices,
+//! and aren't drawn until it's known. Billboards behind the camera are skipped.
+//!
+//! The system is for billboards textured with textures of type `T`.
+
+use std::marker::PhantomData;
+
+use specs;
+use specs::Join;
+
+use nalgebra::{Point3, Matrix4, Eye};
+
+use ::billboard_list::BillboardInstance;
+
+pub struct System<T>(PhantomData<fn(T)>);
+
+impl<T> System<T> {
+    pub fn new() -> System<T> {
+        System(PhantomData)
+    }
+}
+
+impl<T, C> specs::System<C> for System<T> where T: Send + Sync + 'static {
+    fn run(&mut self, arg: specs::RunArg, _: C) {
+        use ::components::billboard::{Component as Billboard, Size};
+        use ::components::hierarchy::world_transform::Component as WorldTransform;
+
+        use ::billboard_list::Resource as BillboardList;
+        use ::camera_matrices::Resource as CameraMatrices;
+
+        let (ref mut list, ref camera, ref billboards, ref world_transforms) = arg.fetch(|world| {
+            (
+                world.write_resource::<BillboardList<T>>(),
+                world.read_resource::<CameraMatrices>(),
+                world.read::<Billboard<T>>(),
+                world.read::<WorldTransform>(),
+            )
+        });
+
+        let mut instances = Vec::new();
+
+        for (billboard, world_transform) in (billboards, world_transforms).iter() {
+            if !billboard.visible {
+                continue;
+            }
+
+            let texture = match billboard.texture {
+                Some(ref handle) => match handle.get() {
+                    Some(texture) => Some(texture),
+                    None => continue,
+                },
+                None => None,
+            };
+
+            let ref m = world_transform.matrix;
+
+            let position = Point3::new(m.m14, m.m24, m.m34);
+            let depth = camera.view_depth(&position);
+
+            if depth <= 0.0 {
+                continue;
+            }
+
+            let (width, height) = match billboard.size {
+                Size::World(width, height) => (width, height),
+                Size::Pixels(width, height) => match camera.world_per_pixel(depth) {
+                    Some(scale) => (width * scale, height * scale),
+                    None => continue,
+                },
+            };
+
+            let (right, up, normal) = billboard.facing.basis(&position, camera);
+
+            let mut transform = Matrix4::new_identity(4);
+
+            transform.m11 = right.x * width;
+            transform.m21 = right.y * width;
+            transform.m31 = right.z * width;
+
+            transform.m12 = up.x * height;
+            transform.m22 = up.y * height;
+            transform.m32 = up.z * height;
+
+            transform.m13 = normal.x;
+            transform.m23 = normal.y;
+            transform.m33 = normal.z;
+
+            transform.m14 = position.x;
+            transform.m24 = position.y;
+            transform.m34 = position.z;
+
+            instances.push((texture, BillboardInstance { transform: transform, tint: billboard.tint, depth: depth }));
+        }
+
+        list.set(instances);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::f32::consts::PI;
+
+    use nalgebra::{Vector3, Perspective3, Norm, Cross};
+
+    use asset::handle::AssetHandle;
+
+    use ::components::billboard::{self, Facing, Size};
+    use ::components::hierarchy::transform::Component as Transform;
+
+    use ::billboard_list;
+    use ::camera_matrices::Resource as CameraMatrices;
+
+    struct TestTexture;
+
+    type Billboard = billboard::Component<TestTexture>;
+    type BillboardList = billboard_list::Resource<TestTexture>;
+
+    fn step(planner: &mut ::Planner) {
+        planner.dispatch(0.0);
+        planner.wait();
+    }
+
+    fn close(a: Vector3<f32>, b: Vector3<f32>) -> bool {
+        (a - b).norm() < 1e-4
+    }
+
+    /// Columns of the transform, scaled by the billboard's size
+    fn axes(transform: &Matrix4<f32>) -> (Vector3<f32>, Vector3<f32>, Vector3<f32>) {
+        let m = transform;
+
+        (Vector3::new(m.m11, m.m21, m.m31), Vector3::new(m.m12, m.m22, m.m32), Vector3::new(m.m13, m.m23, m.m33))
+    }
+
+    /// At the origin looking down -Z, with a 90 degree field of view and an 800x600 viewport
+    fn camera() -> CameraMatrices {
+        let mut camera = CameraMatrices::new();
+
+        camera.set_projection(Perspective3::new(800.0 / 600.0, PI / 2.0, 0.1, 100.0).to_matrix());
+        camera.set_viewport(800.0, 600.0);
+
+        camera
+    }
+
+    fn new_planner(camera: CameraMatrices) -> ::Planner {
+        let mut world = specs::World::new();
+
+        ::components::hierarchy::register_all(&mut world);
+
+        world.register::<Billboard>();
+
+        world.add_resource(camera);
+        world.add_resource(BillboardList::new());
+        world.add_resource(::hierarchy::Resource::new());
+
+        let mut planner = specs::Planner::new(world, 4);
+
+        planner.add_system(::systems::hierarchy::System, "HierarchySystem", 1);
+        planner.add_system(System::<TestTexture>::new(), "BillboardSystem", 0);
+
+        planner
+    }
+
+    fn spawn(planner: &mut ::Planner, x: f32, y: f32, z: f32, billboard: Billboard) {
+        planner.mut_world().create_now().with(Transform::from_translation(x, y, z)).with(billboard).build();
+    }
+
+    /// Every instance, farthest first
+    fn instances(planner: &mut ::Planner) -> Vec<BillboardInstance> {
+        let world = planner.mut_world();
+        let list = world.read_resource::<BillboardList>();
+
+        let instances = list.batches().iter().flat_map(|batch| batch.instances.iter().cloned()).collect();
+
+        instances
+    }
+
+    #[test]
+    fn test_facing_modes() {
+        let mut planner = new_planner(camera());
+
+        //Off to the side and above, so the modes all turn differently
+        spawn(&mut planner, 5.0, 3.0, -5.0, Billboard::new(Size::World(2.0, 1.0)));
+        spawn(&mut planner, 5.0, 3.0, -6.0, Billboard::new(Size::World(2.0, 1.0)).with_facing(Facing::Cylindrical));
+        spawn(&mut planner, 5.0, 3.0, -7.0, Billboard::new(Size::World(2.0, 1.0)).with_facing(Facing::Axis(Vector3::new(1.0, 0.0, 0.0))));
+
+        step(&mut planner);
+        step(&mut planner);
+
+        let instances = instances(&mut planner);
+
+        assert_eq!(instances.len(), 3);
+
+        //Axis locked, farthest away
+        let (right, up, normal) = axes(&instances[0].transform);
+
+        assert!(close(up, Vector3::new(1.0, 0.0, 0.0)));
+        assert!(close(normal, Vector3::new(0.0, -3.0, 7.0).normalize()));
+        assert!(close(right, up.cross(&normal) * 2.0));
+
+        //Cylindrical, which stays upright but turns towards the camera
+        let (right, up, normal) = axes(&instances[1].transform);
+
+        assert!(close(up, Vector3::new(0.0, 1.0, 0.0)));
+        assert!(close(normal, Vector3::new(-5.0, 0.0, 6.0).normalize()));
+        assert!(close(right, Vector3::new(6.0, 0.0, 5.0).normalize() * 2.0));
+
+        //Spherical, which is parallel to the screen
+        let (right, up, normal) = axes(&instances[2].transform);
+
+        assert!(close(right, Vector3::new(2.0, 0.0, 0.0)));
+        assert!(close(up, Vector3::new(0.0, 1.0, 0.0)));
+        assert!(close(normal, Vector3::new(0.0, 0.0, 1.0)));
+
+        assert_eq!((instances[2].transform.m14, instances[2].transform.m24, instances[2].transform.m34), (5.0, 3.0, -5.0));
+    }
+
+    #[test]
+    fn test_looking_down_the_axis() {
+        //Straight above the origin, looking down with -Z at the top of the screen
+        let mut camera = camera();
+        let mut view = Matrix4::new_identity(4);
+
+        view.m22 = 0.0;
+        view.m23 = -1.0;
+        view.m32 = 1.0;
+        view.m33 = 0.0;
+        view.m34 = -10.0;
+
+        camera.set_view(view, Point3::new(0.0, 10.0, 0.0));
+
+        let mut planner = new_planner(camera);
+
+        spawn(&mut planner, 0.0, 0.0, 0.0, Billboard::new(Size::World(2.0, 1.0)).with_facing(Facing::Cylindrical));
+
+        step(&mut planner);
+        step(&mut planner);
+
+        let instances = instances(&mut planner);
+
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].depth, 10.0);
+
+        //Edge on, but still a proper rotation lined up with the screen rather than falling apart
+        let (right, up, normal) = axes(&instances[0].transform);
+
+        assert!(close(right, Vector3::new(2.0, 0.0, 0.0)));
+        assert!(close(up, Vector3::new(0.0, 1.0, 0.0)));
+        assert!(close(normal, Vector3::new(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn test_sizes_and_sorting() {
+        let mut planner = new_planner(camera());
+
+        //With a 90 degree field of view, the 600 pixel high viewport covers 20 units at a depth of 10
+        spawn(&mut planner, 0.0, 0.0, -10.0, Billboard::new(Size::Pixels(60.0, 30.0)));
+        spawn(&mut planner, 0.0, 0.0, -40.0, Billboard::new(Size::Pixels(60.0, 30.0)));
+        spawn(&mut planner, 1.0, 0.0, -20.0, Billboard::new(Size::World(1.0, 1.0)));
+
+        //Hidden, behind the camera, or waiting for a texture
+        let mut hidden = Billboard::new(Size::World(1.0, 1.0));
+
+        hidden.visible = false;
+
+        spawn(&mut planner, 0.0, 0.0, -5.0, hidden);
+        spawn(&mut planner, 0.0, 0.0, 5.0, Billboard::new(Size::World(1.0, 1.0)));
+        spawn(&mut planner, 0.0, 0.0, -5.0, Billboard::new(Size::World(1.0, 1.0)).with_texture(AssetHandle::pending()));
+
+        step(&mut planner);
+        step(&mut planner);
+
+        let instances = instances(&mut planner);
+
+        let depths: Vec<f32> = instances.iter().map(|instance| instance.depth).collect();
+
+        assert_eq!(depths, vec![40.0, 20.0, 10.0]);
+
+        //The same size on screen, so four times bigger in the world at four times the depth
+        let (right, up, _) = axes(&instances[2].transform);
+
+        assert!((right.norm() - 2.0).abs() < 1e-4 && (up.norm() - 1.0).abs() < 1e-4);
+
+        let (right, up, _) = axes(&instances[0].transform);
+
+        assert!((right.norm() - 8.0).abs() < 1e-3 && (up.norm() - 4.0).abs() < 1e-3);
+
+        //Pixel sizes can't be worked out until the viewport size is known
+        planner.mut_world().write_resource::<CameraMatrices>().set_viewport(0.0, 0.0);
+
+        step(&mut planner);
+
+        assert_eq!(planner.mut_world().read_resource::<BillboardList>().len(), 1);
+    }
+}
@@ -0,0 +1,217 @@
+//! Bounds system, which keeps world bounds in sync with local bounds and world transforms
+//!
+//! World bounds are only recomputed for entities the hierarchy system moved this update, or whose local bounds changed.
+//! Entities with a mesh renderer but no bounds are given bounds following their mesh.
+//!
+//! The system is for mesh renderers drawing meshes of type `M` with materials of type `T`.
+
+use std::collections::HashSet;
+use std::marker::PhantomData;
+
+use specs;
+use specs::Join;
+
+use ::components::bounds::MeshBounds;
+
+pub struct System<M, T>(PhantomData<fn(M, T)>);
+
+impl<M, T> System<M, T> {
+    pub fn new() -> System<M, T> {
+        System(PhantomData)
+    }
+}
+
+impl<M, T, C> specs::System<C> for System<M, T> where M: MeshBounds + Send + Sync + 'static,
+                                                      T: Send + Sync + 'static {
+    fn run(&mut self, arg: specs::RunArg, _: C) {
+        use ::components::bounds::Component as Bounds;
+        use ::components::world_bounds::Component as WorldBounds;
+        use ::components::mesh_renderer::Component as MeshRenderer;
+        use ::components::hierarchy::world_transform::Component as WorldTransform;
+
+        use ::hierarchy::Resource as Hierarchy;
+
+        let (ref hierarchy, ref mut bounds, ref mut world_bounds, ref mesh_renderers, ref world_transforms, ref entities) = arg.fetch(|world| {
+            (
+                world.read_resource::<Hierarchy>(),
+                world.write::<Bounds>(),
+                world.write::<WorldBounds>(),
+                world.read::<MeshRenderer<M, T>>(),
+                world.read::<WorldTransform>(),
+                world.entities(),
+            )
+        });
+
+        let missing: Vec<specs::Entity> = (mesh_renderers, entities).iter()
+                                                                    .filter(|&(_, entity)| bounds.get(entity).is_none())
+                                                                    .map(|(_, entity)| entity)
+                                                                    .collect();
+
+        for entity in missing {
+            bounds.insert(entity, Bounds::from_mesh());
+        }
+
+        let moved: HashSet<specs::Entity> = hierarchy.changed().iter().cloned().collect();
+
+        //World bounds of entities that lost their bounds, their world transform, or their mesh's bounds
+        let mut stale = Vec::new();
+
+        for (bounds, world_transform, entity) in (&mut *bounds, world_transforms, entities).iter() {
+            if !bounds.is_manual() {
+                bounds.sync_mesh(mesh_renderers.get(entity).and_then(|renderer| renderer.mesh.get()).and_then(|mesh| mesh.bounds()));
+            }
+
+            if !(bounds.is_dirty() || moved.contains(&entity) || world_bounds.get(entity).is_none()) {
+                continue;
+            }
+
+            match bounds.local() {
+                Some(local) => { world_bounds.insert(entity, WorldBounds::new(local, &world_transform.matrix)); }
+                None => stale.push(entity),
+            }
+
+            bounds.clear_dirty();
+        }
+
+        stale.extend((&*world_bounds, entities).iter()
+                                               .filter(|&(_, entity)| bounds.get(entity).is_none() || world_transforms.get(entity).is_none())
+                                               .map(|(_, entity)| entity));
+
+        for entity in stale {
+            world_bounds.remove(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use nalgebra::{Point3, Vector3, Quaternion, Norm};
+    use num_traits::One;
+
+    use ::bounds::Bounds as Aabb;
+    use ::components::bounds::Component as Bounds;
+    use ::components::mesh_renderer::Component as MeshRenderer;
+    use ::components::world_bounds::Component as WorldBounds;
+    use ::components::hierarchy::transform::Component as Transform;
+    use ::components::hierarchy::parent::Component as Parent;
+
+    use ::hierarchy::Resource as Hierarchy;
+
+    struct TestMesh;
+    struct TestMaterial;
+
+    impl MeshBounds for TestMesh {
+        fn bounds(&self) -> Option<Aabb> { None }
+    }
+
+    fn step(planner: &mut ::Planner) {
+        planner.dispatch(0.0);
+        planner.wait();
+    }
+
+    fn world_aabb(planner: &mut ::Planner, entity: specs::Entity) -> Option<Aabb> {
+        planner.mut_world().read::<WorldBounds>().get(entity).map(|bounds| bounds.aabb)
+    }
+
+    fn assert_aabb(aabb: Option<Aabb>, min: [f32; 3], max: [f32; 3]) {
+        let aabb = aabb.expect("missing world bounds");
+
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+
+        assert!(close(aabb.min.x, min[0]) && close(aabb.min.y, min[1]) && close(aabb.min.z, min[2]), "{:?} != {:?}", aabb.min, min);
+        assert!(close(aabb.max.x, max[0]) && close(aabb.max.y, max[1]) && close(aabb.max.z, max[2]), "{:?} != {:?}", aabb.max, max);
+    }
+
+    fn unit_cube() -> Aabb {
+        Aabb { min: Point3::new(-1.0, -1.0, -1.0), max: Point3::new(1.0, 1.0, 1.0) }
+    }
+
+    /// Rotation of `degrees` around the Z axis
+    fn around_z(degrees: f32) -> Quaternion<f32> {
+        let half = degrees.to_radians() * 0.5;
+
+        Quaternion::new(half.cos(), 0.0, 0.0, half.sin())
+    }
+
+    #[test]
+    fn test_world_bounds_follow_transforms() {
+        let mut world = specs::World::new();
+
+        ::components::hierarchy::register_all(&mut world);
+
+        world.register::<Bounds>();
+        world.register::<WorldBounds>();
+        world.register::<MeshRenderer<TestMesh, TestMaterial>>();
+
+        world.add_resource(Hierarchy::new());
+
+        //Scaled along X, then turned a quarter so that ends up along Y
+        let turned = world.create_now()
+                          .with(Transform::from_parts(Vector3::new(10.0, 0.0, 0.0), around_z(90.0), Vector3::new(2.0, 1.0, 1.0)))
+                          .with(Bounds::manual(unit_cube()))
+                          .build();
+
+        let child = world.create_now()
+                         .with(Transform::from_translation(0.0, 0.0, 5.0))
+                         .with(Parent::new(turned))
+                         .with(Bounds::manual(unit_cube()))
+                         .build();
+
+        let unbounded = world.create_now().with(Transform::new()).build();
+
+        let mut planner = specs::Planner::new(world, 4);
+
+        planner.add_system(::systems::hierarchy::System, "HierarchySystem", 1);
+        planner.add_system(System::<TestMesh, TestMaterial>::new(), "BoundsSystem", 0);
+
+        step(&mut planner);
+
+        assert_aabb(world_aabb(&mut planner, turned), [9.0, -2.0, -1.0], [11.0, 2.0, 1.0]);
+        assert_aabb(world_aabb(&mut planner, child), [9.0, -2.0, 4.0], [11.0, 2.0, 6.0]);
+        assert!(world_aabb(&mut planner, unbounded).is_none());
+
+        //A diagonal turn grows the box to fit the rotated corners, and the sphere still contains them
+        planner.mut_world().write::<Transform>().get_mut(turned).unwrap().set_rotation(around_z(45.0));
+
+        step(&mut planner);
+
+        let x = 2.0 * 0.5f32.sqrt() + 0.5f32.sqrt();
+
+        assert_aabb(world_aabb(&mut planner, turned), [10.0 - x, -x, -1.0], [10.0 + x, x, 1.0]);
+
+        {
+            let world_bounds = planner.mut_world().read::<WorldBounds>();
+            let sphere = world_bounds.get(turned).unwrap().sphere;
+
+            assert!((sphere.center - Point3::new(10.0, 0.0, 0.0)).norm() < 1e-4);
+            assert!(sphere.radius >= 6.0f32.sqrt());
+        }
+
+        //Moving the parent alone moves the child's bounds too
+        planner.mut_world().write::<Transform>().get_mut(turned).unwrap().set_rotation(Quaternion::one());
+        planner.mut_world().write::<Transform>().get_mut(turned).unwrap().set_scale(Vector3::new(1.0, 1.0, 1.0));
+
+        step(&mut planner);
+
+        assert_aabb(world_aabb(&mut planner, child), [9.0, -1.0, 4.0], [11.0, 1.0, 6.0]);
+
+        //Changing the bounds without moving anything still updates the world bounds
+        planner.mut_world().write::<Bounds>().get_mut(child).unwrap().set_manual(Aabb {
+            min: Point3::new(0.0, 0.0, 0.0),
+            max: Point3::new(1.0, 2.0, 3.0),
+        });
+
+        step(&mut planner);
+
+        assert_aabb(world_aabb(&mut planner, child), [10.0, 0.0, 5.0], [11.0, 2.0, 8.0]);
+
+        //Removing the bounds removes the world bounds
+        planner.mut_world().write::<Bounds>().remove(child);
+
+        step(&mut planner);
+
+        assert!(world_aabb(&mut planner, child).is_none());
+    }
+}
@@ -0,0 +1,189 @@
+//! Culling system, which finds the renderable entities inside the view frustum for render submission
+//!
+//! Entities are tested by their world bounds, so anything without them, or tagged as always visible, is kept.
+//!
+//! The system is for mesh renderers drawing meshes of type `M` with materials of type `T`.
+
+use std::marker::PhantomData;
+
+use specs;
+use specs::Join;
+
+use ::frustum::Frustum;
+
+pub struct System<M, T>(PhantomData<fn(M, T)>);
+
+impl<M, T> System<M, T> {
+    pub fn new() -> System<M, T> {
+        System(PhantomData)
+    }
+}
+
+impl<M, T, C> specs::System<C> for System<M, T> where M: Send + Sync + 'static, T: Send + Sync + 'static {
+    fn run(&mut self, arg: specs::RunArg, _: C) {
+        use ::components::renderable::Component as Renderable;
+        use ::components::mesh_renderer::Component as MeshRenderer;
+        use ::components::world_bounds::Component as WorldBounds;
+        use ::components::always_visible::Component as AlwaysVisible;
+
+        use ::visible_set::Resource as VisibleSet;
+        use ::camera_matrices::Resource as CameraMatrices;
+
+        let (ref mut visible_set, ref camera, ref renderables, ref mesh_renderers, ref world_bounds, ref always_visible, ref entities) = arg.fetch(|world| {
+            (
+                world.write_resource::<VisibleSet>(),
+                world.read_resource::<CameraMatrices>(),
+                world.read::<Renderable>(),
+                world.read::<MeshRenderer<M, T>>(),
+                world.read::<WorldBounds>(),
+                world.read::<AlwaysVisible>(),
+                world.entities(),
+            )
+        });
+
+        visible_set.clear();
+
+        let frustum = Frustum::from_matrix(&camera.view_projection);
+        let enabled = visible_set.is_enabled();
+
+        for entity in entities.iter() {
+            if renderables.get(entity).is_none() && mesh_renderers.get(entity).is_none() {
+                continue;
+            }
+
+            let visible = !enabled || always_visible.get(entity).is_some() || match world_bounds.get(entity) {
+                //The sphere is cheaper to test, but looser, so anything it keeps is tested again by its box
+                Some(bounds) => frustum.intersects_sphere(&bounds.sphere.center, bounds.sphere.radius) &&
+                    frustum.intersects_aabb(&bounds.aabb.min, &bounds.aabb.max),
+                None => true,
+            };
+
+            visible_set.mark(entity, visible);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::f32::consts::PI;
+
+    use nalgebra::{Point3, Matrix4, Perspective3, Eye};
+
+    use ::bounds::Bounds;
+    use ::components::renderable::Component as Renderable;
+    use ::components::mesh_renderer::Component as MeshRenderer;
+    use ::components::world_bounds::Component as WorldBounds;
+    use ::components::always_visible::Component as AlwaysVisible;
+
+    use ::visible_set::Resource as VisibleSet;
+    use ::camera_matrices::Resource as CameraMatrices;
+
+    struct TestMesh;
+    struct TestMaterial;
+
+    /// Renderable unit cube centered on `(x, y, z)`
+    fn cube(world: &mut specs::World, x: f32, y: f32, z: f32) -> specs::Entity {
+        let local = Bounds { min: Point3::new(-1.0, -1.0, -1.0), max: Point3::new(1.0, 1.0, 1.0) };
+
+        let mut matrix = Matrix4::new_identity(4);
+
+        matrix.m14 = x;
+        matrix.m24 = y;
+        matrix.m34 = z;
+
+        world.create_now().with(Renderable::new()).with(WorldBounds::new(&local, &matrix)).build()
+    }
+
+    fn step(planner: &mut ::Planner) {
+        planner.dispatch(0.0);
+        planner.wait();
+    }
+
+    #[test]
+    fn test_classification() {
+        let mut world = specs::World::new();
+
+        world.register::<Renderable>();
+        world.register::<WorldBounds>();
+        world.register::<AlwaysVisible>();
+        world.register::<MeshRenderer<TestMesh, TestMaterial>>();
+
+        //At the origin looking down -Z, with a 90 degree field of view so the frustum is 10 units wide either side at z = -10
+        let mut camera = CameraMatrices::new();
+
+        camera.set_projection(Perspective3::new(1.0, PI / 2.0, 0.1, 100.0).to_matrix());
+
+        world.add_resource(camera);
+        world.add_resource(VisibleSet::new());
+
+        let inside = cube(&mut world, 0.0, 0.0, -10.0);
+        let straddling = cube(&mut world, 10.0, 0.0, -10.0);
+        let beside = cube(&mut world, 50.0, 0.0, -10.0);
+        let behind = cube(&mut world, 0.0, 0.0, 10.0);
+        let beyond_far = cube(&mut world, 0.0, 0.0, -200.0);
+
+        let forced = cube(&mut world, 0.0, 50.0, -10.0);
+
+        world.write::<AlwaysVisible>().insert(forced, AlwaysVisible::new());
+
+        let unbounded = world.create_now().with(Renderable::new()).build();
+
+        let mut planner = specs::Planner::new(world, 4);
+
+        planner.add_system(System::<TestMesh, TestMaterial>::new(), "CullingSystem", 0);
+
+        step(&mut planner);
+
+        {
+            let visible_set = planner.mut_world().read_resource::<VisibleSet>();
+
+            for &entity in &[inside, straddling, forced, unbounded] {
+                assert!(visible_set.contains(entity), "{:?} should be visible", entity);
+            }
+
+            for &entity in &[beside, behind, beyond_far] {
+                assert!(!visible_set.contains(entity), "{:?} should be culled", entity);
+            }
+
+            assert_eq!(visible_set.visible(), 4);
+            assert_eq!(visible_set.culled(), 3);
+        }
+
+        //Disabling culling keeps everything
+        planner.mut_world().write_resource::<VisibleSet>().set_enabled(false);
+
+        step(&mut planner);
+
+        {
+            let visible_set = planner.mut_world().read_resource::<VisibleSet>();
+
+            assert!(visible_set.contains(beside) && visible_set.contains(behind));
+            assert_eq!(visible_set.visible(), 7);
+            assert_eq!(visible_set.culled(), 0);
+        }
+
+        //Moving the camera to look the other way swaps what's in front and behind
+        planner.mut_world().write_resource::<VisibleSet>().set_enabled(true);
+
+        {
+            let mut camera = planner.mut_world().write_resource::<CameraMatrices>();
+            let mut view = Matrix4::new_identity(4);
+
+            //Half a turn around Y
+            view.m11 = -1.0;
+            view.m33 = -1.0;
+
+            camera.set_view(view, Point3::new(0.0, 0.0, 0.0));
+        }
+
+        step(&mut planner);
+
+        let visible_set = planner.mut_world().read_resource::<VisibleSet>();
+
+        assert!(visible_set.contains(behind));
+        assert!(!visible_set.contains(inside));
+        assert!(!visible_set.contains(straddling));
+    }
+}
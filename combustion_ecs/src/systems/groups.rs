@@ -0,0 +1,113 @@
+//! Groups system, which prunes deleted entities from the groups resource's index
+
+use specs;
+
+pub struct System;
+
+impl<C> specs::System<C> for System {
+    fn run(&mut self, arg: specs::RunArg, _: C) {
+        use ::components::group::Component as Group;
+
+        use ::groups::Resource as Groups;
+
+        let (ref mut index, ref groups) = arg.fetch(|world| {
+            (
+                world.write_resource::<Groups>(),
+                world.read::<Group>(),
+            )
+        });
+
+        //Deleting an entity removes its group component, so any member without a matching one is gone
+        index.prune(|entity, id| groups.get(entity).map_or(false, |group| group.contains(id)));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use ::components::group::Component as Group;
+
+    use ::groups::{Resource as Groups, GroupsExt};
+
+    use ::testing::Lcg;
+
+    const GROUPS: [&'static str; 4] = ["enemies", "destructible", "flammable", "loot"];
+
+    #[test]
+    fn test_membership_churn() {
+        let mut world = specs::World::new();
+
+        world.register::<Group>();
+        world.add_resource(Groups::new());
+
+        let mut planner: ::Planner = specs::Planner::new(world, 4);
+
+        planner.add_system(System, "GroupsSystem", 0);
+
+        //Seeded, so the churn is the same on every run
+        let mut random = Lcg(7);
+
+        //What the index should hold, built up alongside it
+        let mut expected: HashMap<&'static str, Vec<specs::Entity>> = HashMap::new();
+        let mut alive: Vec<specs::Entity> = Vec::new();
+
+        for frame in 0..200 {
+            {
+                let world = planner.mut_world();
+
+                for _ in 0..random.next(4) {
+                    alive.push(world.create_now().build());
+                }
+
+                for _ in 0..6 {
+                    if alive.is_empty() {
+                        break;
+                    }
+
+                    let entity = alive[random.next(alive.len())];
+                    let name = GROUPS[random.next(GROUPS.len())];
+                    let members = expected.entry(name).or_insert_with(Vec::new);
+
+                    if random.next(3) == 0 {
+                        assert_eq!(world.remove_from_group(entity, name), members.contains(&entity));
+
+                        members.retain(|member| *member != entity);
+                    } else {
+                        assert_eq!(world.add_to_group(entity, name), !members.contains(&entity));
+
+                        if !members.contains(&entity) {
+                            members.push(entity);
+                        }
+                    }
+                }
+
+                //Deleted entities are only dropped from the index once the groups system runs
+                if !alive.is_empty() && random.next(2) == 0 {
+                    let entity = alive.swap_remove(random.next(alive.len()));
+
+                    world.delete_now(entity);
+
+                    for members in expected.values_mut() {
+                        members.retain(|member| *member != entity);
+                    }
+                }
+            }
+
+            planner.dispatch(0.0);
+            planner.wait();
+
+            let world = planner.mut_world();
+
+            for name in GROUPS.iter() {
+                let members = expected.get(name).cloned().unwrap_or_default();
+
+                assert_eq!(world.members_of(name), members, "group {} on frame {}", name, frame);
+            }
+        }
+
+        assert!(!alive.is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+//! Transform hierarchy system, which computes world transforms with every parent solved before its children
+//!
+//! Only entities whose transform or parent changed are recomputed, along with everything below them.
+
+use std::collections::HashMap;
+
+use specs;
+use specs::Join;
+
+use nalgebra::{Matrix4, Eye, Inverse};
+
+pub struct System;
+
+impl<C> specs::System<C> for System {
+    fn run(&mut self, arg: specs::RunArg, _: C) {
+        use ::components::hierarchy::transform::Component as Transform;
+        use ::components::hierarchy::parent::Component as Parent;
+        use ::components::hierarchy::world_transform::Component as WorldTransform;
+
+        use ::hierarchy::Resource as Hierarchy;
+
+        let (ref mut hierarchy, ref mut transforms, ref mut parents, ref mut world_transforms, ref entities) = arg.fetch(|world| {
+            (
+                world.write_resource::<Hierarchy>(),
+                world.write::<Transform>(),
+                world.write::<Parent>(),
+                world.write::<WorldTransform>(),
+                world.entities(),
+            )
+        });
+
+        let force = hierarchy.take_force();
+
+        //The order is worked out from the parent links every update, so entities reparented earlier in the update are already placed correctly
+        let mut visited: HashMap<specs::Entity, bool> = HashMap::new();
+
+        //New world matrices of every entity that needed recomputing
+        let mut solved: HashMap<specs::Entity, Matrix4<f32>> = HashMap::new();
+
+        //Entities whose parent link closed a cycle, which are unlinked afterwards
+        let mut unlinked: Vec<specs::Entity> = Vec::new();
+
+        //Unvisited ancestors of the current entity, from the entity itself upwards
+        let mut chain: Vec<specs::Entity> = Vec::new();
+
+        for (_, entity) in (&*transforms, entities).iter() {
+            if visited.contains_key(&entity) {
+                continue;
+            }
+
+            let mut current = entity;
+            let mut parent = None;
+
+            //Walk up until a visited ancestor or a root, which is anything without a parent that has a transform
+            loop {
+                if visited.contains_key(&current) {
+                    parent = Some(current);
+                    break;
+                }
+
+                if chain.contains(&current) {
+                    //The last entity in the chain has `current` as its parent, so treating it as a root breaks the cycle
+                    let child = *chain.last().unwrap();
+
+                    error!("Parent of entity {:?} forms a cycle, unlinking it from {:?}", child, current);
+
+                    unlinked.push(child);
+                    break;
+                }
+
+                chain.push(current);
+
+                match parents.get(current) {
+                    Some(parent) if transforms.get(parent.0).is_some() => current = parent.0,
+                    _ => break,
+                }
+            }
+
+            let mut parent_dirty = parent.map_or(false, |parent| visited[&parent]);
+
+            //Then back down, recomputing anything that changed or is below something that changed
+            while let Some(current) = chain.pop() {
+                let reparented = hierarchy.set_parent(current, parent);
+
+                let dirty = force || parent_dirty || reparented || world_transforms.get(current).is_none() ||
+                    transforms.get(current).map_or(false, |transform| transform.is_dirty());
+
+                if dirty {
+                    let parent_matrix = match parent {
+                        Some(parent) => match solved.get(&parent) {
+                            Some(matrix) => *matrix,
+                            None => world_transforms.get(parent).map_or(Matrix4::new_identity(4), |world| world.matrix),
+                        },
+                        None => Matrix4::new_identity(4),
+                    };
+
+                    let local_matrix = transforms.get(current).map_or(Matrix4::new_identity(4), |transform| transform.matrix());
+
+                    solved.insert(current, parent_matrix * local_matrix);
+                }
+
+                visited.insert(current, dirty);
+
+                parent = Some(current);
+                parent_dirty = dirty;
+            }
+        }
+
+        for entity in unlinked {
+            parents.remove(entity);
+        }
+
+        hierarchy.finish(solved.keys().cloned().collect(), |entity| transforms.get(entity).is_some());
+
+        for (entity, matrix) in solved {
+            if let Some(transform) = transforms.get_mut(entity) {
+                transform.clear_dirty();
+            }
+
+            world_transforms.insert(entity, WorldTransform { matrix: matrix, inverse: matrix.inverse() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use nalgebra::Vector3;
+
+    use ::components::hierarchy::transform::Component as Transform;
+    use ::components::hierarchy::parent::Component as Parent;
+    use ::components::hierarchy::world_transform::Component as WorldTransform;
+
+    use ::hierarchy::Resource as Hierarchy;
+
+    const GROUPS: usize = 1000;
+    const DEPTH: usize = 10;
+
+    /// Runs one update, returning how many world transforms were recomputed
+    fn step(planner: &mut ::Planner) -> usize {
+        planner.dispatch(0.0);
+        planner.wait();
+
+        planner.mut_world().read_resource::<Hierarchy>().updated()
+    }
+
+    fn world_translation(planner: &mut ::Planner, entity: specs::Entity) -> Vector3<f32> {
+        let world_transforms = planner.mut_world().read::<WorldTransform>();
+        let matrix = world_transforms.get(entity).unwrap().matrix;
+
+        Vector3::new(matrix.m14, matrix.m24, matrix.m34)
+    }
+
+    #[test]
+    fn test_static_scene_does_no_work() {
+        let mut world = specs::World::new();
+
+        ::components::hierarchy::register_all(&mut world);
+
+        world.add_resource(Hierarchy::new());
+
+        //Chains of entities, each one unit above its parent
+        let mut groups = Vec::with_capacity(GROUPS);
+
+        for _ in 0..GROUPS {
+            let mut chain: Vec<specs::Entity> = Vec::with_capacity(DEPTH);
+
+            for depth in 0..DEPTH {
+                let mut builder = world.create_now().with(Transform::from_translation(0.0, 1.0, 0.0));
+
+                if depth > 0 {
+                    builder = builder.with(Parent::new(chain[depth - 1]));
+                }
+
+                chain.push(builder.build());
+            }
+
+            groups.push(chain);
+        }
+
+        let mut planner = specs::Planner::new(world, 4);
+
+        planner.add_system(System, "HierarchySystem", 0);
+
+        assert_eq!(step(&mut planner), GROUPS * DEPTH);
+        assert_eq!(world_translation(&mut planner, groups[0][DEPTH - 1]), Vector3::new(0.0, DEPTH as f32, 0.0));
+
+        //Nothing changed, so nothing is recomputed
+        for _ in 0..10 {
+            assert_eq!(step(&mut planner), 0);
+        }
+
+        //Moving a root recomputes its whole chain
+        planner.mut_world().write::<Transform>().get_mut(groups[0][0]).unwrap().set_translation(Vector3::new(5.0, 1.0, 0.0));
+
+        assert_eq!(step(&mut planner), DEPTH);
+        assert_eq!(world_translation(&mut planner, groups[0][DEPTH - 1]), Vector3::new(5.0, DEPTH as f32, 0.0));
+        assert_eq!(step(&mut planner), 0);
+
+        //Moving something in the middle only recomputes it and what's below it
+        planner.mut_world().write::<Transform>().get_mut(groups[1][4]).unwrap().set_translation(Vector3::new(0.0, 2.0, 0.0));
+
+        assert_eq!(step(&mut planner), DEPTH - 4);
+        assert_eq!(world_translation(&mut planner, groups[1][3]), Vector3::new(0.0, 4.0, 0.0));
+        assert_eq!(world_translation(&mut planner, groups[1][DEPTH - 1]), Vector3::new(0.0, DEPTH as f32 + 1.0, 0.0));
+
+        //Reparenting moves the whole subtree under its new parent
+        planner.mut_world().write::<Parent>().insert(groups[2][0], Parent::new(groups[3][DEPTH - 1]));
+
+        assert_eq!(step(&mut planner), DEPTH);
+        assert_eq!(world_translation(&mut planner, groups[2][DEPTH - 1]), Vector3::new(0.0, 2.0 * DEPTH as f32, 0.0));
+
+        //Deleting a parent turns its child into a root
+        planner.mut_world().delete_now(groups[4][0]);
+
+        assert_eq!(step(&mut planner), DEPTH - 1);
+        assert_eq!(world_translation(&mut planner, groups[4][DEPTH - 1]), Vector3::new(0.0, DEPTH as f32 - 1.0, 0.0));
+
+        assert_eq!(step(&mut planner), 0);
+
+        //Forcing an update recomputes everything once
+        planner.mut_world().write_resource::<Hierarchy>().force_update_all();
+
+        assert_eq!(step(&mut planner), GROUPS * DEPTH - 1);
+        assert_eq!(step(&mut planner), 0);
+    }
+}
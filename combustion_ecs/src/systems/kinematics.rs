@@ -0,0 +1,269 @@
+//! Kinematics system, which moves and turns entities by their velocities every fixed tick and deletes expired ones
+//!
+//! Velocities are integrated with semi-implicit Euler, so with the same tick length the results are the same every run.
+
+use std::collections::HashSet;
+
+use specs;
+use specs::Join;
+
+use nalgebra::{Quaternion, Norm};
+
+pub struct System {
+    /// Animated entities already warned about having velocities, so the warning isn't logged every tick
+    warned: HashSet<specs::Entity>,
+}
+
+impl System {
+    pub fn new() -> System {
+        System { warned: HashSet::new() }
+    }
+}
+
+impl<C: Into<f64>> specs::System<C> for System {
+    fn run(&mut self, arg: specs::RunArg, delta: C) {
+        use ::components::hierarchy::transform::Component as Transform;
+        use ::components::kinematics::velocity::Component as Velocity;
+        use ::components::kinematics::angular_velocity::Component as AngularVelocity;
+        use ::components::kinematics::gravity::Component as Gravity;
+        use ::components::kinematics::damping::Component as Damping;
+        use ::components::kinematics::lifetime::Component as Lifetime;
+        use ::components::animation::animator::Component as Animator;
+
+        let delta = delta.into() as f32;
+
+        let (ref mut transforms, ref mut velocities, ref mut angular_velocities, ref gravities, ref dampings,
+            ref mut lifetimes, ref animators, ref entities) = arg.fetch(|world| {
+            (
+                world.write::<Transform>(),
+                world.write::<Velocity>(),
+                world.write::<AngularVelocity>(),
+                world.read::<Gravity>(),
+                world.read::<Damping>(),
+                world.write::<Lifetime>(),
+                world.read::<Animator>(),
+                world.entities(),
+            )
+        });
+
+        for (lifetime, entity) in (lifetimes, entities).iter() {
+            if lifetime.tick(delta) {
+                arg.delete(entity);
+            }
+        }
+
+        for (transform, entity) in (transforms, entities).iter() {
+            if velocities.get(entity).is_none() && angular_velocities.get(entity).is_none() {
+                continue;
+            }
+
+            //The animation system writes the transform of anything it's playing on, so leave those alone
+            if animators.get(entity).map_or(false, |animator| animator.playing) {
+                if self.warned.insert(entity) {
+                    warn!("Entity {:?} has both velocity and a playing animation, so the animation takes over its transform", entity);
+                }
+
+                continue;
+            }
+
+            let damping = dampings.get(entity);
+
+            if let Some(velocity) = velocities.get_mut(entity) {
+                if let Some(gravity) = gravities.get(entity) {
+                    velocity.0 = velocity.0 + gravity.0 * delta;
+                }
+
+                if let Some(damping) = damping {
+                    velocity.0 = velocity.0 * damping.linear_factor(delta);
+                }
+
+                let translation = *transform.translation() + velocity.0 * delta;
+
+                transform.set_translation(translation);
+            }
+
+            if let Some(angular_velocity) = angular_velocities.get_mut(entity) {
+                if let Some(damping) = damping {
+                    angular_velocity.0 = angular_velocity.0 * damping.angular_factor(delta);
+                }
+
+                let rate = angular_velocity.0.norm();
+
+                if rate > 0.0 {
+                    //Turn about the velocity's axis by however far it goes this tick, in the parent's space
+                    let axis = angular_velocity.0 / rate;
+                    let half_angle = rate * delta * 0.5;
+                    let (sin, cos) = half_angle.sin_cos();
+
+                    let turn = Quaternion::new(cos, axis.x * sin, axis.y * sin, axis.z * sin);
+
+                    transform.set_rotation(normalize(turn * *transform.rotation()));
+                }
+            }
+        }
+
+        //Forget entities that stopped animating, so they're warned about again if it happens again
+        self.warned.retain(|entity| animators.get(*entity).map_or(false, |animator| animator.playing));
+    }
+}
+
+/// Keeps rotations from drifting away from unit length as they're integrated
+fn normalize(q: Quaternion<f32>) -> Quaternion<f32> {
+    let length = (q.w * q.w + q.i * q.i + q.j * q.j + q.k * q.k).sqrt();
+
+    Quaternion::new(q.w / length, q.i / length, q.j / length, q.k / length)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::f32::consts::PI;
+
+    use nalgebra::Vector3;
+
+    use asset::handle::AssetHandle;
+
+    use ::components::hierarchy::transform::Component as Transform;
+    use ::components::kinematics::velocity::Component as Velocity;
+    use ::components::kinematics::angular_velocity::Component as AngularVelocity;
+    use ::components::kinematics::gravity::Component as Gravity;
+    use ::components::kinematics::damping::Component as Damping;
+    use ::components::kinematics::lifetime::Component as Lifetime;
+    use ::components::animation::animator::{Component as Animator, WrapMode};
+    use ::components::animation::clip::{AnimationClip, Interpolation};
+
+    use ::animation_events::Resource as AnimationEvents;
+
+    const DT: ::Delta = 0.25;
+
+    struct Scene {
+        planner: ::Planner,
+        moving: specs::Entity,
+        falling: specs::Entity,
+        damped: specs::Entity,
+        spinning: specs::Entity,
+        expiring: specs::Entity,
+        animated: specs::Entity,
+    }
+
+    fn scene() -> Scene {
+        let mut world = specs::World::new();
+
+        ::components::hierarchy::register_all(&mut world);
+        ::components::kinematics::register_all(&mut world);
+        ::components::animation::register_all(&mut world);
+
+        world.add_resource(AnimationEvents::new());
+
+        let moving = world.create_now().with(Transform::new()).with(Velocity::new(1.0, 0.0, 0.0)).build();
+
+        let falling = world.create_now().with(Transform::from_translation(0.0, 10.0, 0.0))
+                           .with(Velocity::new(0.0, 0.0, 0.0))
+                           .with(Gravity::new(0.0, -10.0, 0.0)).build();
+
+        //Loses half its speed every second
+        let damped = world.create_now().with(Transform::new())
+                          .with(Velocity::new(4.0, 0.0, 0.0))
+                          .with(Damping::new(2.0f32.ln(), 0.0)).build();
+
+        //Half a turn about Z every second
+        let spinning = world.create_now().with(Transform::new()).with(AngularVelocity::new(0.0, 0.0, PI)).build();
+
+        let expiring = world.create_now().with(Transform::new()).with(Velocity::new(1.0, 0.0, 0.0)).with(Lifetime::new(1.0)).build();
+
+        let clip = AnimationClip::new().with_translation(0.0, Vector3::new(3.0, 0.0, 0.0), Interpolation::Step)
+                                       .with_translation(1.0, Vector3::new(3.0, 0.0, 0.0), Interpolation::Step);
+
+        let animated = world.create_now().with(Transform::new())
+                            .with(Velocity::new(0.0, 100.0, 0.0))
+                            .with(Animator::new(AssetHandle::loaded(clip)).with_wrap(WrapMode::Loop)).build();
+
+        let mut planner = specs::Planner::new(world, 4);
+
+        planner.add_system(System::new(), "KinematicsSystem", 2);
+        planner.add_system(::systems::animation::System, "AnimationSystem", 1);
+
+        Scene { planner: planner, moving: moving, falling: falling, damped: damped, spinning: spinning, expiring: expiring, animated: animated }
+    }
+
+    fn simulate(scene: &mut Scene, ticks: usize) {
+        for _ in 0..ticks {
+            scene.planner.dispatch(DT);
+            scene.planner.wait();
+        }
+    }
+
+    fn translation(scene: &mut Scene, entity: specs::Entity) -> Vector3<f32> {
+        *scene.planner.mut_world().read::<Transform>().get(entity).unwrap().translation()
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn test_integration() {
+        let mut scene = scene();
+
+        simulate(&mut scene, 3);
+
+        //Still around with a quarter of a second to go
+        assert!(scene.planner.mut_world().read::<Lifetime>().get(scene.expiring).is_some());
+
+        simulate(&mut scene, 1);
+
+        let (moving, falling, damped, spinning, expiring, animated) =
+            (scene.moving, scene.falling, scene.damped, scene.spinning, scene.expiring, scene.animated);
+
+        assert_eq!(translation(&mut scene, moving), Vector3::new(1.0, 0.0, 0.0));
+
+        //Semi-implicit Euler falls `g * dt^2 * n(n + 1) / 2` in `n` ticks
+        assert_eq!(translation(&mut scene, falling), Vector3::new(0.0, 10.0 - 6.25, 0.0));
+        assert_eq!(scene.planner.mut_world().read::<Velocity>().get(falling).unwrap().0, Vector3::new(0.0, -10.0, 0.0));
+
+        assert!(close(scene.planner.mut_world().read::<Velocity>().get(damped).unwrap().0.x, 2.0));
+
+        let rotation = *scene.planner.mut_world().read::<Transform>().get(spinning).unwrap().rotation();
+
+        assert!(close(rotation.w, 0.0) && close(rotation.k, 1.0));
+
+        //Expired entities are deleted along with all their components
+        assert!(scene.planner.mut_world().read::<Lifetime>().get(expiring).is_none());
+        assert!(scene.planner.mut_world().read::<Transform>().get(expiring).is_none());
+
+        //Animation wins over velocity
+        assert_eq!(translation(&mut scene, animated), Vector3::new(3.0, 0.0, 0.0));
+
+        //Stopping the animation hands the transform back to its velocity
+        scene.planner.mut_world().write::<Animator>().get_mut(animated).unwrap().playing = false;
+
+        simulate(&mut scene, 1);
+
+        assert_eq!(translation(&mut scene, animated), Vector3::new(3.0, 25.0, 0.0));
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let run = || {
+            let mut scene = scene();
+
+            simulate(&mut scene, 37);
+
+            let (moving, falling, damped, spinning) = (scene.moving, scene.falling, scene.damped, scene.spinning);
+
+            let world = scene.planner.mut_world();
+            let transforms = world.read::<Transform>();
+
+            let state: Vec<_> = [moving, falling, damped, spinning].iter().map(|entity| {
+                let transform = transforms.get(*entity).unwrap();
+
+                (*transform.translation(), *transform.rotation())
+            }).collect();
+
+            state
+        };
+
+        assert_eq!(run(), run());
+    }
+}
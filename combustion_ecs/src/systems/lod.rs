@@ -0,0 +1,222 @@
+//! LOD system, which picks the level of detail of every visible entity from how much of the screen it covers
+//!
+//! Coverage is worked out from the distance to the entity's bounding sphere rather than its depth, so turning the
+//! camera on the spot never changes any levels. Culled entities and those without world bounds keep their level.
+//!
+//! The system is for levels of detail of meshes of type `M`, with LOD settings debugging in materials of type `T`.
+
+use std::f32;
+use std::marker::PhantomData;
+
+use specs;
+use specs::Join;
+
+use nalgebra::Norm;
+
+use ::components::world_bounds::Sphere;
+
+use ::camera_matrices::Resource as CameraMatrices;
+
+/// Fraction of the screen's height covered by a sphere, which is unbounded when the camera is inside it
+pub fn screen_coverage(sphere: &Sphere, camera: &CameraMatrices) -> f32 {
+    let p = &camera.projection;
+
+    let distance = (sphere.center - camera.position).norm();
+
+    //Clip space W, which is the distance for perspective projections and one for orthographic ones
+    let w = p.m44 - p.m43 * distance;
+
+    if distance <= sphere.radius || w <= 0.0 {
+        f32::INFINITY
+    } else {
+        sphere.radius * p.m22.abs() / w
+    }
+}
+
+pub struct System<M, T>(PhantomData<fn(M, T)>);
+
+impl<M, T> System<M, T> {
+    pub fn new() -> System<M, T> {
+        System(PhantomData)
+    }
+}
+
+impl<M, T, C> specs::System<C> for System<M, T> where M: Send + Sync + 'static, T: Send + Sync + 'static {
+    fn run(&mut self, arg: specs::RunArg, _: C) {
+        use ::components::lod::Component as Lod;
+        use ::components::world_bounds::Component as WorldBounds;
+
+        use ::lod_settings::Resource as LodSettings;
+        use ::visible_set::Resource as VisibleSet;
+
+        let (ref settings, ref camera, ref visible_set, ref mut lods, ref world_bounds, ref entities) = arg.fetch(|world| {
+            (
+                world.read_resource::<LodSettings<T>>(),
+                world.read_resource::<CameraMatrices>(),
+                world.read_resource::<VisibleSet>(),
+                world.write::<Lod<M>>(),
+                world.read::<WorldBounds>(),
+                world.entities(),
+            )
+        });
+
+        for (lod, bounds, entity) in (&mut *lods, world_bounds, entities).iter() {
+            if !visible_set.contains(entity) {
+                continue;
+            }
+
+            let coverage = settings.biased(screen_coverage(&bounds.sphere, camera));
+
+            lod.select(coverage, settings.hysteresis);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::f32::consts::PI;
+
+    use nalgebra::{Point3, Matrix4, Perspective3, Orthographic3, Eye};
+
+    use asset::handle::AssetHandle;
+
+    use ::bounds::Bounds;
+    use ::components::lod;
+    use ::components::world_bounds::Component as WorldBounds;
+
+    use ::lod_settings::{self, DebugMaterial};
+    use ::visible_set::Resource as VisibleSet;
+
+    struct TestMesh;
+
+    #[derive(Debug, PartialEq)]
+    struct TestMaterial([f32; 3]);
+
+    impl DebugMaterial for TestMaterial {
+        fn flat(color: [f32; 3]) -> TestMaterial { TestMaterial(color) }
+    }
+
+    type Lod = lod::Component<TestMesh>;
+    type LodSettings = lod_settings::Resource<TestMaterial>;
+
+    fn sphere(z: f32, radius: f32) -> Sphere {
+        Sphere { center: Point3::new(0.0, 0.0, z), radius: radius }
+    }
+
+    /// At the origin looking down -Z, with a 90 degree field of view
+    fn camera() -> CameraMatrices {
+        let mut camera = CameraMatrices::new();
+
+        camera.set_projection(Perspective3::new(16.0 / 9.0, PI / 2.0, 0.1, 1000.0).to_matrix());
+
+        camera
+    }
+
+    #[test]
+    fn test_screen_coverage() {
+        let mut camera = camera();
+
+        //With a 90 degree field of view, the screen is 20 units high at a distance of 10
+        assert!((screen_coverage(&sphere(-10.0, 5.0), &camera) - 0.5).abs() < 1e-5);
+        assert!((screen_coverage(&sphere(-20.0, 5.0), &camera) - 0.25).abs() < 1e-5);
+
+        //Only the distance matters, not the direction
+        let beside = Sphere { center: Point3::new(10.0, 0.0, 0.0), radius: 5.0 };
+
+        assert!((screen_coverage(&beside, &camera) - 0.5).abs() < 1e-5);
+
+        //Inside the sphere, it covers everything
+        assert_eq!(screen_coverage(&sphere(-1.0, 5.0), &camera), f32::INFINITY);
+
+        //Orthographic cameras see the same size at any distance
+        camera.set_projection(Orthographic3::new(-40.0, 40.0, -20.0, 20.0, 0.1, 1000.0).to_matrix());
+
+        assert!((screen_coverage(&sphere(-10.0, 5.0), &camera) - 0.25).abs() < 1e-5);
+        assert!((screen_coverage(&sphere(-500.0, 5.0), &camera) - 0.25).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_bias() {
+        let mut settings = LodSettings::new();
+
+        assert_eq!(settings.biased(0.5), 0.5);
+
+        settings.bias = 1.0;
+
+        assert_eq!(settings.biased(0.5), 0.25);
+
+        settings.bias = -1.0;
+
+        assert_eq!(settings.biased(0.5), 1.0);
+
+        assert!(settings.debug_material(0) != settings.debug_material(1));
+    }
+
+    #[test]
+    fn test_selects_levels() {
+        let mut world = specs::World::new();
+
+        world.register::<Lod>();
+        world.register::<WorldBounds>();
+
+        world.add_resource(LodSettings::new());
+        world.add_resource(camera());
+        world.add_resource(VisibleSet::new());
+
+        let lod = || Lod::new().with_level(AssetHandle::pending(), 0.4).with_level(AssetHandle::pending(), 0.1);
+
+        //Unit cube bounds, whose sphere has a radius of sqrt(3)
+        let bounds = |z: f32| {
+            let local = Bounds { min: Point3::new(-1.0, -1.0, -1.0), max: Point3::new(1.0, 1.0, 1.0) };
+
+            let mut matrix = Matrix4::new_identity(4);
+
+            matrix.m34 = z;
+
+            WorldBounds::new(&local, &matrix)
+        };
+
+        let near = world.create_now().with(lod()).with(bounds(-3.0)).build();
+        let middle = world.create_now().with(lod()).with(bounds(-10.0)).build();
+        let far = world.create_now().with(lod()).with(bounds(-100.0)).build();
+        let culled = world.create_now().with(lod()).with(bounds(-100.0)).build();
+        let unbounded = world.create_now().with(lod()).build();
+
+        {
+            let mut visible_set = world.write_resource::<VisibleSet>();
+
+            for &entity in &[near, middle, far, unbounded] {
+                visible_set.mark(entity, true);
+            }
+
+            visible_set.mark(culled, false);
+        }
+
+        let mut planner = specs::Planner::new(world, 4);
+
+        planner.add_system(System::<TestMesh, TestMaterial>::new(), "LodSystem", 0);
+
+        planner.dispatch(0.0);
+        planner.wait();
+
+        let levels = |planner: &mut ::Planner| -> Vec<usize> {
+            let lods = planner.mut_world().read::<Lod>();
+
+            let levels = [near, middle, far, culled, unbounded].iter().map(|&entity| lods.get(entity).unwrap().current).collect();
+
+            levels
+        };
+
+        assert_eq!(levels(&mut planner), vec![0, 1, 2, 0, 0]);
+
+        //Biased towards simpler levels
+        planner.mut_world().write_resource::<LodSettings>().bias = 2.0;
+
+        planner.dispatch(0.0);
+        planner.wait();
+
+        assert_eq!(levels(&mut planner), vec![1, 2, 2, 0, 0]);
+    }
+}
@@ -0,0 +1,16 @@
+//! Systems written against the components and resources in this crate
+//!
+//! Every component is single precision, so these run with any delta that converts to `f64`,
+//! letting games schedule them with whatever delta type their own systems use.
+
+pub mod animation;
+pub mod skeletal_animation;
+pub mod kinematics;
+pub mod hierarchy;
+pub mod bounds;
+pub mod culling;
+pub mod behavior;
+pub mod groups;
+pub mod orphans;
+pub mod billboard;
+pub mod lod;
@@ -0,0 +1,191 @@
+//! Orphan system, which finds entities whose parent was deleted without them and despawns or detaches them
+//!
+//! Despawning an orphan orphans its own children in turn, so whole subtrees are handled in a single update.
+
+use std::collections::HashSet;
+
+use specs;
+use specs::Join;
+
+pub struct System;
+
+impl<C> specs::System<C> for System {
+    fn run(&mut self, arg: specs::RunArg, _: C) {
+        use ::components::hierarchy::parent::Component as Parent;
+        use ::components::hierarchy::orphan_policy::Component as OrphanPolicy;
+
+        use ::hierarchy::child_index;
+
+        let (ref mut parents, ref policies, ref entities) = arg.fetch(|world| {
+            (
+                world.write::<Parent>(),
+                world.read::<OrphanPolicy>(),
+                world.entities(),
+            )
+        });
+
+        //Deleted entities still show up in parent links, so they're found by what isn't alive
+        let alive: HashSet<specs::Entity> = Join::iter(entities).collect();
+
+        let mut orphans: Vec<specs::Entity> = (&*parents, entities).iter()
+                                                                   .filter(|&(parent, _)| !alive.contains(&parent.0))
+                                                                   .map(|(_, entity)| entity)
+                                                                   .collect();
+
+        if orphans.is_empty() {
+            return;
+        }
+
+        let mut children = child_index((&*parents, entities).iter().map(|(parent, child)| (child, parent.0)));
+
+        while let Some(orphan) = orphans.pop() {
+            match policies.get(orphan).cloned().unwrap_or_default() {
+                OrphanPolicy::Detach => {
+                    parents.remove(orphan);
+                }
+                OrphanPolicy::Despawn => {
+                    arg.delete(orphan);
+
+                    if let Some(children) = children.remove(&orphan) {
+                        orphans.extend(children);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use ::components::hierarchy::transform::Component as Transform;
+    use ::components::hierarchy::parent::Component as Parent;
+    use ::components::hierarchy::orphan_policy::Component as OrphanPolicy;
+    use ::components::kinematics::lifetime::Component as Lifetime;
+
+    fn new_planner() -> ::Planner {
+        let mut world = specs::World::new();
+
+        ::components::hierarchy::register_all(&mut world);
+        ::components::kinematics::register_all(&mut world);
+
+        specs::Planner::new(world, 4)
+    }
+
+    fn spawn(world: &mut specs::World, parent: specs::Entity, policy: Option<OrphanPolicy>) -> specs::Entity {
+        let builder = world.create_now().with(Transform::new()).with(Parent::new(parent));
+
+        match policy {
+            Some(policy) => builder.with(policy).build(),
+            None => builder.build(),
+        }
+    }
+
+    fn alive(planner: &mut ::Planner) -> HashSet<specs::Entity> {
+        Join::iter(&planner.mut_world().entities()).collect()
+    }
+
+    #[test]
+    fn test_orphans_follow_their_policy() {
+        let mut planner = new_planner();
+
+        planner.add_system(System, "OrphanSystem", 0);
+
+        let (root, chain, survivor, survivor_child) = {
+            let world = planner.mut_world();
+
+            let root = world.create_now().with(Transform::new()).build();
+
+            //A deep chain that goes with its parent, one link of which keeps itself and everything below
+            let mut chain = vec![root];
+
+            for depth in 1..200 {
+                let parent = chain[depth - 1];
+
+                chain.push(spawn(world, parent, None));
+            }
+
+            let survivor = spawn(world, chain[100], Some(OrphanPolicy::Detach));
+            let survivor_child = spawn(world, survivor, Some(OrphanPolicy::Despawn));
+
+            (root, chain, survivor, survivor_child)
+        };
+
+        planner.mut_world().delete_now(root);
+
+        planner.dispatch(0.0);
+        planner.wait();
+
+        let remaining = alive(&mut planner);
+
+        assert!(chain.iter().all(|link| !remaining.contains(link)));
+        assert_eq!(remaining, vec![survivor, survivor_child].into_iter().collect());
+
+        let world = planner.mut_world();
+        let parents = world.read::<Parent>();
+
+        assert!(parents.get(survivor).is_none());
+        assert_eq!(parents.get(survivor_child), Some(&Parent::new(survivor)));
+    }
+
+    /// Deletes entities with expired lifetimes while joining over them, like the kinematics system does
+    struct Reaper;
+
+    impl specs::System<::Delta> for Reaper {
+        fn run(&mut self, arg: specs::RunArg, delta: ::Delta) {
+            let (ref mut lifetimes, ref entities) = arg.fetch(|world| (world.write::<Lifetime>(), world.entities()));
+
+            for (lifetime, entity) in (lifetimes, entities).iter() {
+                if lifetime.tick(delta as f32) {
+                    arg.delete(entity);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_despawn_during_iteration() {
+        let mut planner = new_planner();
+
+        planner.add_system(Reaper, "Reaper", 1);
+        planner.add_system(System, "OrphanSystem", 0);
+
+        //Parents with a range of lifetimes, each with a couple of children and grandchildren
+        let families: Vec<(specs::Entity, Vec<specs::Entity>)> = {
+            let world = planner.mut_world();
+
+            (0..10).map(|i| {
+                let parent = world.create_now().with(Transform::new()).with(Lifetime::new(i as f32 + 0.5)).build();
+
+                let mut descendants = Vec::new();
+
+                for _ in 0..2 {
+                    let child = spawn(world, parent, None);
+
+                    descendants.push(child);
+                    descendants.push(spawn(world, child, None));
+                }
+
+                (parent, descendants)
+            }).collect()
+        };
+
+        for frame in 0..12 {
+            planner.dispatch(1.0);
+            planner.wait();
+
+            let remaining = alive(&mut planner);
+
+            for (i, &(parent, ref descendants)) in families.iter().enumerate() {
+                //Deletion by the reaper happens at the end of the update, so the orphan system sees it one update later
+                let parent_alive = frame < i;
+                let descendants_alive = frame < i + 1;
+
+                assert_eq!(remaining.contains(&parent), parent_alive, "parent {} on frame {}", i, frame);
+                assert!(descendants.iter().all(|descendant| remaining.contains(descendant) == descendants_alive),
+                        "family {} on frame {}", i, frame);
+            }
+        }
+    }
+}
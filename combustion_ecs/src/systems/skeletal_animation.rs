@@ -0,0 +1,41 @@
+//! Skeletal animation system, which plays every skeletal animator's clip and recomputes its skinning matrices each tick
+
+use specs;
+use specs::Join;
+
+pub struct System;
+
+impl<C: Into<f64>> specs::System<C> for System {
+    fn run(&mut self, arg: specs::RunArg, delta: C) {
+        use ::components::animation::skeletal_animator::Component as SkeletalAnimator;
+
+        use ::animation_events::{AnimationEvent, Resource as AnimationEvents};
+
+        let delta = delta.into() as f32;
+
+        let (ref mut events, ref mut animators, ref entities) = arg.fetch(|world| {
+            (
+                world.write_resource::<AnimationEvents>(),
+                world.write::<SkeletalAnimator>(),
+                world.entities(),
+            )
+        });
+
+        for (animator, entity) in (animators, entities).iter() {
+            if !animator.playing {
+                continue;
+            }
+
+            let (skeleton, clip) = match (animator.skeleton.get(), animator.clip.get()) {
+                (Some(skeleton), Some(clip)) => (skeleton, clip),
+                _ => continue,
+            };
+
+            animator.advance(&*clip, delta, &mut |event| {
+                events.push(AnimationEvent { entity: entity, name: event.name.clone(), time: event.time });
+            });
+
+            animator.update_pose(&skeleton, &clip);
+        }
+    }
+}
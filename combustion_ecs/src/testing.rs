@@ -0,0 +1,18 @@
+//! Helpers for the tests in this crate, only compiled along with them
+
+/// Small deterministic random number generator, so failures can be reproduced
+pub struct Lcg(pub u32);
+
+impl Lcg {
+    /// Somewhere from zero up to but not including `max`
+    pub fn next(&mut self, max: usize) -> usize {
+        self.0 = self.0.wrapping_mul(1664525).wrapping_add(1013904223);
+
+        (self.0 >> 8) as usize % max
+    }
+
+    /// Somewhere between `min` and `max`
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * (self.next(1 << 16) as f32 / (1 << 16) as f32)
+    }
+}
@@ -0,0 +1,219 @@
+//! The Time resource holds the timing of the current frame and of the fixed update ticks, updated once per frame by the render loop
+//!
+//! Systems should read their timing from here rather than the clock, so they all agree on it and it can be scaled.
+//! Scaled time runs at `time_scale` times real time, and is what the simulation follows. Setting the scale to zero
+//! pauses it, since no fixed update ticks become due. Unscaled time always follows real time, for things like UI
+//! that should carry on while the game is in slow motion or paused.
+//!
+//! Totals are kept in seconds as `f64`, which stays precise to well under a microsecond for years of running.
+//! Frame and tick lengths are `f32`, like the components the systems apply them to.
+
+#[derive(Copy, Clone, Debug)]
+pub struct Resource {
+    time_scale: f32,
+    delta: f32,
+    unscaled_delta: f32,
+    elapsed: f64,
+    /// Scaled time at the start of the frame, kept instead of subtracting the delta so `every_seconds` is exact
+    previous_elapsed: f64,
+    unscaled_elapsed: f64,
+    frames: u64,
+    fixed_dt: f32,
+    alpha: f32,
+    frame_ticks: u32,
+    ticks: u64,
+}
+
+impl Default for Resource {
+    #[inline(always)]
+    fn default() -> Resource { Resource::new() }
+}
+
+impl Resource {
+    pub fn new() -> Resource {
+        Resource {
+            time_scale: 1.0,
+            delta: 0.0,
+            unscaled_delta: 0.0,
+            elapsed: 0.0,
+            previous_elapsed: 0.0,
+            unscaled_elapsed: 0.0,
+            frames: 0,
+            fixed_dt: 0.0,
+            alpha: 0.0,
+            frame_ticks: 0,
+            ticks: 0,
+        }
+    }
+
+    /// Multiplier from real time to game time. 1 is normal speed, between 0 and 1 is slow motion, and 0 is paused.
+    #[inline]
+    pub fn time_scale(&self) -> f32 { self.time_scale }
+
+    /// Changes the time scale from the next frame on. Negative or invalid scales are ignored.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        if time_scale >= 0.0 && time_scale.is_finite() {
+            self.time_scale = time_scale;
+        } else {
+            warn!("Invalid time scale {}, keeping {}", time_scale, self.time_scale);
+        }
+    }
+
+    /// Checks if the time scale has paused the game
+    #[inline]
+    pub fn is_paused(&self) -> bool { self.time_scale == 0.0 }
+
+    /// Scaled length of the last frame, in seconds
+    #[inline]
+    pub fn delta(&self) -> f32 { self.delta }
+
+    /// Real length of the last frame, in seconds
+    #[inline]
+    pub fn unscaled_delta(&self) -> f32 { self.unscaled_delta }
+
+    /// Scaled time since the render loop started, in seconds
+    #[inline]
+    pub fn elapsed(&self) -> f64 { self.elapsed }
+
+    /// Real time since the render loop started, in seconds
+    #[inline]
+    pub fn unscaled_elapsed(&self) -> f64 { self.unscaled_elapsed }
+
+    /// Frames since the render loop started
+    #[inline]
+    pub fn frames(&self) -> u64 { self.frames }
+
+    /// Length of every fixed update tick in game time, in seconds
+    #[inline]
+    pub fn fixed_dt(&self) -> f32 { self.fixed_dt }
+
+    /// How far the current frame is between the last tick and the next, from 0 to 1,
+    /// for drawing positions interpolated between the two
+    #[inline]
+    pub fn alpha(&self) -> f32 { self.alpha }
+
+    /// Fixed update ticks run for the current frame
+    #[inline]
+    pub fn frame_ticks(&self) -> u32 { self.frame_ticks }
+
+    /// Fixed update ticks run since the render loop started
+    #[inline]
+    pub fn ticks(&self) -> u64 { self.ticks }
+
+    /// Checks if scaled time passed a multiple of `interval` seconds this frame, for work that only needs doing every so often.
+    ///
+    /// Intervals shorter than a frame are only reported once per frame, and nothing is reported while paused.
+    pub fn every_seconds(&self, interval: f64) -> bool {
+        interval > 0.0 && (self.elapsed / interval).floor() > (self.previous_elapsed / interval).floor()
+    }
+
+    /// Starts a new frame that took `unscaled_delta` real seconds. Returns the scaled length of the frame,
+    /// which is how much game time the fixed update ticks should catch up on.
+    pub fn advance(&mut self, unscaled_delta: f64) -> f64 {
+        let unscaled_delta = unscaled_delta.max(0.0);
+        let delta = unscaled_delta * self.time_scale as f64;
+
+        self.previous_elapsed = self.elapsed;
+        self.elapsed += delta;
+        self.unscaled_elapsed += unscaled_delta;
+
+        self.delta = delta as f32;
+        self.unscaled_delta = unscaled_delta as f32;
+
+        self.frames += 1;
+
+        delta
+    }
+
+    /// Records the fixed update ticks run for the current frame
+    pub fn set_fixed_steps(&mut self, ticks: u32, dt: f32, alpha: f32) {
+        self.frame_ticks = ticks;
+        self.fixed_dt = dt;
+        self.alpha = alpha;
+        self.ticks += ticks as u64;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_time_scale() {
+        let mut time = Resource::new();
+
+        assert_eq!(time.advance(0.5), 0.5);
+
+        time.set_time_scale(0.25);
+
+        assert_eq!(time.advance(1.0), 0.25);
+        assert_eq!(time.delta(), 0.25);
+        assert_eq!(time.unscaled_delta(), 1.0);
+        assert_eq!(time.elapsed(), 0.75);
+        assert_eq!(time.unscaled_elapsed(), 1.5);
+
+        // Paused, game time stands still and nothing throttled runs, but real time carries on
+        time.set_time_scale(0.0);
+
+        assert!(time.is_paused());
+
+        for _ in 0..100 {
+            assert_eq!(time.advance(1.0), 0.0);
+            assert!(!time.every_seconds(0.1));
+        }
+
+        assert_eq!(time.elapsed(), 0.75);
+        assert_eq!(time.unscaled_elapsed(), 101.5);
+
+        time.set_time_scale(-1.0);
+        time.set_time_scale(::std::f32::NAN);
+
+        assert_eq!(time.time_scale(), 0.0);
+    }
+
+    #[test]
+    fn test_long_session() {
+        const FRAME: f64 = 1.0 / 60.0;
+        const HOURS: u64 = 4;
+
+        let frames = HOURS * 60 * 60 * 60;
+
+        let mut time = Resource::new();
+
+        let mut every_second = 0;
+        let mut every_minute = 0;
+
+        //Simulated frames of slightly uneven length, averaging to 60Hz
+        for frame in 0..frames {
+            let jitter = if frame % 2 == 0 { 0.001 } else { -0.001 };
+
+            time.advance(FRAME + jitter);
+            time.set_fixed_steps(1, FRAME as f32, 0.0);
+
+            if time.every_seconds(1.0) {
+                every_second += 1;
+            }
+
+            if time.every_seconds(60.0) {
+                every_minute += 1;
+            }
+        }
+
+        let expected = (HOURS * 60 * 60) as f64;
+
+        assert_eq!(time.frames(), frames);
+        assert_eq!(time.ticks(), frames);
+        assert!((time.elapsed() - expected).abs() < 1e-5, "{} drifted from {}", time.elapsed(), expected);
+        assert!((time.unscaled_elapsed() - expected).abs() < 1e-5);
+
+        // Each crossing is seen exactly once, give or take the last one landing right on the final frame
+        assert!(every_second == expected as u64 || every_second == expected as u64 - 1, "{}", every_second);
+        assert!(every_minute == HOURS * 60 || every_minute == HOURS * 60 - 1, "{}", every_minute);
+
+        // Late in the session the deltas are as precise as they were at the start
+        time.advance(FRAME);
+
+        assert_eq!(time.delta(), FRAME as f32);
+        assert!((time.elapsed() - expected - FRAME).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,72 @@
+//! The VisibleSet resource holds the entities inside the view frustum this update, written by the culling system
+
+use specs;
+
+#[derive(Clone, Debug)]
+pub struct Resource {
+    /// Visibility of each entity, indexed by entity ID
+    visible: Vec<bool>,
+    enabled: bool,
+    visible_count: usize,
+    culled_count: usize,
+}
+
+impl Default for Resource {
+    #[inline(always)]
+    fn default() -> Resource { Resource::new() }
+}
+
+impl Resource {
+    pub fn new() -> Resource {
+        Resource { visible: Vec::new(), enabled: true, visible_count: 0, culled_count: 0 }
+    }
+
+    /// Whether culling is enabled. When it isn't, every entity is visible, which can help when debugging culling.
+    #[inline]
+    pub fn is_enabled(&self) -> bool { self.enabled }
+
+    #[inline]
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Checks if an entity was found visible this update
+    #[inline]
+    pub fn contains(&self, entity: specs::Entity) -> bool {
+        self.visible.get(entity.get_id() as usize).cloned().unwrap_or(false)
+    }
+
+    /// Number of entities found visible this update
+    #[inline]
+    pub fn visible(&self) -> usize { self.visible_count }
+
+    /// Number of entities culled this update
+    #[inline]
+    pub fn culled(&self) -> usize { self.culled_count }
+
+    /// Forgets last update's classification before a new one
+    pub fn clear(&mut self) {
+        for visible in &mut self.visible {
+            *visible = false;
+        }
+
+        self.visible_count = 0;
+        self.culled_count = 0;
+    }
+
+    /// Records whether an entity is visible this update
+    pub fn mark(&mut self, entity: specs::Entity, visible: bool) {
+        if visible {
+            let id = entity.get_id() as usize;
+
+            if id >= self.visible.len() {
+                self.visible.resize(id + 1, false);
+            }
+
+            self.visible[id] = true;
+            self.visible_count += 1;
+        } else {
+            self.culled_count += 1;
+        }
+    }
+}
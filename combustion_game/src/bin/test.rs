@@ -5,6 +5,24 @@ use game::core;
 use core::common::utils::*;
 use core::common::utils::human_readable::*;
 
+use core::input::{Bindings, InputAction, BINDINGS_FILE};
+use core::input::bindings::DEFAULT_BINDINGS;
+
 fn main() {
     println!("{}", humanize_si(1032.5));
-}
\ No newline at end of file
+
+    let bindings = Bindings::load(BINDINGS_FILE).unwrap_or_else(|err| {
+        println!("Couldn't load key bindings from {}, using the defaults: {}", BINDINGS_FILE, err);
+
+        Bindings::default()
+    });
+
+    //Every action has a default chord, so the defaults name all of them
+    for name in DEFAULT_BINDINGS.lines().filter_map(|line| line.split('=').next()).map(str::trim) {
+        if let Some(action) = InputAction::from_name(name) {
+            let chords: Vec<String> = bindings.chords(action).iter().map(|chord| chord.to_string()).collect();
+
+            println!("{} = {}", action, chords.join(", "));
+        }
+    }
+}
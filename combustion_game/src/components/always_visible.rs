@@ -0,0 +1,5 @@
+//! Always visible component
+//!
+//! See `core::ecs::components::always_visible`, which this re-exports so games can find it alongside the other components.
+
+pub use core::ecs::components::always_visible::*;
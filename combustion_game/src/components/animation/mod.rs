@@ -0,0 +1,5 @@
+//! Keyframe animation components
+//!
+//! See `core::ecs::components::animation`, which this re-exports so games can find it alongside the other components.
+
+pub use core::ecs::components::animation::*;
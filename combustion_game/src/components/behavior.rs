@@ -0,0 +1,5 @@
+//! Behavior component, for small one-off behaviors that don't deserve a system of their own
+//!
+//! See `core::ecs::components::behavior`, which this re-exports so games can find it alongside the other components.
+
+pub use core::ecs::components::behavior::*;
@@ -0,0 +1,10 @@
+//! Billboard component, a quad turned to face the camera for things like particles, labels and health bars
+//!
+//! See `core::ecs::components::billboard`. This is it for billboards textured with GPU textures.
+
+use core::ecs::components::billboard;
+use core::backend::gl::GLTexture;
+
+pub use core::ecs::components::billboard::{Facing, Size};
+
+pub type Component = billboard::Component<GLTexture>;
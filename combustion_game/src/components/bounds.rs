@@ -0,0 +1,5 @@
+//! Bounds component, the local space bounding box of an entity used for culling
+//!
+//! See `core::ecs::components::bounds`, which this re-exports so games can find it alongside the other components.
+
+pub use core::ecs::components::bounds::*;
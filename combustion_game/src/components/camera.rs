@@ -1,12 +1,5 @@
-use specs;
+//! Camera component, describing the projection of an entity that can be viewed through
+//!
+//! See `core::ecs::components::camera`, which this re-exports so games can find it alongside the other components.
 
-use ::resources::projection::Resource as Projection;
-
-pub use ::resources::projection::Kind;
-
-/// The `Component` is really a `Projection` resource that can be applied to an entity to achieve dynamic cameras
-pub type Component = Projection;
-
-impl specs::Component for Component {
-    type Storage = specs::VecStorage<Component>;
-}
\ No newline at end of file
+pub use core::ecs::components::camera::*;
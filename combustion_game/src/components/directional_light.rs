@@ -0,0 +1,5 @@
+//! Directional light component, infinitely far away and shining down the entity's local -Z axis
+//!
+//! See `core::ecs::components::directional_light`, which this re-exports so games can find it alongside the other components.
+
+pub use core::ecs::components::directional_light::*;
@@ -8,6 +8,7 @@ use std::mem;
 
 use specs;
 use assimp::components::mesh::Mesh;
+use nalgebra::Point3;
 
 use ::backend::gl::*;
 use ::backend::gl::types::*;
@@ -25,10 +26,13 @@ pub enum BufferField {
     Bitangent,
 }
 
+pub use core::ecs::bounds::Bounds;
+
 /// Contains all the OpenGL buffers for an entity
 pub struct Buffer {
     vao: GLVertexArray,
     num_indices: usize,
+    bounds: Option<Bounds>,
     index_buffer: Option<GLBuffer>,
     vertex_buffer: Option<GLBuffer>,
     normal_buffer: Option<GLBuffer>,
@@ -48,6 +52,7 @@ impl Buffer {
         Ok(Buffer {
             vao: try!(GLVertexArray::new()),
             num_indices: 0,
+            bounds: None,
             index_buffer: None,
             vertex_buffer: None,
             normal_buffer: None,
@@ -61,6 +66,10 @@ impl Buffer {
     #[inline(always)]
     pub fn num_indices(&self) -> usize { self.num_indices }
 
+    /// Object space bounds of the buffered vertices, or `None` if there are none
+    #[inline(always)]
+    pub fn bounds(&self) -> Option<&Bounds> { self.bounds.as_ref() }
+
     /// Binds the entity VAO, allowing it to be rendered
     #[inline(always)]
     pub fn bind(&self) -> GLResult<()> { self.vao.bind() }
@@ -112,6 +121,8 @@ impl Buffer {
 
     fn buffer_vertices<'a>(&mut self, mesh: &'a Mesh<'a>, usage: GLBufferUsage) -> GLResult<()> {
         if let Some(vertices) = mesh.vertices() {
+            self.bounds = Bounds::from_points(vertices.iter().map(|v| Point3::new(v.x, v.y, v.z)));
+
             let mut missing_buffer = false;
 
             if let Some(mut buffer) = self.vertex_buffer.as_mut() {
@@ -0,0 +1,5 @@
+//! Group component, listing the named groups an entity belongs to
+//!
+//! See `core::ecs::components::group`, which this re-exports so games can find it alongside the other components.
+
+pub use core::ecs::components::group::*;
@@ -0,0 +1,5 @@
+//! Transform hierarchy components
+//!
+//! See `core::ecs::components::hierarchy`, which this re-exports so games can find it alongside the other components.
+
+pub use core::ecs::components::hierarchy::*;
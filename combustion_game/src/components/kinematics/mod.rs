@@ -0,0 +1,5 @@
+//! Kinematic motion components
+//!
+//! See `core::ecs::components::kinematics`, which this re-exports so games can find it alongside the other components.
+
+pub use core::ecs::components::kinematics::*;
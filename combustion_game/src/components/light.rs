@@ -1,7 +1,7 @@
 //! Lighted component
 
 use specs;
-use nalgebra::Point3;
+use nalgebra::{Point3, Vector3};
 
 #[derive(Copy, Clone, Debug)]
 pub enum Kind {
@@ -59,6 +59,8 @@ pub struct Component {
     pub enabled: bool,
     /// Light type
     pub kind: Kind,
+    /// Linear RGB color
+    pub color: Vector3<f32>,
     /// Light intensity
     pub intensity: f32,
 }
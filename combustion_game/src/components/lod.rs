@@ -0,0 +1,11 @@
+//! Level of detail component, swapping an entity's mesh for simpler ones as it gets smaller on screen
+//!
+//! See `core::ecs::components::lod`. This is it for the GPU meshes mesh renderers draw.
+
+use core::ecs::components::lod;
+
+use ::components::mesh_renderer::Mesh;
+
+pub type Level = lod::Level<Mesh>;
+
+pub type Component = lod::Component<Mesh>;
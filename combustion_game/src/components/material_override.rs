@@ -0,0 +1,11 @@
+//! Material override component, replacing some parameters of an entity's materials without touching the shared ones
+//!
+//! See `core::ecs::components::material_override`. This is it for overriding the GPU materials mesh renderers draw with,
+//! whose parameters are listed with their `Overridable` implementation.
+
+use core::ecs::components::material_override;
+use core::backend::gl::GLTexture;
+
+pub use core::ecs::components::material_override::Value;
+
+pub type Component = material_override::Component<GLTexture>;
@@ -0,0 +1,56 @@
+//! Mesh renderer component, drawing a mesh on the GPU with one material per submesh
+//!
+//! See `core::ecs::components::mesh_renderer`. These are the GPU meshes and materials it draws with.
+
+use core::ecs::components::mesh_renderer;
+use core::ecs::components::bounds::MeshBounds;
+use core::graphics::pipeline::indirect::MeshRange;
+use core::graphics::pipeline::material::BoundMaterial;
+
+use ::components::gpu_buffer::{LazyBufferSync, Bounds};
+
+/// Part of a mesh drawn with a single material
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Submesh {
+    pub range: MeshRange,
+    /// Index into the renderer's materials
+    pub material: usize,
+}
+
+/// Mesh buffered to the GPU, along with its submesh table
+#[derive(Clone)]
+pub struct Mesh {
+    pub buffer: LazyBufferSync,
+    /// An empty table draws the whole buffer with the first material
+    pub submeshes: Vec<Submesh>,
+    /// Object space bounds stored with the mesh asset, copied into the entity's bounds component
+    pub bounds: Option<Bounds>,
+}
+
+impl Mesh {
+    #[inline]
+    pub fn new(buffer: LazyBufferSync) -> Mesh {
+        Mesh { buffer: buffer, submeshes: Vec::new(), bounds: None }
+    }
+
+    #[inline]
+    pub fn with_submesh(mut self, range: MeshRange, material: usize) -> Mesh {
+        self.submeshes.push(Submesh { range: range, material: material });
+        self
+    }
+
+    #[inline]
+    pub fn with_bounds(mut self, bounds: Bounds) -> Mesh {
+        self.bounds = Some(bounds);
+        self
+    }
+}
+
+impl MeshBounds for Mesh {
+    #[inline]
+    fn bounds(&self) -> Option<Bounds> { self.bounds }
+}
+
+pub type Material = BoundMaterial;
+
+pub type Component = mesh_renderer::Component<Mesh, Material>;
@@ -3,10 +3,17 @@
 use specs;
 
 pub mod node;
+pub mod name;
+pub mod scene_id;
+pub mod scene_origin;
 pub mod renderable;
 pub mod effector;
 pub mod model;
 pub mod mesh;
+pub mod mesh_renderer;
+pub mod bounds;
+pub mod world_bounds;
+pub mod always_visible;
 pub mod material;
 pub mod instanced;
 pub mod position;
@@ -17,15 +24,34 @@ pub mod scale;
 pub mod transform;
 pub mod camera;
 pub mod light;
+pub mod point_light;
+pub mod spot_light;
+pub mod directional_light;
 pub mod physics;
+pub mod behavior;
+pub mod tag;
+pub mod group;
+pub mod billboard;
+pub mod lod;
+pub mod material_override;
 
 pub mod constraints;
+pub mod hierarchy;
+pub mod animation;
+pub mod kinematics;
 
 pub fn register_all(world: &mut specs::World) {
     ecs_register_mod!(world, node);
+    ecs_register_mod!(world, name);
+    ecs_register_mod!(world, scene_id);
+    ecs_register_mod!(world, scene_origin);
     ecs_register_mod!(world, renderable);
     ecs_register_mod!(world, effector);
     ecs_register_mod!(world, mesh);
+    ecs_register_mod!(world, mesh_renderer);
+    ecs_register_mod!(world, bounds);
+    ecs_register_mod!(world, world_bounds);
+    ecs_register_mod!(world, always_visible);
     ecs_register_mod!(world, model);
     ecs_register_mod!(world, material);
     ecs_register_mod!(world, instanced);
@@ -37,7 +63,18 @@ pub fn register_all(world: &mut specs::World) {
     ecs_register_mod!(world, transform);
     ecs_register_mod!(world, camera);
     ecs_register_mod!(world, light);
+    ecs_register_mod!(world, point_light);
+    ecs_register_mod!(world, spot_light);
+    ecs_register_mod!(world, directional_light);
     ecs_register_mod!(world, physics);
+    ecs_register_mod!(world, behavior);
+    ecs_register_mod!(world, group);
+    ecs_register_mod!(world, billboard);
+    ecs_register_mod!(world, lod);
+    ecs_register_mod!(world, material_override);
 
     constraints::register_all(world);
+    hierarchy::register_all(world);
+    animation::register_all(world);
+    kinematics::register_all(world);
 }
\ No newline at end of file
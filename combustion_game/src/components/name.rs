@@ -0,0 +1,5 @@
+//! Name component, for finding entities and showing them in tools
+//!
+//! See `core::ecs::components::name`, which this re-exports so games can find it alongside the other components.
+
+pub use core::ecs::components::name::*;
@@ -0,0 +1,5 @@
+//! Point light component, shining in every direction from the entity's world position
+//!
+//! See `core::ecs::components::point_light`, which this re-exports so games can find it alongside the other components.
+
+pub use core::ecs::components::point_light::*;
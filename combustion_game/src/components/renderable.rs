@@ -1,22 +1,5 @@
 //! Renderable component
 //!
-//! This component is requiered for any entity which should appear on screen
+//! See `core::ecs::components::renderable`, which this re-exports so games can find it alongside the other components.
 
-use specs;
-
-#[derive(Clone, Copy)]
-pub struct Component;
-
-impl specs::Component for Component {
-    type Storage = specs::VecStorage<Component>;
-}
-
-impl Default for Component {
-    #[inline(always)]
-    fn default() -> Component { Component }
-}
-
-impl Component {
-    #[inline(always)]
-    pub fn new() -> Component { Component }
-}
\ No newline at end of file
+pub use core::ecs::components::renderable::*;
@@ -0,0 +1,6 @@
+//! Scene ID component
+//!
+//! Given to entities loaded from a scene file, so saving the world again gives them the same IDs.
+//! Entities without one are given a new ID when saved. See `core::ecs::scene::SceneId`.
+
+pub use core::ecs::scene::SceneId as Component;
@@ -0,0 +1,5 @@
+//! Scene origin component, given to entities loaded from a scene file that's reloaded when it changes
+//!
+//! See `core::ecs::components::scene_origin`, which this re-exports so games can find it alongside the other components.
+
+pub use core::ecs::components::scene_origin::*;
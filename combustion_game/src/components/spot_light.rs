@@ -0,0 +1,5 @@
+//! Spotlight component, shining a cone down the entity's local -Z axis from its world position
+//!
+//! See `core::ecs::components::spot_light`, which this re-exports so games can find it alongside the other components.
+
+pub use core::ecs::components::spot_light::*;
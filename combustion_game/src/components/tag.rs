@@ -0,0 +1,5 @@
+//! Tag component, a zero sized marker for sorting entities into kinds like enemies or pickups
+//!
+//! See `core::ecs::components::tag`, which this re-exports so games can find it alongside the other components.
+
+pub use core::ecs::components::tag::*;
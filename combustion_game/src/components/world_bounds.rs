@@ -0,0 +1,5 @@
+//! World bounds component
+//!
+//! See `core::ecs::components::world_bounds`, which this re-exports so games can find it alongside the other components.
+
+pub use core::ecs::components::world_bounds::*;
@@ -17,16 +17,13 @@ impl super::Entity<Entity> for Entity {
         use components::rotation::Component as Rotation;
         use components::isometry::Component as Isometry;
         use components::camera::Component as Camera;
-        use components::constraints::lookat::{Component as LookAtConstraint, LookAtTarget};
-        use ::game::components::bob::Component as Bob;
 
         let camera = world.create_now()
                           .with(Transform::new())
-                          .with(Position(Point3::new(0.0, 0.0, 0.0)))
+                          //Moved and turned by the camera controller system, starting out looking at the origin
+                          .with(Position(Point3::new(0.0, 0.0, 3.0)))
                           .with(Isometry::empty())
-                          .with(Bob::new())
-                          .with(LookAtConstraint::new(LookAtTarget::Position(Point3::new(0.0, 0.0, 0.0))))
-                          .with(Camera::new_perspective(16.0 / 9.0, 70.0f32.to_radians(), 0.1, 1000.0))
+                          .with(Camera::perspective(70.0f32.to_radians(), 0.1, 1000.0))
                           .build();
 
         Ok(Entity(camera))
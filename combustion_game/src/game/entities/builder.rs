@@ -0,0 +1,49 @@
+//! Fluent helpers for spawning entities, and the `entity!` macro built on them
+//!
+//! See `core::ecs::spawn`, which this re-exports along with `with_mesh` for the GPU meshes mesh renderers draw with.
+
+use std::path::Path;
+
+use specs;
+
+use core::asset::handle::AssetHandle;
+
+use ::components::mesh_renderer::Component as MeshRenderer;
+
+pub use core::ecs::spawn::*;
+
+/// Spawns an entity in `$world` by chaining calls to `Spawn` methods, and returns it.
+///
+/// Bring `SpawnMeshExt` into scope to use `with_mesh`.
+#[macro_export]
+macro_rules! entity {
+    ($world:expr $(, $method:ident ( $($arg:expr),* ))* $(,)*) => {
+        $crate::game::entities::builder::Spawn::new($world.create_now()) $(.$method($($arg),*))* .build()
+    };
+}
+
+/// Spawning with the mesh asset at a path
+pub trait SpawnMeshExt<'a> {
+    type Spawned;
+
+    /// Draws the mesh asset at `path` once it has loaded, with bounds following it for culling
+    fn with_mesh<P: AsRef<Path>>(self, path: P) -> Self::Spawned;
+}
+
+impl<'a, S> SpawnMeshExt<'a> for Spawn<'a, S> {
+    type Spawned = Spawn<'a, S>;
+
+    #[inline]
+    fn with_mesh<P: AsRef<Path>>(self, path: P) -> Spawn<'a, S> {
+        self.with_renderer(MeshRenderer::new(AssetHandle::at(path)))
+    }
+}
+
+impl<'a> SpawnMeshExt<'a> for specs::EntityBuilder<'a> {
+    type Spawned = Spawn<'a, Unplaced>;
+
+    #[inline]
+    fn with_mesh<P: AsRef<Path>>(self, path: P) -> Spawn<'a, Unplaced> {
+        Spawn::new(self).with_mesh(path)
+    }
+}
@@ -1 +1,4 @@
-pub mod test_entities;
\ No newline at end of file
+#[macro_use]
+pub mod builder;
+
+pub mod test_entities;
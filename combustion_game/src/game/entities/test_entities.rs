@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use specs;
 use nalgebra::*;
+use num_traits::One;
 use assimp;
 
 use error::*;
@@ -11,6 +12,51 @@ use systems;
 
 use scene::Scene;
 
+use ::components::point_light::Component as PointLight;
+use ::components::hierarchy::transform::Component as HierarchyTransform;
+
+use super::builder::SpawnMeshExt;
+
+/// Entities spawned by `spawn_test_scene`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TestScene {
+    pub root: specs::Entity,
+    pub floor: specs::Entity,
+    pub statue: specs::Entity,
+    pub lamp: specs::Entity,
+}
+
+/// Spawns a small scene of a statue on a floor, lit by a lamp above it, using meshes from the `models` directory
+pub fn spawn_test_scene(world: &mut specs::World) -> TestScene {
+    let root = entity!(world,
+        named("test scene"),
+        with_transform(HierarchyTransform::new())
+    );
+
+    let floor = entity!(world,
+        named("floor"),
+        with_transform(HierarchyTransform::from_parts(Vector3::new(0.0, -0.05, 0.0), Quaternion::one(), Vector3::new(10.0, 0.1, 10.0))),
+        child_of(root),
+        with_mesh("models/cube.cmdl")
+    );
+
+    let statue = entity!(world,
+        named("statue"),
+        with_transform(HierarchyTransform::from_translation(0.0, 0.5, 0.0)),
+        child_of(root),
+        with_mesh("models/buddha.cmdl")
+    );
+
+    let lamp = entity!(world,
+        named("lamp"),
+        with_transform(HierarchyTransform::from_translation(0.0, 2.0, 0.0)),
+        child_of(statue),
+        with_point_light(PointLight::white(4.0, 6.0))
+    );
+
+    TestScene { root: root, floor: floor, statue: statue, lamp: lamp }
+}
+
 pub fn load(mut scene: &mut Scene) -> AppResult<()> {
     scene.with_world_sources(|mut world: &mut specs::World, mut sources| -> AppResult<()> {
         use ::components::transform::Component as Transform;
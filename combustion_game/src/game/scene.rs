@@ -1,15 +1,27 @@
 //! Game scene setup
 
-use specs;
+use ::systems::schedule::Schedule;
 
 use super::systems;
 
-pub fn add_systems(mut planner: &mut specs::Planner<systems::Delta>) {
-    planner.add_system(systems::turntable::System, "TurntableSystem",
-                       systems::Priorities::Turntable as specs::Priority);
+pub fn add_systems(schedule: &mut Schedule) {
+    use ::components::position::Component as Position;
+    use ::components::rotation::Component as Rotation;
+    use ::game::components::bob::Component as Bob;
+    use ::game::components::turntable::Component as Turntable;
+    use ::resources::event_queue::Resource as EventQueue;
 
-    planner.add_system(systems::blackhole::System, "BlackholeSystem",
-                       systems::Priorities::Blackhole as specs::Priority);
+    schedule.add(systems::turntable::System, "TurntableSystem")
+            .reads::<Turntable>()
+            .writes::<Rotation>()
+            .before("TransformSystem");
 
-    planner.add_system(systems::bob::System, "BobSystem", systems::Priorities::Bob as specs::Priority);
-}
\ No newline at end of file
+    schedule.add(systems::blackhole::System, "BlackholeSystem")
+            .writes_resource::<EventQueue>();
+
+    schedule.add(systems::bob::System, "BobSystem")
+            .writes::<Bob>()
+            .writes::<Position>()
+            .after("CameraControllerSystem")
+            .before("TransformSystem");
+}
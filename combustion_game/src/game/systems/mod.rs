@@ -5,9 +5,3 @@ pub mod blackhole;
 pub mod bob;
 
 pub use systems::Delta;
-
-pub enum Priorities {
-    Blackhole,
-    Turntable,
-    Bob,
-}
\ No newline at end of file
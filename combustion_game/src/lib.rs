@@ -12,7 +12,8 @@ extern crate num_traits;
 extern crate time;
 extern crate num_cpus;
 extern crate vec_map;
-extern crate lazy;
+extern crate capnp;
+extern crate smallvec;
 
 #[macro_use]
 extern crate combustion_macros;
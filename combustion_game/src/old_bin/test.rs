@@ -1,17 +1,67 @@
 extern crate engine;
 
+use std::any::Any;
 use std::thread;
 use std::sync::mpsc;
 use std::sync::atomic::{AtomicBool, ATOMIC_BOOL_INIT, Ordering};
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
-use glfw::{Glfw, Action, Context, Key, WindowHint, WindowEvent};
+use glfw::{Glfw, Action, Context, Key, MouseButton, WindowHint, WindowEvent};
 
 use backend::window::WindowBuilder;
 
 use error::*;
 
-use graphics::{RenderSignal, FullscreenToggle};
+use graphics::{RenderSignal, RenderReply, FrameStats, FullscreenToggle, VsyncMode, FileDropLoader, ShaderWatcher, SHADER_DIRECTORY};
+use graphics::pipeline::{DebugView, RasterMode};
+
+use input::{Bindings, InputAction, BINDINGS_FILE, Gamepads, MouseLook, TextCapture};
+
+const TARGET_FPS: f64 = 60.0;
+
+const TITLE: &'static str = "Combustion";
+
+/// How often events are checked while nothing else is happening, in seconds
+const EVENT_TIMEOUT: f64 = 0.25;
+
+/// How often gamepads are polled while any are connected, in seconds, since GLFW doesn't report them as events
+const GAMEPAD_POLL_INTERVAL: f64 = 1.0 / 120.0;
+
+/// Longest the window title goes between frame statistics updates
+const TITLE_UPDATE_INTERVAL_MS: u64 = 1000;
+
+fn panic_message(panic: &Box<Any + Send>) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+fn stats_title(stats: &FrameStats, paused: bool) -> String {
+    format!("{} - {}{:.1} FPS ({:.2}ms frame, {:.2}ms CPU, {:.2}ms GPU, {:.2}ms 99th percentile, {} drawn, {} culled, {}/{} state changes skipped)",
+            TITLE, if paused { "Paused - " } else { "" },
+            stats.fps_avg, stats.summary.average_ms, stats.cpu_ms, stats.gpu_ms, stats.summary.p99_ms, stats.objects_drawn, stats.objects_culled,
+            stats.state_changes_skipped, stats.state_changes_issued + stats.state_changes_skipped)
+}
+
+/// Frame statistics as copied to the clipboard
+fn stats_report(stats: &FrameStats) -> String {
+    let systems: Vec<String> = stats.systems.iter().map(|system| format!("{} {:.3}ms", system.name, system.ms)).collect();
+
+    format!("Frame {}: {}, {:.2}ms CPU, {:.2}ms GPU, {} drawn, {} culled, {} material binds, {} failed frames\nSystems: {}",
+            stats.frame_number, stats.summary, stats.cpu_ms, stats.gpu_ms, stats.objects_drawn, stats.objects_culled,
+            stats.material_binds, stats.failed_frames, systems.join(", "))
+}
+
+fn console_title(line: &str, cursor: usize) -> String {
+    let (before, after): (String, String) = (line.chars().take(cursor).collect(), line.chars().skip(cursor).collect());
+
+    format!("{} - Console: > {}|{}", TITLE, before, after)
+}
 
 fn main() {
     common::log::init_global_logger("logs").expect("Could not initialize logging system!");
@@ -28,7 +78,7 @@ fn main() {
             WindowHint::DoubleBuffer(true),
             //WindowHint::OpenGlDebugContext(true),
         ])
-        .title("Combustion")
+        .title(TITLE)
         .create()
         .expect_logged_box("Couldn't create window");
 
@@ -40,12 +90,20 @@ fn main() {
     //Load up all the OpenGL functions from the process
     backend::gl::bindings::load_all_with(|symbol| window.get_proc_address(symbol) as *const _);
 
+    info!("{}", backend::gl::GLCapabilities::query().expect_logged("Couldn't query OpenGL capabilities"));
+
+    //Reuse linked shader programs from previous runs
+    backend::gl::set_program_cache_directory(Some("cache/shaders")).expect_logged("Couldn't set shader cache directory");
+
     //Enable debugging of OpenGL messages
     //backend::gl::enable_debug(backend::gl::default_debug_callback, true).unwrap();
 
     //Create channel for forwarding events to the render thread
     let (tx, rx) = mpsc::channel();
 
+    //And a bounded one for frame statistics and pick results coming back, so an unresponsive main thread can't pile them up
+    let (reply_tx, reply_rx) = mpsc::sync_channel::<RenderReply>(8);
+
     // Disconnect current context
     glfw::make_context_current(None);
 
@@ -65,12 +123,12 @@ fn main() {
         //Make the OpenGL context active on the render thread
         glfw::make_context_current(Some(&context));
 
-        let mut state: RenderLoopState = RenderLoopState::new(60.0);
+        let mut state: RenderLoopState = RenderLoopState::new(TARGET_FPS);
 
         state.unpause();
 
         {
-            let res = graphics::render::start(&mut state, context, &rx);
+            let res = graphics::render::start(&mut state, context, &rx, &reply_tx, |_, _| Ok(()));
 
             render_running.store(false, Ordering::SeqCst);
 
@@ -80,54 +138,341 @@ fn main() {
             res
         }.expect_logged_box("Render thread crashed");
 
-        info!("Finished after {} frames", state.total_frames());
+        info!("Finished after {} frames, {}", state.total_frames(), state.stats_summary());
     }).expect_logged_box("Could not create Render thread");
 
+    //Keys are looked up through the bindings, so they can be changed without rebuilding
+    let bindings = Bindings::load(BINDINGS_FILE).unwrap_or_else(|err| {
+        error!("Couldn't load key bindings from {}, using the defaults: {}", BINDINGS_FILE, err);
+
+        Bindings::default()
+    });
+
     //Create fullscreen toggle in primary thread
     let mut fullscreen = FullscreenToggle::new();
 
+    //The wireframe and overdraw toggles switch back to filled rendering, so remember the last raster mode sent
+    let mut raster_mode = RasterMode::Fill;
+
+    //Cycling through debug views starts from the last one shown
+    let mut debug_view = DebugView::default();
+
+    //The frame rate limiter can be toggled off, for benchmarking
+    let mut uncapped = false;
+
+    let mut vsync = VsyncMode::default();
+
+    //Frame statistics in the window title show the last ones received and whether rendering is paused
+    let mut title_stats = true;
+    let mut paused = false;
+
+    //Pausing freezes the game entirely, until it's toggled again
+    let mut user_paused = false;
+    let mut latest_stats: Option<FrameStats> = None;
+    let mut last_title_update: Option<Instant> = None;
+
+    //Set when a signal couldn't be sent, because the render thread has stopped
+    let mut render_disconnected = false;
+
     macro_rules! send_and_unpark {
         ($event:expr) => ({
-            let ret = tx.send($event);
+            if tx.send($event).is_err() {
+                render_disconnected = true;
+            }
+
             render_thread.thread().unpark();
-            ret
         })
     }
 
+    //Cursor capture and movement keys for the mouse-look camera
+    let mut mouse_look = MouseLook::new();
+
+    //The console captures typed text while it's open, which suppresses the bindings
+    let mut text_capture = TextCapture::new();
+
+    //Files dropped onto the window are loaded in the background, with textures going straight to the render thread
+    let file_drops = FileDropLoader::spawn(tx.clone());
+
+    //Shaders are reloaded on request, and also whenever they change while the watcher is on
+    let mut shader_watcher: Option<ShaderWatcher> = None;
+
+    //While inspecting, every picked object is dumped to the log by the render thread
+    let mut inspecting = false;
+
+    //Gamepads are polled on this thread, which owns GLFW, and their state is sent on to the render thread
+    let mut gamepads = Gamepads::new();
+
+    //Framebuffer pixels per window coordinate, which is resent whenever it changes
+    let mut content_scale = graphics::content_scale(&window);
+
+    send_and_unpark!(RenderSignal::ContentScale(content_scale.0, content_scale.1));
+
     info!("Listening for events...");
 
     //Since the primary thread will do nothing but wait on events, do that
     'event_loop: while !window.should_close() {
-        //Instead of polling, actively block the thread since nothing else is happening in it
-        glfw.wait_events();
+        //Instead of polling, actively block the thread since nothing else is happening in it,
+        //but wake up periodically to pick up frame statistics and pick results from the render thread,
+        //and much more often while there are gamepads to poll
+        glfw.wait_events_timeout(if gamepads.state().any_connected() { GAMEPAD_POLL_INTERVAL } else { EVENT_TIMEOUT });
+
+        if running.load(Ordering::SeqCst) {
+            let gamepad_events = gamepads.poll(&glfw);
+
+            //Send the state after a disconnect too, so the last pad to go doesn't stay held down
+            if gamepads.state().any_connected() || !gamepad_events.is_empty() {
+                send_and_unpark!(RenderSignal::Gamepads(gamepads.take_state()));
+            }
+
+            for event in gamepad_events {
+                send_and_unpark!(RenderSignal::GamepadEvent(event));
+            }
+        }
+
+        for reply in reply_rx.try_iter() {
+            match reply {
+                RenderReply::Stats(stats) => {
+                    latest_stats = Some(stats);
+                }
+                RenderReply::Pick { x, y, object: Some(object) } => {
+                    info!("Picked object {} at ({}, {})", object, x, y);
+
+                    if inspecting {
+                        send_and_unpark!(RenderSignal::DumpEntity(object));
+                    }
+                }
+                RenderReply::Pick { x, y, object: None } => {
+                    info!("Nothing to pick at ({}, {})", x, y);
+                }
+                RenderReply::Stopped { frame, consecutive_failures } => {
+                    error!("Rendering stopped at frame {} after {} failed frames in a row", frame, consecutive_failures);
+                }
+                RenderReply::Console { line, cursor } => {
+                    if text_capture.is_active() {
+                        window.set_title(&console_title(&line, cursor));
+                    }
+                }
+            }
+        }
+
+        //The console line takes over the title while it's open
+        if title_stats && !text_capture.is_active() && last_title_update.map_or(true, |last| last.elapsed() >= Duration::from_millis(TITLE_UPDATE_INTERVAL_MS)) {
+            if let Some(ref stats) = latest_stats {
+                window.set_title(&stats_title(stats, paused));
+
+                last_title_update = Some(Instant::now());
+            }
+        }
 
         //While most events are simply forwarded to the
         for (_, event) in glfw::flush_messages(&events) {
             // Do NOT send any events if the render thread cannot accept them.
             if running.load(Ordering::SeqCst) {
                 match event {
-                    WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
-                        window.set_should_close(true);
+                    //While the console is open, keys only edit its line, except for the ones closing it
+                    WindowEvent::Key(key, _, Action::Press, modifiers) if text_capture.is_active() &&
+                        (key == Key::Escape || bindings.action(key, modifiers) == Some(InputAction::ToggleConsole)) => {
+                        text_capture.end();
+                        last_title_update = None;
+
+                        window.set_title(TITLE);
+
+                        send_and_unpark!(RenderSignal::TextCapture(false));
+                    }
+                    WindowEvent::Key(key, _, action, modifiers) if text_capture.is_active() => {
+                        if let Some(edit) = text_capture.key(&window, key, action, modifiers) {
+                            send_and_unpark!(RenderSignal::Text(edit));
+                        }
+                    }
+                    WindowEvent::Char(c) if text_capture.is_active() => {
+                        if let Some(edit) = text_capture.char(c) {
+                            send_and_unpark!(RenderSignal::Text(edit));
+                        }
+                    }
+                    WindowEvent::Key(key, _, Action::Press, modifiers) if bindings.action(key, modifiers).is_some() => {
+                        match bindings.action(key, modifiers).unwrap() {
+                            InputAction::Quit => {
+                                window.set_should_close(true);
+                            }
+                            InputAction::ToggleFullscreen => {
+                                fullscreen.toggle(&mut glfw, &mut window);
+                            }
+                            InputAction::ToggleBorderless => {
+                                fullscreen.toggle_borderless(&mut glfw, &mut window);
+                            }
+                            InputAction::Screenshot => {
+                                send_and_unpark!(RenderSignal::Screenshot(None));
+                            }
+                            InputAction::ToggleWireframe => {
+                                raster_mode = if raster_mode == RasterMode::Wireframe { RasterMode::Fill } else { RasterMode::Wireframe };
+
+                                send_and_unpark!(RenderSignal::RasterMode(raster_mode));
+                            }
+                            InputAction::ToggleOverdraw => {
+                                raster_mode = if raster_mode == RasterMode::Overdraw { RasterMode::Fill } else { RasterMode::Overdraw };
+
+                                send_and_unpark!(RenderSignal::RasterMode(raster_mode));
+                            }
+                            InputAction::ToggleTitleStats => {
+                                title_stats = !title_stats;
+
+                                if title_stats {
+                                    last_title_update = None;
+                                } else {
+                                    window.set_title(TITLE);
+                                }
+                            }
+                            InputAction::ToggleUncapped => {
+                                uncapped = !uncapped;
+
+                                send_and_unpark!(if uncapped { RenderSignal::Uncapped } else { RenderSignal::SetTargetFps(TARGET_FPS) });
+                            }
+                            InputAction::CycleVsync => {
+                                vsync = match vsync {
+                                    VsyncMode::Off => VsyncMode::On,
+                                    VsyncMode::On => VsyncMode::Adaptive,
+                                    VsyncMode::Adaptive => VsyncMode::Off,
+                                };
+
+                                send_and_unpark!(RenderSignal::SetVsync(vsync));
+                            }
+                            InputAction::Pause => {
+                                user_paused = !user_paused;
+                                paused = user_paused;
+
+                                send_and_unpark!(if user_paused { RenderSignal::PauseAll } else { RenderSignal::Resume });
+
+                                //The cursor is given back while paused, and only captured again if it was before
+                                if user_paused {
+                                    if mouse_look.suspend(&mut window) {
+                                        send_and_unpark!(RenderSignal::CursorCapture(false));
+                                    }
+                                } else if mouse_look.resume(&mut window) {
+                                    send_and_unpark!(RenderSignal::CursorCapture(true));
+                                }
+                            }
+                            InputAction::ToggleMouseLook => {
+                                mouse_look.toggle_capture(&mut window);
+
+                                send_and_unpark!(RenderSignal::CursorCapture(mouse_look.is_captured()));
+                            }
+                            InputAction::CopyStats => {
+                                match latest_stats {
+                                    Some(ref stats) => {
+                                        window.set_clipboard_string(&stats_report(stats));
+
+                                        info!("Copied frame statistics to the clipboard");
+                                    }
+                                    None => warn!("No frame statistics to copy yet"),
+                                }
+                            }
+                            InputAction::ToggleInspect => {
+                                inspecting = !inspecting;
+
+                                info!("{} inspecting picked objects", if inspecting { "Started" } else { "Stopped" });
+                            }
+                            InputAction::DumpWorld => {
+                                send_and_unpark!(RenderSignal::DumpWorld);
+                            }
+                            InputAction::HideOverlay => {
+                                send_and_unpark!(RenderSignal::HideOverlay);
+                            }
+                            InputAction::ReloadShaders => {
+                                send_and_unpark!(RenderSignal::ReloadShaders);
+                            }
+                            InputAction::ToggleShaderWatch => {
+                                if shader_watcher.take().is_some() {
+                                    info!("Stopped watching shaders");
+                                } else {
+                                    shader_watcher = Some(ShaderWatcher::spawn(SHADER_DIRECTORY, tx.clone()));
+                                }
+                            }
+                            InputAction::ToggleConsole => {
+                                text_capture.begin_from_key(key, modifiers);
+
+                                //Keys held for movement won't see their releases while typing
+                                mouse_look.set_captured(&mut window, false);
+
+                                send_and_unpark!(RenderSignal::CursorCapture(false));
+                                send_and_unpark!(RenderSignal::CameraInput(mouse_look.release_all()));
+                                send_and_unpark!(RenderSignal::TextCapture(true));
+                            }
+                            InputAction::DebugViewNext => {
+                                debug_view = debug_view.next();
+
+                                send_and_unpark!(RenderSignal::DebugView(debug_view));
+                            }
+                            InputAction::DebugView(index) => {
+                                if let Some(view) = DebugView::from_index(index as usize) {
+                                    debug_view = view;
+
+                                    send_and_unpark!(RenderSignal::DebugView(view));
+                                }
+                            }
+                        }
                     }
-                    WindowEvent::Key(Key::F11, _, Action::Press, _) => {
-                        fullscreen.toggle(&mut glfw, &mut window);
+                    WindowEvent::Key(key, _, action, _) if MouseLook::is_movement_key(key) => {
+                        if let Some(input) = mouse_look.key(key, action) {
+                            send_and_unpark!(RenderSignal::CameraInput(input));
+                        }
+                    }
+                    WindowEvent::CursorPos(x, y) if mouse_look.is_captured() => {
+                        if let Some(input) = mouse_look.cursor_moved(x, y) {
+                            send_and_unpark!(RenderSignal::CameraInput(input));
+                        }
                     }
-                    WindowEvent::FramebufferSize(width, height) |
-                    WindowEvent::Size(width, height) if width > 0 && height > 0 => {
-                        send_and_unpark!(RenderSignal::ViewportResize(width, height)).unwrap();
+                    WindowEvent::FileDrop(paths) => {
+                        file_drops.load(paths);
                     }
-                    WindowEvent::Iconify(iconified) if iconified => {
-                        send_and_unpark!(RenderSignal::Pause).unwrap();
+                    WindowEvent::MouseButton(MouseButton::Button1, Action::Press, _) => {
+                        //Cursor positions are in window coordinates, but picking works in framebuffer pixels
+                        let (x, y) = graphics::window_to_framebuffer(&window, window.get_cursor_pos());
+
+                        send_and_unpark!(RenderSignal::Pick(x, y));
+                    }
+                    //The window size is in window coordinates, which only match pixels without display scaling
+                    WindowEvent::FramebufferSize(width, height) if width > 0 && height > 0 => {
+                        send_and_unpark!(RenderSignal::ViewportResize(width, height));
+
+                        let scale = graphics::content_scale(&window);
+
+                        if scale != content_scale {
+                            content_scale = scale;
+
+                            send_and_unpark!(RenderSignal::ContentScale(scale.0, scale.1));
+                        }
+                    }
+                    //Losing the window only stops drawing, so game time keeps going. A pause from the user stays in effect
+                    WindowEvent::Iconify(iconified) if iconified && !user_paused => {
+                        paused = true;
+
+                        send_and_unpark!(RenderSignal::PauseRendering);
                     }
-                    WindowEvent::Focus(focus) => {
+                    WindowEvent::Focus(focus) if !user_paused => {
+                        paused = !focus;
+
                         if focus {
-                            send_and_unpark!(RenderSignal::Resume).unwrap();
+                            send_and_unpark!(RenderSignal::Resume);
+
+                            //Capture the cursor again only if losing focus is what released it
+                            if mouse_look.resume(&mut window) {
+                                send_and_unpark!(RenderSignal::CursorCapture(true));
+                            }
                         } else {
-                            send_and_unpark!(RenderSignal::Pause).unwrap();
+                            send_and_unpark!(RenderSignal::PauseRendering);
+
+                            //Key releases go to whatever has focus now, so stop moving and give the cursor back
+                            if mouse_look.suspend(&mut window) {
+                                send_and_unpark!(RenderSignal::CursorCapture(false));
+                            }
+
+                            send_and_unpark!(RenderSignal::CameraInput(mouse_look.release_all()));
                         }
                     }
                     _ => {
-                        tx.send(RenderSignal::Event(event)).unwrap();
+                        if tx.send(RenderSignal::Event(event)).is_err() {
+                            render_disconnected = true;
+                        }
                     }
                 }
             } else {
@@ -135,16 +480,29 @@ fn main() {
                 break 'event_loop;
             }
         }
+
+        if render_disconnected {
+            error!("Render thread is no longer receiving signals, shutting down");
+
+            break 'event_loop;
+        }
     }
 
     info!("Shutting down...");
 
     if running.swap(false, Ordering::SeqCst) {
         //Signal the render thread to close
-        send_and_unpark!(RenderSignal::Stop).expect_logged("Failed to signal render task.");
+        send_and_unpark!(RenderSignal::Stop);
     }
 
-    render_thread.join().expect_logged("Failed to join render thread");
+    //Finish loading any dropped files first, so the loader isn't left sending to a render thread that's gone
+    drop(file_drops);
+    drop(shader_watcher);
+
+    //Surface why the render thread stopped, if it panicked
+    if let Err(panic) = render_thread.join() {
+        error!("Render thread panicked: {}", panic_message(&panic));
+    }
 
     info!("Goodbye");
 }
\ No newline at end of file
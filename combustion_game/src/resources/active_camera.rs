@@ -0,0 +1,21 @@
+//! The ActiveCamera resource chooses which camera entity the scene is rendered from
+
+use specs;
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Resource(pub Option<specs::Entity>);
+
+impl Resource {
+    #[inline(always)]
+    pub fn new(entity: specs::Entity) -> Resource {
+        Resource(Some(entity))
+    }
+
+    #[inline(always)]
+    pub fn entity(&self) -> Option<specs::Entity> { self.0 }
+
+    #[inline(always)]
+    pub fn set(&mut self, entity: Option<specs::Entity>) {
+        self.0 = entity;
+    }
+}
@@ -0,0 +1,5 @@
+//! The AnimationEvents resource holds clip events fired by the animation systems during the current tick
+//!
+//! See `core::ecs::animation_events`, which this re-exports so games can find it alongside the other resources.
+
+pub use core::ecs::animation_events::*;
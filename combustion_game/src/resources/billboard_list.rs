@@ -0,0 +1,12 @@
+//! The BillboardList resource holds this update's billboards, batched by texture by the billboard system for render submission
+//!
+//! See `core::ecs::billboard_list`. This is it for billboards textured with GPU textures.
+
+use core::ecs::billboard_list;
+use core::backend::gl::GLTexture;
+
+pub use core::ecs::billboard_list::{BillboardInstance, batch};
+
+pub type BillboardBatch = billboard_list::BillboardBatch<GLTexture>;
+
+pub type Resource = billboard_list::Resource<GLTexture>;
@@ -0,0 +1,77 @@
+//! The CameraController resource holds the mouse-look camera's orientation, settings and pending input.
+
+use std::f32::consts::PI;
+
+use input::camera::CameraInput;
+
+/// Pitch is kept just short of straight up or down, where yaw stops making sense
+pub const MAX_PITCH: f32 = 89.0 * PI / 180.0;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Resource {
+    /// Rotation around the vertical axis in radians, with zero looking down negative Z
+    pub yaw: f32,
+    /// Rotation up or down in radians, between `-MAX_PITCH` and `MAX_PITCH`
+    pub pitch: f32,
+    /// Radians turned per window coordinate the cursor moves
+    pub sensitivity: f32,
+    /// Units moved per second
+    pub speed: f32,
+    /// Speed multiplier while the speed modifier is held
+    pub fast_multiplier: f32,
+    /// Input received since the cursor movement was last applied
+    pub input: CameraInput,
+    /// Whether the main thread has the cursor captured. Cursor movement is ignored otherwise.
+    pub captured: bool,
+    /// Drops the next cursor movement, which includes the jump from wherever the cursor was before capture started
+    pub settling: bool,
+}
+
+impl Default for Resource {
+    #[inline(always)]
+    fn default() -> Resource { Resource::new() }
+}
+
+impl Resource {
+    pub fn new() -> Resource {
+        Resource {
+            yaw: 0.0,
+            pitch: 0.0,
+            sensitivity: 0.0025,
+            speed: 2.0,
+            fast_multiplier: 4.0,
+            input: CameraInput::default(),
+            captured: false,
+            settling: false,
+        }
+    }
+
+    /// Turns by a cursor movement in window coordinates, clamping pitch
+    pub fn look(&mut self, dx: f32, dy: f32) {
+        self.yaw = (self.yaw + dx * self.sensitivity) % (2.0 * PI);
+        self.pitch = (self.pitch - dy * self.sensitivity).max(-MAX_PITCH).min(MAX_PITCH);
+    }
+
+    /// Records a change in cursor capture, discarding any cursor movement from before it
+    pub fn set_captured(&mut self, captured: bool) {
+        self.captured = captured;
+        self.settling = captured;
+
+        self.input.take_look();
+    }
+
+    /// Unit vector the camera looks along
+    pub fn forward(&self) -> (f32, f32, f32) {
+        (self.pitch.cos() * self.yaw.sin(), self.pitch.sin(), -self.pitch.cos() * self.yaw.cos())
+    }
+
+    /// Horizontal unit vector to the camera's right
+    pub fn right(&self) -> (f32, f32, f32) {
+        (self.yaw.cos(), 0.0, self.yaw.sin())
+    }
+
+    /// Current movement speed, in units per second
+    pub fn current_speed(&self) -> f32 {
+        if self.input.fast { self.speed * self.fast_multiplier } else { self.speed }
+    }
+}
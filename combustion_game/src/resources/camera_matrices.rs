@@ -0,0 +1,5 @@
+//! The CameraMatrices resource holds the matrices of the active camera, as used by rendering
+//!
+//! See `core::ecs::camera_matrices`, which this re-exports so games can find it alongside the other resources.
+
+pub use core::ecs::camera_matrices::*;
@@ -0,0 +1,30 @@
+//! The Console resource holds the line being typed into the console and the lines entered before it.
+
+use common::line_edit::LineBuffer;
+
+/// Most entered lines kept in the history
+pub const MAX_HISTORY: usize = 64;
+
+#[derive(Clone, Debug, Default)]
+pub struct Resource {
+    pub line: LineBuffer,
+    /// Entered lines, oldest first
+    pub history: Vec<String>,
+    /// Set whenever the line changes, until the render loop sends it to the main thread for display
+    pub changed: bool,
+}
+
+impl Resource {
+    pub fn new() -> Resource {
+        Resource::default()
+    }
+
+    /// Adds an entered line to the history, dropping the oldest once it's full
+    pub fn push_history(&mut self, line: String) {
+        if self.history.len() >= MAX_HISTORY {
+            self.history.remove(0);
+        }
+
+        self.history.push(line);
+    }
+}
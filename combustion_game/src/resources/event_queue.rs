@@ -2,8 +2,12 @@ use std::ops::{Deref, DerefMut};
 
 use glfw::WindowEvent;
 
+use input::gamepad::GamepadEvent;
+
 pub enum Event {
-    WindowEvent(WindowEvent)
+    WindowEvent(WindowEvent),
+    /// A gamepad was connected or disconnected
+    Gamepad(GamepadEvent),
 }
 
 pub struct Resource {
@@ -0,0 +1,5 @@
+//! Typed events, for systems to tell each other about things that happened without depending on each other
+//!
+//! See `core::ecs::events`, which this re-exports so games can find it alongside the other resources.
+
+pub use core::ecs::events::*;
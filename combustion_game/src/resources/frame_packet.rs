@@ -0,0 +1,42 @@
+//! Frame packets carry everything the render thread draws out of the ECS world
+//!
+//! The render submission system sends a packet each update through the sender resource, and the render loop
+//! keeps only the newest through a `FrameReceiver`. The channel itself is `core::ecs::frame_channel`.
+
+use core::ecs::frame_channel::{self, Frame};
+use core::graphics::pipeline::lights::Light;
+
+use ::resources::render_queue::{RenderItem, RENDER_QUEUE_SIZE};
+use ::resources::camera_matrices::Resource as CameraMatrices;
+use ::resources::billboard_list::BillboardBatch;
+
+/// A single update's worth of render data, holding only shared handles and plain data, never GL objects themselves
+#[derive(Clone)]
+pub struct FramePacket {
+    /// Counts up by one for every packet sent
+    pub sequence: u64,
+    /// Draw list, sorted by material then mesh
+    pub items: Vec<RenderItem>,
+    pub lights: Vec<Light>,
+    /// Billboards drawn in the transparent pass, farthest batch first
+    pub billboards: Vec<BillboardBatch>,
+    pub camera: CameraMatrices,
+}
+
+impl Frame for FramePacket {
+    fn new(sequence: u64) -> FramePacket {
+        FramePacket {
+            sequence: sequence,
+            items: Vec::with_capacity(RENDER_QUEUE_SIZE),
+            lights: Vec::new(),
+            billboards: Vec::new(),
+            camera: CameraMatrices::new(),
+        }
+    }
+}
+
+/// Render loop end of the frame channel
+pub type FrameReceiver = frame_channel::FrameReceiver<FramePacket>;
+
+/// Sending end of the frame channel, used by the render submission system
+pub type Resource = frame_channel::FrameSender<FramePacket>;
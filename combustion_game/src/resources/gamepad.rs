@@ -0,0 +1,30 @@
+//! The Gamepad resource holds the state of every gamepad slot, updated by the render loop from the main thread's polling.
+//!
+//! Button edges like `just_pressed` cover everything since the previous frame's fixed update ticks.
+
+use std::ops::{Deref, DerefMut};
+
+use input::gamepad::GamepadState;
+
+#[derive(Clone, Debug, Default)]
+pub struct Resource {
+    pub state: GamepadState,
+}
+
+impl Resource {
+    pub fn new() -> Resource {
+        Resource { state: GamepadState::new() }
+    }
+}
+
+impl Deref for Resource {
+    type Target = GamepadState;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target { &self.state }
+}
+
+impl DerefMut for Resource {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.state }
+}
@@ -0,0 +1,5 @@
+//! The groups resource interns group names and indexes the members of every group
+//!
+//! See `core::ecs::groups`, which this re-exports so games can find it alongside the other resources.
+
+pub use core::ecs::groups::*;
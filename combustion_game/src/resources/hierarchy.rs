@@ -0,0 +1,5 @@
+//! The Hierarchy resource tracks what the hierarchy system saw last update, so only changed subtrees are recomputed
+//!
+//! See `core::ecs::hierarchy`, which this re-exports so games can find it alongside the other resources.
+
+pub use core::ecs::hierarchy::*;
@@ -0,0 +1,5 @@
+//! The LightList resource holds every enabled light in the scene, gathered each update for the lighting pass
+//!
+//! See `core::ecs::light_list`, which this re-exports so games can find it alongside the other resources.
+
+pub use core::ecs::light_list::*;
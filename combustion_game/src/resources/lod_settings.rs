@@ -0,0 +1,11 @@
+//! The LodSettings resource holds the global settings used by the LOD system to pick each entity's level of detail
+//!
+//! See `core::ecs::lod_settings`. This is it for debugging in the GPU materials mesh renderers draw with.
+
+use core::ecs::lod_settings;
+
+use ::components::mesh_renderer::Material;
+
+pub use core::ecs::lod_settings::{DEBUG_COLORS, DebugMaterial};
+
+pub type Resource = lod_settings::Resource<Material>;
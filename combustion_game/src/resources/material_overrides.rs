@@ -0,0 +1,12 @@
+//! The MaterialOverrides resource holds the materials merged from shared materials and material override components
+//!
+//! See `core::ecs::material_overrides`. This is it for the GPU materials mesh renderers draw with.
+
+use core::ecs::material_overrides;
+
+use ::components::mesh_renderer::Material;
+
+pub use core::ecs::material_overrides::Overridable;
+pub use core::graphics::pipeline::material::MaterialKey;
+
+pub type Resource = material_overrides::Resource<Material>;
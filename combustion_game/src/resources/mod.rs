@@ -3,4 +3,25 @@ pub mod cursor;
 pub mod camera;
 pub mod event_queue;
 pub mod render_queue;
-pub mod projection;
\ No newline at end of file
+pub mod frame_packet;
+pub mod projection;
+pub mod active_camera;
+pub mod camera_matrices;
+pub mod light_list;
+pub mod time;
+pub mod gamepad;
+pub mod camera_controller;
+pub mod text_input;
+pub mod console;
+pub mod scene_settings;
+pub mod prefab_store;
+pub mod name_index;
+pub mod hierarchy;
+pub mod animation_events;
+pub mod visible_set;
+pub mod events;
+pub mod groups;
+pub mod spatial_index;
+pub mod billboard_list;
+pub mod lod_settings;
+pub mod material_overrides;
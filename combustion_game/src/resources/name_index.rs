@@ -0,0 +1,5 @@
+//! The NameIndex resource finds entities by their name component, kept up to date by the name index system
+//!
+//! See `core::ecs::name_index`, which this re-exports so games can find it alongside the other resources.
+
+pub use core::ecs::name_index::*;
@@ -0,0 +1,17 @@
+//! The PrefabStore resource holds prefabs, which are scene fragments spawned into the world as many times as needed
+//!
+//! See `core::ecs::prefab_store`, which this re-exports so games can find it alongside the other resources.
+
+use core::ecs::prefab_store;
+use error::*;
+use core::scene::storage::{ComponentRegistry, BuiltinComponents};
+
+pub use core::ecs::prefab_store::{Overrides, PREFAB_COMPONENT};
+
+pub type Prefab = prefab_store::Prefab<AppError>;
+pub type Resource = prefab_store::Resource<AppError>;
+
+/// Creates an empty store, spawning the built-in components
+pub fn builtin() -> Resource {
+    Resource::with_registry(ComponentRegistry::builtin())
+}
@@ -1,49 +1,38 @@
-use std::mem;
-use std::ops::{Deref, DerefMut};
-
 use nalgebra::Matrix4;
 
+use core::graphics::pipeline::indirect::MeshRange;
+use core::graphics::pipeline::material::MaterialHandle;
+
 use ::components;
 
 pub static RENDER_QUEUE_SIZE: usize = 256;
 
+#[derive(Clone)]
 pub struct RenderItem {
     pub buffer: components::gpu_buffer::LazyBufferSync,
+    /// Indices of the buffer to draw, or all of them if `None`
+    pub range: Option<MeshRange>,
     pub transform: Matrix4<f32>,
-    pub inverse: Option<Matrix4<f32>>
+    pub inverse: Option<Matrix4<f32>>,
+    /// Whether the item is inside the view. Items outside it are only drawn into shadow maps.
+    pub visible: bool,
+    /// Material to draw the item with, or the renderer's default material if `None`
+    pub material: Option<MaterialHandle>,
+    /// Written to the geometry stage for `Pipeline::pick`, usually the entity ID. `NO_OBJECT` makes the item unpickable.
+    pub object_id: u32,
+    /// Whether the item is drawn into shadow maps
+    pub cast_shadows: bool,
 }
 
-unsafe impl Send for RenderItem {}
-
-unsafe impl Sync for RenderItem {}
-
-pub struct Resource {
-    pub queue: Vec<RenderItem>
-}
+impl RenderItem {
+    /// Sorting by this puts items with the same material together, then identical meshes within those, so they can be drawn instanced
+    pub fn sort_key(&self) -> (usize, usize, Option<(u32, u32, i32)>) {
+        let material = self.material.as_ref().map_or(0, |material| &**material as *const _ as usize);
 
-impl Default for Resource {
-    #[inline(always)]
-    fn default() -> Resource { Resource::new() }
-}
-
-impl Resource {
-    pub fn new() -> Resource {
-        Resource { queue: Vec::with_capacity(RENDER_QUEUE_SIZE) }
-    }
-
-    pub fn swap(&mut self, mut other: &mut Vec<RenderItem>) {
-        mem::swap(&mut self.queue, other);
+        (material, &*self.buffer as *const _ as usize, self.range.map(|range| (range.first_index, range.count, range.base_vertex)))
     }
 }
 
-impl Deref for Resource {
-    type Target = Vec<RenderItem>;
-
-    #[inline(always)]
-    fn deref(&self) -> &Self::Target { &self.queue }
-}
+unsafe impl Send for RenderItem {}
 
-impl DerefMut for Resource {
-    #[inline(always)]
-    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.queue }
-}
\ No newline at end of file
+unsafe impl Sync for RenderItem {}
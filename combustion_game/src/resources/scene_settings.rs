@@ -0,0 +1,5 @@
+//! The SceneSettings resource holds rendering settings for the whole scene, saved and loaded along with its entities
+//!
+//! See `core::ecs::scene_settings`, which this re-exports so games can find it alongside the other resources.
+
+pub use core::ecs::scene_settings::*;
@@ -0,0 +1,5 @@
+//! The SpatialIndex resource, a loose octree over world bounds for finding what's near a box, sphere or ray
+//!
+//! See `core::ecs::spatial_index`, which this re-exports so games can find it alongside the other resources.
+
+pub use core::ecs::spatial_index::*;
@@ -0,0 +1,32 @@
+//! The TextInput resource collects typed text and editing keys while the main thread is capturing text.
+//!
+//! Whichever system owns text entry, such as the console, drains the edits as it applies them.
+
+use std::ops::{Deref, DerefMut};
+
+use input::text::TextEdit;
+
+#[derive(Clone, Debug, Default)]
+pub struct Resource {
+    /// Whether the main thread is capturing text, which suppresses key bindings
+    pub active: bool,
+    pub edits: Vec<TextEdit>,
+}
+
+impl Resource {
+    pub fn new() -> Resource {
+        Resource { active: false, edits: Vec::new() }
+    }
+}
+
+impl Deref for Resource {
+    type Target = Vec<TextEdit>;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target { &self.edits }
+}
+
+impl DerefMut for Resource {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.edits }
+}
@@ -0,0 +1,5 @@
+//! The Time resource holds the timing of the current frame and of the fixed update ticks, updated once per frame by the render loop
+//!
+//! See `core::ecs::time`, which this re-exports so games can find it alongside the other resources.
+
+pub use core::ecs::time::*;
@@ -0,0 +1,5 @@
+//! The VisibleSet resource holds the entities inside the view frustum this update, written by the culling system
+//!
+//! See `core::ecs::visible_set`, which this re-exports so games can find it alongside the other resources.
+
+pub use core::ecs::visible_set::*;
@@ -0,0 +1,5 @@
+//! Animation system, which plays every animator's clip and writes the sampled pose into its transform each tick
+//!
+//! See `core::ecs::systems::animation`, which this re-exports so games can schedule it alongside the other systems.
+
+pub use core::ecs::systems::animation::*;
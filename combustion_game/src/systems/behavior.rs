@@ -0,0 +1,5 @@
+//! Behavior system, which runs every enabled behavior component once per fixed tick
+//!
+//! See `core::ecs::systems::behavior`, which this re-exports so games can schedule it alongside the other systems.
+
+pub use core::ecs::systems::behavior::*;
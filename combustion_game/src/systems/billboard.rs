@@ -0,0 +1,8 @@
+//! Billboard system, which turns billboards to face the active camera and batches them by texture for render submission
+//!
+//! See `core::ecs::systems::billboard`. This is it for billboards textured with GPU textures.
+
+use core::ecs::systems::billboard;
+use core::backend::gl::GLTexture;
+
+pub type System = billboard::System<GLTexture>;
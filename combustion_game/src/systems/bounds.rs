@@ -0,0 +1,9 @@
+//! Bounds system, which keeps world bounds in sync with local bounds and world transforms
+//!
+//! See `core::ecs::systems::bounds`. This is it for the GPU meshes and materials mesh renderers draw with.
+
+use core::ecs::systems::bounds;
+
+use ::components::mesh_renderer::{Mesh, Material};
+
+pub type System = bounds::System<Mesh, Material>;
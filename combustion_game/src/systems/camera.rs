@@ -0,0 +1,51 @@
+//! Camera system, which computes the active camera's matrices from its camera component and transform
+
+use specs;
+
+use nalgebra::Point3;
+
+pub struct System;
+
+impl specs::System<super::Delta> for System {
+    fn run(&mut self, arg: specs::RunArg, _: super::Delta) {
+        use ::components::camera::Component as Camera;
+        use ::components::hierarchy::world_transform::Component as WorldTransform;
+        use ::components::transform::Component as Transform;
+
+        use ::resources::active_camera::Resource as ActiveCamera;
+        use ::resources::camera_matrices::Resource as CameraMatrices;
+
+        let (ref active, ref mut matrices, ref cameras, ref world_transforms, ref transforms) = arg.fetch(|world| {
+            (
+                world.read_resource::<ActiveCamera>(),
+                world.write_resource::<CameraMatrices>(),
+                world.read::<Camera>(),
+                world.read::<WorldTransform>(),
+                world.read::<Transform>(),
+            )
+        });
+
+        let entity = match active.entity() {
+            Some(entity) => entity,
+            None => return,
+        };
+
+        if let Some(camera) = cameras.get(entity) {
+            matrices.set_projection(camera.projection());
+        }
+
+        if let Some(world_transform) = world_transforms.get(entity) {
+            //The world transform places the camera in the world, so viewing through it is the inverse
+            if let Some(view) = world_transform.inverse {
+                let ref matrix = world_transform.matrix;
+
+                matrices.set_view(view, Point3::new(matrix.m14, matrix.m24, matrix.m34));
+            }
+        } else if let Some(transform) = transforms.get(entity) {
+            //Cameras outside the hierarchy are turned with look-at isometries, which are view matrices already
+            if let Some(ref inverse) = transform.inverse {
+                matrices.set_view(transform.matrix, Point3::new(inverse.m14, inverse.m24, inverse.m34));
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Mouse-look camera system, which moves and turns the camera entity from the camera controller resource
+
+use specs;
+
+use nalgebra::{Isometry3, Point3, Vector3};
+
+pub struct System;
+
+impl specs::System<super::Delta> for System {
+    fn run(&mut self, arg: specs::RunArg, delta: super::Delta) {
+        use ::components::position::Component as Position;
+        use ::components::isometry::Component as Isometry;
+
+        use ::resources::camera::Resource as CameraResource;
+        use ::resources::camera_controller::Resource as CameraController;
+
+        let (ref mut controller, ref camera, ref mut positions, ref mut isometries) = arg.fetch(|world| {
+            (
+                world.write_resource::<CameraController>(),
+                world.read_resource::<CameraResource>(),
+                world.write::<Position>(),
+                world.write::<Isometry>(),
+            )
+        });
+
+        //Cursor movement is turned all at once, but held keys move the camera by however long the tick is
+        let (dx, dy) = controller.input.take_look();
+
+        if controller.settling {
+            if dx != 0.0 || dy != 0.0 {
+                controller.settling = false;
+            }
+        } else if controller.captured {
+            controller.look(dx, dy);
+        }
+
+        let entity = camera.entity();
+
+        let eye = match positions.get_mut(entity) {
+            Some(mut position) => {
+                let (right, up, forward) = controller.input.movement;
+                let distance = controller.current_speed() * delta;
+
+                let (fx, fy, fz) = controller.forward();
+                let (rx, ry, rz) = controller.right();
+
+                position.0 += Vector3::new(fx, fy, fz) * forward * distance +
+                              Vector3::new(rx, ry, rz) * right * distance +
+                              Vector3::new(0.0, 1.0, 0.0) * up * distance;
+
+                position.0
+            }
+            None => return,
+        };
+
+        if let Some(mut isometry) = isometries.get_mut(entity) {
+            let (fx, fy, fz) = controller.forward();
+
+            isometry.0 = Isometry3::look_at_rh(&eye, &(eye + Vector3::new(fx, fy, fz)), &Vector3::new(0.0, 1.0, 0.0));
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Console system, which edits the console line from captured text and runs lines as they're entered
+
+use specs;
+
+pub struct System;
+
+impl System {
+    fn run_line(line: &str) {
+        let mut words = line.split_whitespace();
+
+        match words.next() {
+            Some("help") => info!("Console commands: help, echo <text>"),
+            Some("echo") => info!("{}", words.collect::<Vec<_>>().join(" ")),
+            Some(command) => warn!("Unknown console command \"{}\"", command),
+            None => {}
+        }
+    }
+}
+
+impl specs::System<super::Delta> for System {
+    fn run(&mut self, arg: specs::RunArg, _: super::Delta) {
+        use ::resources::text_input::Resource as TextInput;
+        use ::resources::console::Resource as Console;
+
+        let (ref mut text_input, ref mut console) = arg.fetch(|world| {
+            (
+                world.write_resource::<TextInput>(),
+                world.write_resource::<Console>(),
+            )
+        });
+
+        if !text_input.active || text_input.is_empty() {
+            return;
+        }
+
+        for edit in text_input.drain(..) {
+            if let Some(line) = console.line.apply(&edit) {
+                info!("> {}", line);
+
+                System::run_line(&line);
+
+                console.push_history(line);
+            }
+        }
+
+        console.changed = true;
+    }
+}
@@ -0,0 +1,9 @@
+//! Culling system, which finds the renderable entities inside the view frustum for render submission
+//!
+//! See `core::ecs::systems::culling`. This is it for the GPU meshes and materials mesh renderers draw with.
+
+use core::ecs::systems::culling;
+
+use ::components::mesh_renderer::{Mesh, Material};
+
+pub type System = culling::System<Mesh, Material>;
@@ -0,0 +1,6 @@
+//! Events system, which starts a new update for every registered event type before anything else runs
+//!
+//! Running first means events sent during an update stay readable for the whole of the next one, whatever order
+//! the sending and reading systems run in.
+
+pub use core::ecs::events::UpdateEvents as System;
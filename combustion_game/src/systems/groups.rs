@@ -0,0 +1,5 @@
+//! Groups system, which prunes deleted entities from the groups resource's index
+//!
+//! See `core::ecs::systems::groups`, which this re-exports so games can schedule it alongside the other systems.
+
+pub use core::ecs::systems::groups::*;
@@ -0,0 +1,5 @@
+//! Transform hierarchy system, which computes world transforms with every parent solved before its children
+//!
+//! See `core::ecs::systems::hierarchy`, which this re-exports so games can schedule it alongside the other systems.
+
+pub use core::ecs::systems::hierarchy::*;
@@ -0,0 +1,5 @@
+//! Kinematics system, which moves and turns entities by their velocities every fixed tick and deletes expired ones
+//!
+//! See `core::ecs::systems::kinematics`, which this re-exports so games can schedule it alongside the other systems.
+
+pub use core::ecs::systems::kinematics::*;
@@ -0,0 +1,83 @@
+//! Light gathering system, which collects every enabled light and its world transform into the light list resource
+
+use specs;
+use specs::Join;
+
+use nalgebra::{Point3, Vector3, Matrix4, Eye, Norm};
+
+use core::graphics::pipeline::lights::{Light, LightKind};
+
+/// World position and the direction of the local -Z axis, which lights shine along
+fn placement(matrix: &Matrix4<f32>) -> (Point3<f32>, Vector3<f32>) {
+    let direction = -Vector3::new(matrix.m13, matrix.m23, matrix.m33);
+
+    (Point3::new(matrix.m14, matrix.m24, matrix.m34), direction.try_normalize(1.0e-6).unwrap_or(Vector3::new(0.0, 0.0, -1.0)))
+}
+
+pub struct System;
+
+impl specs::System<super::Delta> for System {
+    fn run(&mut self, arg: specs::RunArg, _: super::Delta) {
+        use ::components::point_light::Component as PointLight;
+        use ::components::spot_light::Component as SpotLight;
+        use ::components::directional_light::Component as DirectionalLight;
+        use ::components::hierarchy::world_transform::Component as WorldTransform;
+
+        use ::resources::light_list::Resource as LightList;
+
+        let (ref mut list, ref points, ref spots, ref directionals, ref world_transforms, ref entities) = arg.fetch(|world| {
+            (
+                world.write_resource::<LightList>(),
+                world.read::<PointLight>(),
+                world.read::<SpotLight>(),
+                world.read::<DirectionalLight>(),
+                world.read::<WorldTransform>(),
+                world.entities(),
+            )
+        });
+
+        let identity = Matrix4::new_identity(4);
+
+        //Lights without a world transform sit at the origin, pointing down -Z
+        let placement_of = |entity: specs::Entity| {
+            placement(world_transforms.get(entity).map_or(&identity, |transform| &transform.matrix))
+        };
+
+        list.clear();
+
+        //Directional lights reach everything, so they're gathered first in case the list fills up
+        for (light, entity) in (directionals, entities).iter() {
+            if light.enabled {
+                let (_, direction) = placement_of(entity);
+
+                list.push(Light::directional(direction, light.color, light.intensity));
+            }
+        }
+
+        for (light, entity) in (spots, entities).iter() {
+            if light.enabled {
+                let (position, direction) = placement_of(entity);
+
+                list.push(Light {
+                    kind: LightKind::Spot,
+                    position: position,
+                    direction: direction,
+                    color: light.color,
+                    intensity: light.intensity,
+                    radius: light.radius,
+                    cone: (light.inner_angle, light.outer_angle),
+                });
+            }
+        }
+
+        for (light, entity) in (points, entities).iter() {
+            if light.enabled {
+                let (position, _) = placement_of(entity);
+
+                list.push(Light::point(position, light.color, light.intensity, light.radius));
+            }
+        }
+
+        list.finish();
+    }
+}
@@ -0,0 +1,11 @@
+//! LOD system, which picks the level of detail of every visible entity from how much of the screen it covers
+//!
+//! See `core::ecs::systems::lod`. This is it for the GPU meshes and materials mesh renderers draw with.
+
+use core::ecs::systems::lod;
+
+use ::components::mesh_renderer::{Mesh, Material};
+
+pub use core::ecs::systems::lod::screen_coverage;
+
+pub type System = lod::System<Mesh, Material>;
@@ -3,15 +3,226 @@ pub mod clean;
 pub mod physics;
 pub mod transform;
 pub mod constraints;
+pub mod camera_controller;
+pub mod console;
+pub mod hierarchy;
+pub mod camera;
+pub mod light_gather;
+pub mod render_submission;
+pub mod name_index;
+pub mod animation;
+pub mod skeletal_animation;
+pub mod kinematics;
+pub mod bounds;
+pub mod culling;
+pub mod events;
+pub mod behavior;
+pub mod groups;
+pub mod orphans;
+pub mod spatial_index;
+pub mod billboard;
+pub mod lod;
+pub mod schedule;
 
 pub type Delta = f32;
 
-pub enum Priorities {
-    LAST = 0,
-    Render,
-    Constraints,
-    Transforms,
-    Physics,
-    Clean,
-    FIRST
-}
\ No newline at end of file
+/// Every engine system, in a schedule that game systems can be added to before it's built
+pub fn schedule() -> schedule::Schedule {
+    use ::components;
+    use ::components::hierarchy::transform::Component as Transform;
+    use ::components::hierarchy::world_transform::Component as WorldTransform;
+    use ::components::hierarchy::parent::Component as Parent;
+    use ::components::animation::animator::Component as Animator;
+
+    use ::resources;
+
+    use core::ecs::storage::ClearChanges;
+
+    let mut schedule = schedule::Schedule::new();
+
+    //Swaps event buffers, so every event type is written
+    schedule.add(events::System, "EventsSystem")
+            .reads_resource::<resources::events::Registry>();
+
+    schedule.add(name_index::System, "NameIndexSystem")
+            .writes_resource::<resources::name_index::Resource>()
+            .reads::<components::name::Component>();
+
+    schedule.add(groups::System, "GroupsSystem")
+            .writes_resource::<resources::groups::Resource>()
+            .reads::<components::group::Component>();
+
+    schedule.add(console::System, "ConsoleSystem")
+            .writes_resource::<resources::text_input::Resource>()
+            .writes_resource::<resources::console::Resource>();
+
+    schedule.add(camera_controller::System, "CameraControllerSystem")
+            .writes_resource::<resources::camera_controller::Resource>()
+            .reads_resource::<resources::camera::Resource>()
+            .writes::<components::position::Component>()
+            .writes::<components::isometry::Component>()
+            .after("ConsoleSystem");
+
+    schedule.add(transform::System, "TransformSystem")
+            .reads::<components::position::Component>()
+            .reads::<components::isometry::Component>()
+            .reads::<components::rotation::Component>()
+            .reads::<components::quaternion_rotation::Component>()
+            .reads::<components::scale::Component>()
+            .writes::<components::transform::Component>()
+            .after("CameraControllerSystem");
+
+    schedule.add(constraints::System, "ConstrainSystem")
+            .reads::<components::position::Component>()
+            .reads::<components::constraints::lookat::Component>()
+            .writes::<components::isometry::Component>()
+            .after("TransformSystem");
+
+    schedule.add(kinematics::System::new(), "KinematicsSystem")
+            .writes::<Transform>()
+            .writes::<components::kinematics::velocity::Component>()
+            .writes::<components::kinematics::angular_velocity::Component>()
+            .reads::<components::kinematics::gravity::Component>()
+            .reads::<components::kinematics::damping::Component>()
+            .writes::<components::kinematics::lifetime::Component>()
+            .reads::<Animator>();
+
+    schedule.add(behavior::System, "BehaviorSystem")
+            .writes::<components::behavior::Component>()
+            .writes::<Transform>()
+            .writes_resource::<resources::events::Events<components::behavior::BehaviorEvent>>()
+            .reads_resource::<resources::time::Resource>()
+            .reads::<WorldTransform>()
+            .after("EventsSystem")
+            .after("KinematicsSystem");
+
+    schedule.add(animation::System, "AnimationSystem")
+            .writes_resource::<resources::animation_events::Resource>()
+            .writes::<Animator>()
+            .writes::<Transform>()
+            .after("BehaviorSystem");
+
+    schedule.add(skeletal_animation::System, "SkeletalAnimationSystem")
+            .writes_resource::<resources::animation_events::Resource>()
+            .writes::<components::animation::skeletal_animator::Component>()
+            .after("AnimationSystem");
+
+    schedule.add(orphans::System, "OrphanSystem")
+            .writes::<Parent>()
+            .reads::<components::hierarchy::orphan_policy::Component>()
+            .before("HierarchySystem");
+
+    schedule.add(hierarchy::System, "HierarchySystem")
+            .writes_resource::<resources::hierarchy::Resource>()
+            .writes::<Transform>()
+            .writes::<Parent>()
+            .writes::<WorldTransform>()
+            .after("AnimationSystem");
+
+    schedule.add(bounds::System::new(), "BoundsSystem")
+            .reads_resource::<resources::hierarchy::Resource>()
+            .writes::<components::bounds::Component>()
+            .writes::<components::world_bounds::Component>()
+            .reads::<components::mesh_renderer::Component>()
+            .reads::<WorldTransform>()
+            .after("HierarchySystem");
+
+    schedule.add(spatial_index::System::new(), "SpatialIndexSystem")
+            .writes_resource::<resources::spatial_index::Resource>()
+            .reads::<components::world_bounds::Component>()
+            .after("BoundsSystem");
+
+    //Clears the world bounds change log once everything following it has read this update's changes
+    schedule.add(ClearChanges::<components::world_bounds::Component>::new(), "ClearWorldBoundsChanges")
+            .writes::<components::world_bounds::Component>()
+            .after("SpatialIndexSystem");
+
+    schedule.add(camera::System, "CameraSystem")
+            .reads_resource::<resources::active_camera::Resource>()
+            .writes_resource::<resources::camera_matrices::Resource>()
+            .reads::<components::camera::Component>()
+            .reads::<WorldTransform>()
+            .reads::<components::transform::Component>()
+            .after("HierarchySystem");
+
+    schedule.add(culling::System::new(), "CullingSystem")
+            .writes_resource::<resources::visible_set::Resource>()
+            .reads_resource::<resources::camera_matrices::Resource>()
+            .reads::<components::renderable::Component>()
+            .reads::<components::mesh_renderer::Component>()
+            .reads::<components::world_bounds::Component>()
+            .reads::<components::always_visible::Component>()
+            .after("BoundsSystem")
+            .after("CameraSystem");
+
+    schedule.add(lod::System::new(), "LodSystem")
+            .reads_resource::<resources::lod_settings::Resource>()
+            .reads_resource::<resources::camera_matrices::Resource>()
+            .reads_resource::<resources::visible_set::Resource>()
+            .writes::<components::lod::Component>()
+            .reads::<components::world_bounds::Component>()
+            .after("CullingSystem");
+
+    schedule.add(light_gather::System, "LightGatherSystem")
+            .writes_resource::<resources::light_list::Resource>()
+            .reads::<components::point_light::Component>()
+            .reads::<components::spot_light::Component>()
+            .reads::<components::directional_light::Component>()
+            .reads::<WorldTransform>()
+            .after("HierarchySystem");
+
+    schedule.add(billboard::System::new(), "BillboardSystem")
+            .writes_resource::<resources::billboard_list::Resource>()
+            .reads_resource::<resources::camera_matrices::Resource>()
+            .reads::<components::billboard::Component>()
+            .reads::<WorldTransform>()
+            .after("HierarchySystem")
+            .after("CameraSystem");
+
+    schedule.add(render_submission::System, "RenderSubmissionSystem")
+            .writes_resource::<resources::frame_packet::Resource>()
+            .writes_resource::<resources::material_overrides::Resource>()
+            .reads_resource::<resources::light_list::Resource>()
+            .reads_resource::<resources::camera_matrices::Resource>()
+            .reads_resource::<resources::visible_set::Resource>()
+            .reads_resource::<resources::billboard_list::Resource>()
+            .reads_resource::<resources::lod_settings::Resource>()
+            .reads::<components::renderable::Component>()
+            .reads::<components::gpu_buffer::Component>()
+            .reads::<components::transform::Component>()
+            .reads::<components::mesh_renderer::Component>()
+            .reads::<components::lod::Component>()
+            .reads::<components::material_override::Component>()
+            .reads::<WorldTransform>()
+            .after("CullingSystem")
+            .after("LodSystem")
+            .after("LightGatherSystem")
+            .after("BillboardSystem");
+
+    schedule
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_engine_schedule() {
+        let order = schedule().order().unwrap();
+
+        let position = |name| order.iter().position(|system| *system == name).unwrap();
+
+        assert!(position("TransformSystem") < position("HierarchySystem"));
+        assert!(position("HierarchySystem") < position("BoundsSystem"));
+        assert!(position("BoundsSystem") < position("CullingSystem"));
+        assert!(position("BoundsSystem") < position("SpatialIndexSystem"));
+        assert!(position("SpatialIndexSystem") < position("ClearWorldBoundsChanges"));
+        assert!(position("CameraSystem") < position("CullingSystem"));
+        assert!(position("CullingSystem") < position("RenderSubmissionSystem"));
+        assert!(position("LightGatherSystem") < position("RenderSubmissionSystem"));
+        assert!(position("CameraSystem") < position("BillboardSystem"));
+        assert!(position("BillboardSystem") < position("RenderSubmissionSystem"));
+        assert!(position("CullingSystem") < position("LodSystem"));
+        assert!(position("LodSystem") < position("RenderSubmissionSystem"));
+    }
+}
@@ -0,0 +1,25 @@
+//! Name index system, which keeps the name index resource in step with every entity's name component
+
+use specs;
+use specs::Join;
+
+pub struct System;
+
+impl specs::System<super::Delta> for System {
+    fn run(&mut self, arg: specs::RunArg, _: super::Delta) {
+        use ::components::name::Component as Name;
+
+        use ::resources::name_index::Resource as NameIndex;
+
+        let (ref mut index, ref names, ref entities) = arg.fetch(|world| {
+            (
+                world.write_resource::<NameIndex>(),
+                world.read::<Name>(),
+                world.entities(),
+            )
+        });
+
+        //Storages can't report what changed, so every name is compared against the index instead
+        index.sync((names, entities).iter().map(|(name, entity)| (entity, name.0.as_str())));
+    }
+}
@@ -0,0 +1,5 @@
+//! Orphan system, which finds entities whose parent was deleted without them and despawns or detaches them
+//!
+//! See `core::ecs::systems::orphans`, which this re-exports so games can schedule it alongside the other systems.
+
+pub use core::ecs::systems::orphans::*;
@@ -0,0 +1,161 @@
+//! Render submission system, which sends everything to be drawn this update to the render loop as a frame packet
+
+use specs;
+use specs::Join;
+
+use nalgebra::{Matrix4, Eye};
+
+use ::resources::render_queue::RenderItem;
+
+pub struct System;
+
+impl specs::System<super::Delta> for System {
+    fn run(&mut self, arg: specs::RunArg, _: super::Delta) {
+        use ::components::transform::Component as Transform;
+        use ::components::gpu_buffer::Component as GPU_Buffer;
+        use ::components::renderable::Component as Renderable;
+        use ::components::mesh_renderer::Component as MeshRenderer;
+        use ::components::hierarchy::world_transform::Component as WorldTransform;
+        use ::components::lod::Component as Lod;
+        use ::components::material_override::Component as MaterialOverride;
+
+        use ::resources::frame_packet::Resource as FrameSender;
+        use ::resources::light_list::Resource as LightList;
+        use ::resources::visible_set::Resource as VisibleSet;
+        use ::resources::camera_matrices::Resource as CameraMatrices;
+        use ::resources::billboard_list::Resource as BillboardList;
+        use ::resources::lod_settings::Resource as LodSettings;
+        use ::resources::material_overrides::Resource as MaterialOverrides;
+
+        let (ref mut sender, ref mut merged_materials, ref lights, ref camera, ref visible_set, ref billboards, ref lod_settings,
+            ref renderables, ref gpu_buffers, ref transforms, ref mesh_renderers, ref lods, ref material_overrides,
+            ref world_transforms, ref entities) = arg.fetch(|world| {
+            (
+                world.write_resource::<FrameSender>(),
+                world.write_resource::<MaterialOverrides>(),
+                world.read_resource::<LightList>(),
+                world.read_resource::<CameraMatrices>(),
+                world.read_resource::<VisibleSet>(),
+                world.read_resource::<BillboardList>(),
+                world.read_resource::<LodSettings>(),
+                world.read::<Renderable>(),
+                world.read::<GPU_Buffer>(),
+                world.read::<Transform>(),
+                world.read::<MeshRenderer>(),
+                world.read::<Lod>(),
+                world.read::<MaterialOverride>(),
+                world.read::<WorldTransform>(),
+                world.entities(),
+            )
+        });
+
+        let mut packet = sender.next_frame();
+
+        for (_, gpu_buffer, entity) in (renderables, gpu_buffers, entities).iter() {
+            let (matrix, inverse) = if let Some(transform) = transforms.get(entity) {
+                (transform.matrix, transform.inverse)
+            } else {
+                (Matrix4::new_identity(4), Some(Matrix4::new_identity(4)))
+            };
+
+            packet.items.push(RenderItem {
+                buffer: gpu_buffer.buffer(),
+                range: None,
+                transform: matrix,
+                inverse: inverse,
+                visible: visible_set.contains(entity),
+                material: None,
+                object_id: entity.get_id() as u32,
+                cast_shadows: true,
+            });
+        }
+
+        for (transform, renderer, entity) in (world_transforms, mesh_renderers, entities).iter() {
+            let visible = visible_set.contains(entity);
+
+            //Culled entities are still needed for shadows, unless they don't cast any
+            if !renderer.visible || !(visible || renderer.cast_shadows) {
+                continue;
+            }
+
+            let lod = lods.get(entity);
+
+            //A level that hasn't loaded yet is drawn with the renderer's own mesh until it has
+            let (level, mesh) = match lod.and_then(|lod| lod.mesh(&renderer.mesh).get().map(|mesh| (lod.current, mesh))) {
+                Some(selected) => selected,
+                None => match renderer.mesh.get() {
+                    Some(mesh) => (0, mesh),
+                    None => continue,
+                },
+            };
+
+            //Each submesh's material, or a single material for the whole mesh if it has no submesh table
+            let parts: Vec<_> = if mesh.submeshes.is_empty() {
+                vec![(None, 0)]
+            } else {
+                mesh.submeshes.iter().map(|submesh| (Some(submesh.range), submesh.material)).collect()
+            };
+
+            let mut materials = Vec::with_capacity(parts.len());
+
+            for &(_, index) in &parts {
+                materials.push(match renderer.materials.get(index) {
+                    Some(handle) => match handle.get() {
+                        Some(material) => Some(material),
+                        None => break,
+                    },
+                    None => None,
+                });
+            }
+
+            //Drawing some submeshes without others would look broken, so wait until everything has loaded
+            if materials.len() < parts.len() {
+                continue;
+            }
+
+            //Overrides are merged over each part's own material, leaving parts drawn with the default material alone
+            if let Some(material_override) = material_overrides.get(entity) {
+                for slot in &mut materials {
+                    if let Some(ref mut material) = *slot {
+                        let merged = merged_materials.merge(material, material_override);
+
+                        *material = merged;
+                    }
+                }
+            }
+
+            //Every part is drawn in the color of its level instead, when debugging levels of detail
+            if lod.is_some() && lod_settings.is_debug() {
+                let material = lod_settings.debug_material(level);
+
+                for slot in &mut materials {
+                    *slot = Some(material.clone());
+                }
+            }
+
+            for (&(range, _), material) in parts.iter().zip(materials) {
+                packet.items.push(RenderItem {
+                    buffer: mesh.buffer.clone(),
+                    range: range,
+                    transform: transform.matrix,
+                    inverse: transform.inverse,
+                    visible: visible,
+                    material: material,
+                    object_id: entity.get_id() as u32,
+                    cast_shadows: renderer.cast_shadows,
+                });
+            }
+        }
+
+        packet.items.sort_by_key(RenderItem::sort_key);
+
+        //Merged materials no entity used this update aren't kept around for later ones
+        merged_materials.collect();
+
+        packet.lights.extend_from_slice(lights.lights());
+        packet.billboards.extend_from_slice(billboards.batches());
+        packet.camera = **camera;
+
+        sender.send(packet);
+    }
+}
@@ -0,0 +1,12 @@
+//! Explicit system scheduling, from the data each system uses and the systems it has to follow
+//!
+//! See `core::ecs::schedule`. These are its types for systems run with this crate's `Delta`.
+
+use core::ecs::schedule;
+
+use super::Delta;
+
+pub use core::ecs::schedule::{ScheduleError, ScheduleResult, SystemTiming, SystemTimings};
+
+pub type Schedule = schedule::Schedule<Delta>;
+pub type ScheduledSystem = schedule::ScheduledSystem<Delta>;
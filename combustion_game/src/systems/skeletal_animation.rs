@@ -0,0 +1,5 @@
+//! Skeletal animation system, which plays every skeletal animator's clip and recomputes its skinning matrices each tick
+//!
+//! See `core::ecs::systems::skeletal_animation`, which this re-exports so games can schedule it alongside the other systems.
+
+pub use core::ecs::systems::skeletal_animation::*;
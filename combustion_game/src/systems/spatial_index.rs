@@ -0,0 +1,170 @@
+//! Spatial index system, which keeps the spatial index in sync with world bounds
+//!
+//! Only entities whose world bounds were inserted, modified or removed since the last update are moved in the index.
+//! The index is rebuilt from scratch instead the first time the system runs, whenever it has missed changes, and
+//! whenever the index asks for it.
+
+use specs;
+use specs::Join;
+
+use core::ecs::storage::{tracked, ReaderId};
+
+pub struct System {
+    reader: ReaderId,
+}
+
+impl System {
+    pub fn new() -> System {
+        System { reader: ReaderId::new() }
+    }
+}
+
+impl specs::System<super::Delta> for System {
+    fn run(&mut self, arg: specs::RunArg, _: super::Delta) {
+        use ::components::world_bounds::Component as WorldBounds;
+
+        use ::resources::spatial_index::Resource as SpatialIndex;
+
+        let (ref mut index, ref world_bounds, ref entities) = arg.fetch(|world| {
+            (
+                world.write_resource::<SpatialIndex>(),
+                world.read::<WorldBounds>(),
+                world.entities(),
+            )
+        });
+
+        let missed = self.reader.missed();
+
+        let changes = tracked(world_bounds).read_changes(&mut self.reader);
+
+        if !index.needs_rebuild() && self.reader.missed() == missed {
+            for &id in &changes.removed {
+                index.remove_by_id(id);
+            }
+
+            //Only joined immutably, so reading every world bounds here doesn't count as changing them
+            if !changes.inserted.is_empty() || !changes.modified.is_empty() {
+                for (bounds, entity) in (world_bounds, entities).iter() {
+                    if changes.is_dirty(entity.get_id()) {
+                        index.insert(entity, bounds.aabb);
+                    }
+                }
+            }
+
+            if !index.needs_rebuild() {
+                return;
+            }
+        }
+
+        index.rebuild((world_bounds, entities).iter().map(|(bounds, entity)| (entity, bounds.aabb)));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use nalgebra::{Point3, Vector3, Matrix4};
+
+    use core::ecs::storage::{ClearChanges, tracked_mut};
+
+    use ::components::gpu_buffer::Bounds;
+    use ::components::world_bounds::Component as WorldBounds;
+
+    use ::resources::spatial_index::Resource as SpatialIndex;
+
+    fn step(planner: &mut specs::Planner<super::super::Delta>) {
+        planner.dispatch(0.0);
+        planner.wait();
+    }
+
+    /// World bounds of a unit cube centered on `(x, y, z)`
+    fn cube(x: f32, y: f32, z: f32) -> WorldBounds {
+        let local = Bounds { min: Point3::new(-1.0, -1.0, -1.0), max: Point3::new(1.0, 1.0, 1.0) };
+
+        let mut matrix = Matrix4::new_identity(4);
+
+        matrix.m14 = x;
+        matrix.m24 = y;
+        matrix.m34 = z;
+
+        WorldBounds::new(&local, &matrix)
+    }
+
+    /// Entities the index finds along +X from the origin, nearest first
+    fn along_x(planner: &mut specs::Planner<super::super::Delta>) -> Vec<specs::Entity> {
+        let world = planner.mut_world();
+        let index = world.read_resource::<SpatialIndex>();
+
+        index.raycast(&Point3::new(0.0, 0.0, 0.0), &Vector3::new(1.0, 0.0, 0.0), 1000.0).iter().map(|hit| hit.entity).collect()
+    }
+
+    #[test]
+    fn test_follows_world_bounds() {
+        let mut world = specs::World::new();
+
+        world.register::<WorldBounds>();
+        world.add_resource(SpatialIndex::new());
+
+        let near = world.create_now().with(cube(10.0, 0.0, 0.0)).build();
+        let far = world.create_now().with(cube(20.0, 0.0, 0.0)).build();
+        let aside = world.create_now().with(cube(0.0, 10.0, 0.0)).build();
+
+        let mut planner = specs::Planner::new(world, 4);
+
+        planner.add_system(System::new(), "SpatialIndexSystem", 1);
+        planner.add_system(ClearChanges::<WorldBounds>::new(), "ClearWorldBoundsChanges", 0);
+
+        step(&mut planner);
+
+        assert_eq!(along_x(&mut planner), vec![near, far]);
+
+        //Moving, adding and removing bounds are each picked up
+        *planner.mut_world().write::<WorldBounds>().get_mut(aside).unwrap() = cube(5.0, 0.0, 0.0);
+        *planner.mut_world().write::<WorldBounds>().get_mut(far).unwrap() = cube(2.0, 0.0, 0.0);
+
+        let added = planner.mut_world().create_now().with(cube(30.0, 0.0, 0.0)).build();
+
+        planner.mut_world().write::<WorldBounds>().remove(near);
+
+        step(&mut planner);
+
+        assert_eq!(along_x(&mut planner), vec![far, aside, added]);
+
+        //So is deleting entities, and the index being invalidated leaves it the same once rebuilt
+        planner.mut_world().delete_now(aside);
+        planner.mut_world().write_resource::<SpatialIndex>().invalidate();
+
+        step(&mut planner);
+
+        assert_eq!(along_x(&mut planner), vec![far, added]);
+        assert_eq!(planner.mut_world().read_resource::<SpatialIndex>().len(), 2);
+    }
+
+    #[test]
+    fn test_rebuilds_after_missed_changes() {
+        let mut world = specs::World::new();
+
+        world.register::<WorldBounds>();
+        world.add_resource(SpatialIndex::new());
+
+        let first = world.create_now().with(cube(10.0, 0.0, 0.0)).build();
+
+        let mut planner = specs::Planner::new(world, 4);
+
+        planner.add_system(System::new(), "SpatialIndexSystem", 0);
+
+        step(&mut planner);
+
+        assert_eq!(along_x(&mut planner), vec![first]);
+
+        //Changes cleared before the system could read them can only be caught by starting again
+        let second = planner.mut_world().create_now().with(cube(5.0, 0.0, 0.0)).build();
+
+        tracked_mut(&mut planner.mut_world().write::<WorldBounds>()).end_frame();
+
+        step(&mut planner);
+
+        assert_eq!(along_x(&mut planner), vec![second, first]);
+    }
+}
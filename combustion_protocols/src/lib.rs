@@ -21,6 +21,7 @@ extern crate serde_derive;
 extern crate trace_error;
 extern crate base64;
 extern crate blob;
+extern crate image;
 #[cfg(test)]
 extern crate serde_json;
 
@@ -1,6 +1,6 @@
 #![allow(missing_docs)]
 
-use nalgebra::{Vector3, Point3, Matrix4};
+use nalgebra::{Vector3, Point3, Matrix4, Quaternion};
 
 include!(concat!(env!("OUT_DIR"), "/protocols/math_capnp.rs"));
 
@@ -64,4 +64,20 @@ impl<'a> point3::Reader<'a> {
     pub fn get_point(&self) -> Point3<f32> {
         Point3::new(self.get_x(), self.get_y(), self.get_z())
     }
-}
\ No newline at end of file
+}
+
+impl<'a> quaternion::Builder<'a> {
+    pub fn set_quaternion(&mut self, quaternion: &Quaternion<f32>) {
+        self.set_w(quaternion.w);
+        self.set_i(quaternion.i);
+        self.set_j(quaternion.j);
+        self.set_k(quaternion.k);
+    }
+}
+
+impl<'a> quaternion::Reader<'a> {
+    #[inline]
+    pub fn get_quaternion(&self) -> Quaternion<f32> {
+        Quaternion::new(self.get_w(), self.get_i(), self.get_j(), self.get_k())
+    }
+}
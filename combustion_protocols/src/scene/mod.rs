@@ -11,6 +11,7 @@ use common::traits::named::DefaultName;
 use ::math::data::Transform;
 
 pub mod defaults;
+pub mod protocol;
 
 #[cfg(feature = "sample")]
 pub mod sample;
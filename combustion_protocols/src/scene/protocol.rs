@@ -0,0 +1,3 @@
+#![allow(missing_docs)]
+
+include!(concat!(env!("OUT_DIR"), "/protocols/scene_capnp.rs"));
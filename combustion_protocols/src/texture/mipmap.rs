@@ -0,0 +1,318 @@
+//! CPU-side mipmap generation for uncompressed 8-bit textures
+
+use std::f32::consts::PI;
+
+/// Filter used when downsampling each mipmap level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MipmapFilter {
+    /// Area-weighted box filter. Fast, but prone to aliasing.
+    Box,
+    /// Kaiser-windowed sinc filter. Slower, but retains much more detail in the smaller levels.
+    Kaiser,
+}
+
+/// Describes what the texture data represents, which determines how values are treated while filtering
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MipmapContent {
+    /// Linear data, filtered as-is
+    Linear,
+    /// sRGB encoded color data. Color channels are converted to linear space before filtering
+    /// and back to sRGB afterwards, while alpha is always filtered linearly.
+    Srgb,
+    /// Tangent-space normal map. Vectors are renormalized after filtering each level.
+    ///
+    /// Two-channel normal maps are assumed to have an implicit Z component,
+    /// so only vectors longer than one are renormalized.
+    Normal,
+}
+
+/// Radius of the Kaiser filter, in destination pixels
+const KAISER_RADIUS: f32 = 3.0;
+
+/// Alpha (shape) parameter of the Kaiser window
+const KAISER_ALPHA: f32 = 4.0;
+
+/// A single generated mipmap level
+#[derive(Debug, Clone)]
+pub struct MipmapLevel {
+    /// Width of the level
+    pub width: u32,
+    /// Height of the level
+    pub height: u32,
+    /// Tightly packed pixel data, with the same number of channels as the source
+    pub data: Vec<u8>,
+}
+
+/// Number of levels below the base level required to reach a 1x1 texture
+pub fn num_levels(width: u32, height: u32) -> u32 {
+    let mut largest = width.max(height);
+    let mut levels = 0;
+
+    while largest > 1 {
+        largest /= 2;
+        levels += 1;
+    }
+
+    levels
+}
+
+/// Generates the full mipmap chain for the given tightly packed 8-bit image,
+/// not including the base level itself.
+///
+/// Each level is generated from the previous one in linear space, down to and including a 1x1 level.
+/// Non-power-of-two sizes are rounded down at each level.
+pub fn generate(data: &[u8], width: u32, height: u32, channels: usize,
+                filter: MipmapFilter, content: MipmapContent) -> Vec<MipmapLevel> {
+    assert_eq!(data.len(), width as usize * height as usize * channels);
+
+    let mut levels = Vec::with_capacity(num_levels(width, height) as usize);
+
+    let mut current = decode(data, channels, content);
+    let (mut current_width, mut current_height) = (width, height);
+
+    while current_width > 1 || current_height > 1 {
+        let next_width = (current_width / 2).max(1);
+        let next_height = (current_height / 2).max(1);
+
+        current = downsample(&current, current_width, current_height, next_width, next_height, channels, filter);
+
+        if content == MipmapContent::Normal {
+            renormalize(&mut current, channels);
+        }
+
+        current_width = next_width;
+        current_height = next_height;
+
+        levels.push(MipmapLevel {
+            width: current_width,
+            height: current_height,
+            data: encode(&current, channels, content),
+        });
+    }
+
+    levels
+}
+
+/// Checks if the given channel holds alpha, which is never gamma corrected or remapped.
+///
+/// Two-channel normal maps hold X and Y, so they have no alpha.
+#[inline]
+fn is_alpha(channel: usize, channels: usize, content: MipmapContent) -> bool {
+    match channels {
+        2 => content != MipmapContent::Normal && channel == 1,
+        4 => channel == 3,
+        _ => false,
+    }
+}
+
+/// Converts a single sRGB encoded value to linear space
+#[inline]
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a single linear value to sRGB encoding
+#[inline]
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Converts 8-bit data into floating point values in the space filtering is performed in
+fn decode(data: &[u8], channels: usize, content: MipmapContent) -> Vec<f32> {
+    data.iter().enumerate().map(|(i, &c)| {
+        let value = c as f32 / 255.0;
+        let channel = i % channels;
+
+        match content {
+            MipmapContent::Linear => value,
+            MipmapContent::Srgb if is_alpha(channel, channels, content) => value,
+            MipmapContent::Srgb => srgb_to_linear(value),
+            MipmapContent::Normal if channel < 3 && !is_alpha(channel, channels, content) => value * 2.0 - 1.0,
+            MipmapContent::Normal => value,
+        }
+    }).collect()
+}
+
+/// Converts filtered floating point values back into 8-bit data
+fn encode(data: &[f32], channels: usize, content: MipmapContent) -> Vec<u8> {
+    data.iter().enumerate().map(|(i, &value)| {
+        let channel = i % channels;
+
+        let value = match content {
+            MipmapContent::Linear => value,
+            MipmapContent::Srgb if is_alpha(channel, channels, content) => value,
+            MipmapContent::Srgb => linear_to_srgb(value.max(0.0)),
+            MipmapContent::Normal if channel < 3 && !is_alpha(channel, channels, content) => value * 0.5 + 0.5,
+            MipmapContent::Normal => value,
+        };
+
+        (value.max(0.0).min(1.0) * 255.0).round() as u8
+    }).collect()
+}
+
+/// Renormalizes the vectors of a decoded normal map
+fn renormalize(data: &mut [f32], channels: usize) {
+    for pixel in data.chunks_mut(channels) {
+        match channels {
+            3 | 4 => {
+                let length = (pixel[0] * pixel[0] + pixel[1] * pixel[1] + pixel[2] * pixel[2]).sqrt();
+
+                if length > 1e-6 {
+                    pixel[0] /= length;
+                    pixel[1] /= length;
+                    pixel[2] /= length;
+                }
+            },
+            // Z is reconstructed from X and Y, so those can only be shortened
+            2 => {
+                let length = (pixel[0] * pixel[0] + pixel[1] * pixel[1]).sqrt();
+
+                if length > 1.0 {
+                    pixel[0] /= length;
+                    pixel[1] /= length;
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Computes area-weighted box filter taps for each destination pixel along one axis.
+///
+/// For odd source sizes each destination pixel covers one and a half source pixels,
+/// so the shared middle pixel is split between its neighbors instead of the last row or column being dropped.
+fn box_weights(src_len: usize, dst_len: usize) -> Vec<Vec<(usize, f32)>> {
+    let scale = src_len as f32 / dst_len as f32;
+
+    (0..dst_len).map(|i| {
+        let start = i as f32 * scale;
+        let end = start + scale;
+
+        let mut taps = Vec::with_capacity(scale.ceil() as usize + 1);
+
+        for j in start.floor() as usize..(end.ceil() as usize).min(src_len) {
+            let overlap = end.min(j as f32 + 1.0) - start.max(j as f32);
+
+            if overlap > 1e-6 {
+                taps.push((j, overlap / scale));
+            }
+        }
+
+        taps
+    }).collect()
+}
+
+/// Zeroth order modified Bessel function of the first kind, via its power series
+fn bessel_i0(x: f32) -> f32 {
+    let half = x * 0.5;
+
+    let mut sum = 1.0;
+    let mut term = 1.0;
+
+    for k in 1..32 {
+        term *= (half / k as f32) * (half / k as f32);
+        sum += term;
+
+        if term < sum * 1e-8 {
+            break;
+        }
+    }
+
+    sum
+}
+
+#[inline]
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 { 1.0 } else { (PI * x).sin() / (PI * x) }
+}
+
+#[inline]
+fn kaiser(t: f32) -> f32 {
+    if t.abs() >= 1.0 {
+        0.0
+    } else {
+        bessel_i0(KAISER_ALPHA * (1.0 - t * t).sqrt()) / bessel_i0(KAISER_ALPHA)
+    }
+}
+
+/// Computes the normalized filter taps for each destination pixel along one axis
+fn kaiser_weights(src_len: usize, dst_len: usize) -> Vec<Vec<(usize, f32)>> {
+    let scale = src_len as f32 / dst_len as f32;
+    let support = KAISER_RADIUS * scale;
+
+    (0..dst_len).map(|i| {
+        let center = (i as f32 + 0.5) * scale;
+
+        let start = (center - support).floor() as isize;
+        let end = (center + support).ceil() as isize;
+
+        let mut taps = Vec::with_capacity((end - start) as usize);
+        let mut total = 0.0;
+
+        for j in start..end {
+            let distance = (j as f32 + 0.5 - center) / scale;
+            let weight = sinc(distance) * kaiser(distance / KAISER_RADIUS);
+
+            if weight != 0.0 {
+                // Clamp to edge
+                let index = j.max(0).min(src_len as isize - 1) as usize;
+
+                taps.push((index, weight));
+                total += weight;
+            }
+        }
+
+        for tap in &mut taps {
+            tap.1 /= total;
+        }
+
+        taps
+    }).collect()
+}
+
+/// Separable downsample using the given per-axis filter taps
+fn downsample(src: &[f32], width: u32, height: u32, next_width: u32, next_height: u32, channels: usize, filter: MipmapFilter) -> Vec<f32> {
+    let (width, height) = (width as usize, height as usize);
+    let (next_width, next_height) = (next_width as usize, next_height as usize);
+
+    let (horizontal, vertical) = match filter {
+        MipmapFilter::Box => (box_weights(width, next_width), box_weights(height, next_height)),
+        MipmapFilter::Kaiser => (kaiser_weights(width, next_width), kaiser_weights(height, next_height)),
+    };
+
+    // Filter rows first
+    let mut tmp = vec![0.0; next_width * height * channels];
+
+    for y in 0..height {
+        for (x, taps) in horizontal.iter().enumerate() {
+            for &(sx, weight) in taps {
+                for c in 0..channels {
+                    tmp[(y * next_width + x) * channels + c] += src[(y * width + sx) * channels + c] * weight;
+                }
+            }
+        }
+    }
+
+    // Then columns
+    let mut dst = vec![0.0; next_width * next_height * channels];
+
+    for (y, taps) in vertical.iter().enumerate() {
+        for &(sy, weight) in taps {
+            for x in 0..next_width {
+                for c in 0..channels {
+                    dst[(y * next_width + x) * channels + c] += tmp[(sy * next_width + x) * channels + c] * weight;
+                }
+            }
+        }
+    }
+
+    dst
+}
@@ -1,6 +1,7 @@
 //! Generic texture protocol
 
 pub mod data;
+pub mod mipmap;
 pub mod protocol;
 pub mod storage;
 
@@ -1,10 +1,13 @@
 //! Storage routines for textures
 
-use ::error::ProtocolResult;
+use image::{DynamicImage, GenericImage, ImageBuffer};
+
+use ::error::{ProtocolResult, ProtocolError};
 use ::traits::{Storage, StorageQuery};
 
 use super::data::{format, texture};
 use super::data::texture::{Texture, RootTexture};
+use super::mipmap::{self, MipmapFilter, MipmapContent};
 use super::protocol;
 
 /// Query for determining `RootTexture` variation without actually loading the data into memory
@@ -208,4 +211,180 @@ impl<'a> Storage<'a> for RootTexture {
             protocol::root_texture::texture::Array(_) => RootTextureQuery::Array,
         })
     }
-}
\ No newline at end of file
+}
+
+/// Options for encoding an image into a texture protocol
+#[derive(Debug, Clone, Copy)]
+pub struct ImageSaveOptions {
+    /// Mark the texture as being in the sRGB color space
+    pub srgb: bool,
+    /// Generate a full mipmap chain using the given filter.
+    ///
+    /// For sRGB textures, filtering is performed in linear space.
+    pub mipmaps: Option<MipmapFilter>,
+    /// Treat the image as a tangent-space normal map, so generated mipmaps are renormalized
+    /// instead of gamma corrected.
+    pub normal_map: bool,
+    /// Flip the image vertically before encoding, since OpenGL expects the first row to be the bottom row
+    pub flip_y: bool,
+    /// Convert the image to the given channel layout before encoding.
+    ///
+    /// Channels are truncated or expanded as-is, not converted to luminance,
+    /// so for example `Rgb -> Rg` keeps the red and green channels of a normal map.
+    ///
+    /// If `None`, the natural layout of the image is used.
+    pub channels: Option<protocol::Channels>,
+}
+
+impl Default for ImageSaveOptions {
+    fn default() -> ImageSaveOptions {
+        ImageSaveOptions {
+            srgb: false,
+            mipmaps: None,
+            normal_map: false,
+            flip_y: false,
+            channels: None,
+        }
+    }
+}
+
+/// Returns the channel layout an image is stored in
+pub fn image_channels(image: &DynamicImage) -> protocol::Channels {
+    match *image {
+        DynamicImage::ImageLuma8(_) => protocol::Channels::R,
+        DynamicImage::ImageLumaA8(_) => protocol::Channels::Rg,
+        DynamicImage::ImageRgb8(_) => protocol::Channels::Rgb,
+        DynamicImage::ImageRgba8(_) => protocol::Channels::Rgba,
+    }
+}
+
+/// Returns the image pixels in the requested channel layout
+fn convert_channels(image: &DynamicImage, channels: protocol::Channels) -> Vec<u8> {
+    if image_channels(image) == channels {
+        return image.raw_pixels();
+    }
+
+    let rgba = image.to_rgba().into_raw();
+
+    let n = channels.num_channels();
+
+    let mut pixels = Vec::with_capacity(rgba.len() / 4 * n);
+
+    for pixel in rgba.chunks(4) {
+        pixels.extend_from_slice(&pixel[..n]);
+    }
+
+    pixels
+}
+
+fn flip_rows(pixels: &[u8], row_length: usize) -> Vec<u8> {
+    let mut flipped = Vec::with_capacity(pixels.len());
+
+    for row in pixels.chunks(row_length).rev() {
+        flipped.extend_from_slice(row);
+    }
+
+    flipped
+}
+
+/// Encodes an image into a texture builder as an uncompressed 8-bit texture, applying the given options.
+///
+/// Images with a height of one are saved as 1D textures, everything else is saved as a 2D texture.
+/// Single column images stay 2D, since a 1D texture's pixels run along its width.
+pub fn save_image_to_builder(mut builder: protocol::texture::Builder,
+                             image: &DynamicImage,
+                             options: ImageSaveOptions) -> ProtocolResult<()> {
+    let (width, height) = image.dimensions();
+
+    let channels = options.channels.unwrap_or_else(|| image_channels(image));
+
+    let mut pixels = convert_channels(image, channels);
+
+    if options.flip_y {
+        pixels = flip_rows(&pixels, width as usize * channels.num_channels());
+    }
+
+    builder.set_kind(if height == 1 {
+        protocol::TextureKind::Texture1D
+    } else {
+        protocol::TextureKind::Texture2D
+    });
+
+    {
+        let mut dimensions_builder = builder.borrow().init_dimensions();
+
+        dimensions_builder.set_width(width);
+        dimensions_builder.set_height(height);
+        dimensions_builder.set_depth(0);
+    }
+
+    builder.set_srgb(options.srgb);
+
+    {
+        let mut uncompressed_builder = builder.borrow().init_compression().init_none();
+
+        uncompressed_builder.set_format(channels);
+        uncompressed_builder.set_type(protocol::DataType::UnsignedByte);
+    }
+
+    if let Some(filter) = options.mipmaps {
+        let content = if options.normal_map {
+            MipmapContent::Normal
+        } else if options.srgb {
+            MipmapContent::Srgb
+        } else {
+            MipmapContent::Linear
+        };
+
+        let levels = mipmap::generate(&pixels, width, height, channels.num_channels(), filter, content);
+
+        let mut mipmaps_builder = builder.borrow().init_mipmaps(levels.len() as u32);
+
+        for (i, level) in levels.iter().enumerate() {
+            mipmaps_builder.set(i as u32, &level.data);
+        }
+    }
+
+    builder.set_data(&pixels);
+
+    Ok(())
+}
+
+/// Decodes the base level of a texture into an image.
+///
+/// Only uncompressed textures with an unsigned byte data type can be decoded. Any mipmaps are ignored.
+pub fn load_texture_as_image(reader: protocol::texture::Reader) -> ProtocolResult<DynamicImage> {
+    let channels = match try_throw!(reader.get_compression().which()) {
+        protocol::texture::compression::None(uncompressed_reader) => {
+            let uncompressed_reader = try_throw!(uncompressed_reader);
+
+            match try_throw!(uncompressed_reader.get_type()) {
+                protocol::DataType::UnsignedByte | protocol::DataType::Unspecified => {}
+                data_type => throw!(ProtocolError::MismatchedTypes(data_type, protocol::DataType::UnsignedByte)),
+            }
+
+            try_throw!(uncompressed_reader.get_format())
+        },
+        _ => throw!(ProtocolError::Unsupported),
+    };
+
+    let dimensions_reader = reader.get_dimensions();
+
+    let width = dimensions_reader.get_width();
+    let height = dimensions_reader.get_height().max(1);
+
+    let data = try_throw!(reader.get_data()).to_vec();
+
+    if data.len() != width as usize * height as usize * channels.num_channels() {
+        throw!(ProtocolError::InvalidLength);
+    }
+
+    let image = match channels {
+        protocol::Channels::R => ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageLuma8),
+        protocol::Channels::Rg => ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageLumaA8),
+        protocol::Channels::Rgb => ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgb8),
+        protocol::Channels::Rgba => ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgba8),
+    };
+
+    Ok(try_throw!(image.ok_or(ProtocolError::InvalidLength)))
+}
@@ -0,0 +1,170 @@
+extern crate combustion_protocols as protocols;
+extern crate capnp;
+extern crate image;
+
+use capnp::message::Builder;
+
+use image::{DynamicImage, GenericImage, ImageBuffer};
+
+use protocols::texture::protocol;
+use protocols::texture::mipmap::MipmapFilter;
+use protocols::texture::storage::{save_image_to_builder, load_texture_as_image, ImageSaveOptions};
+
+fn pattern(width: u32, height: u32, channels: usize) -> Vec<u8> {
+    (0..width as usize * height as usize * channels).map(|i| (i * 37 % 256) as u8).collect()
+}
+
+fn sample_images() -> Vec<DynamicImage> {
+    let (w, h) = (5, 3);
+
+    vec![
+        DynamicImage::ImageLuma8(ImageBuffer::from_raw(w, h, pattern(w, h, 1)).unwrap()),
+        DynamicImage::ImageLumaA8(ImageBuffer::from_raw(w, h, pattern(w, h, 2)).unwrap()),
+        DynamicImage::ImageRgb8(ImageBuffer::from_raw(w, h, pattern(w, h, 3)).unwrap()),
+        DynamicImage::ImageRgba8(ImageBuffer::from_raw(w, h, pattern(w, h, 4)).unwrap()),
+    ]
+}
+
+fn round_trip(image: &DynamicImage, options: ImageSaveOptions) -> DynamicImage {
+    let mut message = Builder::new_default();
+
+    save_image_to_builder(message.init_root::<protocol::texture::Builder>(), image, options).unwrap();
+
+    load_texture_as_image(message.get_root_as_reader::<protocol::texture::Reader>().unwrap()).unwrap()
+}
+
+#[test]
+fn round_trip_all_variants() {
+    for image in sample_images() {
+        let loaded = round_trip(&image, ImageSaveOptions::default());
+
+        assert_eq!(image.color(), loaded.color());
+        assert_eq!(image.dimensions(), loaded.dimensions());
+        assert_eq!(image.raw_pixels(), loaded.raw_pixels());
+    }
+}
+
+#[test]
+fn round_trip_flipped_twice() {
+    for image in sample_images() {
+        let options = ImageSaveOptions { flip_y: true, ..ImageSaveOptions::default() };
+
+        let flipped = round_trip(&image, options);
+
+        assert!(image.raw_pixels() != flipped.raw_pixels());
+        assert_eq!(image.raw_pixels(), round_trip(&flipped, options).raw_pixels());
+    }
+}
+
+#[test]
+fn only_rows_are_1d() {
+    for &(w, h, kind) in &[(5, 1, protocol::TextureKind::Texture1D),
+                           (1, 5, protocol::TextureKind::Texture2D),
+                           (1, 1, protocol::TextureKind::Texture1D)] {
+        let image = DynamicImage::ImageLuma8(ImageBuffer::from_raw(w, h, pattern(w, h, 1)).unwrap());
+
+        let mut message = Builder::new_default();
+
+        save_image_to_builder(message.init_root::<protocol::texture::Builder>(), &image, ImageSaveOptions::default()).unwrap();
+
+        let reader = message.get_root_as_reader::<protocol::texture::Reader>().unwrap();
+
+        assert!(reader.get_kind().unwrap() == kind);
+
+        assert_eq!(round_trip(&image, ImageSaveOptions::default()).dimensions(), (w, h));
+    }
+}
+
+#[test]
+fn convert_rgb_to_rg() {
+    let (w, h) = (4, 4);
+    let image = DynamicImage::ImageRgb8(ImageBuffer::from_raw(w, h, pattern(w, h, 3)).unwrap());
+
+    let options = ImageSaveOptions { channels: Some(protocol::Channels::Rg), ..ImageSaveOptions::default() };
+
+    let loaded = round_trip(&image, options);
+
+    let expected: Vec<u8> = image.raw_pixels().chunks(3).flat_map(|p| vec![p[0], p[1]]).collect();
+
+    assert_eq!(loaded.raw_pixels(), expected);
+}
+
+#[test]
+fn mipmap_chain_length() {
+    for &filter in &[MipmapFilter::Box, MipmapFilter::Kaiser] {
+        let (w, h) = (16, 4);
+        let image = DynamicImage::ImageRgba8(ImageBuffer::from_raw(w, h, pattern(w, h, 4)).unwrap());
+
+        let mut message = Builder::new_default();
+
+        let options = ImageSaveOptions { mipmaps: Some(filter), ..ImageSaveOptions::default() };
+
+        save_image_to_builder(message.init_root::<protocol::texture::Builder>(), &image, options).unwrap();
+
+        let reader = message.get_root_as_reader::<protocol::texture::Reader>().unwrap();
+
+        let mipmaps = reader.get_mipmaps().unwrap();
+
+        // 8x2, 4x1, 2x1, 1x1
+        assert_eq!(mipmaps.len(), 4);
+        assert_eq!(mipmaps.get(0).unwrap().len(), 8 * 2 * 4);
+        assert_eq!(mipmaps.get(3).unwrap().len(), 4);
+    }
+}
+
+#[test]
+fn srgb_mipmaps_are_gamma_correct() {
+    use protocols::texture::mipmap::{generate, MipmapContent};
+
+    // Alternating black and white columns should average to 50% linear intensity, not 50% sRGB
+    let data: Vec<u8> = (0..4 * 4).map(|i| if i % 2 == 0 { 0 } else { 255 }).collect();
+
+    let linear = generate(&data, 4, 4, 1, MipmapFilter::Box, MipmapContent::Linear);
+    let srgb = generate(&data, 4, 4, 1, MipmapFilter::Box, MipmapContent::Srgb);
+
+    assert_eq!(linear[0].data[0], 128);
+    assert_eq!(srgb[0].data[0], 188);
+}
+
+#[test]
+fn odd_mipmaps_keep_last_column() {
+    use protocols::texture::mipmap::{generate, MipmapContent};
+
+    // Only the last column is lit, which a plain 2x2 box filter would drop entirely
+    let data: Vec<u8> = (0..3).map(|i| if i == 2 { 255 } else { 0 }).collect();
+
+    let levels = generate(&data, 3, 1, 1, MipmapFilter::Box, MipmapContent::Linear);
+
+    assert_eq!(levels[0].data[0], 85);
+}
+
+#[test]
+fn normal_mipmaps_are_renormalized() {
+    use protocols::texture::mipmap::{generate, MipmapContent};
+
+    // Normals along +X and the -X/+Z diagonal average to a much shorter vector
+    let data = vec![255, 128, 128, 0, 128, 255];
+
+    let levels = generate(&data, 2, 1, 3, MipmapFilter::Box, MipmapContent::Normal);
+
+    let n: Vec<f32> = levels[0].data.iter().map(|&c| c as f32 / 255.0 * 2.0 - 1.0).collect();
+
+    let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+
+    assert!((length - 1.0).abs() < 0.02);
+}
+
+#[test]
+fn rg_normal_mipmaps_are_renormalized() {
+    use protocols::texture::mipmap::{generate, MipmapContent};
+
+    // Both channels of a two-channel normal map are vector components, so Y is remapped and shortened along with X
+    let data = vec![255, 0, 255, 0];
+
+    let levels = generate(&data, 2, 1, 2, MipmapFilter::Box, MipmapContent::Normal);
+
+    let n: Vec<f32> = levels[0].data.iter().map(|&c| c as f32 / 255.0 * 2.0 - 1.0).collect();
+
+    assert!((n[0] - 0.7071).abs() < 0.01);
+    assert!((n[1] + 0.7071).abs() < 0.01);
+}
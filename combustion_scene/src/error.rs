@@ -2,8 +2,7 @@ use std::error::Error;
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
 use ecs::Entity;
-use petgraph::graph::NodeIndex;
-use super::Ix;
+use graph::SceneIndex;
 
 pub type SceneResult<T> = Result<T, SceneError>;
 
@@ -13,7 +12,7 @@ pub enum SceneError {
     MissingChild(Entity),
     InvalidNode,
     InvalidEdge,
-    AlreadyExists(Entity, NodeIndex<Ix>),
+    AlreadyExists(Entity, SceneIndex),
 }
 
 impl Display for SceneError {
@@ -1,11 +1,10 @@
-use std::ops::{Deref, DerefMut};
+use std::ops::Deref;
 
 use petgraph::prelude::*;
 use petgraph::visit::*;
 use petgraph::algo::*;
 
 use ecs::{Entity, World};
-use std::collections::hash_map::Entry;
 use fnv::FnvHashMap;
 
 use error::*;
@@ -14,122 +13,232 @@ use edge::*;
 
 use super::Ix;
 
+/// Generation-checked index of a node in a `SceneGraph`.
+///
+/// Removed nodes leave their slot in the graph to be reused by the next node added, so a plain `NodeIndex`
+/// kept across a removal could end up pointing at an unrelated node. Each slot counts how many times
+/// it has been freed, and a `SceneIndex` is only valid while its generation matches.
+#[derive(Debug, Copy, Clone, Default, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct SceneIndex {
+    index: NodeIndex<Ix>,
+    generation: u32,
+}
+
+impl SceneIndex {
+    /// Index into the underlying graph, which isn't checked against removals
+    #[inline(always)]
+    pub fn index(&self) -> NodeIndex<Ix> { self.index }
+
+    /// How many times the node's slot had been freed when this index was created
+    #[inline(always)]
+    pub fn generation(&self) -> u32 { self.generation }
+}
+
 pub struct SceneGraph {
     graph: StableDiGraph<SceneNode, SceneEdge, Ix>,
     cycle_state: DfsSpace<NodeIndex<Ix>, <StableDiGraph<SceneNode, SceneEdge, Ix> as Visitable>::Map>,
-    root: NodeIndex<Ix>,
-    entity_table: FnvHashMap<Entity, NodeIndex<Ix>>,
+    /// Generation of each slot in the graph, bumped whenever the node in it is removed
+    generations: Vec<u32>,
+    root: SceneIndex,
+    entity_table: FnvHashMap<Entity, SceneIndex>,
 }
 
 impl SceneGraph {
     pub fn new(world: &World) -> SceneGraph {
-        let mut graph = StableDiGraph::default();
+        let mut graph = SceneGraph {
+            graph: StableDiGraph::default(),
+            cycle_state: DfsSpace::default(),
+            generations: Vec::new(),
+            root: SceneIndex::default(),
+            entity_table: FnvHashMap::default(),
+        };
 
-        let root = graph.add_node(SceneNode::new_entity_node(world.create_later()));
+        graph.root = graph.insert(SceneNode::new_entity_node(world.create_later()));
 
-        SceneGraph { graph: graph, cycle_state: DfsSpace::default(), root: root, entity_table: FnvHashMap::default() }
+        graph
     }
 
     #[inline(always)]
-    pub fn root(&self) -> NodeIndex<Ix> { self.root }
+    pub fn root(&self) -> SceneIndex { self.root }
 
     /// Adds a new scene node to the graph with the given parent, and returns the new node's index
-    pub fn add_child(&mut self, parent: NodeIndex<Ix>, node: SceneNode) -> SceneResult<NodeIndex<Ix>> {
-        let child_node = self.graph.add_node(node);
-
-        {
-            // Get a reference to the node we literally just added...
-            let node = self.graph.node_weight(child_node).unwrap();
+    pub fn add_child(&mut self, parent: SceneIndex, node: SceneNode) -> SceneResult<SceneIndex> {
+        let parent = try!(self.check(parent));
+
+        let entities = match node.kind() {
+            &SceneNodeKind::EntityNode(ref node) => vec![node.entity()],
+            &SceneNodeKind::MultiEntityNode(ref node) => node.to_vec(),
+            _ => Vec::new(),
+        };
+
+        // Check everything up front so a failed insertion leaves the graph untouched
+        for entity in &entities {
+            if let Some(existing) = self.entity_table.get(entity) {
+                return Err(SceneError::AlreadyExists(*entity, *existing));
+            }
+        }
 
-            match node.kind() {
-                &SceneNodeKind::EntityNode(ref node) => {
-                    let entity = node.entity();
+        let child = self.insert(node);
 
-                    match self.entity_table.entry(entity) {
-                        Entry::Occupied(entry) => {
-                            return Err(SceneError::AlreadyExists(entity, entry.get().clone()));
-                        }
-                        Entry::Vacant(entry) => {
-                            entry.insert(child_node);
-                        }
-                    }
-                }
-                &SceneNodeKind::MultiEntityNode(ref node) => {
-                    for entity in node.iter().cloned() {
-                        match self.entity_table.entry(entity) {
-                            Entry::Occupied(entry) => {
-                                return Err(SceneError::AlreadyExists(entity, entry.get().clone()));
-                            }
-                            Entry::Vacant(entry) => {
-                                entry.insert(child_node);
-                            }
-                        }
-                    }
-                }
-                _ => {}
-            }
+        for entity in entities {
+            self.entity_table.insert(entity, child);
         }
 
-        self.graph.add_edge(parent, child_node, SceneEdge {});
+        self.graph.add_edge(parent, child.index, SceneEdge {});
 
-        Ok(child_node)
+        Ok(child)
     }
 
     /// Adds a new scene node to the graph with root as its parent, and returns the new node's index.
     #[inline]
-    pub fn add_node(&mut self, node: SceneNode) -> SceneResult<NodeIndex<Ix>> {
+    pub fn add_node(&mut self, node: SceneNode) -> SceneResult<SceneIndex> {
         let root = self.root;
 
         self.add_child(root, node)
     }
 
+    /// Checks if the index still refers to a node in the graph
+    #[inline]
+    pub fn contains(&self, index: SceneIndex) -> bool {
+        self.check(index).is_ok()
+    }
+
+    /// Gets the current `SceneIndex` for a node in the graph, if there is one at that index
+    pub fn index_of(&self, index: NodeIndex<Ix>) -> Option<SceneIndex> {
+        if self.graph.node_weight(index).is_some() {
+            Some(SceneIndex { index: index, generation: self.generations[index.index()] })
+        } else {
+            None
+        }
+    }
+
     /// Find the node index for a specific entity using a lookup table.
     ///
     /// This operation is `O(1)`
     #[inline]
-    pub fn lookup_index(&self, entity: Entity) -> Option<&NodeIndex<Ix>> {
-        self.entity_table.get(&entity)
+    pub fn lookup_index(&self, entity: Entity) -> Option<SceneIndex> {
+        self.entity_table.get(&entity).cloned()
     }
 
-    /// Find the node value from the given index.
+    /// Find the node value from the given index, unless it has since been removed.
     ///
     /// This operation is `O(1)`
     #[inline]
-    pub fn lookup_node(&self, index: NodeIndex<Ix>) -> Option<&SceneNode> {
-        self.graph.node_weight(index)
+    pub fn lookup_node(&self, index: SceneIndex) -> Option<&SceneNode> {
+        self.check(index).ok().and_then(move |index| self.graph.node_weight(index))
+    }
+
+    /// Mutable version of `lookup_node`
+    #[inline]
+    pub fn lookup_node_mut(&mut self, index: SceneIndex) -> Option<&mut SceneNode> {
+        match self.check(index) {
+            Ok(index) => self.graph.node_weight_mut(index),
+            Err(_) => None,
+        }
+    }
+
+    /// Find the parent of a node. Only the root has no parent.
+    ///
+    /// This operation is `O(e')`
+    pub fn parent(&self, index: SceneIndex) -> SceneResult<Option<SceneIndex>> {
+        let index = try!(self.check(index));
+
+        Ok(self.graph.neighbors_directed(index, Incoming).next().and_then(|parent| self.index_of(parent)))
     }
 
-    /// This operation is `O(e' + e')` for the two edge lookups.
-    pub fn reparent(&mut self, child: NodeIndex<Ix>, old_parent: NodeIndex<Ix>, new_parent: NodeIndex<Ix>) -> SceneResult<()> {
-        if has_path_connecting(&self.graph, new_parent, child, Some(&mut self.cycle_state)) {
+    /// Moves a node and everything below it to a new parent.
+    ///
+    /// This operation is `O(e')` for the edge lookup, plus a search of the subtree to check it won't cycle.
+    pub fn reparent(&mut self, child: SceneIndex, new_parent: SceneIndex) -> SceneResult<()> {
+        let child = try!(self.check(child));
+        let new_parent = try!(self.check(new_parent));
+
+        // Attaching the node below itself would cut the subtree off from the root
+        if has_path_connecting(&self.graph, child, new_parent, Some(&mut self.cycle_state)) {
             return Err(SceneError::WouldCycle);
         }
 
-        if let Some(edge) = self.graph.find_edge(old_parent, child) {
-            self.graph.remove_edge(edge);
+        let edge = match self.graph.neighbors_directed(child, Incoming).next() {
+            Some(old_parent) => self.graph.find_edge(old_parent, child),
+            None => None,
+        };
 
-            self.graph.add_edge(new_parent, child, SceneEdge {});
+        let edge = match edge {
+            Some(edge) => edge,
+            None => return Err(SceneError::InvalidEdge),
+        };
 
-            Ok(())
-        } else {
-            Err(SceneError::InvalidEdge)
+        self.graph.remove_edge(edge);
+        self.graph.add_edge(new_parent, child, SceneEdge {});
+
+        Ok(())
+    }
+
+    /// Removes a node along with all of its descendants, returning them in the order they were removed.
+    ///
+    /// Every `SceneIndex` to a removed node is invalidated, even once its slot is reused. The root can't be removed.
+    pub fn recursive_remove(&mut self, index: SceneIndex) -> SceneResult<Vec<SceneNode>> {
+        let index = try!(self.check(index));
+
+        if index == self.root.index {
+            return Err(SceneError::InvalidNode);
+        }
+
+        let mut stack = vec![index];
+        let mut removed = Vec::new();
+
+        while let Some(index) = stack.pop() {
+            stack.extend(self.graph.neighbors_directed(index, Outgoing));
+
+            if let Some(node) = self.graph.remove_node(index) {
+                match node.kind() {
+                    &SceneNodeKind::EntityNode(ref node) => {
+                        self.entity_table.remove(&node.entity());
+                    }
+                    &SceneNodeKind::MultiEntityNode(ref node) => {
+                        for entity in node.iter() {
+                            self.entity_table.remove(entity);
+                        }
+                    }
+                    _ => {}
+                }
+
+                let generation = &mut self.generations[index.index()];
+
+                *generation = generation.wrapping_add(1);
+
+                removed.push(node);
+            }
         }
+
+        Ok(removed)
     }
 
-    pub fn recursive_remove(&mut self, _node: NodeIndex<Ix>) -> SceneResult<()> {
-        //TODO
-        Ok(())
+    /// Adds a node without any edges, making sure its slot has a generation
+    fn insert(&mut self, node: SceneNode) -> SceneIndex {
+        let index = self.graph.add_node(node);
+
+        if index.index() >= self.generations.len() {
+            self.generations.resize(index.index() + 1, 0);
+        }
+
+        SceneIndex { index: index, generation: self.generations[index.index()] }
+    }
+
+    /// Resolves a `SceneIndex` to its node index, if the node hasn't been removed since
+    fn check(&self, index: SceneIndex) -> SceneResult<NodeIndex<Ix>> {
+        match self.generations.get(index.index.index()) {
+            Some(&generation) if generation == index.generation && self.graph.node_weight(index.index).is_some() => Ok(index.index),
+            _ => Err(SceneError::InvalidNode),
+        }
     }
 }
 
+/// Only shared access is given to the underlying graph,
+/// since changing it directly would bypass the generation checks and entity table.
 impl Deref for SceneGraph {
     type Target = StableDiGraph<SceneNode, SceneEdge, Ix>;
 
     #[inline(always)]
     fn deref(&self) -> &Self::Target { &self.graph }
 }
-
-impl DerefMut for SceneGraph {
-    #[inline(always)]
-    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.graph }
-}
\ No newline at end of file
@@ -11,8 +11,10 @@ pub mod error;
 pub mod node;
 pub mod edge;
 pub mod graph;
+pub mod traverse;
 
 pub use error::{SceneError, SceneResult};
 pub use node::{SceneNode, SceneNodeExt, SceneNodeKind, EntityNode, MultiEntityNode};
 pub use edge::SceneEdge;
-pub use graph::SceneGraph;
\ No newline at end of file
+pub use graph::{SceneGraph, SceneIndex};
+pub use traverse::{Visit, Children, Descendants, Ancestors};
\ No newline at end of file
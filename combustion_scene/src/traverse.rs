@@ -0,0 +1,320 @@
+//! Iterators and visitors for walking the scene graph hierarchy
+//!
+//! Each of these borrows the graph, so it can't change during a traversal.
+//! Between traversals, stale indices are caught by their generation instead.
+
+use std::vec;
+use std::iter::Rev;
+
+use petgraph::prelude::*;
+
+use error::*;
+use node::SceneNode;
+use graph::{SceneGraph, SceneIndex};
+
+use super::Ix;
+
+/// What to do after visiting a node in `SceneGraph::visit_depth_first`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Visit {
+    /// Carry on into the node's children
+    Continue,
+    /// Don't visit anything below this node, but carry on with its siblings
+    Skip,
+    /// End the traversal immediately
+    Stop,
+}
+
+/// Direct children of a node, in the order they were attached to it
+pub struct Children<'a> {
+    graph: &'a SceneGraph,
+    children: Rev<vec::IntoIter<NodeIndex<Ix>>>,
+}
+
+impl<'a> Children<'a> {
+    fn new(graph: &'a SceneGraph, parent: NodeIndex<Ix>) -> Children<'a> {
+        // petgraph lists the most recently added edge first
+        let children: Vec<_> = graph.neighbors_directed(parent, Outgoing).collect();
+
+        Children { graph: graph, children: children.into_iter().rev() }
+    }
+}
+
+impl<'a> Iterator for Children<'a> {
+    type Item = SceneIndex;
+
+    fn next(&mut self) -> Option<SceneIndex> {
+        self.children.next().and_then(|child| self.graph.index_of(child))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.children.size_hint()
+    }
+}
+
+/// Every node below a node, depth first with each node coming before its children
+pub struct Descendants<'a> {
+    graph: &'a SceneGraph,
+    stack: Vec<NodeIndex<Ix>>,
+}
+
+impl<'a> Descendants<'a> {
+    fn new(graph: &'a SceneGraph, node: NodeIndex<Ix>) -> Descendants<'a> {
+        let mut descendants = Descendants { graph: graph, stack: Vec::new() };
+
+        descendants.push_children(node);
+
+        descendants
+    }
+
+    /// Pushes children so the first attached is on top of the stack
+    fn push_children(&mut self, node: NodeIndex<Ix>) {
+        self.stack.extend(self.graph.neighbors_directed(node, Outgoing));
+    }
+}
+
+impl<'a> Iterator for Descendants<'a> {
+    type Item = SceneIndex;
+
+    fn next(&mut self) -> Option<SceneIndex> {
+        self.stack.pop().and_then(|node| {
+            self.push_children(node);
+
+            self.graph.index_of(node)
+        })
+    }
+}
+
+/// Parent of a node, then its parent, and so on up to and including the root
+pub struct Ancestors<'a> {
+    graph: &'a SceneGraph,
+    current: NodeIndex<Ix>,
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = SceneIndex;
+
+    fn next(&mut self) -> Option<SceneIndex> {
+        self.graph.neighbors_directed(self.current, Incoming).next().and_then(|parent| {
+            self.current = parent;
+
+            self.graph.index_of(parent)
+        })
+    }
+}
+
+impl SceneGraph {
+    /// Iterates over the direct children of a node
+    pub fn children(&self, node: SceneIndex) -> SceneResult<Children> {
+        let node = try!(self.resolve(node));
+
+        Ok(Children::new(self, node))
+    }
+
+    /// Iterates over every node below a node, not including the node itself
+    pub fn descendants(&self, node: SceneIndex) -> SceneResult<Descendants> {
+        let node = try!(self.resolve(node));
+
+        Ok(Descendants::new(self, node))
+    }
+
+    /// Iterates upwards from the parent of a node to the root
+    pub fn ancestors(&self, node: SceneIndex) -> SceneResult<Ancestors> {
+        let node = try!(self.resolve(node));
+
+        Ok(Ancestors { graph: self, current: node })
+    }
+
+    /// Lists a node followed by all of its ancestors, ending with the root
+    pub fn path_to_root(&self, node: SceneIndex) -> SceneResult<Vec<SceneIndex>> {
+        let mut path = vec![node];
+
+        path.extend(try!(self.ancestors(node)));
+
+        Ok(path)
+    }
+
+    /// Visits a node and everything below it depth first, in the same order as `descendants`.
+    ///
+    /// The visitor decides whether to descend into each node's children, skip them, or stop altogether.
+    pub fn visit_depth_first(&self, node: SceneIndex, visitor: &mut FnMut(SceneIndex, &SceneNode) -> Visit) -> SceneResult<()> {
+        let mut stack = vec![try!(self.resolve(node))];
+
+        while let Some(node) = stack.pop() {
+            if let (Some(index), Some(weight)) = (self.index_of(node), self.node_weight(node)) {
+                match visitor(index, weight) {
+                    Visit::Continue => stack.extend(self.neighbors_directed(node, Outgoing)),
+                    Visit::Skip => {}
+                    Visit::Stop => break,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn resolve(&self, node: SceneIndex) -> SceneResult<NodeIndex<Ix>> {
+        if self.contains(node) { Ok(node.index()) } else { Err(SceneError::InvalidNode) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use ecs::World;
+    use node::{SceneNodeExt, SceneNodeKind};
+
+    struct Tree {
+        graph: SceneGraph,
+        a: SceneIndex,
+        a1: SceneIndex,
+        a1x: SceneIndex,
+        a2: SceneIndex,
+        b: SceneIndex,
+        b1: SceneIndex,
+    }
+
+    /// root
+    /// ├── a
+    /// │   ├── a1
+    /// │   │   └── a1x
+    /// │   └── a2
+    /// └── b
+    ///     └── b1 (entity)
+    fn tree(world: &mut World) -> Tree {
+        let mut graph = SceneGraph::new(world);
+
+        let a = graph.add_node(SceneNode::new_meta_node()).unwrap();
+        let a1 = graph.add_child(a, SceneNode::new_meta_node()).unwrap();
+        let a1x = graph.add_child(a1, SceneNode::new_meta_node()).unwrap();
+        let a2 = graph.add_child(a, SceneNode::new_meta_node()).unwrap();
+        let b = graph.add_node(SceneNode::new_meta_node()).unwrap();
+        let b1 = graph.add_child(b, SceneNode::new_entity_node(world.create_now().build())).unwrap();
+
+        Tree { graph: graph, a: a, a1: a1, a1x: a1x, a2: a2, b: b, b1: b1 }
+    }
+
+    fn depth_first(graph: &SceneGraph, node: SceneIndex, skip: SceneIndex) -> Vec<SceneIndex> {
+        let mut visited = Vec::new();
+
+        graph.visit_depth_first(node, &mut |index, _| {
+            visited.push(index);
+
+            if index == skip { Visit::Skip } else { Visit::Continue }
+        }).unwrap();
+
+        visited
+    }
+
+    #[test]
+    fn test_traversal() {
+        let mut world = World::new();
+        let Tree { graph, a, a1, a1x, a2, b, b1 } = tree(&mut world);
+        let root = graph.root();
+
+        assert_eq!(graph.children(root).unwrap().collect::<Vec<_>>(), vec![a, b]);
+        assert_eq!(graph.children(a).unwrap().collect::<Vec<_>>(), vec![a1, a2]);
+        assert_eq!(graph.children(a1x).unwrap().count(), 0);
+
+        assert_eq!(graph.descendants(root).unwrap().collect::<Vec<_>>(), vec![a, a1, a1x, a2, b, b1]);
+        assert_eq!(graph.descendants(a).unwrap().collect::<Vec<_>>(), vec![a1, a1x, a2]);
+
+        assert_eq!(graph.ancestors(a1x).unwrap().collect::<Vec<_>>(), vec![a1, a, root]);
+        assert_eq!(graph.ancestors(root).unwrap().count(), 0);
+
+        assert_eq!(graph.path_to_root(b1).unwrap(), vec![b1, b, root]);
+        assert_eq!(graph.path_to_root(root).unwrap(), vec![root]);
+
+        // Visiting matches descendants, but includes the starting node
+        assert_eq!(depth_first(&graph, root, b1), vec![root, a, a1, a1x, a2, b, b1]);
+
+        // Skipping a node prunes its subtree but carries on with its siblings
+        assert_eq!(depth_first(&graph, root, a1), vec![root, a, a1, a2, b, b1]);
+        assert_eq!(depth_first(&graph, root, a), vec![root, a, b, b1]);
+
+        let mut visited = 0;
+
+        graph.visit_depth_first(root, &mut |index, _| {
+            visited += 1;
+
+            if index == a1 { Visit::Stop } else { Visit::Continue }
+        }).unwrap();
+
+        assert_eq!(visited, 3);
+    }
+
+    #[test]
+    fn test_reparenting() {
+        let mut world = World::new();
+        let Tree { mut graph, a, a1, a1x, a2, b, b1 } = tree(&mut world);
+        let root = graph.root();
+
+        // Moving a1 carries its own children along with it
+        graph.reparent(a1, b).unwrap();
+
+        assert_eq!(graph.children(a).unwrap().collect::<Vec<_>>(), vec![a2]);
+        assert_eq!(graph.children(b).unwrap().collect::<Vec<_>>(), vec![b1, a1]);
+        assert_eq!(graph.descendants(b).unwrap().collect::<Vec<_>>(), vec![b1, a1, a1x]);
+        assert_eq!(graph.path_to_root(a1x).unwrap(), vec![a1x, a1, b, root]);
+        assert_eq!(graph.parent(a1).unwrap(), Some(b));
+
+        // Moving a node up to its grandparent is fine
+        graph.reparent(a1x, root).unwrap();
+
+        assert_eq!(graph.path_to_root(a1x).unwrap(), vec![a1x, root]);
+        assert_eq!(graph.children(root).unwrap().collect::<Vec<_>>(), vec![a, b, a1x]);
+
+        // Nodes can't be moved below themselves
+        assert!(if let Err(SceneError::WouldCycle) = graph.reparent(b, a1) { true } else { false });
+        assert!(if let Err(SceneError::WouldCycle) = graph.reparent(a, a) { true } else { false });
+        assert!(if let Err(SceneError::WouldCycle) = graph.reparent(root, a2) { true } else { false });
+
+        assert_eq!(graph.descendants(root).unwrap().collect::<Vec<_>>(), vec![a, a2, b, b1, a1, a1x]);
+    }
+
+    #[test]
+    fn test_removal_invalidates_indices() {
+        let mut world = World::new();
+        let Tree { mut graph, a, a1, a1x, a2, b, b1 } = tree(&mut world);
+        let root = graph.root();
+
+        let entity = match graph.lookup_node(b1).unwrap().kind() {
+            &SceneNodeKind::EntityNode(ref node) => node.entity(),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(graph.lookup_index(entity), Some(b1));
+
+        assert_eq!(graph.recursive_remove(a1).unwrap().len(), 2);
+        assert_eq!(graph.recursive_remove(b).unwrap().len(), 2);
+        assert!(graph.recursive_remove(root).is_err());
+
+        assert_eq!(graph.lookup_index(entity), None);
+        assert_eq!(graph.descendants(root).unwrap().collect::<Vec<_>>(), vec![a, a2]);
+
+        // New nodes reuse the freed slots, but old indices to them stay invalid
+        let c = graph.add_child(a2, SceneNode::new_meta_node()).unwrap();
+        let d = graph.add_child(c, SceneNode::new_meta_node()).unwrap();
+
+        let reused = [a1, a1x, b, b1].iter().any(|old| old.index() == c.index() || old.index() == d.index());
+
+        assert!(reused);
+
+        for &stale in &[a1, a1x, b, b1] {
+            assert!(!graph.contains(stale));
+            assert!(graph.lookup_node(stale).is_none());
+            assert!(graph.children(stale).is_err());
+            assert!(graph.descendants(stale).is_err());
+            assert!(graph.ancestors(stale).is_err());
+            assert!(graph.path_to_root(stale).is_err());
+            assert!(graph.visit_depth_first(stale, &mut |_, _| Visit::Continue).is_err());
+            assert!(graph.add_child(stale, SceneNode::new_meta_node()).is_err());
+            assert!(graph.reparent(a, stale).is_err());
+        }
+
+        assert_eq!(graph.path_to_root(d).unwrap(), vec![d, c, a2, a, root]);
+        assert_eq!(graph.descendants(root).unwrap().collect::<Vec<_>>(), vec![a, a2, c, d]);
+    }
+}
@@ -350,11 +350,12 @@ fn main() {
         //Load up all the OpenGL functions from the process
         backend::gl::bindings::load_all_with(|symbol| window.get_proc_address(symbol) as *const _);
 
-        //Enable debugging of OpenGL messages
-        backend::gl::enable_debug(backend::gl::default_debug_callback, true).unwrap();
-
+        //Enable debugging of OpenGL messages, except for notifications
         //Debug message: (131202): Texture state performance warning: emulating compressed format not supported in hardware with decompressed images
-        backend::gl::gl_debug::DEBUG_IGNORED.write().unwrap().push(131202);
+        backend::gl::enable_debug_filtered(backend::gl::default_debug_callback, &backend::gl::GLDebugFilter {
+            muted_ids: vec![131202],
+            ..backend::gl::GLDebugFilter::default()
+        }).unwrap();
 
         // Generate a new plain 2D texture to reuse for all compressions
         let _ = GLTexture::new(GLTextureKind::Texture2D).unwrap();
@@ -0,0 +1,124 @@
+//! Exporting the currently viewed texture for bug reports and comparisons
+
+use std::path::{Path, PathBuf};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use image::{self, ImageBuffer};
+
+use backend::gl::*;
+use backend::gl::types::*;
+use backend::gl::bindings as glb;
+
+/// Finds a path that does not exist yet by appending an increasing counter to the file stem
+///
+/// E.g. `texture.png` becomes `texture-1.png`, then `texture-2.png`, etc.
+pub fn unique_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = path.extension().map(|ext| ext.to_string_lossy().into_owned());
+
+    let mut counter = 1;
+
+    loop {
+        let name = match extension {
+            Some(ref extension) => format!("{}-{}.{}", stem, counter, extension),
+            None => format!("{}-{}", stem, counter),
+        };
+
+        let candidate = path.with_file_name(name);
+
+        if !candidate.exists() {
+            return candidate;
+        }
+
+        counter += 1;
+    }
+}
+
+/// Reads back the base level of the currently bound 2D texture as RGBA floats
+///
+/// The driver takes care of decompressing and converting whatever format the texture is stored in.
+pub fn read_texture_rgba(width: u32, height: u32) -> GLResult<Vec<f32>> {
+    let mut pixels = vec![0.0f32; width as usize * height as usize * 4];
+
+    unsafe {
+        glb::PixelStorei(glb::PACK_ALIGNMENT, 4);
+        glb::GetTexImage(glb::TEXTURE_2D, 0, glb::RGBA, glb::FLOAT, pixels.as_mut_ptr() as *mut _);
+    }
+
+    check_errors!();
+
+    Ok(pixels)
+}
+
+/// Saves floating point RGBA pixels as an 8-bit PNG, with exposure applied to the color channels before clamping
+pub fn save_png(path: &Path, width: u32, height: u32, pixels: &[f32], exposure: f32) -> io::Result<()> {
+    let bytes = pixels.iter().enumerate().map(|(i, value)| {
+        // Alpha is coverage rather than light, so exposure doesn't apply to it
+        let value = if i % 4 == 3 { *value } else { value * exposure };
+
+        (value.max(0.0).min(1.0) * 255.0).round() as u8
+    }).collect();
+
+    let buffer = match ImageBuffer::from_raw(width, height, bytes) {
+        Some(buffer) => buffer,
+        None => return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                          format!("{} pixel values do not make a {}x{} RGBA image", pixels.len(), width, height))),
+    };
+
+    image::ImageRgba8(buffer).save(path)
+}
+
+/// Saves floating point RGBA pixels as an uncompressed Radiance HDR (RGBE) file, discarding alpha.
+///
+/// No exposure is applied, so this is the raw data as the GPU sees it.
+pub fn save_hdr(path: &Path, width: u32, height: u32, pixels: &[f32]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    write!(writer, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", height, width)?;
+
+    for pixel in pixels.chunks(4) {
+        writer.write_all(&to_rgbe(pixel[0], pixel[1], pixel[2]))?;
+    }
+
+    writer.flush()
+}
+
+/// Encodes a linear color into the shared-exponent RGBE format
+fn to_rgbe(r: f32, g: f32, b: f32) -> [u8; 4] {
+    let max = r.max(g).max(b);
+
+    if max < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    let exponent = max.log2().floor() as i32 + 1;
+    let scale = 256.0 / 2.0f32.powi(exponent);
+
+    [(r.max(0.0) * scale) as u8,
+     (g.max(0.0) * scale) as u8,
+     (b.max(0.0) * scale) as u8,
+     (exponent + 128) as u8]
+}
+
+/// Exports the currently bound texture to `path` as a PNG, along with a raw `.hdr` dump next to it.
+///
+/// Existing files are never overwritten. The paths actually written to are logged.
+pub fn export(path: &Path, width: u32, height: u32, exposure: f32) -> GLResult<()> {
+    let pixels = try!(read_texture_rgba(width, height));
+
+    let png_path = unique_path(&path.with_extension("png"));
+    let hdr_path = unique_path(&path.with_extension("hdr"));
+
+    try!(save_png(&png_path, width, height, &pixels, exposure));
+    info!("Exported texture to {}", png_path.display());
+
+    try!(save_hdr(&hdr_path, width, height, &pixels));
+    info!("Exported raw texture data to {}", hdr_path.display());
+
+    Ok(())
+}
@@ -16,7 +16,7 @@ use common::error::*;
 use backend::window::WindowBuilder;
 
 use std::sync::mpsc;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::thread::Builder;
 
 use clap::{App, Arg};
@@ -24,6 +24,7 @@ use glfw::{Action, Key, WindowHint, WindowEvent};
 
 pub mod render;
 pub mod screen;
+pub mod export;
 
 use render::RenderSignal;
 
@@ -68,10 +69,13 @@ fn run<P: AsRef<Path>>(path: Option<P>) {
         //Load up all the OpenGL functions from the process
         backend::gl::bindings::load_all_with(|symbol| window.get_proc_address(symbol) as *const _);
 
-        //Enable debugging of OpenGL messages
-        backend::gl::enable_debug(backend::gl::default_debug_callback, true).unwrap();
+        info!("{}", backend::gl::GLCapabilities::query().expect_logged("Couldn't query OpenGL capabilities"));
 
-        backend::gl::gl_debug::DEBUG_IGNORED.write().unwrap().extend_from_slice(&[131154, 131202]);
+        //Enable debugging of OpenGL messages, except for notifications
+        backend::gl::enable_debug_filtered(backend::gl::default_debug_callback, &backend::gl::GLDebugFilter {
+            muted_ids: vec![131154, 131202],
+            ..backend::gl::GLDebugFilter::default()
+        }).unwrap();
 
         //Create Send-able context to send to render thread
         window.render_context()
@@ -95,9 +99,12 @@ fn run<P: AsRef<Path>>(path: Option<P>) {
         glfw::make_context_current(None);
     }).expect_logged("Could not start render thread");
 
+    //Keep track of the texture being viewed so exports can be named after it
+    let mut current_path: Option<PathBuf> = path.map(|path| path.as_ref().to_path_buf());
+
     //If there was a path given at the command line, load it up first
-    if let Some(path) = path {
-        tx.send(RenderSignal::ChangeTexture(path.as_ref().to_path_buf())).unwrap();
+    if let Some(ref path) = current_path {
+        tx.send(RenderSignal::ChangeTexture(path.clone())).unwrap();
     }
 
     macro_rules! send_and_unpark {
@@ -124,9 +131,20 @@ fn run<P: AsRef<Path>>(path: Option<P>) {
                 WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
                     window.write().unwrap().set_should_close(true);
                 }
+                WindowEvent::Key(Key::S, _, Action::Press, modifiers) if modifiers.contains(glfw::Control) => {
+                    if let Some(ref path) = current_path {
+                        send_and_unpark!(RenderSignal::Export(path.clone())).unwrap();
+                    } else {
+                        error!("No texture loaded to export");
+                    }
+                }
+                WindowEvent::Key(Key::F, _, Action::Press, _) => {
+                    send_and_unpark!(RenderSignal::CycleFilter).unwrap();
+                }
                 WindowEvent::FileDrop(paths) => {
                     if let Some(last) = paths.last() {
                         if last.extension().is_some() {
+                            current_path = Some(last.clone());
                             send_and_unpark!(RenderSignal::ChangeTexture(last.clone())).unwrap();
                         } else {
                             error!("Invalid path");
@@ -136,12 +154,23 @@ fn run<P: AsRef<Path>>(path: Option<P>) {
                 WindowEvent::Refresh => {
                     send_and_unpark!(RenderSignal::Refresh).unwrap();
                 }
-                WindowEvent::FramebufferSize(width, height) |
-                WindowEvent::Size(width, height) if width > 0 && height > 0 => {
+                // The window size is in window coordinates, which only match pixels without display scaling
+                WindowEvent::FramebufferSize(width, height) if width > 0 && height > 0 => {
                     send_and_unpark!(RenderSignal::Resize(width, height)).unwrap();
                 }
                 WindowEvent::Scroll(_, v) => {
-                    send_and_unpark!(RenderSignal::Zoom(v)).unwrap();
+                    let control = {
+                        let window = window.read().unwrap();
+
+                        window.get_key(Key::LeftControl) == Action::Press || window.get_key(Key::RightControl) == Action::Press
+                    };
+
+                    // Ctrl+Scroll adjusts exposure instead of zooming
+                    if control {
+                        send_and_unpark!(RenderSignal::Exposure(v)).unwrap();
+                    } else {
+                        send_and_unpark!(RenderSignal::Zoom(v)).unwrap();
+                    }
                 }
                 WindowEvent::MouseButton(glfw::MouseButtonLeft, Action::Press, _) => {
                     left_mouse_pressed = true;
@@ -157,7 +186,21 @@ fn run<P: AsRef<Path>>(path: Option<P>) {
                     last_cursor_pos = (x, y);
 
                     if left_mouse_pressed {
-                        send_and_unpark!(RenderSignal::Move(delta.0, delta.1)).unwrap();
+                        // Panning is in framebuffer pixels, so the texture stays under the cursor on high-DPI displays
+                        let scale = {
+                            let window = window.read().unwrap();
+
+                            let (width, height) = window.get_size();
+                            let (framebuffer_width, framebuffer_height) = window.get_framebuffer_size();
+
+                            if width > 0 && height > 0 {
+                                (framebuffer_width as f64 / width as f64, framebuffer_height as f64 / height as f64)
+                            } else {
+                                (1.0, 1.0)
+                            }
+                        };
+
+                        send_and_unpark!(RenderSignal::Move(delta.0 * scale.0, delta.1 * scale.1)).unwrap();
                     }
                 }
                 _ => {}
@@ -4,11 +4,12 @@ use std::fs::File;
 use std::io::BufReader;
 
 use glfw::{self, Context};
-use image::{self, DynamicImage, GenericImage};
+use image::{self, GenericImage};
 use capnp;
 
 use common::error::*;
 use common::utils::*;
+use common::pacing::FramePacer;
 
 use backend::gl::*;
 use backend::gl::types::*;
@@ -16,12 +17,13 @@ use backend::gl::bindings as glb;
 
 use combustion_protocols as protocols;
 
-use self::protocols::texture;
-use self::protocols::texture::protocol::{Kind};
 use self::protocols::texture::protocol::texture as texture_protocol;
-use self::protocols::texture::gl::*;
 
 use screen::ScreenQuad;
+use export;
+
+/// Frame rate the viewer redraws at, so animated textures keep playing between window events
+pub const TARGET_FPS: f64 = 60.0;
 
 pub enum RenderSignal {
     Stop,
@@ -29,7 +31,51 @@ pub enum RenderSignal {
     Resize(i32, i32),
     ChangeTexture(PathBuf),
     Zoom(f64),
-    Move(f64, f64)
+    Move(f64, f64),
+    Exposure(f64),
+    Export(PathBuf),
+    CycleFilter,
+}
+
+/// Texture filtering modes the viewer can switch between
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterMode {
+    Nearest,
+    Linear,
+    Anisotropic,
+}
+
+impl FilterMode {
+    pub fn next(self) -> FilterMode {
+        match self {
+            FilterMode::Nearest => FilterMode::Linear,
+            FilterMode::Linear => FilterMode::Anisotropic,
+            FilterMode::Anisotropic => FilterMode::Nearest,
+        }
+    }
+
+    pub fn apply(self, texture: &mut GLTexture) -> GLResult<()> {
+        match self {
+            FilterMode::Nearest => {
+                try!(texture.set_filter(GLTextureFilter::Nearest, Some(GLTextureFilter::Nearest)));
+                try!(texture.set_anisotropy(1.0));
+            }
+            FilterMode::Linear => {
+                try!(texture.set_filter(GLTextureFilter::Linear, Some(GLTextureFilter::Linear)));
+                try!(texture.set_anisotropy(1.0));
+            }
+            FilterMode::Anisotropic => {
+                try!(texture.set_filter(GLTextureFilter::Linear, Some(GLTextureFilter::Linear)));
+
+                // Clamped to the maximum supported level
+                let level = try!(texture.set_anisotropy(16.0));
+
+                info!("Anisotropy level: {}", level);
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(debug_assertions)]
@@ -66,22 +112,31 @@ fn load_screen_shader() -> GLResult<GLShaderProgram> {
 pub fn start(mut context: glfw::RenderContext, rx: mpsc::Receiver<RenderSignal>) -> GLResult<()> {
     let mut active_texture = try!(GLTexture::new(GLTextureKind::Texture2D));
 
-    try!(active_texture.set_filter(GLTextureFilter::Nearest, Some(GLTextureFilter::Nearest)));
-    try!(active_texture.set_wrap(GLTextureWrap::ClampToBorder));
+    let mut filter_mode = FilterMode::Nearest;
 
-    let max_anisotropy = active_texture.get_max_anisotropy().expect_logged("Couldn't get max anisotropy value");
-    active_texture.set_anisotropy(max_anisotropy).expect_logged("Couldn't set max anisotropy");
+    try!(filter_mode.apply(&mut active_texture));
+    try!(active_texture.set_wrap_axes(GLTextureWrap::ClampToBorder, GLTextureWrap::ClampToBorder, GLTextureWrap::ClampToBorder));
+    try!(active_texture.set_border_color([0.0, 0.0, 0.0, 0.0]));
 
     let screen_shader = try!(load_screen_shader());
 
+    // Reserved rather than relying on unit 0 being active
+    let mut texture_units = try!(GLTextureUnits::new(0));
+    let texture_unit = try!(texture_units.reserve("texture"));
+
     let mut screen = try!(ScreenQuad::new());
 
     let mut resolution: (u32, u32) = (800, 600);
     let mut texture_resolution: (u32, u32) = (0, 0);
     let mut zoom: f64 = 1.0;
     let mut pos: (f64, f64) = (0.0, 0.0);
+    let mut exposure: f64 = 1.0;
+
+    let mut pacer = FramePacer::new(TARGET_FPS);
 
     'render: loop {
+        pacer.begin_frame();
+
         let mut viewport_size = None;
 
         for event in rx.try_iter() {
@@ -100,12 +155,35 @@ pub fn start(mut context: glfw::RenderContext, rx: mpsc::Receiver<RenderSignal>)
                     pos.0 += x;
                     pos.1 += y;
                 }
+                RenderSignal::CycleFilter => {
+                    filter_mode = filter_mode.next();
+
+                    try!(filter_mode.apply(&mut active_texture));
+
+                    info!("Texture filtering: {:?}", filter_mode);
+                }
+                RenderSignal::Exposure(value) => {
+                    exposure = clamp(exposure * 2.0f64.powf(value * 0.25), 1.0 / 64.0, 64.0);
+
+                    info!("Exposure: {:.3}", exposure);
+                }
+                RenderSignal::Export(path) => {
+                    if texture_resolution.0 == 0 || texture_resolution.1 == 0 {
+                        error!("No texture loaded to export");
+                    } else {
+                        try!(active_texture.bind());
+
+                        if let Err(err) = export::export(&path, texture_resolution.0, texture_resolution.1, exposure as f32) {
+                            error!("Failed to export texture: {}", err);
+                        }
+                    }
+                }
                 RenderSignal::ChangeTexture(path) => {
-                    try!(active_texture.bind());
+                    info!("Loading {}...", path.display());
 
-                    if path.extension().unwrap() == protocols::texture::EXTENSION {
-                        info!("Loading Combustion texture...");
+                    let options = GLTextureOptions { wrap: GLTextureWrap::ClampToBorder, ..GLTextureOptions::default() };
 
+                    if path.extension().map_or(false, |extension| extension == protocols::texture::EXTENSION) {
                         let mut source = BufReader::new(File::open(path)?);
 
                         let texture_message = capnp::serialize_packed::read_message(&mut source, capnp::message::ReaderOptions {
@@ -115,77 +193,17 @@ pub fn start(mut context: glfw::RenderContext, rx: mpsc::Receiver<RenderSignal>)
                         let texture = texture_message.get_root::<texture_protocol::Reader>()
                                                      .expect_logged("No texture protocol root found");
 
-                        let width = texture.get_width();
-                        let height = texture.get_height();
-
-                        //TODO: Support more kinds
-                        //let depth = texture.get_depth();
-
-                        let kind = texture.get_kind()
-                                          .expect_logged("Couldn't find Kind value. This could be caused by using an older texture format.");
-
-                        //TODO: Support more kinds
-                        assert!(kind == Kind::Texture2D);
-
-                        let specific_format = texture::SpecificFormat::read_texture(&texture)
-                            .expect_logged("Error retrieving texture information");
-
-                        let data = texture.get_data()
-                                          .expect_logged("No texture data found");
-
-                        info!("Combustion texture loaded.");
-
-                        let generic_format = specific_format.to_generic();
-
-                        info!("Buffering Combustion texture...");
-
-                        if specific_format.is_compressed() {
-                            unsafe {
-                                glb::CompressedTexImage2D(glb::TEXTURE_2D, 0, specific_format.specific(),
-                                                          width as GLsizei, height as GLsizei,
-                                                          0, data.len() as GLsizei, data.as_ptr() as *const _);
-                            }
-
-                        } else {
-                            unsafe {
-                                glb::TexImage2D(glb::TEXTURE_2D, 0, specific_format.specific() as GLint,
-                                                width as GLsizei, height as GLsizei, 0,
-                                                generic_format.generic(), glb::UNSIGNED_BYTE, data.as_ptr() as *const _);
-                            }
-                        }
-
-                        check_errors!();
-
-                        texture_resolution = (width, height);
+                        texture_resolution = (texture.get_dimensions().get_width(), texture.get_dimensions().get_height());
+                        active_texture = try!(GLTexture::from_protocol_reader(texture, options));
                     } else {
-                        info!("Loading normal image...");
-
                         let image = try!(image::open(path));
 
-                        let (width, height) = image.dimensions();
-
-                        let (format, iformat, data) = match image {
-                            DynamicImage::ImageLuma8(i) => (glb::RED, glb::R8, i.into_vec()),
-                            DynamicImage::ImageLumaA8(i) => (glb::RG, glb::RG8, i.into_vec()),
-                            DynamicImage::ImageRgb8(i) => (glb::RGB, glb::RGB8, i.into_vec()),
-                            DynamicImage::ImageRgba8(i) => (glb::RGBA, glb::RGBA8, i.into_vec())
-                        };
-
-                        info!("Buffering normal image...");
-
-                        unsafe {
-                            glb::TexImage2D(glb::TEXTURE_2D, 0, iformat as GLint,
-                                            width as GLsizei, height as GLsizei, 0,
-                                            format, glb::UNSIGNED_BYTE, data.as_ptr() as *const _);
-                        }
-
-                        check_errors!();
-
-                        texture_resolution = (width, height);
+                        texture_resolution = image.dimensions();
+                        active_texture = try!(GLTexture::from_image(&image, options));
                     }
 
-                    info!("Generating mipmaps...");
-                    try!(active_texture.generate_mipmap());
+                    try!(filter_mode.apply(&mut active_texture));
+                    try!(active_texture.set_border_color([0.0, 0.0, 0.0, 0.0]));
 
                     zoom = 1.0;
                     pos = (0.0, 0.0);
@@ -207,21 +225,26 @@ pub fn start(mut context: glfw::RenderContext, rx: mpsc::Receiver<RenderSignal>)
 
         try!(screen_shader.use_program());
 
+        try!(GLTextureUnits::bind(texture_unit, &active_texture));
+        try!(screen_shader.get_uniform("screen")?.int1(texture_unit as GLint));
+
         let mut res_uniform = try!(screen_shader.get_uniform("resolution"));
         let mut tex_res_uniform = try!(screen_shader.get_uniform("texture_resolution"));
         let mut zoom_uniform = try!(screen_shader.get_uniform("zoom"));
         let mut pos_uniform = try!(screen_shader.get_uniform("pos"));
+        let mut exposure_uniform = try!(screen_shader.get_uniform("exposure"));
 
         try!(res_uniform.float2(resolution.0 as f32, resolution.1 as f32));
         try!(tex_res_uniform.float2(texture_resolution.0 as f32, texture_resolution.1 as f32));
         try!(zoom_uniform.float1(zoom as f32));
         try!(pos_uniform.float2(pos.0 as f32, pos.1 as f32));
+        try!(exposure_uniform.float1(exposure as f32));
 
         try!(screen.draw());
 
         context.swap_buffers();
 
-        ::std::thread::park();
+        pacer.wait();
     }
 
     Ok(())